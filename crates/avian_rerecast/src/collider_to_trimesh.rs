@@ -1,4 +1,6 @@
-//! Contains traits and methods for converting [`Collider`]s into trimeshes, expressed as [`TrimeshedCollider`]s.
+//! Contains traits and methods for converting [`Collider`]s into [`TriMesh`]es.
+//!
+//! Colliders are converted straight from their parry shapes, without going through a bevy [`Mesh`].
 
 use avian3d::{
     parry::shape::{Compound, TypedShape},
@@ -32,6 +34,23 @@ pub trait ToTriMesh {
     /// - [`RoundCylinder`](avian3d::parry::shape::RoundCylinder)
     /// - [`RoundCone`](avian3d::parry::shape::RoundCone)
     fn to_trimesh(&self, subdivisions: u32) -> Option<TriMesh>;
+
+    /// Same as [`ToTriMesh::to_trimesh`], but with the vertices transformed into world space by `transform`
+    /// and all triangles marked as `area`, e.g. for rasterizing colliders into a
+    /// [`Heightfield`](bevy_rerecast_core::rerecast::Heightfield) without going through the navmesh affector backend.
+    fn to_world_trimesh(
+        &self,
+        subdivisions: u32,
+        transform: &GlobalTransform,
+        area: AreaType,
+    ) -> Option<TriMesh> {
+        let mut trimesh = self.to_trimesh(subdivisions)?;
+        for vertex in &mut trimesh.vertices {
+            *vertex = transform.transform_point(Vec3::from(*vertex)).into();
+        }
+        trimesh.area_types.fill(area);
+        Some(trimesh)
+    }
 }
 
 impl ToTriMesh for Collider {
@@ -81,19 +100,18 @@ fn shape_to_trimesh(shape: &TypedShape, subdivisions: u32) -> Option<TriMesh> {
         TypedShape::HalfSpace(_half_space) => return None,
         TypedShape::Custom(_shape) => return None,
     };
-    let indices_len = indices.len();
-    Some(TriMesh {
-        vertices: vertices.into_iter().map(|v| v.into()).collect(),
-        indices: indices.into_iter().map(|i| i.into()).collect(),
-        area_types: vec![AreaType::NOT_WALKABLE; indices_len],
-    })
+    TriMesh::from_parts(
+        vertices.into_iter().map(|v| v.into()).collect(),
+        indices.into_iter().map(|i| i.into()).collect(),
+    )
+    .ok()
 }
 
 fn compound_trimesh(compound: &Compound, subdivisions: u32) -> TriMesh {
     compound.shapes().iter().fold(
         TriMesh::default(),
         |mut compound_trimesh, (isometry, shape)| {
-            let Some(trimesh) =
+            let Some(mut trimesh) =
                 // No need to track recursive compounds because parry panics on nested compounds anyways lol
                 shape_to_trimesh(&shape.as_typed_shape(), subdivisions)
             else {
@@ -105,8 +123,8 @@ fn compound_trimesh(compound: &Compound, subdivisions: u32) -> TriMesh {
                 rotation: Quat::from(isometry.rotation),
             };
 
-            apply_isometry(&mut compound_trimesh, isometry);
-            compound_trimesh.extend(trimesh);
+            apply_isometry(&mut trimesh, isometry);
+            compound_trimesh.extend(&trimesh);
             compound_trimesh
         },
     )
@@ -130,4 +148,35 @@ mod tests {
         assert_eq!(trimesh.vertices.len(), 8);
         assert_eq!(trimesh.indices.len(), 12);
     }
+
+    #[test]
+    fn places_compound_shapes_at_their_isometries() {
+        let collider = Collider::compound(vec![
+            (
+                Vec3::new(5.0, 0.0, 0.0),
+                Quat::IDENTITY,
+                Collider::cuboid(1.0, 1.0, 1.0),
+            ),
+            (Vec3::ZERO, Quat::IDENTITY, Collider::cuboid(1.0, 1.0, 1.0)),
+        ]);
+        let aabb = collider.to_trimesh(1).unwrap().compute_aabb().unwrap();
+        assert_eq!(aabb.min.x, -0.5);
+        assert_eq!(aabb.max.x, 5.5);
+    }
+
+    #[test]
+    fn bakes_transform_and_area() {
+        let collider = Collider::cuboid(1.0, 1.0, 1.0);
+        let transform = GlobalTransform::from_translation(Vec3::new(0.0, 10.0, 0.0));
+        let trimesh = collider
+            .to_world_trimesh(1, &transform, AreaType::DEFAULT_WALKABLE)
+            .unwrap();
+        assert_eq!(trimesh.compute_aabb().unwrap().min.y, 9.5);
+        assert!(
+            trimesh
+                .area_types
+                .iter()
+                .all(|area| *area == AreaType::DEFAULT_WALKABLE)
+        );
+    }
 }
@@ -1,42 +1,322 @@
 #![doc = include_str!("../../../readme.md")]
 
+use std::{fmt, sync::Arc};
+
 use avian3d::prelude::*;
-use bevy::prelude::*;
-use bevy_rerecast_core::{NavmeshApp as _, rerecast::TriMesh};
+use bevy::{ecs::world::EntityRef, prelude::*};
+use bevy_rerecast_core::{
+    NavmeshAffectorArea, NavmeshAffectorAreas, NavmeshApp as _,
+    rerecast::{AreaType, TriMesh},
+};
 
 mod collider_to_trimesh;
-use crate::collider_to_trimesh::ToTriMesh;
+pub use collider_to_trimesh::{ToTriMesh, apply_isometry};
 
 /// Everything you need to get started with the Navmesh plugin.
 pub mod prelude {
-    pub use crate::AvianRerecastPlugin;
+    pub use crate::{AffectorCollider, AvianRerecastPlugin, ColliderSurface, ToTriMesh};
 }
 
 /// The plugin of the crate. Will make all entities with [`Collider`] a collider belonging to a static [`RigidBody`] available for navmesh generation.
+///
+/// [`Sensor`] colliders are skipped by default, as they do not physically obstruct anything.
+/// Use [`AvianRerecastPlugin::with_filter`] to control which colliders are used.
 #[non_exhaustive]
-#[derive(Debug, Default)]
-pub struct AvianRerecastPlugin;
+#[derive(Clone)]
+pub struct AvianRerecastPlugin {
+    /// Whether colliders with a [`Sensor`] component are passed to the filter. Defaults to `false`.
+    pub include_sensors: bool,
+    /// How the triangles of the colliders are marked before rasterization.
+    pub areas: NavmeshAffectorAreas,
+    filter: ColliderFilter,
+    area_mapping: Option<AreaMapping>,
+}
+
+impl Default for AvianRerecastPlugin {
+    fn default() -> Self {
+        Self {
+            include_sensors: false,
+            areas: NavmeshAffectorAreas::default(),
+            filter: ColliderFilter(Arc::new(|collider| collider.rigid_body.is_static())),
+            area_mapping: None,
+        }
+    }
+}
+
+impl fmt::Debug for AvianRerecastPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AvianRerecastPlugin")
+            .field("include_sensors", &self.include_sensors)
+            .field("areas", &self.areas)
+            .field("area_mapping", &self.area_mapping.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl AvianRerecastPlugin {
+    /// The name the backend is added under, e.g. for [`NavmeshAffectorBackends::set_enabled`](bevy_rerecast_core::NavmeshAffectorBackends::set_enabled).
+    pub const BACKEND_NAME: &'static str = "avian";
+
+    /// Replaces the filter deciding which colliders are used for navmesh generation.
+    /// The default filter only accepts colliders belonging to a static [`RigidBody`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use avian3d::prelude::*;
+    /// # use avian_rerecast::prelude::*;
+    /// // Also generate the navmesh around kinematic bodies, but ignore everything on layer 1.
+    /// let plugin = AvianRerecastPlugin::default().with_filter(|collider| {
+    ///     !collider.rigid_body.is_dynamic() && !collider.layers.memberships.has_all(1 << 1)
+    /// });
+    /// ```
+    pub fn with_filter(
+        mut self,
+        filter: impl Fn(&AffectorCollider) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filter = ColliderFilter(Arc::new(filter));
+        self
+    }
+
+    /// Derives the area of the colliders from their surface, e.g. to give ice or mud their own area.
+    /// Colliders for which the mapping returns `Some` get a [`NavmeshAffectorArea`], all others use [`AvianRerecastPlugin::areas`].
+    ///
+    /// The mapping is only called for colliders accepted by the filter set with [`AvianRerecastPlugin::with_filter`].
+    /// It is reevaluated every frame, so changing the [`Friction`] of a collider changes its area on the next navmesh regeneration.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use avian3d::prelude::*;
+    /// # use avian_rerecast::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # use bevy_rerecast_core::rerecast::AreaType;
+    /// const ICE: AreaType = AreaType(1);
+    /// const MUD: AreaType = AreaType(2);
+    ///
+    /// // A component of the game describing what a surface is made of.
+    /// #[derive(Component)]
+    /// struct Mud;
+    ///
+    /// let plugin = AvianRerecastPlugin::default().with_area_mapping(|surface| {
+    ///     if surface.get::<Mud>().is_some() {
+    ///         Some(MUD)
+    ///     } else if surface.friction.dynamic_coefficient < 0.1 {
+    ///         Some(ICE)
+    ///     } else {
+    ///         None
+    ///     }
+    /// });
+    /// ```
+    pub fn with_area_mapping(
+        mut self,
+        mapping: impl Fn(&ColliderSurface) -> Option<AreaType> + Send + Sync + 'static,
+    ) -> Self {
+        self.area_mapping = Some(AreaMapping(Arc::new(mapping)));
+        self
+    }
+
+    /// Sets [`AvianRerecastPlugin::include_sensors`].
+    pub fn with_sensors(mut self, include_sensors: bool) -> Self {
+        self.include_sensors = include_sensors;
+        self
+    }
+
+    /// Sets the area walkable triangles are marked with. See [`NavmeshAffectorAreas::walkable_area`].
+    pub fn with_walkable_area(mut self, area: AreaType) -> Self {
+        self.areas.walkable_area = area;
+        self
+    }
+
+    /// Sets whether triangles are only walkable if they are flat enough. See [`NavmeshAffectorAreas::mark_by_slope`].
+    pub fn with_slope_marking(mut self, mark_by_slope: bool) -> Self {
+        self.areas.mark_by_slope = mark_by_slope;
+        self
+    }
+}
 
 impl Plugin for AvianRerecastPlugin {
     fn build(&self, app: &mut App) {
-        app.set_navmesh_affector_backend(collider_backend);
+        app.insert_resource(ColliderSettings {
+            include_sensors: self.include_sensors,
+            filter: self.filter.clone(),
+        });
+        app.add_navmesh_affector_backend_with_areas(
+            Self::BACKEND_NAME,
+            collider_backend,
+            self.areas,
+        );
+        app.add_navmesh_affector_component::<Collider>();
+        if let Some(area_mapping) = &self.area_mapping {
+            app.insert_resource(area_mapping.clone());
+            app.add_systems(Update, map_collider_areas);
+        }
+    }
+}
+
+/// A collider considered for navmesh generation, as passed to the filter set with [`AvianRerecastPlugin::with_filter`].
+#[derive(Debug, Clone, Copy)]
+pub struct AffectorCollider {
+    /// The entity holding the [`Collider`].
+    pub entity: Entity,
+    /// The entity holding the [`RigidBody`] the collider belongs to.
+    pub body: Entity,
+    /// The type of the rigid body the collider belongs to.
+    pub rigid_body: RigidBody,
+    /// The collision layers of the collider, or the default layers if it has none.
+    pub layers: CollisionLayers,
+    /// Whether the collider is a [`Sensor`].
+    pub is_sensor: bool,
+}
+
+/// The surface of a collider considered for navmesh generation, as passed to the mapping set with [`AvianRerecastPlugin::with_area_mapping`].
+#[derive(Clone, Copy)]
+pub struct ColliderSurface<'a> {
+    /// The collider itself.
+    pub collider: AffectorCollider,
+    /// The [`Friction`] of the collider, falling back to the one of its rigid body and then to the default.
+    pub friction: Friction,
+    /// The [`Restitution`] of the collider, falling back to the one of its rigid body and then to the default.
+    pub restitution: Restitution,
+    entity: EntityRef<'a>,
+    body: EntityRef<'a>,
+}
+
+impl<'a> ColliderSurface<'a> {
+    /// Gets a component describing the surface, e.g. a physics material of the game,
+    /// from the collider entity or, if it has none, from its rigid body.
+    pub fn get<C: Component>(&self) -> Option<&'a C> {
+        self.entity.get::<C>().or_else(|| self.body.get::<C>())
+    }
+}
+
+impl fmt::Debug for ColliderSurface<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ColliderSurface")
+            .field("collider", &self.collider)
+            .field("friction", &self.friction)
+            .field("restitution", &self.restitution)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone)]
+struct ColliderFilter(Arc<dyn Fn(&AffectorCollider) -> bool + Send + Sync>);
+
+#[derive(Resource, Clone)]
+struct AreaMapping(Arc<dyn Fn(&ColliderSurface) -> Option<AreaType> + Send + Sync>);
+
+/// Marks a [`NavmeshAffectorArea`] that was inserted by the [`AreaMapping`] rather than by the user,
+/// so that it can be removed again when the mapping no longer applies.
+#[derive(Component)]
+struct MappedArea;
+
+#[derive(Resource)]
+struct ColliderSettings {
+    include_sensors: bool,
+    filter: ColliderFilter,
+}
+
+type AffectorColliderData = (
+    Entity,
+    &'static ColliderOf,
+    Option<&'static CollisionLayers>,
+    Has<Sensor>,
+);
+
+impl ColliderSettings {
+    /// The collider as passed to the filter, or `None` if it is not used for navmesh generation.
+    fn accept(
+        &self,
+        (entity, collider_of, layers, is_sensor): (
+            Entity,
+            &ColliderOf,
+            Option<&CollisionLayers>,
+            bool,
+        ),
+        bodies: &Query<&RigidBody>,
+    ) -> Option<AffectorCollider> {
+        if is_sensor && !self.include_sensors {
+            return None;
+        }
+        let rigid_body = *bodies.get(collider_of.body).ok()?;
+        let candidate = AffectorCollider {
+            entity,
+            body: collider_of.body,
+            rigid_body,
+            layers: layers.copied().unwrap_or_default(),
+            is_sensor,
+        };
+        (self.filter.0)(&candidate).then_some(candidate)
     }
 }
 
 fn collider_backend(
-    colliders: Query<(&GlobalTransform, &Collider, &ColliderOf)>,
+    colliders: Query<(&GlobalTransform, &Collider, AffectorColliderData)>,
     bodies: Query<&RigidBody>,
-) -> Vec<(GlobalTransform, TriMesh)> {
+    settings: Res<ColliderSettings>,
+) -> Vec<(Entity, GlobalTransform, TriMesh)> {
     colliders
         .iter()
-        .filter_map(|(transform, collider, collider_of)| {
-            let body = bodies.get(collider_of.body).ok()?;
-            if !body.is_static() {
-                return None;
-            }
+        .filter_map(|(transform, collider, data)| {
+            let candidate = settings.accept(data, &bodies)?;
             let subdivisions = 10;
             let mesh = collider.to_trimesh(subdivisions)?;
-            Some((*transform, mesh))
+            Some((candidate.entity, *transform, mesh))
         })
         .collect::<Vec<_>>()
 }
+
+fn map_collider_areas(
+    world: &World,
+    colliders: Query<(
+        AffectorColliderData,
+        Option<&NavmeshAffectorArea>,
+        Has<MappedArea>,
+    )>,
+    bodies: Query<&RigidBody>,
+    settings: Res<ColliderSettings>,
+    mapping: Res<AreaMapping>,
+    mut commands: Commands,
+) {
+    for (data, current, mapped) in &colliders {
+        // Leave areas the user inserted themselves alone.
+        if current.is_some() && !mapped {
+            continue;
+        }
+        let area = settings.accept(data, &bodies).and_then(|collider| {
+            let entity = world.entity(collider.entity);
+            let body = world.entity(collider.body);
+            let surface = ColliderSurface {
+                collider,
+                friction: entity
+                    .get::<Friction>()
+                    .or_else(|| body.get::<Friction>())
+                    .copied()
+                    .unwrap_or_default(),
+                restitution: entity
+                    .get::<Restitution>()
+                    .or_else(|| body.get::<Restitution>())
+                    .copied()
+                    .unwrap_or_default(),
+                entity,
+                body,
+            };
+            (mapping.0)(&surface)
+        });
+        let entity = data.0;
+        match area {
+            Some(area) if current.map(|current| **current) != Some(area) => {
+                commands
+                    .entity(entity)
+                    .insert((NavmeshAffectorArea(area), MappedArea));
+            }
+            None if mapped => {
+                commands
+                    .entity(entity)
+                    .remove::<(NavmeshAffectorArea, MappedArea)>();
+            }
+            _ => {}
+        }
+    }
+}
@@ -6,7 +6,25 @@ pub use bevy_rerecast_editor_integration as editor_integration;
 
 /// Everything you need to get started with the Navmesh plugins.
 pub mod prelude {
-    pub use crate::{Navmesh, NavmeshPlugins, generator::NavmeshGenerator};
+    pub use crate::{
+        Navmesh, NavmeshPlugins,
+        generator::{NavmeshGenerator, NavmeshPriority},
+        heightmap::HeightmapAffector,
+        navmeshes::{
+            AgentProfile, AgentProfiles, NavmeshFailed, NavmeshReady, NavmeshStepped, Navmeshes,
+        },
+        obstacles::NavmeshObstacle,
+        volume::NavmeshVolume,
+    };
+
+    #[cfg(feature = "debug_plugin")]
+    pub use crate::debug::{DebugPath, NavmeshDebugPlugin, NavmeshDebugSettings};
+
+    #[cfg(feature = "editor_overlay")]
+    pub use crate::overlay::RerecastEditorOverlayPlugin;
+
+    #[cfg(feature = "inspector")]
+    pub use crate::inspector::NavmeshInspectorPlugin;
 }
 
 /// The plugin group of the crate. Contains the following plugins:
@@ -14,16 +32,31 @@ pub mod prelude {
 /// - [`RerecastEditorIntegrationPlugin`](editor_integration::RerecastEditorIntegrationPlugin): Allows communication with the editor.
 ///   Requires the `editor_integration` feature.
 ///
+/// The [`NavmeshDebugPlugin`](debug::NavmeshDebugPlugin) is not part of the group, so that it can be added only to debug builds.
+/// Requires the `debug_plugin` feature.
+/// Neither is the [`RerecastEditorOverlayPlugin`](overlay::RerecastEditorOverlayPlugin), which tunes the navmesh settings
+/// from within the game instead of the editor. Requires the `editor_overlay` feature.
+/// Nor is the [`NavmeshInspectorPlugin`](inspector::NavmeshInspectorPlugin), which shows navmeshes, configs and build stats
+/// as summaries in `bevy-inspector-egui`. Requires the `inspector` feature.
+///
 /// Note that rerecast does not do anything until you also add a navmesh affector backend.
 /// A navmesh affector is something that represents non-walkable geometry in form of a [`TriMesh`](rerecast::TriMesh).
 ///
 /// A backend's job is to provide the [`TriMesh`](rerecast::TriMesh)es that will be used to create the navmesh.
 /// For example, if you enable the `bevy_mesh` feature, you can add the [`Mesh3dNavmeshPlugin`] to your app to
 /// set a backend that generates navmeshes from entities with a `Mesh3d` component.
+/// Terrain chunks can skip the backend by spawning them with a [`HeightmapAffector`](heightmap::HeightmapAffector),
+/// which is rasterized straight from its heights.
+/// Parts of the walkable area can be given their own area type with a [`NavmeshVolume`](volume::NavmeshVolume),
+/// without adding any geometry.
+///
+/// To add your own backend, use [`NavmeshApp::add_navmesh_affector_backend`].
+/// The affectors of all backends are combined, so e.g. the [`Mesh3dNavmeshPlugin`] and a physics backend
+/// can contribute to the same navmesh. Backends can be turned off individually through [`NavmeshAffectorBackends`].
+/// By default, no backend is added.
 ///
-/// To set your own backend, use [`NavmeshApp::set_navmesh_affector_backend`].
-/// Only one backend can be set at a time. Setting a new backend will replace the previous one.
-/// By default, no backend is set.
+/// To configure the [`RerecastPlugin`], replace it in the group, e.g.
+/// `NavmeshPlugins::default().set(RerecastPlugin::new().with_backend(my_backend))`.
 #[derive(Debug, Default)]
 #[non_exhaustive]
 pub struct NavmeshPlugins;
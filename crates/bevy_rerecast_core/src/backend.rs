@@ -1,30 +1,317 @@
+use std::borrow::Cow;
+
 use bevy_app::prelude::*;
 use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::{prelude::*, system::SystemId};
+use bevy_platform::collections::HashMap;
+use bevy_reflect::prelude::*;
 use bevy_transform::prelude::*;
-use rerecast::TriMesh;
+use rerecast::{AreaType, PolygonNavmesh, TriMesh};
+
+/// A navmesh affector backend registered through [`NavmeshApp::add_navmesh_affector_backend`].
+/// Returns the entity each affector belongs to, along with its transform and mesh.
+///
+/// The [`TriMesh::area_types`] of the meshes are the area types of walkable triangles, see [`NavmeshAffectorAreas::mark`].
+/// Backends that don't assign area types leave them at [`AreaType::NOT_WALKABLE`].
+#[derive(Clone, Deref, DerefMut)]
+pub struct NavmeshAffectorBackend {
+    #[deref]
+    id: SystemId<(), Vec<(Entity, GlobalTransform, TriMesh)>>,
+    name: Cow<'static, str>,
+    enabled: bool,
+    areas: Option<NavmeshAffectorAreas>,
+}
+
+impl NavmeshAffectorBackend {
+    /// The name the backend was registered under, e.g. for error messages.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the affectors of the backend are part of navmesh builds. See [`NavmeshAffectorBackends::set_enabled`].
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// How the triangles of the backend are marked, or `None` if it uses the [`NavmeshAffectorAreas`] resource.
+    /// See [`NavmeshAffectorBackends::set_areas`].
+    pub fn areas(&self) -> Option<NavmeshAffectorAreas> {
+        self.areas
+    }
 
-/// The current backend registered through [`NavmeshApp::set_navmesh_affector_backend`]
-#[derive(Resource, Clone, Deref, DerefMut)]
-pub struct NavmeshAffectorBackend(SystemId<(), Vec<(GlobalTransform, TriMesh)>>);
+    /// How the triangles of the backend are marked, falling back to `default` if the backend has no areas of its own.
+    pub fn areas_or(&self, default: NavmeshAffectorAreas) -> NavmeshAffectorAreas {
+        self.areas.unwrap_or(default)
+    }
+}
+
+/// All navmesh affector backends, in the order they were added.
+///
+/// The affectors of all enabled backends are combined into one build, e.g. to generate a navmesh
+/// from both physics colliders and render meshes. Without any enabled backend, only [`HeightmapAffector`](crate::heightmap::HeightmapAffector)s are used.
+#[derive(Resource, Clone, Default)]
+pub struct NavmeshAffectorBackends(Vec<NavmeshAffectorBackend>);
+
+impl NavmeshAffectorBackends {
+    /// The backend registered under `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&NavmeshAffectorBackend> {
+        self.0.iter().find(|backend| backend.name == name)
+    }
+
+    /// All backends, in the order they were added, including the disabled ones.
+    pub fn iter(&self) -> impl Iterator<Item = &NavmeshAffectorBackend> {
+        self.0.iter()
+    }
+
+    /// The backends whose affectors are part of navmesh builds, in the order they were added.
+    pub fn enabled(&self) -> impl Iterator<Item = &NavmeshAffectorBackend> {
+        self.0.iter().filter(|backend| backend.enabled)
+    }
 
-/// Extension used to implement [`NavmeshApp::set_navmesh_affector_backend`] on [`App`]
+    /// Sets whether the affectors of the backend registered under `name` are part of navmesh builds.
+    /// Backends are enabled when added. Returns `false` if there is no backend with that name.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.0.iter_mut().find(|backend| backend.name == name) {
+            Some(backend) => {
+                backend.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sets how the triangles of the backend registered under `name` are marked, overriding the [`NavmeshAffectorAreas`] resource.
+    /// `None` makes the backend use the resource again. Returns `false` if there is no backend with that name.
+    pub fn set_areas(&mut self, name: &str, areas: Option<NavmeshAffectorAreas>) -> bool {
+        match self.0.iter_mut().find(|backend| backend.name == name) {
+            Some(backend) => {
+                backend.areas = areas;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, backend: NavmeshAffectorBackend) {
+        match self.0.iter_mut().find(|old| old.name == backend.name) {
+            Some(old) => *old = backend,
+            None => self.0.push(backend),
+        }
+    }
+}
+
+/// Extension used to implement [`NavmeshApp::add_navmesh_affector_backend`] on [`App`]
 pub trait NavmeshApp {
-    /// Set the backend for generating navmesh affectors. Only one backend can be set at a time.
-    /// Setting a backend will replace any existing backend. By default, no backend is set.
+    /// Adds a backend for generating navmesh affectors under `name`, e.g. `"avian"`.
+    /// The affectors of all backends are combined, see [`NavmeshAffectorBackends`].
+    /// Adding a backend under a name that is already taken replaces that backend. By default, no backend is added.
+    fn add_navmesh_affector_backend<M>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        system: impl IntoSystem<(), Vec<(Entity, GlobalTransform, TriMesh)>, M> + 'static,
+    ) -> &mut App;
+
+    /// Same as [`NavmeshApp::add_navmesh_affector_backend`], but the triangles of the backend are marked with `areas`
+    /// instead of the [`NavmeshAffectorAreas`] resource, so that backends can be configured independently of each other.
+    fn add_navmesh_affector_backend_with_areas<M>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        system: impl IntoSystem<(), Vec<(Entity, GlobalTransform, TriMesh)>, M> + 'static,
+        areas: NavmeshAffectorAreas,
+    ) -> &mut App;
+
+    /// Replaces all backends with the given one, named after the system.
+    ///
+    /// The affectors are not tied to any entity, so they can't be made obstructions or given areas through components,
+    /// and moving them does not update [`IncrementalNavmesh`](crate::tiled::IncrementalNavmesh)es on its own.
+    /// Use [`NavmeshApp::set_navmesh_affector_backend_with_entities`] to return the entity of each affector.
     fn set_navmesh_affector_backend<M>(
         &mut self,
         system: impl IntoSystem<(), Vec<(GlobalTransform, TriMesh)>, M> + 'static,
     ) -> &mut App;
+
+    /// Same as [`NavmeshApp::set_navmesh_affector_backend`], but the system also returns the entity each affector belongs to.
+    fn set_navmesh_affector_backend_with_entities<M>(
+        &mut self,
+        system: impl IntoSystem<(), Vec<(Entity, GlobalTransform, TriMesh)>, M> + 'static,
+    ) -> &mut App;
+
+    /// Registers `C` as a component that makes an entity a navmesh affector of a backend, e.g. `Mesh3d`.
+    /// Spawning an entity with it updates the tiles of every [`IncrementalNavmesh`](crate::tiled::IncrementalNavmesh),
+    /// while spawning other entities does not.
+    fn add_navmesh_affector_component<C: Component>(&mut self) -> &mut App;
 }
 
 impl NavmeshApp for App {
+    fn add_navmesh_affector_backend<M>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        system: impl IntoSystem<(), Vec<(Entity, GlobalTransform, TriMesh)>, M> + 'static,
+    ) -> &mut App {
+        insert_backend(self, name.into(), system, None)
+    }
+
+    fn add_navmesh_affector_backend_with_areas<M>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        system: impl IntoSystem<(), Vec<(Entity, GlobalTransform, TriMesh)>, M> + 'static,
+        areas: NavmeshAffectorAreas,
+    ) -> &mut App {
+        insert_backend(self, name.into(), system, Some(areas))
+    }
+
     fn set_navmesh_affector_backend<M>(
         &mut self,
         system: impl IntoSystem<(), Vec<(GlobalTransform, TriMesh)>, M> + 'static,
     ) -> &mut App {
-        let id = self.register_system(system);
-        self.world_mut().insert_resource(NavmeshAffectorBackend(id));
+        let system = IntoSystem::into_system(system);
+        let name = system.name();
+        self.world_mut()
+            .insert_resource(NavmeshAffectorBackends::default());
+        let system = system.map(|affectors: Vec<(GlobalTransform, TriMesh)>| {
+            affectors
+                .into_iter()
+                .map(|(transform, trimesh)| (Entity::PLACEHOLDER, transform, trimesh))
+                .collect::<Vec<_>>()
+        });
+        self.add_navmesh_affector_backend(name, system)
+    }
+
+    fn set_navmesh_affector_backend_with_entities<M>(
+        &mut self,
+        system: impl IntoSystem<(), Vec<(Entity, GlobalTransform, TriMesh)>, M> + 'static,
+    ) -> &mut App {
+        let system = IntoSystem::into_system(system);
+        let name = system.name();
+        self.world_mut()
+            .insert_resource(NavmeshAffectorBackends::default());
+        self.add_navmesh_affector_backend(name, system)
+    }
+
+    fn add_navmesh_affector_component<C: Component>(&mut self) -> &mut App {
+        crate::tiled::track_spawned_affectors::<C>(self);
         self
     }
 }
+
+fn insert_backend<'a, M>(
+    app: &'a mut App,
+    name: Cow<'static, str>,
+    system: impl IntoSystem<(), Vec<(Entity, GlobalTransform, TriMesh)>, M> + 'static,
+    areas: Option<NavmeshAffectorAreas>,
+) -> &'a mut App {
+    let id = app.register_system(system);
+    app.world_mut()
+        .get_resource_or_init::<NavmeshAffectorBackends>()
+        .insert(NavmeshAffectorBackend {
+            id,
+            name,
+            enabled: true,
+            areas,
+        });
+    app
+}
+
+/// How the triangles returned by the [`NavmeshAffectorBackends`] are marked before rasterization.
+///
+/// As a resource, it applies to [`HeightmapAffector`](crate::heightmap::HeightmapAffector)s and to the backends that have no areas of their own.
+/// The backend plugins set their own areas with [`NavmeshApp::add_navmesh_affector_backend_with_areas`], which users configure
+/// e.g. through `Mesh3dNavmeshPlugin::with_walkable_area`, so that every backend is marked independently. If missing, the default is used.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct NavmeshAffectorAreas {
+    /// The area walkable triangles are marked with. Defaults to [`AreaType::DEFAULT_WALKABLE`].
+    pub walkable_area: AreaType,
+    /// Whether only triangles flatter than [`NavmeshConfig::walkable_slope_angle`](rerecast::NavmeshConfig::walkable_slope_angle)
+    /// are walkable. If `false`, all triangles are marked with [`Self::walkable_area`] regardless of their slope,
+    /// e.g. for geometry that is known to be walkable. Defaults to `true`.
+    pub mark_by_slope: bool,
+}
+
+impl Default for NavmeshAffectorAreas {
+    fn default() -> Self {
+        Self {
+            walkable_area: AreaType::DEFAULT_WALKABLE,
+            mark_by_slope: true,
+        }
+    }
+}
+
+impl NavmeshAffectorAreas {
+    /// Marks the walkable triangles of `trimesh` according to these settings.
+    ///
+    /// Walkable triangles that were already assigned an area type other than [`AreaType::NOT_WALKABLE`],
+    /// e.g. by an `AreaTypeProvider`, keep it instead of being marked with [`Self::walkable_area`].
+    pub fn mark(&self, trimesh: &mut TriMesh, walkable_slope_angle: f32) {
+        let assigned = std::mem::replace(
+            &mut trimesh.area_types,
+            vec![AreaType::NOT_WALKABLE; trimesh.indices.len()],
+        );
+        if self.mark_by_slope {
+            trimesh.mark_walkable_triangles_as(walkable_slope_angle, self.walkable_area);
+        } else {
+            trimesh.area_types.fill(self.walkable_area);
+        }
+        for (area, assigned) in trimesh.area_types.iter_mut().zip(assigned) {
+            if *area != AreaType::NOT_WALKABLE && assigned != AreaType::NOT_WALKABLE {
+                *area = assigned;
+            }
+        }
+    }
+
+    /// These settings with the [`NavmeshAffectorArea`] of an entity applied, if it has one.
+    pub fn with_override(self, area: Option<NavmeshAffectorArea>) -> Self {
+        match area {
+            Some(area) => Self {
+                walkable_area: *area,
+                ..self
+            },
+            None => self,
+        }
+    }
+}
+
+/// Overrides [`NavmeshAffectorAreas::walkable_area`] for the navmesh affectors of an entity,
+/// e.g. to give ice or mud its own area. The triangles are still marked by slope if [`NavmeshAffectorAreas::mark_by_slope`] is set.
+///
+/// Ignored for entities with a [`NavmeshObstruction`].
+///
+/// Triangles that were assigned an area type by the backend, e.g. through an `AreaTypeProvider`, keep theirs.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Deref, DerefMut, Reflect)]
+#[reflect(Component)]
+#[doc(alias = "NavmeshAreaType")]
+pub struct NavmeshAffectorArea(pub AreaType);
+
+/// Marks an entity whose navmesh affectors are always obstacles, e.g. a fountain or a statue with a flat top
+/// that agents should not walk onto.
+///
+/// All triangles the [`NavmeshAffectorBackends`] return for the entity are marked as [`AreaType::NOT_WALKABLE`]
+/// instead of being marked according to [`NavmeshAffectorAreas`].
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component, Default)]
+pub struct NavmeshObstruction;
+
+impl NavmeshObstruction {
+    /// Marks all triangles of `trimesh` as [`AreaType::NOT_WALKABLE`].
+    pub fn mark(trimesh: &mut TriMesh) {
+        trimesh.area_types.fill(AreaType::NOT_WALKABLE);
+    }
+}
+
+/// The [flags](PolygonNavmesh::flags) of the polygons of each area type, assigned to every navmesh generated by
+/// [`NavmeshGenerator`](crate::generator::NavmeshGenerator). Polygons of areas without an entry get no flags.
+///
+/// Recast leaves flags to the user, who usually derives them from the areas, e.g. a swim flag for water,
+/// so that agents can filter the polygons they may enter by flags instead of listing every area.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Deref, DerefMut, Reflect)]
+#[reflect(Resource, Default)]
+pub struct NavmeshAreaFlags(pub HashMap<AreaType, u16>);
+
+impl NavmeshAreaFlags {
+    /// Sets the flags of all polygons of `mesh` according to their area type.
+    pub fn apply(&self, mesh: &mut PolygonNavmesh) {
+        for (flags, area) in mesh.flags.iter_mut().zip(&mesh.areas) {
+            *flags = self.get(area).copied().unwrap_or_default();
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! Debug visualization of [`Navmesh`]es, drawn with retained gizmos and meshes.
+//!
+//! Add [`NavmeshDebugPlugin`] next to the `PbrPlugin` and `GizmoPlugin` of Bevy and toggle the layers with [`NavmeshDebugSettings`].
+//! Every loaded navmesh is drawn, and redrawn whenever it or the settings change.
+//! Paths stored in a [`DebugPath`] are drawn every frame, so that they can be updated whenever the pathfinder runs.
+
+use bevy_app::prelude::*;
+use bevy_asset::prelude::*;
+use bevy_color::{Alpha as _, Color, ColorToComponents as _, LinearRgba, Mix as _};
+use bevy_ecs::prelude::*;
+use bevy_gizmos::{
+    config::GizmoLineConfig,
+    prelude::{GizmoAsset, Gizmos},
+    retained::Gizmo,
+};
+use bevy_mesh::{Indices, Mesh, PrimitiveTopology};
+use bevy_pbr::{MeshMaterial3d, StandardMaterial};
+use bevy_platform::collections::HashSet;
+use bevy_reflect::prelude::*;
+use bevy_render::{alpha::AlphaMode, mesh::Mesh3d, render_asset::RenderAssetUsages};
+use glam::Vec3;
+use rerecast::{AreaType, EdgeConnection, PolyId, PolygonNavmesh};
+
+use crate::Navmesh;
+
+/// Draws every loaded [`Navmesh`]: the polygons colored by their area type, and optionally the detail mesh,
+/// the region boundaries and the contours. Which of these are drawn is configured by [`NavmeshDebugSettings`].
+/// Also draws the path of every entity with a [`DebugPath`].
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct NavmeshDebugPlugin;
+
+impl Plugin for NavmeshDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<(NavmeshDebugSettings, DebugPath)>();
+        app.init_resource::<NavmeshDebugSettings>();
+        app.add_systems(PostUpdate, (draw_navmeshes, draw_paths));
+    }
+}
+
+/// Which layers [`NavmeshDebugPlugin`] draws. Changing this redraws all navmeshes.
+#[derive(Resource, Debug, Clone, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct NavmeshDebugSettings {
+    /// Whether the faces of the polygons are drawn, colored by their [`AreaType`].
+    /// [`AreaType::DEFAULT_WALKABLE`] is drawn in light blue, other areas in a color derived from their value. Defaults to `true`.
+    pub polygons: bool,
+    /// Whether the outlines of the polygons are drawn. Defaults to `true`.
+    pub polygon_outlines: bool,
+    /// Whether the triangles of the detail mesh are drawn as a wireframe. Defaults to `false`.
+    pub detail_mesh: bool,
+    /// Whether the edges between polygons of different regions are drawn. Defaults to `false`.
+    pub region_boundaries: bool,
+    /// Whether the outline of every region is drawn in a color derived from its id. Defaults to `false`.
+    ///
+    /// The contours themselves are not part of a [`Navmesh`], so they are traced from the regions of its polygons.
+    /// They match the simplified contours of the build, as the polygons of a region are a triangulation of its contour.
+    pub contours: bool,
+    /// How far everything is drawn above the navmesh, so that it doesn't flicker with the level geometry. Defaults to `0.05`.
+    pub height_offset: f32,
+    /// How tall the cost bar of a [`DebugPath`] segment is per unit of cost. Defaults to `0.1`.
+    pub path_cost_scale: f32,
+}
+
+impl Default for NavmeshDebugSettings {
+    fn default() -> Self {
+        Self {
+            polygons: true,
+            polygon_outlines: true,
+            detail_mesh: false,
+            region_boundaries: false,
+            contours: false,
+            height_offset: 0.05,
+            path_cost_scale: 0.1,
+        }
+    }
+}
+
+/// The last path computed for an entity, drawn by [`NavmeshDebugPlugin`] until the component is removed.
+///
+/// rerecast doesn't search paths itself, so this is filled in by whichever pathfinder runs on the [`Navmesh`].
+/// The polygons of the [`Self::corridor`] are outlined in yellow, with the edges they are entered through in cyan.
+/// The [`Self::waypoints`] are connected in white, and every segment with a cost gets a vertical bar at its middle
+/// whose height is the cost times [`NavmeshDebugSettings::path_cost_scale`]. The more expensive the segment is
+/// compared to its length, the redder it is drawn, e.g. because it crosses an area with a higher cost.
+#[derive(Component, Debug, Clone, Default, PartialEq, Reflect)]
+#[reflect(Component, Debug, Clone, Default, PartialEq)]
+pub struct DebugPath {
+    /// The navmesh the [`Self::corridor`] lies on.
+    pub navmesh: Handle<Navmesh>,
+    /// The polygons the path passes through, from the start to the goal.
+    /// Polygons that are not part of the navmesh, e.g. after it was rebuilt, are skipped.
+    pub corridor: Vec<PolyId>,
+    /// The smoothed path, from the start to the goal, in world space.
+    pub waypoints: Vec<Vec3>,
+    /// The cost of each segment between consecutive [`Self::waypoints`], as computed by the pathfinder.
+    /// Segments without a cost are drawn without a cost bar.
+    pub costs: Vec<f32>,
+}
+
+impl DebugPath {
+    /// Creates a path through the given corridor of the navmesh along the given waypoints, without any costs.
+    pub fn new(
+        navmesh: impl Into<Handle<Navmesh>>,
+        corridor: impl Into<Vec<PolyId>>,
+        waypoints: impl Into<Vec<Vec3>>,
+    ) -> Self {
+        Self {
+            navmesh: navmesh.into(),
+            corridor: corridor.into(),
+            waypoints: waypoints.into(),
+            costs: Vec::new(),
+        }
+    }
+
+    /// Sets the cost of each segment. See [`Self::costs`].
+    pub fn with_costs(mut self, costs: impl Into<Vec<f32>>) -> Self {
+        self.costs = costs.into();
+        self
+    }
+
+    /// The sum of the [`Self::costs`] of all segments.
+    pub fn total_cost(&self) -> f32 {
+        self.costs.iter().sum()
+    }
+}
+
+/// Holds the debug visualization of the given navmesh.
+#[derive(Component, Debug)]
+struct NavmeshDebug(AssetId<Navmesh>);
+
+fn draw_navmeshes(
+    mut events: EventReader<AssetEvent<Navmesh>>,
+    settings: Res<NavmeshDebugSettings>,
+    navmeshes: Res<Assets<Navmesh>>,
+    drawn: Query<(Entity, &NavmeshDebug)>,
+    mut gizmos: ResMut<Assets<GizmoAsset>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    let dirty = if settings.is_changed() {
+        events.clear();
+        drawn
+            .iter()
+            .map(|(_, debug)| debug.0)
+            .chain(navmeshes.ids())
+            .collect::<HashSet<_>>()
+    } else {
+        events
+            .read()
+            .filter_map(|event| match *event {
+                AssetEvent::Added { id }
+                | AssetEvent::Modified { id }
+                | AssetEvent::Removed { id }
+                | AssetEvent::Unused { id } => Some(id),
+                AssetEvent::LoadedWithDependencies { .. } => None,
+            })
+            .collect()
+    };
+    if dirty.is_empty() {
+        return;
+    }
+
+    for (entity, debug) in &drawn {
+        if dirty.contains(&debug.0) {
+            commands.entity(entity).despawn();
+        }
+    }
+    for id in dirty {
+        let Some(navmesh) = navmeshes.get(id) else {
+            continue;
+        };
+        let mut gizmo = GizmoAsset::new();
+        draw_lines(&mut gizmo, navmesh, &settings);
+        let mut entity = commands.spawn((
+            Name::new("Navmesh Debug"),
+            NavmeshDebug(id),
+            Gizmo {
+                handle: gizmos.add(gizmo),
+                line_config: GizmoLineConfig {
+                    width: 1.5,
+                    ..Default::default()
+                },
+                depth_bias: -0.001,
+            },
+        ));
+        if settings.polygons {
+            entity.insert((
+                Mesh3d(meshes.add(polygon_faces(navmesh.polygon(), settings.height_offset))),
+                MeshMaterial3d(materials.add(StandardMaterial {
+                    base_color: Color::WHITE,
+                    unlit: true,
+                    double_sided: true,
+                    cull_mode: None,
+                    alpha_mode: AlphaMode::Blend,
+                    ..Default::default()
+                })),
+            ));
+        }
+    }
+}
+
+/// The faces of the polygons, fan-triangulated and colored by their area type.
+fn polygon_faces(mesh: &PolygonNavmesh, height_offset: f32) -> Mesh {
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    let mut indices = Vec::new();
+    for polygon in mesh.polygon_ids() {
+        let color = area_color(mesh.areas[polygon.index()]).with_alpha(0.5);
+        let base = positions.len() as u32;
+        for vertex in mesh.polygon_vertices(polygon) {
+            positions.push(mesh.vertex_world(vertex) + Vec3::Y * height_offset);
+            colors.push(LinearRgba::from(color).to_f32_array());
+        }
+        let count = positions.len() as u32 - base;
+        for i in 1..count.saturating_sub(1) {
+            indices.extend([base, base + i, base + i + 1]);
+        }
+    }
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+fn draw_lines(gizmo: &mut GizmoAsset, navmesh: &Navmesh, settings: &NavmeshDebugSettings) {
+    let mesh = navmesh.polygon();
+    let offset = settings.height_offset;
+    if settings.polygon_outlines {
+        for polygon in mesh.polygon_ids() {
+            let mut vertices = mesh
+                .polygon_vertices(polygon)
+                .map(|vertex| mesh.vertex_world(vertex) + Vec3::Y * offset)
+                .collect::<Vec<_>>();
+            vertices.extend(vertices.first().copied());
+            gizmo.linestrip(vertices, Color::srgba(0.0, 0.19, 0.25, 0.6));
+        }
+    }
+
+    if settings.detail_mesh
+        && let Some(detail) = navmesh.detail()
+    {
+        for submesh in &detail.meshes {
+            let vertices = &detail.vertices[submesh.base_vertex_index as usize..]
+                [..submesh.vertex_count as usize];
+            let triangles = &detail.triangles[submesh.base_triangle_index as usize..]
+                [..submesh.triangle_count as usize];
+            for triangle in triangles {
+                let [a, b, c] =
+                    [0, 1, 2].map(|i| vertices[triangle[i] as usize] + Vec3::Y * offset);
+                gizmo.linestrip([a, b, c, a], Color::srgba(0.0, 0.5, 0.25, 0.6));
+            }
+        }
+    }
+
+    if !settings.region_boundaries && !settings.contours {
+        return;
+    }
+    for polygon in mesh.polygon_ids() {
+        let region = mesh.regions[polygon.index()];
+        for edge in mesh.edges(polygon) {
+            let other_region = match mesh.edge_connection(edge) {
+                EdgeConnection::Polygon(other) => Some(mesh.regions[other.index()]),
+                EdgeConnection::Portal(_) | EdgeConnection::Border => None,
+            };
+            if other_region == Some(region) {
+                continue;
+            }
+            let [a, b] = mesh
+                .edge_vertices(edge)
+                .map(|vertex| mesh.vertex_world(vertex) + Vec3::Y * offset);
+            // Shared edges are visited from both sides, but only drawn once.
+            if settings.region_boundaries && other_region.is_some_and(|other| other > region) {
+                gizmo.line(a, b, Color::WHITE);
+            }
+            if settings.contours {
+                let lift = Vec3::Y * offset;
+                gizmo.line(a + lift, b + lift, hashed_color(region.bits()));
+            }
+        }
+    }
+}
+
+fn draw_paths(
+    paths: Query<&DebugPath>,
+    navmeshes: Res<Assets<Navmesh>>,
+    settings: Res<NavmeshDebugSettings>,
+    mut gizmos: Gizmos,
+) {
+    let lift = Vec3::Y * settings.height_offset;
+    for path in &paths {
+        if let Some(navmesh) = navmeshes.get(&path.navmesh) {
+            draw_corridor(
+                &mut gizmos,
+                navmesh.polygon(),
+                &path.corridor,
+                settings.height_offset,
+            );
+        }
+        for (i, segment) in path.waypoints.windows(2).enumerate() {
+            let (start, end) = (segment[0] + lift, segment[1] + lift);
+            let Some(cost) = path.costs.get(i).copied() else {
+                gizmos.line(start, end, Color::WHITE);
+                continue;
+            };
+            let color = cost_color(cost, start.distance(end));
+            gizmos.line(start, end, color);
+            let middle = start.midpoint(end);
+            gizmos.line(
+                middle,
+                middle + Vec3::Y * cost * settings.path_cost_scale,
+                color,
+            );
+        }
+        for waypoint in &path.waypoints {
+            gizmos.sphere(*waypoint + lift, 0.05, Color::WHITE);
+        }
+    }
+}
+
+fn draw_corridor(
+    gizmos: &mut Gizmos,
+    mesh: &PolygonNavmesh,
+    corridor: &[PolyId],
+    height_offset: f32,
+) {
+    let polygon_count = mesh.polygon_count();
+    for polygon in corridor
+        .iter()
+        .filter(|polygon| polygon.index() < polygon_count)
+    {
+        let mut vertices = mesh
+            .polygon_vertices(*polygon)
+            .map(|vertex| mesh.vertex_world(vertex) + Vec3::Y * height_offset)
+            .collect::<Vec<_>>();
+        vertices.extend(vertices.first().copied());
+        gizmos.linestrip(vertices, Color::srgb(1.0, 0.85, 0.0));
+    }
+    for pair in corridor.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        if from.index() >= polygon_count || to.index() >= polygon_count {
+            continue;
+        }
+        let Some(portal) = mesh
+            .edges(from)
+            .find(|edge| mesh.edge_connection(*edge) == EdgeConnection::Polygon(to))
+        else {
+            continue;
+        };
+        let [a, b] = mesh
+            .edge_vertices(portal)
+            .map(|vertex| mesh.vertex_world(vertex) + Vec3::Y * height_offset);
+        gizmos.line(a, b, Color::srgb(0.0, 1.0, 1.0));
+    }
+}
+
+/// Fades from white for segments that cost as much as they are long to red for much more expensive ones.
+fn cost_color(cost: f32, length: f32) -> Color {
+    let multiplier = if length > f32::EPSILON {
+        cost / length
+    } else {
+        1.0
+    };
+    let expense = (1.0 - 1.0 / multiplier.max(1.0)).clamp(0.0, 1.0);
+    Color::WHITE.mix(&Color::srgb(1.0, 0.1, 0.0), expense)
+}
+
+/// The color of polygons with the given area type.
+fn area_color(area: AreaType) -> Color {
+    match area {
+        AreaType::DEFAULT_WALKABLE => Color::srgb_u8(0, 192, 255),
+        AreaType::NOT_WALKABLE => Color::BLACK,
+        area => hashed_color(area.0 as u16),
+    }
+}
+
+/// A distinct color for every id, so that neighboring regions or areas can be told apart.
+fn hashed_color(id: u16) -> Color {
+    // Same as `duIntToCol` of the Recast debug draw.
+    let bit = |bit: u16| (id >> bit) & 1;
+    let channel = |low: u16, high: u16| ((bit(low) + bit(high) * 2 + 1) * 63) as u8;
+    Color::srgb_u8(channel(1, 3), channel(2, 4), channel(0, 5))
+}
@@ -0,0 +1,132 @@
+//! Navmesh metrics for Bevy's diagnostics, so that they show up in standard diagnostic overlays and logs,
+//! e.g. through `LogDiagnosticsPlugin`.
+
+use core::time::Duration;
+
+use bevy_app::prelude::*;
+use bevy_diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic as _};
+use bevy_ecs::prelude::*;
+use bevy_reflect::prelude::*;
+use rerecast::{BuildScratchStats, BuildStage};
+
+use crate::{Navmesh, generator::generate_navmeshes};
+
+pub(super) fn plugin(app: &mut App) {
+    for stage in BuildStage::ALL {
+        app.register_diagnostic(
+            Diagnostic::new(NavmeshDiagnostics::stage_duration(stage)).with_suffix("ms"),
+        );
+    }
+    app.register_diagnostic(Diagnostic::new(NavmeshDiagnostics::BUILD_DURATION).with_suffix("ms"));
+    app.register_diagnostic(Diagnostic::new(NavmeshDiagnostics::POLYGON_COUNT));
+    app.register_diagnostic(
+        Diagnostic::new(NavmeshDiagnostics::MEMORY_ESTIMATE).with_suffix("KiB"),
+    );
+    app.register_diagnostic(Diagnostic::new(NavmeshDiagnostics::AFFECTOR_COUNT));
+    app.register_diagnostic(Diagnostic::new(NavmeshDiagnostics::SCRATCH_GROWTHS));
+    app.register_diagnostic(
+        Diagnostic::new(NavmeshDiagnostics::SCRATCH_CAPACITY).with_suffix("KiB"),
+    );
+    app.add_systems(
+        PostUpdate,
+        add_measurements
+            .after(generate_navmeshes)
+            .run_if(resource_exists_and_changed::<NavmeshBuildStats>),
+    );
+}
+
+/// The paths of the diagnostics registered by [`RerecastPlugin`](crate::RerecastPlugin).
+/// All of them refer to the most recent navmesh generated by [`NavmeshGenerator`](crate::generator::NavmeshGenerator).
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct NavmeshDiagnostics;
+
+impl NavmeshDiagnostics {
+    /// The total duration of the last navmesh build, in milliseconds.
+    pub const BUILD_DURATION: DiagnosticPath = DiagnosticPath::const_new("rerecast/build_duration");
+    /// The number of polygons in the last built navmesh.
+    pub const POLYGON_COUNT: DiagnosticPath = DiagnosticPath::const_new("rerecast/polygon_count");
+    /// An estimate of the memory used by the last built navmesh, in KiB.
+    pub const MEMORY_ESTIMATE: DiagnosticPath =
+        DiagnosticPath::const_new("rerecast/memory_estimate");
+    /// The number of navmesh affectors collected for the last build.
+    pub const AFFECTOR_COUNT: DiagnosticPath = DiagnosticPath::const_new("rerecast/affector_count");
+    /// The number of build buffers that had to be allocated or grown during the last build.
+    /// Stays at zero while navmeshes are rebuilt without getting larger.
+    pub const SCRATCH_GROWTHS: DiagnosticPath =
+        DiagnosticPath::const_new("rerecast/scratch_growths");
+    /// The memory held by the reused build buffers, in KiB.
+    pub const SCRATCH_CAPACITY: DiagnosticPath =
+        DiagnosticPath::const_new("rerecast/scratch_capacity");
+
+    /// The duration of the given stage of the last navmesh build, in milliseconds.
+    pub fn stage_duration(stage: BuildStage) -> DiagnosticPath {
+        DiagnosticPath::new(format!("rerecast/build_duration/{}", stage.name()))
+    }
+}
+
+/// Statistics of the most recent navmesh build. Inserted once the first navmesh has been generated.
+#[derive(Resource, Debug, Clone, Default, Reflect)]
+#[reflect(Resource, Default)]
+pub struct NavmeshBuildStats {
+    /// How long each stage of the build took, in the order the stages were run.
+    pub stage_durations: Vec<(BuildStage, Duration)>,
+    /// How long the whole build took, including collecting the affectors.
+    pub total_duration: Duration,
+    /// The number of polygons in the built navmesh.
+    pub polygon_count: usize,
+    /// An estimate of the memory used by the built navmesh, in bytes.
+    pub memory_estimate: usize,
+    /// The number of navmesh affectors collected for the build.
+    pub affector_count: usize,
+    /// How well the build buffers in [`NavmeshBuildScratch`](crate::generator::NavmeshBuildScratch) were reused.
+    pub scratch: BuildScratchStats,
+}
+
+impl Navmesh {
+    /// Estimates the memory used by the data of this navmesh, in bytes.
+    pub fn memory_estimate(&self) -> usize {
+        fn size_of_vec<T>(vec: &[T]) -> usize {
+            core::mem::size_of_val(vec)
+        }
+        let polygon = self.polygon();
+        let mut size = size_of_vec(&polygon.vertices)
+            + size_of_vec(&polygon.polygons)
+            + size_of_vec(&polygon.polygon_neighbors)
+            + size_of_vec(&polygon.flags)
+            + size_of_vec(&polygon.regions)
+            + size_of_vec(&polygon.areas);
+        if let Some(detail) = self.detail() {
+            size += size_of_vec(&detail.meshes)
+                + size_of_vec(&detail.vertices)
+                + size_of_vec(&detail.triangles);
+        }
+        size
+    }
+}
+
+fn add_measurements(stats: Res<NavmeshBuildStats>, mut diagnostics: Diagnostics) {
+    for (stage, duration) in &stats.stage_durations {
+        diagnostics.add_measurement(&NavmeshDiagnostics::stage_duration(*stage), || {
+            duration.as_secs_f64() * 1000.0
+        });
+    }
+    diagnostics.add_measurement(&NavmeshDiagnostics::BUILD_DURATION, || {
+        stats.total_duration.as_secs_f64() * 1000.0
+    });
+    diagnostics.add_measurement(&NavmeshDiagnostics::POLYGON_COUNT, || {
+        stats.polygon_count as f64
+    });
+    diagnostics.add_measurement(&NavmeshDiagnostics::MEMORY_ESTIMATE, || {
+        stats.memory_estimate as f64 / 1024.0
+    });
+    diagnostics.add_measurement(&NavmeshDiagnostics::AFFECTOR_COUNT, || {
+        stats.affector_count as f64
+    });
+    diagnostics.add_measurement(&NavmeshDiagnostics::SCRATCH_GROWTHS, || {
+        stats.scratch.growths as f64
+    });
+    diagnostics.add_measurement(&NavmeshDiagnostics::SCRATCH_CAPACITY, || {
+        stats.scratch.capacity_bytes as f64 / 1024.0
+    });
+}
@@ -0,0 +1,153 @@
+//! Navmesh fragments that move with their entity, e.g. for elevators and moving platforms.
+//!
+//! A fragment is baked once in the local space of its entity and then only transformed,
+//! so moving a platform does not require regenerating any navmesh.
+//! The fragment is connected to the static navmesh through off-mesh links whose endpoints are updated every frame.
+
+use bevy_app::prelude::*;
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_transform::{TransformSystem, prelude::*};
+use glam::Vec3;
+use rerecast::{Aabb3d, NavmeshConfig, OffMeshConnection, TriMesh};
+
+use crate::Navmesh;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PostUpdate,
+        (bake_fragments, update_fragment_transforms)
+            .chain()
+            .after(TransformSystem::TransformPropagate),
+    );
+}
+
+/// Bakes a small navmesh for this entity and keeps it attached to the entity's transform.
+///
+/// The navmesh is baked from [`DynamicNavmeshFragment::geometry`] whenever this component is changed.
+/// The result is written to the [`NavmeshFragment`] of this entity.
+#[derive(Component, Debug, Clone)]
+#[require(Transform)]
+pub struct DynamicNavmeshFragment {
+    /// The geometry of the platform in the local space of the entity.
+    pub geometry: TriMesh,
+    /// The config used to bake the fragment.
+    /// If [`NavmeshConfig::aabb`] is left at its default, it is set to the bounds of [`DynamicNavmeshFragment::geometry`].
+    pub config: NavmeshConfig,
+    /// Off-mesh connections linking the fragment to the static navmesh.
+    /// [`OffMeshConnection::start`] lies on the fragment, in the local space of the entity,
+    /// while [`OffMeshConnection::end`] lies on the static navmesh, in world space.
+    pub links: Vec<OffMeshConnection>,
+}
+
+impl DynamicNavmeshFragment {
+    /// Creates a fragment without any links.
+    pub fn new(geometry: TriMesh, config: NavmeshConfig) -> Self {
+        Self {
+            geometry,
+            config,
+            links: Vec::new(),
+        }
+    }
+
+    /// Adds a link connecting the fragment to the static navmesh, see [`DynamicNavmeshFragment::links`].
+    pub fn with_link(mut self, link: OffMeshConnection) -> Self {
+        self.links.push(link);
+        self
+    }
+}
+
+/// The baked state of a [`DynamicNavmeshFragment`]. Inserted and kept up to date automatically.
+#[derive(Component, Debug, Clone)]
+pub struct NavmeshFragment {
+    /// The baked navmesh, in the local space of the entity.
+    pub navmesh: Handle<Navmesh>,
+    /// The transform that maps the navmesh into world space.
+    /// Updated every frame from the entity's [`GlobalTransform`].
+    pub transform: GlobalTransform,
+    /// The [`DynamicNavmeshFragment::links`] with both endpoints in world space.
+    /// The start of each link lies on the fragment.
+    pub links: Vec<OffMeshConnection>,
+}
+
+impl NavmeshFragment {
+    /// Converts a point in the local space of the fragment's navmesh into world space.
+    #[inline]
+    pub fn to_world(&self, local: Vec3) -> Vec3 {
+        self.transform.transform_point(local)
+    }
+
+    /// Converts a point in world space into the local space of the fragment's navmesh.
+    #[inline]
+    pub fn to_local(&self, world: Vec3) -> Vec3 {
+        self.transform.affine().inverse().transform_point3(world)
+    }
+}
+
+fn bake_fragments(
+    mut fragments: Query<
+        (
+            Entity,
+            &DynamicNavmeshFragment,
+            &GlobalTransform,
+            Option<&NavmeshFragment>,
+        ),
+        Changed<DynamicNavmeshFragment>,
+    >,
+    mut navmeshes: ResMut<Assets<Navmesh>>,
+    mut commands: Commands,
+) {
+    for (entity, fragment, transform, baked) in &mut fragments {
+        let mut config = fragment.config;
+        if config.aabb == Aabb3d::default()
+            && let Some(aabb) = fragment.geometry.compute_aabb()
+        {
+            config.aabb = aabb;
+        }
+        let navmesh = match rerecast::build_navmesh(fragment.geometry.clone(), &config) {
+            Ok((polygon, detail)) => Navmesh::new(polygon, detail),
+            Err(err) => {
+                tracing::error!("Failed to bake navmesh fragment for {entity}: {err}");
+                continue;
+            }
+        };
+        let handle = match baked {
+            Some(baked) => {
+                navmeshes.insert(&baked.navmesh, navmesh);
+                baked.navmesh.clone()
+            }
+            None => navmeshes.add(navmesh),
+        };
+        commands.entity(entity).insert(NavmeshFragment {
+            navmesh: handle,
+            transform: *transform,
+            links: world_links(&fragment.links, transform),
+        });
+    }
+}
+
+fn update_fragment_transforms(
+    mut fragments: Query<(
+        &DynamicNavmeshFragment,
+        &GlobalTransform,
+        &mut NavmeshFragment,
+    )>,
+) {
+    for (fragment, transform, mut baked) in &mut fragments {
+        if baked.transform == *transform {
+            continue;
+        }
+        baked.transform = *transform;
+        baked.links = world_links(&fragment.links, transform);
+    }
+}
+
+fn world_links(links: &[OffMeshConnection], transform: &GlobalTransform) -> Vec<OffMeshConnection> {
+    links
+        .iter()
+        .map(|link| OffMeshConnection {
+            start: transform.transform_point(link.start),
+            ..*link
+        })
+        .collect()
+}
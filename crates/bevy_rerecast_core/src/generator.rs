@@ -1,17 +1,52 @@
 //! Utilities for generating navmeshes at runtime.
 
-use std::{collections::VecDeque, marker::PhantomData};
+use core::{f32::consts::FRAC_PI_2, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    marker::PhantomData,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
 use bevy_app::prelude::*;
 use bevy_asset::prelude::*;
 use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::{prelude::*, system::SystemParam};
-use rerecast::NavmeshConfig;
+use bevy_platform::{collections::HashMap, time::Instant};
+use bevy_reflect::Reflect;
+use bevy_tasks::{AsyncComputeTaskPool, Task, block_on};
+use bevy_transform::prelude::*;
+use glam::Vec3;
+use rerecast::{
+    Aabb3d, AreaMergePolicy, AreaType, BuildContext, BuildNavmeshError, BuildScratch, BuildStage,
+    CachedBuild, ConvexVolume, DetailNavmesh, Heightfield, Heightmap, NavmeshConfig,
+    OffMeshConnection, PolygonNavmesh, RasterizationError, RecordedSpans, SteppedBuild, TriMesh,
+};
+use thiserror::Error;
 
-use crate::Navmesh;
+use crate::{
+    DefaultNavmeshConfig, Navmesh, NavmeshAffectorArea, NavmeshAffectorAreas,
+    NavmeshAffectorBackends, NavmeshAreaFlags, NavmeshObstruction,
+    diagnostics::NavmeshBuildStats,
+    heightmap::HeightmapAffector,
+    navmeshes::{
+        AgentProfiles, NavmeshFailed, NavmeshKey, NavmeshReady, NavmeshStepped, Navmeshes,
+    },
+    off_mesh::collect_off_mesh_connections,
+    volume::NavmeshVolume,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.init_resource::<NavmeshQueue>();
+    app.init_resource::<RunningBuilds>();
+    app.init_resource::<NavmeshBuildScratch>();
+    app.init_resource::<NavmeshBuildLimit>();
+    app.init_resource::<SteppedNavmeshBuilds>();
+    app.register_type::<NavmeshPriority>();
+    app.add_systems(PostUpdate, generate_navmeshes);
 }
 
 /// System parameter for generating navmeshes.
@@ -22,19 +57,1623 @@ pub struct NavmeshGenerator<'w, Marker: 'static> {
     )]
     navmeshes: Res<'w, Assets<Navmesh>>,
     queue: ResMut<'w, NavmeshQueue>,
+    registry: ResMut<'w, Navmeshes>,
+    stepped: ResMut<'w, SteppedNavmeshBuilds>,
+    default_config: Res<'w, DefaultNavmeshConfig>,
+    agent_profiles: Res<'w, AgentProfiles>,
     marker: PhantomData<Marker>,
 }
 
 impl<'w, Marker: 'static> NavmeshGenerator<'w, Marker> {
-    /// Queue a navmesh generation task.
+    /// Queue a navmesh generation task with [`NavmeshPriority::Normal`].
     /// When you call this method, a new navmesh will be generated asynchronously.
-    /// Calling it multiple times will queue multiple navmeshes to be generated in a FIFO order.
+    /// Calling it multiple times will queue multiple navmeshes to be generated in a FIFO order,
+    /// at most [`NavmeshBuildLimit::max_concurrent_builds`] at a time.
+    ///
+    /// If the [`NavmeshConfig::aabb`] is left at its default, it is set to the bounds of all navmesh affectors.
     pub fn generate(&mut self, config: NavmeshConfig) -> Handle<Navmesh> {
+        self.generate_with_priority(config, NavmeshPriority::Normal)
+    }
+
+    /// Same as [`NavmeshGenerator::generate`], but with the given priority.
+    /// Navmeshes with a higher priority are started before all queued navmeshes with a lower priority.
+    pub fn generate_with_priority(
+        &mut self,
+        config: NavmeshConfig,
+        priority: NavmeshPriority,
+    ) -> Handle<Navmesh> {
         let handle = self.navmeshes.reserve_handle();
-        self.queue.push_back((handle.clone(), config));
+        self.queue.push_back(QueuedNavmesh {
+            handle: handle.clone(),
+            key: None,
+            config,
+            priority,
+            stepped: false,
+        });
         handle
     }
+
+    /// Same as [`NavmeshGenerator::generate`], but in step mode: instead of running the whole pipeline in the background,
+    /// the build waits for [`NavmeshGenerator::step`] to run its next [`BuildStage`] on the main thread.
+    ///
+    /// The input is collected when the build starts, like for any other build. After every stage, [`NavmeshStepped`] is sent
+    /// and the results of the stage can be inspected through [`SteppedNavmeshBuilds::get`], e.g. to visualize how the navmesh comes together.
+    /// Once the last stage is done, the navmesh is stored and [`NavmeshReady`] is sent like for any other build.
+    pub fn generate_stepped(&mut self, config: NavmeshConfig) -> Handle<Navmesh> {
+        let handle = self.navmeshes.reserve_handle();
+        self.queue.push_back(QueuedNavmesh {
+            handle: handle.clone(),
+            key: None,
+            config,
+            priority: NavmeshPriority::Normal,
+            stepped: true,
+        });
+        handle
+    }
+
+    /// Runs the next stage of a build started with [`NavmeshGenerator::generate_stepped`] at the end of the frame.
+    /// Calling it several times in one frame runs several stages.
+    ///
+    /// Steps requested while the build is still queued run once it starts. Does nothing for navmeshes that are not built in step mode.
+    pub fn step(&mut self, handle: &Handle<Navmesh>) {
+        self.stepped.pending_steps.push(handle.id());
+    }
+
+    /// Same as [`NavmeshGenerator::generate`], but uses the [`DefaultNavmeshConfig`].
+    pub fn generate_default(&mut self) -> Handle<Navmesh> {
+        let config = self.default_config.build();
+        self.generate(config)
+    }
+
+    /// Same as [`NavmeshGenerator::generate`], but registers the navmesh under `key` in [`Navmeshes`].
+    ///
+    /// If a navmesh is already registered under `key`, it is regenerated in place and its handle is returned,
+    /// so that everything holding on to the handle sees the new navmesh once it is ready.
+    /// The [`NavmeshReady`] event sent once the navmesh is ready carries the key.
+    pub fn generate_keyed(
+        &mut self,
+        key: impl Into<NavmeshKey>,
+        config: NavmeshConfig,
+    ) -> Handle<Navmesh> {
+        self.generate_keyed_with_priority(key, config, NavmeshPriority::Normal)
+    }
+
+    /// Generates one navmesh per profile in [`AgentProfiles`] with [`NavmeshGenerator::generate_keyed`],
+    /// keyed by the name of the profile. Each uses the [`DefaultNavmeshConfig`] with the profile applied.
+    ///
+    /// The affectors are collected only once for all builds that start in the same frame,
+    /// so the navmeshes share the same geometry unless more builds are queued than the [`NavmeshBuildLimit`] allows.
+    /// Returns the handles ordered by the names of the profiles.
+    pub fn generate_for_agents(&mut self) -> Vec<Handle<Navmesh>> {
+        let mut profiles = self
+            .agent_profiles
+            .iter()
+            .map(|(key, profile)| (key.clone(), *profile))
+            .collect::<Vec<_>>();
+        profiles.sort_by(|(a, _), (b, _)| a.cmp(b));
+        profiles
+            .into_iter()
+            .map(|(key, profile)| {
+                let mut config = self.default_config.0;
+                profile.apply(&mut config);
+                self.generate_keyed(key, config.build())
+            })
+            .collect()
+    }
+
+    /// Same as [`NavmeshGenerator::generate_keyed`], but with the given priority.
+    /// See [`NavmeshGenerator::generate_with_priority`].
+    pub fn generate_keyed_with_priority(
+        &mut self,
+        key: impl Into<NavmeshKey>,
+        config: NavmeshConfig,
+        priority: NavmeshPriority,
+    ) -> Handle<Navmesh> {
+        let key = key.into();
+        let handle = match self.registry.get(key.clone()) {
+            Some(handle) => handle.clone(),
+            None => {
+                let handle = self.navmeshes.reserve_handle();
+                self.registry.insert(key.clone(), handle.clone());
+                handle
+            }
+        };
+        self.queue.push_back(QueuedNavmesh {
+            handle: handle.clone(),
+            key: Some(key),
+            config,
+            priority,
+            stepped: false,
+        });
+        handle
+    }
+}
+
+/// How urgently a navmesh queued with [`NavmeshGenerator`] is needed.
+///
+/// Queued navmeshes are started from the highest priority down, and in the order they were queued within a priority.
+/// Running builds are never interrupted for more urgent ones, only cancelled once a newer build of the same navmesh starts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+pub enum NavmeshPriority {
+    /// Rebuilds that can wait, e.g. after cosmetic changes.
+    /// They never take the last free slot of the [`NavmeshBuildLimit`], so that more urgent builds can always start right away.
+    Background,
+    /// The default priority.
+    #[default]
+    Normal,
+    /// Rebuilds the game cannot do without, e.g. after a level-altering event.
+    Critical,
+}
+
+/// Limits how many navmeshes [`NavmeshGenerator`] builds at the same time, so that runtime rebuilds
+/// don't occupy every thread of the [`AsyncComputeTaskPool`]. Navmeshes beyond the limit wait in the queue.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NavmeshBuildLimit {
+    /// The maximum number of navmeshes built at the same time. Values below 1 are treated as 1. Defaults to 2.
+    pub max_concurrent_builds: usize,
+}
+
+impl Default for NavmeshBuildLimit {
+    fn default() -> Self {
+        Self {
+            max_concurrent_builds: 2,
+        }
+    }
+}
+
+impl NavmeshBuildLimit {
+    /// Whether a build with the given priority may start while `running` builds are in progress.
+    fn allows(&self, priority: NavmeshPriority, running: usize) -> bool {
+        let max = self.max_concurrent_builds.max(1);
+        match priority {
+            // Keep a slot free for more urgent builds, unless there is only one slot at all.
+            NavmeshPriority::Background => running < max.saturating_sub(1).max(1),
+            NavmeshPriority::Normal | NavmeshPriority::Critical => running < max,
+        }
+    }
 }
 
 #[derive(Resource, Default, Deref, DerefMut)]
-struct NavmeshQueue(VecDeque<(Handle<Navmesh>, NavmeshConfig)>);
+struct NavmeshQueue(VecDeque<QueuedNavmesh>);
+
+impl NavmeshQueue {
+    /// Removes the queued navmesh with the highest priority, preferring the one queued first.
+    fn pop_most_urgent(&mut self) -> Option<QueuedNavmesh> {
+        let index = self
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, queued)| (queued.priority, std::cmp::Reverse(*index)))
+            .map(|(index, _)| index)?;
+        self.remove(index)
+    }
+
+    fn most_urgent_priority(&self) -> Option<NavmeshPriority> {
+        self.iter().map(|queued| queued.priority).max()
+    }
+}
+
+struct QueuedNavmesh {
+    handle: Handle<Navmesh>,
+    key: Option<NavmeshKey>,
+    config: NavmeshConfig,
+    priority: NavmeshPriority,
+    /// Whether the build was started with [`NavmeshGenerator::generate_stepped`].
+    stepped: bool,
+}
+
+/// The navmeshes currently being built on the [`AsyncComputeTaskPool`].
+#[derive(Resource, Default)]
+struct RunningBuilds {
+    builds: Vec<RunningBuild>,
+    /// The number of builds started so far, used to discard builds that finish after a newer build of the same navmesh.
+    started: u64,
+    /// The index of the newest build started for each navmesh, so that a build that finishes after a newer one
+    /// of the same navmesh is still recognized as outdated. Removed once no build of the navmesh is in flight anymore.
+    generations: HashMap<AssetId<Navmesh>, u64>,
+}
+
+impl RunningBuilds {
+    /// Hands out the index of a new build of the given navmesh, making all earlier builds of it outdated.
+    fn next_generation(&mut self, id: AssetId<Navmesh>) -> u64 {
+        let index = self.started;
+        self.started += 1;
+        self.generations.insert(id, index);
+        index
+    }
+
+    /// Whether a newer build of the given navmesh was started after the build with the given index.
+    fn is_outdated(&self, id: AssetId<Navmesh>, index: u64) -> bool {
+        self.generations
+            .get(&id)
+            .is_some_and(|&generation| index < generation)
+    }
+
+    /// Forgets the newest build of every navmesh that has neither a running nor a [stepped](SteppedNavmeshBuilds) build,
+    /// as no outdated build of it can finish anymore. Keeps the generations from piling up for navmeshes that are gone.
+    fn forget_idle(&mut self, stepped: &SteppedNavmeshBuilds) {
+        let builds = &self.builds;
+        self.generations.retain(|id, _| {
+            stepped.builds.contains_key(id) || builds.iter().any(|build| build.handle.id() == *id)
+        });
+    }
+}
+
+struct RunningBuild {
+    handle: Handle<Navmesh>,
+    key: Option<NavmeshKey>,
+    index: u64,
+    affector_count: usize,
+    collection_duration: Duration,
+    build_start: Instant,
+    /// Set once a newer build of the same navmesh starts.
+    cancel: Arc<AtomicBool>,
+    task: Task<FinishedBuild>,
+}
+
+struct FinishedBuild {
+    result: Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError>,
+    /// Only kept while [`CacheNavmeshBuilds`] exists.
+    cache: Option<AreaRebuildCache>,
+    stage_durations: Vec<(BuildStage, Duration)>,
+    scratch: BuildScratch,
+}
+
+/// Makes the generator keep the eroded heightfield of every navmesh it builds, so that [`Navmesh::rebuild_areas`]
+/// can apply changed [`NavmeshVolume`]s without running the whole pipeline again.
+///
+/// Insert this resource to enable the cache. The heightfield takes up considerably more memory than the navmesh itself,
+/// so only enable it when areas change at runtime. Builds started with [`NavmeshGenerator::generate_stepped`] are not cached.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct CacheNavmeshBuilds;
+
+/// What [`Navmesh::rebuild_areas`] needs to finish a navmesh like the generator does.
+#[derive(Debug)]
+pub(crate) struct AreaRebuildCache {
+    build: CachedBuild,
+    connections: Vec<OffMeshConnection>,
+    area_flags: Option<NavmeshAreaFlags>,
+}
+
+impl Navmesh {
+    /// Rebuilds the navmesh with `volumes` marked on it, replacing the volumes it was built with.
+    /// Only the stages after the erosion are run again, on the heightfield cached by the last build.
+    ///
+    /// Use this when only the areas change, e.g. when a [`NavmeshVolume`] is toggled to mark a zone as water,
+    /// and the geometry stays the same. Unlike [`Navmesh::remark_area_in_volume`], the polygons are split along the volumes.
+    /// The off-mesh connections and [`NavmeshAreaFlags`] of the last build are applied again.
+    ///
+    /// The navmesh must have been built by the [`NavmeshGenerator`] while [`CacheNavmeshBuilds`] existed,
+    /// otherwise [`RebuildAreasError::NotCached`] is returned. Runs on the calling thread.
+    pub fn rebuild_areas(&mut self, volumes: &[ConvexVolume]) -> Result<(), RebuildAreasError> {
+        let cache = self
+            .build_cache
+            .clone()
+            .ok_or(RebuildAreasError::NotCached)?;
+        let (mut polygon, detail) = cache.build.rebuild(
+            volumes,
+            &mut BuildScratch::default(),
+            &BuildContext::default(),
+        )?;
+        finish_polygons(&mut polygon, &cache.connections, cache.area_flags.as_ref());
+        self.polygon = polygon;
+        self.detail = Some(detail);
+        Ok(())
+    }
+
+    /// Whether the heightfield of the last build is cached, so that [`Navmesh::rebuild_areas`] can be used.
+    pub fn can_rebuild_areas(&self) -> bool {
+        self.build_cache.is_some()
+    }
+}
+
+/// Why [`Navmesh::rebuild_areas`] failed.
+#[derive(Error, Debug)]
+pub enum RebuildAreasError {
+    /// Happens when the navmesh was not built by the [`NavmeshGenerator`] while [`CacheNavmeshBuilds`] existed,
+    /// e.g. because it was loaded from a file.
+    #[error("The navmesh has no cached build. Insert `CacheNavmeshBuilds` before generating it")]
+    NotCached,
+    /// Happens when building the navmesh from the cached heightfield fails.
+    #[error(transparent)]
+    Build(#[from] BuildNavmeshError),
+}
+
+/// The builds started with [`NavmeshGenerator::generate_stepped`] that are not done yet.
+///
+/// A build is added once it leaves the queue and removed once its last stage is done, when it fails,
+/// or when a newer build of the same navmesh starts.
+#[derive(Resource, Default)]
+pub struct SteppedNavmeshBuilds {
+    builds: HashMap<AssetId<Navmesh>, SteppedNavmeshBuild>,
+    pending_steps: Vec<AssetId<Navmesh>>,
+}
+
+impl SteppedNavmeshBuilds {
+    /// The build of the given navmesh, holding the results of the stages that ran so far.
+    pub fn get(&self, id: impl Into<AssetId<Navmesh>>) -> Option<&SteppedBuild<'static>> {
+        self.builds.get(&id.into()).map(|stepped| &stepped.build)
+    }
+
+    /// The navmeshes that are currently built in step mode.
+    pub fn ids(&self) -> impl Iterator<Item = AssetId<Navmesh>> + '_ {
+        self.builds.keys().copied()
+    }
+}
+
+struct SteppedNavmeshBuild {
+    handle: Handle<Navmesh>,
+    key: Option<NavmeshKey>,
+    index: u64,
+    build: SteppedBuild<'static>,
+    connections: Vec<OffMeshConnection>,
+    area_flags: Option<NavmeshAreaFlags>,
+}
+
+/// Insert this resource to write the inputs of every navmesh generation to a file in [`RecordNavmeshInputs::directory`].
+///
+/// The files can be replayed with [`rerecast::replay`], which makes it possible to reproduce a generation
+/// without access to the project it was made in, e.g. when filing a bug report.
+#[cfg(feature = "recording")]
+#[derive(Resource, Debug, Clone)]
+pub struct RecordNavmeshInputs {
+    /// The directory to write the recordings to. Must already exist.
+    pub directory: std::path::PathBuf,
+    count: u32,
+}
+
+#[cfg(feature = "recording")]
+impl RecordNavmeshInputs {
+    /// Records navmesh inputs into the given directory.
+    pub fn new(directory: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            count: 0,
+        }
+    }
+
+    fn record(&mut self, trimeshes: &[TriMesh], config: NavmeshConfig) {
+        let path = self
+            .directory
+            .join(format!("navmesh_inputs_{}.bin.gz", self.count));
+        self.count += 1;
+        let recording = rerecast::InputRecording {
+            trimeshes: trimeshes.to_vec(),
+            config,
+        };
+        match recording.save(&path) {
+            Ok(()) => tracing::info!("Recorded navmesh inputs to {}", path.display()),
+            Err(err) => tracing::error!("Failed to record navmesh inputs: {err}"),
+        }
+    }
+}
+
+/// The temporary buffers reused by all navmesh builds of [`NavmeshGenerator`],
+/// so that rebuilding navmeshes at runtime does not allocate them again and again.
+///
+/// Holds one [`BuildScratch`] per build that ran at the same time, up to [`NavmeshBuildLimit::max_concurrent_builds`].
+/// Buffers in use by a running build are not in here.
+///
+/// The buffers grow to the size of the largest build. Call [`BuildScratch::clear`] to release their memory,
+/// e.g. after generating an unusually large navmesh.
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub struct NavmeshBuildScratch(pub Vec<BuildScratch>);
+
+/// A navmesh affector returned by one of the [`NavmeshAffectorBackends`], transformed into world space.
+pub(crate) struct CollectedAffector {
+    pub(crate) entity: Entity,
+    pub(crate) trimesh: TriMesh,
+    obstruction: bool,
+    areas: NavmeshAffectorAreas,
+}
+
+impl CollectedAffector {
+    /// The trimesh with its triangles marked for rasterization.
+    pub(crate) fn marked(&self, walkable_slope_angle: f32) -> TriMesh {
+        let mut trimesh = self.trimesh.clone();
+        if self.obstruction {
+            NavmeshObstruction::mark(&mut trimesh);
+        } else {
+            self.areas.mark(&mut trimesh, walkable_slope_angle);
+        }
+        trimesh
+    }
+}
+
+/// A [`HeightmapAffector`] that lines up with the heightfield, transformed into world space.
+pub(crate) struct CollectedHeightmap {
+    pub(crate) entity: Entity,
+    pub(crate) heightmap: Heightmap,
+    pub(crate) obstruction: bool,
+    pub(crate) areas: NavmeshAffectorAreas,
+}
+
+impl CollectedHeightmap {
+    /// Rasterizes the heightmap, marked the same way as the triangles of a [`CollectedAffector`].
+    pub(crate) fn rasterize(
+        &self,
+        heightfield: &mut Heightfield,
+        config: &NavmeshConfig,
+    ) -> Result<(), RasterizationError> {
+        let (walkable_slope_angle, area) = if self.obstruction {
+            (0.0, AreaType::NOT_WALKABLE)
+        } else if self.areas.mark_by_slope {
+            (config.walkable_slope_angle, self.areas.walkable_area)
+        } else {
+            (FRAC_PI_2, self.areas.walkable_area)
+        };
+        heightfield.rasterize_heightmap(
+            &self.heightmap,
+            walkable_slope_angle,
+            area,
+            config.walkable_climb,
+        )
+    }
+
+    /// The heightmap as triangles, for recordings, which only hold a [`TriMesh`].
+    #[cfg(feature = "recording")]
+    fn triangulated(&self) -> CollectedAffector {
+        CollectedAffector {
+            entity: self.entity,
+            trimesh: self.heightmap.to_trimesh(),
+            obstruction: self.obstruction,
+            areas: self.areas,
+        }
+    }
+}
+
+/// The navmesh affectors returned by the [`NavmeshAffectorBackends`], along with all [`HeightmapAffector`]s and [`NavmeshVolume`]s.
+pub(crate) struct CollectedInput {
+    /// The affectors of the enabled backends and the heightmaps that had to be triangulated, sorted by entity.
+    pub(crate) affectors: Vec<CollectedAffector>,
+    heightmaps: Vec<CollectedHeightmap>,
+    /// The volumes in world space, marked in this order.
+    volumes: Vec<ConvexVolume>,
+    /// What each enabled backend matched, in the order they were run.
+    backends: Vec<BackendMatches>,
+}
+
+impl CollectedInput {
+    /// The affectors and the heightmaps that are rasterized directly, for the tiled builds.
+    pub(crate) fn into_affectors_and_heightmaps(
+        self,
+    ) -> (Vec<CollectedAffector>, Vec<CollectedHeightmap>) {
+        (self.affectors, self.heightmaps)
+    }
+
+    /// [`CollectInputError::NoInputGeometry`] if none of the affectors has a triangle or a quad.
+    fn check_geometry(&self) -> Result<(), CollectInputError> {
+        if self
+            .affectors
+            .iter()
+            .any(|affector| !affector.trimesh.indices.is_empty())
+            || self
+                .heightmaps
+                .iter()
+                .any(|heightmap| heightmap.heightmap.aabb().is_some())
+        {
+            return Ok(());
+        }
+        Err(CollectInputError::NoInputGeometry {
+            backends: self.backends.clone(),
+        })
+    }
+}
+
+/// Runs the enabled [`NavmeshAffectorBackends`] and collects the [`HeightmapAffector`]s and [`NavmeshVolume`]s.
+pub(crate) fn collect_affectors(world: &mut World) -> Result<CollectedInput, CollectInputError> {
+    let areas = world
+        .get_resource::<NavmeshAffectorAreas>()
+        .copied()
+        .unwrap_or_default();
+    let (triangulated, heightmaps) = collect_heightmaps(world, areas);
+    let volumes = collect_volumes(world);
+    let enabled_backends = world
+        .get_resource::<NavmeshAffectorBackends>()
+        .map(|backends| backends.enabled().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let mut affectors = Vec::new();
+    let mut backends = Vec::with_capacity(enabled_backends.len());
+    for backend in enabled_backends {
+        let name: Cow<'static, str> = Cow::Owned(backend.name().to_string());
+        let matched = world
+            .run_system(*backend)
+            .map_err(|err| CollectInputError::Backend {
+                name: name.clone(),
+                error: err.to_string(),
+            })?;
+        backends.push(BackendMatches {
+            name,
+            entities: matched.len(),
+        });
+        let backend_areas = backend.areas_or(areas);
+        affectors.extend(matched.into_iter().map(|(entity, transform, mut trimesh)| {
+            for vertex in &mut trimesh.vertices {
+                *vertex = transform.transform_point(Vec3::from(*vertex)).into();
+            }
+            CollectedAffector {
+                entity,
+                trimesh,
+                obstruction: world.get::<NavmeshObstruction>(entity).is_some(),
+                areas: backend_areas
+                    .with_override(world.get::<NavmeshAffectorArea>(entity).copied()),
+            }
+        }));
+    }
+    affectors.extend(triangulated);
+    // Queries don't guarantee an order, but the order of the triangles decides which area wins where spans merge.
+    // Sorting by entity makes rebuilds of the same world identical. The sort is stable, so the order of the backends
+    // decides between affectors of the same entity.
+    affectors.sort_by_key(|affector| affector.entity);
+    Ok(CollectedInput {
+        affectors,
+        heightmaps,
+        volumes,
+        backends,
+    })
+}
+
+/// Collects the valid [`HeightmapAffector`]s. Returns those that have to be triangulated because they are rotated
+/// separately from those that can be rasterized directly.
+fn collect_heightmaps(
+    world: &mut World,
+    areas: NavmeshAffectorAreas,
+) -> (Vec<CollectedAffector>, Vec<CollectedHeightmap>) {
+    let mut query = world.query::<(
+        Entity,
+        &HeightmapAffector,
+        &GlobalTransform,
+        Has<NavmeshObstruction>,
+        Option<&NavmeshAffectorArea>,
+    )>();
+    let mut triangulated = Vec::new();
+    let mut heightmaps = Vec::new();
+    for (entity, heightmap, transform, obstruction, area) in query.iter(world) {
+        if let Err(err) = heightmap.validate() {
+            tracing::warn!("Skipping invalid heightmap affector {entity}: {err}");
+            continue;
+        }
+        let areas = areas.with_override(area.copied());
+        match heightmap.to_world(transform) {
+            Some(heightmap) => heightmaps.push(CollectedHeightmap {
+                entity,
+                heightmap,
+                obstruction,
+                areas,
+            }),
+            None => triangulated.push(CollectedAffector {
+                entity,
+                trimesh: heightmap.to_world_trimesh(transform),
+                obstruction,
+                areas,
+            }),
+        }
+    }
+    heightmaps.sort_by_key(|heightmap| heightmap.entity);
+    (triangulated, heightmaps)
+}
+
+/// Collects the [`NavmeshVolume`]s in world space, skipping those whose footprint has fewer than three corners.
+/// Sorted by entity, as later volumes win where they overlap.
+fn collect_volumes(world: &mut World) -> Vec<ConvexVolume> {
+    let mut query = world.query::<(Entity, &NavmeshVolume, &GlobalTransform)>();
+    let mut volumes = query.iter(world).collect::<Vec<_>>();
+    volumes.sort_by_key(|(entity, ..)| *entity);
+    volumes
+        .into_iter()
+        .filter_map(|(entity, volume, transform)| {
+            if volume.footprint.len() < 3 {
+                tracing::warn!(
+                    "Skipping navmesh volume {entity} with only {} corners",
+                    volume.footprint.len()
+                );
+                return None;
+            }
+            Some(volume.to_world(transform))
+        })
+        .collect()
+}
+
+/// Why [`NavmeshGenerator`] failed to generate a navmesh, as sent with [`NavmeshFailed`].
+#[derive(Error, Debug)]
+pub enum NavmeshGenerationFailed {
+    /// Happens when no backend is enabled, or when none of the entities the enabled backends matched has any triangles.
+    /// This usually means that the backend plugin is missing or that its filter matches nothing.
+    #[error("{}", describe_missing_geometry(.backends))]
+    NoInputGeometry {
+        /// What each enabled backend matched, or nothing if no backend is enabled.
+        backends: Vec<BackendMatches>,
+    },
+    /// Happens when a backend system could not be run.
+    #[error("Failed to run navmesh affector backend `{name}`: {error}")]
+    Backend {
+        /// The name the backend was registered under.
+        name: Cow<'static, str>,
+        /// Why the backend could not be run.
+        error: String,
+    },
+    /// Happens when building the navmesh from the collected geometry fails.
+    #[error(transparent)]
+    Build(#[from] BuildNavmeshError),
+}
+
+/// Why the input of the navmeshes could not be collected from the world.
+#[derive(Error, Debug, Clone)]
+pub(crate) enum CollectInputError {
+    /// See [`NavmeshGenerationFailed::NoInputGeometry`].
+    #[error("{}", describe_missing_geometry(.backends))]
+    NoInputGeometry { backends: Vec<BackendMatches> },
+    /// See [`NavmeshGenerationFailed::Backend`].
+    #[error("Failed to run navmesh affector backend `{name}`: {error}")]
+    Backend {
+        name: Cow<'static, str>,
+        error: String,
+    },
+}
+
+impl From<CollectInputError> for NavmeshGenerationFailed {
+    fn from(error: CollectInputError) -> Self {
+        match error {
+            CollectInputError::NoInputGeometry { backends } => Self::NoInputGeometry { backends },
+            CollectInputError::Backend { name, error } => Self::Backend { name, error },
+        }
+    }
+}
+
+/// What a backend matched when it returned no geometry, as part of [`NavmeshGenerationFailed::NoInputGeometry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendMatches {
+    /// The name the backend was registered under.
+    pub name: Cow<'static, str>,
+    /// The number of entities the backend returned affectors for. All of them had no triangles.
+    pub entities: usize,
+}
+
+fn describe_missing_geometry(backends: &[BackendMatches]) -> String {
+    if backends.is_empty() {
+        return "No input geometry: no navmesh affector backend is enabled. \
+            Add a backend plugin or call `NavmeshApp::add_navmesh_affector_backend`"
+            .to_string();
+    }
+    let matches = backends
+        .iter()
+        .map(|BackendMatches { name, entities }| match entities {
+            0 => format!("the navmesh affector backend `{name}` matched no entities"),
+            _ => format!(
+                "the navmesh affector backend `{name}` matched {entities} entities, \
+                but none of them has any triangles"
+            ),
+        })
+        .collect::<Vec<_>>();
+    format!("No input geometry: {}", matches.join("; "))
+}
+
+/// Logs the failure and sends [`NavmeshFailed`].
+fn report_failure(
+    world: &mut World,
+    handle: Handle<Navmesh>,
+    key: Option<NavmeshKey>,
+    error: NavmeshGenerationFailed,
+) {
+    match &key {
+        Some(key) => tracing::error!("Failed to generate navmesh \"{key}\": {error}"),
+        None => tracing::error!("Failed to generate navmesh: {error}"),
+    }
+    world.send_event(NavmeshFailed { handle, key, error });
+}
+
+/// Fails every queued navmesh, e.g. because the input could not be collected.
+fn fail_queue(world: &mut World, error: impl Fn() -> NavmeshGenerationFailed) {
+    let queue = std::mem::take(&mut world.resource_mut::<NavmeshQueue>().0);
+    for queued in queue {
+        report_failure(world, queued.handle, queued.key, error());
+    }
+}
+
+pub(crate) fn generate_navmeshes(world: &mut World) {
+    #[cfg(feature = "gpu_rasterization")]
+    crate::gpu_rasterization::poll_readbacks(world);
+    finish_builds(world);
+    start_builds(world);
+    step_builds(world);
+    world.resource_scope(|world, mut running: Mut<RunningBuilds>| {
+        running.forget_idle(world.resource::<SteppedNavmeshBuilds>());
+    });
+}
+
+fn finish_builds(world: &mut World) {
+    let mut running = world.resource_mut::<RunningBuilds>();
+    if running.builds.is_empty() {
+        return;
+    }
+    let (finished, still_running) = std::mem::take(&mut running.builds)
+        .into_iter()
+        .partition::<Vec<_>, _>(|build| build.task.is_finished());
+    running.builds = still_running;
+
+    for build in finished {
+        let FinishedBuild {
+            result,
+            cache,
+            stage_durations,
+            scratch,
+        } = block_on(build.task);
+        let scratch_stats = scratch.stats();
+        world.resource_mut::<NavmeshBuildScratch>().push(scratch);
+
+        if world
+            .resource::<RunningBuilds>()
+            .is_outdated(build.handle.id(), build.index)
+        {
+            // A newer build of the same navmesh was started in the meantime, so this one is already outdated.
+            continue;
+        }
+
+        match result {
+            Ok((polygon, detail)) => {
+                let mut navmesh = Navmesh::new(polygon, detail);
+                navmesh.build_cache = cache.map(Arc::new);
+                world.insert_resource(NavmeshBuildStats {
+                    stage_durations,
+                    total_duration: build.collection_duration + build.build_start.elapsed(),
+                    polygon_count: navmesh.polygon().polygon_count(),
+                    memory_estimate: navmesh.memory_estimate(),
+                    affector_count: build.affector_count,
+                    scratch: scratch_stats,
+                });
+                world
+                    .resource_mut::<Assets<Navmesh>>()
+                    .insert(build.handle.id(), navmesh);
+                world.send_event(NavmeshReady {
+                    handle: build.handle,
+                    key: build.key,
+                });
+            }
+            Err(err) => report_failure(world, build.handle, build.key, err.into()),
+        }
+    }
+}
+
+fn start_builds(world: &mut World) {
+    // The affectors are only collected once per frame, no matter how many builds start.
+    let mut collected: Option<(
+        Vec<CollectedAffector>,
+        Arc<[CollectedHeightmap]>,
+        Arc<[ConvexVolume]>,
+        TriMesh,
+        Vec<OffMeshConnection>,
+        Duration,
+    )> = None;
+    loop {
+        let limit = *world.resource::<NavmeshBuildLimit>();
+        let running = world.resource::<RunningBuilds>().builds.len();
+        let Some(priority) = world.resource::<NavmeshQueue>().most_urgent_priority() else {
+            return;
+        };
+        if !limit.allows(priority, running) {
+            return;
+        }
+        if collected.is_none() {
+            let collection_start = Instant::now();
+            let input = collect_affectors(world).and_then(|input| {
+                input.check_geometry()?;
+                Ok(input)
+            });
+            let (affectors, heightmaps, volumes) = match input {
+                Ok(input) => (
+                    input.affectors,
+                    Arc::from(input.heightmaps),
+                    Arc::from(input.volumes),
+                ),
+                Err(err) => {
+                    fail_queue(world, || err.clone().into());
+                    return;
+                }
+            };
+            let mut trimesh = TriMesh::default();
+            for affector in &affectors {
+                trimesh.extend(&affector.trimesh);
+            }
+            let connections = collect_off_mesh_connections(world);
+            collected = Some((
+                affectors,
+                heightmaps,
+                volumes,
+                trimesh,
+                connections,
+                collection_start.elapsed(),
+            ));
+        }
+        let Some((affectors, heightmaps, volumes, trimesh, connections, collection_duration)) =
+            &collected
+        else {
+            return;
+        };
+        let Some(QueuedNavmesh {
+            handle,
+            key,
+            mut config,
+            priority: _,
+            stepped,
+        }) = world.resource_mut::<NavmeshQueue>().pop_most_urgent()
+        else {
+            return;
+        };
+
+        if config.aabb == Aabb3d::default()
+            && let Some(aabb) = trimesh
+                .compute_aabb()
+                .into_iter()
+                .chain(heightmaps.iter().filter_map(|h| h.heightmap.aabb()))
+                .reduce(|a, b| Aabb3d {
+                    min: a.min.min(b.min),
+                    max: a.max.max(b.max),
+                })
+        {
+            config.aabb = aabb;
+        }
+
+        let build_start = Instant::now();
+        let marked_affectors = affectors
+            .iter()
+            .map(|affector| affector.marked(config.walkable_slope_angle))
+            .collect::<Vec<_>>();
+        let mut marked = TriMesh::default();
+        for affector in &marked_affectors {
+            marked.extend(affector);
+        }
+
+        #[cfg(feature = "recording")]
+        if let Some(mut recorder) = world.get_resource_mut::<RecordNavmeshInputs>() {
+            // Recordings only hold triangles, so the heightmaps are replayed as their triangulation
+            // and the volumes are left out.
+            let triangulated = heightmaps
+                .iter()
+                .map(|heightmap| heightmap.triangulated().marked(config.walkable_slope_angle));
+            let trimeshes = marked_affectors
+                .iter()
+                .cloned()
+                .chain(triangulated)
+                .collect::<Vec<_>>();
+            recorder.record(&trimeshes, config);
+        }
+
+        // The GPU needs the render world, so rasterizing starts here and the task waits for the spans.
+        // Stepped builds are stepped on the main thread, which must not wait for the GPU.
+        let rasterization_start = Instant::now();
+        #[cfg(feature = "gpu_rasterization")]
+        let pending_spans = (!stepped)
+            .then(|| crate::gpu_rasterization::record_spans(world, &marked, &config))
+            .flatten();
+        #[cfg(feature = "gpu_rasterization")]
+        let spans_on_gpu = pending_spans.is_some();
+        #[cfg(not(feature = "gpu_rasterization"))]
+        let spans_on_gpu = false;
+        let connections = connections.clone();
+        let task_heightmaps = heightmaps.clone();
+        let volumes = volumes.clone();
+        let area_flags = world.get_resource::<NavmeshAreaFlags>().cloned();
+        let keep_cache = world.contains_resource::<CacheNavmeshBuilds>();
+
+        let rasterizer = move |spans: Option<RecordedSpans>| {
+            move |heightfield: &mut Heightfield| {
+                match &spans {
+                    Some(spans) => heightfield.replay_spans(
+                        spans,
+                        config.walkable_climb,
+                        AreaMergePolicy::default(),
+                    )?,
+                    None => heightfield.rasterize_triangles(&marked, config.walkable_climb)?,
+                }
+                for heightmap in task_heightmaps.iter() {
+                    heightmap.rasterize(heightfield, &config)?;
+                }
+                Ok(())
+            }
+        };
+
+        let mut running = world.resource_mut::<RunningBuilds>();
+        // Older builds of the same navmesh would be discarded once they finish anyway.
+        for outdated in running
+            .builds
+            .iter()
+            .filter(|build| build.handle.id() == handle.id())
+        {
+            outdated.cancel.store(true, Ordering::Relaxed);
+        }
+        let index = running.next_generation(handle.id());
+        let mut stepped_builds = world.resource_mut::<SteppedNavmeshBuilds>();
+        stepped_builds.builds.remove(&handle.id());
+        if stepped {
+            stepped_builds.builds.insert(
+                handle.id(),
+                SteppedNavmeshBuild {
+                    handle,
+                    key,
+                    index,
+                    build: SteppedBuild::new(config, rasterizer(None))
+                        .with_volumes(volumes.to_vec()),
+                    connections,
+                    area_flags,
+                },
+            );
+            continue;
+        }
+
+        let mut scratch = world
+            .resource_mut::<NavmeshBuildScratch>()
+            .pop()
+            .unwrap_or_default();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let task_cancel = cancel.clone();
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            #[cfg(feature = "gpu_rasterization")]
+            let spans = match pending_spans {
+                Some(pending) => pending
+                    .read()
+                    .await
+                    .inspect_err(|err| {
+                        tracing::warn!(
+                            "Failed to rasterize on the GPU, rasterizing on the CPU instead: {err}"
+                        );
+                    })
+                    .ok(),
+                None => None,
+            };
+            #[cfg(not(feature = "gpu_rasterization"))]
+            let spans = None;
+            let rasterize = rasterizer(spans);
+            let timings = Mutex::new((
+                Vec::with_capacity(BuildStage::ALL.len()),
+                spans_on_gpu.then_some((BuildStage::Rasterization, rasterization_start)),
+            ));
+            let progress = |stage, _| {
+                let (stage_durations, current_stage) = &mut *timings.lock().unwrap();
+                // Rasterizing on the GPU begins the rasterization stage before the pipeline does,
+                // and the pipeline reports the last stage once more when it is done.
+                if current_stage.is_some_and(|(current, _)| current == stage) {
+                    return;
+                }
+                let now = Instant::now();
+                if let Some((previous, start)) = current_stage.replace((stage, now)) {
+                    stage_durations.push((previous, now - start));
+                }
+            };
+            let context = BuildContext::new(&progress, &task_cancel);
+            let (result, cache) = if keep_cache {
+                match CachedBuild::build(config, &volumes, &mut scratch, &context, rasterize) {
+                    Ok((build, result)) => (Ok(result), Some(build)),
+                    Err(err) => (Err(err), None),
+                }
+            } else {
+                let result = rerecast::build_navmesh_with_rasterizer(
+                    &config,
+                    &volumes,
+                    &mut scratch,
+                    &context,
+                    rasterize,
+                );
+                (result, None)
+            };
+            let (mut stage_durations, current_stage) = timings.into_inner().unwrap();
+            if let Some((stage, start)) = current_stage {
+                stage_durations.push((stage, start.elapsed()));
+            }
+            let result = result.map(|(mut polygon, detail)| {
+                finish_polygons(&mut polygon, &connections, area_flags.as_ref());
+                (polygon, detail)
+            });
+            let cache = cache.map(|build| AreaRebuildCache {
+                build,
+                connections,
+                area_flags,
+            });
+            FinishedBuild {
+                result,
+                cache,
+                stage_durations,
+                scratch,
+            }
+        });
+
+        world
+            .resource_mut::<RunningBuilds>()
+            .builds
+            .push(RunningBuild {
+                handle,
+                key,
+                index,
+                affector_count: affectors.len() + heightmaps.len(),
+                collection_duration: *collection_duration,
+                build_start,
+                cancel,
+                task,
+            });
+    }
+}
+
+/// Bakes the off-mesh connections into a freshly built navmesh and applies the [`NavmeshAreaFlags`].
+fn finish_polygons(
+    polygon: &mut PolygonNavmesh,
+    connections: &[OffMeshConnection],
+    area_flags: Option<&NavmeshAreaFlags>,
+) {
+    let skipped = polygon.bake_off_mesh_connections(connections);
+    if !skipped.is_empty() {
+        tracing::warn!(
+            "Skipped {} of {} off-mesh connections whose endpoints are not on the navmesh",
+            skipped.len(),
+            connections.len()
+        );
+    }
+    finish_tile_polygons(polygon, area_flags);
+}
+
+/// Applies the [`NavmeshAreaFlags`] to a freshly built navmesh or tile, including the tiles of
+/// [`IncrementalNavmesh`](crate::tiled::IncrementalNavmesh)es and [`CarvedNavmesh`](crate::obstacles::CarvedNavmesh)es.
+pub(crate) fn finish_tile_polygons(
+    polygon: &mut PolygonNavmesh,
+    area_flags: Option<&NavmeshAreaFlags>,
+) {
+    if let Some(area_flags) = area_flags {
+        area_flags.apply(polygon);
+    }
+}
+
+/// Runs the stages requested with [`NavmeshGenerator::step`].
+fn step_builds(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<SteppedNavmeshBuilds>().pending_steps);
+    for id in pending {
+        let mut stepped_builds = world.resource_mut::<SteppedNavmeshBuilds>();
+        let Some(stepped) = stepped_builds.builds.get_mut(&id) else {
+            let queued = world
+                .resource::<NavmeshQueue>()
+                .iter()
+                .any(|queued| queued.stepped && queued.handle.id() == id);
+            if queued {
+                world
+                    .resource_mut::<SteppedNavmeshBuilds>()
+                    .pending_steps
+                    .push(id);
+            }
+            continue;
+        };
+        let handle = stepped.handle.clone();
+        let result = stepped.build.step(&BuildContext::default());
+        let done = stepped.build.next_stage().is_none();
+        if let Ok(Some(stage)) = result {
+            world.send_event(NavmeshStepped {
+                handle: handle.clone(),
+                stage,
+            });
+        }
+        if !done {
+            continue;
+        }
+
+        let Some(stepped) = world
+            .resource_mut::<SteppedNavmeshBuilds>()
+            .builds
+            .remove(&id)
+        else {
+            continue;
+        };
+        if world
+            .resource::<RunningBuilds>()
+            .is_outdated(id, stepped.index)
+        {
+            continue;
+        }
+        match result {
+            Ok(_) => {
+                let Some((mut polygon, detail)) = stepped.build.into_navmesh() else {
+                    continue;
+                };
+                finish_polygons(
+                    &mut polygon,
+                    &stepped.connections,
+                    stepped.area_flags.as_ref(),
+                );
+                world
+                    .resource_mut::<Assets<Navmesh>>()
+                    .insert(id, Navmesh::new(polygon, detail));
+                world.send_event(NavmeshReady {
+                    handle,
+                    key: stepped.key,
+                });
+            }
+            Err(err) => report_failure(world, handle, stepped.key, err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::{AssetPlugin, uuid::Uuid};
+    use bevy_ecs::system::RunSystemOnce as _;
+    use bevy_tasks::{IoTaskPool, TaskPool};
+    use glam::Vec2;
+
+    use super::*;
+    use crate::NavmeshApp as _;
+
+    fn id(id: u128) -> AssetId<Navmesh> {
+        AssetId::Uuid {
+            uuid: Uuid::from_u128(id),
+        }
+    }
+
+    fn queued(id: u128, priority: NavmeshPriority) -> QueuedNavmesh {
+        QueuedNavmesh {
+            handle: Handle::Weak(self::id(id)),
+            key: None,
+            config: rerecast::NavmeshConfigBuilder::default().build(),
+            priority,
+            stepped: false,
+        }
+    }
+
+    /// A 10x10 floor centered on the origin.
+    fn floor() -> Vec<(GlobalTransform, TriMesh)> {
+        let trimesh = TriMesh::from_parts(
+            vec![
+                Vec3::new(-5.0, 0.0, -5.0).into(),
+                Vec3::new(-5.0, 0.0, 5.0).into(),
+                Vec3::new(5.0, 0.0, 5.0).into(),
+                Vec3::new(5.0, 0.0, -5.0).into(),
+            ],
+            vec![glam::UVec3::new(0, 1, 2), glam::UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        vec![(GlobalTransform::IDENTITY, trimesh)]
+    }
+
+    /// An app with the [`RerecastPlugin`](crate::RerecastPlugin) and the task pools the builds run on.
+    fn navmesh_app() -> App {
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        IoTaskPool::get_or_init(TaskPool::new);
+        let mut app = App::new();
+        app.add_plugins((AssetPlugin::default(), crate::RerecastPlugin::new()));
+        app
+    }
+
+    /// Queues navmeshes with `generate` and updates the app until all builds are done.
+    fn generate<T: Send + 'static>(
+        app: &mut App,
+        generate: impl FnOnce(&mut NavmeshGenerator<()>) -> T + Send + Sync + 'static,
+    ) -> T {
+        let mut generate = Some(generate);
+        let queued = app
+            .world_mut()
+            .run_system_once(move |mut generator: NavmeshGenerator<()>| {
+                generate.take().unwrap()(&mut generator)
+            })
+            .unwrap();
+        finish_all_builds(app);
+        queued
+    }
+
+    fn finish_all_builds(app: &mut App) {
+        for _ in 0..1000 {
+            app.update();
+            let world = app.world();
+            if world.resource::<NavmeshQueue>().is_empty()
+                && world.resource::<RunningBuilds>().builds.is_empty()
+            {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        panic!("The navmesh builds did not finish");
+    }
+
+    fn built(app: &App, handle: &Handle<Navmesh>) -> Navmesh {
+        app.world()
+            .resource::<Assets<Navmesh>>()
+            .get(handle)
+            .expect("The navmesh was built")
+            .clone()
+    }
+
+    #[test]
+    fn rebuilt_areas_match_a_full_build() {
+        const WATER: AreaType = AreaType(3);
+        let mut app = navmesh_app();
+        app.set_navmesh_affector_backend(floor)
+            .init_resource::<CacheNavmeshBuilds>();
+        let config = rerecast::NavmeshConfigBuilder::default().build();
+        let handle = generate(&mut app, move |generator| generator.generate(config));
+        let mut navmesh = built(&app, &handle);
+        assert!(navmesh.can_rebuild_areas());
+        assert!(!navmesh.polygon().areas.contains(&WATER));
+
+        // Covers the half of the floor with positive x.
+        let volume = NavmeshVolume::new(
+            [
+                Vec2::new(0.0, -6.0),
+                Vec2::new(0.0, 6.0),
+                Vec2::new(6.0, 6.0),
+                Vec2::new(6.0, -6.0),
+            ],
+            -1.0,
+            1.0,
+            WATER,
+        );
+        let volumes = [volume.to_world(&GlobalTransform::IDENTITY)];
+        app.world_mut().spawn(volume);
+        let handle = generate(&mut app, move |generator| generator.generate(config));
+        let expected = built(&app, &handle);
+        assert!(expected.polygon().areas.contains(&WATER));
+
+        navmesh.rebuild_areas(&volumes).unwrap();
+        assert_eq!(navmesh, expected);
+    }
+
+    #[test]
+    fn areas_of_uncached_navmeshes_cannot_be_rebuilt() {
+        let mut app = navmesh_app();
+        app.set_navmesh_affector_backend(floor);
+        let config = rerecast::NavmeshConfigBuilder::default().build();
+        let handle = generate(&mut app, move |generator| generator.generate(config));
+        let mut navmesh = built(&app, &handle);
+        assert!(!navmesh.can_rebuild_areas());
+        assert!(matches!(
+            navmesh.rebuild_areas(&[]),
+            Err(RebuildAreasError::NotCached)
+        ));
+    }
+
+    #[test]
+    fn stepped_builds_match_background_builds() {
+        let mut app = navmesh_app();
+        app.set_navmesh_affector_backend(floor);
+        let config = rerecast::NavmeshConfigBuilder::default().build();
+        let handle = generate(&mut app, move |generator| generator.generate(config));
+        let expected = built(&app, &handle);
+
+        let handle = generate(&mut app, move |generator| {
+            generator.generate_stepped(config)
+        });
+        let mut steps = 0;
+        while app
+            .world()
+            .resource::<SteppedNavmeshBuilds>()
+            .get(&handle)
+            .is_some()
+        {
+            assert!(
+                app.world()
+                    .resource::<Assets<Navmesh>>()
+                    .get(&handle)
+                    .is_none()
+            );
+            let step = handle.clone();
+            generate(&mut app, move |generator| generator.step(&step));
+            steps += 1;
+        }
+        assert_eq!(steps, BuildStage::ALL.len());
+        assert_eq!(built(&app, &handle), expected);
+    }
+
+    #[test]
+    fn builds_finishing_out_of_order_keep_the_newest() {
+        let (first, second) = (id(1), id(2));
+        let mut running = RunningBuilds::default();
+        let older = running.next_generation(first);
+        let other = running.next_generation(second);
+        let newer = running.next_generation(first);
+
+        // The newer build finishes first and is accepted. The older one finishing afterwards must still be discarded.
+        assert!(!running.is_outdated(first, newer));
+        assert!(running.is_outdated(first, older));
+        assert!(!running.is_outdated(second, other));
+
+        // A build of a navmesh that was never started is not outdated.
+        assert!(!running.is_outdated(id(3), 0));
+    }
+
+    #[test]
+    fn agents_get_navmeshes_of_their_own_size() {
+        use crate::navmeshes::AgentProfile;
+
+        /// The floor with a ceiling 1.5 units above its half with x > 1.
+        fn floor_under_ceiling() -> Vec<(GlobalTransform, TriMesh)> {
+            let ceiling = TriMesh::from_parts(
+                vec![
+                    Vec3::new(1.0, 1.5, -5.0).into(),
+                    Vec3::new(1.0, 1.5, 5.0).into(),
+                    Vec3::new(5.0, 1.5, 5.0).into(),
+                    Vec3::new(5.0, 1.5, -5.0).into(),
+                ],
+                vec![glam::UVec3::new(0, 1, 2), glam::UVec3::new(0, 2, 3)],
+            )
+            .unwrap();
+            let mut input = floor();
+            input.push((GlobalTransform::IDENTITY, ceiling));
+            input
+        }
+        /// The lowest and highest x of the navmesh on the floor, leaving out the top of the ceiling.
+        fn floor_extent(navmesh: &Navmesh) -> (f32, f32) {
+            let polygon = navmesh.polygon();
+            polygon
+                .polygon_ids()
+                .flat_map(|id| polygon.polygon_vertices(id))
+                .map(|vertex| polygon.vertex_world(vertex))
+                .filter(|vertex| vertex.y < 0.5)
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), vertex| {
+                    (min.min(vertex.x), max.max(vertex.x))
+                })
+        }
+
+        let small = AgentProfile::new(0.2, 1.0);
+        let large = AgentProfile::new(1.0, 2.5);
+        let mut app = navmesh_app();
+        app.set_navmesh_affector_backend(floor_under_ceiling);
+        let mut profiles = app.world_mut().resource_mut::<AgentProfiles>();
+        profiles.insert("small", small);
+        profiles.insert("large", large);
+        let handles = generate(&mut app, |generator| generator.generate_for_agents());
+        let registry = app.world().resource::<Navmeshes>();
+        assert_eq!(
+            [registry.get("large"), registry.get("small")],
+            [Some(&handles[0]), Some(&handles[1])]
+        );
+
+        for (handle, profile) in handles.iter().zip([large, small]) {
+            let mut config = rerecast::NavmeshConfigBuilder::default();
+            profile.apply(&mut config);
+            let expected = generate(&mut app, move |generator| {
+                generator.generate(config.build())
+            });
+            assert_eq!(built(&app, handle), built(&app, &expected));
+        }
+
+        let (large_min, large_max) = floor_extent(&built(&app, &handles[0]));
+        let (small_min, small_max) = floor_extent(&built(&app, &handles[1]));
+        // The larger radius keeps the navmesh further away from the edge of the floor.
+        assert!(small_min < large_min - 0.5, "{small_min} {large_min}");
+        // Only the smaller agent fits under the ceiling.
+        assert!(small_max > 3.0, "{small_max}");
+        assert!(large_max < 1.0, "{large_max}");
+    }
+
+    #[test]
+    fn spawn_order_does_not_change_the_navmesh() {
+        #[derive(Component)]
+        struct Quad(TriMesh);
+
+        fn quads(
+            quads: Query<(Entity, &GlobalTransform, &Quad)>,
+        ) -> Vec<(Entity, GlobalTransform, TriMesh)> {
+            quads
+                .iter()
+                .map(|(entity, transform, quad)| (entity, *transform, quad.0.clone()))
+                .collect()
+        }
+        fn quad(min_x: f32, max_x: f32) -> Quad {
+            let trimesh = TriMesh::from_parts(
+                vec![
+                    Vec3::new(min_x, 0.0, -5.0).into(),
+                    Vec3::new(min_x, 0.0, 5.0).into(),
+                    Vec3::new(max_x, 0.0, 5.0).into(),
+                    Vec3::new(max_x, 0.0, -5.0).into(),
+                ],
+                vec![glam::UVec3::new(0, 1, 2), glam::UVec3::new(0, 2, 3)],
+            )
+            .unwrap();
+            Quad(trimesh)
+        }
+        fn volume(min_x: f32, max_x: f32, area: AreaType) -> NavmeshVolume {
+            NavmeshVolume::new(
+                [
+                    Vec2::new(min_x, -6.0),
+                    Vec2::new(min_x, 6.0),
+                    Vec2::new(max_x, 6.0),
+                    Vec2::new(max_x, -6.0),
+                ],
+                -1.0,
+                1.0,
+                area,
+            )
+        }
+
+        // Overlapping floors and volumes with different areas, so that their order decides which area wins.
+        let spawn = |order: [usize; 4]| {
+            let mut app = navmesh_app();
+            app.add_navmesh_affector_backend("quads", quads);
+            let entities = [(); 4].map(|_| app.world_mut().spawn_empty().id());
+            for index in order {
+                let mut entity = app.world_mut().entity_mut(entities[index]);
+                entity.insert(GlobalTransform::IDENTITY);
+                match index {
+                    0 => entity.insert((quad(-5.0, 1.0), NavmeshAffectorArea(AreaType(3)))),
+                    1 => entity.insert((quad(-1.0, 5.0), NavmeshAffectorArea(AreaType(4)))),
+                    2 => entity.insert(volume(-3.0, 2.0, AreaType(5))),
+                    _ => entity.insert(volume(-2.0, 3.0, AreaType(6))),
+                };
+            }
+            let config = rerecast::NavmeshConfigBuilder::default().build();
+            let handle = generate(&mut app, move |generator| generator.generate(config));
+            built(&app, &handle)
+        };
+        let navmesh = spawn([0, 1, 2, 3]);
+        assert_eq!(spawn([3, 2, 1, 0]), navmesh);
+        assert_eq!(spawn([1, 3, 0, 2]), navmesh);
+    }
+
+    #[test]
+    fn generations_are_forgotten_once_no_build_is_in_flight() {
+        let mut app = navmesh_app();
+        app.set_navmesh_affector_backend(floor);
+        let config = rerecast::NavmeshConfigBuilder::default().build();
+        let handles = generate(&mut app, move |generator| {
+            [generator.generate(config), generator.generate(config)]
+        });
+        for handle in &handles {
+            built(&app, handle);
+        }
+        assert!(
+            app.world()
+                .resource::<RunningBuilds>()
+                .generations
+                .is_empty()
+        );
+
+        let handle = generate(&mut app, move |generator| {
+            generator.generate_stepped(config)
+        });
+        let generations = &app.world().resource::<RunningBuilds>().generations;
+        assert_eq!(generations.keys().collect::<Vec<_>>(), [&handle.id()]);
+        while app
+            .world()
+            .resource::<SteppedNavmeshBuilds>()
+            .get(&handle)
+            .is_some()
+        {
+            let step = handle.clone();
+            generate(&mut app, move |generator| generator.step(&step));
+        }
+        assert!(
+            app.world()
+                .resource::<RunningBuilds>()
+                .generations
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn backends_mark_their_affectors_with_their_own_areas() {
+        const WATER: AreaType = AreaType(7);
+        fn quad(entity: Entity) -> Vec<(Entity, GlobalTransform, TriMesh)> {
+            let trimesh = TriMesh::from_parts(
+                vec![
+                    Vec3::ZERO.into(),
+                    Vec3::X.into(),
+                    Vec3::Z.into(),
+                    Vec3::new(1.0, 0.0, 1.0).into(),
+                ],
+                vec![glam::UVec3::new(0, 2, 1), glam::UVec3::new(1, 2, 3)],
+            )
+            .unwrap();
+            vec![(entity, GlobalTransform::IDENTITY, trimesh)]
+        }
+
+        let mut app = App::new();
+        let ground = app.world_mut().spawn_empty().id();
+        let lake = app.world_mut().spawn_empty().id();
+        app.insert_resource(NavmeshAffectorAreas {
+            walkable_area: AreaType(3),
+            mark_by_slope: true,
+        });
+        app.add_navmesh_affector_backend("ground", move || quad(ground));
+        app.add_navmesh_affector_backend_with_areas(
+            "lake",
+            move || quad(lake),
+            NavmeshAffectorAreas {
+                walkable_area: WATER,
+                mark_by_slope: false,
+            },
+        );
+
+        let input = collect_affectors(app.world_mut()).unwrap();
+        let areas = |entity| {
+            let affector = input
+                .affectors
+                .iter()
+                .find(|affector| affector.entity == entity)
+                .unwrap();
+            affector.marked(0.5).area_types
+        };
+        assert_eq!(areas(ground), vec![AreaType(3); 2]);
+        assert_eq!(areas(lake), vec![WATER; 2]);
+    }
+
+    #[test]
+    fn backends_without_entities_are_collected() {
+        fn backend() -> Vec<(GlobalTransform, TriMesh)> {
+            let trimesh = TriMesh::from_parts(
+                vec![Vec3::ZERO.into(), Vec3::X.into(), Vec3::Z.into()],
+                vec![glam::UVec3::new(0, 2, 1)],
+            )
+            .unwrap();
+            vec![
+                (GlobalTransform::IDENTITY, trimesh.clone()),
+                (GlobalTransform::IDENTITY, trimesh),
+            ]
+        }
+
+        let mut app = App::new();
+        app.set_navmesh_affector_backend(backend);
+
+        let input = collect_affectors(app.world_mut()).unwrap();
+        assert_eq!(input.affectors.len(), 2);
+        assert!(
+            input
+                .affectors
+                .iter()
+                .all(|affector| affector.entity == Entity::PLACEHOLDER && !affector.obstruction)
+        );
+    }
+
+    #[test]
+    fn worlds_without_affectors_have_no_input_geometry() {
+        let mut app = App::new();
+        let input = collect_affectors(app.world_mut()).unwrap();
+        assert!(matches!(
+            input.check_geometry(),
+            Err(CollectInputError::NoInputGeometry { backends }) if backends.is_empty()
+        ));
+
+        app.add_navmesh_affector_backend("nothing", Vec::<(Entity, GlobalTransform, TriMesh)>::new);
+        let input = collect_affectors(app.world_mut()).unwrap();
+        let Err(CollectInputError::NoInputGeometry { backends }) = input.check_geometry() else {
+            panic!("Expected no input geometry");
+        };
+        assert_eq!(
+            backends,
+            [BackendMatches {
+                name: "nothing".into(),
+                entities: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn queue_pops_by_priority_then_order() {
+        let mut queue = NavmeshQueue::default();
+        queue.push_back(queued(1, NavmeshPriority::Background));
+        queue.push_back(queued(2, NavmeshPriority::Normal));
+        queue.push_back(queued(3, NavmeshPriority::Critical));
+        queue.push_back(queued(4, NavmeshPriority::Normal));
+        assert_eq!(
+            queue.most_urgent_priority(),
+            Some(NavmeshPriority::Critical)
+        );
+
+        let order = std::iter::from_fn(|| queue.pop_most_urgent())
+            .map(|queued| queued.handle.id())
+            .collect::<Vec<_>>();
+        let expected = [3, 2, 4, 1].map(id);
+        assert_eq!(order, expected);
+        assert_eq!(queue.most_urgent_priority(), None);
+    }
+
+    #[test]
+    fn build_limit_keeps_a_slot_for_urgent_builds() {
+        let limit = NavmeshBuildLimit {
+            max_concurrent_builds: 3,
+        };
+        assert!(limit.allows(NavmeshPriority::Background, 1));
+        assert!(!limit.allows(NavmeshPriority::Background, 2));
+        assert!(limit.allows(NavmeshPriority::Normal, 2));
+        assert!(limit.allows(NavmeshPriority::Critical, 2));
+        assert!(!limit.allows(NavmeshPriority::Critical, 3));
+
+        // With a single slot, background builds may still take it.
+        let limit = NavmeshBuildLimit {
+            max_concurrent_builds: 0,
+        };
+        assert!(limit.allows(NavmeshPriority::Background, 0));
+        assert!(!limit.allows(NavmeshPriority::Normal, 1));
+    }
+}
@@ -0,0 +1,513 @@
+//! Rasterizing the geometry of navmesh generations on the GPU.
+//!
+//! Rasterization is usually the most expensive step when generating navmeshes for levels with many triangles.
+//! Insert [`GpuRasterization`] to run it in a compute shader instead of on the CPU.
+//! The rest of the pipeline still runs on the CPU and behaves exactly the same.
+
+use async_channel::Receiver;
+use bevy_ecs::prelude::*;
+use bevy_render::{
+    render_resource::{
+        BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, Buffer, BufferAsyncError,
+        BufferDescriptor, BufferInitDescriptor, BufferUsages, CommandEncoderDescriptor,
+        ComputePassDescriptor, ComputePipeline, Maintain, MapMode, PipelineCompilationOptions,
+        PipelineLayoutDescriptor, RawComputePipelineDescriptor, ShaderModuleDescriptor,
+        ShaderSource, ShaderStages,
+        binding_types::{
+            storage_buffer_read_only_sized, storage_buffer_sized, uniform_buffer_sized,
+        },
+    },
+    renderer::{RenderDevice, RenderQueue},
+};
+use rerecast::{
+    Heightfield, HeightfieldBuilder, NavmeshConfig, RasterizationError, RecordedSpans, TriMesh,
+};
+use thiserror::Error;
+
+/// Insert this resource to rasterize the geometry of every navmesh generation on the GPU.
+///
+/// The triangles are voxelized by a compute shader and read back into [`RecordedSpans`],
+/// which are then inserted into the heightfield in the same order as by the CPU rasterizer.
+/// Whenever the GPU cannot be used, e.g. because no [`RenderDevice`] exists or the device does not support
+/// large enough buffers, the geometry is rasterized on the CPU instead.
+///
+/// Floating point operations may be rounded slightly differently on the GPU, which can move the edges of single spans.
+/// Enable [`GpuRasterization::validate`] to check the results against the CPU rasterizer for your hardware and levels.
+///
+/// The spans are read back asynchronously by the build task, so the app never waits for the GPU.
+#[derive(Resource, Default)]
+pub struct GpuRasterization {
+    /// Whether to also rasterize every generation on the CPU and fall back to its spans if they differ from the GPU's.
+    /// This defeats the purpose of rasterizing on the GPU, so only enable it while testing. Defaults to `false`.
+    pub validate: bool,
+    /// Generations with fewer triangles than this are rasterized on the CPU,
+    /// as uploading and reading back small meshes takes longer than rasterizing them directly. Defaults to `0`.
+    pub min_triangles: usize,
+    pipeline: Option<RasterizationPipeline>,
+    warned_unavailable: bool,
+}
+
+impl GpuRasterization {
+    /// Rasterizes on the GPU without validation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts rasterizing the triangles of `trimesh` on the GPU for a heightfield with the same layout as `heightfield`,
+    /// like [`Heightfield::record_triangles`]. The heightfield itself is left untouched.
+    ///
+    /// Await [`PendingSpans::read`] for the spans. They only arrive once the device is polled,
+    /// which the app does every frame while navmeshes are being built.
+    pub fn record_triangles(
+        &mut self,
+        device: &RenderDevice,
+        queue: &RenderQueue,
+        heightfield: &Heightfield,
+        trimesh: &TriMesh,
+    ) -> Result<PendingSpans, GpuRasterizationError> {
+        let recorded = RecordedSpans::new(heightfield);
+        let validation = self
+            .validate
+            .then(|| (heightfield.clone(), trimesh.clone()));
+        if trimesh.indices.is_empty() {
+            return Ok(PendingSpans {
+                readback: None,
+                recorded,
+                capacity: 0,
+                validation,
+            });
+        }
+        let capacity = span_capacity(heightfield, trimesh);
+        let max_capacity = device.limits().max_storage_buffer_binding_size as u64 / SPAN_SIZE;
+        if capacity > max_capacity {
+            return Err(GpuRasterizationError::TooManySpans(capacity));
+        }
+        let pipeline = self
+            .pipeline
+            .get_or_insert_with(|| RasterizationPipeline::new(device));
+
+        let params = [
+            heightfield.aabb.min.x.to_le_bytes(),
+            heightfield.aabb.min.y.to_le_bytes(),
+            heightfield.aabb.min.z.to_le_bytes(),
+            heightfield.cell_size.to_le_bytes(),
+            heightfield.aabb.max.x.to_le_bytes(),
+            heightfield.aabb.max.y.to_le_bytes(),
+            heightfield.aabb.max.z.to_le_bytes(),
+            heightfield.cell_height.to_le_bytes(),
+            (1.0 / heightfield.cell_size).to_le_bytes(),
+            (1.0 / heightfield.cell_height).to_le_bytes(),
+            0.0_f32.to_le_bytes(),
+            0.0_f32.to_le_bytes(),
+            u32::from(heightfield.width).to_le_bytes(),
+            u32::from(heightfield.height).to_le_bytes(),
+            (trimesh.indices.len() as u32).to_le_bytes(),
+            (capacity as u32).to_le_bytes(),
+        ]
+        .concat();
+        let triangles = trimesh
+            .indices
+            .iter()
+            .flat_map(|triangle| triangle.to_array())
+            .flat_map(|index| trimesh.vertices[index as usize].to_array())
+            .flat_map(f32::to_le_bytes)
+            .collect::<Vec<_>>();
+        let areas = trimesh
+            .area_types
+            .iter()
+            .flat_map(|area| u32::from(area.0).to_le_bytes())
+            .collect::<Vec<_>>();
+
+        let params = device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("rerecast_rasterization_params"),
+            contents: &params,
+            usage: BufferUsages::UNIFORM,
+        });
+        let triangles = device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("rerecast_rasterization_triangles"),
+            contents: &triangles,
+            usage: BufferUsages::STORAGE,
+        });
+        let areas = device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("rerecast_rasterization_areas"),
+            contents: &areas,
+            usage: BufferUsages::STORAGE,
+        });
+        let span_count = device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("rerecast_rasterization_span_count"),
+            contents: &0_u32.to_le_bytes(),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+        });
+        // Bindings must not be empty, even if no triangle touches the heightfield.
+        let spans_size = capacity.max(1) * SPAN_SIZE;
+        let spans = device.create_buffer(&BufferDescriptor {
+            label: Some("rerecast_rasterization_spans"),
+            size: spans_size,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = device.create_buffer(&BufferDescriptor {
+            label: Some("rerecast_rasterization_readback"),
+            size: 4 + spans_size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(
+            "rerecast_rasterization_bind_group",
+            &pipeline.layout,
+            &BindGroupEntries::sequential((
+                params.as_entire_binding(),
+                triangles.as_entire_binding(),
+                areas.as_entire_binding(),
+                span_count.as_entire_binding(),
+                spans.as_entire_binding(),
+            )),
+        );
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("rerecast_rasterization"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("rerecast_rasterization"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&pipeline.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            let workgroups = (trimesh.indices.len() as u32).div_ceil(WORKGROUP_SIZE);
+            let workgroups_x = workgroups.min(MAX_WORKGROUPS_PER_DIMENSION);
+            pass.dispatch_workgroups(workgroups_x, workgroups.div_ceil(workgroups_x), 1);
+        }
+        encoder.copy_buffer_to_buffer(&span_count, 0, &readback, 0, 4);
+        encoder.copy_buffer_to_buffer(&spans, 0, &readback, 4, spans_size);
+        queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = async_channel::bounded(1);
+        readback.slice(..).map_async(MapMode::Read, move |result| {
+            // The receiver is only dropped if the build was abandoned.
+            let _ = sender.try_send(result);
+        });
+        Ok(PendingSpans {
+            readback: Some((readback, receiver)),
+            recorded,
+            capacity,
+            validation,
+        })
+    }
+}
+
+/// Spans that are being rasterized on the GPU, started by [`GpuRasterization::record_triangles`].
+pub struct PendingSpans {
+    readback: Option<(Buffer, Receiver<Result<(), BufferAsyncError>>)>,
+    recorded: RecordedSpans,
+    capacity: u64,
+    validation: Option<(Heightfield, TriMesh)>,
+}
+
+impl PendingSpans {
+    /// Waits until the GPU is done and reads back the spans.
+    pub async fn read(self) -> Result<RecordedSpans, GpuRasterizationError> {
+        let mut recorded = self.recorded;
+        if let Some((readback, receiver)) = self.readback {
+            receiver
+                .recv()
+                .await
+                .map_err(|_| GpuRasterizationError::Readback(None))?
+                .map_err(|err| GpuRasterizationError::Readback(Some(err)))?;
+            let words = readback
+                .slice(..)
+                .get_mapped_range()
+                .chunks_exact(4)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect::<Vec<_>>();
+            readback.unmap();
+            decode_spans(&words, self.capacity, &mut recorded)?;
+        }
+        if let Some((heightfield, trimesh)) = self.validation
+            && recorded != heightfield.record_triangles(&trimesh)?
+        {
+            return Err(GpuRasterizationError::ValidationMismatch);
+        }
+        Ok(recorded)
+    }
+}
+
+/// Pushes the spans read back from the GPU, i.e. their count followed by the spans themselves, into `recorded`.
+fn decode_spans(
+    words: &[u32],
+    capacity: u64,
+    recorded: &mut RecordedSpans,
+) -> Result<(), GpuRasterizationError> {
+    let count = u64::from(words[0]);
+    if count > capacity {
+        return Err(GpuRasterizationError::TooManySpans(count));
+    }
+    let mut spans = words[1..]
+        .chunks_exact(4)
+        .take(count as usize)
+        .collect::<Vec<_>>();
+    // Invocations finish in any order, but the CPU inserts spans triangle by triangle, row by row.
+    spans.sort_unstable_by_key(|span| (span[0], span[1] >> 16, span[1] & 0xffff));
+    for span in spans {
+        let [_triangle, xz, min_max, area] = [span[0], span[1], span[2], span[3]];
+        recorded.push(
+            xz as u16,
+            (xz >> 16) as u16,
+            min_max as u16,
+            (min_max >> 16) as u16,
+            rerecast::AreaType(area as u8),
+        );
+    }
+    Ok(())
+}
+
+/// Errors that can occur when rasterizing with [`GpuRasterization::record_triangles`].
+#[derive(Error, Debug)]
+pub enum GpuRasterizationError {
+    /// Happens when the spans could exceed the largest storage buffer supported by the device.
+    #[error(
+        "Up to {0} spans could be rasterized, which exceeds the storage buffer size of the device."
+    )]
+    TooManySpans(u64),
+    /// Happens when the spans could not be read back from the GPU.
+    #[error("Failed to read back the rasterized spans from the GPU: {0:?}")]
+    Readback(Option<BufferAsyncError>),
+    /// Happens when [`GpuRasterization::validate`] is enabled and the spans differ from the CPU's.
+    #[error("The spans rasterized on the GPU differ from the ones rasterized on the CPU.")]
+    ValidationMismatch,
+    /// Happens when [`GpuRasterization::validate`] is enabled and rasterizing on the CPU fails.
+    #[error("Failed to rasterize triangles on the CPU for validation: {0}")]
+    Validation(#[from] RasterizationError),
+}
+
+/// The size of a span written by the shader. `[Units: bytes]`
+const SPAN_SIZE: u64 = 16;
+/// Must match the workgroup size of the shader.
+const WORKGROUP_SIZE: u32 = 64;
+const MAX_WORKGROUPS_PER_DIMENSION: u32 = 65535;
+
+struct RasterizationPipeline {
+    layout: BindGroupLayout,
+    pipeline: ComputePipeline,
+}
+
+impl RasterizationPipeline {
+    fn new(device: &RenderDevice) -> Self {
+        let module = device.create_and_validate_shader_module(ShaderModuleDescriptor {
+            label: Some("rerecast_rasterization"),
+            source: ShaderSource::Wgsl(include_str!("gpu_rasterization.wgsl").into()),
+        });
+        let layout = device.create_bind_group_layout(
+            "rerecast_rasterization_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    uniform_buffer_sized(false, None),
+                    storage_buffer_read_only_sized(false, None),
+                    storage_buffer_read_only_sized(false, None),
+                    storage_buffer_sized(false, None),
+                    storage_buffer_sized(false, None),
+                ),
+            ),
+        );
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("rerecast_rasterization"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&RawComputePipelineDescriptor {
+            label: Some("rerecast_rasterization"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some("rasterize"),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+        Self { layout, pipeline }
+    }
+}
+
+/// An upper bound for the number of spans the triangles of `trimesh` produce,
+/// i.e. the number of columns covered by their bounding boxes, plus one column of slack on every side.
+fn span_capacity(heightfield: &Heightfield, trimesh: &TriMesh) -> u64 {
+    let inverse_cell_size = 1.0 / heightfield.cell_size;
+    let cells = |min: f32, max: f32, origin: f32, count: u16| {
+        let first = ((min - origin) * inverse_cell_size).floor().max(-1.0);
+        let last = ((max - origin) * inverse_cell_size)
+            .ceil()
+            .min(count as f32);
+        (last - first + 2.0).max(0.0) as u64
+    };
+    trimesh
+        .indices
+        .iter()
+        .map(|triangle| {
+            let [a, b, c] = triangle
+                .to_array()
+                .map(|index| trimesh.vertices[index as usize]);
+            let (min, max) = (a.min(b).min(c), a.max(b).max(c));
+            cells(min.x, max.x, heightfield.aabb.min.x, heightfield.width)
+                * cells(min.z, max.z, heightfield.aabb.min.z, heightfield.height)
+        })
+        .sum()
+}
+
+/// Starts rasterizing the geometry of a generation with `config` on the GPU if [`GpuRasterization`] is enabled.
+/// Returns `None` if the geometry should be rasterized on the CPU instead.
+pub(crate) fn record_spans(
+    world: &mut World,
+    trimesh: &TriMesh,
+    config: &NavmeshConfig,
+) -> Option<PendingSpans> {
+    let device = world.get_resource::<RenderDevice>().cloned();
+    let queue = world.get_resource::<RenderQueue>().cloned();
+    let mut gpu = world.get_resource_mut::<GpuRasterization>()?;
+    if trimesh.indices.len() < gpu.min_triangles {
+        return None;
+    }
+    let (Some(device), Some(queue)) = (device, queue) else {
+        if !gpu.warned_unavailable {
+            tracing::warn!(
+                "GPU rasterization is enabled, but there is no render device. Rasterizing on the CPU instead."
+            );
+            gpu.warned_unavailable = true;
+        }
+        return None;
+    };
+    // Errors are reported by the CPU path, which fails the same way.
+    let heightfield = HeightfieldBuilder {
+        aabb: config.aabb,
+        cell_size: config.cell_size,
+        cell_height: config.cell_height,
+    }
+    .build()
+    .ok()?;
+    match gpu.record_triangles(&device, &queue, &heightfield, trimesh) {
+        Ok(pending) => Some(pending),
+        Err(err) => {
+            tracing::warn!("Failed to rasterize on the GPU, rasterizing on the CPU instead: {err}");
+            None
+        }
+    }
+}
+
+/// Lets the GPU finish the readbacks of [`PendingSpans`] without blocking.
+pub(crate) fn poll_readbacks(world: &World) {
+    if world.contains_resource::<GpuRasterization>()
+        && let Some(device) = world.get_resource::<RenderDevice>()
+    {
+        device.poll(Maintain::Poll);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bevy_render::renderer::WgpuWrapper;
+    use bevy_tasks::block_on;
+    use glam::{UVec3, Vec3A};
+    use rerecast::{Aabb3d, AreaType};
+
+    use super::*;
+
+    /// A floor, a ramp leaning against it and a ledge above both, partly outside of the heightfield.
+    fn scene() -> (Heightfield, TriMesh) {
+        let heightfield = HeightfieldBuilder {
+            aabb: Aabb3d::new([0.0, 0.0, 0.0], [4.0, 3.0, 4.0]),
+            cell_size: 0.25,
+            cell_height: 0.1,
+        }
+        .build()
+        .unwrap();
+        let vertices = [
+            [0.0, 0.0, 0.0],
+            [4.0, 0.0, 0.0],
+            [0.0, 0.0, 4.0],
+            [4.0, 0.0, 4.0],
+            [1.0, 0.0, 1.0],
+            [3.0, 1.3, 1.0],
+            [1.0, 0.0, 3.0],
+            [3.0, 1.3, 3.0],
+            [2.5, 2.0, 2.5],
+            [5.0, 2.0, 2.5],
+            [2.5, 2.0, 5.0],
+        ];
+        let mut trimesh = TriMesh::from_parts(
+            vertices.into_iter().map(Vec3A::from).collect(),
+            vec![
+                UVec3::new(0, 2, 1),
+                UVec3::new(1, 2, 3),
+                UVec3::new(4, 6, 5),
+                UVec3::new(5, 6, 7),
+                UVec3::new(8, 10, 9),
+            ],
+        )
+        .unwrap();
+        trimesh.area_types = vec![
+            AreaType::DEFAULT_WALKABLE,
+            AreaType::DEFAULT_WALKABLE,
+            AreaType(3),
+            AreaType(3),
+            AreaType::NOT_WALKABLE,
+        ];
+        (heightfield, trimesh)
+    }
+
+    /// Returns `None` on machines without a GPU, e.g. in CI.
+    fn device() -> Option<(RenderDevice, RenderQueue)> {
+        let instance = wgpu::Instance::default();
+        let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+        let (device, queue) =
+            block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).ok()?;
+        Some((
+            RenderDevice::from(device),
+            RenderQueue(Arc::new(WgpuWrapper::new(queue))),
+        ))
+    }
+
+    #[test]
+    fn gpu_rasterizes_the_same_spans_as_the_cpu() {
+        let Some((device, queue)) = device() else {
+            eprintln!("Skipping the comparison of GPU and CPU rasterization, as there is no GPU.");
+            return;
+        };
+        let (heightfield, trimesh) = scene();
+        let pending = GpuRasterization::new()
+            .record_triangles(&device, &queue, &heightfield, &trimesh)
+            .unwrap();
+        // The spans arrive asynchronously, so this only needs to poll until the GPU is done.
+        device.poll(Maintain::Wait);
+        let gpu = block_on(pending.read()).unwrap();
+        assert_eq!(gpu, heightfield.record_triangles(&trimesh).unwrap());
+    }
+
+    #[test]
+    fn read_back_spans_are_inserted_in_triangle_and_row_order() {
+        let (heightfield, _) = scene();
+        let span =
+            |triangle: u32, x: u32, z: u32, area: u32| [triangle, x | z << 16, 3 | 5 << 16, area];
+        let words = [
+            [3].as_slice(),
+            &span(1, 0, 0, 7),
+            &span(0, 2, 1, 3),
+            &span(0, 1, 1, 3),
+            // Beyond the count, e.g. left over from an earlier dispatch.
+            &span(0, 0, 0, 9),
+        ]
+        .concat();
+        let mut recorded = RecordedSpans::new(&heightfield);
+        decode_spans(&words, 8, &mut recorded).unwrap();
+
+        let mut expected = RecordedSpans::new(&heightfield);
+        expected.push(1, 1, 3, 5, AreaType(3));
+        expected.push(2, 1, 3, 5, AreaType(3));
+        expected.push(0, 0, 3, 5, AreaType(7));
+        assert_eq!(recorded, expected);
+
+        assert!(matches!(
+            decode_spans(&words, 2, &mut RecordedSpans::new(&heightfield)),
+            Err(GpuRasterizationError::TooManySpans(3))
+        ));
+    }
+}
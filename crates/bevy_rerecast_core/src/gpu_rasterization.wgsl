@@ -0,0 +1,255 @@
+// Rasterizes triangles into the spans of a heightfield, with one invocation per triangle.
+// This mirrors `RasterGrid::rasterize_triangle` of rerecast step by step, see there for details.
+
+struct Params {
+    // xyz: minimum of the heightfield AABB, w: cell size
+    aabb_min: vec4<f32>,
+    // xyz: maximum of the heightfield AABB, w: cell height
+    aabb_max: vec4<f32>,
+    // x: inverse cell size, y: inverse cell height. Computed on the CPU so that they are rounded the same way.
+    inverse_cell: vec4<f32>,
+    // x: width, y: height, z: triangle count, w: span capacity
+    counts: vec4<u32>,
+}
+
+struct OutSpan {
+    triangle: u32,
+    // x in the low 16 bits, z in the high 16 bits
+    xz: u32,
+    // min in the low 16 bits, max in the high 16 bits
+    min_max: u32,
+    area: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Nine floats per triangle
+@group(0) @binding(1) var<storage, read> triangles: array<f32>;
+@group(0) @binding(2) var<storage, read> areas: array<u32>;
+@group(0) @binding(3) var<storage, read_write> span_count: atomic<u32>;
+@group(0) @binding(4) var<storage, read_write> spans: array<OutSpan>;
+
+const WORKGROUP_SIZE: u32 = 64u;
+const MAX_VERTICES: u32 = 12u;
+const MAX_HEIGHT: i32 = 65535;
+const AXIS_X: u32 = 0u;
+const AXIS_Z: u32 = 2u;
+
+struct Polygon {
+    vertices: array<vec3<f32>, 12>,
+    count: u32,
+}
+
+struct Division {
+    // The part of the polygon at or below the dividing line
+    below: Polygon,
+    // The part of the polygon at or above the dividing line
+    above: Polygon,
+}
+
+fn axis_value(vertex: vec3<f32>, axis: u32) -> f32 {
+    if axis == AXIS_X {
+        return vertex.x;
+    }
+    return vertex.z;
+}
+
+fn push_vertex(polygon: ptr<function, Polygon>, vertex: vec3<f32>) {
+    if (*polygon).count < MAX_VERTICES {
+        (*polygon).vertices[(*polygon).count] = vertex;
+    }
+    (*polygon).count += 1u;
+}
+
+// Divides a convex polygon into two convex polygons across a separating axis, like `divide_poly`.
+fn divide_polygon(polygon: Polygon, offset: f32, axis: u32) -> Division {
+    var input = polygon;
+    var below: Polygon;
+    var above: Polygon;
+    below.count = 0u;
+    above.count = 0u;
+    let count = min(input.count, MAX_VERTICES);
+    if count == 0u {
+        return Division(below, above);
+    }
+
+    var delta: array<f32, 12>;
+    for (var i = 0u; i < count; i++) {
+        delta[i] = offset - axis_value(input.vertices[i], axis);
+    }
+
+    var b = count - 1u;
+    for (var a = 0u; a < count; a++) {
+        let same_side = (delta[a] >= 0.0) == (delta[b] >= 0.0);
+        if !same_side {
+            let s = delta[b] / (delta[b] - delta[a]);
+            let vertex = input.vertices[b] + (input.vertices[a] - input.vertices[b]) * s;
+            push_vertex(&below, vertex);
+            push_vertex(&above, vertex);
+            // Points on the dividing line were already added above.
+            if delta[a] > 0.0 {
+                push_vertex(&below, input.vertices[a]);
+            } else if delta[a] < 0.0 {
+                push_vertex(&above, input.vertices[a]);
+            }
+        } else {
+            if delta[a] >= 0.0 {
+                push_vertex(&below, input.vertices[a]);
+                if delta[a] != 0.0 {
+                    b = a;
+                    continue;
+                }
+            }
+            push_vertex(&above, input.vertices[a]);
+        }
+        b = a;
+    }
+    return Division(below, above);
+}
+
+// Clips the polygon against the cells of the grid on the xz-plane, like `RasterGrid::clip_to_grid`.
+fn clip_to_grid(polygon: Polygon, grid_max: vec2<f32>) -> Polygon {
+    var clipped = polygon;
+    let offsets = array<f32, 4>(params.aabb_min.x, grid_max.x, params.aabb_min.z, grid_max.y);
+    let axes = array<u32, 4>(AXIS_X, AXIS_X, AXIS_Z, AXIS_Z);
+    for (var bound = 0u; bound < 4u; bound++) {
+        let offset = offsets[bound];
+        let axis = axes[bound];
+        let keep_below = bound % 2u == 1u;
+        var crosses = false;
+        for (var i = 0u; i < min(clipped.count, MAX_VERTICES); i++) {
+            let value = axis_value(clipped.vertices[i], axis);
+            crosses = crosses || select(value < offset, value > offset, keep_below);
+        }
+        if !crosses {
+            continue;
+        }
+        let division = divide_polygon(clipped, offset, axis);
+        if keep_below {
+            clipped = division.below;
+        } else {
+            clipped = division.above;
+        }
+        if clipped.count < 3u {
+            break;
+        }
+    }
+    return clipped;
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn rasterize(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) workgroups: vec3<u32>,
+) {
+    let triangle = id.x + id.y * workgroups.x * WORKGROUP_SIZE;
+    if triangle >= params.counts.z {
+        return;
+    }
+
+    var in_triangle: Polygon;
+    in_triangle.count = 3u;
+    for (var i = 0u; i < 3u; i++) {
+        let base = triangle * 9u + i * 3u;
+        in_triangle.vertices[i] = vec3(triangles[base], triangles[base + 1u], triangles[base + 2u]);
+    }
+    let triangle_min = min(min(in_triangle.vertices[0], in_triangle.vertices[1]), in_triangle.vertices[2]);
+    let triangle_max = max(max(in_triangle.vertices[0], in_triangle.vertices[1]), in_triangle.vertices[2]);
+
+    let aabb_min = params.aabb_min.xyz;
+    let aabb_max = params.aabb_max.xyz;
+    // If the triangle does not touch the bounding box of the heightfield, skip the triangle.
+    if any(aabb_min > triangle_max) || any(aabb_max < triangle_min) {
+        return;
+    }
+
+    let cell_size = params.aabb_min.w;
+    let inverse_cell_size = params.inverse_cell.x;
+    let inverse_cell_height = params.inverse_cell.y;
+    let w = i32(params.counts.x);
+    let h = i32(params.counts.y);
+    let by = aabb_max.y - aabb_min.y;
+    let grid_max = aabb_min.xz + vec2(f32(params.counts.x), f32(params.counts.y)) * cell_size;
+
+    var min_z = triangle_min.z;
+    var max_z = triangle_max.z;
+    let inside = all(triangle_min.xz >= aabb_min.xz) && all(triangle_max.xz <= grid_max);
+    if !inside {
+        in_triangle = clip_to_grid(in_triangle, grid_max);
+        if in_triangle.count < 3u {
+            return;
+        }
+        min_z = 3.40282347e+38;
+        max_z = -3.40282347e+38;
+        for (var i = 0u; i < min(in_triangle.count, MAX_VERTICES); i++) {
+            min_z = min(min_z, in_triangle.vertices[i].z);
+            max_z = max(max_z, in_triangle.vertices[i].z);
+        }
+    }
+
+    // Use -1 rather than 0 to cut the polygon properly at the start of the tile.
+    let z0 = clamp(i32((min_z - aabb_min.z) * inverse_cell_size), -1, h - 1);
+    let z1 = clamp(i32((max_z - aabb_min.z) * inverse_cell_size), 0, h - 1);
+
+    for (var z = z0; z <= z1; z++) {
+        // Clip the polygon to the row and keep the rest for the next rows.
+        let cell_z = aabb_min.z + f32(z) * cell_size;
+        let row_division = divide_polygon(in_triangle, cell_z + cell_size, AXIS_Z);
+        var in_row = row_division.below;
+        in_triangle = row_division.above;
+        if in_row.count < 3u || z < 0 {
+            continue;
+        }
+
+        var min_x = in_row.vertices[0].x;
+        var max_x = in_row.vertices[0].x;
+        for (var i = 1u; i < min(in_row.count, MAX_VERTICES); i++) {
+            min_x = min(min_x, in_row.vertices[i].x);
+            max_x = max(max_x, in_row.vertices[i].x);
+        }
+        let x0 = i32((min_x - aabb_min.x) * inverse_cell_size);
+        let x1 = i32((max_x - aabb_min.x) * inverse_cell_size);
+        if x1 < 0 || x0 >= w {
+            continue;
+        }
+
+        for (var x = clamp(x0, -1, w - 1); x <= clamp(x1, 0, w - 1); x++) {
+            // Clip the row to the column and keep the rest for the next columns.
+            let cx = aabb_min.x + f32(x) * cell_size;
+            let column_division = divide_polygon(in_row, cx + cell_size, AXIS_X);
+            var cell = column_division.below;
+            in_row = column_division.above;
+            if cell.count < 3u || x < 0 {
+                continue;
+            }
+
+            var span_min = cell.vertices[0].y;
+            var span_max = span_min;
+            for (var i = 1u; i < min(cell.count, MAX_VERTICES); i++) {
+                span_min = min(span_min, cell.vertices[i].y);
+                span_max = max(span_max, cell.vertices[i].y);
+            }
+            span_min -= aabb_min.y;
+            span_max -= aabb_min.y;
+            // Skip the span if it's completely outside the heightfield bounding box.
+            if span_max < 0.0 || span_min > by {
+                continue;
+            }
+            span_min = max(span_min, 0.0);
+            span_max = min(span_max, by);
+
+            // Snap the span to the heightfield height grid.
+            let min_cell = clamp(i32(floor(span_min * inverse_cell_height)), 0, MAX_HEIGHT);
+            let max_cell = clamp(i32(ceil(span_max * inverse_cell_height)), min_cell + 1, MAX_HEIGHT);
+
+            let index = atomicAdd(&span_count, 1u);
+            if index < params.counts.w {
+                spans[index] = OutSpan(
+                    triangle,
+                    u32(x) | (u32(z) << 16u),
+                    u32(min_cell) | (u32(max_cell) << 16u),
+                    areas[triangle],
+                );
+            }
+        }
+    }
+}
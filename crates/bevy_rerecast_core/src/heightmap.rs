@@ -0,0 +1,49 @@
+//! Terrain chunks that are rasterized straight from their heights instead of through a [`TriMesh`].
+//!
+//! Terrain is usually the largest navmesh affector by far. Handing it to a [`NavmeshAffectorBackend`](crate::NavmeshAffectorBackend)
+//! as a mesh means building two triangles per sample and clipping each of them against the heightfield,
+//! while a [`HeightmapAffector`] is rasterized directly with [`Heightfield::rasterize_heightmap`](rerecast::Heightfield::rasterize_heightmap).
+
+use bevy_derive::{Deref, DerefMut};
+use bevy_ecs::prelude::*;
+use bevy_transform::prelude::*;
+use glam::{Quat, Vec3, Vec3Swizzles as _};
+use rerecast::{Heightmap, TriMesh};
+
+/// A navmesh affector made of a regular grid of heights, e.g. a terrain chunk.
+///
+/// Heightmap affectors are collected alongside the affectors of the [`NavmeshAffectorBackends`](crate::NavmeshAffectorBackends),
+/// so they work with any backend, or without one. Like those, they are marked according to the [`NavmeshAffectorAreas`](crate::NavmeshAffectorAreas),
+/// an optional [`NavmeshAffectorArea`](crate::NavmeshAffectorArea) and an optional [`NavmeshObstruction`](crate::NavmeshObstruction) of the entity.
+///
+/// The heightmap is in the local space of the entity. Translation and positive scale keep the fast path;
+/// rotated chunks are triangulated with [`Heightmap::to_trimesh`] instead.
+#[derive(Component, Debug, Clone, PartialEq, Deref, DerefMut)]
+#[require(Transform)]
+pub struct HeightmapAffector(pub Heightmap);
+
+impl HeightmapAffector {
+    /// The heightmap in world space, or `None` if the transform rotates or mirrors it,
+    /// in which case it no longer lines up with the heightfield and has to be triangulated with [`HeightmapAffector::to_world_trimesh`].
+    pub fn to_world(&self, transform: &GlobalTransform) -> Option<Heightmap> {
+        let (scale, rotation, _) = transform.to_scale_rotation_translation();
+        if !rotation.abs_diff_eq(Quat::IDENTITY, 1e-5) || scale.cmple(Vec3::ZERO).any() {
+            return None;
+        }
+        Some(Heightmap {
+            origin: transform.transform_point(self.origin),
+            spacing: self.spacing * scale.xz(),
+            size: self.size,
+            heights: self.heights.iter().map(|height| height * scale.y).collect(),
+        })
+    }
+
+    /// The triangulated heightmap in world space, see [`Heightmap::to_trimesh`].
+    pub fn to_world_trimesh(&self, transform: &GlobalTransform) -> TriMesh {
+        let mut trimesh = self.to_trimesh();
+        for vertex in &mut trimesh.vertices {
+            *vertex = transform.transform_point(Vec3::from(*vertex)).into();
+        }
+        trimesh
+    }
+}
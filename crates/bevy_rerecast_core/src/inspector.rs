@@ -0,0 +1,401 @@
+//! Integration with `bevy-inspector-egui`, so that navmeshes can be inspected in the usual inspector workflow.
+//!
+//! The plugin replaces the default reflection UI of a few types with summaries, as a [`Navmesh`] holds
+//! tens of thousands of vertices and indices that are of little use when listed one by one.
+
+use core::{
+    any::{Any, TypeId},
+    time::Duration,
+};
+
+use bevy_app::prelude::*;
+use bevy_asset::AssetApp as _;
+use bevy_ecs::reflect::AppTypeRegistry;
+use bevy_inspector_egui::{
+    egui,
+    inspector_egui_impls::{InspectorEguiImpl, InspectorPrimitive},
+    reflect_inspector::{InspectorUi, ProjectorReflect},
+};
+use bevy_platform::collections::HashMap;
+use bevy_reflect::{NamedField, PartialReflect, Struct as _, TypeInfo, Typed as _};
+use rerecast::{ConfigField, ConfigRounding, ConfigTooltip, ConfigUnit, NavmeshConfigBuilder};
+
+use crate::{DefaultNavmeshConfig, Navmesh, diagnostics::NavmeshBuildStats};
+
+/// Shows [`Navmesh`]es, [`NavmeshConfigBuilder`]s and the [`NavmeshBuildStats`] as summaries in `bevy-inspector-egui`.
+///
+/// Not part of the [`RerecastPlugin`](crate::RerecastPlugin), so that it can be added only to debug builds.
+/// Requires the `inspector` feature. The inspector itself, e.g. `WorldInspectorPlugin`, has to be added separately.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct NavmeshInspectorPlugin;
+
+impl Plugin for NavmeshInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_asset_reflect::<Navmesh>();
+        app.register_type::<DefaultNavmeshConfig>();
+        app.register_type::<NavmeshBuildStats>();
+        app.register_type_data::<Navmesh, InspectorEguiImpl>();
+        app.register_type::<NavmeshConfigBuilder>();
+        app.world()
+            .resource::<AppTypeRegistry>()
+            .write()
+            .get_mut(TypeId::of::<NavmeshConfigBuilder>())
+            .expect("Registered above")
+            .insert(InspectorEguiImpl::new(
+                config_ui,
+                config_ui_readonly,
+                config_ui_many,
+            ));
+        app.register_type_data::<NavmeshBuildStats, InspectorEguiImpl>();
+    }
+}
+
+impl InspectorPrimitive for Navmesh {
+    fn ui(&mut self, ui: &mut egui::Ui, options: &dyn Any, id: egui::Id, env: InspectorUi) -> bool {
+        // The data of a navmesh only makes sense as a whole, so it is not edited by hand.
+        self.ui_readonly(ui, options, id, env);
+        false
+    }
+
+    fn ui_readonly(&self, ui: &mut egui::Ui, _: &dyn Any, id: egui::Id, _: InspectorUi) {
+        let polygon = self.polygon();
+        egui::Grid::new(id.with("navmesh"))
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                row(ui, "Polygons", polygon.polygon_count());
+                row(ui, "Vertices", polygon.vertices.len());
+                row(
+                    ui,
+                    "Vertices per polygon",
+                    format!("at most {}", polygon.max_vertices_per_polygon),
+                );
+                row(
+                    ui,
+                    "Off-mesh connections",
+                    polygon.off_mesh_connections.len(),
+                );
+                row(
+                    ui,
+                    "Bounds",
+                    format!(
+                        "{:.2?} to {:.2?}",
+                        polygon.aabb.min.to_array(),
+                        polygon.aabb.max.to_array()
+                    ),
+                );
+                row(
+                    ui,
+                    "Cells",
+                    format!(
+                        "{:.2} wide, {:.2} high",
+                        polygon.cell_size, polygon.cell_height
+                    ),
+                );
+                let detail = match self.detail() {
+                    Some(detail) => format!(
+                        "{} vertices, {} triangles",
+                        detail.vertices.len(),
+                        detail.triangles.len()
+                    ),
+                    None => "Not loaded".to_string(),
+                };
+                row(ui, "Detail mesh", detail);
+                row(ui, "Memory", format_bytes(self.memory_estimate()));
+            });
+
+        let mut areas = HashMap::<u8, usize>::default();
+        for area in &polygon.areas {
+            *areas.entry(area.0).or_default() += 1;
+        }
+        let mut areas = areas.into_iter().collect::<Vec<_>>();
+        areas.sort_unstable();
+        ui.collapsing(format!("Polygons per area ({})", areas.len()), |ui| {
+            egui::Grid::new(id.with("areas"))
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (area, count) in areas {
+                        row(ui, format!("Area {area}"), count);
+                    }
+                });
+        });
+    }
+}
+
+impl InspectorPrimitive for NavmeshBuildStats {
+    fn ui(&mut self, ui: &mut egui::Ui, options: &dyn Any, id: egui::Id, env: InspectorUi) -> bool {
+        // The stats describe a build that already happened.
+        self.ui_readonly(ui, options, id, env);
+        false
+    }
+
+    fn ui_readonly(&self, ui: &mut egui::Ui, _: &dyn Any, id: egui::Id, _: InspectorUi) {
+        egui::Grid::new(id.with("stats"))
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                row(ui, "Total", format_duration(self.total_duration));
+                row(ui, "Polygons", self.polygon_count);
+                row(ui, "Affectors", self.affector_count);
+                row(ui, "Memory", format_bytes(self.memory_estimate));
+                row(
+                    ui,
+                    "Scratch",
+                    format!(
+                        "{} after {} builds, {} buffers grown",
+                        format_bytes(self.scratch.capacity_bytes),
+                        self.scratch.builds,
+                        self.scratch.growths
+                    ),
+                );
+            });
+        ui.collapsing("Stages", |ui| {
+            egui::Grid::new(id.with("stages"))
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (stage, duration) in &self.stage_durations {
+                        row(ui, stage.name(), format_duration(*duration));
+                    }
+                });
+        });
+    }
+}
+
+/// Draws the fields of a [`NavmeshConfigBuilder`], the numeric ones as the [`ConfigField`]s the editor shows,
+/// followed by the values they result in.
+///
+/// [`NavmeshConfigBuilder`] is defined in `rerecast`, so the UI is registered as plain functions
+/// instead of through [`InspectorPrimitive`].
+fn config_ui(
+    value: &mut dyn Any,
+    ui: &mut egui::Ui,
+    _: &dyn Any,
+    id: egui::Id,
+    mut env: InspectorUi,
+) -> bool {
+    let builder = value
+        .downcast_mut::<NavmeshConfigBuilder>()
+        .expect("Registered for `NavmeshConfigBuilder`");
+    let numeric = ConfigField::all()
+        .map(|field| (field.info().name(), field))
+        .collect::<HashMap<_, _>>();
+    let mut changed = false;
+    egui::Grid::new(id.with("config"))
+        .num_columns(3)
+        .show(ui, |ui| {
+            for (index, info) in config_fields() {
+                if let Some(&field) = numeric.get(info.name()) {
+                    ui.label(field.title()).on_hover_text(field.tooltip());
+                    changed |= number_input(ui, builder, field);
+                    ui.label(unit_text(builder, field));
+                } else {
+                    let label = ui.label(info.name());
+                    if let Some(tooltip) = info.get_attribute::<ConfigTooltip>() {
+                        label.on_hover_text(tooltip.0);
+                    }
+                    // Enums, bounds and toggles keep their default UI.
+                    let field = builder
+                        .field_at_mut(index)
+                        .expect("The field index comes from the type info");
+                    changed |= env.ui_for_reflect(field, ui);
+                    ui.label("");
+                }
+                ui.end_row();
+            }
+        });
+    ui.collapsing("Resulting config", |ui| {
+        config_summary(builder, ui, id);
+    });
+    changed
+}
+
+fn config_ui_readonly(
+    value: &dyn Any,
+    ui: &mut egui::Ui,
+    options: &dyn Any,
+    id: egui::Id,
+    env: InspectorUi,
+) {
+    let mut builder = *value
+        .downcast_ref::<NavmeshConfigBuilder>()
+        .expect("Registered for `NavmeshConfigBuilder`");
+    ui.add_enabled_ui(false, |ui| {
+        config_ui(&mut builder, ui, options, id, env);
+    });
+}
+
+fn config_ui_many(
+    ui: &mut egui::Ui,
+    _: &dyn Any,
+    _: egui::Id,
+    _: InspectorUi,
+    _: &mut [&mut dyn PartialReflect],
+    _: &dyn ProjectorReflect,
+) -> bool {
+    ui.label("Editing several navmesh configs at once is not supported");
+    false
+}
+
+/// The fields of [`NavmeshConfigBuilder`] along with their index, in declaration order.
+fn config_fields() -> impl Iterator<Item = (usize, &'static NamedField)> {
+    let TypeInfo::Struct(info) = NavmeshConfigBuilder::type_info() else {
+        unreachable!("`NavmeshConfigBuilder` is a struct");
+    };
+    info.iter().enumerate()
+}
+
+/// Edits `field` in its [`ConfigField::range`] and [`ConfigField::step`], in degrees for angles.
+fn number_input(ui: &mut egui::Ui, builder: &mut NavmeshConfigBuilder, field: ConfigField) -> bool {
+    let mut value = field.get(builder);
+    let (min, max) = field.range();
+    let mut input = egui::DragValue::new(&mut value)
+        .range(min..=max)
+        .speed(field.step());
+    if field.is_integer() {
+        input = input.fixed_decimals(0);
+    }
+    let changed = ui.add(input).changed();
+    if changed {
+        field.set(builder, value);
+    }
+    changed
+}
+
+/// The unit of `field`, along with its value converted to the other unit like the build does, or whether it is turned off.
+fn unit_text(builder: &NavmeshConfigBuilder, field: ConfigField) -> String {
+    let unit = match field.unit() {
+        Some(
+            ConfigUnit::WorldUnits
+            | ConfigUnit::HorizontalWorldUnits
+            | ConfigUnit::VerticalWorldUnits,
+        ) => "wu",
+        Some(ConfigUnit::Voxels) => "vx",
+        Some(ConfigUnit::CellSizes) => "cs",
+        Some(ConfigUnit::CellHeights) => "ch",
+        Some(ConfigUnit::Radians) => "°",
+        Some(ConfigUnit::Count) | None => return String::new(),
+    };
+    if field.is_disabled(builder) {
+        format!("{unit} (off)")
+    } else if let Some(cells) = field.cells(builder) {
+        // Rounded fields are shown in the whole cells the build ends up with.
+        if field.info().has_attribute::<ConfigRounding>() {
+            format!("{unit} ({cells} vx)")
+        } else {
+            format!("{unit} ({cells:.1} vx)")
+        }
+    } else if let Some(world_units) = field.world_units(builder) {
+        format!("{unit} ({world_units:.2} wu)")
+    } else {
+        unit.to_string()
+    }
+}
+
+/// The values the build actually uses, most of which are converted to cells.
+fn config_summary(builder: &NavmeshConfigBuilder, ui: &mut egui::Ui, id: egui::Id) {
+    let config = builder.build();
+    egui::Grid::new(id.with("resulting_config"))
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            row(
+                ui,
+                "Walkable height",
+                format!("{} vx", config.walkable_height),
+            );
+            row(
+                ui,
+                "Walkable climb",
+                format!("{} vx", config.walkable_climb),
+            );
+            row(
+                ui,
+                "Walkable radius",
+                format!("{} vx", config.walkable_radius),
+            );
+            row(
+                ui,
+                "Walkable slope",
+                format!("{:.1}°", config.walkable_slope_angle.to_degrees()),
+            );
+            row(ui, "Max edge length", format!("{} vx", config.max_edge_len));
+            row(
+                ui,
+                "Min region area",
+                format!("{} cells", config.min_region_area),
+            );
+            row(
+                ui,
+                "Merge region area",
+                format!("{} cells", config.merge_region_area),
+            );
+            row(ui, "Border", format!("{} vx", config.border_size));
+            row(
+                ui,
+                "Detail sample distance",
+                format!("{:.2} wu", config.detail_sample_dist),
+            );
+            row(
+                ui,
+                "Detail max error",
+                format!("{:.2} wu", config.detail_sample_max_error),
+            );
+        });
+}
+
+fn row(ui: &mut egui::Ui, label: impl Into<egui::WidgetText>, value: impl ToString) {
+    ui.label(label);
+    ui.label(value.to_string());
+    ui.end_row();
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const KIB: f64 = 1024.0;
+    let kib = bytes as f64 / KIB;
+    if kib >= KIB {
+        format!("{:.1} MiB", kib / KIB)
+    } else if kib >= 1.0 {
+        format!("{kib:.1} KiB")
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugin_registers_the_summaries() {
+        let mut app = App::new();
+        app.add_plugins(NavmeshInspectorPlugin);
+        let registry = app.world().resource::<AppTypeRegistry>().read();
+        for type_id in [
+            TypeId::of::<Navmesh>(),
+            TypeId::of::<NavmeshConfigBuilder>(),
+            TypeId::of::<NavmeshBuildStats>(),
+        ] {
+            assert!(
+                registry
+                    .get_type_data::<InspectorEguiImpl>(type_id)
+                    .is_some()
+            );
+        }
+    }
+
+    #[test]
+    fn small_sizes_are_shown_in_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(40), "40 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
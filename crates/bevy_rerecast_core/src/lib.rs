@@ -1,36 +1,348 @@
 #![doc = include_str!("../../../readme.md")]
 
+use std::sync::Arc;
+
 use bevy_app::prelude::*;
 use bevy_asset::prelude::*;
+use bevy_derive::{Deref, DerefMut};
+use bevy_ecs::prelude::*;
+use bevy_transform::prelude::*;
+use glam::Vec3;
+use thiserror::Error;
 #[cfg(feature = "bevy_mesh")]
 mod mesh;
-use bevy_reflect::Reflect;
+use bevy_reflect::prelude::*;
 #[cfg(feature = "bevy_mesh")]
-pub use mesh::{Mesh3dNavmeshPlugin, TriMeshFromBevyMesh};
+pub use mesh::{
+    AreaTypeProvider, MaterialAreas, Mesh3dAreaTypes, Mesh3dNavmeshPlugin, TriMeshFromBevyMesh,
+    VertexAttributeAreas,
+};
 mod backend;
+#[cfg(feature = "debug_plugin")]
+pub mod debug;
+pub mod diagnostics;
+pub mod fragment;
 pub mod generator;
+#[cfg(feature = "gpu_rasterization")]
+pub mod gpu_rasterization;
+pub mod heightmap;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+#[cfg(feature = "serialize")]
+pub mod nav_file;
+pub mod navmeshes;
+pub mod obstacles;
+pub mod off_mesh;
+#[cfg(feature = "editor_overlay")]
+pub mod overlay;
+#[cfg(feature = "streaming")]
+pub mod streaming;
+pub mod tile_store;
+pub mod tiled;
+pub mod volume;
 pub use backend::*;
 
+use navmeshes::{AgentProfile, AgentProfiles, NavmeshKey};
 pub use rerecast;
-use rerecast::{DetailNavmesh, PolygonNavmesh};
+use rerecast::{
+    AreaOutline, AreaType, ConvexVolume, CostVolumes, DetailNavmesh, NavmeshConfigBuilder,
+    PickingNavmesh, PolyId, PolygonNavmesh, ScatterConfig, ScatterPoint, TeleportDestination,
+    TeleportError, TriMesh,
+};
 
 /// The main plugin of the crate. Adds functionality for creating and managing navmeshes.
-#[non_exhaustive]
+///
+/// Common setups can be configured right where the plugin is added:
+///
+/// ```no_run
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_transform::prelude::*;
+/// # use bevy_rerecast_core::{RerecastPlugin, rerecast::{NavmeshConfigBuilder, TriMesh}};
+/// fn backend() -> Vec<(Entity, GlobalTransform, TriMesh)> {
+///     Vec::new()
+/// }
+///
+/// App::new().add_plugins(
+///     RerecastPlugin::new()
+///         .with_default_config(NavmeshConfigBuilder {
+///             agent_radius: 0.4,
+///             ..Default::default()
+///         })
+///         .with_backend(backend),
+/// );
+/// ```
 #[derive(Default)]
-pub struct RerecastPlugin;
+pub struct RerecastPlugin {
+    default_config: Option<NavmeshConfigBuilder>,
+    agent_profiles: AgentProfiles,
+    backend: Option<BackendInstaller>,
+    #[cfg(feature = "recording")]
+    debug_artifacts: bool,
+}
+
+/// Sets the backend passed to [`RerecastPlugin::with_backend`].
+/// Plugins are built through a shared reference, so the backend has to be cloned out of the plugin.
+type BackendInstaller = Box<dyn Fn(&mut App) + Send + Sync>;
+
+impl RerecastPlugin {
+    /// Creates the plugin with nothing configured. Same as [`RerecastPlugin::default`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the [`DefaultNavmeshConfig`], used e.g. by [`NavmeshGenerator::generate_default`](generator::NavmeshGenerator::generate_default).
+    pub fn with_default_config(mut self, config: NavmeshConfigBuilder) -> Self {
+        self.default_config = Some(config);
+        self
+    }
+
+    /// Registers an agent profile in [`AgentProfiles`], e.g. `with_agent_profile("ogre", AgentProfile::new(1.2, 3.0))`.
+    pub fn with_agent_profile(mut self, key: impl Into<NavmeshKey>, profile: AgentProfile) -> Self {
+        self.agent_profiles.insert(key, profile);
+        self
+    }
+
+    /// Replaces all navmesh affector backends with `system`, as with [`NavmeshApp::set_navmesh_affector_backend_with_entities`].
+    /// Use [`NavmeshApp::add_navmesh_affector_backend`] to combine several backends.
+    pub fn with_backend<M>(
+        mut self,
+        system: impl IntoSystem<(), Vec<(Entity, GlobalTransform, TriMesh)>, M>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.backend = Some(Box::new(move |app| {
+            app.set_navmesh_affector_backend_with_entities(system.clone());
+        }));
+        self
+    }
+
+    /// Whether the inputs of every navmesh generation are written to the `navmesh_debug_artifacts` directory
+    /// in the working directory, as with [`RecordNavmeshInputs`](generator::RecordNavmeshInputs). Defaults to `false`.
+    #[cfg(feature = "recording")]
+    pub fn with_debug_artifacts(mut self, enabled: bool) -> Self {
+        self.debug_artifacts = enabled;
+        self
+    }
+}
 
 impl Plugin for RerecastPlugin {
     fn build(&self, app: &mut App) {
         app.init_asset::<Navmesh>();
-        app.add_plugins(generator::plugin);
+        app.add_plugins((
+            generator::plugin,
+            navmeshes::plugin,
+            fragment::plugin,
+            diagnostics::plugin,
+            tiled::plugin,
+            tile_store::plugin,
+            obstacles::plugin,
+            off_mesh::plugin,
+        ));
+        #[cfg(feature = "serialize")]
+        app.add_plugins(nav_file::plugin);
+        app.insert_resource(DefaultNavmeshConfig(
+            self.default_config.unwrap_or_default(),
+        ));
+        app.insert_resource(self.agent_profiles.clone());
+        app.init_resource::<NavmeshCostVolumes>();
+        app.register_type::<NavmeshObstruction>();
+        app.register_type::<NavmeshAffectorArea>();
+        app.register_type::<NavmeshAreaFlags>();
+        app.register_type::<volume::NavmeshVolume>();
+        if let Some(install_backend) = &self.backend {
+            install_backend(app);
+        }
+        #[cfg(feature = "recording")]
+        if self.debug_artifacts {
+            const DIRECTORY: &str = "navmesh_debug_artifacts";
+            match std::fs::create_dir_all(DIRECTORY) {
+                Ok(()) => {
+                    app.insert_resource(generator::RecordNavmeshInputs::new(DIRECTORY));
+                }
+                Err(err) => {
+                    tracing::error!(
+                        "Failed to create the directory for navmesh debug artifacts: {err}"
+                    );
+                }
+            }
+        }
     }
 }
 
+/// The config used when no other config is given, e.g. by [`NavmeshGenerator::generate_default`](generator::NavmeshGenerator::generate_default).
+/// Set it with [`RerecastPlugin::with_default_config`] or by modifying the resource.
+#[derive(Resource, Debug, Clone, Default, Deref, DerefMut, Reflect)]
+#[reflect(Resource, Default)]
+pub struct DefaultNavmeshConfig(pub NavmeshConfigBuilder);
+
+/// Short-lived volumes that make traversing parts of the navmeshes more or less expensive, e.g. grenades or fire patches.
+/// Pathfinding can consult them through [`CostVolumes::polygon_multiplier`] without any navmesh being modified.
+///
+/// Volumes are kept until they are removed again with the key returned by [`CostVolumes::insert`].
+#[derive(Resource, Debug, Clone, Default, Deref, DerefMut)]
+pub struct NavmeshCostVolumes(pub CostVolumes);
+
 /// Resource containing the navmesh data.
-/// Load this using either a file or by using [`NavmeshGenerator`](generator::NavmeshGenerator)
-#[derive(Debug, Default, Clone, PartialEq, Asset, Reflect)]
+/// Load this using either a file or by using [`NavmeshGenerator`](generator::NavmeshGenerator).
+/// With the `serialize` feature, navmeshes can also be saved to and loaded from `.nav` files, see the `nav_file` module.
+///
+/// The detail mesh is optional so that large maps can load the small polygon mesh first
+/// and stream in the detail mesh later, e.g. only once it is needed near the player.
+/// Use [`Navmesh::split_detail`] to serialize both parts separately and [`Navmesh::insert_detail`]
+/// to attach the detail mesh once it has been loaded.
+#[derive(Debug, Default, Clone, Asset, Reflect)]
 #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Navmesh {
     polygon: PolygonNavmesh,
-    detail: DetailNavmesh,
+    detail: Option<DetailNavmesh>,
+    /// Set by the generator while [`CacheNavmeshBuilds`](generator::CacheNavmeshBuilds) exists, see [`Navmesh::rebuild_areas`].
+    #[reflect(ignore)]
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    build_cache: Option<Arc<generator::AreaRebuildCache>>,
+}
+
+impl PartialEq for Navmesh {
+    fn eq(&self, other: &Self) -> bool {
+        // The build cache only speeds up rebuilds, so it does not change what the navmesh is.
+        self.polygon == other.polygon && self.detail == other.detail
+    }
+}
+
+impl Navmesh {
+    /// Creates a new navmesh from a polygon mesh and its associated detail mesh.
+    pub fn new(polygon: PolygonNavmesh, detail: DetailNavmesh) -> Self {
+        Self {
+            polygon,
+            detail: Some(detail),
+            build_cache: None,
+        }
+    }
+
+    /// Creates a new navmesh from only a polygon mesh. The detail mesh can be added later
+    /// through [`Navmesh::insert_detail`].
+    pub fn from_polygon(polygon: PolygonNavmesh) -> Self {
+        Self {
+            polygon,
+            detail: None,
+            build_cache: None,
+        }
+    }
+
+    /// The polygon mesh of this navmesh.
+    pub fn polygon(&self) -> &PolygonNavmesh {
+        &self.polygon
+    }
+
+    /// The detail mesh of this navmesh, if it has been loaded.
+    pub fn detail(&self) -> Option<&DetailNavmesh> {
+        self.detail.as_ref()
+    }
+
+    /// Whether the detail mesh has been loaded.
+    pub fn detail_loaded(&self) -> bool {
+        self.detail.is_some()
+    }
+
+    /// Attaches a detail mesh that was loaded separately from the polygon mesh.
+    /// Returns the previously loaded detail mesh, if any.
+    ///
+    /// The detail mesh must have been built from this navmesh's polygon mesh,
+    /// i.e. it must contain one sub-mesh per polygon. Otherwise it is rejected and the navmesh is left unchanged.
+    pub fn insert_detail(
+        &mut self,
+        detail: DetailNavmesh,
+    ) -> Result<Option<DetailNavmesh>, InsertDetailError> {
+        let polygon_count = self.polygon.polygon_count();
+        if detail.meshes.len() != polygon_count {
+            return Err(InsertDetailError::PolygonCountMismatch {
+                polygons: polygon_count,
+                detail_meshes: detail.meshes.len(),
+            });
+        }
+        Ok(self.detail.replace(detail))
+    }
+
+    /// Removes the detail mesh from this navmesh, leaving only the polygon mesh.
+    ///
+    /// Serializing the navmesh afterwards only writes the polygon mesh, so that the returned
+    /// detail mesh can be serialized and streamed in separately, e.g. as a `.navdetail` file with the `serialize` feature.
+    pub fn split_detail(&mut self) -> Option<DetailNavmesh> {
+        self.detail.take()
+    }
+
+    /// Sets the [`AreaType`] of the polygons within the given convex volume, without regenerating the navmesh.
+    /// The detail mesh stays valid, as the geometry does not change.
+    ///
+    /// See [`PolygonNavmesh::remark_area_in_volume`] for which polygons are affected.
+    pub fn remark_area_in_volume(&mut self, volume: &ConvexVolume, area: AreaType) -> Vec<PolyId> {
+        self.polygon.remark_area_in_volume(volume, area)
+    }
+
+    /// Like [`Navmesh::remark_area_in_volume`], but also sets the flags of the remarked polygons.
+    pub fn remark_area_in_volume_with_flags(
+        &mut self,
+        volume: &ConvexVolume,
+        area: AreaType,
+        flags: u16,
+    ) -> Vec<PolyId> {
+        self.polygon
+            .remark_area_in_volume_with_flags(volume, area, flags)
+    }
+
+    /// Scatters points on the navmesh, e.g. for spawning foliage, loot or patrol points.
+    /// Returns `None` if the detail mesh is not loaded, as the points are placed on its surface.
+    ///
+    /// See [`PolygonNavmesh::scatter_points`] for details.
+    pub fn scatter_points(&self, config: &ScatterConfig) -> Option<Vec<ScatterPoint>> {
+        let detail = self.detail.as_ref()?;
+        Some(self.polygon.scatter_points(detail, config))
+    }
+
+    /// Traces the 2D outlines of the walkable areas, e.g. for a vector minimap or zone overlays.
+    ///
+    /// See [`PolygonNavmesh::area_outlines`] for details.
+    pub fn area_outlines(&self, max_error: f32) -> Vec<AreaOutline> {
+        self.polygon.area_outlines(max_error)
+    }
+
+    /// Creates a copy of the navmesh with its borders pushed outward by `margin`, for resolving clicks that land slightly off it.
+    /// The navmesh itself is not changed, so agents still keep their distance to walls.
+    ///
+    /// See [`PolygonNavmesh::dilated_for_picking`] for details.
+    pub fn dilated_for_picking(&self, margin: f32) -> PickingNavmesh {
+        self.polygon.dilated_for_picking(margin)
+    }
+
+    /// Checks whether an agent of the given profile standing at `from` can be teleported to `to`,
+    /// moving the destination onto the navmesh if it lies just off of it, e.g. right next to a wall.
+    /// Obstacles carved into a [`CarvedNavmesh`](crate::obstacles::CarvedNavmesh) are not part of this navmesh,
+    /// use [`NavmeshTileCache::validate_teleport`](crate::obstacles::NavmeshTileCache::validate_teleport) for those.
+    ///
+    /// See [`PolygonNavmesh::validate_teleport`] for details.
+    pub fn validate_teleport(
+        &self,
+        from: Vec3,
+        to: Vec3,
+        agent: &AgentProfile,
+    ) -> Result<TeleportDestination, TeleportError> {
+        self.polygon.validate_teleport(from, to, agent.into())
+    }
+}
+
+/// Why [`Navmesh::insert_detail`] rejected a detail mesh.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum InsertDetailError {
+    /// Happens when the detail mesh was built from a different polygon mesh, e.g. when the files of two navmeshes were mixed up.
+    #[error(
+        "The detail mesh has {detail_meshes} sub-meshes, but the navmesh has {polygons} polygons"
+    )]
+    PolygonCountMismatch {
+        /// The number of polygons of the navmesh.
+        polygons: usize,
+        /// The number of sub-meshes of the rejected detail mesh.
+        detail_meshes: usize,
+    },
 }
@@ -1,70 +1,312 @@
+use std::sync::Arc;
+
 use bevy_app::prelude::*;
-use bevy_asset::prelude::*;
-use bevy_ecs::prelude::*;
-use bevy_mesh::{Mesh, PrimitiveTopology};
+use bevy_asset::{AsAssetId, prelude::*};
+use bevy_derive::Deref;
+use bevy_ecs::{prelude::*, world::EntityRef};
+use bevy_mesh::{Mesh, MeshVertexAttribute, PrimitiveTopology, VertexAttributeValues};
+use bevy_platform::collections::HashMap;
 use bevy_render::prelude::*;
 use bevy_transform::components::GlobalTransform;
 use glam::{UVec3, Vec3A};
 use rerecast::{AreaType, TriMesh};
 
-use crate::NavmeshApp as _;
+use crate::{NavmeshAffectorAreas, NavmeshApp as _};
 
 /// A backend for navmesh generation.
 /// Uses all entities with a [`Mesh3d`] component as navmesh affectors.
 #[derive(Debug, Default)]
 #[non_exhaustive]
-pub struct Mesh3dNavmeshPlugin;
+pub struct Mesh3dNavmeshPlugin {
+    /// How the triangles of the meshes are marked before rasterization.
+    pub areas: NavmeshAffectorAreas,
+    /// Assigns area types to individual triangles, which take precedence over [`Self::areas`]. Defaults to `None`.
+    pub area_types: Option<Mesh3dAreaTypes>,
+}
+
+impl Mesh3dNavmeshPlugin {
+    /// The name the backend is added under, e.g. for [`NavmeshAffectorBackends::set_enabled`](crate::NavmeshAffectorBackends::set_enabled).
+    pub const BACKEND_NAME: &'static str = "mesh3d";
+
+    /// Sets the area walkable triangles are marked with. See [`NavmeshAffectorAreas::walkable_area`].
+    pub fn with_walkable_area(mut self, area: AreaType) -> Self {
+        self.areas.walkable_area = area;
+        self
+    }
+
+    /// Sets whether triangles are only walkable if they are flat enough. See [`NavmeshAffectorAreas::mark_by_slope`].
+    pub fn with_slope_marking(mut self, mark_by_slope: bool) -> Self {
+        self.areas.mark_by_slope = mark_by_slope;
+        self
+    }
+
+    /// Sets the provider that assigns area types to individual triangles. See [`Self::area_types`].
+    pub fn with_area_types(mut self, provider: impl AreaTypeProvider) -> Self {
+        self.area_types = Some(Mesh3dAreaTypes(Arc::new(provider)));
+        self
+    }
+}
 
 impl Plugin for Mesh3dNavmeshPlugin {
     fn build(&self, app: &mut App) {
-        app.set_navmesh_affector_backend(mesh3d_backend);
+        app.add_navmesh_affector_backend_with_areas(Self::BACKEND_NAME, mesh3d_backend, self.areas);
+        app.add_navmesh_affector_component::<Mesh3d>();
+        if let Some(area_types) = &self.area_types {
+            app.insert_resource(area_types.clone());
+        }
     }
 }
 
 fn mesh3d_backend(
     meshes: Res<Assets<Mesh>>,
-    affectors: Query<(&GlobalTransform, &Mesh3d)>,
-) -> Vec<(GlobalTransform, TriMesh)> {
+    affectors: Query<(EntityRef, &GlobalTransform, &Mesh3d)>,
+    area_types: Option<Res<Mesh3dAreaTypes>>,
+) -> Vec<(Entity, GlobalTransform, TriMesh)> {
     affectors
         .iter()
-        .filter_map(|(transform, mesh)| {
+        .filter_map(|(entity, transform, mesh)| {
             let transform = *transform;
             let mesh = meshes.get(mesh)?;
-            let proxy_mesh = TriMesh::from_mesh(mesh)?;
-            Some((transform, proxy_mesh))
+            let mut proxy_mesh = TriMesh::from_mesh(mesh)?;
+            if let Some(area_types) = &area_types {
+                area_types.assign_area_types(entity, mesh, &mut proxy_mesh);
+            }
+            Some((entity.id(), transform, proxy_mesh))
         })
         .collect::<Vec<_>>()
 }
 
+/// Assigns area types to the triangles of the meshes used by [`Mesh3dNavmeshPlugin`], e.g. to mark a road
+/// painted into the vertex colors of a terrain, or everything rendered with a water material.
+///
+/// Triangles keep the area type assigned here as long as they are walkable. Triangles left at [`AreaType::NOT_WALKABLE`]
+/// are marked according to [`NavmeshAffectorAreas`], including the [`NavmeshAffectorArea`](crate::NavmeshAffectorArea)
+/// of their entity, which assigns an area type to the whole entity. Mark entities that are never walkable with a
+/// [`NavmeshObstruction`](crate::NavmeshObstruction) instead.
+///
+/// Implemented for [`VertexAttributeAreas`], [`MaterialAreas`] and closures with the signature of [`Self::assign_area_types`].
+pub trait AreaTypeProvider: Send + Sync + 'static {
+    /// Writes the area types of the triangles of `trimesh` into [`TriMesh::area_types`].
+    ///
+    /// `trimesh` was converted from `mesh` with [`TriMeshFromBevyMesh::from_mesh`], so its vertices are those of `mesh`
+    /// in the same order. The area types passed in are all [`AreaType::NOT_WALKABLE`].
+    fn assign_area_types(&self, entity: EntityRef, mesh: &Mesh, trimesh: &mut TriMesh);
+}
+
+impl<F> AreaTypeProvider for F
+where
+    F: Fn(EntityRef, &Mesh, &mut TriMesh) + Send + Sync + 'static,
+{
+    fn assign_area_types(&self, entity: EntityRef, mesh: &Mesh, trimesh: &mut TriMesh) {
+        self(entity, mesh, trimesh);
+    }
+}
+
+/// The [`AreaTypeProvider`] of [`Mesh3dNavmeshPlugin`], inserted by the plugin if one is set.
+#[derive(Resource, Clone, Deref)]
+pub struct Mesh3dAreaTypes(pub Arc<dyn AreaTypeProvider>);
+
+impl std::fmt::Debug for Mesh3dAreaTypes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Mesh3dAreaTypes").finish_non_exhaustive()
+    }
+}
+
+/// Reads the area types from a custom vertex attribute with the format [`VertexFormat::Uint32`](bevy_mesh::VertexFormat::Uint32).
+///
+/// Each triangle gets the highest area type of its vertices, just like overlapping spans keep the highest area type
+/// by default. Values above [`u8::MAX`] are clamped. Meshes without the attribute, or with a different format, are left untouched.
+#[derive(Debug, Clone)]
+pub struct VertexAttributeAreas(pub MeshVertexAttribute);
+
+impl AreaTypeProvider for VertexAttributeAreas {
+    fn assign_area_types(&self, _entity: EntityRef, mesh: &Mesh, trimesh: &mut TriMesh) {
+        let Some(VertexAttributeValues::Uint32(values)) = mesh.attribute(self.0.id) else {
+            return;
+        };
+        for (area, triangle) in trimesh.area_types.iter_mut().zip(&trimesh.indices) {
+            let value = triangle
+                .to_array()
+                .map(|index| values.get(index as usize).copied().unwrap_or_default())
+                .into_iter()
+                .max()
+                .unwrap_or_default();
+            *area = AreaType(value.min(u8::MAX as u32) as u8);
+        }
+    }
+}
+
+/// Assigns an area type to all triangles of entities using one of the given materials, e.g. `MaterialAreas::<MeshMaterial3d<StandardMaterial>>`.
+///
+/// Entities without the component `C`, or with a material that has no entry, are left untouched.
+#[derive(Debug)]
+pub struct MaterialAreas<C: AsAssetId> {
+    /// The area type of each material.
+    pub areas: HashMap<AssetId<C::Asset>, AreaType>,
+}
+
+impl<C: AsAssetId> MaterialAreas<C> {
+    /// Creates a provider without any materials.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assigns `area` to all triangles rendered with `material`.
+    pub fn with(mut self, material: impl Into<AssetId<C::Asset>>, area: AreaType) -> Self {
+        self.areas.insert(material.into(), area);
+        self
+    }
+}
+
+impl<C: AsAssetId> Default for MaterialAreas<C> {
+    fn default() -> Self {
+        Self {
+            areas: HashMap::default(),
+        }
+    }
+}
+
+impl<C: AsAssetId> AreaTypeProvider for MaterialAreas<C> {
+    fn assign_area_types(&self, entity: EntityRef, _mesh: &Mesh, trimesh: &mut TriMesh) {
+        let Some(area) = entity
+            .get::<C>()
+            .and_then(|material| self.areas.get(&material.as_asset_id()))
+        else {
+            return;
+        };
+        trimesh.area_types.fill(*area);
+    }
+}
+
 /// Used to add [`TriMeshFromBevyMesh::from_mesh`] to [`TriMesh`].
 pub trait TriMeshFromBevyMesh {
     /// Converts a [`Mesh`] into a [`TriMesh`].
+    ///
+    /// Supports [`PrimitiveTopology::TriangleList`] and [`PrimitiveTopology::TriangleStrip`], with or without indices.
+    /// Returns `None` for other topologies, which contain no triangles.
     fn from_mesh(mesh: &Mesh) -> Option<TriMesh>;
 }
 
 impl TriMeshFromBevyMesh for TriMesh {
     fn from_mesh(mesh: &Mesh) -> Option<TriMesh> {
-        if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
-            return None;
-        }
-
-        let mut trimesh = TriMesh::default();
         let position = mesh.attribute(Mesh::ATTRIBUTE_POSITION)?;
         let float = position.as_float3()?;
-        trimesh.vertices = float.iter().map(|v| Vec3A::from(*v)).collect();
+        let vertices: Vec<_> = float.iter().map(|v| Vec3A::from(*v)).collect();
+
+        // Meshes without indices use every vertex once, in order.
+        let indices: Vec<u32> = match mesh.indices() {
+            Some(indices) => indices.iter().map(|i| i as u32).collect(),
+            None => (0..vertices.len() as u32).collect(),
+        };
+        let indices = match mesh.primitive_topology() {
+            PrimitiveTopology::TriangleList => {
+                if !indices.len().is_multiple_of(3) {
+                    return None;
+                }
+                indices.chunks(3).map(UVec3::from_slice).collect()
+            }
+            PrimitiveTopology::TriangleStrip => unroll_triangle_strip(&indices),
+            _ => return None,
+        };
+        TriMesh::from_parts(vertices, indices).ok()
+    }
+}
+
+/// Converts a triangle strip into a triangle list.
+/// Every other triangle of a strip has its winding flipped, so those are flipped back to keep the winding consistent.
+/// Degenerate triangles, which are commonly used to stitch several strips together, are dropped.
+fn unroll_triangle_strip(indices: &[u32]) -> Vec<UVec3> {
+    indices
+        .windows(3)
+        .enumerate()
+        .filter(|(_, w)| w[0] != w[1] && w[1] != w[2] && w[0] != w[2])
+        .map(|(i, w)| {
+            if i % 2 == 0 {
+                UVec3::new(w[0], w[1], w[2])
+            } else {
+                UVec3::new(w[1], w[0], w[2])
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::RenderAssetUsages;
+    use bevy_mesh::VertexFormat;
+
+    use super::*;
+
+    const ROAD: AreaType = AreaType(7);
+    const WATER: AreaType = AreaType(9);
+
+    /// A flat triangle followed by a vertical one.
+    fn floor_and_wall() -> Mesh {
+        Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all())
+            .with_inserted_attribute(
+                Mesh::ATTRIBUTE_POSITION,
+                vec![
+                    [0.0, 0.0, 0.0],
+                    [0.0, 0.0, 1.0],
+                    [1.0, 0.0, 0.0],
+                    [0.0, 0.0, 0.0],
+                    [0.0, 1.0, 0.0],
+                    [1.0, 0.0, 0.0],
+                ],
+            )
+    }
+
+    /// Assigns the area types of `provider` to [`floor_and_wall`] and marks them like a backend without own areas would.
+    fn marked_areas(
+        provider: &impl AreaTypeProvider,
+        mesh: &Mesh,
+        entity: EntityRef,
+    ) -> Vec<AreaType> {
+        let mut trimesh = TriMesh::from_mesh(mesh).unwrap();
+        provider.assign_area_types(entity, mesh, &mut trimesh);
+        NavmeshAffectorAreas::default().mark(&mut trimesh, 45_f32.to_radians());
+        trimesh.area_types
+    }
+
+    #[test]
+    fn vertex_attribute_areas_take_the_highest_area_of_each_triangle() {
+        const ATTRIBUTE_AREA: MeshVertexAttribute =
+            MeshVertexAttribute::new("Area", 740_219_563, VertexFormat::Uint32);
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+        let provider = VertexAttributeAreas(ATTRIBUTE_AREA);
+
+        // Meshes without the attribute are marked by slope only.
+        let mesh = floor_and_wall();
+        let areas = marked_areas(&provider, &mesh, world.entity(entity));
+        assert_eq!(areas, [AreaType::DEFAULT_WALKABLE, AreaType::NOT_WALKABLE]);
+
+        let mesh = floor_and_wall()
+            .with_inserted_attribute(ATTRIBUTE_AREA, vec![0, ROAD.0 as u32, 1, 0, 0, 1_000]);
+        // The wall keeps being unwalkable, no matter the area painted onto it.
+        let areas = marked_areas(&provider, &mesh, world.entity(entity));
+        assert_eq!(areas, [ROAD, AreaType::NOT_WALKABLE]);
+    }
+
+    #[test]
+    fn material_areas_mark_entities_by_their_material() {
+        let mut world = World::new();
+        let mut meshes = Assets::<Mesh>::default();
+        let water = meshes.add(floor_and_wall());
+        let other = meshes.add(floor_and_wall());
+        let lake = world.spawn(Mesh3d(water.clone())).id();
+        let ground = world.spawn(Mesh3d(other)).id();
+        let untagged = world.spawn_empty().id();
+        let provider = MaterialAreas::<Mesh3d>::new().with(&water, WATER);
 
-        let indices: Vec<_> = mesh.indices()?.iter().collect();
-        if !indices.len().is_multiple_of(3) {
-            return None;
+        let mesh = floor_and_wall();
+        let areas = |entity| marked_areas(&provider, &mesh, world.entity(entity));
+        assert_eq!(areas(lake), [WATER, AreaType::NOT_WALKABLE]);
+        for entity in [ground, untagged] {
+            assert_eq!(
+                areas(entity),
+                [AreaType::DEFAULT_WALKABLE, AreaType::NOT_WALKABLE]
+            );
         }
-        trimesh.indices = indices
-            .chunks(3)
-            .map(|indices| {
-                UVec3::from_array([indices[0] as u32, indices[1] as u32, indices[2] as u32])
-            })
-            .collect();
-        // TODO: accept vertex attributes for this?
-        trimesh.area_types = vec![AreaType::NOT_WALKABLE; trimesh.indices.len()];
-        Some(trimesh)
     }
 }
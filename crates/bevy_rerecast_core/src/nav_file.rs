@@ -0,0 +1,277 @@
+//! Loading and saving [`Navmesh`]es as `.nav` files, so that navmeshes can be baked ahead of time
+//! and loaded with `asset_server.load("navmeshes/level1.nav")` instead of being generated at startup.
+//!
+//! A `.nav` file is gzip-compressed and starts with a magic number and [`NAV_FILE_VERSION`],
+//! so that files written by a different version of rerecast are rejected instead of silently decoding garbage.
+//!
+//! Large maps can save the detail mesh to a separate `.navdetail` file with [`NavmeshDetail::encode`]
+//! after splitting it off with [`Navmesh::split_detail`]. The small `.nav` file is then loaded first,
+//! and the [`NavmeshDetail`] is loaded only once it is needed and attached with [`Navmesh::insert_detail`].
+
+use std::io::{Read as _, Write as _};
+
+use bevy_app::prelude::*;
+use bevy_asset::{
+    AssetLoader, LoadContext,
+    io::{Reader, Writer},
+    prelude::*,
+    saver::{AssetSaver, SavedAsset},
+};
+use bevy_derive::{Deref, DerefMut};
+use bevy_reflect::TypePath;
+use bevy_tasks::futures_lite::AsyncWriteExt as _;
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use rerecast::DetailNavmesh;
+use serde::{Serialize, de::DeserializeOwned};
+use thiserror::Error;
+
+use crate::Navmesh;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<NavmeshDetail>();
+    app.register_asset_loader(NavmeshLoader);
+    app.register_asset_loader(NavmeshDetailLoader);
+}
+
+/// The version of the layout of `.nav` and `.navdetail` files.
+/// Must be bumped whenever the serialized layout of [`Navmesh`] or [`DetailNavmesh`] changes.
+pub const NAV_FILE_VERSION: u16 = 3;
+
+/// Marks a `.nav` file.
+const MAGIC: [u8; 4] = *b"RRNV";
+
+/// Marks a `.navdetail` file.
+const DETAIL_MAGIC: [u8; 4] = *b"RRND";
+
+impl Navmesh {
+    /// Encodes the navmesh to the contents of a `.nav` file.
+    ///
+    /// Only the polygon mesh is written if the detail mesh was split off with [`Navmesh::split_detail`].
+    pub fn encode(&self) -> Result<Vec<u8>, NavFileError> {
+        encode_file(MAGIC, self)
+    }
+
+    /// Decodes the contents of a `.nav` file written by [`Navmesh::encode`].
+    pub fn decode(bytes: &[u8]) -> Result<Self, NavFileError> {
+        decode_file(MAGIC, bytes)
+    }
+}
+
+/// A detail mesh saved separately from its navmesh as a `.navdetail` file, so that it can be streamed in on demand.
+/// Attach it to the navmesh it was split off from with [`Navmesh::insert_detail`].
+#[derive(Asset, TypePath, Debug, Clone, PartialEq, Deref, DerefMut)]
+pub struct NavmeshDetail(pub DetailNavmesh);
+
+impl NavmeshDetail {
+    /// Encodes the detail mesh to the contents of a `.navdetail` file.
+    pub fn encode(&self) -> Result<Vec<u8>, NavFileError> {
+        encode_file(DETAIL_MAGIC, &self.0)
+    }
+
+    /// Decodes the contents of a `.navdetail` file written by [`NavmeshDetail::encode`].
+    pub fn decode(bytes: &[u8]) -> Result<Self, NavFileError> {
+        decode_file(DETAIL_MAGIC, bytes).map(Self)
+    }
+}
+
+/// Writes the header with the given magic number, followed by `value`, compressed.
+fn encode_file(magic: [u8; 4], value: &impl Serialize) -> Result<Vec<u8>, NavFileError> {
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(&magic)?;
+    gz.write_all(&NAV_FILE_VERSION.to_le_bytes())?;
+    bincode::serde::encode_into_std_write(value, &mut gz, bincode::config::standard())?;
+    Ok(gz.finish()?)
+}
+
+/// Checks the header written by [`encode_file`] and decodes the value after it.
+fn decode_file<T: DeserializeOwned>(magic: [u8; 4], bytes: &[u8]) -> Result<T, NavFileError> {
+    let mut gz = GzDecoder::new(bytes);
+    let mut header = [0; 6];
+    gz.read_exact(&mut header)
+        .map_err(|_| NavFileError::InvalidHeader)?;
+    if header[..4] != magic {
+        return Err(NavFileError::InvalidHeader);
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != NAV_FILE_VERSION {
+        return Err(NavFileError::UnsupportedVersion(version));
+    }
+    Ok(bincode::serde::decode_from_std_read(
+        &mut gz,
+        bincode::config::standard(),
+    )?)
+}
+
+/// Loads a [`Navmesh`] from a `.nav` file. Registered by [`RerecastPlugin`](crate::RerecastPlugin).
+#[derive(Debug, Default)]
+pub struct NavmeshLoader;
+
+impl AssetLoader for NavmeshLoader {
+    type Asset = Navmesh;
+    type Settings = ();
+    type Error = NavFileError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Navmesh::decode(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["nav"]
+    }
+}
+
+/// Loads a [`NavmeshDetail`] from a `.navdetail` file. Registered by [`RerecastPlugin`](crate::RerecastPlugin).
+#[derive(Debug, Default)]
+pub struct NavmeshDetailLoader;
+
+impl AssetLoader for NavmeshDetailLoader {
+    type Asset = NavmeshDetail;
+    type Settings = ();
+    type Error = NavFileError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        NavmeshDetail::decode(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["navdetail"]
+    }
+}
+
+/// Saves a [`Navmesh`] as a `.nav` file, e.g. as the last step of an asset processor.
+///
+/// To write a file directly, e.g. from an editor or a build script, use [`Navmesh::encode`] instead.
+#[derive(Debug, Default)]
+pub struct NavmeshSaver;
+
+impl AssetSaver for NavmeshSaver {
+    type Asset = Navmesh;
+    type Settings = ();
+    type OutputLoader = NavmeshLoader;
+    type Error = NavFileError;
+
+    async fn save(
+        &self,
+        writer: &mut Writer,
+        asset: SavedAsset<'_, Self::Asset>,
+        _settings: &(),
+    ) -> Result<(), Self::Error> {
+        writer.write_all(&asset.encode()?).await?;
+        Ok(())
+    }
+}
+
+/// Errors that can occur when loading or saving a `.nav` or `.navdetail` file.
+#[derive(Error, Debug)]
+pub enum NavFileError {
+    /// Happens when the file cannot be read or written.
+    #[error("Failed to access navmesh file: {0}")]
+    Io(#[from] std::io::Error),
+    /// Happens when the navmesh cannot be encoded.
+    #[error("Failed to encode navmesh file: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    /// Happens when the contents of the file are corrupted.
+    #[error("Failed to decode navmesh file: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+    /// Happens when the file is not a `.nav` file, or not a `.navdetail` file where one is expected.
+    #[error("Not a navmesh file")]
+    InvalidHeader,
+    /// Happens when the file was written with a different [`NAV_FILE_VERSION`].
+    #[error("Unsupported navmesh file version {0}, expected {NAV_FILE_VERSION}")]
+    UnsupportedVersion(u16),
+}
+
+#[cfg(test)]
+mod tests {
+    use rerecast::{NavmeshConfigBuilder, StressScene, build_navmesh};
+
+    use super::*;
+
+    fn navmesh() -> Navmesh {
+        let scene = StressScene::new(2).with_floors(2);
+        let config = NavmeshConfigBuilder {
+            aabb: scene.aabb(),
+            ..Default::default()
+        }
+        .build();
+        let (polygon, detail) = build_navmesh(scene.build(), &config).unwrap();
+        Navmesh::new(polygon, detail)
+    }
+
+    #[test]
+    fn navmeshes_survive_a_round_trip() {
+        let navmesh = navmesh();
+        let bytes = navmesh.encode().unwrap();
+        assert_eq!(Navmesh::decode(&bytes).unwrap(), navmesh);
+    }
+
+    #[test]
+    fn split_detail_meshes_survive_a_round_trip() {
+        let navmesh = navmesh();
+        let mut polygon_only = navmesh.clone();
+        let detail = NavmeshDetail(polygon_only.split_detail().unwrap());
+        let navmesh_bytes = polygon_only.encode().unwrap();
+        let detail_bytes = detail.encode().unwrap();
+
+        let mut loaded = Navmesh::decode(&navmesh_bytes).unwrap();
+        assert!(!loaded.detail_loaded());
+        let detail = NavmeshDetail::decode(&detail_bytes).unwrap();
+        assert_eq!(loaded.insert_detail(detail.0), Ok(None));
+        assert_eq!(loaded, navmesh);
+
+        // The two kinds of files cannot be mixed up.
+        assert!(matches!(
+            Navmesh::decode(&detail_bytes),
+            Err(NavFileError::InvalidHeader)
+        ));
+        assert!(matches!(
+            NavmeshDetail::decode(&navmesh_bytes),
+            Err(NavFileError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn detail_meshes_of_other_navmeshes_are_rejected() {
+        let mut navmesh = navmesh();
+        let mut detail = navmesh.split_detail().unwrap();
+        detail.meshes.pop();
+        assert_eq!(
+            navmesh.insert_detail(detail),
+            Err(crate::InsertDetailError::PolygonCountMismatch {
+                polygons: navmesh.polygon().polygon_count(),
+                detail_meshes: navmesh.polygon().polygon_count() - 1,
+            })
+        );
+        assert!(!navmesh.detail_loaded());
+    }
+
+    #[test]
+    fn files_of_other_versions_are_rejected() {
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&MAGIC).unwrap();
+        gz.write_all(&(NAV_FILE_VERSION + 1).to_le_bytes()).unwrap();
+        let bytes = gz.finish().unwrap();
+        assert!(matches!(
+            Navmesh::decode(&bytes),
+            Err(NavFileError::UnsupportedVersion(version)) if version == NAV_FILE_VERSION + 1
+        ));
+        assert!(matches!(
+            Navmesh::decode(b"not a navmesh"),
+            Err(NavFileError::InvalidHeader)
+        ));
+    }
+}
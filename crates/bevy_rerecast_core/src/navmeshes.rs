@@ -0,0 +1,229 @@
+//! A registry of named navmeshes, for games that need several navmeshes at once,
+//! e.g. one per agent size or one for swimming agents.
+//!
+//! Register the agent sizes in [`AgentProfiles`] to generate one navmesh per size
+//! with [`NavmeshGenerator::generate_for_agents`](crate::generator::NavmeshGenerator::generate_for_agents).
+
+use std::{borrow::Cow, fmt};
+
+use bevy_app::prelude::*;
+use bevy_asset::prelude::*;
+use bevy_derive::{Deref, DerefMut};
+use bevy_ecs::prelude::*;
+use bevy_platform::collections::HashMap;
+use bevy_reflect::prelude::*;
+use rerecast::{BuildStage, NavmeshConfigBuilder, TeleportAgent};
+
+use crate::{Navmesh, generator::NavmeshGenerationFailed};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Navmeshes>();
+    app.init_resource::<AgentProfiles>();
+    app.register_type::<AgentProfiles>();
+    app.add_event::<NavmeshReady>();
+    app.add_event::<NavmeshFailed>();
+    app.add_event::<NavmeshStepped>();
+}
+
+/// The name of a navmesh in [`Navmeshes`], e.g. `"human"`, `"large_bot"` or `"water"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct NavmeshKey(pub Cow<'static, str>);
+
+impl NavmeshKey {
+    /// Creates a key from a static string.
+    pub const fn new(name: &'static str) -> Self {
+        Self(Cow::Borrowed(name))
+    }
+
+    /// The name of the key.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for NavmeshKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&'static str> for NavmeshKey {
+    fn from(name: &'static str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for NavmeshKey {
+    fn from(name: String) -> Self {
+        Self(Cow::Owned(name))
+    }
+}
+
+/// The navmeshes of the game, by name.
+///
+/// Filled by [`NavmeshGenerator::generate_keyed`](crate::generator::NavmeshGenerator::generate_keyed).
+/// Navmeshes loaded from files can be registered with [`Navmeshes::insert`].
+#[derive(Resource, Debug, Default, Clone, Deref, DerefMut)]
+pub struct Navmeshes(HashMap<NavmeshKey, Handle<Navmesh>>);
+
+impl Navmeshes {
+    /// Returns the handle of the navmesh with the given name.
+    pub fn get(&self, key: impl Into<NavmeshKey>) -> Option<&Handle<Navmesh>> {
+        self.0.get(&key.into())
+    }
+
+    /// Registers a navmesh under the given name, returning the handle previously registered under it.
+    pub fn insert(
+        &mut self,
+        key: impl Into<NavmeshKey>,
+        handle: Handle<Navmesh>,
+    ) -> Option<Handle<Navmesh>> {
+        self.0.insert(key.into(), handle)
+    }
+
+    /// Unregisters the navmesh with the given name, returning its handle.
+    pub fn remove(&mut self, key: impl Into<NavmeshKey>) -> Option<Handle<Navmesh>> {
+        self.0.remove(&key.into())
+    }
+}
+
+/// The kinds of agents of the game by name, e.g. `"rat"` and `"ogre"`, each needing its own navmesh.
+///
+/// [`NavmeshGenerator::generate_for_agents`](crate::generator::NavmeshGenerator::generate_for_agents) generates
+/// one navmesh per profile and registers it in [`Navmeshes`] under the name of the profile, so that
+/// `navmeshes.get("ogre")` returns the navmesh of the ogres.
+/// Profiles can also be registered with [`RerecastPlugin::with_agent_profile`](crate::RerecastPlugin::with_agent_profile).
+#[derive(Resource, Debug, Default, Clone, Deref, DerefMut, Reflect)]
+#[reflect(Resource, Default)]
+pub struct AgentProfiles(HashMap<NavmeshKey, AgentProfile>);
+
+impl AgentProfiles {
+    /// Returns the profile with the given name.
+    pub fn get(&self, key: impl Into<NavmeshKey>) -> Option<&AgentProfile> {
+        self.0.get(&key.into())
+    }
+
+    /// Registers a profile under the given name, returning the profile previously registered under it.
+    pub fn insert(
+        &mut self,
+        key: impl Into<NavmeshKey>,
+        profile: AgentProfile,
+    ) -> Option<AgentProfile> {
+        self.0.insert(key.into(), profile)
+    }
+
+    /// Unregisters the profile with the given name, returning it.
+    /// The navmesh generated for it stays registered in [`Navmeshes`].
+    pub fn remove(&mut self, key: impl Into<NavmeshKey>) -> Option<AgentProfile> {
+        self.0.remove(&key.into())
+    }
+}
+
+/// The size and movement limits of a kind of agent, which replace those of the config its navmesh is generated with.
+/// All values are in world units, like the fields of [`NavmeshConfigBuilder`] they replace.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct AgentProfile {
+    /// See [`NavmeshConfigBuilder::agent_radius`].
+    pub radius: f32,
+    /// See [`NavmeshConfigBuilder::agent_height`].
+    pub height: f32,
+    /// See [`NavmeshConfigBuilder::agent_max_climb`].
+    pub max_climb: f32,
+    /// See [`NavmeshConfigBuilder::agent_max_slope`]. `[Units: Radians]`
+    pub max_slope: f32,
+}
+
+impl Default for AgentProfile {
+    fn default() -> Self {
+        Self::from(&NavmeshConfigBuilder::default())
+    }
+}
+
+impl From<&NavmeshConfigBuilder> for AgentProfile {
+    fn from(config: &NavmeshConfigBuilder) -> Self {
+        Self {
+            radius: config.agent_radius,
+            height: config.agent_height,
+            max_climb: config.agent_max_climb,
+            max_slope: config.agent_max_slope,
+        }
+    }
+}
+
+impl From<&AgentProfile> for TeleportAgent {
+    fn from(profile: &AgentProfile) -> Self {
+        Self {
+            radius: profile.radius,
+            height: profile.height,
+            max_climb: profile.max_climb,
+        }
+    }
+}
+
+impl AgentProfile {
+    /// Creates a profile with the given radius and height, climbing and walking slopes like the default config does.
+    pub fn new(radius: f32, height: f32) -> Self {
+        Self {
+            radius,
+            height,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the highest ledge the agent can step up. See [`Self::max_climb`].
+    pub fn with_max_climb(mut self, max_climb: f32) -> Self {
+        self.max_climb = max_climb;
+        self
+    }
+
+    /// Sets the steepest slope the agent can walk on, in radians. See [`Self::max_slope`].
+    pub fn with_max_slope(mut self, max_slope: f32) -> Self {
+        self.max_slope = max_slope;
+        self
+    }
+
+    /// Replaces the agent settings of `config` with this profile.
+    pub fn apply(&self, config: &mut NavmeshConfigBuilder) {
+        config.agent_radius = self.radius;
+        config.agent_height = self.height;
+        config.agent_max_climb = self.max_climb;
+        config.agent_max_slope = self.max_slope;
+    }
+}
+
+/// Sent when [`NavmeshGenerator`](crate::generator::NavmeshGenerator) finished generating a navmesh.
+#[derive(Event, Debug, Clone)]
+pub struct NavmeshReady {
+    /// The handle of the generated navmesh. The asset is available in [`Assets<Navmesh>`] when this event is read.
+    pub handle: Handle<Navmesh>,
+    /// The name of the navmesh, if it was generated through
+    /// [`NavmeshGenerator::generate_keyed`](crate::generator::NavmeshGenerator::generate_keyed).
+    pub key: Option<NavmeshKey>,
+}
+
+/// Sent when [`NavmeshGenerator`](crate::generator::NavmeshGenerator) failed to generate a navmesh.
+/// The asset of the handle is left as it was, i.e. it keeps the previous navmesh if there was one.
+#[derive(Event, Debug)]
+pub struct NavmeshFailed {
+    /// The handle the navmesh would have been stored under.
+    pub handle: Handle<Navmesh>,
+    /// The name of the navmesh, if it was generated through
+    /// [`NavmeshGenerator::generate_keyed`](crate::generator::NavmeshGenerator::generate_keyed).
+    pub key: Option<NavmeshKey>,
+    /// Why the navmesh could not be generated.
+    pub error: NavmeshGenerationFailed,
+}
+
+/// Sent when a build started with [`NavmeshGenerator::generate_stepped`](crate::generator::NavmeshGenerator::generate_stepped)
+/// finished a stage. Until the last stage is done, its results can be inspected
+/// through [`SteppedNavmeshBuilds::get`](crate::generator::SteppedNavmeshBuilds::get).
+#[derive(Event, Debug, Clone)]
+pub struct NavmeshStepped {
+    /// The handle the navmesh is stored under once the build is done.
+    pub handle: Handle<Navmesh>,
+    /// The stage that just finished. After [`BuildStage::DetailMesh`], [`NavmeshReady`] follows.
+    pub stage: BuildStage,
+}
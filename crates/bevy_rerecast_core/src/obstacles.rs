@@ -0,0 +1,327 @@
+//! Temporary obstacles like crates or turrets that are carved into a navmesh at runtime.
+//!
+//! Unlike navmesh affectors, obstacles are never rasterized. A [`CarvedNavmesh`] keeps the heightfields of its tiles
+//! in a [`TileCache`], so that adding, moving or removing an obstacle only rebuilds the regions, contours and polygons of the tiles it overlaps.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_platform::collections::HashMap;
+use bevy_reflect::prelude::*;
+use bevy_transform::{TransformSystem, prelude::*};
+use glam::{UVec2, Vec3};
+use rerecast::{
+    Aabb3d, BuildContext, NavmeshConfig, Obstacle, ObstacleId, TeleportDestination, TeleportError,
+    TileCache, TilePolygon, TriMesh,
+};
+
+use crate::{
+    NavmeshAreaFlags,
+    generator::{collect_affectors, finish_tile_polygons},
+    navmeshes::AgentProfile,
+    tiled::{NavmeshTilesRebuilt, rasterize_heightmaps},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<NavmeshObstacle>();
+    app.add_systems(
+        PostUpdate,
+        (build_tile_caches, sync_obstacles)
+            .chain()
+            .after(TransformSystem::TransformPropagate),
+    );
+}
+
+/// A temporary obstacle centered on the translation of this entity, carved into every [`CarvedNavmesh`].
+///
+/// Obstacles are cheap to add, move and remove, but only cut holes into the navmesh. Use a navmesh affector instead
+/// for anything that agents should be able to walk on.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+#[require(Transform)]
+pub enum NavmeshObstacle {
+    /// An upright cylinder. Rotation is ignored, the scale along the x-axis scales the radius.
+    Cylinder {
+        /// The radius of the cylinder.
+        radius: f32,
+        /// The height of the cylinder.
+        height: f32,
+    },
+    /// A box. Rotated boxes are carved as their axis-aligned bounds.
+    Box {
+        /// Half of the size of the box along each axis.
+        half_extents: Vec3,
+    },
+}
+
+impl NavmeshObstacle {
+    /// The obstacle in world space.
+    pub fn to_world(&self, transform: &GlobalTransform) -> Obstacle {
+        match *self {
+            NavmeshObstacle::Cylinder { radius, height } => {
+                let (scale, _, translation) = transform.to_scale_rotation_translation();
+                let height = height * scale.y;
+                Obstacle::Cylinder {
+                    base: translation - Vec3::Y * height / 2.0,
+                    radius: radius * scale.x,
+                    height,
+                }
+            }
+            NavmeshObstacle::Box { half_extents } => {
+                let affine = transform.affine();
+                let center = Vec3::from(affine.translation);
+                // The extents of the rotated and scaled box along each world axis.
+                let half_size = affine.matrix3.abs() * half_extents;
+                Obstacle::Box(Aabb3d::new(center, half_size))
+            }
+        }
+    }
+}
+
+/// Generates a navmesh for this entity that [`NavmeshObstacle`]s are carved into.
+///
+/// The navmesh is built from the navmesh affectors whenever this component is changed and written to the
+/// [`NavmeshTileCache`] of this entity. Afterwards, only obstacles are kept up to date; changes to the affectors
+/// are not picked up until this component is changed again.
+#[derive(Component, Debug, Clone)]
+pub struct CarvedNavmesh {
+    /// The config of the whole navmesh, see [`TileCache::build`].
+    /// If [`NavmeshConfig::aabb`] is left at its default, it is set to the bounds of all navmesh affectors.
+    pub config: NavmeshConfig,
+}
+
+impl CarvedNavmesh {
+    /// Creates a carved navmesh with the given config.
+    pub fn new(config: NavmeshConfig) -> Self {
+        Self { config }
+    }
+}
+
+/// The generated state of a [`CarvedNavmesh`]. Inserted and kept up to date automatically.
+///
+/// Rebuilt tiles are announced with [`NavmeshTilesRebuilt`].
+#[derive(Component, Debug, Clone)]
+pub struct NavmeshTileCache {
+    /// The tile cache holding the navmesh with all obstacles carved into it.
+    pub cache: TileCache,
+    obstacles: HashMap<Entity, (ObstacleId, Obstacle)>,
+    /// Whether the last update failed. It is only retried once the obstacles change again.
+    failed: bool,
+}
+
+impl NavmeshTileCache {
+    /// Checks whether an agent of the given profile standing at `from` can be teleported to `to`,
+    /// moving the destination out of carved obstacles and off walls if it lies just next to them.
+    ///
+    /// See [`TiledNavmesh::validate_teleport`](rerecast::TiledNavmesh::validate_teleport) for details.
+    pub fn validate_teleport(
+        &self,
+        from: Vec3,
+        to: Vec3,
+        agent: &AgentProfile,
+    ) -> Result<TeleportDestination<TilePolygon>, TeleportError> {
+        self.cache
+            .navmesh()
+            .validate_teleport(from, to, agent.into())
+    }
+}
+
+/// Marks a [`CarvedNavmesh`] whose tile cache failed to build, so that it is only retried once the component changes.
+#[derive(Component, Debug)]
+struct FailedTileCacheBuild;
+
+fn build_tile_caches(world: &mut World) {
+    let mut query = world.query_filtered::<(Entity, &CarvedNavmesh), Or<(
+        Changed<CarvedNavmesh>,
+        (Without<NavmeshTileCache>, Without<FailedTileCacheBuild>),
+    )>>();
+    let pending = query
+        .iter(world)
+        .map(|(entity, carved)| (entity, carved.config))
+        .collect::<Vec<_>>();
+    if pending.is_empty() {
+        return;
+    }
+    let (affectors, heightmaps) = match collect_affectors(world) {
+        Ok(input) => input.into_affectors_and_heightmaps(),
+        Err(err) => {
+            tracing::error!("Failed to build carved navmeshes: {err}");
+            for (entity, _) in pending {
+                world.entity_mut(entity).insert(FailedTileCacheBuild);
+            }
+            return;
+        }
+    };
+    let area_flags = world.get_resource::<NavmeshAreaFlags>().cloned();
+
+    for (entity, mut config) in pending {
+        let mut trimesh = TriMesh::default();
+        for affector in &affectors {
+            trimesh.extend(&affector.marked(config.walkable_slope_angle));
+        }
+        if config.aabb == Aabb3d::default()
+            && let Some(aabb) = trimesh
+                .compute_aabb()
+                .into_iter()
+                .chain(heightmaps.iter().filter_map(|h| h.heightmap.aabb()))
+                .reduce(|a, b| Aabb3d {
+                    min: a.min.min(b.min),
+                    max: a.max.max(b.max),
+                })
+        {
+            config.aabb = aabb;
+        }
+        let built = TileCache::build_premarked_with_rasterizer(
+            trimesh,
+            &config,
+            &BuildContext::default(),
+            |heightfield| rasterize_heightmaps(&heightmaps, heightfield, &config),
+        );
+        match built {
+            Ok(mut cache) => {
+                let tiles = cache
+                    .navmesh()
+                    .tiles
+                    .iter()
+                    .map(|tile| tile.coord)
+                    .collect::<Vec<_>>();
+                finish_cache_tiles(&mut cache, &tiles, area_flags.as_ref());
+                world
+                    .entity_mut(entity)
+                    .remove::<FailedTileCacheBuild>()
+                    .insert(NavmeshTileCache {
+                        cache,
+                        obstacles: HashMap::default(),
+                        failed: false,
+                    });
+                world.send_event(NavmeshTilesRebuilt { entity, tiles });
+            }
+            // Not retried until the `CarvedNavmesh` changes, just like a failed build of an `IncrementalNavmesh`.
+            Err(err) => {
+                tracing::error!("Failed to build carved navmesh for {entity}: {err}");
+                world.entity_mut(entity).insert(FailedTileCacheBuild);
+            }
+        }
+    }
+}
+
+/// Applies the [`NavmeshAreaFlags`] to the freshly built tiles of a tile cache.
+fn finish_cache_tiles(
+    cache: &mut TileCache,
+    tiles: &[UVec2],
+    area_flags: Option<&NavmeshAreaFlags>,
+) {
+    for &coord in tiles {
+        if let Some(polygon) = cache.polygon_mesh_mut(coord) {
+            finish_tile_polygons(polygon, area_flags);
+        }
+    }
+}
+
+fn sync_obstacles(
+    mut caches: Query<(Entity, &mut NavmeshTileCache)>,
+    obstacles: Query<(Entity, &NavmeshObstacle, &GlobalTransform)>,
+    area_flags: Option<Res<NavmeshAreaFlags>>,
+    mut rebuilt: EventWriter<NavmeshTilesRebuilt>,
+) {
+    for (entity, mut tile_cache) in &mut caches {
+        let NavmeshTileCache {
+            cache,
+            obstacles: tracked,
+            failed,
+        } = tile_cache.bypass_change_detection();
+        let mut previous = std::mem::take(tracked);
+        let mut changed = false;
+        for (obstacle_entity, obstacle, transform) in &obstacles {
+            let obstacle = obstacle.to_world(transform);
+            let id = match previous.remove(&obstacle_entity) {
+                Some((id, old)) if old == obstacle => id,
+                Some((id, _)) => {
+                    changed = true;
+                    cache.remove_obstacle(id);
+                    cache.add_obstacle(obstacle)
+                }
+                None => {
+                    changed = true;
+                    cache.add_obstacle(obstacle)
+                }
+            };
+            tracked.insert(obstacle_entity, (id, obstacle));
+        }
+        for (id, _) in previous.into_values() {
+            changed = true;
+            cache.remove_obstacle(id);
+        }
+        // The affected tiles stay dirty after a failed update, but are only tried again once the obstacles change.
+        if !cache.needs_update() || (*failed && !changed) {
+            continue;
+        }
+
+        let result = cache.update();
+        *failed = result.is_err();
+        match result {
+            Ok(tiles) => {
+                finish_cache_tiles(cache, &tiles, area_flags.as_deref());
+                tile_cache.set_changed();
+                rebuilt.write(NavmeshTilesRebuilt { entity, tiles });
+            }
+            Err(err) => tracing::error!("Failed to carve obstacles into navmesh {entity}: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3A};
+
+    use super::*;
+    use crate::{NavmeshApp as _, backend::NavmeshAffectorAreas};
+
+    fn floor() -> Vec<(Entity, GlobalTransform, TriMesh)> {
+        let trimesh = TriMesh::from_parts(
+            vec![
+                Vec3A::new(-5.0, 0.0, -5.0),
+                Vec3A::new(-5.0, 0.0, 5.0),
+                Vec3A::new(5.0, 0.0, 5.0),
+                Vec3A::new(5.0, 0.0, -5.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        vec![(Entity::PLACEHOLDER, GlobalTransform::IDENTITY, trimesh)]
+    }
+
+    #[test]
+    fn failed_builds_are_only_retried_once_the_config_changes() {
+        let mut app = App::new();
+        app.add_event::<NavmeshTilesRebuilt>()
+            .init_resource::<NavmeshAffectorAreas>()
+            .add_navmesh_affector_backend("floor", floor)
+            .add_systems(Update, build_tile_caches);
+        let mut config = rerecast::NavmeshConfigBuilder {
+            tile_size: 0,
+            ..Default::default()
+        }
+        .build();
+        let entity = app.world_mut().spawn(CarvedNavmesh::new(config)).id();
+        app.update();
+        assert!(app.world().get::<FailedTileCacheBuild>(entity).is_some());
+
+        // Fixing the config behind the back of change detection must not trigger another build.
+        config.tile_size = 16;
+        app.world_mut()
+            .get_mut::<CarvedNavmesh>(entity)
+            .unwrap()
+            .bypass_change_detection()
+            .config = config;
+        app.update();
+        assert!(app.world().get::<NavmeshTileCache>(entity).is_none());
+
+        app.world_mut()
+            .get_mut::<CarvedNavmesh>(entity)
+            .unwrap()
+            .set_changed();
+        app.update();
+        assert!(app.world().get::<NavmeshTileCache>(entity).is_some());
+        assert!(app.world().get::<FailedTileCacheBuild>(entity).is_none());
+    }
+}
@@ -0,0 +1,74 @@
+//! Off-mesh connections authored as entities, e.g. jumps, ladders and teleporters.
+
+use bevy_app::prelude::*;
+use bevy_derive::{Deref, DerefMut};
+use bevy_ecs::prelude::*;
+use bevy_reflect::prelude::*;
+use bevy_transform::prelude::*;
+use glam::Vec3;
+use rerecast::{AreaType, OffMeshConnection};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<NavmeshOffMeshConnection>();
+}
+
+/// An off-mesh connection whose endpoints are relative to the transform of this entity.
+///
+/// Baked into every navmesh generated by [`NavmeshGenerator`](crate::generator::NavmeshGenerator) afterwards,
+/// see [`PolygonNavmesh::bake_off_mesh_connections`](rerecast::PolygonNavmesh::bake_off_mesh_connections).
+/// Navmeshes that were already generated are not updated when the connection changes.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default, Deref, DerefMut, Reflect)]
+#[reflect(Component)]
+#[require(Transform)]
+pub struct NavmeshOffMeshConnection(pub OffMeshConnection);
+
+impl NavmeshOffMeshConnection {
+    /// Creates a bidirectional connection from the translation of this entity to `end`, relative to its transform.
+    pub fn new(end: Vec3) -> Self {
+        Self(OffMeshConnection {
+            end,
+            ..Default::default()
+        })
+    }
+
+    /// Sets [`OffMeshConnection::radius`].
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Sets [`OffMeshConnection::bidirectional`].
+    pub fn with_bidirectional(mut self, bidirectional: bool) -> Self {
+        self.bidirectional = bidirectional;
+        self
+    }
+
+    /// Sets [`OffMeshConnection::area`].
+    pub fn with_area(mut self, area: AreaType) -> Self {
+        self.area = area;
+        self
+    }
+
+    /// The connection in world space for an entity with the given transform.
+    pub fn to_world(&self, transform: &GlobalTransform) -> OffMeshConnection {
+        OffMeshConnection {
+            start: transform.transform_point(self.start),
+            end: transform.transform_point(self.end),
+            ..self.0
+        }
+    }
+}
+
+/// The connections of all entities with a [`NavmeshOffMeshConnection`], in world space.
+pub(crate) fn collect_off_mesh_connections(world: &mut World) -> Vec<OffMeshConnection> {
+    let mut connections = world
+        .query::<(Entity, &NavmeshOffMeshConnection, &GlobalTransform)>()
+        .iter(world)
+        .collect::<Vec<_>>();
+    // Sorted so that the connections are baked in the same order on every build.
+    connections.sort_by_key(|(entity, ..)| *entity);
+    connections
+        .into_iter()
+        .map(|(_, connection, transform)| connection.to_world(transform))
+        .collect()
+}
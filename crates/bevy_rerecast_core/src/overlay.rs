@@ -0,0 +1,463 @@
+//! An in-game overlay for tuning navmesh settings inside the game itself, without running the separate editor.
+//!
+//! Add [`RerecastEditorOverlayPlugin`] to a development build. Pressing [`RerecastEditorOverlayPlugin::toggle_key`]
+//! shows a panel that edits the [`DefaultNavmeshConfig`], rebuilds a navmesh with it and toggles the layers
+//! of the [`NavmeshDebugPlugin`], which is added as well if it is missing.
+
+use bevy_app::prelude::*;
+use bevy_color::Color;
+use bevy_ecs::prelude::*;
+use bevy_input::{ButtonInput, keyboard::KeyCode};
+use bevy_text::{TextColor, TextFont};
+use bevy_ui::{Val::*, prelude::*};
+use rerecast::{ConfigField, ConfigRounding, ConfigUnit, NavmeshConfigBuilder};
+
+use crate::{
+    DefaultNavmeshConfig,
+    debug::{NavmeshDebugPlugin, NavmeshDebugSettings},
+    diagnostics::NavmeshBuildStats,
+    generator::NavmeshGenerator,
+    navmeshes::{NavmeshFailed, NavmeshKey},
+};
+
+/// Shows a panel inside the game for editing the [`DefaultNavmeshConfig`], rebuilding the navmesh registered under
+/// [`Self::navmesh`] with it, and toggling the layers of the [`NavmeshDebugPlugin`].
+///
+/// Requires the `UiPlugin` of Bevy and a camera rendering the UI.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RerecastEditorOverlayPlugin {
+    /// The key that shows and hides the overlay. Defaults to [`KeyCode::F9`].
+    pub toggle_key: KeyCode,
+    /// The navmesh in [`Navmeshes`](crate::navmeshes::Navmeshes) that the build button regenerates in place.
+    /// Defaults to `"default"`.
+    pub navmesh: NavmeshKey,
+    /// Whether the overlay is shown right away. Defaults to `false`.
+    pub visible: bool,
+}
+
+impl Default for RerecastEditorOverlayPlugin {
+    fn default() -> Self {
+        Self {
+            toggle_key: KeyCode::F9,
+            navmesh: NavmeshKey::new("default"),
+            visible: false,
+        }
+    }
+}
+
+impl RerecastEditorOverlayPlugin {
+    /// Sets the key that shows and hides the overlay. See [`Self::toggle_key`].
+    pub fn with_toggle_key(mut self, key: KeyCode) -> Self {
+        self.toggle_key = key;
+        self
+    }
+
+    /// Sets the navmesh the build button regenerates. See [`Self::navmesh`].
+    pub fn with_navmesh(mut self, key: impl Into<NavmeshKey>) -> Self {
+        self.navmesh = key.into();
+        self
+    }
+
+    /// Sets whether the overlay is shown right away. See [`Self::visible`].
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+}
+
+impl Plugin for RerecastEditorOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<NavmeshDebugPlugin>() {
+            app.add_plugins(NavmeshDebugPlugin::default());
+        }
+        app.insert_resource(EditorOverlay {
+            toggle_key: self.toggle_key,
+            navmesh: self.navmesh.clone(),
+            visible: self.visible,
+        });
+        app.add_systems(Startup, spawn_overlay);
+        app.add_systems(
+            Update,
+            (
+                toggle_overlay,
+                press_buttons,
+                update_fields.run_if(
+                    resource_changed::<DefaultNavmeshConfig>
+                        .or(resource_changed::<NavmeshDebugSettings>),
+                ),
+                update_status,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The state of the overlay added by [`RerecastEditorOverlayPlugin`].
+#[derive(Resource, Debug, Clone)]
+pub struct EditorOverlay {
+    /// The key that shows and hides the overlay.
+    pub toggle_key: KeyCode,
+    /// The navmesh that the build button regenerates.
+    pub navmesh: NavmeshKey,
+    /// Whether the overlay is shown.
+    pub visible: bool,
+}
+
+/// The root node of the overlay.
+#[derive(Component)]
+struct OverlayRoot;
+
+/// What a button of the overlay does when pressed.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum OverlayButton {
+    Decrease(ConfigField),
+    Increase(ConfigField),
+    Toggle(DebugLayer),
+    Build,
+    Reset,
+}
+
+/// The text showing the value of a [`ConfigField`].
+#[derive(Component)]
+struct FieldValue(ConfigField);
+
+/// The text showing the unit of a [`ConfigField`].
+#[derive(Component)]
+struct FieldUnit(ConfigField);
+
+/// The text showing the outcome of the last build.
+#[derive(Component)]
+struct OverlayStatus;
+
+const PANEL_BACKGROUND: Color = Color::srgba(0.1, 0.1, 0.1, 0.85);
+const BUTTON_BACKGROUND: Color = Color::srgb(0.25, 0.25, 0.25);
+const BUTTON_ACTIVE: Color = Color::srgb(0.2, 0.45, 0.65);
+const LABEL_TEXT: Color = Color::srgb(0.9, 0.9, 0.9);
+
+fn spawn_overlay(overlay: Res<EditorOverlay>, mut commands: Commands) {
+    let root = commands
+        .spawn((
+            Name::new("Navmesh Editor Overlay"),
+            OverlayRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Px(10.0),
+                right: Px(10.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Px(4.0),
+                padding: UiRect::all(Px(10.0)),
+                display: if overlay.visible {
+                    Display::Flex
+                } else {
+                    Display::None
+                },
+                ..Default::default()
+            },
+            BackgroundColor(PANEL_BACKGROUND),
+            GlobalZIndex(i32::MAX),
+        ))
+        .id();
+    commands.spawn((
+        ChildOf(root),
+        text(format!("Navmesh \"{}\"", overlay.navmesh), 16.0),
+    ));
+    for field in ConfigField::all() {
+        let row = commands.spawn((ChildOf(root), row())).id();
+        commands.spawn((
+            ChildOf(row),
+            Node {
+                width: Px(130.0),
+                ..Default::default()
+            },
+            text(field.title(), 13.0),
+        ));
+        commands.spawn((ChildOf(row), button("-", OverlayButton::Decrease(field))));
+        commands.spawn((
+            ChildOf(row),
+            FieldValue(field),
+            Node {
+                width: Px(50.0),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            text("", 13.0),
+        ));
+        commands.spawn((ChildOf(row), button("+", OverlayButton::Increase(field))));
+        commands.spawn((
+            ChildOf(row),
+            FieldUnit(field),
+            Node {
+                width: Px(90.0),
+                ..Default::default()
+            },
+            text("", 12.0),
+        ));
+    }
+
+    let toggles = commands
+        .spawn((
+            ChildOf(root),
+            Node {
+                flex_wrap: FlexWrap::Wrap,
+                max_width: Px(260.0),
+                column_gap: Px(4.0),
+                row_gap: Px(4.0),
+                margin: UiRect::vertical(Px(4.0)),
+                ..Default::default()
+            },
+        ))
+        .id();
+    for layer in DebugLayer::ALL {
+        commands.spawn((
+            ChildOf(toggles),
+            button(layer.name(), OverlayButton::Toggle(layer)),
+        ));
+    }
+
+    let actions = commands.spawn((ChildOf(root), row())).id();
+    commands.spawn((ChildOf(actions), button("Build", OverlayButton::Build)));
+    commands.spawn((
+        ChildOf(actions),
+        button("Reset Config", OverlayButton::Reset),
+    ));
+    commands.spawn((
+        ChildOf(root),
+        OverlayStatus,
+        Node {
+            max_width: Px(260.0),
+            ..Default::default()
+        },
+        text(
+            format!("Press {:?} to hide this overlay", overlay.toggle_key),
+            12.0,
+        ),
+    ));
+}
+
+fn row() -> impl Bundle {
+    Node {
+        align_items: AlignItems::Center,
+        column_gap: Px(4.0),
+        ..Default::default()
+    }
+}
+
+fn text(text: impl Into<String>, font_size: f32) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont::from_font_size(font_size),
+        TextColor(LABEL_TEXT),
+    )
+}
+
+fn button(label: &str, action: OverlayButton) -> impl Bundle {
+    (
+        Name::new(format!("{label} Button")),
+        Button,
+        action,
+        Node {
+            padding: UiRect::axes(Px(6.0), Px(2.0)),
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        },
+        BackgroundColor(BUTTON_BACKGROUND),
+        children![text(label, 13.0)],
+    )
+}
+
+fn toggle_overlay(
+    input: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<EditorOverlay>,
+    mut roots: Query<&mut Node, With<OverlayRoot>>,
+) {
+    if !input.just_pressed(overlay.toggle_key) {
+        return;
+    }
+    overlay.visible = !overlay.visible;
+    for mut node in &mut roots {
+        node.display = if overlay.visible {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+fn press_buttons(
+    buttons: Query<(&Interaction, &OverlayButton), Changed<Interaction>>,
+    overlay: Res<EditorOverlay>,
+    mut config: ResMut<DefaultNavmeshConfig>,
+    mut settings: ResMut<NavmeshDebugSettings>,
+    mut generator: NavmeshGenerator<()>,
+    mut status: Query<&mut Text, With<OverlayStatus>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *button {
+            OverlayButton::Decrease(field) => step_field(field, &mut config, -1.0),
+            OverlayButton::Increase(field) => step_field(field, &mut config, 1.0),
+            OverlayButton::Toggle(layer) => {
+                let enabled = layer.get(&settings);
+                layer.set(&mut settings, !enabled);
+            }
+            OverlayButton::Build => {
+                generator.generate_keyed(overlay.navmesh.clone(), config.build());
+                for mut text in &mut status {
+                    text.0 = "Building...".to_string();
+                }
+            }
+            OverlayButton::Reset => **config = NavmeshConfigBuilder::default(),
+        }
+    }
+}
+
+fn update_fields(
+    config: Res<DefaultNavmeshConfig>,
+    settings: Res<NavmeshDebugSettings>,
+    mut values: Query<(&FieldValue, &mut Text), Without<FieldUnit>>,
+    mut units: Query<(&FieldUnit, &mut Text), Without<FieldValue>>,
+    mut buttons: Query<(&OverlayButton, &mut BackgroundColor)>,
+) {
+    for (value, mut text) in &mut values {
+        text.0 = format_field(value.0, &config);
+    }
+    for (unit, mut text) in &mut units {
+        text.0 = format_unit(unit.0, &config);
+    }
+    for (button, mut background) in &mut buttons {
+        if let OverlayButton::Toggle(layer) = button {
+            background.0 = if layer.get(&settings) {
+                BUTTON_ACTIVE
+            } else {
+                BUTTON_BACKGROUND
+            };
+        }
+    }
+}
+
+fn update_status(
+    overlay: Res<EditorOverlay>,
+    stats: Option<Res<NavmeshBuildStats>>,
+    mut failures: EventReader<NavmeshFailed>,
+    mut status: Query<&mut Text, With<OverlayStatus>>,
+) {
+    let message = if let Some(failure) = failures
+        .read()
+        .filter(|failure| failure.key.as_ref() == Some(&overlay.navmesh))
+        .last()
+    {
+        format!("Build failed: {}", failure.error)
+    } else if let Some(stats) = stats.filter(|stats| stats.is_changed()) {
+        format!(
+            "Built {} polygons from {} affectors in {:.1} ms",
+            stats.polygon_count,
+            stats.affector_count,
+            stats.total_duration.as_secs_f32() * 1000.0
+        )
+    } else {
+        return;
+    };
+    for mut text in &mut status {
+        text.0.clone_from(&message);
+    }
+}
+
+/// Changes the value of `field` by `direction` steps, clamped to its range.
+fn step_field(field: ConfigField, config: &mut NavmeshConfigBuilder, direction: f32) {
+    let step = field.step();
+    let (min, max) = field.range();
+    // Rounding to the step keeps repeated presses from accumulating floating point errors.
+    let value = ((field.get(config) / step).round() + direction) * step;
+    field.set(config, value.clamp(min, max));
+}
+
+fn format_field(field: ConfigField, config: &NavmeshConfigBuilder) -> String {
+    let value = field.get(config);
+    if field.is_integer() || field.unit() == Some(ConfigUnit::Radians) {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+    }
+}
+
+/// The unit of `field`, along with its value converted to the other unit, based on the current cell size.
+fn format_unit(field: ConfigField, config: &NavmeshConfigBuilder) -> String {
+    let unit = match field.unit() {
+        Some(
+            ConfigUnit::WorldUnits
+            | ConfigUnit::HorizontalWorldUnits
+            | ConfigUnit::VerticalWorldUnits,
+        ) => "wu",
+        Some(ConfigUnit::Voxels) => "vx",
+        Some(ConfigUnit::CellSizes) => "cs",
+        Some(ConfigUnit::CellHeights) => "ch",
+        Some(ConfigUnit::Radians) => "deg",
+        Some(ConfigUnit::Count) | None => return String::new(),
+    };
+    if field.is_disabled(config) {
+        format!("{unit} (off)")
+    } else if let Some(cells) = field.cells(config) {
+        if field.info().has_attribute::<ConfigRounding>() {
+            format!("{unit} ({cells} vx)")
+        } else {
+            format!("{unit} ({cells:.1} vx)")
+        }
+    } else if let Some(world_units) = field.world_units(config) {
+        format!("{unit} ({world_units:.2} wu)")
+    } else {
+        unit.to_string()
+    }
+}
+
+/// A layer of [`NavmeshDebugSettings`] that can be toggled in the overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DebugLayer {
+    Polygons,
+    PolygonOutlines,
+    DetailMesh,
+    RegionBoundaries,
+    Contours,
+}
+
+impl DebugLayer {
+    const ALL: [Self; 5] = [
+        Self::Polygons,
+        Self::PolygonOutlines,
+        Self::DetailMesh,
+        Self::RegionBoundaries,
+        Self::Contours,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Polygons => "Polygons",
+            Self::PolygonOutlines => "Outlines",
+            Self::DetailMesh => "Detail Mesh",
+            Self::RegionBoundaries => "Regions",
+            Self::Contours => "Contours",
+        }
+    }
+
+    fn get(self, settings: &NavmeshDebugSettings) -> bool {
+        match self {
+            Self::Polygons => settings.polygons,
+            Self::PolygonOutlines => settings.polygon_outlines,
+            Self::DetailMesh => settings.detail_mesh,
+            Self::RegionBoundaries => settings.region_boundaries,
+            Self::Contours => settings.contours,
+        }
+    }
+
+    fn set(self, settings: &mut NavmeshDebugSettings, enabled: bool) {
+        match self {
+            Self::Polygons => settings.polygons = enabled,
+            Self::PolygonOutlines => settings.polygon_outlines = enabled,
+            Self::DetailMesh => settings.detail_mesh = enabled,
+            Self::RegionBoundaries => settings.region_boundaries = enabled,
+            Self::Contours => settings.contours = enabled,
+        }
+    }
+}
@@ -0,0 +1,395 @@
+//! Streaming the tiles of a [`TiledNavmesh`] from disk based on the position of the player, so that huge worlds
+//! only keep the tiles around the player in memory.
+//!
+//! Save the navmesh with [`TiledNavmesh::save_tiles`] into the assets folder, add the [`NavmeshStreamer`] plugin,
+//! spawn an entity with a [`StreamedNavmesh`] pointing to the saved directory and mark the player with a [`NavmeshStreamingAnchor`].
+//! Tiles are loaded asynchronously through the [`AssetServer`] and inserted into the [`NavmeshTileStore`] of the entity's [`StreamedNavmeshTiles`].
+
+use bevy_app::prelude::*;
+use bevy_asset::{AssetLoader, LoadContext, LoadState, io::Reader, prelude::*};
+use bevy_derive::Deref;
+use bevy_ecs::prelude::*;
+use bevy_platform::collections::{HashMap, HashSet};
+use bevy_reflect::prelude::*;
+use bevy_transform::{TransformSystem, prelude::*};
+use glam::{UVec2, Vec2, Vec3Swizzles as _};
+use rerecast::{NavmeshConfig, NavmeshTile, TileFileError, TileManifest, TiledNavmesh};
+use thiserror::Error;
+
+use crate::tile_store::NavmeshTileStore;
+
+/// Streams the tiles of every [`StreamedNavmesh`] in and out depending on their distance to the [`NavmeshStreamingAnchor`]s.
+///
+/// Also inserted as a resource, so the radii can be changed at runtime.
+#[derive(Resource, Debug, Clone, Copy, Reflect)]
+#[reflect(Resource)]
+#[non_exhaustive]
+pub struct NavmeshStreamer {
+    /// Tiles that are at most this far away from any anchor on the xz-plane are loaded. Defaults to `50.0`.
+    pub radius: f32,
+    /// Tiles that are farther away than this from every anchor on the xz-plane are unloaded. Defaults to `75.0`.
+    ///
+    /// Should be larger than [`NavmeshStreamer::radius`], so that tiles on the edge are not loaded and unloaded
+    /// over and over again while the player walks back and forth.
+    pub unload_radius: f32,
+}
+
+impl Default for NavmeshStreamer {
+    fn default() -> Self {
+        Self {
+            radius: 50.0,
+            unload_radius: 75.0,
+        }
+    }
+}
+
+impl NavmeshStreamer {
+    /// Sets [`NavmeshStreamer::radius`], and [`NavmeshStreamer::unload_radius`] to one and a half times of it.
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self.unload_radius = radius * 1.5;
+        self
+    }
+
+    /// Sets [`NavmeshStreamer::unload_radius`].
+    pub fn with_unload_radius(mut self, unload_radius: f32) -> Self {
+        self.unload_radius = unload_radius;
+        self
+    }
+}
+
+impl Plugin for NavmeshStreamer {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(*self);
+        app.register_type::<(NavmeshStreamer, StreamedNavmesh, NavmeshStreamingAnchor)>();
+        app.init_asset::<NavmeshTileManifest>();
+        app.init_asset::<NavmeshTileAsset>();
+        app.register_asset_loader(NavmeshTileManifestLoader);
+        app.register_asset_loader(NavmeshTileLoader);
+        app.add_event::<NavmeshTilesStreamed>();
+        app.add_systems(
+            PostUpdate,
+            (load_manifests, start_streaming, stream_tiles)
+                .chain()
+                .after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Streams the tiles of a navmesh saved with [`TiledNavmesh::save_tiles`] into the [`StreamedNavmeshTiles`] of this entity.
+///
+/// The tiles are placed in world space as they were built, regardless of the transform of this entity.
+/// Changing this component starts streaming from scratch.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct StreamedNavmesh {
+    /// The asset path of the directory the tiles were saved to, e.g. `"navmeshes/world"`.
+    pub path: String,
+}
+
+impl StreamedNavmesh {
+    /// Streams the tiles saved to the directory at the given asset path.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn manifest_path(&self) -> String {
+        format!("{}/{}", self.path, TileManifest::FILE_NAME)
+    }
+
+    fn tile_path(&self, coord: UVec2) -> String {
+        format!("{}/{}", self.path, TileManifest::tile_file_name(coord))
+    }
+}
+
+/// Marks an entity, usually the player or the camera, around which the tiles of every [`StreamedNavmesh`] are loaded.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component, Default)]
+#[require(Transform)]
+pub struct NavmeshStreamingAnchor;
+
+/// The streamed state of a [`StreamedNavmesh`]. Inserted once its manifest is loaded and kept up to date automatically.
+#[derive(Component, Debug, Clone)]
+pub struct StreamedNavmeshTiles {
+    store: NavmeshTileStore,
+    saved: HashSet<UVec2>,
+    loading: HashMap<UVec2, Handle<NavmeshTileAsset>>,
+    failed: HashSet<UVec2>,
+}
+
+impl StreamedNavmeshTiles {
+    /// The tiles that are currently loaded. Tiles that were saved without polygons are never loaded.
+    pub fn store(&self) -> &NavmeshTileStore {
+        &self.store
+    }
+
+    /// The navmesh with all tiles that are currently loaded. All other tiles are empty and not linked to their neighbors,
+    /// so queries transparently span exactly the loaded tiles.
+    pub fn navmesh(&self) -> &TiledNavmesh {
+        self.store.navmesh()
+    }
+
+    /// The config the navmesh was built with.
+    pub fn config(&self) -> &NavmeshConfig {
+        self.store.config()
+    }
+
+    /// Whether the tile at `coord` is loaded. Tiles that were saved without polygons are never loaded.
+    pub fn is_loaded(&self, coord: UVec2) -> bool {
+        self.store.is_loaded(coord)
+    }
+
+    /// The coordinates of all loaded tiles.
+    pub fn loaded_tiles(&self) -> impl Iterator<Item = UVec2> + '_ {
+        self.store.loaded_tiles()
+    }
+}
+
+/// Sent when tiles of a [`StreamedNavmesh`] were loaded or unloaded.
+#[derive(Event, Debug, Clone)]
+pub struct NavmeshTilesStreamed {
+    /// The entity holding the [`StreamedNavmesh`].
+    pub entity: Entity,
+    /// The coordinates of the tiles that were loaded.
+    pub loaded: Vec<UVec2>,
+    /// The coordinates of the tiles that were unloaded.
+    pub unloaded: Vec<UVec2>,
+}
+
+/// A [`TileManifest`] loaded from a `.navtiles` file.
+#[derive(Asset, TypePath, Debug, Clone, Deref)]
+pub struct NavmeshTileManifest(pub TileManifest);
+
+/// A [`NavmeshTile`] loaded from a `.navtile` file.
+#[derive(Asset, TypePath, Debug, Clone, Deref)]
+pub struct NavmeshTileAsset(pub NavmeshTile);
+
+/// Loads a [`NavmeshTileManifest`].
+#[derive(Debug, Default)]
+pub struct NavmeshTileManifestLoader;
+
+impl AssetLoader for NavmeshTileManifestLoader {
+    type Asset = NavmeshTileManifest;
+    type Settings = ();
+    type Error = NavmeshTileLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(NavmeshTileManifest(TileManifest::decode(&bytes)?))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["navtiles"]
+    }
+}
+
+/// Loads a [`NavmeshTileAsset`].
+#[derive(Debug, Default)]
+pub struct NavmeshTileLoader;
+
+impl AssetLoader for NavmeshTileLoader {
+    type Asset = NavmeshTileAsset;
+    type Settings = ();
+    type Error = NavmeshTileLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(NavmeshTileAsset(NavmeshTile::decode(&bytes)?))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["navtile"]
+    }
+}
+
+/// Errors that can occur when loading a [`NavmeshTileManifest`] or [`NavmeshTileAsset`].
+#[derive(Error, Debug)]
+pub enum NavmeshTileLoadError {
+    /// Happens when the file cannot be read.
+    #[error("Failed to read tile file: {0}")]
+    Io(#[from] std::io::Error),
+    /// Happens when the file cannot be decoded.
+    #[error(transparent)]
+    File(#[from] TileFileError),
+}
+
+/// The manifest of a [`StreamedNavmesh`] that is being loaded.
+#[derive(Component)]
+struct LoadingManifest(Handle<NavmeshTileManifest>);
+
+fn load_manifests(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    navmeshes: Query<(Entity, &StreamedNavmesh), Changed<StreamedNavmesh>>,
+) {
+    for (entity, streamed) in &navmeshes {
+        let manifest = asset_server.load(streamed.manifest_path());
+        commands
+            .entity(entity)
+            .remove::<StreamedNavmeshTiles>()
+            .insert(LoadingManifest(manifest));
+    }
+}
+
+fn start_streaming(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    manifests: Res<Assets<NavmeshTileManifest>>,
+    loading: Query<(Entity, &StreamedNavmesh, &LoadingManifest)>,
+) {
+    for (entity, streamed, LoadingManifest(handle)) in &loading {
+        if let Some(manifest) = manifests.get(handle) {
+            commands.entity(entity).remove::<LoadingManifest>();
+            match NavmeshTileStore::new(manifest.config) {
+                Ok(store) => {
+                    commands.entity(entity).insert(StreamedNavmeshTiles {
+                        store,
+                        saved: manifest.tiles.iter().copied().collect(),
+                        loading: HashMap::default(),
+                        failed: HashSet::default(),
+                    });
+                }
+                Err(err) => tracing::error!(
+                    "Failed to stream navmesh \"{}\" for {entity}: {err}",
+                    streamed.path
+                ),
+            }
+        } else if let LoadState::Failed(err) = asset_server.load_state(handle) {
+            commands.entity(entity).remove::<LoadingManifest>();
+            tracing::error!(
+                "Failed to load navmesh manifest \"{}\" for {entity}: {err}",
+                streamed.path
+            );
+        }
+    }
+}
+
+fn stream_tiles(
+    streamer: Res<NavmeshStreamer>,
+    asset_server: Res<AssetServer>,
+    mut tile_assets: ResMut<Assets<NavmeshTileAsset>>,
+    anchors: Query<&GlobalTransform, With<NavmeshStreamingAnchor>>,
+    mut navmeshes: Query<(Entity, &StreamedNavmesh, &mut StreamedNavmeshTiles)>,
+    mut streamed_events: EventWriter<NavmeshTilesStreamed>,
+) {
+    let anchors = anchors
+        .iter()
+        .map(|transform| transform.translation().xz())
+        .collect::<Vec<_>>();
+    for (entity, streamed, mut tiles) in &mut navmeshes {
+        let tiles = &mut *tiles;
+        let config = *tiles.config();
+
+        let pending = tiles.loading.keys().copied().collect::<Vec<_>>();
+        for coord in pending {
+            let handle = &tiles.loading[&coord];
+            // Taking the tile out of the assets and dropping the handle afterwards frees the asset,
+            // so that only the copy in the navmesh stays in memory.
+            if let Some(NavmeshTileAsset(tile)) = tile_assets.remove(handle) {
+                tiles.loading.remove(&coord);
+                if let Err(err) = tiles.store.insert_tile(tile) {
+                    tracing::error!(
+                        "Failed to insert navmesh tile {coord} of \"{}\" for {entity}: {err}",
+                        streamed.path
+                    );
+                    tiles.failed.insert(coord);
+                }
+            } else if let LoadState::Failed(err) = asset_server.load_state(handle) {
+                tracing::error!(
+                    "Failed to load navmesh tile {coord} of \"{}\" for {entity}: {err}",
+                    streamed.path
+                );
+                tiles.loading.remove(&coord);
+                // Don't retry tiles that failed, so that a missing file is not requested every frame.
+                tiles.failed.insert(coord);
+            }
+        }
+
+        let far = |coord: UVec2| {
+            anchors
+                .iter()
+                .all(|&anchor| distance_to_tile(coord, anchor, &config) > streamer.unload_radius)
+        };
+        let far_tiles = tiles
+            .store
+            .loaded_tiles()
+            .filter(|&coord| far(coord))
+            .collect::<Vec<_>>();
+        for coord in far_tiles {
+            tiles.store.remove_tile(coord);
+        }
+        // Dropping the handle of a tile that is still loading cancels the load.
+        tiles.loading.retain(|&coord, _| !far(coord));
+
+        for &anchor in &anchors {
+            let tile_counts = tiles.navmesh().tile_counts;
+            for coord in tiles_within(&config, tile_counts, anchor, streamer.radius) {
+                let requested = tiles.store.is_loaded(coord)
+                    || tiles.loading.contains_key(&coord)
+                    || tiles.failed.contains(&coord);
+                if tiles.saved.contains(&coord) && !requested {
+                    let handle = asset_server.load(streamed.tile_path(coord));
+                    tiles.loading.insert(coord, handle);
+                }
+            }
+        }
+
+        let (loaded, unloaded) = tiles.store.take_changes();
+        if !loaded.is_empty() || !unloaded.is_empty() {
+            streamed_events.write(NavmeshTilesStreamed {
+                entity,
+                loaded,
+                unloaded,
+            });
+        }
+    }
+}
+
+/// The distance from `point` to the tile at `coord` on the xz-plane, excluding its border.
+fn distance_to_tile(coord: UVec2, point: Vec2, config: &NavmeshConfig) -> f32 {
+    let bounds = TiledNavmesh::tile_bounds(coord, config);
+    let border = config.border_size as f32 * config.cell_size;
+    let min = bounds.min.xz() + border;
+    let max = bounds.max.xz() - border;
+    (min - point).max(point - max).max(Vec2::ZERO).length()
+}
+
+/// The coordinates of the tiles that are at most `radius` away from `center` on the xz-plane.
+fn tiles_within(
+    config: &NavmeshConfig,
+    tile_counts: UVec2,
+    center: Vec2,
+    radius: f32,
+) -> impl Iterator<Item = UVec2> {
+    let tile_world_size = config.tile_size as f32 * config.cell_size;
+    let first = ((center - radius - config.aabb.min.xz()) / tile_world_size).floor();
+    let last = ((center + radius - config.aabb.min.xz()) / tile_world_size).floor();
+    let outside = tile_counts.cmpeq(UVec2::ZERO).any()
+        || last.cmplt(Vec2::ZERO).any()
+        || first.cmpge(tile_counts.as_vec2()).any();
+    let max_coord = tile_counts.saturating_sub(UVec2::ONE).as_vec2();
+    let (first, last) = if outside {
+        (UVec2::ONE, UVec2::ZERO)
+    } else {
+        (
+            first.clamp(Vec2::ZERO, max_coord).as_uvec2(),
+            last.clamp(Vec2::ZERO, max_coord).as_uvec2(),
+        )
+    };
+    let config = *config;
+    (first.y..=last.y)
+        .flat_map(move |z| (first.x..=last.x).map(move |x| UVec2::new(x, z)))
+        .filter(move |&coord| distance_to_tile(coord, center, &config) <= radius)
+}
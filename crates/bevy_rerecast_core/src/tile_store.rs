@@ -0,0 +1,243 @@
+//! Loading and unloading the tiles of a navmesh by hand, e.g. alongside the terrain chunks of an open world.
+//!
+//! Insert a [`NavmeshTileStore`] describing the grid of tiles, then add and remove tiles with [`NavmeshTileStore::insert_tile`]
+//! and [`NavmeshTileStore::remove_tile`] whenever chunks are streamed in or out. Every change is announced with a [`NavmeshTilesChanged`].
+//! Unlike the streaming of the `streaming` feature, the store doesn't decide which tiles to load or where they come from.
+//! The streamer of the `streaming` feature keeps one store per streamed navmesh and fills it with the tiles it loads from disk.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_platform::collections::HashSet;
+use glam::{UVec2, Vec3Swizzles as _};
+use rerecast::{Aabb3d, NavmeshConfig, NavmeshTile, TiledNavmesh, TiledNavmeshError};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<NavmeshTilesChanged>();
+    app.add_systems(
+        PostUpdate,
+        send_tile_changes.run_if(resource_exists::<NavmeshTileStore>),
+    );
+}
+
+/// The tiles of a navmesh that are currently loaded.
+///
+/// All other tiles are empty and not linked to their neighbors, so queries on [`NavmeshTileStore::navmesh`]
+/// transparently span exactly the loaded tiles.
+#[derive(Resource, Debug, Clone)]
+pub struct NavmeshTileStore {
+    navmesh: TiledNavmesh,
+    config: NavmeshConfig,
+    loaded: HashSet<UVec2>,
+    inserted: HashSet<UVec2>,
+    removed: HashSet<UVec2>,
+}
+
+impl NavmeshTileStore {
+    /// Creates a store without any loaded tiles, for the grid of tiles described by `config`.
+    /// `config` must be the config the tiles are built with, see [`TiledNavmesh::with_empty_tiles`].
+    pub fn new(config: NavmeshConfig) -> Result<Self, TiledNavmeshError> {
+        Ok(Self {
+            navmesh: TiledNavmesh::with_empty_tiles(&config)?,
+            config,
+            loaded: HashSet::default(),
+            inserted: HashSet::default(),
+            removed: HashSet::default(),
+        })
+    }
+
+    /// Loads the tile at [`NavmeshTile::coord`] and stitches it to its loaded neighbors. Replaces the tile if it is already loaded.
+    pub fn insert_tile(&mut self, tile: NavmeshTile) -> Result<(), TiledNavmeshError> {
+        let coord = tile.coord;
+        self.navmesh.insert_tile(tile, self.config.walkable_climb)?;
+        self.loaded.insert(coord);
+        self.removed.remove(&coord);
+        self.inserted.insert(coord);
+        Ok(())
+    }
+
+    /// Unloads the tile at `coord` and unlinks it from its neighbors.
+    /// Returns the removed tile, or `None` if it was not loaded.
+    pub fn remove_tile(&mut self, coord: UVec2) -> Option<NavmeshTile> {
+        if !self.loaded.remove(&coord) {
+            return None;
+        }
+        let tile = self.navmesh.remove_tile(coord)?;
+        self.inserted.remove(&coord);
+        self.removed.insert(coord);
+        Some(tile)
+    }
+
+    /// The loaded tiles whose area on the xz-plane overlaps `bounds`.
+    /// The borders of the tiles, which overlap their neighbors, are not part of their area.
+    pub fn tiles_in_bounds(&self, bounds: &Aabb3d) -> impl Iterator<Item = &NavmeshTile> + '_ {
+        let border = self.config.border_size as f32 * self.config.cell_size;
+        let coords = self
+            .navmesh
+            .tiles_overlapping(bounds, &self.config)
+            .filter(|coord| self.loaded.contains(coord))
+            .filter(|&coord| {
+                let tile = TiledNavmesh::tile_bounds(coord, &self.config);
+                let (min, max) = (tile.min.xz() + border, tile.max.xz() - border);
+                min.cmplt(bounds.max.xz()).all() && bounds.min.xz().cmplt(max).all()
+            })
+            .collect::<Vec<_>>();
+        coords
+            .into_iter()
+            .filter_map(|coord| self.navmesh.tile(coord))
+    }
+
+    /// Whether the tile at `coord` is loaded.
+    pub fn is_loaded(&self, coord: UVec2) -> bool {
+        self.loaded.contains(&coord)
+    }
+
+    /// The coordinates of all loaded tiles.
+    pub fn loaded_tiles(&self) -> impl Iterator<Item = UVec2> + '_ {
+        self.loaded.iter().copied()
+    }
+
+    /// The navmesh with all loaded tiles.
+    pub fn navmesh(&self) -> &TiledNavmesh {
+        &self.navmesh
+    }
+
+    /// The config the tiles are built with.
+    pub fn config(&self) -> &NavmeshConfig {
+        &self.config
+    }
+
+    /// Takes the tiles that were inserted and removed since the last call, as announced by [`NavmeshTilesChanged`].
+    pub(crate) fn take_changes(&mut self) -> (Vec<UVec2>, Vec<UVec2>) {
+        (
+            self.inserted.drain().collect(),
+            self.removed.drain().collect(),
+        )
+    }
+}
+
+/// Sent once per frame in which tiles of the [`NavmeshTileStore`] were inserted or removed.
+#[derive(Event, Debug, Clone)]
+pub struct NavmeshTilesChanged {
+    /// The coordinates of the tiles that were loaded or replaced, and are still loaded.
+    pub inserted: Vec<UVec2>,
+    /// The coordinates of the tiles that were unloaded, and are still unloaded.
+    pub removed: Vec<UVec2>,
+}
+
+fn send_tile_changes(
+    mut store: ResMut<NavmeshTileStore>,
+    mut changed_events: EventWriter<NavmeshTilesChanged>,
+) {
+    if store.inserted.is_empty() && store.removed.is_empty() {
+        return;
+    }
+    let (inserted, removed) = store.take_changes();
+    changed_events.write(NavmeshTilesChanged { inserted, removed });
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3, Vec3A};
+    use rerecast::{BuildContext, NavmeshConfigBuilder, TriMesh};
+
+    use super::*;
+
+    fn config() -> NavmeshConfig {
+        NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::ZERO, [10.0, 2.0, 10.0]),
+            tile_size: 24,
+            ..Default::default()
+        }
+        .build()
+    }
+
+    /// The tiles of a flat floor covering the whole grid of [`config`].
+    fn tiles() -> Vec<NavmeshTile> {
+        let floor = TriMesh::from_parts(
+            vec![
+                Vec3A::new(-10.0, 0.0, -10.0),
+                Vec3A::new(-10.0, 0.0, 10.0),
+                Vec3A::new(10.0, 0.0, 10.0),
+                Vec3A::new(10.0, 0.0, -10.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        TiledNavmesh::build(floor, &config(), &BuildContext::default())
+            .unwrap()
+            .tiles
+    }
+
+    fn tile(coord: UVec2) -> NavmeshTile {
+        tiles()
+            .into_iter()
+            .find(|tile| tile.coord == coord)
+            .unwrap()
+    }
+
+    #[test]
+    fn inserted_tiles_are_stitched_to_loaded_neighbors_only() {
+        let mut store = NavmeshTileStore::new(config()).unwrap();
+        store.insert_tile(tile(UVec2::new(0, 0))).unwrap();
+        assert!(store.navmesh().links.is_empty());
+
+        store.insert_tile(tile(UVec2::new(1, 0))).unwrap();
+        assert!(store.is_loaded(UVec2::new(1, 0)));
+        assert!(!store.navmesh().links.is_empty());
+
+        assert!(store.remove_tile(UVec2::new(1, 0)).is_some());
+        assert!(store.remove_tile(UVec2::new(1, 0)).is_none());
+        assert!(store.navmesh().links.is_empty());
+        assert_eq!(store.loaded_tiles().collect::<Vec<_>>(), [UVec2::ZERO]);
+
+        let outside = NavmeshTile::empty(UVec2::new(5, 5));
+        assert!(store.insert_tile(outside).is_err());
+        assert!(!store.is_loaded(UVec2::new(5, 5)));
+    }
+
+    #[test]
+    fn tiles_in_bounds_ignore_borders_and_unloaded_tiles() {
+        let mut store = NavmeshTileStore::new(config()).unwrap();
+        for tile in tiles() {
+            if tile.coord != UVec2::new(1, 1) {
+                store.insert_tile(tile).unwrap();
+            }
+        }
+        // Right before the end of the first tile, where the border of its neighbor along the x-axis begins.
+        let tile_end = -10.0 + 24.0 * config().cell_size;
+        let corner = Aabb3d::new(Vec3::new(tile_end - 0.05, 0.0, -9.5), [0.01, 1.0, 0.01]);
+        let coords = store
+            .tiles_in_bounds(&corner)
+            .map(|tile| tile.coord)
+            .collect::<Vec<_>>();
+        assert_eq!(coords, [UVec2::ZERO]);
+
+        let center = Aabb3d::new(Vec3::ZERO, [0.1, 1.0, 0.1]);
+        assert_eq!(store.tiles_in_bounds(&center).count(), 0);
+    }
+
+    #[test]
+    fn changes_are_announced_once_per_frame() {
+        let mut app = App::new();
+        app.add_plugins(plugin);
+        app.insert_resource(NavmeshTileStore::new(config()).unwrap());
+        let mut store = app.world_mut().resource_mut::<NavmeshTileStore>();
+        store.insert_tile(tile(UVec2::new(0, 0))).unwrap();
+        store.insert_tile(tile(UVec2::new(1, 0))).unwrap();
+        store.remove_tile(UVec2::new(1, 0));
+        app.update();
+
+        let events = app.world().resource::<Events<NavmeshTilesChanged>>();
+        let changes = events
+            .iter_current_update_events()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].inserted, [UVec2::ZERO]);
+        assert_eq!(changes[0].removed, [UVec2::new(1, 0)]);
+
+        app.update();
+        let events = app.world().resource::<Events<NavmeshTilesChanged>>();
+        assert_eq!(events.iter_current_update_events().count(), 0);
+    }
+}
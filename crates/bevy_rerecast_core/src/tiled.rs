@@ -0,0 +1,647 @@
+//! Navmeshes made of tiles that are rebuilt individually, so that opening a door or moving a crate
+//! does not regenerate the whole navmesh.
+//!
+//! The navmesh affectors are tracked by their bounds. Whenever one of them is added, moved or removed,
+//! only the tiles overlapping its old and new bounds are rebuilt and stitched to their neighbors again.
+
+use std::{
+    any::TypeId,
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_platform::collections::{HashMap, HashSet};
+use bevy_transform::{TransformSystem, prelude::*};
+use glam::UVec2;
+use rerecast::{
+    Aabb3d, BuildContext, Heightfield, NavmeshConfig, RasterizationError, TiledNavmesh, TriMesh,
+};
+
+use crate::{
+    NavmeshAreaFlags,
+    generator::{CollectedAffector, CollectedHeightmap, collect_affectors, finish_tile_polygons},
+    heightmap::HeightmapAffector,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<NavmeshTilesRebuilt>();
+    track_spawned_affectors::<HeightmapAffector>(app);
+    app.add_systems(
+        PostUpdate,
+        (detect_affector_changes, update_navmesh_tiles)
+            .chain()
+            .after(TransformSystem::TransformPropagate),
+    );
+}
+
+/// Generates a [`TiledNavmesh`] for this entity and keeps it up to date with the navmesh affectors.
+///
+/// The whole navmesh is built whenever this component is changed. Afterwards, only the tiles overlapping
+/// affectors that were added, moved or removed are rebuilt. The result is written to the [`NavmeshTiles`] of this entity.
+#[derive(Component, Debug, Clone)]
+pub struct IncrementalNavmesh {
+    /// The config of the whole navmesh, see [`TiledNavmesh::build`].
+    /// If [`NavmeshConfig::aabb`] is left at its default, it is set to the bounds of all navmesh affectors
+    /// when the whole navmesh is built. Affectors outside of these bounds are ignored until then.
+    pub config: NavmeshConfig,
+}
+
+impl IncrementalNavmesh {
+    /// Creates an incremental navmesh with the given config.
+    pub fn new(config: NavmeshConfig) -> Self {
+        Self { config }
+    }
+}
+
+/// The generated state of an [`IncrementalNavmesh`]. Inserted and kept up to date automatically.
+///
+/// Affectors are checked for changes whenever the [`GlobalTransform`] of an affector changes or is removed,
+/// or when an entity with one of the affector components registered through
+/// [`NavmeshApp::add_navmesh_affector_component`](crate::NavmeshApp::add_navmesh_affector_component) is spawned.
+/// Changes that none of these catch, e.g. a new [`NavmeshObstruction`](crate::NavmeshObstruction),
+/// have to be announced with [`NavmeshTiles::request_update`].
+#[derive(Component, Debug, Clone)]
+pub struct NavmeshTiles {
+    /// The tiled navmesh.
+    pub navmesh: TiledNavmesh,
+    /// The config the navmesh was built with, with [`NavmeshConfig::aabb`] filled in.
+    pub config: NavmeshConfig,
+    affectors: HashMap<AffectorKey, TrackedAffector>,
+    update_requested: bool,
+    rebuild_requested: bool,
+}
+
+impl NavmeshTiles {
+    /// Checks all navmesh affectors for changes on the next update and rebuilds the tiles of those that changed.
+    pub fn request_update(&mut self) {
+        self.update_requested = true;
+    }
+}
+
+/// Sent when tiles of an [`IncrementalNavmesh`] or a [`CarvedNavmesh`](crate::obstacles::CarvedNavmesh) were rebuilt.
+#[derive(Event, Debug, Clone)]
+pub struct NavmeshTilesRebuilt {
+    /// The entity holding the [`IncrementalNavmesh`] or [`CarvedNavmesh`](crate::obstacles::CarvedNavmesh).
+    pub entity: Entity,
+    /// The coordinates of the rebuilt tiles. Contains all tiles when the whole navmesh was built.
+    pub tiles: Vec<UVec2>,
+}
+
+/// Identifies an affector across builds: its entity and how many affectors of the same entity came before it.
+///
+/// An entity can have affectors from several backends, and affectors of backends that don't return entities
+/// all share [`Entity::PLACEHOLDER`].
+type AffectorKey = (Entity, usize);
+
+/// The [`AffectorKey`] of every affector followed by those of every heightmap, in the same order.
+/// Both are sorted by entity, so the keys are stable across builds.
+fn affector_keys(
+    affectors: &[CollectedAffector],
+    heightmaps: &[CollectedHeightmap],
+) -> (Vec<AffectorKey>, Vec<AffectorKey>) {
+    let mut counts = HashMap::<Entity, usize>::default();
+    let mut key = |entity: Entity| {
+        let count = counts.entry(entity).or_default();
+        *count += 1;
+        (entity, *count - 1)
+    };
+    let affector_keys = affectors
+        .iter()
+        .map(|affector| key(affector.entity))
+        .collect();
+    let heightmap_keys = heightmaps
+        .iter()
+        .map(|heightmap| key(heightmap.entity))
+        .collect();
+    (affector_keys, heightmap_keys)
+}
+
+/// Rasterizes the heightmaps overlapping the heightfield of a tile.
+pub(crate) fn rasterize_heightmaps(
+    heightmaps: &[CollectedHeightmap],
+    heightfield: &mut Heightfield,
+    config: &NavmeshConfig,
+) -> Result<(), RasterizationError> {
+    for heightmap in heightmaps {
+        if heightmap
+            .heightmap
+            .aabb()
+            .is_some_and(|bounds| bounds.intersects(&heightfield.aabb))
+        {
+            heightmap.rasterize(heightfield, config)?;
+        }
+    }
+    Ok(())
+}
+
+/// What an affector looked like when its tiles were last built.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TrackedAffector {
+    /// The world-space bounds of the affector, or `None` if it has no triangles.
+    bounds: Option<Aabb3d>,
+    /// A hash of the marked world-space geometry, so that changes that keep the bounds are noticed as well.
+    fingerprint: u64,
+}
+
+impl TrackedAffector {
+    fn new(marked: &TriMesh) -> Self {
+        let mut hasher = DefaultHasher::new();
+        for vertex in &marked.vertices {
+            vertex.to_array().map(f32::to_bits).hash(&mut hasher);
+        }
+        for indices in &marked.indices {
+            indices.to_array().hash(&mut hasher);
+        }
+        marked.area_types.hash(&mut hasher);
+        Self {
+            bounds: marked.compute_aabb(),
+            fingerprint: hasher.finish(),
+        }
+    }
+
+    fn from_heightmap(heightmap: &CollectedHeightmap) -> Self {
+        let mut hasher = DefaultHasher::new();
+        let CollectedHeightmap {
+            heightmap,
+            obstruction,
+            areas,
+            ..
+        } = heightmap;
+        heightmap
+            .origin
+            .to_array()
+            .map(f32::to_bits)
+            .hash(&mut hasher);
+        heightmap
+            .spacing
+            .to_array()
+            .map(f32::to_bits)
+            .hash(&mut hasher);
+        heightmap.size.to_array().hash(&mut hasher);
+        for height in &heightmap.heights {
+            height.to_bits().hash(&mut hasher);
+        }
+        obstruction.hash(&mut hasher);
+        areas.hash(&mut hasher);
+        Self {
+            bounds: heightmap.aabb(),
+            fingerprint: hasher.finish(),
+        }
+    }
+}
+
+/// Whether an entity with one of the affector components registered through
+/// [`NavmeshApp::add_navmesh_affector_component`](crate::NavmeshApp::add_navmesh_affector_component) was spawned
+/// since the affectors were last checked for changes.
+#[derive(Resource, Default)]
+pub(crate) struct SpawnedAffectors {
+    spawned: bool,
+    components: HashSet<TypeId>,
+}
+
+/// Notices entities with the affector component `C` being spawned.
+/// See [`NavmeshApp::add_navmesh_affector_component`](crate::NavmeshApp::add_navmesh_affector_component).
+pub(crate) fn track_spawned_affectors<C: Component>(app: &mut App) {
+    let mut spawned = app.world_mut().get_resource_or_init::<SpawnedAffectors>();
+    if !spawned.components.insert(TypeId::of::<C>()) {
+        return;
+    }
+    app.add_systems(
+        PostUpdate,
+        detect_spawned_affectors::<C>
+            .after(TransformSystem::TransformPropagate)
+            .before(detect_affector_changes),
+    );
+}
+
+fn detect_spawned_affectors<C: Component>(
+    // Affectors only count once they have both, no matter which was inserted first.
+    spawned: Query<
+        (),
+        (
+            With<C>,
+            With<GlobalTransform>,
+            Or<(Added<C>, Added<GlobalTransform>)>,
+        ),
+    >,
+    mut affectors: ResMut<SpawnedAffectors>,
+) {
+    if !spawned.is_empty() {
+        affectors.spawned = true;
+    }
+}
+
+fn detect_affector_changes(
+    incremental: Query<Entity, Changed<IncrementalNavmesh>>,
+    mut navmeshes: Query<&mut NavmeshTiles>,
+    moved: Query<Entity, Or<(Changed<GlobalTransform>, Changed<HeightmapAffector>)>>,
+    mut spawned: ResMut<SpawnedAffectors>,
+    mut removed: RemovedComponents<GlobalTransform>,
+) {
+    for entity in &incremental {
+        if let Ok(mut tiles) = navmeshes.get_mut(entity) {
+            tiles.rebuild_requested = true;
+        }
+    }
+    let spawned = std::mem::take(&mut spawned.spawned);
+    let removed = removed.read().collect::<HashSet<_>>();
+    for mut tiles in &mut navmeshes {
+        if tiles.update_requested {
+            continue;
+        }
+        let changed = spawned
+            || removed
+                .iter()
+                .any(|&entity| tiles.affectors.contains_key(&(entity, 0)))
+            || moved
+                .iter()
+                .any(|entity| tiles.affectors.contains_key(&(entity, 0)));
+        if changed {
+            tiles.update_requested = true;
+        }
+    }
+}
+
+fn update_navmesh_tiles(world: &mut World) {
+    let mut query = world.query::<(Entity, &IncrementalNavmesh, Option<&NavmeshTiles>)>();
+    let pending = query
+        .iter(world)
+        .filter(|(_, _, tiles)| {
+            tiles.is_none_or(|tiles| tiles.update_requested || tiles.rebuild_requested)
+        })
+        .map(|(entity, incremental, _)| (entity, incremental.config))
+        .collect::<Vec<_>>();
+    if pending.is_empty() {
+        return;
+    }
+    let (affectors, heightmaps) = match collect_affectors(world) {
+        Ok(input) => input.into_affectors_and_heightmaps(),
+        Err(err) => {
+            tracing::error!("Failed to update tiled navmeshes: {err}");
+            return;
+        }
+    };
+    let area_flags = world.get_resource::<NavmeshAreaFlags>().cloned();
+
+    for (entity, config) in pending {
+        let tiles = world.entity_mut(entity).take::<NavmeshTiles>();
+        let (mut tiles, rebuilt) = match tiles {
+            Some(tiles) if !tiles.rebuild_requested && !tiles.navmesh.tiles.is_empty() => {
+                update_tiles(entity, tiles, &affectors, &heightmaps)
+            }
+            _ => build_tiles(entity, config, &affectors, &heightmaps),
+        };
+        for tile in &mut tiles.navmesh.tiles {
+            if rebuilt.contains(&tile.coord) {
+                finish_tile_polygons(&mut tile.polygon_mesh, area_flags.as_ref());
+            }
+        }
+        world.entity_mut(entity).insert(tiles);
+        if !rebuilt.is_empty() {
+            world.send_event(NavmeshTilesRebuilt {
+                entity,
+                tiles: rebuilt,
+            });
+        }
+    }
+}
+
+/// Builds the whole navmesh. Returns the coordinates of all tiles.
+fn build_tiles(
+    entity: Entity,
+    mut config: NavmeshConfig,
+    affectors: &[CollectedAffector],
+    heightmaps: &[CollectedHeightmap],
+) -> (NavmeshTiles, Vec<UVec2>) {
+    let mut trimesh = TriMesh::default();
+    let mut tracked = HashMap::default();
+    let (affector_keys, heightmap_keys) = affector_keys(affectors, heightmaps);
+    for (affector, key) in affectors.iter().zip(affector_keys) {
+        let marked = affector.marked(config.walkable_slope_angle);
+        tracked.insert(key, TrackedAffector::new(&marked));
+        trimesh.extend(&marked);
+    }
+    for (heightmap, key) in heightmaps.iter().zip(heightmap_keys) {
+        tracked.insert(key, TrackedAffector::from_heightmap(heightmap));
+    }
+    if config.aabb == Aabb3d::default()
+        && let Some(aabb) = trimesh
+            .compute_aabb()
+            .into_iter()
+            .chain(heightmaps.iter().filter_map(|h| h.heightmap.aabb()))
+            .reduce(|a, b| Aabb3d {
+                min: a.min.min(b.min),
+                max: a.max.max(b.max),
+            })
+    {
+        config.aabb = aabb;
+    }
+
+    let built = TiledNavmesh::build_premarked_with_rasterizer(
+        trimesh,
+        &config,
+        &BuildContext::default(),
+        |heightfield| rasterize_heightmaps(heightmaps, heightfield, &config),
+    );
+    let navmesh = match built {
+        Ok(navmesh) => navmesh,
+        Err(err) => {
+            tracing::error!("Failed to build tiled navmesh for {entity}: {err}");
+            // Keep the tracked affectors so that the build is only retried once something changes.
+            TiledNavmesh::default()
+        }
+    };
+    let coords = navmesh.tiles.iter().map(|tile| tile.coord).collect();
+    let tiles = NavmeshTiles {
+        navmesh,
+        config,
+        affectors: tracked,
+        update_requested: false,
+        rebuild_requested: false,
+    };
+    (tiles, coords)
+}
+
+/// Rebuilds the tiles overlapping the affectors that changed since the last build. Returns the coordinates of the rebuilt tiles.
+fn update_tiles(
+    entity: Entity,
+    mut tiles: NavmeshTiles,
+    affectors: &[CollectedAffector],
+    heightmaps: &[CollectedHeightmap],
+) -> (NavmeshTiles, Vec<UVec2>) {
+    tiles.update_requested = false;
+    let config = tiles.config;
+    let mut dirty = HashSet::new();
+    let mut mark_dirty = |bounds: Option<Aabb3d>| {
+        if let Some(bounds) = bounds {
+            dirty.extend(tiles.navmesh.tiles_overlapping(&bounds, &config));
+        }
+    };
+
+    let mut previous = tiles.affectors.clone();
+    let mut tracked = HashMap::default();
+    let mut track = |key: AffectorKey, current: TrackedAffector| {
+        match previous.remove(&key) {
+            Some(previous) if previous == current => {}
+            Some(previous) => {
+                mark_dirty(previous.bounds);
+                mark_dirty(current.bounds);
+            }
+            None => mark_dirty(current.bounds),
+        }
+        tracked.insert(key, current);
+    };
+    let (affector_keys, heightmap_keys) = affector_keys(affectors, heightmaps);
+    let mut marked_affectors = Vec::with_capacity(affectors.len());
+    for (affector, key) in affectors.iter().zip(affector_keys) {
+        let marked = affector.marked(config.walkable_slope_angle);
+        let current = TrackedAffector::new(&marked);
+        track(key, current);
+        marked_affectors.push((current.bounds, marked));
+    }
+    for (heightmap, key) in heightmaps.iter().zip(heightmap_keys) {
+        track(key, TrackedAffector::from_heightmap(heightmap));
+    }
+    for removed in previous.into_values() {
+        mark_dirty(removed.bounds);
+    }
+    if dirty.is_empty() {
+        tiles.affectors = tracked;
+        return (tiles, Vec::new());
+    }
+
+    // Only the geometry that can influence the dirty tiles needs to be rasterized again.
+    let dirty_bounds = dirty
+        .iter()
+        .map(|&coord| TiledNavmesh::tile_bounds(coord, &config))
+        .collect::<Vec<_>>();
+    let mut trimesh = TriMesh::default();
+    for (bounds, marked) in &marked_affectors {
+        if bounds.is_some_and(|bounds| dirty_bounds.iter().any(|tile| tile.intersects(&bounds))) {
+            trimesh.extend(marked);
+        }
+    }
+
+    let mut rebuilt = dirty.into_iter().collect::<Vec<_>>();
+    rebuilt.sort_by_key(|coord| (coord.y, coord.x));
+    match tiles.navmesh.rebuild_tiles_premarked_with_rasterizer(
+        trimesh,
+        &config,
+        rebuilt.iter().copied(),
+        &BuildContext::default(),
+        |heightfield| rasterize_heightmaps(heightmaps, heightfield, &config),
+    ) {
+        Ok(()) => {
+            tiles.affectors = tracked;
+            (tiles, rebuilt)
+        }
+        Err(err) => {
+            // Keep the previously tracked affectors so that the tiles are rebuilt again on the next change.
+            tracing::error!("Failed to rebuild navmesh tiles for {entity}: {err}");
+            (tiles, Vec::new())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3A};
+    use rerecast::AreaType;
+
+    use super::*;
+    use crate::{NavmeshAffectorAreas, NavmeshApp as _};
+
+    #[derive(Component)]
+    struct Affector;
+
+    /// Whether [`floor`] also returns a patch of floor in one corner, e.g. a rug.
+    #[derive(Resource, Default)]
+    struct Platform(bool);
+
+    fn quad(min: f32, max: f32, height: f32) -> TriMesh {
+        TriMesh::from_parts(
+            vec![
+                Vec3A::new(min, height, min),
+                Vec3A::new(min, height, max),
+                Vec3A::new(max, height, max),
+                Vec3A::new(max, height, min),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap()
+    }
+
+    fn floor(platform: Res<Platform>) -> Vec<(Entity, GlobalTransform, TriMesh)> {
+        let mut affectors = vec![(
+            Entity::PLACEHOLDER,
+            GlobalTransform::IDENTITY,
+            quad(-5.0, 5.0, 0.0),
+        )];
+        if platform.0 {
+            affectors.push((
+                Entity::PLACEHOLDER,
+                GlobalTransform::IDENTITY,
+                quad(-5.0, -3.0, 0.0),
+            ));
+        }
+        affectors
+    }
+
+    #[test]
+    fn area_flags_are_applied_to_built_and_rebuilt_tiles() {
+        const WALK: u16 = 0b10;
+        let mut app = App::new();
+        app.add_event::<NavmeshTilesRebuilt>()
+            .init_resource::<NavmeshAffectorAreas>()
+            .init_resource::<Platform>()
+            .insert_resource(NavmeshAreaFlags(HashMap::from_iter([(
+                AreaType::DEFAULT_WALKABLE,
+                WALK,
+            )])))
+            .add_navmesh_affector_backend("floor", floor)
+            .add_systems(Update, update_navmesh_tiles);
+        let config = rerecast::NavmeshConfigBuilder {
+            tile_size: 16,
+            ..Default::default()
+        }
+        .build();
+        let entity = app.world_mut().spawn(IncrementalNavmesh::new(config)).id();
+        let flags = |app: &App| {
+            let tiles = app.world().get::<NavmeshTiles>(entity).unwrap();
+            tiles
+                .navmesh
+                .tiles
+                .iter()
+                .flat_map(|tile| tile.polygon_mesh.flags.clone())
+                .collect::<Vec<_>>()
+        };
+        app.update();
+        let built = flags(&app);
+        assert!(!built.is_empty());
+        assert!(built.iter().all(|&flags| flags == WALK));
+
+        app.world_mut().resource_mut::<Platform>().0 = true;
+        app.world_mut()
+            .get_mut::<NavmeshTiles>(entity)
+            .unwrap()
+            .request_update();
+        app.update();
+        let rebuilt = app
+            .world_mut()
+            .resource_mut::<Events<NavmeshTilesRebuilt>>()
+            .drain()
+            .last()
+            .unwrap();
+        assert!(
+            rebuilt.tiles.len()
+                < app
+                    .world()
+                    .get::<NavmeshTiles>(entity)
+                    .unwrap()
+                    .navmesh
+                    .tiles
+                    .len()
+        );
+        assert!(flags(&app).iter().all(|&flags| flags == WALK));
+    }
+
+    #[test]
+    fn heightmaps_only_rebuild_the_tiles_they_changed() {
+        let mut app = App::new();
+        app.add_event::<NavmeshTilesRebuilt>()
+            .init_resource::<NavmeshAffectorAreas>()
+            .add_systems(Update, update_navmesh_tiles);
+        // Two terrain chunks next to each other, so that changing one of them leaves the tiles of the other alone.
+        let chunk = |x: f32| {
+            let heightmap = rerecast::Heightmap::new(
+                glam::Vec3::new(x, 0.0, -5.0),
+                glam::Vec2::ONE,
+                UVec2::new(6, 11),
+                vec![0.0; 6 * 11],
+            )
+            .unwrap();
+            (HeightmapAffector(heightmap), GlobalTransform::IDENTITY)
+        };
+        let terrain = app.world_mut().spawn(chunk(-5.0)).id();
+        app.world_mut().spawn(chunk(0.0));
+        let config = rerecast::NavmeshConfigBuilder {
+            tile_size: 16,
+            ..Default::default()
+        }
+        .build();
+        let entity = app.world_mut().spawn(IncrementalNavmesh::new(config)).id();
+        let rebuilt = |app: &mut App| {
+            app.update();
+            app.world_mut()
+                .resource_mut::<Events<NavmeshTilesRebuilt>>()
+                .drain()
+                .flat_map(|rebuilt| rebuilt.tiles)
+                .count()
+        };
+        let tile_count = rebuilt(&mut app);
+        let tiles = app.world().get::<NavmeshTiles>(entity).unwrap();
+        assert_eq!(tile_count, tiles.navmesh.tiles.len());
+        assert!(tiles.navmesh.tiles.iter().any(|tile| !tile.is_empty()));
+
+        // Raise a single sample in the corner of the first chunk.
+        app.world_mut()
+            .get_mut::<HeightmapAffector>(terrain)
+            .unwrap()
+            .heights[0] = 0.5;
+        app.world_mut()
+            .get_mut::<NavmeshTiles>(entity)
+            .unwrap()
+            .request_update();
+        let changed = rebuilt(&mut app);
+        assert!(
+            changed > 0 && changed < tile_count,
+            "{changed} of {tile_count}"
+        );
+
+        app.world_mut()
+            .get_mut::<NavmeshTiles>(entity)
+            .unwrap()
+            .request_update();
+        assert_eq!(rebuilt(&mut app), 0);
+    }
+
+    #[test]
+    fn only_spawned_affectors_request_updates() {
+        let mut app = App::new();
+        app.init_resource::<SpawnedAffectors>()
+            .add_navmesh_affector_component::<Affector>()
+            .add_systems(PostUpdate, detect_affector_changes);
+        let navmesh = app
+            .world_mut()
+            .spawn(NavmeshTiles {
+                navmesh: TiledNavmesh::default(),
+                config: rerecast::NavmeshConfigBuilder::default().build(),
+                affectors: HashMap::default(),
+                update_requested: false,
+                rebuild_requested: false,
+            })
+            .id();
+        let update_requested = |app: &mut App| {
+            let mut tiles = app.world_mut().get_mut::<NavmeshTiles>(navmesh).unwrap();
+            std::mem::take(&mut tiles.update_requested)
+        };
+        app.update();
+        assert!(!update_requested(&mut app));
+
+        app.world_mut().spawn(GlobalTransform::IDENTITY);
+        app.update();
+        assert!(!update_requested(&mut app));
+
+        // The affector component may also be inserted after the transform.
+        let affector = app.world_mut().spawn(GlobalTransform::IDENTITY).id();
+        app.update();
+        assert!(!update_requested(&mut app));
+        app.world_mut().entity_mut(affector).insert(Affector);
+        app.update();
+        assert!(update_requested(&mut app));
+
+        app.update();
+        assert!(!update_requested(&mut app));
+    }
+}
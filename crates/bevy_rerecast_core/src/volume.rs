@@ -0,0 +1,88 @@
+//! Convex volumes that give the walkable area within them their own [`AreaType`], e.g. shallow water, roads or no-go zones.
+//!
+//! Unlike a [`NavmeshAffectorArea`](crate::NavmeshAffectorArea), a [`NavmeshVolume`] doesn't need any geometry of its own:
+//! it marks whatever walkable ground ends up within it, after the ground was eroded by the agent radius.
+
+use bevy_ecs::prelude::*;
+use bevy_reflect::prelude::*;
+use bevy_transform::prelude::*;
+use glam::{EulerRot, Quat, Vec2, Vec3, Vec3Swizzles as _};
+use rerecast::{AreaType, ConvexVolume};
+
+/// A convex prism that marks the walkable area within it with [`NavmeshVolume::area`] whenever [`NavmeshGenerator`](crate::generator::NavmeshGenerator)
+/// builds a navmesh. Use [`AreaType::NOT_WALKABLE`] to cut the area out of the navmesh.
+///
+/// The footprint is a convex polygon on the xz-plane of the entity, extruded from [`Self::min_y`] to [`Self::max_y`].
+/// Translation and scale of the entity apply to the whole volume, while only the rotation around the y-axis is kept,
+/// so that the volume stays upright. Where volumes overlap, the one on the greater [`Entity`] wins.
+///
+/// Tiled and carved navmeshes ignore volumes.
+#[derive(Component, Debug, Clone, PartialEq, Reflect)]
+#[reflect(Component, Debug, Clone, PartialEq)]
+#[require(Transform)]
+pub struct NavmeshVolume {
+    /// The corners of the convex footprint, as x and z coordinates in the local space of the entity.
+    pub footprint: Vec<Vec2>,
+    /// The bottom of the volume, in the local space of the entity.
+    pub min_y: f32,
+    /// The top of the volume, in the local space of the entity.
+    pub max_y: f32,
+    /// The area type the walkable spans within the volume are marked with.
+    pub area: AreaType,
+}
+
+impl NavmeshVolume {
+    /// Creates a volume from the corners of a convex footprint, extruded from `min_y` to `max_y`.
+    pub fn new(footprint: impl Into<Vec<Vec2>>, min_y: f32, max_y: f32, area: AreaType) -> Self {
+        Self {
+            footprint: footprint.into(),
+            min_y,
+            max_y,
+            area,
+        }
+    }
+
+    /// Creates a box centered on the entity, `half_size` being half its width and depth.
+    pub fn rectangle(half_size: Vec2, min_y: f32, max_y: f32, area: AreaType) -> Self {
+        Self::new(
+            [
+                Vec2::new(-half_size.x, -half_size.y),
+                Vec2::new(-half_size.x, half_size.y),
+                Vec2::new(half_size.x, half_size.y),
+                Vec2::new(half_size.x, -half_size.y),
+            ],
+            min_y,
+            max_y,
+            area,
+        )
+    }
+
+    /// The volume in world space.
+    pub fn to_world(&self, transform: &GlobalTransform) -> ConvexVolume {
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+        let (yaw, _, _) = rotation.to_euler(EulerRot::YXZ);
+        let upright = Transform {
+            translation,
+            rotation: Quat::from_rotation_y(yaw),
+            scale,
+        };
+        let (min_y, max_y) = (
+            translation.y + self.min_y * scale.y,
+            translation.y + self.max_y * scale.y,
+        );
+        ConvexVolume {
+            vertices: self
+                .footprint
+                .iter()
+                .map(|corner| {
+                    upright
+                        .transform_point(Vec3::new(corner.x, 0.0, corner.y))
+                        .xz()
+                })
+                .collect(),
+            min_y: min_y.min(max_y),
+            max_y: min_y.max(max_y),
+            area: self.area,
+        }
+    }
+}
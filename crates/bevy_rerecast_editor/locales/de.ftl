@@ -0,0 +1,166 @@
+# The messages of the editor UI in German. Messages missing here are shown in English.
+
+language-name = Deutsch
+
+## Menu bar
+
+menu-load-scene = Szene laden
+menu-build-navmesh = Navmesh bauen
+menu-step-build = Schrittweise bauen
+menu-save-navmesh = Navmesh speichern
+stage-progress = Schritt { $index }/{ $count }: { $stage }
+stage-rasterization = Rasterung
+stage-filtering = Filterung
+stage-compaction = Verdichtung
+stage-erosion = Erosion
+stage-distance-field = Distanzfeld
+stage-regions = Regionen
+stage-contours = Konturen
+stage-polygon-mesh = Polygonnetz
+stage-detail-mesh = Detailnetz
+
+## Status bar
+
+status-bar = Statusleiste
+status-version = Rerecast-Editor v{ $version }
+
+## Property panel
+
+sessions = Sitzungen
+sessions-add = Sitzung hinzufügen
+input-filter = Eingabefilter
+input-filter-name = Name
+input-filter-marker = Marker
+input-filter-bounds = Bereich
+show-visual = Darstellung anzeigen
+show-affector = Affektoren anzeigen
+show-polygon-mesh = Polygonnetz anzeigen
+show-detail-mesh = Detailnetz anzeigen
+show-off-mesh-links = Off-Mesh-Links anzeigen
+seed-from-camera = Von Kamera aus starten
+auto-rebuild = Automatisch neu bauen
+stage-view = Zwischenschritt
+stage-view-navmesh = Navmesh
+stage-view-heightfield = Höhenfeld-Spans
+stage-view-compact-heightfield = Kompaktes Höhenfeld
+stage-view-distance-field = Distanzfeld
+stage-view-regions = Regionen
+stage-view-raw-contours = Rohe Konturen
+stage-view-contours = Vereinfachte Konturen
+
+off-mesh-links = Off-Mesh-Links
+off-mesh-link-entry = { $marker } Link { $index }: { $status }
+off-mesh-link-not-validated = nicht geprüft
+off-mesh-link-valid = gültig
+off-mesh-link-invalid = ungültig
+off-mesh-link-start = Start: { $position }
+off-mesh-link-end = Ende: { $position }
+off-mesh-link-radius = Radius: { $radius }
+off-mesh-link-bidirectional = In beide Richtungen
+off-mesh-link-one-way = Einbahn
+off-mesh-link-unvalidated = Baue das Navmesh, um diesen Link zu prüfen.
+off-mesh-link-no-problems = Keine Probleme gefunden.
+off-mesh-link-start-off-navmesh = Der Start liegt nicht auf dem Navmesh
+off-mesh-link-end-off-navmesh = Das Ende liegt nicht auf dem Navmesh
+off-mesh-link-obstructed = Der Bogen ist bei { $point } blockiert
+
+volumes = Volumen
+show-volumes = Volumen anzeigen
+volume-add = Volumen hinzufügen
+volume-delete = Löschen
+volume-entry = { $marker } Volumen { $index }: Bereich { $area }
+volume-yaw = Gieren (Grad)
+volume-scale = Skalierung
+volume-min-y = Min. Y
+volume-max-y = Max. Y
+volume-area = Bereich
+
+input-entities = Eingabe-Entitäten
+input-source-affector = Affektor { $source }: { $triangles } Dreiecke
+input-source-obstruction = Affektor { $source }: { $triangles } Dreiecke, Hindernis
+input-source-visual = Darstellung { $source }
+input-source-more = ... und { $count } weitere
+
+payload-last-fetch = Letzter Abruf
+payload-not-fetched = Noch nicht abgerufen
+payload-transmitted = Übertragen: { $transmitted } ({ $uncompressed } unkomprimiert)
+payload-affector-meshes = Affektor-Meshes: { $size }
+payload-visual-meshes = Darstellungs-Meshes: { $size }
+payload-materials = Materialien: { $size }
+payload-images = Bilder: { $size }
+payload-other = Sonstiges: { $size }
+payload-largest-entities = Größte Entitäten:
+payload-unknown-entity = Unbekannte Entität
+
+## Config panel
+
+config = Konfiguration
+config-save-preset = Vorlage speichern
+config-load-preset = Vorlage laden
+config-suggest-settings = Einstellungen vorschlagen
+config-apply = Übernehmen
+config-field-cell-size = Zellgröße
+config-field-cell-height = Zellhöhe
+config-field-agent-height = Agentenhöhe
+config-field-agent-radius = Agentenradius
+config-field-agent-max-climb = Max. Stufenhöhe
+config-field-agent-max-slope = Max. Steigung
+config-field-region-min-size = Min. Regionsgröße
+config-field-region-merge-size = Regionen-Zusammenführung
+config-field-edge-max-len = Max. Kantenlänge
+config-field-edge-max-error = Max. Kantenfehler
+config-field-verts-per-poly = Ecken pro Polygon
+config-field-detail-sample-dist = Detail-Abtastabstand
+config-field-detail-sample-max-error = Max. Detailfehler
+config-field-tile-size = Kachelgröße
+config-field-slope-cost = Steigungskosten
+config-field-agent-radius-tooltip = Der Radius des Agenten. Das Navmesh hält diesen Abstand zu Wänden.
+config-field-agent-height-tooltip = Die Höhe des Agenten. Bereiche mit weniger Platz sind nicht begehbar.
+config-field-agent-max-climb-tooltip = Die höchste Kante oder Stufe, die der Agent erklimmen kann.
+config-field-agent-max-slope-tooltip = Die steilste Steigung, auf der der Agent laufen kann.
+unit-voxels = Vx
+unit-degrees = Grad
+unit-off = aus
+suggested-settings = Vorschlag: Zellhöhe { $cell_height }, max. Stufenhöhe { $max_climb }
+suggested-steps = Stufen { $min }-{ $max } wu
+suggested-no-steps = keine Stufen
+suggested-lowest-passage = niedrigster Durchgang { $height } wu
+suggested-no-low-passages = keine niedrigen Durchgänge
+
+sweep = Durchlauf
+sweep-to = bis
+sweep-in = in
+sweep-steps = Schritten
+sweep-run = Durchlauf starten
+sweep-bake = Bauzeit
+sweep-polys = Polys
+sweep-verts = Ecken
+sweep-detail-tris = Detail-Dreiecke
+sweep-failed = fehlgeschlagen
+sweep-remaining = { $count } Bauvorgänge verbleibend...
+
+## Timeline
+
+timeline-no-bake = Noch nicht gebaut
+timeline-last-bake = Letzter Bau: { $total }
+timeline-last-bake-compared = Letzter Bau: { $total } (vorher: { $previous }, { $delta })
+
+## Load scene dialog
+
+load-scene-title = Szene laden
+load-scene-load = Laden
+
+## Onboarding wizard
+
+onboarding-title = Willkommen im Rerecast-Editor
+onboarding-probing = Suche nach einem laufenden Spiel unter { $url } ...
+onboarding-game-found = Spiel unter { $url } gefunden. Rufe seine Szene ab, um ein Navmesh dafür zu bauen.
+onboarding-incompatible-game = Spiel unter { $url } gefunden. { $reason }
+onboarding-unreadable-game = Spiel unter { $url } gefunden, aber der Editor kann seine Szene nicht lesen.
+onboarding-missing-integration = Unter { $url } läuft ein Spiel, aber es hat keine Editor-Integration. Füge ihm `NavmeshPlugins` mit dem Feature `editor_integration` hinzu und starte es neu.
+onboarding-no-game = Unter { $url } läuft kein Spiel. Starte dein Spiel mit `RemotePlugin`, `RemoteHttpPlugin` und dem Feature `editor_integration` von `NavmeshPlugins`, oder probiere den Editor mit der Beispielszene aus.
+onboarding-fetch-scene = Szene abrufen
+onboarding-retry = Erneut versuchen
+onboarding-open-navmesh = Navmesh-Datei öffnen
+onboarding-load-sample-scene = Beispielszene laden
+onboarding-skip = Überspringen
@@ -0,0 +1,147 @@
+# The messages of the editor UI in English. Every other locale falls back to these.
+#
+# Config fields and build stages are named after their names in the library unless a locale names them
+# with `config-field-<name>`, `config-field-<name>-tooltip` and `stage-<name>`, e.g. `config-field-agent-radius`.
+
+language-name = English
+
+## Menu bar
+
+menu-load-scene = Load Scene
+menu-build-navmesh = Build Navmesh
+menu-step-build = Step Build
+menu-save-navmesh = Save Navmesh
+stage-progress = Stage { $index }/{ $count }: { $stage }
+
+## Status bar
+
+status-bar = Status Bar
+status-version = Rerecast Editor v{ $version }
+
+## Property panel
+
+sessions = Sessions
+sessions-add = Add Session
+input-filter = Input Filter
+input-filter-name = Name
+input-filter-marker = Marker
+input-filter-bounds = Bounds
+show-visual = Show Visual
+show-affector = Show Affector
+show-polygon-mesh = Show Polygon Mesh
+show-detail-mesh = Show Detail Mesh
+show-off-mesh-links = Show Off-Mesh Links
+seed-from-camera = Seed From Camera
+auto-rebuild = Auto Rebuild
+stage-view = Stage View
+stage-view-navmesh = Navmesh
+stage-view-heightfield = Heightfield Spans
+stage-view-compact-heightfield = Compact Heightfield
+stage-view-distance-field = Distance Field
+stage-view-regions = Regions
+stage-view-raw-contours = Raw Contours
+stage-view-contours = Simplified Contours
+
+off-mesh-links = Off-Mesh Links
+off-mesh-link-entry = { $marker } Link { $index }: { $status }
+off-mesh-link-not-validated = not validated
+off-mesh-link-valid = valid
+off-mesh-link-invalid = invalid
+off-mesh-link-start = Start: { $position }
+off-mesh-link-end = End: { $position }
+off-mesh-link-radius = Radius: { $radius }
+off-mesh-link-bidirectional = Bidirectional
+off-mesh-link-one-way = One-way
+off-mesh-link-unvalidated = Build the navmesh to validate this link.
+off-mesh-link-no-problems = No problems found.
+off-mesh-link-start-off-navmesh = Start is not on the navmesh
+off-mesh-link-end-off-navmesh = End is not on the navmesh
+off-mesh-link-obstructed = Arc is obstructed at { $point }
+
+volumes = Volumes
+show-volumes = Show Volumes
+volume-add = Add Volume
+volume-delete = Delete
+volume-entry = { $marker } Volume { $index }: area { $area }
+volume-x = X
+volume-y = Y
+volume-z = Z
+volume-yaw = Yaw (deg)
+volume-scale = Scale
+volume-min-y = Min Y
+volume-max-y = Max Y
+volume-area = Area
+
+input-entities = Input Entities
+input-source-affector = Affector { $source }: { $triangles } tris
+input-source-obstruction = Affector { $source }: { $triangles } tris, obstruction
+input-source-visual = Visual { $source }
+input-source-more = ... and { $count } more
+
+payload-last-fetch = Last Fetch
+payload-not-fetched = Not fetched yet
+payload-transmitted = Transmitted: { $transmitted } ({ $uncompressed } uncompressed)
+payload-affector-meshes = Affector meshes: { $size }
+payload-visual-meshes = Visual meshes: { $size }
+payload-materials = Materials: { $size }
+payload-images = Images: { $size }
+payload-other = Other: { $size }
+payload-largest-entities = Largest entities:
+payload-unknown-entity = Unknown entity
+
+## Config panel
+
+config = Config
+config-save-preset = Save Preset
+config-load-preset = Load Preset
+config-suggest-settings = Suggest Settings
+config-apply = Apply
+unit-world-units = wu
+unit-voxels = vx
+unit-cell-sizes = cs
+unit-cell-heights = ch
+unit-degrees = deg
+unit-off = off
+suggested-settings = Suggested: cell height { $cell_height }, max climb { $max_climb }
+suggested-steps = steps { $min }-{ $max } wu
+suggested-no-steps = no steps
+suggested-lowest-passage = lowest passage { $height } wu
+suggested-no-low-passages = no low passages
+
+sweep = Sweep
+sweep-to = to
+sweep-in = in
+sweep-steps = steps
+sweep-run = Run Sweep
+sweep-bake = Bake
+sweep-polys = Polys
+sweep-verts = Verts
+sweep-detail-tris = Detail Tris
+sweep-failed = failed
+sweep-remaining = { $count } bakes remaining...
+
+## Timeline
+
+timeline-no-bake = No bake yet
+timeline-last-bake = Last bake: { $total }
+timeline-last-bake-compared = Last bake: { $total } (previous: { $previous }, { $delta })
+
+## Load scene dialog
+
+load-scene-title = Load Scene
+load-scene-load = Load
+
+## Onboarding wizard
+
+onboarding-title = Welcome to the Rerecast Editor
+onboarding-probing = Looking for a running game at { $url } ...
+onboarding-game-found = Found a game at { $url }. Fetch its scene to build a navmesh for it.
+onboarding-incompatible-game = Found a game at { $url }. { $reason }
+onboarding-unreadable-game = Found a game at { $url }, but the editor cannot read its scene.
+onboarding-missing-integration = A game is running at { $url }, but it has no editor integration. Add `NavmeshPlugins` with the `editor_integration` feature to it and restart it.
+onboarding-no-game = No game is running at { $url }. Start your game with `RemotePlugin`, `RemoteHttpPlugin` and the `editor_integration` feature of `NavmeshPlugins`, or try the editor on the sample scene.
+onboarding-fetch-scene = Fetch Scene
+onboarding-retry = Retry
+onboarding-open-navmesh = Open Navmesh File
+onboarding-load-sample-scene = Load Sample Scene
+onboarding-skip = Skip
@@ -1,15 +1,37 @@
+use std::{
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+    time::Duration,
+};
+
 use anyhow::Context;
-use bevy::prelude::*;
+use bevy::{
+    ecs::system::SystemParam,
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
 use bevy_rerecast::{
-    TriMeshFromBevyMesh as _,
-    rerecast::{self, DetailNavmesh, HeightfieldBuilder, TriMesh},
+    NavmeshAffectorAreas, NavmeshObstruction, TriMeshFromBevyMesh as _,
+    rerecast::{
+        self, Aabb3d, AreaMergePolicy, BuildStage, DetailNavmesh, Heightfield, HeightfieldBuilder,
+        NavmeshConfig, RecordedSpans, RegionPartitioning, TriMesh,
+    },
+    volume::NavmeshVolume,
 };
 
-use crate::visualization::Navmesh;
+use crate::{
+    session::{SessionId, Sessions},
+    timeline::{BakeTimings, StageTimer},
+    visualization::{Navmesh, StageSnapshots, StageView},
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_observer(build_navmesh);
     app.init_resource::<BuildNavmeshConfig>();
+    app.init_resource::<SeedFromCamera>();
+    app.init_resource::<AutoRebuild>();
+    app.init_resource::<PendingRebuild>();
+    app.init_resource::<RasterizationCache>();
+    app.add_systems(Update, (invalidate_modified_meshes, auto_rebuild));
 }
 
 #[derive(Event)]
@@ -18,100 +40,398 @@ pub(crate) struct BuildNavmesh;
 #[derive(Resource, Default, Deref, DerefMut)]
 pub(crate) struct BuildNavmeshConfig(rerecast::NavmeshConfigBuilder);
 
+/// Whether only regions reachable from the ground below the camera are kept,
+/// so that e.g. rooftops a player cannot get to are left out of the build.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub(crate) struct SeedFromCamera(pub(crate) bool);
+
+/// Whether the navmesh is rebuilt shortly after the config changes, so that tuning it is interactive.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub(crate) struct AutoRebuild(pub(crate) bool);
+
+/// How long the config has to stay unchanged before [`AutoRebuild`] rebuilds the navmesh,
+/// so that typing or stepping through values doesn't rebuild after every change.
+const AUTO_REBUILD_DELAY: Duration = Duration::from_millis(300);
+
+/// The countdown to the next automatic rebuild, restarted whenever the config changes.
+#[derive(Resource, Default)]
+struct PendingRebuild(Option<Timer>);
+
+/// A mesh affecting the navmesh, along with how the backend of the game marks its triangles.
+/// Affectors with a [`NavmeshObstruction`] are unwalkable regardless of the areas.
 #[derive(Component)]
-pub(crate) struct NavmeshAffector;
+pub(crate) struct NavmeshAffector(pub(crate) NavmeshAffectorAreas);
 
-fn build_navmesh(
-    _trigger: Trigger<BuildNavmesh>,
-    affectors: Query<(&Mesh3d, &GlobalTransform), With<NavmeshAffector>>,
-    meshes: Res<Assets<Mesh>>,
+/// Rasterized spans of each affector from the previous builds,
+/// so that rebuilding after moving a few affectors only needs to rasterize those again.
+#[derive(Resource, Default, Deref, DerefMut)]
+struct RasterizationCache(HashMap<AffectorKey, CachedAffector>);
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub(crate) struct AffectorKey {
+    mesh: AssetId<Mesh>,
+    transform: u64,
+    areas: NavmeshAffectorAreas,
+    obstruction: bool,
+}
+
+impl AffectorKey {
+    fn new(
+        mesh: &Mesh3d,
+        transform: &GlobalTransform,
+        areas: NavmeshAffectorAreas,
+        obstruction: bool,
+    ) -> Self {
+        let mut hasher = DefaultHasher::new();
+        for value in transform.affine().to_cols_array() {
+            value.to_bits().hash(&mut hasher);
+        }
+        Self {
+            mesh: mesh.id(),
+            transform: hasher.finish(),
+            areas,
+            obstruction,
+        }
+    }
+}
+
+struct CachedAffector {
+    /// The world-space geometry of the affector, before marking walkable triangles.
+    trimesh: TriMesh,
+    aabb: Aabb3d,
+    /// The recorded spans, along with the hash of the config they were recorded with.
+    spans: Option<(u64, RecordedSpans)>,
+}
+
+fn invalidate_modified_meshes(
+    mut events: EventReader<AssetEvent<Mesh>>,
+    mut cache: ResMut<RasterizationCache>,
+) {
+    for event in events.read() {
+        if let AssetEvent::Modified { id } | AssetEvent::Removed { id } = event {
+            cache.retain(|key, _| key.mesh != *id);
+        }
+    }
+}
+
+/// Triggers [`BuildNavmesh`] once the config has stayed unchanged for [`AUTO_REBUILD_DELAY`], if [`AutoRebuild`] is enabled.
+/// The build discards a stepped build that is still in progress, as it was started with the old config.
+fn auto_rebuild(
+    auto_rebuild: Res<AutoRebuild>,
     config: Res<BuildNavmeshConfig>,
+    time: Res<Time>,
+    mut pending: ResMut<PendingRebuild>,
     mut commands: Commands,
-) -> Result {
-    let mut trimesh = TriMesh::default();
-    let config = config.build();
-    for (mesh, transform) in affectors.iter() {
-        let Some(mesh) = meshes.get(mesh) else {
-            warn!("Failed to get mesh for navmesh build. Skipping.");
-            continue;
-        };
-        let Some(mut current_trimesh) = TriMesh::from_mesh(mesh) else {
-            warn!("Failed to convert collider to trimesh. Skipping.");
-            continue;
-        };
-        let transform = transform.compute_transform();
-        for vertex in &mut current_trimesh.vertices {
-            *vertex = transform.transform_point(Vec3::from(*vertex)).into();
-        }
-        trimesh.extend(current_trimesh);
+) {
+    if !**auto_rebuild {
+        pending.0 = None;
+        return;
+    }
+    if config.is_changed() && !config.is_added() {
+        pending.0 = Some(Timer::new(AUTO_REBUILD_DELAY, TimerMode::Once));
+    }
+    let Some(timer) = &mut pending.0 else {
+        return;
+    };
+    if timer.tick(time.delta()).finished() {
+        pending.0 = None;
+        commands.trigger(BuildNavmesh);
+    }
+}
+
+/// The affectors of the active session, rasterized through the [`RasterizationCache`].
+#[derive(SystemParam)]
+pub(crate) struct SessionAffectors<'w, 's> {
+    affectors: Query<
+        'w,
+        's,
+        (
+            &'static Mesh3d,
+            &'static GlobalTransform,
+            &'static SessionId,
+            &'static NavmeshAffector,
+            Has<NavmeshObstruction>,
+        ),
+    >,
+    meshes: Res<'w, Assets<Mesh>>,
+    sessions: Res<'w, Sessions>,
+    cache: ResMut<'w, RasterizationCache>,
+}
+
+impl SessionAffectors<'_, '_> {
+    /// The session whose affectors are rasterized.
+    pub(crate) fn session(&self) -> SessionId {
+        self.sessions.active_id()
     }
 
-    let aabb = trimesh.compute_aabb().context("Trimesh is empty")?;
+    /// Records the spans of every affector of the active session that are not cached for `config` yet.
+    /// Returns an empty heightfield covering all affectors, along with the keys of their spans for [`Self::spans`].
+    pub(crate) fn record(
+        &mut self,
+        config: &NavmeshConfig,
+    ) -> Result<(Heightfield, Vec<AffectorKey>)> {
+        let mut keys = Vec::new();
+        for (mesh, transform, session, affector, obstruction) in self.affectors.iter() {
+            if *session != self.sessions.active_id() {
+                continue;
+            }
+            let key = AffectorKey::new(mesh, transform, affector.0, obstruction);
+            if !self.cache.contains_key(&key) {
+                let Some(mesh) = self.meshes.get(mesh) else {
+                    warn!("Failed to get mesh for navmesh build. Skipping.");
+                    continue;
+                };
+                let Some(mut trimesh) = TriMesh::from_mesh(mesh) else {
+                    warn!("Failed to convert collider to trimesh. Skipping.");
+                    continue;
+                };
+                let transform = transform.compute_transform();
+                for vertex in &mut trimesh.vertices {
+                    *vertex = transform.transform_point(Vec3::from(*vertex)).into();
+                }
+                let Some(aabb) = trimesh.compute_aabb() else {
+                    continue;
+                };
+                self.cache.insert(
+                    key,
+                    CachedAffector {
+                        trimesh,
+                        aabb,
+                        spans: None,
+                    },
+                );
+            }
+            keys.push(key);
+        }
+        let used_keys: HashSet<_> = keys.iter().copied().collect();
+        self.cache.retain(|key, _| used_keys.contains(key));
+
+        let aabb = keys
+            .iter()
+            .map(|key| self.cache[key].aabb)
+            .reduce(|a, b| Aabb3d {
+                min: a.min.min(b.min),
+                max: a.max.max(b.max),
+            })
+            .context("Trimesh is empty")?;
+
+        let heightfield = HeightfieldBuilder {
+            aabb,
+            cell_size: config.cell_size,
+            cell_height: config.cell_height,
+        }
+        .build()?;
 
-    trimesh.mark_walkable_triangles(config.walkable_slope_angle);
+        let mut hasher = DefaultHasher::new();
+        for value in [
+            aabb.min.to_array(),
+            aabb.max.to_array(),
+            [
+                config.cell_size,
+                config.cell_height,
+                config.walkable_slope_angle,
+            ],
+        ]
+        .as_flattened()
+        {
+            value.to_bits().hash(&mut hasher);
+        }
+        let config_hash = hasher.finish();
 
-    let mut heightfield = HeightfieldBuilder {
-        aabb,
-        cell_size: config.cell_size,
-        cell_height: config.cell_height,
+        for key in &keys {
+            let Some(affector) = self.cache.get_mut(key) else {
+                continue;
+            };
+            if affector
+                .spans
+                .as_ref()
+                .is_some_and(|(hash, _)| *hash == config_hash)
+            {
+                continue;
+            }
+            let mut trimesh = affector.trimesh.clone();
+            if key.obstruction {
+                NavmeshObstruction::mark(&mut trimesh);
+            } else {
+                key.areas.mark(&mut trimesh, config.walkable_slope_angle);
+            }
+            affector.spans = Some((config_hash, heightfield.record_triangles(&trimesh)?));
+        }
+        Ok((heightfield, keys))
     }
-    .build()?;
 
-    heightfield.rasterize_triangles(&trimesh, config.walkable_climb)?;
+    /// The spans of an affector, once [`Self::record`] recorded them.
+    pub(crate) fn spans(&self, key: &AffectorKey) -> Option<&RecordedSpans> {
+        self.cache.get(key)?.spans.as_ref().map(|(_, spans)| spans)
+    }
 
+    /// The world-space geometry of an affector, before marking walkable triangles.
+    fn trimesh(&self, key: &AffectorKey) -> &TriMesh {
+        &self.cache[key].trimesh
+    }
+}
+
+fn build_navmesh(
+    _trigger: Trigger<BuildNavmesh>,
+    mut affectors: SessionAffectors,
+    volumes: Query<(&NavmeshVolume, &Transform, &SessionId)>,
+    config: Res<BuildNavmeshConfig>,
+    seed_from_camera: Res<SeedFromCamera>,
+    camera: Single<&GlobalTransform, With<Camera3d>>,
+    stage_view: Res<StageView>,
+    mut timings: ResMut<BakeTimings>,
+    mut commands: Commands,
+) -> Result {
+    let config = config.build();
+    let keep_snapshots = *stage_view != StageView::Navmesh;
+    let session = affectors.session();
+    let mut timer = StageTimer::default();
+    timer.begin(BuildStage::Rasterization);
+    let (mut heightfield, keys) = affectors.record(&config)?;
+    for key in &keys {
+        if let Some(spans) = affectors.spans(key) {
+            heightfield.replay_spans(spans, config.walkable_climb, AreaMergePolicy::default())?;
+        }
+    }
+
+    timer.begin(BuildStage::Filtering);
     // Once all geometry is rasterized, we do initial pass of filtering to
     // remove unwanted overhangs caused by the conservative rasterization
     // as well as filter spans where the character cannot possibly stand.
     heightfield.filter_low_hanging_walkable_obstacles(config.walkable_climb);
-    heightfield.filter_ledge_spans(config.walkable_height, config.walkable_climb);
+    heightfield.filter_ledge_spans_with(
+        config.walkable_height,
+        config.walkable_climb,
+        config.ledge_filter,
+    );
     heightfield.filter_walkable_low_height_spans(config.walkable_height);
+    heightfield.compact_spans();
+    let heightfield_snapshot = keep_snapshots.then(|| heightfield.clone());
 
+    timer.begin(BuildStage::Compaction);
     let mut compact_heightfield =
         heightfield.into_compact(config.walkable_height, config.walkable_climb)?;
 
-    compact_heightfield.erode_walkable_area(config.walkable_radius);
+    timer.begin(BuildStage::Erosion);
+    compact_heightfield.erode_walkable_area_with(config.walkable_radius, config.erosion_method);
 
-    /*
-    let volume = ConvexVolume {
-        vertices: volume
-            .verts
-            .iter()
-            .map(|[x, _y, z]| Vec2::new(*x, *z))
-            .collect(),
-        min_y: volume.hmin,
-        max_y: volume.hmax,
-        area: AreaType::from(volume.area),
-    };
-    compact_heightfield.mark_convex_poly_area(volume);
-    */
+    for volume in active_volumes(&volumes, session) {
+        compact_heightfield.mark_convex_poly_area(volume);
+    }
 
-    compact_heightfield.build_distance_field();
+    timer.begin(BuildStage::DistanceField);
+    if config.region_partitioning == RegionPartitioning::Watershed {
+        compact_heightfield.build_distance_field();
+    }
 
-    compact_heightfield.build_regions(
+    timer.begin(BuildStage::Regions);
+    let camera = camera.translation();
+    let seed = seed_from_camera
+        .then(|| {
+            keys.iter()
+                .filter_map(|key| ground_below(camera, affectors.trimesh(key)))
+                .reduce(f32::max)
+        })
+        .flatten();
+    compact_heightfield.build_regions_with(
+        config.region_partitioning,
         config.border_size,
         config.min_region_area,
         config.merge_region_area,
     )?;
+    match seed {
+        Some(ground) => compact_heightfield.discard_unreachable_regions(&[camera.with_y(ground)]),
+        None => {
+            if **seed_from_camera {
+                warn!("No ground below the camera to seed the navmesh from. Keeping all regions.");
+            }
+        }
+    }
+    if let Some(max_extent) = config.max_region_extent {
+        compact_heightfield.split_large_regions(max_extent)?;
+    }
+    let slopes = (config.slope_cost > 0.0).then(|| compact_heightfield.region_slopes());
 
+    timer.begin(BuildStage::Contours);
     let contours = compact_heightfield.build_contours(
         config.max_simplification_error,
         config.max_edge_len,
         config.contour_flags,
     );
 
-    let poly_mesh = contours.into_polygon_mesh(config.max_vertices_per_polygon)?;
+    let contours_snapshot = keep_snapshots.then(|| contours.clone());
 
-    let detail_mesh = DetailNavmesh::new(
+    timer.begin(BuildStage::PolygonMesh);
+    let mut poly_mesh = contours.into_polygon_mesh(config.max_vertices_per_polygon)?;
+    if config.cache_polygon_geometry {
+        poly_mesh.cache_polygon_geometry();
+    }
+    if let Some(slopes) = slopes {
+        poly_mesh.bake_slope_costs(&slopes, config.slope_cost);
+    }
+
+    timer.begin(BuildStage::DetailMesh);
+    let detail_mesh = DetailNavmesh::new_with(
         &poly_mesh,
         &compact_heightfield,
         config.detail_sample_dist,
         config.detail_sample_max_error,
+        config.detail_sampling,
     )?;
+    timings.record(timer.finish());
 
+    match (heightfield_snapshot, contours_snapshot) {
+        (Some(heightfield), Some(contours)) => commands.insert_resource(StageSnapshots {
+            heightfield,
+            compact_heightfield,
+            contours,
+            session,
+        }),
+        _ => commands.remove_resource::<StageSnapshots>(),
+    }
     commands.insert_resource(Navmesh {
         poly_mesh,
         detail_mesh,
+        session,
     });
 
     Ok(())
 }
+
+/// The volumes of the given session in world space.
+pub(crate) fn active_volumes(
+    volumes: &Query<(&NavmeshVolume, &Transform, &SessionId)>,
+    active: SessionId,
+) -> Vec<rerecast::ConvexVolume> {
+    // The volumes are edited through their transforms, which may not have propagated yet.
+    volumes
+        .iter()
+        .filter(|(volume, _, session)| **session == active && volume.footprint.len() >= 3)
+        .map(|(volume, transform, _)| volume.to_world(&(*transform).into()))
+        .collect()
+}
+
+/// Returns the height of the highest triangle of `trimesh` below `point`, if any.
+fn ground_below(point: Vec3, trimesh: &TriMesh) -> Option<f32> {
+    let xz = point.xz();
+    trimesh
+        .indices
+        .iter()
+        .filter_map(|indices| {
+            let [a, b, c] = indices
+                .to_array()
+                .map(|i| Vec3::from(trimesh.vertices[i as usize]));
+            let (ab, ac, ap) = (b.xz() - a.xz(), c.xz() - a.xz(), xz - a.xz());
+            let denominator = ab.perp_dot(ac);
+            if denominator.abs() <= f32::EPSILON {
+                // Vertical triangles have no ground to stand on.
+                return None;
+            }
+            let u = ap.perp_dot(ac) / denominator;
+            let v = ab.perp_dot(ap) / denominator;
+            (u >= 0.0 && v >= 0.0 && u + v <= 1.0)
+                .then_some(a.y + (b.y - a.y) * u + (c.y - a.y) * v)
+        })
+        .filter(|height| *height <= point.y)
+        .reduce(f32::max)
+}
@@ -18,6 +18,7 @@ pub struct CameraControllerPlugin;
 
 impl Plugin for CameraControllerPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<CameraFocus>();
         app.add_systems(Update, run_camera_controller);
     }
 }
@@ -55,6 +56,11 @@ pub struct CameraController {
     pub mouse_key_cursor_grab: MouseButton,
     /// [`KeyCode`] for grabbing the keyboard focus.
     pub keyboard_key_toggle_cursor_grab: KeyCode,
+    /// [`KeyCode`] that lets the mouse scroll wheel adjust the speed while the cursor is not grabbed.
+    /// While the cursor is grabbed, scrolling always adjusts the speed.
+    pub key_scroll_speed: KeyCode,
+    /// [`KeyCode`] for framing the current selection, see [`CameraFocus`].
+    pub key_focus: KeyCode,
     /// Multiplier for unmodified translation speed.
     pub walk_speed: f32,
     /// Multiplier for running translation speed.
@@ -62,6 +68,12 @@ pub struct CameraController {
     /// Multiplier for how the mouse scroll wheel modifies [`walk_speed`](CameraController::walk_speed)
     /// and [`run_speed`](CameraController::run_speed).
     pub scroll_factor: f32,
+    /// The lower limit of [`walk_speed`](CameraController::walk_speed) when adjusted by scrolling.
+    pub min_speed: f32,
+    /// The upper limit of [`walk_speed`](CameraController::walk_speed) when adjusted by scrolling.
+    pub max_speed: f32,
+    /// How long it takes to fly to a new position set by [`CameraController::fly_to`], in seconds.
+    pub transition_duration: f32,
     /// Friction factor used to exponentially decay [`velocity`](CameraController::velocity) over time.
     pub friction: f32,
     /// This [`CameraController`]'s pitch rotation.
@@ -70,8 +82,36 @@ pub struct CameraController {
     pub yaw: f32,
     /// This [`CameraController`]'s translation velocity.
     pub velocity: Vec3,
+    /// The flight started by [`CameraController::fly_to`], if it is still ongoing.
+    transition: Option<Transition>,
 }
 
+/// A smooth flight from one translation to another.
+struct Transition {
+    /// Set to the current translation once the transition starts.
+    from: Option<Vec3>,
+    to: Vec3,
+    elapsed: f32,
+}
+
+impl CameraController {
+    /// Smoothly moves the camera to the given translation over [`transition_duration`](CameraController::transition_duration)
+    /// seconds, keeping its orientation. Flying manually cancels the transition.
+    pub fn fly_to(&mut self, to: Vec3) {
+        self.velocity = Vec3::ZERO;
+        self.transition = Some(Transition {
+            from: None,
+            to,
+            elapsed: 0.0,
+        });
+    }
+}
+
+/// Sent when the [`key_focus`](CameraController::key_focus) of the [`CameraController`] is pressed.
+/// The app decides what to frame and calls [`CameraController::fly_to`].
+#[derive(Event)]
+pub struct CameraFocus;
+
 impl Default for CameraController {
     fn default() -> Self {
         Self {
@@ -87,13 +127,19 @@ impl Default for CameraController {
             key_run: KeyCode::ShiftLeft,
             mouse_key_cursor_grab: MouseButton::Right,
             keyboard_key_toggle_cursor_grab: KeyCode::KeyM,
+            key_scroll_speed: KeyCode::AltLeft,
+            key_focus: KeyCode::KeyF,
             walk_speed: 15.0,
             run_speed: 45.0,
             scroll_factor: 0.1,
+            min_speed: 0.5,
+            max_speed: 500.0,
+            transition_duration: 0.35,
             friction: 0.5,
             pitch: 0.0,
             yaw: 0.0,
             velocity: Vec3::ZERO,
+            transition: None,
         }
     }
 }
@@ -105,13 +151,17 @@ impl fmt::Display for CameraController {
             "
 Freecam Controls:
     Mouse\t- Move camera orientation
-    Scroll\t- Adjust movement speed
+    Scroll\t- Adjust movement speed while the cursor is grabbed
+    {:?} & Scroll\t- Adjust movement speed
+    {:?}\t- Frame the selection
     {:?}\t- Hold to grab cursor
     {:?}\t- Toggle cursor grab
     {:?} & {:?}\t- Fly forward & backwards
     {:?} & {:?}\t- Fly sideways left & right
     {:?} & {:?}\t- Fly up & down
     {:?}\t- Fly faster while held",
+            self.key_scroll_speed,
+            self.key_focus,
             self.mouse_key_cursor_grab,
             self.keyboard_key_toggle_cursor_grab,
             self.key_forward,
@@ -135,6 +185,7 @@ fn run_camera_controller(
     mut toggle_cursor_grab: Local<bool>,
     mut mouse_cursor_grab: Local<bool>,
     mut query: Query<(&mut Transform, &mut CameraController), With<Camera>>,
+    mut focus: EventWriter<CameraFocus>,
 ) {
     let dt = time.delta_secs();
 
@@ -153,15 +204,9 @@ fn run_camera_controller(
         return;
     }
 
-    let mut scroll = 0.0;
-
-    let amount = match accumulated_mouse_scroll.unit {
-        MouseScrollUnit::Line => accumulated_mouse_scroll.delta.y,
-        MouseScrollUnit::Pixel => accumulated_mouse_scroll.delta.y / 16.0,
-    };
-    scroll += amount;
-    controller.walk_speed += scroll * controller.scroll_factor * controller.walk_speed;
-    controller.run_speed = controller.walk_speed * 3.0;
+    if key_input.just_pressed(controller.key_focus) {
+        focus.write(CameraFocus);
+    }
 
     // Handle key input
     let mut axis_input = Vec3::ZERO;
@@ -199,6 +244,18 @@ fn run_camera_controller(
     }
     let cursor_grab = *mouse_cursor_grab || *toggle_cursor_grab;
 
+    // Only adjust the speed when the scroll wheel is not needed for scrolling the UI.
+    if cursor_grab || key_input.pressed(controller.key_scroll_speed) {
+        let scroll = match accumulated_mouse_scroll.unit {
+            MouseScrollUnit::Line => accumulated_mouse_scroll.delta.y,
+            MouseScrollUnit::Pixel => accumulated_mouse_scroll.delta.y / 16.0,
+        };
+        controller.walk_speed = (controller.walk_speed
+            + scroll * controller.scroll_factor * controller.walk_speed)
+            .clamp(controller.min_speed, controller.max_speed);
+        controller.run_speed = controller.walk_speed * 3.0;
+    }
+
     if !cursor_grab {
         axis_input = Vec3::ZERO;
     }
@@ -219,6 +276,22 @@ fn run_camera_controller(
         }
     }
 
+    // Apply transition
+    if axis_input != Vec3::ZERO {
+        controller.transition = None;
+    }
+    let duration = controller.transition_duration.max(f32::EPSILON);
+    if let Some(transition) = &mut controller.transition {
+        transition.elapsed += dt;
+        let t = (transition.elapsed / duration).min(1.0);
+        let eased = t * t * (3.0 - 2.0 * t);
+        let from = *transition.from.get_or_insert(transform.translation);
+        transform.translation = from.lerp(transition.to, eased);
+        if t >= 1.0 {
+            controller.transition = None;
+        }
+    }
+
     // Apply movement update
     if controller.velocity != Vec3::ZERO {
         let forward = *transform.forward();
@@ -1,18 +1,25 @@
-use bevy::prelude::*;
+use bevy::{picking::mesh_picking::MeshPickingCamera, prelude::*, render::primitives::Aabb};
 
-use crate::camera::camera_controller::CameraController;
+use crate::{
+    build::NavmeshAffector,
+    camera::camera_controller::{CameraController, CameraFocus},
+    off_mesh_link::{OffMeshLink, SelectedOffMeshLink},
+    visualization::{Navmesh, VisualMesh},
+};
 
 mod camera_controller;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(Startup, setup);
     app.add_plugins(camera_controller::CameraControllerPlugin);
+    app.add_systems(Update, focus_camera.run_if(on_event::<CameraFocus>));
 }
 
 fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn((
         Camera3d::default(),
         CameraController::default(),
+        MeshPickingCamera,
         Transform::from_xyz(19.769, 50.702, 20.619).looking_at(Vec3::new(0.0, 10.0, 0.0), Vec3::Y),
         EnvironmentMapLight {
             diffuse_map: asset_server.load("environment_maps/voortrekker_interior_1k_diffuse.ktx2"),
@@ -27,3 +34,59 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         Transform::default().looking_to(Vec3::new(0.5, -1.0, 0.3), Vec3::Y),
     ));
 }
+
+/// Frames the selected off-mesh link, or the whole navmesh if nothing is selected,
+/// or the scene if no navmesh has been built yet.
+fn focus_camera(
+    mut camera: Single<(&Transform, &Projection, &mut CameraController)>,
+    selected_link: Res<SelectedOffMeshLink>,
+    links: Query<&OffMeshLink>,
+    navmesh: Option<Res<Navmesh>>,
+    meshes: Query<(&Aabb, &GlobalTransform), Or<(With<VisualMesh>, With<NavmeshAffector>)>>,
+) {
+    let link = selected_link.and_then(|entity| links.get(entity).ok());
+    let (min, max) = if let Some(OffMeshLink { link, .. }) = link {
+        let radius = Vec3::splat(link.radius);
+        (
+            link.start.min(link.end) - radius,
+            link.start.max(link.end) + radius,
+        )
+    } else if let Some(navmesh) = navmesh
+        && navmesh.poly_mesh.polygon_count() > 0
+    {
+        let aabb = navmesh.poly_mesh.aabb;
+        (aabb.min, aabb.max)
+    } else {
+        let Some(bounds) = meshes
+            .iter()
+            .flat_map(|(aabb, transform)| {
+                let (center, half) = (Vec3::from(aabb.center), Vec3::from(aabb.half_extents));
+                [-1.0, 1.0].into_iter().flat_map(move |x| {
+                    [-1.0, 1.0].into_iter().flat_map(move |y| {
+                        [-1.0, 1.0].map(move |z| {
+                            transform.transform_point(center + half * Vec3::new(x, y, z))
+                        })
+                    })
+                })
+            })
+            .fold(None, |bounds: Option<(Vec3, Vec3)>, corner| {
+                Some(bounds.map_or((corner, corner), |(min, max)| {
+                    (min.min(corner), max.max(corner))
+                }))
+            })
+        else {
+            return;
+        };
+        bounds
+    };
+
+    let (transform, projection, controller) = &mut *camera;
+    let fov = match projection {
+        Projection::Perspective(perspective) => perspective.fov,
+        _ => PerspectiveProjection::default().fov,
+    };
+    let center = (min + max) / 2.0;
+    let radius = ((max - min).length() / 2.0).max(1.0);
+    let distance = radius / (fov / 2.0).sin();
+    controller.fly_to(center - transform.forward() * distance);
+}
@@ -0,0 +1,426 @@
+//! The panel for editing the [`BuildNavmeshConfig`].
+
+use bevy::{
+    ecs::{spawn::SpawnWith, system::ObserverSystem},
+    prelude::*,
+    ui::Val::*,
+};
+use bevy_rerecast::{
+    NavmeshObstruction, TriMeshFromBevyMesh as _,
+    rerecast::{
+        self, ConfigRounding, ConfigUnit, NavmeshConfigBuilder, TriMesh,
+        VersionedNavmeshConfigBuilder, VerticalAnalysis,
+    },
+};
+
+use crate::{
+    build::{BuildNavmeshConfig, NavmeshAffector},
+    localization::Localization,
+    session::{SessionId, Sessions},
+    sweep::sweep_section,
+    theme::{
+        numeric_input::{NumericInput, NumericInputCommitted},
+        palette::{BEVY_GRAY, LABEL_TEXT},
+        widget::{button, button_small, label, numeric_input},
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SuggestedSettings>();
+    app.add_systems(
+        Update,
+        (
+            update_config_fields.run_if(
+                resource_changed::<BuildNavmeshConfig>
+                    .or(resource_changed::<Localization>)
+                    .or(numeric_inputs_changed),
+            ),
+            update_config_field_labels.run_if(resource_changed::<Localization>),
+            update_suggested_settings_text
+                .run_if(resource_changed::<SuggestedSettings>.or(resource_changed::<Localization>)),
+        ),
+    );
+}
+
+/// The cell height and max climb suggested for the geometry of the active session,
+/// as analyzed by [`NavmeshConfigBuilder::analyze_vertical`].
+#[derive(Resource, Default, Deref, DerefMut)]
+struct SuggestedSettings(Option<VerticalAnalysis>);
+
+/// The text describing the [`SuggestedSettings`].
+#[derive(Component)]
+struct SuggestedSettingsText;
+
+/// The text showing the tooltip of the hovered [`ConfigField`].
+#[derive(Component)]
+struct ConfigFieldHint;
+
+/// The panel listing all [`ConfigField`]s.
+pub(crate) fn config_panel() -> impl Bundle {
+    (
+        Name::new("Config Panel"),
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Px(4.0),
+            padding: UiRect::all(Px(20.0)),
+            ..default()
+        },
+        BackgroundColor(BEVY_GRAY.with_alpha(0.6)),
+        Children::spawn(SpawnWith(|parent: &mut ChildSpawner| {
+            parent.spawn(label("config"));
+            parent.spawn(preset_row());
+            for field in ConfigField::all() {
+                parent
+                    .spawn(config_row(field))
+                    .observe(show_hint(field))
+                    .observe(hide_hint);
+            }
+            parent.spawn((
+                ConfigFieldHint,
+                Node {
+                    max_width: Px(330.0),
+                    min_height: Px(15.0),
+                    ..default()
+                },
+                Text::default(),
+                TextFont::from_font_size(12.0),
+                TextColor(LABEL_TEXT.with_alpha(0.7)),
+            ));
+            parent.spawn(suggested_settings_row());
+            parent.spawn(sweep_section());
+        })),
+    )
+}
+
+/// Where the editor saves and loads its config preset, relative to the working directory.
+const PRESET_PATH: &str = "navmesh.navcfg";
+
+fn preset_row() -> impl Bundle {
+    (
+        Name::new("Preset"),
+        Node {
+            column_gap: Px(5.0),
+            margin: UiRect::bottom(Px(4.0)),
+            ..default()
+        },
+        children![
+            button("config-save-preset", save_preset),
+            button("config-load-preset", load_preset),
+        ],
+    )
+}
+
+/// Saves the config as a [`VersionedNavmeshConfigBuilder`], so that later versions of the editor can still load it.
+fn save_preset(_: Trigger<Pointer<Click>>, config: Res<BuildNavmeshConfig>) -> Result {
+    let preset = VersionedNavmeshConfigBuilder::from(**config);
+    std::fs::write(PRESET_PATH, serde_json::to_string_pretty(&preset)?)?;
+    info!("Saved config preset to {PRESET_PATH}");
+    Ok(())
+}
+
+fn load_preset(_: Trigger<Pointer<Click>>, mut config: ResMut<BuildNavmeshConfig>) -> Result {
+    let preset: VersionedNavmeshConfigBuilder =
+        serde_json::from_str(&std::fs::read_to_string(PRESET_PATH)?)?;
+    if preset.version() < VersionedNavmeshConfigBuilder::LATEST_VERSION {
+        info!(
+            "Migrating config preset from version {} to {}",
+            preset.version(),
+            VersionedNavmeshConfigBuilder::LATEST_VERSION
+        );
+    }
+    **config = preset.into_latest();
+    Ok(())
+}
+
+fn suggested_settings_row() -> impl Bundle {
+    (
+        Name::new("Suggested Settings"),
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Px(4.0),
+            margin: UiRect::top(Px(8.0)),
+            ..default()
+        },
+        children![
+            (
+                Node {
+                    column_gap: Px(5.0),
+                    ..default()
+                },
+                children![
+                    button("config-suggest-settings", suggest_settings),
+                    button("config-apply", apply_suggested_settings),
+                ],
+            ),
+            (
+                SuggestedSettingsText,
+                Node {
+                    max_width: Px(330.0),
+                    ..default()
+                },
+                Text::default(),
+                TextFont::from_font_size(12.0),
+                TextColor(LABEL_TEXT.with_alpha(0.7)),
+            ),
+        ],
+    )
+}
+
+fn config_row(field: ConfigField) -> impl Bundle {
+    (
+        Name::new(field.title()),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Px(5.0),
+            ..default()
+        },
+        Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+            parent.spawn((
+                ConfigFieldLabel(field),
+                Node {
+                    width: Px(130.0),
+                    ..default()
+                },
+                Text::default(),
+                TextFont::from_font_size(14.0),
+                TextColor(LABEL_TEXT),
+            ));
+            let (min, max) = field.range();
+            parent
+                .spawn((numeric_input(min, max, field.is_integer()), field))
+                .observe(set_field(field));
+            parent.spawn((
+                Name::new("Unit"),
+                ConfigFieldUnit(field),
+                Node {
+                    width: Px(90.0),
+                    ..default()
+                },
+                Text::default(),
+                TextFont::from_font_size(12.0),
+                TextColor(LABEL_TEXT.with_alpha(0.7)),
+            ));
+            parent.spawn(button_small(String::from("R"), reset_field(field)));
+        })),
+    )
+}
+
+/// A numeric field of the [`NavmeshConfigBuilder`] that can be edited in the [`config_panel`].
+///
+/// The fields are the [`rerecast::ConfigField`]s of the builder, labeled after their name and described by their tooltip,
+/// unless the [`Localization`] has messages for them.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Deref)]
+pub(crate) struct ConfigField(rerecast::ConfigField);
+
+/// The text showing the unit of a [`ConfigField`].
+#[derive(Component, Debug, Clone, Copy)]
+struct ConfigFieldUnit(ConfigField);
+
+/// The text showing the label of a [`ConfigField`].
+#[derive(Component, Debug, Clone, Copy)]
+struct ConfigFieldLabel(ConfigField);
+
+impl ConfigField {
+    /// All editable fields, in the order they are declared in the [`NavmeshConfigBuilder`].
+    pub(crate) fn all() -> impl Iterator<Item = Self> {
+        rerecast::ConfigField::all().map(Self)
+    }
+
+    /// The key of the label of the field in the [`Localization`], e.g. `config-field-agent-max-climb`.
+    fn message(self) -> String {
+        format!("config-field-{}", self.info().name().replace('_', "-"))
+    }
+
+    /// The label of the field in the current locale, falling back to its title.
+    pub(crate) fn label(self, localization: &Localization) -> String {
+        localization
+            .try_get(&self.message())
+            .map_or_else(|| self.title(), str::to_string)
+    }
+
+    fn localized_tooltip(self, localization: &Localization) -> &'static str {
+        localization
+            .try_get(&format!("{}-tooltip", self.message()))
+            .unwrap_or_else(|| self.tooltip())
+    }
+
+    /// The unit of the field, along with its value converted to the other unit, based on the current cell size.
+    fn localized_unit(self, config: &NavmeshConfigBuilder, localization: &Localization) -> String {
+        let wu = localization.get("unit-world-units");
+        let vx = localization.get("unit-voxels");
+        let unit = match self.unit() {
+            Some(
+                ConfigUnit::WorldUnits
+                | ConfigUnit::HorizontalWorldUnits
+                | ConfigUnit::VerticalWorldUnits,
+            ) => wu,
+            Some(ConfigUnit::Voxels) => vx,
+            Some(ConfigUnit::CellSizes) => localization.get("unit-cell-sizes"),
+            Some(ConfigUnit::CellHeights) => localization.get("unit-cell-heights"),
+            Some(ConfigUnit::Radians) => localization.get("unit-degrees"),
+            Some(ConfigUnit::Count) | None => return String::new(),
+        };
+        if self.is_disabled(config) {
+            format!("{unit} ({})", localization.get("unit-off"))
+        } else if let Some(cells) = self.cells(config) {
+            // Rounded fields are shown in the whole cells the build ends up with.
+            if self.info().has_attribute::<ConfigRounding>() {
+                format!("{unit} ({cells} {vx})")
+            } else {
+                format!("{unit} ({cells:.1} {vx})")
+            }
+        } else if let Some(world_units) = self.world_units(config) {
+            format!("{unit} ({world_units:.2} {wu})")
+        } else {
+            unit.to_string()
+        }
+    }
+
+    fn format(self, config: &NavmeshConfigBuilder) -> String {
+        let value = self.get(config);
+        if self.is_integer() {
+            format!("{value:.0}")
+        } else {
+            format!("{value:.2}")
+        }
+    }
+}
+
+fn numeric_inputs_changed(inputs: Query<(), (Changed<NumericInput>, With<ConfigField>)>) -> bool {
+    !inputs.is_empty()
+}
+
+fn update_config_fields(
+    config: Res<BuildNavmeshConfig>,
+    localization: Res<Localization>,
+    mut values: Query<(&ConfigField, &NumericInput, &mut Text), Without<ConfigFieldUnit>>,
+    mut units: Query<(&ConfigFieldUnit, &mut Text), Without<ConfigField>>,
+) {
+    for (field, input, mut text) in &mut values {
+        // Don't overwrite what the user is typing.
+        if input.editing.is_none() {
+            text.0 = field.format(&config);
+        }
+    }
+    for (unit, mut text) in &mut units {
+        text.0 = unit.0.localized_unit(&config, &localization);
+    }
+}
+
+fn update_config_field_labels(
+    localization: Res<Localization>,
+    mut labels: Query<(&ConfigFieldLabel, &mut Text)>,
+) {
+    for (label, mut text) in &mut labels {
+        text.0 = label.0.label(&localization);
+    }
+}
+
+fn set_field(field: ConfigField) -> impl ObserverSystem<NumericInputCommitted, (), ()> {
+    IntoSystem::into_system(
+        move |trigger: Trigger<NumericInputCommitted>, mut config: ResMut<BuildNavmeshConfig>| {
+            field.set(&mut config, **trigger.event());
+        },
+    )
+}
+
+fn show_hint(field: ConfigField) -> impl ObserverSystem<Pointer<Over>, (), ()> {
+    IntoSystem::into_system(
+        move |_: Trigger<Pointer<Over>>,
+              localization: Res<Localization>,
+              mut hint: Single<&mut Text, With<ConfigFieldHint>>| {
+            hint.0 = field.localized_tooltip(&localization).to_string();
+        },
+    )
+}
+
+fn hide_hint(_: Trigger<Pointer<Out>>, mut hint: Single<&mut Text, With<ConfigFieldHint>>) {
+    hint.0.clear();
+}
+
+fn reset_field(field: ConfigField) -> impl ObserverSystem<Pointer<Click>, (), ()> {
+    IntoSystem::into_system(
+        move |_: Trigger<Pointer<Click>>, mut config: ResMut<BuildNavmeshConfig>| {
+            let value = field.get(&NavmeshConfigBuilder::default());
+            field.set(&mut config, value);
+        },
+    )
+}
+
+fn suggest_settings(
+    _: Trigger<Pointer<Click>>,
+    affectors: Query<
+        (&Mesh3d, &GlobalTransform, &SessionId),
+        (With<NavmeshAffector>, Without<NavmeshObstruction>),
+    >,
+    meshes: Res<Assets<Mesh>>,
+    sessions: Res<Sessions>,
+    config: Res<BuildNavmeshConfig>,
+    mut suggested: ResMut<SuggestedSettings>,
+) -> Result {
+    let mut trimesh = TriMesh::default();
+    for (mesh, transform, session) in &affectors {
+        if *session != sessions.active_id() {
+            continue;
+        }
+        let Some(mut affector) = meshes.get(mesh).and_then(TriMesh::from_mesh) else {
+            continue;
+        };
+        let transform = transform.compute_transform();
+        for vertex in &mut affector.vertices {
+            *vertex = transform.transform_point(Vec3::from(*vertex)).into();
+        }
+        trimesh.extend(&affector);
+    }
+    **suggested = Some(config.analyze_vertical(&trimesh)?);
+    Ok(())
+}
+
+fn apply_suggested_settings(
+    _: Trigger<Pointer<Click>>,
+    suggested: Res<SuggestedSettings>,
+    mut config: ResMut<BuildNavmeshConfig>,
+) {
+    if let Some(analysis) = &**suggested {
+        analysis.apply(&mut config);
+    }
+}
+
+fn update_suggested_settings_text(
+    suggested: Res<SuggestedSettings>,
+    localization: Res<Localization>,
+    mut text: Single<&mut Text, With<SuggestedSettingsText>>,
+) {
+    let Some(analysis) = &**suggested else {
+        text.0.clear();
+        return;
+    };
+    let steps = match analysis.common_step_range() {
+        Some((min, max)) => localization.format(
+            "suggested-steps",
+            &[("min", &format!("{min:.2}")), ("max", &format!("{max:.2}"))],
+        ),
+        None => localization.get("suggested-no-steps").to_string(),
+    };
+    let gap = match analysis.min_passable_gap_height {
+        Some(gap) => localization.format(
+            "suggested-lowest-passage",
+            &[("height", &format!("{gap:.2}"))],
+        ),
+        None => localization.get("suggested-no-low-passages").to_string(),
+    };
+    let suggestion = localization.format(
+        "suggested-settings",
+        &[
+            (
+                "cell_height",
+                &format!("{:.2}", analysis.suggested_cell_height),
+            ),
+            (
+                "max_climb",
+                &format!("{:.2}", analysis.suggested_agent_max_climb),
+            ),
+        ],
+    );
+    text.0 = format!("{suggestion}\n({steps}, {gap})");
+}
@@ -6,14 +6,28 @@ use bevy::{
     remote::BrpRequest,
     render::mesh::{Indices, PrimitiveTopology},
 };
-use bevy_rerecast::editor_integration::{
-    brp::{BRP_GET_NAVMESH_INPUT_METHOD, NavmeshInputResponse},
-    transmission::deserialize,
+use bevy_rerecast::{
+    NavmeshAffectorAreas, NavmeshObstruction,
+    editor_integration::{
+        brp::{BRP_GET_NAVMESH_INPUT_METHOD, NavmeshInputResponse, SourceEntity},
+        transmission::{CustomMeshAttributes, PayloadSizes, deserialize},
+        version::{BRP_GET_VERSION_METHOD, EditorCompatibility, IntegrationVersion},
+    },
+    rerecast::TriMesh,
+    volume::NavmeshVolume,
 };
 
-use crate::{build::NavmeshAffector, visualization::VisualMesh};
+use crate::{
+    build::NavmeshAffector,
+    input_source::InputSource,
+    off_mesh_link::{OffMeshLink, SelectedOffMeshLink},
+    session::{SessionId, Sessions},
+    visualization::{Navmesh, VisualMesh},
+    volume::{SelectedVolume, volume_bundle},
+};
 
 pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CustomMeshAttributes>();
     app.add_observer(fetch_navmesh_input);
 }
 
@@ -26,19 +40,36 @@ fn fetch_navmesh_input(
     mut commands: Commands,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut images: ResMut<Assets<Image>>,
-    mesh_handles: Query<Entity, (With<Mesh3d>, Or<(With<VisualMesh>, With<NavmeshAffector>)>)>,
-    gizmo_handles: Query<&Gizmo>,
+    mut custom_attributes: ResMut<CustomMeshAttributes>,
+    mesh_handles: Query<
+        (Entity, &SessionId, Option<&Gizmo>),
+        Or<(
+            With<VisualMesh>,
+            With<NavmeshAffector>,
+            With<OffMeshLink>,
+            With<NavmeshVolume>,
+        )>,
+    >,
     mut gizmos: ResMut<Assets<GizmoAsset>>,
+    mut sessions: ResMut<Sessions>,
+    navmesh: Option<Res<Navmesh>>,
+    mut selected_link: ResMut<SelectedOffMeshLink>,
+    mut selected_volume: ResMut<SelectedVolume>,
 ) -> Result {
-    // Create the URL. We're going to need it to issue the HTTP request.
-    let host_part = format!("{}:{}", "127.0.0.1", 15702);
-    let url = format!("http://{host_part}/");
+    let session = sessions.active_id();
+    let url = sessions.active().url();
+    let filter = &sessions.active().input_filter;
+
+    check_game_version(&ureq::Agent::new_with_defaults(), &url)?;
 
     let req = BrpRequest {
         jsonrpc: String::from("2.0"),
         method: String::from(BRP_GET_NAVMESH_INPUT_METHOD),
         id: Some(serde_json::to_value(1)?),
-        params: None,
+        // Games with older versions of the editor integration reject any parameters.
+        params: (!filter.is_empty())
+            .then(|| serde_json::to_value(filter))
+            .transpose()?,
     };
 
     let response = ureq::post(&url)
@@ -51,40 +82,53 @@ fn fetch_navmesh_input(
         .get("result")
         .context("Failed to get `result` from response")?;
     let response: NavmeshInputResponse = deserialize(result)?;
+    sessions.active_mut().payload_sizes = Some(PayloadSizes::measure(&response, result)?);
 
-    for entity in mesh_handles.iter() {
+    for (entity, entity_session, gizmo) in mesh_handles.iter() {
+        if *entity_session != session {
+            continue;
+        }
         commands.entity(entity).despawn();
+        if **selected_link == Some(entity) {
+            **selected_link = None;
+        }
+        if **selected_volume == Some(entity) {
+            **selected_volume = None;
+        }
+        if let Some(gizmo) = gizmo.and_then(|gizmo| gizmos.get_mut(&gizmo.handle)) {
+            gizmo.clear();
+        }
     }
-    for gizmo in gizmo_handles.iter() {
-        let Some(gizmo) = gizmos.get_mut(&gizmo.handle) else {
-            continue;
-        };
-        gizmo.clear();
+    if navmesh.is_some_and(|navmesh| navmesh.session == session) {
+        commands.remove_resource::<Navmesh>();
     }
 
     for affector in response.affector_meshes {
-        let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all())
-            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, affector.mesh.vertices)
-            .with_inserted_indices(Indices::U32(
-                affector
-                    .mesh
-                    .indices
-                    .into_iter()
-                    .flat_map(|indices| indices.to_array())
-                    .collect(),
-            ));
+        let areas = affector.resolved_areas(response.affector_areas);
+        let mut entity = commands.spawn(affector_bundle(
+            affector.transform.compute_transform(),
+            meshes.add(affector_mesh(affector.mesh)),
+            areas,
+            session,
+            &mut gizmos,
+        ));
+        if affector.obstruction {
+            entity.insert(NavmeshObstruction);
+        }
+        if let Some(source) = affector.source {
+            entity.insert(source_components(source));
+        }
+    }
 
+    for link in response.off_mesh_links {
         commands.spawn((
-            affector.transform.compute_transform(),
-            Mesh3d(meshes.add(mesh)),
-            NavmeshAffector,
-            Visibility::Hidden,
+            OffMeshLink::new(link),
+            session,
             Gizmo {
                 handle: gizmos.add(GizmoAsset::new()),
                 line_config: GizmoLineConfig {
                     perspective: true,
                     width: 20.0,
-                    joints: GizmoLineJoint::Bevel,
                     ..default()
                 },
                 depth_bias: -0.001,
@@ -92,6 +136,10 @@ fn fetch_navmesh_input(
         ));
     }
 
+    for volume in response.volumes {
+        commands.spawn(volume_bundle(volume, session, &mut gizmos));
+    }
+
     let mut image_indices: HashMap<u32, Handle<Image>> = HashMap::new();
     let mut material_indices: HashMap<u32, Handle<StandardMaterial>> = HashMap::new();
     let mut mesh_indices: HashMap<u32, Handle<Mesh>> = HashMap::new();
@@ -102,7 +150,7 @@ fn fetch_navmesh_input(
             mesh_handle.clone()
         } else {
             let serialized_mesh = response.meshes[visual.mesh as usize].clone();
-            let mesh = serialized_mesh.into_mesh();
+            let mesh = serialized_mesh.into_mesh_with(&mut custom_attributes);
             let handle = meshes.add(mesh);
             mesh_indices.insert(visual.mesh, handle.clone());
             handle
@@ -126,13 +174,92 @@ fn fetch_navmesh_input(
             fallback_material.clone()
         };
 
-        commands.spawn((
+        let mut entity = commands.spawn((
             visual.transform.compute_transform(),
             Mesh3d(mesh),
             MeshMaterial3d(material),
             VisualMesh,
+            session,
+            Pickable::default(),
         ));
+        if let Some(source) = visual.source {
+            entity.insert(source_components(source));
+        }
     }
 
     Ok(())
 }
+
+/// Asks the game at `url` for its [`IntegrationVersion`] and fails with an
+/// [`IncompatibleVersions`](bevy_rerecast::editor_integration::version::IncompatibleVersions)
+/// if the editor cannot read its data. Games from before the version handshake pass, as their data is decoded as legacy data.
+pub(crate) fn check_game_version(agent: &ureq::Agent, url: &str) -> Result {
+    let req = BrpRequest {
+        jsonrpc: String::from("2.0"),
+        method: String::from(BRP_GET_VERSION_METHOD),
+        id: Some(serde_json::to_value(1)?),
+        params: Some(serde_json::to_value(IntegrationVersion::current())?),
+    };
+    let response = agent
+        .post(url)
+        .send_json(req)?
+        .body_mut()
+        .read_json::<serde_json::Value>()?;
+    if let Some(error) = response.get("error") {
+        let code = error.get("code").and_then(serde_json::Value::as_i64);
+        if code == Some(i64::from(bevy::remote::error_codes::METHOD_NOT_FOUND)) {
+            return Ok(());
+        }
+        return Err(anyhow::anyhow!("Failed to get the version of the game: {error}").into());
+    }
+    let result = response
+        .get("result")
+        .context("Failed to get `result` from response")?;
+    let game: IntegrationVersion = serde_json::from_value(result.clone())?;
+    EditorCompatibility::default().check(&IntegrationVersion::current(), &game)?;
+    Ok(())
+}
+
+/// Converts the triangles of an affector into a mesh that [`NavmeshAffector`] gizmos and builds can read back.
+pub(crate) fn affector_mesh(trimesh: TriMesh) -> Mesh {
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, trimesh.vertices)
+        .with_inserted_indices(Indices::U32(
+            trimesh
+                .indices
+                .into_iter()
+                .flat_map(|indices| indices.to_array())
+                .collect(),
+        ))
+}
+
+/// The components of an entity holding a [`NavmeshAffector`] of the given session. Hidden until its gizmo is drawn.
+pub(crate) fn affector_bundle(
+    transform: Transform,
+    mesh: Handle<Mesh>,
+    areas: NavmeshAffectorAreas,
+    session: SessionId,
+    gizmos: &mut Assets<GizmoAsset>,
+) -> impl Bundle {
+    (
+        transform,
+        Mesh3d(mesh),
+        NavmeshAffector(areas),
+        session,
+        Visibility::Hidden,
+        Gizmo {
+            handle: gizmos.add(GizmoAsset::new()),
+            line_config: GizmoLineConfig {
+                perspective: true,
+                width: 20.0,
+                joints: GizmoLineJoint::Bevel,
+                ..default()
+            },
+            depth_bias: -0.001,
+        },
+    )
+}
+
+fn source_components(source: SourceEntity) -> impl Bundle {
+    (Name::new(source.to_string()), InputSource(source))
+}
@@ -0,0 +1,173 @@
+//! Editing of the [`Session::input_filter`](crate::session::Session::input_filter), which restricts
+//! which part of the game's scene is fetched, e.g. to only work on a single sub-level.
+//!
+//! The filter applies to the next fetch of the active session.
+
+use bevy::{
+    ecs::{spawn::SpawnWith, system::ObserverSystem},
+    prelude::*,
+    ui::Val::*,
+};
+use bevy_rerecast::{editor_integration::brp::NavmeshInputParams, rerecast::Aabb3d};
+
+use crate::{
+    localization::UiText,
+    session::Sessions,
+    theme::{
+        palette::LABEL_TEXT,
+        text_input::{TextInput, TextInputCommitted},
+        widget::{label, text_input},
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        update_filter_inputs.run_if(resource_changed::<Sessions>.or(filter_inputs_changed)),
+    );
+}
+
+/// Which part of the [`NavmeshInputParams`] a [`TextInput`] edits.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterInput {
+    Name,
+    Marker,
+    Bounds,
+}
+
+impl FilterInput {
+    const ALL: [Self; 3] = [Self::Name, Self::Marker, Self::Bounds];
+
+    /// The key of the label of the input in the [`Localization`](crate::localization::Localization).
+    fn message(self) -> &'static str {
+        match self {
+            Self::Name => "input-filter-name",
+            Self::Marker => "input-filter-marker",
+            Self::Bounds => "input-filter-bounds",
+        }
+    }
+
+    fn get(self, filter: &NavmeshInputParams) -> String {
+        match self {
+            Self::Name => filter.name.clone().unwrap_or_default(),
+            Self::Marker => filter.marker.clone().unwrap_or_default(),
+            Self::Bounds => filter
+                .aabb
+                .map(|aabb| {
+                    [aabb.min.to_array(), aabb.max.to_array()]
+                        .concat()
+                        .iter()
+                        .map(|value| format!("{value}"))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Sets the filter from the committed text. Empty text removes the filter.
+    fn set(self, filter: &mut NavmeshInputParams, text: &str) {
+        let text = (!text.is_empty()).then(|| text.to_string());
+        match self {
+            Self::Name => filter.name = text,
+            Self::Marker => filter.marker = text,
+            Self::Bounds => match text.as_deref().map(parse_bounds) {
+                None => filter.aabb = None,
+                Some(Some(aabb)) => filter.aabb = Some(aabb),
+                Some(None) => warn!(
+                    "Ignoring input bounds \"{}\": expected six numbers, the minimum and maximum corner",
+                    text.unwrap_or_default()
+                ),
+            },
+        }
+    }
+}
+
+/// Parses `min_x min_y min_z max_x max_y max_z`, separated by spaces or commas.
+fn parse_bounds(text: &str) -> Option<Aabb3d> {
+    let values = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse::<f32>().ok().filter(|value| value.is_finite()))
+        .collect::<Option<Vec<_>>>()?;
+    let [min_x, min_y, min_z, max_x, max_y, max_z] = values[..] else {
+        return None;
+    };
+    let (a, b) = (
+        Vec3::new(min_x, min_y, min_z),
+        Vec3::new(max_x, max_y, max_z),
+    );
+    Some(Aabb3d {
+        min: a.min(b),
+        max: a.max(b),
+    })
+}
+
+pub(crate) fn input_filter_section() -> impl Bundle {
+    (
+        Name::new("Input Filter"),
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Px(2.0),
+            padding: UiRect::vertical(Px(5.0)),
+            ..default()
+        },
+        Children::spawn(SpawnWith(|parent: &mut ChildSpawner| {
+            parent.spawn(label("input-filter"));
+            for input in FilterInput::ALL {
+                parent.spawn(filter_input_row(input));
+            }
+        })),
+    )
+}
+
+fn filter_input_row(input: FilterInput) -> impl Bundle {
+    (
+        Name::new("Input Filter Row"),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Px(5.0),
+            ..default()
+        },
+        Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+            parent.spawn((
+                Node {
+                    width: Px(60.0),
+                    ..default()
+                },
+                UiText::from(input.message()),
+                TextFont::from_font_size(14.0),
+                TextColor(LABEL_TEXT),
+            ));
+            parent
+                .spawn((text_input(170.0), input))
+                .observe(set_filter_input(input));
+        })),
+    )
+}
+
+fn set_filter_input(input: FilterInput) -> impl ObserverSystem<TextInputCommitted, (), ()> {
+    IntoSystem::into_system(
+        move |trigger: Trigger<TextInputCommitted>, mut sessions: ResMut<Sessions>| {
+            input.set(&mut sessions.active_mut().input_filter, trigger.event());
+        },
+    )
+}
+
+fn filter_inputs_changed(inputs: Query<(), (Changed<TextInput>, With<FilterInput>)>) -> bool {
+    !inputs.is_empty()
+}
+
+fn update_filter_inputs(
+    sessions: Res<Sessions>,
+    mut inputs: Query<(&FilterInput, &TextInput, &mut Text)>,
+) {
+    let filter = &sessions.active().input_filter;
+    for (input, text_input, mut text) in &mut inputs {
+        // Don't overwrite what the user is typing.
+        if text_input.editing.is_some() {
+            continue;
+        }
+        text.0 = input.get(filter);
+    }
+}
@@ -0,0 +1,186 @@
+//! Traces the affector and visual meshes of the navmesh input back to the entities in the game they were collected from,
+//! both in a list in the property panel and in a tooltip when hovering a mesh in the viewport.
+
+use bevy::{
+    picking::mesh_picking::{MeshPickingPlugin, MeshPickingSettings},
+    prelude::*,
+    ui::Val::*,
+};
+use bevy_rerecast::{NavmeshObstruction, editor_integration::brp::SourceEntity};
+
+use crate::{
+    build::NavmeshAffector,
+    localization::Localization,
+    session::{SessionId, Sessions},
+    theme::palette::{BEVY_GRAY, LABEL_TEXT},
+    visualization::VisualMesh,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(MeshPickingPlugin);
+    // Only the meshes of the navmesh input are hovered, not the gizmos or the navmesh visualization.
+    app.insert_resource(MeshPickingSettings {
+        require_markers: true,
+        ..default()
+    });
+    app.add_systems(Startup, spawn_tooltip);
+    app.add_systems(
+        Update,
+        update_input_source_list.run_if(
+            resource_changed::<Sessions>
+                .or(resource_changed::<Localization>)
+                .or(input_sources_changed),
+        ),
+    );
+    app.add_observer(show_tooltip);
+    app.add_observer(move_tooltip);
+    app.add_observer(hide_tooltip);
+}
+
+/// The entity in the game that a mesh of the navmesh input was collected from.
+/// Missing for input from games with an editor integration that does not send the source.
+#[derive(Component, Debug, Clone, Deref)]
+pub(crate) struct InputSource(pub(crate) SourceEntity);
+
+/// The list of input sources in the property panel.
+#[derive(Component)]
+pub(crate) struct InputSourceList;
+
+/// How many entities the [`InputSourceList`] shows before summarizing the rest.
+const MAX_LISTED_SOURCES: usize = 20;
+
+fn input_sources_changed(
+    added: Query<(), Added<InputSource>>,
+    mut removed: RemovedComponents<InputSource>,
+) -> bool {
+    !added.is_empty() || removed.read().count() > 0
+}
+
+fn update_input_source_list(
+    list: Single<Entity, With<InputSourceList>>,
+    affectors: Query<
+        (&InputSource, &SessionId, &Mesh3d, Has<NavmeshObstruction>),
+        With<NavmeshAffector>,
+    >,
+    visuals: Query<(&InputSource, &SessionId), With<VisualMesh>>,
+    sessions: Res<Sessions>,
+    meshes: Res<Assets<Mesh>>,
+    localization: Res<Localization>,
+    mut commands: Commands,
+) {
+    let list = *list;
+    commands.entity(list).despawn_related::<Children>();
+    let session = sessions.active_id();
+
+    let affector_rows = affectors
+        .iter()
+        .filter(|(_, entity_session, _, _)| **entity_session == session)
+        .map(|(source, _, mesh, obstruction)| {
+            let triangles = meshes
+                .get(mesh)
+                .and_then(|mesh| mesh.indices())
+                .map_or(0, |indices| indices.len() / 3);
+            let key = if obstruction {
+                "input-source-obstruction"
+            } else {
+                "input-source-affector"
+            };
+            localization.format(key, &[("source", &**source), ("triangles", &triangles)])
+        });
+    let visual_rows = visuals
+        .iter()
+        .filter(|(_, entity_session)| **entity_session == session)
+        .map(|(source, _)| localization.format("input-source-visual", &[("source", &**source)]));
+    let mut rows = affector_rows.chain(visual_rows).collect::<Vec<_>>();
+    rows.sort();
+
+    let hidden = rows.len().saturating_sub(MAX_LISTED_SOURCES);
+    for row in rows.into_iter().take(MAX_LISTED_SOURCES) {
+        commands.spawn((ChildOf(list), source_text(row)));
+    }
+    if hidden > 0 {
+        commands.spawn((
+            ChildOf(list),
+            source_text(localization.format("input-source-more", &[("count", &hidden)])),
+        ));
+    }
+}
+
+fn source_text(text: impl Into<String>) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont::from_font_size(14.0),
+        TextColor(LABEL_TEXT.with_alpha(0.8)),
+    )
+}
+
+#[derive(Component)]
+struct Tooltip;
+
+fn spawn_tooltip(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Input Source Tooltip"),
+        Tooltip,
+        Node {
+            position_type: PositionType::Absolute,
+            display: Display::None,
+            padding: UiRect::axes(Px(6.0), Px(3.0)),
+            ..default()
+        },
+        GlobalZIndex(i32::MAX),
+        BackgroundColor(BEVY_GRAY.with_alpha(0.9)),
+        BorderRadius::all(Px(4.0)),
+        Pickable::IGNORE,
+        children![(
+            Text::default(),
+            TextFont::from_font_size(14.0),
+            TextColor(LABEL_TEXT),
+            Pickable::IGNORE,
+        )],
+    ));
+}
+
+fn show_tooltip(
+    trigger: Trigger<Pointer<Over>>,
+    sources: Query<&InputSource>,
+    tooltip: Single<(&mut Node, &Children), With<Tooltip>>,
+    mut texts: Query<&mut Text>,
+) {
+    let Ok(source) = sources.get(trigger.target()) else {
+        return;
+    };
+    let (mut node, children) = tooltip.into_inner();
+    for child in children {
+        if let Ok(mut text) = texts.get_mut(*child) {
+            text.0 = source.to_string();
+        }
+    }
+    node.display = Display::Flex;
+    place_tooltip(&mut node, trigger.pointer_location.position);
+}
+
+fn move_tooltip(
+    trigger: Trigger<Pointer<Move>>,
+    sources: Query<(), With<InputSource>>,
+    mut tooltip: Single<&mut Node, With<Tooltip>>,
+) {
+    if sources.contains(trigger.target()) {
+        place_tooltip(&mut tooltip, trigger.pointer_location.position);
+    }
+}
+
+fn hide_tooltip(
+    trigger: Trigger<Pointer<Out>>,
+    sources: Query<(), With<InputSource>>,
+    mut tooltip: Single<&mut Node, With<Tooltip>>,
+) {
+    if sources.contains(trigger.target()) {
+        tooltip.display = Display::None;
+    }
+}
+
+fn place_tooltip(node: &mut Node, cursor: Vec2) {
+    // Offset the tooltip so that it doesn't cover what is being hovered.
+    node.left = Px(cursor.x + 16.0);
+    node.top = Px(cursor.y + 16.0);
+}
@@ -0,0 +1,167 @@
+//! The message catalog for the strings of the UI, so that the editor can be translated without forking it.
+//!
+//! Each [`Locale`] is a file in `locales/` written in a subset of the [Fluent](https://projectfluent.org) syntax:
+//! one `key = value` message per line, `#` comments, and `{ $name }` placeables that are filled in by [`Localization::format`].
+//! Messages missing from a locale fall back to English, so translations can be added bit by bit.
+
+use std::fmt::Display;
+
+use bevy::{platform::collections::HashMap, prelude::*, ui::UiSystem};
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(Localization::new(Locale::from_env()));
+    app.add_systems(PostUpdate, update_ui_texts.before(UiSystem::Prepare));
+}
+
+/// A language the editor is translated to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub(crate) enum Locale {
+    #[default]
+    English,
+    German,
+}
+
+impl Locale {
+    pub(crate) const ALL: [Self; 2] = [Self::English, Self::German];
+
+    /// The locale matching the `LANG` environment variable, or English if the editor is not translated to it.
+    fn from_env() -> Self {
+        match std::env::var("LANG") {
+            Ok(lang) if lang.starts_with("de") => Self::German,
+            _ => Self::default(),
+        }
+    }
+
+    /// The locale after this one in [`Self::ALL`], wrapping around.
+    pub(crate) fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|locale| *locale == self)
+            .unwrap_or_default();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Self::English => include_str!("../locales/en-US.ftl"),
+            Self::German => include_str!("../locales/de.ftl"),
+        }
+    }
+}
+
+/// The messages of the current [`Locale`]. Change the locale with [`Localization::set_locale`]
+/// to switch the language of the whole UI at runtime.
+#[derive(Resource, Debug)]
+pub(crate) struct Localization {
+    locale: Locale,
+    messages: HashMap<&'static str, &'static str>,
+    fallback: HashMap<&'static str, &'static str>,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self::new(Locale::default())
+    }
+}
+
+impl Localization {
+    pub(crate) fn new(locale: Locale) -> Self {
+        Self {
+            locale,
+            messages: parse(locale.source()),
+            fallback: parse(Locale::English.source()),
+        }
+    }
+
+    pub(crate) fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    pub(crate) fn set_locale(&mut self, locale: Locale) {
+        *self = Self::new(locale);
+    }
+
+    /// The message with the given key, or `None` if neither the current locale nor English has it.
+    pub(crate) fn try_get(&self, key: &str) -> Option<&'static str> {
+        self.messages
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .copied()
+    }
+
+    /// The message with the given key. Unknown keys are shown as is, so that missing messages stand out.
+    pub(crate) fn get<'a>(&self, key: &'a str) -> &'a str {
+        self.try_get(key).unwrap_or(key)
+    }
+
+    /// The message with the given key, with each `{ $name }` placeable replaced by the argument of the same name.
+    pub(crate) fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut message = self.get(key);
+        let mut formatted = String::with_capacity(message.len());
+        while let Some(start) = message.find('{') {
+            let Some(end) = message[start..].find('}').map(|end| start + end) else {
+                break;
+            };
+            formatted.push_str(&message[..start]);
+            let name = message[start + 1..end].trim().trim_start_matches('$');
+            match args.iter().find(|(arg, _)| *arg == name) {
+                Some((_, value)) => formatted.push_str(&value.to_string()),
+                None => formatted.push_str(&message[start..=end]),
+            }
+            message = &message[end + 1..];
+        }
+        formatted.push_str(message);
+        formatted
+    }
+}
+
+/// Parses the messages of a locale file. Lines that are not messages are skipped.
+fn parse(source: &'static str) -> HashMap<&'static str, &'static str> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+/// The text of a UI node, kept in sync with the current [`Locale`].
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+#[require(Text)]
+pub(crate) enum UiText {
+    /// The key of a message, looked up in the [`Localization`].
+    Message(&'static str),
+    /// Text that is shown as is, e.g. a host name or already localized text.
+    Verbatim(String),
+}
+
+impl From<&'static str> for UiText {
+    fn from(key: &'static str) -> Self {
+        Self::Message(key)
+    }
+}
+
+impl From<String> for UiText {
+    fn from(text: String) -> Self {
+        Self::Verbatim(text)
+    }
+}
+
+fn update_ui_texts(localization: Res<Localization>, mut texts: Query<(Ref<UiText>, &mut Text)>) {
+    for (ui_text, mut text) in &mut texts {
+        if !localization.is_changed() && !ui_text.is_changed() {
+            continue;
+        }
+        text.0 = match &*ui_text {
+            UiText::Message(key) => localization.get(key).to_string(),
+            UiText::Verbatim(verbatim) => verbatim.clone(),
+        };
+    }
+}
+
+/// Switches to the next [`Locale`] when clicked.
+pub(crate) fn next_locale(_: Trigger<Pointer<Click>>, mut localization: ResMut<Localization>) {
+    let locale = localization.locale().next();
+    localization.set_locale(locale);
+}
@@ -8,10 +8,21 @@ use bevy_rerecast::prelude::*;
 
 mod build;
 mod camera;
+mod config_panel;
 mod get_navmesh_input;
+mod input_filter;
+mod input_source;
+mod localization;
+mod off_mesh_link;
+mod payload_size;
+mod session;
+mod stepping;
+mod sweep;
 mod theme;
+mod timeline;
 mod ui;
 mod visualization;
+mod volume;
 
 fn main() -> AppExit {
     GLOBAL_ERROR_HANDLER
@@ -21,13 +32,23 @@ fn main() -> AppExit {
     App::new()
         .add_plugins(DefaultPlugins)
         .add_plugins(NavmeshPlugins::default())
+        .add_plugins(localization::plugin)
         .add_plugins((
             camera::plugin,
+            config_panel::plugin,
             get_navmesh_input::plugin,
+            input_source::plugin,
+            off_mesh_link::plugin,
+            payload_size::plugin,
+            session::plugin,
+            stepping::plugin,
+            sweep::plugin,
             ui::plugin,
             theme::plugin,
+            timeline::plugin,
             build::plugin,
             visualization::plugin,
+            volume::plugin,
         ))
         .run()
 }
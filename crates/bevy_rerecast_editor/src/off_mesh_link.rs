@@ -0,0 +1,304 @@
+//! Visualization and validation of the off-mesh links sent by the game.
+
+use bevy::{color::palettes::tailwind, prelude::*};
+use bevy_rerecast::{
+    TriMeshFromBevyMesh as _,
+    rerecast::{OffMeshConnection, TriMesh},
+};
+
+use crate::{
+    build::{BuildNavmeshConfig, NavmeshAffector},
+    localization::Localization,
+    session::{SessionId, Sessions},
+    visualization::{AvailableGizmos, GizmosToDraw, Navmesh},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SelectedOffMeshLink>();
+    app.add_systems(
+        Update,
+        (
+            validate_off_mesh_links
+                .run_if(resource_changed_or_removed::<Navmesh>.or(off_mesh_links_added)),
+            draw_off_mesh_links
+                .run_if(gizmo_enabled_and_changed.or(resource_changed::<SelectedOffMeshLink>)),
+            hide_off_mesh_links.run_if(gizmo_disabled_and_changed),
+        )
+            .chain(),
+    );
+}
+
+/// An off-mesh link of a navmesh fragment in the game.
+#[derive(Component)]
+pub(crate) struct OffMeshLink {
+    pub(crate) link: OffMeshConnection,
+    /// The problems found during the last validation, or `None` if the link has not been validated
+    /// against a navmesh of its session yet.
+    pub(crate) problems: Option<Vec<LinkProblem>>,
+}
+
+impl OffMeshLink {
+    pub(crate) fn new(link: OffMeshConnection) -> Self {
+        Self {
+            link,
+            problems: None,
+        }
+    }
+
+    pub(crate) fn is_valid(&self) -> Option<bool> {
+        self.problems.as_ref().map(Vec::is_empty)
+    }
+
+    /// A summary of the validation, as shown when the link is selected.
+    pub(crate) fn diagnostics(&self, localization: &Localization) -> String {
+        let link = &self.link;
+        let mut lines = vec![
+            localization.format(
+                "off-mesh-link-start",
+                &[("position", &format!("{:.2}", link.start))],
+            ),
+            localization.format(
+                "off-mesh-link-end",
+                &[("position", &format!("{:.2}", link.end))],
+            ),
+            localization.format(
+                "off-mesh-link-radius",
+                &[("radius", &format!("{:.2}", link.radius))],
+            ),
+            localization
+                .get(if link.bidirectional {
+                    "off-mesh-link-bidirectional"
+                } else {
+                    "off-mesh-link-one-way"
+                })
+                .to_string(),
+        ];
+        match &self.problems {
+            None => lines.push(localization.get("off-mesh-link-unvalidated").to_string()),
+            Some(problems) if problems.is_empty() => {
+                lines.push(localization.get("off-mesh-link-no-problems").to_string());
+            }
+            Some(problems) => {
+                for problem in problems {
+                    lines.push(format!("- {}", problem.message(localization)));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// The off-mesh link whose diagnostics are shown in the property panel.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub(crate) struct SelectedOffMeshLink(pub(crate) Option<Entity>);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LinkProblem {
+    /// The start of the link is not on the navmesh.
+    StartOffNavmesh,
+    /// The end of the link is not on the navmesh.
+    EndOffNavmesh,
+    /// An affector intersects the arc of the link at the given point.
+    Obstructed(Vec3),
+}
+
+impl LinkProblem {
+    fn message(&self, localization: &Localization) -> String {
+        match self {
+            LinkProblem::StartOffNavmesh => localization
+                .get("off-mesh-link-start-off-navmesh")
+                .to_string(),
+            LinkProblem::EndOffNavmesh => localization
+                .get("off-mesh-link-end-off-navmesh")
+                .to_string(),
+            LinkProblem::Obstructed(point) => localization.format(
+                "off-mesh-link-obstructed",
+                &[("point", &format!("{point:.2}"))],
+            ),
+        }
+    }
+}
+
+/// The number of line segments used for the arc of a link.
+const ARC_SEGMENTS: usize = 16;
+
+/// The points of the arc drawn between the endpoints of a link.
+fn arc_points(link: &OffMeshConnection) -> impl Iterator<Item = Vec3> {
+    let height = (link.start.distance(link.end) * 0.25).max(0.25);
+    (0..=ARC_SEGMENTS).map(move |i| {
+        let t = i as f32 / ARC_SEGMENTS as f32;
+        link.start.lerp(link.end, t) + Vec3::Y * height * 4.0 * t * (1.0 - t)
+    })
+}
+
+fn off_mesh_links_added(links: Query<(), Added<OffMeshLink>>) -> bool {
+    !links.is_empty()
+}
+
+fn validate_off_mesh_links(
+    navmesh: Option<Res<Navmesh>>,
+    mut links: Query<(&mut OffMeshLink, &SessionId)>,
+    affectors: Query<(&Mesh3d, &GlobalTransform, &SessionId), With<NavmeshAffector>>,
+    meshes: Res<Assets<Mesh>>,
+    config: Res<BuildNavmeshConfig>,
+) {
+    let Some(navmesh) = navmesh else {
+        for (mut link, _) in &mut links {
+            link.problems = None;
+        }
+        return;
+    };
+    let config = config.build();
+    let climb = config.walkable_climb as f32 * config.cell_height;
+
+    let geometry = affectors
+        .iter()
+        .filter(|(.., session)| **session == navmesh.session)
+        .filter_map(|(mesh, transform, _)| {
+            let mut trimesh = TriMesh::from_mesh(meshes.get(mesh)?)?;
+            for vertex in &mut trimesh.vertices {
+                *vertex = transform.transform_point(Vec3::from(*vertex)).into();
+            }
+            Some(trimesh)
+        })
+        .collect::<Vec<_>>();
+
+    for (mut connection, session) in &mut links {
+        if *session != navmesh.session {
+            connection.problems = None;
+            continue;
+        }
+        let link = connection.link;
+        let mut problems = Vec::new();
+        if !is_on_navmesh(&navmesh, link.start, climb) {
+            problems.push(LinkProblem::StartOffNavmesh);
+        }
+        if !is_on_navmesh(&navmesh, link.end, climb) {
+            problems.push(LinkProblem::EndOffNavmesh);
+        }
+        // Lift the arc by the climb height so that the ground the endpoints stand on does not count as an obstruction.
+        let arc = arc_points(&link)
+            .map(|point| point + Vec3::Y * climb)
+            .collect::<Vec<_>>();
+        if let Some(point) = arc
+            .windows(2)
+            .find_map(|segment| first_hit(&geometry, segment[0], segment[1]))
+        {
+            problems.push(LinkProblem::Obstructed(point));
+        }
+        connection.problems = Some(problems);
+    }
+}
+
+fn is_on_navmesh(navmesh: &Navmesh, point: Vec3, tolerance: f32) -> bool {
+    let detail = &navmesh.detail_mesh;
+    navmesh.poly_mesh.polygon_ids().any(|polygon| {
+        detail
+            .height_at(polygon, point.xz())
+            .is_some_and(|height| (height - point.y).abs() <= tolerance)
+    })
+}
+
+/// Returns the first point where the segment from `start` to `end` intersects any triangle of `geometry`.
+fn first_hit(geometry: &[TriMesh], start: Vec3, end: Vec3) -> Option<Vec3> {
+    let dir = end - start;
+    let mut closest: Option<f32> = None;
+    for trimesh in geometry {
+        for indices in &trimesh.indices {
+            let [a, b, c] = indices
+                .to_array()
+                .map(|i| Vec3::from(trimesh.vertices[i as usize]));
+            if let Some(t) = intersect_segment_triangle(start, dir, a, b, c)
+                && closest.is_none_or(|closest| t < closest)
+            {
+                closest = Some(t);
+            }
+        }
+    }
+    closest.map(|t| start + dir * t)
+}
+
+/// Möller-Trumbore intersection, restricted to the segment `start..start + dir`.
+fn intersect_segment_triangle(start: Vec3, dir: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+    let ab = b - a;
+    let ac = c - a;
+    let p = dir.cross(ac);
+    let det = ab.dot(p);
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = start - a;
+    let u = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(ab);
+    let v = dir.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = ac.dot(q) * inv_det;
+    (0.0..=1.0).contains(&t).then_some(t)
+}
+
+fn gizmo_enabled_and_changed(
+    gizmos: Res<GizmosToDraw>,
+    links: Query<(), Changed<OffMeshLink>>,
+) -> bool {
+    gizmos.contains(&AvailableGizmos::OffMeshLinks) && (gizmos.is_changed() || !links.is_empty())
+}
+
+fn gizmo_disabled_and_changed(gizmos: Res<GizmosToDraw>) -> bool {
+    gizmos.is_changed() && !gizmos.contains(&AvailableGizmos::OffMeshLinks)
+}
+
+fn draw_off_mesh_links(
+    links: Query<(Entity, &OffMeshLink, &SessionId, &Gizmo)>,
+    mut gizmo_assets: ResMut<Assets<GizmoAsset>>,
+    gizmos: Res<GizmosToDraw>,
+    selected: Res<SelectedOffMeshLink>,
+    sessions: Res<Sessions>,
+) {
+    for (entity, connection, session, gizmo) in &links {
+        let Some(gizmo) = gizmo_assets.get_mut(&gizmo.handle) else {
+            error!("Failed to get gizmo asset");
+            return;
+        };
+        gizmo.clear();
+        if *session != sessions.active_id() || !gizmos.contains(&AvailableGizmos::OffMeshLinks) {
+            continue;
+        }
+        let color = if **selected == Some(entity) {
+            tailwind::YELLOW_400
+        } else {
+            match connection.is_valid() {
+                None => tailwind::GRAY_400,
+                Some(true) => tailwind::GREEN_500,
+                Some(false) => tailwind::RED_600,
+            }
+        };
+        let link = &connection.link;
+        gizmo.linestrip(arc_points(link), color);
+        for endpoint in [link.start, link.end] {
+            gizmo.circle(
+                Isometry3d::new(endpoint, Quat::from_rotation_arc(Vec3::Z, Vec3::Y)),
+                link.radius,
+                color,
+            );
+        }
+    }
+}
+
+fn hide_off_mesh_links(
+    links: Query<&Gizmo, With<OffMeshLink>>,
+    mut gizmo_assets: ResMut<Assets<GizmoAsset>>,
+) {
+    for gizmo in &links {
+        let Some(gizmo) = gizmo_assets.get_mut(&gizmo.handle) else {
+            error!("Failed to get gizmo asset");
+            return;
+        };
+        gizmo.clear();
+    }
+}
@@ -0,0 +1,99 @@
+//! Shows how large the navmesh input of the active session was when it was last fetched, both per kind of data and per entity,
+//! so that users can see why fetching is slow and which entities are worth excluding.
+
+use bevy::{prelude::*, ui::Val::*};
+use bevy_rerecast::editor_integration::transmission::PayloadSizes;
+
+use crate::{
+    localization::Localization,
+    session::Sessions,
+    theme::{palette::LABEL_TEXT, widget::label},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        update_payload_size_summary
+            .run_if(resource_changed::<Sessions>.or(resource_changed::<Localization>)),
+    );
+}
+
+/// The text in the session panel that lists the payload sizes of the active session.
+#[derive(Component)]
+struct PayloadSizeSummary;
+
+/// How many of the largest entities the [`PayloadSizeSummary`] lists.
+const MAX_LISTED_ENTITIES: usize = 5;
+
+pub(crate) fn payload_size_section() -> impl Bundle {
+    (
+        Name::new("Payload Size"),
+        Node {
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::vertical(Px(5.0)),
+            ..default()
+        },
+        children![
+            label("payload-last-fetch"),
+            (
+                Name::new("Payload Size Summary"),
+                PayloadSizeSummary,
+                Text::default(),
+                TextFont::from_font_size(14.0),
+                TextColor(LABEL_TEXT.with_alpha(0.8)),
+            ),
+        ],
+    )
+}
+
+fn update_payload_size_summary(
+    mut summary: Single<&mut Text, With<PayloadSizeSummary>>,
+    sessions: Res<Sessions>,
+    localization: Res<Localization>,
+) {
+    summary.0 = match &sessions.active().payload_sizes {
+        Some(sizes) => describe(sizes, &localization),
+        None => localization.get("payload-not-fetched").to_string(),
+    };
+}
+
+fn describe(sizes: &PayloadSizes, localization: &Localization) -> String {
+    let size = |key, bytes| localization.format(key, &[("size", &format_bytes(bytes))]);
+    let mut lines = vec![
+        localization.format(
+            "payload-transmitted",
+            &[
+                ("transmitted", &format_bytes(sizes.transmitted)),
+                ("uncompressed", &format_bytes(sizes.uncompressed())),
+            ],
+        ),
+        size("payload-affector-meshes", sizes.affector_meshes),
+        size("payload-visual-meshes", sizes.meshes),
+        size("payload-materials", sizes.materials),
+        size("payload-images", sizes.images),
+        size("payload-other", sizes.other),
+    ];
+    if !sizes.entities.is_empty() {
+        lines.push(localization.get("payload-largest-entities").to_string());
+    }
+    for entity in sizes.entities.iter().take(MAX_LISTED_ENTITIES) {
+        let source = entity.source.as_ref().map_or_else(
+            || localization.get("payload-unknown-entity").to_string(),
+            ToString::to_string,
+        );
+        lines.push(format!("  {source}: {}", format_bytes(entity.total())));
+    }
+    lines.join("\n")
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const KIB: f32 = 1024.0;
+    let bytes_f32 = bytes as f32;
+    if bytes_f32 < KIB {
+        format!("{bytes} B")
+    } else if bytes_f32 < KIB * KIB {
+        format!("{:.1} KiB", bytes_f32 / KIB)
+    } else {
+        format!("{:.1} MiB", bytes_f32 / (KIB * KIB))
+    }
+}
@@ -0,0 +1,151 @@
+//! Connections to running games over BRP.
+//! Multiple sessions allow e.g. inspecting both a client and a server running locally.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_rerecast::editor_integration::{brp::NavmeshInputParams, transmission::PayloadSizes};
+
+use crate::{
+    build::NavmeshAffector,
+    input_filter,
+    visualization::{AvailableGizmos, GizmosToDraw, Navmesh, VisualMesh},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(input_filter::plugin);
+    app.init_resource::<Sessions>();
+    app.init_resource::<StashedNavmeshes>();
+    app.add_observer(switch_session);
+    app.add_observer(add_session);
+}
+
+/// Identifies a session. Entities and navmeshes fetched from a session are tagged with this.
+#[derive(Component, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub(crate) struct SessionId(usize);
+
+#[derive(Resource)]
+pub(crate) struct Sessions {
+    sessions: Vec<Session>,
+    active: SessionId,
+}
+
+impl Sessions {
+    pub(crate) fn active_id(&self) -> SessionId {
+        self.active
+    }
+
+    pub(crate) fn active(&self) -> &Session {
+        &self.sessions[self.active.0]
+    }
+
+    pub(crate) fn active_mut(&mut self) -> &mut Session {
+        &mut self.sessions[self.active.0]
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (SessionId, &Session)> {
+        self.sessions
+            .iter()
+            .enumerate()
+            .map(|(i, session)| (SessionId(i), session))
+    }
+}
+
+impl Default for Sessions {
+    fn default() -> Self {
+        Self {
+            sessions: vec![Session::new(Session::DEFAULT_PORT)],
+            active: SessionId(0),
+        }
+    }
+}
+
+pub(crate) struct Session {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    /// Restricts which part of the game's scene is fetched, e.g. to only work on a single sub-level.
+    pub(crate) input_filter: NavmeshInputParams,
+    /// How large the navmesh input was when it was last fetched from this session.
+    pub(crate) payload_sizes: Option<PayloadSizes>,
+}
+
+impl Session {
+    /// The default port of `RemoteHttpPlugin`.
+    const DEFAULT_PORT: u16 = 15702;
+
+    fn new(port: u16) -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port,
+            input_filter: NavmeshInputParams::default(),
+            payload_sizes: None,
+        }
+    }
+
+    pub(crate) fn url(&self) -> String {
+        format!("http://{}:{}/", self.host, self.port)
+    }
+}
+
+/// Navmeshes of the sessions that are currently not active.
+#[derive(Resource, Default, Deref, DerefMut)]
+struct StashedNavmeshes(HashMap<SessionId, Navmesh>);
+
+#[derive(Event)]
+pub(crate) struct SwitchSession(pub(crate) SessionId);
+
+/// Adds a session on the port after the highest one that is currently in use.
+#[derive(Event)]
+pub(crate) struct AddSession;
+
+fn add_session(_: Trigger<AddSession>, mut sessions: ResMut<Sessions>) {
+    let port = sessions
+        .sessions
+        .iter()
+        .map(|session| session.port)
+        .max()
+        .unwrap_or(Session::DEFAULT_PORT - 1)
+        .saturating_add(1);
+    sessions.sessions.push(Session::new(port));
+}
+
+fn switch_session(
+    trigger: Trigger<SwitchSession>,
+    mut sessions: ResMut<Sessions>,
+    mut stashed: ResMut<StashedNavmeshes>,
+    navmesh: Option<Res<Navmesh>>,
+    mut visuals: Query<(&SessionId, &mut Visibility), With<VisualMesh>>,
+    affectors: Query<&Gizmo, With<NavmeshAffector>>,
+    mut gizmo_assets: ResMut<Assets<GizmoAsset>>,
+    mut gizmos: ResMut<GizmosToDraw>,
+    mut commands: Commands,
+) {
+    let id = trigger.0;
+    if id == sessions.active || id.0 >= sessions.sessions.len() {
+        return;
+    }
+    sessions.active = id;
+
+    if let Some(navmesh) = navmesh {
+        stashed.insert(navmesh.session, navmesh.clone());
+    }
+    if let Some(navmesh) = stashed.remove(&id) {
+        commands.insert_resource(navmesh);
+    } else {
+        commands.remove_resource::<Navmesh>();
+    }
+
+    let show_visuals = gizmos.contains(&AvailableGizmos::Visual);
+    for (session, mut visibility) in &mut visuals {
+        *visibility = if *session == id && show_visuals {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    for gizmo in &affectors {
+        if let Some(gizmo) = gizmo_assets.get_mut(&gizmo.handle) {
+            gizmo.clear();
+        }
+    }
+    // Redraw all enabled gizmos for the new session.
+    gizmos.set_changed();
+}
@@ -0,0 +1,239 @@
+//! Stepping through a navmesh build one stage at a time, drawing what the last stage produced.
+//!
+//! Every [`StepNavmesh`] runs the next stage of a [`SteppedBuild`]. Once the last stage is done,
+//! the navmesh is shown like the result of a full build, and the next step starts over with the current scene.
+
+use bevy::prelude::*;
+use bevy_rerecast::{
+    rerecast::{AreaMergePolicy, BuildContext, BuildStage, PolygonNavmesh, SteppedBuild},
+    volume::NavmeshVolume,
+};
+
+use crate::{
+    build::{BuildNavmesh, BuildNavmeshConfig, SessionAffectors, active_volumes},
+    localization::Localization,
+    session::SessionId,
+    visualization::{Navmesh, StageSnapshots, StageView, draw_stage_view, region_color},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SteppedBake>();
+    app.add_observer(step_navmesh);
+    app.add_observer(discard_stepped_bake);
+    app.add_systems(Startup, spawn_stage_gizmo);
+    app.add_systems(
+        Update,
+        (
+            draw_stage.run_if(resource_changed::<SteppedBake>),
+            update_stage_label
+                .run_if(resource_changed::<SteppedBake>.or(resource_changed::<Localization>)),
+        ),
+    );
+}
+
+/// Runs the next stage of the stepped build. Starts a new build from the current scene if there is none,
+/// if the last one is done or failed, or if it was started for another session.
+///
+/// Unlike [`BuildNavmesh`], stepped builds keep all regions, regardless of [`SeedFromCamera`](crate::build::SeedFromCamera).
+#[derive(Event)]
+pub(crate) struct StepNavmesh;
+
+/// The build that is being stepped through.
+#[derive(Resource, Default)]
+pub(crate) struct SteppedBake(Option<Bake>);
+
+struct Bake {
+    build: SteppedBuild<'static>,
+    /// The session the build was started for.
+    session: SessionId,
+    /// The stage that ran last.
+    stage: Option<BuildStage>,
+}
+
+/// Shows which stage of the stepped build ran last.
+#[derive(Component)]
+pub(crate) struct StageLabel;
+
+#[derive(Component)]
+struct StageGizmo;
+
+fn step_navmesh(
+    _trigger: Trigger<StepNavmesh>,
+    mut affectors: SessionAffectors,
+    volumes: Query<(&NavmeshVolume, &Transform, &SessionId)>,
+    config: Res<BuildNavmeshConfig>,
+    mut bake: ResMut<SteppedBake>,
+    mut commands: Commands,
+) -> Result {
+    let session = affectors.session();
+    let outdated = bake
+        .0
+        .as_ref()
+        .is_none_or(|bake| bake.build.next_stage().is_none() || bake.session != session);
+    if outdated {
+        let mut config = config.build();
+        let (heightfield, keys) = affectors.record(&config)?;
+        config.aabb = heightfield.aabb;
+        let spans = keys
+            .iter()
+            .filter_map(|key| affectors.spans(key).cloned())
+            .collect::<Vec<_>>();
+        let build = SteppedBuild::new(config, move |heightfield| {
+            for spans in &spans {
+                heightfield.replay_spans(
+                    spans,
+                    config.walkable_climb,
+                    AreaMergePolicy::default(),
+                )?;
+            }
+            Ok(())
+        })
+        .with_volumes(active_volumes(&volumes, session));
+        bake.0 = Some(Bake {
+            build,
+            session,
+            stage: None,
+        });
+        // Only the stages of the new build are shown until it is done.
+        commands.remove_resource::<Navmesh>();
+        commands.remove_resource::<StageSnapshots>();
+    }
+
+    let Some(bake) = bake.0.as_mut() else {
+        return Ok(());
+    };
+    bake.stage = bake.build.next_stage();
+    bake.build.step(&BuildContext::default())?;
+    if let (Some(poly_mesh), Some(detail_mesh)) =
+        (bake.build.polygon_mesh(), bake.build.detail_mesh())
+    {
+        commands.insert_resource(Navmesh {
+            poly_mesh: poly_mesh.clone(),
+            detail_mesh: detail_mesh.clone(),
+            session: bake.session,
+        });
+    }
+    Ok(())
+}
+
+fn discard_stepped_bake(_trigger: Trigger<BuildNavmesh>, mut bake: ResMut<SteppedBake>) {
+    bake.0 = None;
+}
+
+fn spawn_stage_gizmo(mut gizmos: ResMut<Assets<GizmoAsset>>, mut commands: Commands) {
+    commands.spawn((
+        Name::new("Build Stage Gizmo"),
+        StageGizmo,
+        Gizmo {
+            handle: gizmos.add(GizmoAsset::new()),
+            line_config: GizmoLineConfig {
+                perspective: true,
+                width: 10.0,
+                ..default()
+            },
+            depth_bias: -0.001,
+        },
+    ));
+}
+
+/// A label for the menu bar that shows the stage of the stepped build that ran last.
+pub(crate) fn stage_label() -> impl Bundle {
+    (
+        Name::new("Build Stage"),
+        StageLabel,
+        Node {
+            align_self: AlignSelf::Center,
+            ..default()
+        },
+        Text::default(),
+        TextFont::from_font_size(15.0),
+        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+    )
+}
+
+/// The name of the stage in the current locale, falling back to [`BuildStage::name`].
+pub(crate) fn stage_name(stage: BuildStage, localization: &Localization) -> &'static str {
+    let key = format!("stage-{}", stage.name().replace('_', "-"));
+    localization.try_get(&key).unwrap_or(stage.name())
+}
+
+fn update_stage_label(
+    bake: Res<SteppedBake>,
+    localization: Res<Localization>,
+    mut labels: Query<&mut Text, With<StageLabel>>,
+) {
+    let text = match &bake.0 {
+        Some(Bake {
+            stage: Some(stage), ..
+        }) => localization.format(
+            "stage-progress",
+            &[
+                ("index", &(stage.index() + 1)),
+                ("count", &BuildStage::ALL.len()),
+                ("stage", &stage_name(*stage, &localization)),
+            ],
+        ),
+        _ => String::new(),
+    };
+    for mut label in &mut labels {
+        label.0.clone_from(&text);
+    }
+}
+
+fn draw_stage(
+    bake: Res<SteppedBake>,
+    gizmo: Single<&Gizmo, With<StageGizmo>>,
+    mut gizmos: ResMut<Assets<GizmoAsset>>,
+) {
+    let Some(gizmo) = gizmos.get_mut(&gizmo.handle) else {
+        error!("Failed to get gizmo asset");
+        return;
+    };
+    gizmo.clear();
+    let Some(Bake {
+        build,
+        stage: Some(stage),
+        ..
+    }) = &bake.0
+    else {
+        return;
+    };
+    let view = match stage {
+        BuildStage::Rasterization | BuildStage::Filtering => StageView::Heightfield,
+        BuildStage::Compaction | BuildStage::Erosion => StageView::CompactHeightfield,
+        BuildStage::DistanceField => StageView::DistanceField,
+        BuildStage::Regions => StageView::Regions,
+        BuildStage::Contours => StageView::Contours,
+        BuildStage::PolygonMesh => {
+            if let Some(poly_mesh) = build.polygon_mesh() {
+                draw_polygons(gizmo, poly_mesh);
+            }
+            return;
+        }
+        // The finished navmesh is drawn like the result of a full build.
+        BuildStage::DetailMesh => return,
+    };
+    draw_stage_view(
+        gizmo,
+        view,
+        build.heightfield(),
+        build.compact_heightfield(),
+        build.contours(),
+    );
+}
+
+fn draw_polygons(gizmo: &mut GizmoAsset, poly_mesh: &PolygonNavmesh) {
+    for polygon in poly_mesh.polygon_ids() {
+        let vertices = poly_mesh
+            .polygon_vertices(polygon)
+            .map(|vertex| poly_mesh.vertex_world(vertex))
+            .collect::<Vec<_>>();
+        let Some(first) = vertices.first().copied() else {
+            continue;
+        };
+        gizmo.linestrip(
+            vertices.into_iter().chain([first]),
+            region_color(poly_mesh.regions[polygon.index()].bits()),
+        );
+    }
+}
@@ -0,0 +1,421 @@
+//! Bakes the same scene with one [`ConfigField`] swept across a range of values and lists the results,
+//! so that the trade-off between bake time and navmesh detail can be compared side by side.
+
+use std::time::Duration;
+
+use bevy::{
+    color::palettes::tailwind,
+    ecs::{spawn::SpawnWith, system::ObserverSystem},
+    prelude::*,
+    ui::Val::*,
+};
+use bevy_rerecast::rerecast::NavmeshConfigBuilder;
+
+use crate::{
+    build::{BuildNavmesh, BuildNavmeshConfig},
+    config_panel::ConfigField,
+    localization::{Localization, UiText},
+    theme::{
+        numeric_input::{NumericInput, NumericInputCommitted},
+        palette::LABEL_TEXT,
+        widget::{button, button_small, numeric_input},
+    },
+    timeline::{BakeTimings, format_ms},
+    visualization::Navmesh,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Sweep>();
+    app.add_systems(
+        Update,
+        (
+            advance_sweep.run_if(sweep_running),
+            (update_sweep_settings, update_sweep_table).run_if(
+                resource_changed::<Sweep>
+                    .or(resource_changed::<Localization>)
+                    .or(sweep_inputs_changed),
+            ),
+        )
+            .chain(),
+    );
+}
+
+/// The settings of the sweep and the results of the last one.
+#[derive(Resource, Debug)]
+struct Sweep {
+    field: ConfigField,
+    from: f32,
+    to: f32,
+    steps: u16,
+    run: Option<SweepRun>,
+    results: Vec<SweepResult>,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self {
+            field: ConfigField::all()
+                .next()
+                .expect("the config has editable fields"),
+            from: 0.2,
+            to: 0.5,
+            steps: 5,
+            run: None,
+            results: Vec::new(),
+        }
+    }
+}
+
+impl Sweep {
+    const MAX_STEPS: u16 = 20;
+
+    /// The values the field takes, evenly spaced from [`Self::from`] to [`Self::to`].
+    fn values(&self) -> Vec<f32> {
+        if self.steps <= 1 {
+            return vec![self.from];
+        }
+        (0..self.steps)
+            .map(|i| {
+                let value = self.from + (self.to - self.from) * i as f32 / (self.steps - 1) as f32;
+                if self.field.is_integer() {
+                    value.round()
+                } else {
+                    value
+                }
+            })
+            .collect()
+    }
+}
+
+/// A sweep in progress. The config is restored once all values are baked.
+#[derive(Debug)]
+struct SweepRun {
+    original: NavmeshConfigBuilder,
+    pending: std::vec::IntoIter<f32>,
+    /// The value of the bake triggered last frame, whose result is collected next.
+    baking: Option<f32>,
+}
+
+#[derive(Debug)]
+struct SweepResult {
+    value: f32,
+    /// `None` if the bake failed.
+    stats: Option<SweepStats>,
+}
+
+#[derive(Debug)]
+struct SweepStats {
+    duration: Duration,
+    polygons: usize,
+    vertices: usize,
+    detail_triangles: usize,
+}
+
+/// Which setting of the [`Sweep`] a [`NumericInput`] edits.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum SweepInput {
+    From,
+    To,
+    Steps,
+}
+
+#[derive(Component)]
+struct SweepFieldText;
+
+#[derive(Component)]
+struct SweepTable;
+
+/// The section of the config panel for setting up and running a sweep.
+pub(crate) fn sweep_section() -> impl Bundle {
+    (
+        Name::new("Sweep"),
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Px(4.0),
+            margin: UiRect::top(Px(8.0)),
+            ..default()
+        },
+        children![
+            (
+                Name::new("Sweep Field"),
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Px(5.0),
+                    ..default()
+                },
+                children![
+                    sweep_text("sweep", 130.0),
+                    (SweepFieldText, sweep_text(String::new(), 130.0)),
+                    button_small(String::from(">"), next_sweep_field),
+                ],
+            ),
+            (
+                Name::new("Sweep Range"),
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Px(5.0),
+                    ..default()
+                },
+                Children::spawn(SpawnWith(|parent: &mut ChildSpawner| {
+                    spawn_sweep_input(parent, SweepInput::From);
+                    parent.spawn(sweep_text("sweep-to", 20.0));
+                    spawn_sweep_input(parent, SweepInput::To);
+                    parent.spawn(sweep_text("sweep-in", 20.0));
+                    spawn_sweep_input(parent, SweepInput::Steps);
+                    parent.spawn(sweep_text("sweep-steps", 40.0));
+                })),
+            ),
+            (
+                Name::new("Sweep Buttons"),
+                Node::default(),
+                children![button("sweep-run", start_sweep)],
+            ),
+            (
+                Name::new("Sweep Table"),
+                SweepTable,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Px(2.0),
+                    ..default()
+                },
+            ),
+        ],
+    )
+}
+
+fn sweep_text(text: impl Into<UiText>, width: f32) -> impl Bundle {
+    (
+        Node {
+            width: Px(width),
+            ..default()
+        },
+        text.into(),
+        TextFont::from_font_size(14.0),
+        TextColor(LABEL_TEXT),
+    )
+}
+
+fn spawn_sweep_input(parent: &mut ChildSpawner, input: SweepInput) {
+    // The range of the swept field is only known when committing, so the values are clamped there.
+    let (min, max, integer) = match input {
+        SweepInput::From | SweepInput::To => (f32::MIN, f32::MAX, false),
+        SweepInput::Steps => (1.0, Sweep::MAX_STEPS as f32, true),
+    };
+    parent
+        .spawn((numeric_input(min, max, integer), input))
+        .observe(set_sweep_input(input));
+}
+
+fn set_sweep_input(input: SweepInput) -> impl ObserverSystem<NumericInputCommitted, (), ()> {
+    IntoSystem::into_system(
+        move |trigger: Trigger<NumericInputCommitted>, mut sweep: ResMut<Sweep>| {
+            let value = **trigger.event();
+            let (min, max) = sweep.field.range();
+            match input {
+                SweepInput::From => sweep.from = value.clamp(min, max),
+                SweepInput::To => sweep.to = value.clamp(min, max),
+                SweepInput::Steps => sweep.steps = value as u16,
+            }
+        },
+    )
+}
+
+fn next_sweep_field(
+    _: Trigger<Pointer<Click>>,
+    mut sweep: ResMut<Sweep>,
+    config: Res<BuildNavmeshConfig>,
+) {
+    let fields = ConfigField::all().collect::<Vec<_>>();
+    let index = fields
+        .iter()
+        .position(|field| *field == sweep.field)
+        .unwrap_or_default();
+    let field = fields[(index + 1) % fields.len()];
+    // Start out around the current value.
+    let (min, max) = field.range();
+    let value = field.get(&config);
+    sweep.field = field;
+    sweep.from = (value * 0.5).clamp(min, max);
+    sweep.to = (value * 1.5).clamp(min, max);
+}
+
+fn start_sweep(
+    _: Trigger<Pointer<Click>>,
+    mut sweep: ResMut<Sweep>,
+    config: Res<BuildNavmeshConfig>,
+) {
+    if sweep.run.is_some() {
+        return;
+    }
+    sweep.results.clear();
+    sweep.run = Some(SweepRun {
+        original: **config,
+        pending: sweep.values().into_iter(),
+        baking: None,
+    });
+}
+
+fn sweep_running(sweep: Res<Sweep>) -> bool {
+    sweep.run.is_some()
+}
+
+fn sweep_inputs_changed(inputs: Query<(), (Changed<NumericInput>, With<SweepInput>)>) -> bool {
+    !inputs.is_empty()
+}
+
+/// Collects the result of the bake triggered last frame and triggers the next one.
+/// Bakes are triggered as commands, so they finish right after this system.
+fn advance_sweep(
+    mut sweep: ResMut<Sweep>,
+    mut config: ResMut<BuildNavmeshConfig>,
+    timings: Res<BakeTimings>,
+    navmesh: Option<Res<Navmesh>>,
+    mut commands: Commands,
+) {
+    let Some(mut run) = sweep.run.take() else {
+        return;
+    };
+    if let Some(value) = run.baking.take() {
+        // Failed bakes don't record any timings.
+        let stats = navmesh
+            .filter(|_| timings.is_changed())
+            .map(|navmesh| SweepStats {
+                duration: timings.last_total(),
+                polygons: navmesh.poly_mesh.polygon_count(),
+                vertices: navmesh.poly_mesh.vertices.len(),
+                detail_triangles: navmesh.detail_mesh.triangles.len(),
+            });
+        sweep.results.push(SweepResult { value, stats });
+    }
+
+    let Some(value) = run.pending.next() else {
+        // Leave the navmesh as it was before the sweep.
+        **config = run.original;
+        commands.trigger(BuildNavmesh);
+        return;
+    };
+    let mut builder = run.original;
+    sweep.field.set(&mut builder, value);
+    **config = builder;
+    commands.trigger(BuildNavmesh);
+    run.baking = Some(value);
+    sweep.run = Some(run);
+}
+
+fn update_sweep_settings(
+    sweep: Res<Sweep>,
+    localization: Res<Localization>,
+    mut field_text: Single<&mut UiText, With<SweepFieldText>>,
+    mut inputs: Query<(&SweepInput, &NumericInput, &mut Text)>,
+) {
+    **field_text = UiText::Verbatim(sweep.field.label(&localization));
+    for (input, numeric_input, mut text) in &mut inputs {
+        // Don't overwrite what the user is typing.
+        if numeric_input.editing.is_some() {
+            continue;
+        }
+        text.0 = match input {
+            SweepInput::From => format!("{:.2}", sweep.from),
+            SweepInput::To => format!("{:.2}", sweep.to),
+            SweepInput::Steps => sweep.steps.to_string(),
+        };
+    }
+}
+
+/// Lists the results as a table, with a bar per row comparing the bake times.
+fn update_sweep_table(
+    sweep: Res<Sweep>,
+    localization: Res<Localization>,
+    table: Single<Entity, With<SweepTable>>,
+    mut commands: Commands,
+) {
+    let table = *table;
+    commands.entity(table).despawn_related::<Children>();
+    if sweep.results.is_empty() {
+        return;
+    }
+
+    let columns = [
+        sweep.field.label(&localization),
+        localization.get("sweep-bake").to_string(),
+        localization.get("sweep-polys").to_string(),
+        localization.get("sweep-verts").to_string(),
+        localization.get("sweep-detail-tris").to_string(),
+    ];
+    commands.spawn((ChildOf(table), table_row(columns, None)));
+    let slowest = sweep
+        .results
+        .iter()
+        .filter_map(|result| result.stats.as_ref())
+        .map(|stats| stats.duration)
+        .max()
+        .unwrap_or_default();
+    for result in &sweep.results {
+        let value = format!("{:.2}", result.value);
+        let row = match &result.stats {
+            Some(stats) => table_row(
+                [
+                    value,
+                    format_ms(stats.duration),
+                    stats.polygons.to_string(),
+                    stats.vertices.to_string(),
+                    stats.detail_triangles.to_string(),
+                ],
+                Some(stats.duration.as_secs_f32() / slowest.as_secs_f32().max(1e-6)),
+            ),
+            None => table_row(
+                [
+                    value,
+                    localization.get("sweep-failed").to_string(),
+                    "-".into(),
+                    "-".into(),
+                    "-".into(),
+                ],
+                None,
+            ),
+        };
+        commands.spawn((ChildOf(table), row));
+    }
+    if let Some(run) = &sweep.run {
+        let remaining = run.pending.len() + usize::from(run.baking.is_some());
+        commands.spawn((
+            ChildOf(table),
+            sweep_text(
+                localization.format("sweep-remaining", &[("count", &remaining)]),
+                200.0,
+            ),
+        ));
+    }
+}
+
+fn table_row(cells: [String; 5], bar: Option<f32>) -> impl Bundle {
+    (
+        Node {
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+            for (i, cell) in cells.into_iter().enumerate() {
+                parent.spawn((
+                    Node {
+                        width: Px(if i == 0 { 110.0 } else { 70.0 }),
+                        ..default()
+                    },
+                    Text::new(cell),
+                    TextFont::from_font_size(12.0),
+                    TextColor(LABEL_TEXT.with_alpha(0.8)),
+                ));
+            }
+            if let Some(fraction) = bar {
+                parent.spawn((
+                    Node {
+                        width: Px(60.0 * fraction),
+                        height: Px(8.0),
+                        ..default()
+                    },
+                    BackgroundColor(tailwind::SKY_500.into()),
+                ));
+            }
+        })),
+    )
+}
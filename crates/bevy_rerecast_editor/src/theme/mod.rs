@@ -4,7 +4,9 @@
 #![allow(dead_code)]
 
 pub mod interaction;
+pub mod numeric_input;
 pub mod palette;
+pub mod text_input;
 pub mod widget;
 
 #[allow(unused_imports)]
@@ -15,5 +17,9 @@ pub mod prelude {
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins(interaction::plugin);
+    app.add_plugins((
+        interaction::plugin,
+        numeric_input::plugin,
+        text_input::plugin,
+    ));
 }
@@ -0,0 +1,152 @@
+//! A text field that only accepts numbers within a range.
+//!
+//! Clicking the field starts editing it. Enter commits the typed value, Escape discards it.
+//! A committed value is clamped to the range of the field and reported through [`NumericInputCommitted`].
+//! Values that don't parse as a number are discarded.
+
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+
+use crate::theme::palette::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<FocusedNumericInput>();
+    app.add_observer(focus_numeric_input);
+    app.add_systems(
+        Update,
+        (
+            edit_focused_input,
+            highlight_focused_input.run_if(resource_changed::<FocusedNumericInput>),
+        )
+            .chain(),
+    );
+}
+
+/// A numeric text field. Use [`widget::numeric_input`](super::widget::numeric_input) to spawn one.
+///
+/// While the field is not being edited, its [`Text`] is owned by whoever spawned it.
+#[derive(Component, Debug, Clone)]
+pub struct NumericInput {
+    /// The smallest value the field accepts.
+    pub min: f32,
+    /// The largest value the field accepts.
+    pub max: f32,
+    /// Whether the field only accepts whole numbers.
+    pub integer: bool,
+    /// The text typed so far, or `None` if the field is not being edited.
+    pub editing: Option<String>,
+}
+
+impl NumericInput {
+    /// Parses `text` and clamps it to the range of the field.
+    pub fn parse(&self, text: &str) -> Option<f32> {
+        let value = text.trim().parse::<f32>().ok().filter(|v| v.is_finite())?;
+        let value = if self.integer { value.round() } else { value };
+        Some(value.clamp(self.min, self.max))
+    }
+}
+
+/// Triggered on a [`NumericInput`] entity when a valid value was committed. The value is already clamped.
+#[derive(Event, Debug, Clone, Copy, Deref)]
+pub struct NumericInputCommitted(pub f32);
+
+/// The [`NumericInput`] that currently receives keyboard input.
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub struct FocusedNumericInput(Option<Entity>);
+
+fn focus_numeric_input(
+    trigger: Trigger<Pointer<Click>>,
+    mut inputs: Query<(&mut NumericInput, &Text)>,
+    mut focused: ResMut<FocusedNumericInput>,
+    mut commands: Commands,
+) {
+    // Only handle the click once, not for every ancestor it bubbles up to.
+    let entity = trigger.event().target;
+    if trigger.target() != entity || **focused == Some(entity) {
+        return;
+    }
+    // Clicking anywhere else commits the field that is currently being edited.
+    if let Some(previous) = focused.take()
+        && let Ok((mut input, _)) = inputs.get_mut(previous)
+        && let Some(typed) = input.editing.take()
+        && let Some(value) = input.parse(&typed)
+    {
+        commands.trigger_targets(NumericInputCommitted(value), previous);
+    }
+    if let Ok((mut input, text)) = inputs.get_mut(entity) {
+        input.editing = Some(text.0.clone());
+        **focused = Some(entity);
+    }
+}
+
+fn edit_focused_input(
+    mut keyboard: EventReader<KeyboardInput>,
+    mut focused: ResMut<FocusedNumericInput>,
+    mut inputs: Query<(&mut NumericInput, &mut Text)>,
+    mut commands: Commands,
+) {
+    let Some(entity) = **focused else {
+        keyboard.clear();
+        return;
+    };
+    let Ok((mut input, mut text)) = inputs.get_mut(entity) else {
+        **focused = None;
+        return;
+    };
+    let Some(typed) = input.editing.as_mut() else {
+        **focused = None;
+        return;
+    };
+    for event in keyboard.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(chars) => typed.extend(
+                chars
+                    .chars()
+                    .filter(|c| c.is_ascii_digit() || matches!(c, '.' | '-')),
+            ),
+            Key::Backspace => {
+                typed.pop();
+            }
+            Key::Enter => {
+                let typed = input.editing.take().unwrap_or_default();
+                if let Some(value) = input.parse(&typed) {
+                    commands.trigger_targets(NumericInputCommitted(value), entity);
+                }
+                **focused = None;
+                return;
+            }
+            Key::Escape => {
+                input.editing = None;
+                **focused = None;
+                return;
+            }
+            _ => {}
+        }
+    }
+    // Show a cursor while editing.
+    let display = format!("{typed}|");
+    if text.0 != display {
+        text.0 = display;
+    }
+}
+
+fn highlight_focused_input(
+    focused: Res<FocusedNumericInput>,
+    mut inputs: Query<(Entity, &mut BorderColor), With<NumericInput>>,
+) {
+    for (entity, mut border) in &mut inputs {
+        border.0 = if **focused == Some(entity) {
+            BUTTON_HOVERED_BACKGROUND
+        } else {
+            INPUT_BORDER
+        };
+    }
+}
@@ -19,3 +19,8 @@ pub const BUTTON_DISABLED_BACKGROUND: Color = Color::srgb(0.6117647, 0.6392157,
 
 // #080202
 pub(crate) const BEVY_GRAY: Color = Color::srgb(0.035, 0.01, 0.01);
+
+/// #1a1a1a
+pub const INPUT_BACKGROUND: Color = Color::srgb(0.1, 0.1, 0.1);
+/// #595959
+pub const INPUT_BORDER: Color = Color::srgb(0.35, 0.35, 0.35);
@@ -0,0 +1,130 @@
+//! A text field for free text, e.g. a host name or a file path.
+//!
+//! Works like a [`NumericInput`](super::numeric_input::NumericInput): clicking the field starts editing it,
+//! Enter commits the typed text and Escape discards it. The committed text is reported through [`TextInputCommitted`].
+
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+
+use crate::theme::palette::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<FocusedTextInput>();
+    app.add_observer(focus_text_input);
+    app.add_systems(
+        Update,
+        (
+            edit_focused_input,
+            highlight_focused_input.run_if(resource_changed::<FocusedTextInput>),
+        )
+            .chain(),
+    );
+}
+
+/// A text field. Use [`widget::text_input`](super::widget::text_input) to spawn one.
+///
+/// While the field is not being edited, its [`Text`] is owned by whoever spawned it.
+#[derive(Component, Debug, Clone, Default)]
+pub struct TextInput {
+    /// The text typed so far, or `None` if the field is not being edited.
+    pub editing: Option<String>,
+}
+
+/// Triggered on a [`TextInput`] entity when text was committed. The text is trimmed and may be empty.
+#[derive(Event, Debug, Clone, Deref)]
+pub struct TextInputCommitted(pub String);
+
+/// The [`TextInput`] that currently receives keyboard input.
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub struct FocusedTextInput(Option<Entity>);
+
+fn focus_text_input(
+    trigger: Trigger<Pointer<Click>>,
+    mut inputs: Query<(&mut TextInput, &Text)>,
+    mut focused: ResMut<FocusedTextInput>,
+    mut commands: Commands,
+) {
+    // Only handle the click once, not for every ancestor it bubbles up to.
+    let entity = trigger.event().target;
+    if trigger.target() != entity || **focused == Some(entity) {
+        return;
+    }
+    // Clicking anywhere else commits the field that is currently being edited.
+    if let Some(previous) = focused.take()
+        && let Ok((mut input, _)) = inputs.get_mut(previous)
+        && let Some(typed) = input.editing.take()
+    {
+        commands.trigger_targets(TextInputCommitted(typed.trim().to_string()), previous);
+    }
+    if let Ok((mut input, text)) = inputs.get_mut(entity) {
+        input.editing = Some(text.0.clone());
+        **focused = Some(entity);
+    }
+}
+
+fn edit_focused_input(
+    mut keyboard: EventReader<KeyboardInput>,
+    mut focused: ResMut<FocusedTextInput>,
+    mut inputs: Query<(&mut TextInput, &mut Text)>,
+    mut commands: Commands,
+) {
+    let Some(entity) = **focused else {
+        keyboard.clear();
+        return;
+    };
+    let Ok((mut input, mut text)) = inputs.get_mut(entity) else {
+        **focused = None;
+        return;
+    };
+    let Some(typed) = input.editing.as_mut() else {
+        **focused = None;
+        return;
+    };
+    for event in keyboard.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(chars) => typed.push_str(chars),
+            Key::Space => typed.push(' '),
+            Key::Backspace => {
+                typed.pop();
+            }
+            Key::Enter => {
+                let typed = input.editing.take().unwrap_or_default();
+                commands.trigger_targets(TextInputCommitted(typed.trim().to_string()), entity);
+                **focused = None;
+                return;
+            }
+            Key::Escape => {
+                input.editing = None;
+                **focused = None;
+                return;
+            }
+            _ => {}
+        }
+    }
+    // Show a cursor while editing.
+    let display = format!("{typed}|");
+    if text.0 != display {
+        text.0 = display;
+    }
+}
+
+fn highlight_focused_input(
+    focused: Res<FocusedTextInput>,
+    mut inputs: Query<(Entity, &mut BorderColor), With<TextInput>>,
+) {
+    for (entity, mut border) in &mut inputs {
+        border.0 = if **focused == Some(entity) {
+            BUTTON_HOVERED_BACKGROUND
+        } else {
+            INPUT_BORDER
+        };
+    }
+}
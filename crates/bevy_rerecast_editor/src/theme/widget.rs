@@ -1,4 +1,7 @@
 //! Helper functions for creating common widgets.
+//!
+//! Widget texts are [`UiText`]s: string literals are keys into the [`Localization`](crate::localization::Localization),
+//! while owned strings are shown as is.
 
 use std::borrow::Cow;
 
@@ -8,7 +11,13 @@ use bevy::{
     ui::Val::*,
 };
 
-use crate::theme::{interaction::InteractionPalette, palette::*};
+use crate::{
+    localization::UiText,
+    theme::{
+        interaction::InteractionPalette, numeric_input::NumericInput, palette::*,
+        text_input::TextInput,
+    },
+};
 
 /// A root UI node that fills the window and centers its content.
 pub fn ui_root(name: impl Into<Cow<'static, str>>) -> impl Bundle {
@@ -30,20 +39,20 @@ pub fn ui_root(name: impl Into<Cow<'static, str>>) -> impl Bundle {
 }
 
 /// A simple header label. Bigger than [`label`].
-pub fn header(text: impl Into<String>) -> impl Bundle {
+pub fn header(text: impl Into<UiText>) -> impl Bundle {
     (
         Name::new("Header"),
-        Text(text.into()),
+        text.into(),
         TextFont::from_font_size(40.0),
         TextColor(HEADER_TEXT),
     )
 }
 
 /// A simple text label.
-pub fn label(text: impl Into<String>) -> impl Bundle {
+pub fn label(text: impl Into<UiText>) -> impl Bundle {
     (
         Name::new("Label"),
-        Text(text.into()),
+        text.into(),
         TextFont::from_font_size(18.0),
         TextColor(LABEL_TEXT),
     )
@@ -60,7 +69,7 @@ pub fn hspace(px: f32) -> impl Bundle {
 }
 
 /// A small square button with text and an action defined as an [`Observer`].
-pub(crate) fn button_small<E, B, M, I>(text: impl Into<String>, action: I) -> impl Bundle
+pub(crate) fn button_small<E, B, M, I>(text: impl Into<UiText>, action: I) -> impl Bundle
 where
     E: Event,
     B: Bundle,
@@ -85,7 +94,7 @@ where
 }
 
 /// A large rounded button with text and an action defined as an [`Observer`].
-pub fn button<E, B, M, I>(text: impl Into<String>, action: I) -> impl Bundle
+pub fn button<E, B, M, I>(text: impl Into<UiText>, action: I) -> impl Bundle
 where
     E: Event,
     B: Bundle,
@@ -109,7 +118,7 @@ where
 
 /// A simple button with text and an action defined as an [`Observer`]. The button's layout is provided by `button_bundle`.
 fn button_base<E, B, M, I>(
-    text: impl Into<String>,
+    text: impl Into<UiText>,
     action: I,
     button_bundle: impl Bundle,
     label_bundle: impl Bundle,
@@ -140,7 +149,7 @@ where
                         parent
                             .spawn((
                                 Name::new("Button Text"),
-                                Text(text),
+                                text,
                                 TextFont::from_font_size(20.0),
                                 TextColor(BUTTON_TEXT),
                                 // Don't bubble picking events from the text up to the button.
@@ -155,7 +164,7 @@ where
     )
 }
 
-pub fn checkbox<E, B, M, I>(text: impl Into<String>, action: I) -> impl Bundle
+pub fn checkbox<E, B, M, I>(text: impl Into<UiText>, action: I) -> impl Bundle
 where
     E: Event,
     B: Bundle,
@@ -167,6 +176,57 @@ where
             align_items: AlignItems::Center,
             ..default()
         },
-        children![label(text), hspace(10.0), button_small("", action)],
+        children![
+            label(text),
+            hspace(10.0),
+            button_small(String::new(), action)
+        ],
+    )
+}
+
+/// A text field accepting numbers between `min` and `max`. Observe [`NumericInputCommitted`](super::numeric_input::NumericInputCommitted)
+/// on the spawned entity to receive the values typed into it.
+pub fn numeric_input(min: f32, max: f32, integer: bool) -> impl Bundle {
+    (
+        Name::new("Numeric Input"),
+        NumericInput {
+            min,
+            max,
+            integer,
+            editing: None,
+        },
+        Node {
+            width: Px(70.0),
+            padding: UiRect::axes(Px(4.0), Px(1.0)),
+            border: UiRect::all(Px(1.0)),
+            ..default()
+        },
+        Text::default(),
+        TextFont::from_font_size(14.0),
+        TextColor(LABEL_TEXT),
+        BackgroundColor(INPUT_BACKGROUND),
+        BorderColor(INPUT_BORDER),
+        BorderRadius::all(Px(3.0)),
+    )
+}
+
+/// A text field `width` pixels wide. Observe [`TextInputCommitted`](super::text_input::TextInputCommitted)
+/// on the spawned entity to receive the text typed into it.
+pub fn text_input(width: f32) -> impl Bundle {
+    (
+        Name::new("Text Input"),
+        TextInput::default(),
+        Node {
+            width: Px(width),
+            padding: UiRect::axes(Px(4.0), Px(1.0)),
+            border: UiRect::all(Px(1.0)),
+            ..default()
+        },
+        Text::default(),
+        TextFont::from_font_size(14.0),
+        TextColor(LABEL_TEXT),
+        BackgroundColor(INPUT_BACKGROUND),
+        BorderColor(INPUT_BORDER),
+        BorderRadius::all(Px(3.0)),
     )
 }
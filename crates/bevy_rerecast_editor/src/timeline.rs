@@ -0,0 +1,243 @@
+//! The timeline showing how long each stage of the last bake took, compared to the bake before it.
+
+use std::time::{Duration, Instant};
+
+use bevy::{color::palettes::tailwind, prelude::*, ui::Val::*};
+use bevy_rerecast::rerecast::BuildStage;
+
+use crate::{
+    localization::{Localization, UiText},
+    stepping::stage_name,
+    theme::palette::{BEVY_GRAY, LABEL_TEXT},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<BakeTimings>();
+    app.add_systems(
+        Update,
+        update_timeline
+            .run_if(resource_changed::<BakeTimings>.or(resource_changed::<Localization>)),
+    );
+}
+
+/// The stage durations of the last two bakes.
+#[derive(Resource, Debug, Default)]
+pub(crate) struct BakeTimings {
+    current: Vec<(BuildStage, Duration)>,
+    previous: Vec<(BuildStage, Duration)>,
+}
+
+impl BakeTimings {
+    /// Stores the durations of a finished bake, keeping the ones of the bake before for comparison.
+    pub(crate) fn record(&mut self, stages: Vec<(BuildStage, Duration)>) {
+        self.previous = std::mem::replace(&mut self.current, stages);
+    }
+
+    /// The total duration of the last bake.
+    pub(crate) fn last_total(&self) -> Duration {
+        Self::total(&self.current)
+    }
+
+    fn total(stages: &[(BuildStage, Duration)]) -> Duration {
+        stages.iter().map(|(_, duration)| *duration).sum()
+    }
+
+    fn previous_duration(&self, stage: BuildStage) -> Option<Duration> {
+        self.previous
+            .iter()
+            .find(|(previous, _)| *previous == stage)
+            .map(|(_, duration)| *duration)
+    }
+}
+
+/// Measures the stages of a bake. Each call to [`StageTimer::begin`] ends the stage that was running before.
+#[derive(Debug, Default)]
+pub(crate) struct StageTimer {
+    stages: Vec<(BuildStage, Duration)>,
+    current: Option<(BuildStage, Instant)>,
+}
+
+impl StageTimer {
+    pub(crate) fn begin(&mut self, stage: BuildStage) {
+        let now = Instant::now();
+        if let Some((previous, start)) = self.current.replace((stage, now)) {
+            self.stages.push((previous, now - start));
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<(BuildStage, Duration)> {
+        if let Some((stage, start)) = self.current.take() {
+            self.stages.push((stage, start.elapsed()));
+        }
+        self.stages
+    }
+}
+
+/// The timeline, meant to be placed above the status bar.
+pub(crate) fn timeline() -> impl Bundle {
+    (
+        Name::new("Timeline"),
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Px(4.0),
+            padding: UiRect::axes(Px(10.0), Px(5.0)),
+            ..default()
+        },
+        BackgroundColor(BEVY_GRAY.with_alpha(0.6)),
+        children![
+            (
+                Name::new("Timeline Summary"),
+                TimelineSummary,
+                UiText::from("timeline-no-bake"),
+                TextFont::from_font_size(14.0),
+                TextColor(LABEL_TEXT),
+            ),
+            (
+                Name::new("Timeline Bar"),
+                TimelineBar,
+                Node {
+                    width: Percent(100.0),
+                    height: Px(12.0),
+                    ..default()
+                },
+            ),
+            (
+                Name::new("Timeline Legend"),
+                TimelineLegend,
+                Node {
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Px(15.0),
+                    ..default()
+                },
+            ),
+        ],
+    )
+}
+
+#[derive(Component)]
+struct TimelineSummary;
+
+#[derive(Component)]
+struct TimelineBar;
+
+#[derive(Component)]
+struct TimelineLegend;
+
+fn update_timeline(
+    timings: Res<BakeTimings>,
+    localization: Res<Localization>,
+    mut summary: Single<&mut UiText, With<TimelineSummary>>,
+    bar: Single<Entity, With<TimelineBar>>,
+    legend: Single<Entity, With<TimelineLegend>>,
+    mut commands: Commands,
+) {
+    let (bar, legend) = (*bar, *legend);
+    commands.entity(bar).despawn_related::<Children>();
+    commands.entity(legend).despawn_related::<Children>();
+
+    let total = BakeTimings::total(&timings.current);
+    **summary = if timings.current.is_empty() {
+        UiText::from("timeline-no-bake")
+    } else if timings.previous.is_empty() {
+        UiText::Verbatim(localization.format("timeline-last-bake", &[("total", &format_ms(total))]))
+    } else {
+        let previous = BakeTimings::total(&timings.previous);
+        UiText::Verbatim(localization.format(
+            "timeline-last-bake-compared",
+            &[
+                ("total", &format_ms(total)),
+                ("previous", &format_ms(previous)),
+                ("delta", &format_delta(total, previous)),
+            ],
+        ))
+    };
+
+    for (stage, duration) in &timings.current {
+        let color = stage_color(*stage);
+        commands.spawn((
+            Name::new(stage.name()),
+            ChildOf(bar),
+            Node {
+                // Stages too short to measure still get a sliver so that the order stays visible.
+                flex_grow: duration.as_secs_f32().max(1e-6),
+                height: Percent(100.0),
+                ..default()
+            },
+            BackgroundColor(color),
+        ));
+
+        let mut text = format!(
+            "{}: {}",
+            stage_name(*stage, &localization),
+            format_ms(*duration)
+        );
+        let mut delta_color = LABEL_TEXT;
+        if let Some(previous) = timings.previous_duration(*stage) {
+            text += &format!(" ({})", format_delta(*duration, previous));
+            delta_color = delta_color_for(*duration, previous);
+        }
+        commands.spawn((
+            Name::new(stage.name()),
+            ChildOf(legend),
+            Node {
+                align_items: AlignItems::Center,
+                column_gap: Px(4.0),
+                ..default()
+            },
+            children![
+                (
+                    Node {
+                        width: Px(10.0),
+                        height: Px(10.0),
+                        ..default()
+                    },
+                    BackgroundColor(color),
+                ),
+                (
+                    Text::new(text),
+                    TextFont::from_font_size(12.0),
+                    TextColor(delta_color),
+                )
+            ],
+        ));
+    }
+}
+
+fn stage_color(stage: BuildStage) -> Color {
+    let index = BuildStage::ALL
+        .iter()
+        .position(|s| *s == stage)
+        .unwrap_or_default();
+    Color::hsl(
+        index as f32 * 360.0 / BuildStage::ALL.len() as f32,
+        0.6,
+        0.5,
+    )
+}
+
+pub(crate) fn format_ms(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn format_delta(current: Duration, previous: Duration) -> String {
+    let delta = (current.as_secs_f64() - previous.as_secs_f64()) * 1000.0;
+    if previous.is_zero() {
+        return format!("{delta:+.2} ms");
+    }
+    let percent = delta / (previous.as_secs_f64() * 1000.0) * 100.0;
+    format!("{delta:+.2} ms, {percent:+.0}%")
+}
+
+/// Highlights stages that became noticeably slower or faster.
+/// Tiny differences are mostly noise, so they are ignored.
+fn delta_color_for(current: Duration, previous: Duration) -> Color {
+    const THRESHOLD: f64 = 0.1;
+    let (current, previous) = (current.as_secs_f64(), previous.as_secs_f64());
+    if current - previous > previous * THRESHOLD && current - previous > 1e-4 {
+        tailwind::RED_400.into()
+    } else if previous - current > previous * THRESHOLD && previous - current > 1e-4 {
+        tailwind::GREEN_400.into()
+    } else {
+        LABEL_TEXT
+    }
+}
@@ -0,0 +1,468 @@
+use bevy::{color::palettes::tailwind, ecs::system::ObserverSystem, prelude::*, ui::Val::*};
+
+use crate::{
+    build::{AutoRebuild, BuildNavmesh, SeedFromCamera},
+    config_panel::config_panel,
+    get_navmesh_input::GetNavmeshInput,
+    input_filter::input_filter_section,
+    input_source::InputSourceList,
+    localization::{Localization, UiText, next_locale},
+    off_mesh_link::{OffMeshLink, SelectedOffMeshLink},
+    payload_size::payload_size_section,
+    session::{AddSession, SessionId, Sessions, SwitchSession},
+    stepping::{StepNavmesh, stage_label},
+    theme::{
+        palette::BEVY_GRAY,
+        widget::{button, checkbox, label},
+    },
+    timeline::timeline,
+    visualization::{AvailableGizmos, GizmosToDraw, Navmesh, stage_view_section},
+    volume::volume_section,
+};
+
+mod onboarding;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(onboarding::plugin);
+    app.add_systems(Startup, spawn_ui);
+    app.add_systems(
+        Update,
+        (
+            update_session_list.run_if(resource_changed::<Sessions>),
+            update_status_bar_version.run_if(resource_changed::<Localization>),
+            update_off_mesh_link_list.run_if(
+                resource_changed::<Sessions>
+                    .or(resource_changed::<SelectedOffMeshLink>)
+                    .or(resource_changed::<Localization>)
+                    .or(off_mesh_links_changed),
+            ),
+        ),
+    );
+    app.add_observer(close_modal);
+}
+
+fn spawn_ui(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Canvas"),
+        Node {
+            width: Percent(100.0),
+            height: Percent(100.0),
+            display: Display::Grid,
+            grid_template_rows: vec![
+                // Menu bar
+                RepeatedGridTrack::auto(1),
+                // Config and property panel
+                RepeatedGridTrack::fr(1, 1.0),
+                // Timeline
+                RepeatedGridTrack::auto(1),
+                // Status bar
+                RepeatedGridTrack::auto(1),
+            ],
+            ..default()
+        },
+        Pickable::IGNORE,
+        children![
+            (
+                Name::new("Menu Bar"),
+                Node {
+                    padding: UiRect::axes(Px(10.0), Px(5.0)),
+                    column_gap: Val::Px(5.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.1, 0.1, 0.1)),
+                children![
+                    button("menu-load-scene", spawn_load_scene_modal),
+                    button("menu-build-navmesh", build_navmesh),
+                    button("menu-step-build", step_navmesh),
+                    button("menu-save-navmesh", save_navmesh),
+                    button("language-name", next_locale),
+                    stage_label(),
+                ]
+            ),
+            (
+                Name::new("Panels"),
+                Node {
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
+                Pickable::IGNORE,
+                children![
+                    config_panel(),
+                    (
+                        Name::new("Property Panel"),
+                        Node {
+                            width: Px(300.0),
+                            flex_direction: FlexDirection::Column,
+                            padding: UiRect::all(Px(30.0)),
+                            ..default()
+                        },
+                        children![
+                            (
+                                Name::new("Sessions"),
+                                Node {
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                },
+                                children![
+                                    label("sessions"),
+                                    (
+                                        Name::new("Session List"),
+                                        SessionList,
+                                        Node {
+                                            flex_direction: FlexDirection::Column,
+                                            row_gap: Px(5.0),
+                                            padding: UiRect::vertical(Px(5.0)),
+                                            ..default()
+                                        },
+                                    ),
+                                    button("sessions-add", add_session),
+                                    input_filter_section(),
+                                    payload_size_section(),
+                                ],
+                            ),
+                            checkbox("show-visual", toggle_gizmo(AvailableGizmos::Visual)),
+                            checkbox("show-affector", toggle_gizmo(AvailableGizmos::Affector)),
+                            checkbox("show-polygon-mesh", toggle_gizmo(AvailableGizmos::PolyMesh)),
+                            checkbox(
+                                "show-detail-mesh",
+                                toggle_gizmo(AvailableGizmos::DetailMesh)
+                            ),
+                            checkbox(
+                                "show-off-mesh-links",
+                                toggle_gizmo(AvailableGizmos::OffMeshLinks)
+                            ),
+                            checkbox("seed-from-camera", toggle_seed_from_camera),
+                            checkbox("auto-rebuild", toggle_auto_rebuild),
+                            stage_view_section(),
+                            (
+                                Name::new("Off-Mesh Links"),
+                                Node {
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                },
+                                children![
+                                    label("off-mesh-links"),
+                                    (
+                                        Name::new("Off-Mesh Link List"),
+                                        OffMeshLinkList,
+                                        Node {
+                                            flex_direction: FlexDirection::Column,
+                                            row_gap: Px(5.0),
+                                            padding: UiRect::vertical(Px(5.0)),
+                                            ..default()
+                                        },
+                                    ),
+                                    (
+                                        Name::new("Off-Mesh Link Diagnostics"),
+                                        OffMeshLinkDiagnostics,
+                                        Text::default(),
+                                        TextFont::from_font_size(14.0),
+                                        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                                    ),
+                                ],
+                            ),
+                            volume_section(),
+                            (
+                                Name::new("Input Entities"),
+                                Node {
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                },
+                                children![
+                                    label("input-entities"),
+                                    (
+                                        Name::new("Input Entity List"),
+                                        InputSourceList,
+                                        Node {
+                                            flex_direction: FlexDirection::Column,
+                                            row_gap: Px(2.0),
+                                            padding: UiRect::vertical(Px(5.0)),
+                                            ..default()
+                                        },
+                                    )
+                                ],
+                            )
+                        ],
+                        BackgroundColor(BEVY_GRAY.with_alpha(0.6)),
+                    )
+                ],
+            ),
+            timeline(),
+            (
+                Name::new("Status Bar"),
+                Node {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::SpaceBetween,
+                    padding: UiRect::axes(Px(10.0), Px(5.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.1, 0.1, 0.1)),
+                children![
+                    status_bar_text("status-bar"),
+                    (StatusBarVersion, status_bar_text(String::new())),
+                ],
+            )
+        ],
+    ));
+}
+
+#[derive(Component)]
+struct LoadSceneModal;
+
+#[derive(Component)]
+struct StatusBarVersion;
+
+#[derive(Component)]
+struct SessionList;
+
+fn update_session_list(
+    list: Single<Entity, With<SessionList>>,
+    sessions: Res<Sessions>,
+    mut commands: Commands,
+) {
+    let list = *list;
+    commands.entity(list).despawn_related::<Children>();
+    for (id, session) in sessions.iter() {
+        let marker = if id == sessions.active_id() { ">" } else { " " };
+        let text = format!("{marker} {}:{}", session.host, session.port);
+        commands.spawn((ChildOf(list), button(text, switch_session(id))));
+    }
+}
+
+#[derive(Component)]
+struct OffMeshLinkList;
+
+#[derive(Component)]
+struct OffMeshLinkDiagnostics;
+
+fn off_mesh_links_changed(
+    changed: Query<(), Changed<OffMeshLink>>,
+    mut removed: RemovedComponents<OffMeshLink>,
+) -> bool {
+    !changed.is_empty() || removed.read().count() > 0
+}
+
+fn update_off_mesh_link_list(
+    list: Single<Entity, With<OffMeshLinkList>>,
+    mut diagnostics: Single<&mut Text, With<OffMeshLinkDiagnostics>>,
+    links: Query<(Entity, &OffMeshLink, &SessionId)>,
+    sessions: Res<Sessions>,
+    selected: Res<SelectedOffMeshLink>,
+    localization: Res<Localization>,
+    mut commands: Commands,
+) {
+    let list = *list;
+    commands.entity(list).despawn_related::<Children>();
+    diagnostics.0.clear();
+    for (i, (entity, connection, session)) in links.iter().enumerate() {
+        if *session != sessions.active_id() {
+            continue;
+        }
+        let status = localization.get(match connection.is_valid() {
+            None => "off-mesh-link-not-validated",
+            Some(true) => "off-mesh-link-valid",
+            Some(false) => "off-mesh-link-invalid",
+        });
+        let marker = if **selected == Some(entity) {
+            diagnostics.0 = connection.diagnostics(&localization);
+            ">"
+        } else {
+            " "
+        };
+        let text = localization.format(
+            "off-mesh-link-entry",
+            &[("marker", &marker), ("index", &i), ("status", &status)],
+        );
+        commands.spawn((ChildOf(list), button(text, select_off_mesh_link(entity))));
+    }
+}
+
+fn select_off_mesh_link(entity: Entity) -> impl ObserverSystem<Pointer<Click>, (), ()> {
+    IntoSystem::into_system(
+        move |_: Trigger<Pointer<Click>>, mut selected: ResMut<SelectedOffMeshLink>| {
+            **selected = if **selected == Some(entity) {
+                None
+            } else {
+                Some(entity)
+            };
+        },
+    )
+}
+
+fn switch_session(id: SessionId) -> impl ObserverSystem<Pointer<Click>, (), ()> {
+    IntoSystem::into_system(move |_: Trigger<Pointer<Click>>, mut commands: Commands| {
+        commands.trigger(SwitchSession(id));
+    })
+}
+
+fn add_session(_: Trigger<Pointer<Click>>, mut commands: Commands) {
+    commands.trigger(AddSession);
+}
+
+fn build_navmesh(_: Trigger<Pointer<Click>>, mut commands: Commands) {
+    commands.trigger(BuildNavmesh);
+}
+
+fn step_navmesh(_: Trigger<Pointer<Click>>, mut commands: Commands) {
+    commands.trigger(StepNavmesh);
+}
+
+/// Where the editor saves the built navmesh, relative to the working directory.
+/// Load it in the game with `asset_server.load("navmeshes/navmesh.nav")`.
+const NAVMESH_PATH: &str = "assets/navmeshes/navmesh.nav";
+
+fn save_navmesh(_: Trigger<Pointer<Click>>, navmesh: Option<Res<Navmesh>>) -> Result {
+    let Some(navmesh) = navmesh else {
+        warn!("Build a navmesh before saving it");
+        return Ok(());
+    };
+    let navmesh =
+        bevy_rerecast::Navmesh::new(navmesh.poly_mesh.clone(), navmesh.detail_mesh.clone());
+    let path = std::path::Path::new(NAVMESH_PATH);
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    std::fs::write(path, navmesh.encode()?)?;
+    info!("Saved navmesh to {NAVMESH_PATH}");
+    Ok(())
+}
+
+fn spawn_load_scene_modal(
+    _: Trigger<Pointer<Click>>,
+    sessions: Res<Sessions>,
+    mut commands: Commands,
+) {
+    commands.spawn((
+        Name::new("Backdrop"),
+        Node {
+            width: Percent(100.0),
+            height: Percent(100.0),
+            display: Display::Grid,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        LoadSceneModal,
+        Pickable {
+            should_block_lower: true,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.5)),
+        children![(
+            Name::new("Modal"),
+            Node {
+                min_width: Px(300.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(tailwind::GRAY_300.into()),
+            BorderRadius::all(Px(10.0)),
+            children![
+                (
+                    Name::new("Title Bar"),
+                    Node {
+                        column_gap: Val::Px(5.0),
+                        align_items: AlignItems::Center,
+                        padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::BLACK.with_alpha(0.1)),
+                    children![
+                        modal_title("load-scene-title"),
+                        button(String::from("x"), close_load_scene),
+                    ],
+                ),
+                (
+                    Name::new("Modal Content"),
+                    Node {
+                        padding: UiRect::all(Val::Px(10.0)),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    children![
+                        modal_text(sessions.active().url()),
+                        (
+                            Name::new("Load Button"),
+                            Node { ..default() },
+                            children![button("load-scene-load", load_scene)]
+                        )
+                    ]
+                )
+            ],
+        )],
+    ));
+}
+
+fn modal_title(text: impl Into<UiText>) -> impl Bundle {
+    (
+        Node {
+            flex_grow: 1.0,
+            ..default()
+        },
+        text.into(),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextFont::from_font_size(17.0),
+        TextColor(Color::BLACK),
+    )
+}
+
+fn modal_text(text: impl Into<UiText>) -> impl Bundle {
+    (
+        text.into(),
+        TextFont::from_font_size(15.0),
+        TextColor(tailwind::GRAY_800.into()),
+    )
+}
+
+fn load_scene(_: Trigger<Pointer<Click>>, mut commands: Commands) {
+    commands.trigger(CloseModal);
+    commands.trigger(GetNavmeshInput);
+}
+
+#[derive(Event)]
+struct CloseModal;
+
+fn close_modal(
+    _: Trigger<CloseModal>,
+    mut commands: Commands,
+    modal: Single<Entity, With<LoadSceneModal>>,
+) {
+    commands.entity(*modal).try_despawn();
+}
+
+fn close_load_scene(_: Trigger<Pointer<Click>>, mut commands: Commands) {
+    commands.trigger(CloseModal);
+}
+
+fn status_bar_text(text: impl Into<UiText>) -> impl Bundle {
+    (
+        text.into(),
+        TextFont::from_font_size(15.0),
+        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+    )
+}
+
+fn update_status_bar_version(
+    mut version: Single<&mut UiText, With<StatusBarVersion>>,
+    localization: Res<Localization>,
+) {
+    **version = UiText::Verbatim(localization.format("status-version", &[("version", &"0.1.0")]));
+}
+
+fn toggle_gizmo(gizmo: AvailableGizmos) -> impl ObserverSystem<Pointer<Click>, (), ()> {
+    IntoSystem::into_system(
+        move |_: Trigger<Pointer<Click>>, mut gizmos: ResMut<GizmosToDraw>| {
+            gizmos.toggle(gizmo);
+        },
+    )
+}
+
+fn toggle_seed_from_camera(_: Trigger<Pointer<Click>>, mut seed: ResMut<SeedFromCamera>) {
+    **seed = !**seed;
+}
+
+fn toggle_auto_rebuild(_: Trigger<Pointer<Click>>, mut auto_rebuild: ResMut<AutoRebuild>) {
+    **auto_rebuild = !**auto_rebuild;
+}
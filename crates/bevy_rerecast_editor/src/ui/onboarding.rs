@@ -0,0 +1,379 @@
+//! The wizard shown when the editor starts, so that new users don't land in an empty editor without guidance.
+//!
+//! It probes the active session for a running game and then offers to fetch its scene,
+//! open a navmesh file, or load a sample scene to try the editor without a game.
+
+use std::{path::Path, time::Duration};
+
+use bevy::{
+    color::palettes::tailwind,
+    ecs::system::ObserverSystem,
+    prelude::*,
+    remote::{BrpRequest, builtin_methods::RPC_DISCOVER_METHOD},
+    tasks::{IoTaskPool, Task, block_on, futures_lite::future},
+    ui::Val::*,
+};
+use bevy_rerecast::{
+    NavmeshAffectorAreas,
+    editor_integration::{brp::BRP_GET_NAVMESH_INPUT_METHOD, version::IncompatibleVersions},
+    rerecast::StressScene,
+};
+
+use crate::{
+    get_navmesh_input::{GetNavmeshInput, affector_bundle, affector_mesh, check_game_version},
+    localization::Localization,
+    session::Sessions,
+    theme::widget::button,
+    visualization::{self, VisualMesh},
+};
+
+use super::{NAVMESH_PATH, modal_text, modal_title};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_state::<Onboarding>();
+    app.enable_state_scoped_entities::<Onboarding>();
+    app.add_systems(OnEnter(Onboarding::Probing), (spawn_wizard, start_probe));
+    app.add_systems(
+        Update,
+        (
+            finish_probe.run_if(in_state(Onboarding::Probing)),
+            finish_navmesh_file_picker,
+        ),
+    );
+    for step in [
+        Onboarding::GameFound,
+        Onboarding::IncompatibleGame,
+        Onboarding::MissingIntegration,
+        Onboarding::NoGame,
+    ] {
+        app.add_systems(OnEnter(step), spawn_wizard);
+    }
+}
+
+/// The steps of the wizard. Each step spawns its own modal, which is despawned when the step is left.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+enum Onboarding {
+    /// Waiting for the game of the active session to answer.
+    #[default]
+    Probing,
+    /// A game with the editor integration is running.
+    GameFound,
+    /// A game is running, but its editor integration is too new for the editor. See [`GameIncompatibility`].
+    IncompatibleGame,
+    /// A game is running, but it doesn't know how to send its navmesh input.
+    MissingIntegration,
+    /// Nothing answered.
+    NoGame,
+    /// The wizard was dismissed.
+    Done,
+}
+
+/// How long the probe waits for the game to answer. Local games answer almost instantly,
+/// so this only needs to cover a busy game loading its first level.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Component)]
+struct Probe(Task<(Onboarding, Option<IncompatibleVersions>)>);
+
+/// Why the game found by the last probe cannot be paired with the editor.
+#[derive(Resource)]
+struct GameIncompatibility(IncompatibleVersions);
+
+fn start_probe(sessions: Res<Sessions>, mut commands: Commands) {
+    let url = sessions.active().url();
+    let task = IoTaskPool::get().spawn(async move { probe(&url) });
+    commands.spawn((
+        Name::new("Game Probe"),
+        Probe(task),
+        StateScoped(Onboarding::Probing),
+    ));
+}
+
+/// Asks the game for its remote methods to find out whether it runs the editor integration,
+/// and if it does, whether the editor can read its data.
+fn probe(url: &str) -> (Onboarding, Option<IncompatibleVersions>) {
+    let agent = ureq::Agent::new_with_config(
+        ureq::Agent::config_builder()
+            .timeout_global(Some(PROBE_TIMEOUT))
+            .build(),
+    );
+    let req = BrpRequest {
+        jsonrpc: String::from("2.0"),
+        method: String::from(RPC_DISCOVER_METHOD),
+        id: Some(serde_json::Value::from(1)),
+        params: None,
+    };
+    let Ok(response) = agent
+        .post(url)
+        .send_json(req)
+        .and_then(|mut response| response.body_mut().read_json::<serde_json::Value>())
+    else {
+        return (Onboarding::NoGame, None);
+    };
+    let has_integration = response
+        .pointer("/result/methods")
+        .and_then(serde_json::Value::as_array)
+        .is_some_and(|methods| {
+            methods.iter().any(|method| {
+                method.get("name").and_then(serde_json::Value::as_str)
+                    == Some(BRP_GET_NAVMESH_INPUT_METHOD)
+            })
+        });
+    if !has_integration {
+        return (Onboarding::MissingIntegration, None);
+    }
+    match check_game_version(&agent, url) {
+        Err(err) => match err.downcast_ref::<IncompatibleVersions>() {
+            Some(incompatibility) => (Onboarding::IncompatibleGame, Some(incompatibility.clone())),
+            // Fetching the scene reports any other failure.
+            None => (Onboarding::GameFound, None),
+        },
+        Ok(()) => (Onboarding::GameFound, None),
+    }
+}
+
+fn finish_probe(
+    mut probes: Query<(Entity, &mut Probe)>,
+    mut next: ResMut<NextState<Onboarding>>,
+    mut commands: Commands,
+) {
+    for (entity, mut probe) in &mut probes {
+        if let Some((step, incompatibility)) = block_on(future::poll_once(&mut probe.0)) {
+            // The state only changes on the next frame, and a finished task must not be polled again.
+            commands.entity(entity).despawn();
+            match incompatibility {
+                Some(incompatibility) => {
+                    commands.insert_resource(GameIncompatibility(incompatibility));
+                }
+                None => commands.remove_resource::<GameIncompatibility>(),
+            }
+            next.set(step);
+        }
+    }
+}
+
+fn spawn_wizard(
+    state: Res<State<Onboarding>>,
+    sessions: Res<Sessions>,
+    incompatibility: Option<Res<GameIncompatibility>>,
+    localization: Res<Localization>,
+    mut commands: Commands,
+) {
+    let step = *state.get();
+    let url = sessions.active().url();
+    let key = match step {
+        Onboarding::Probing => "onboarding-probing",
+        Onboarding::GameFound => "onboarding-game-found",
+        Onboarding::IncompatibleGame if incompatibility.is_some() => "onboarding-incompatible-game",
+        Onboarding::IncompatibleGame => "onboarding-unreadable-game",
+        Onboarding::MissingIntegration => "onboarding-missing-integration",
+        Onboarding::NoGame => "onboarding-no-game",
+        Onboarding::Done => return,
+    };
+    let reason = incompatibility.map(|incompatibility| incompatibility.0.to_string());
+    let message = localization.format(
+        key,
+        &[("url", &url), ("reason", &reason.unwrap_or_default())],
+    );
+
+    let wizard = commands
+        .spawn((
+            Name::new("Onboarding Backdrop"),
+            Node {
+                width: Percent(100.0),
+                height: Percent(100.0),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            GlobalZIndex(1),
+            Pickable {
+                should_block_lower: true,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.5)),
+            StateScoped(step),
+        ))
+        .id();
+    let actions = commands
+        .spawn((
+            Name::new("Onboarding Actions"),
+            Node {
+                column_gap: Px(5.0),
+                flex_wrap: FlexWrap::Wrap,
+                ..default()
+            },
+        ))
+        .id();
+    match step {
+        Onboarding::Probing => {}
+        Onboarding::GameFound => {
+            commands.spawn((
+                ChildOf(actions),
+                button("onboarding-fetch-scene", fetch_scene),
+            ));
+        }
+        Onboarding::IncompatibleGame | Onboarding::MissingIntegration | Onboarding::NoGame => {
+            commands.spawn((
+                ChildOf(actions),
+                button("onboarding-retry", go_to(Onboarding::Probing)),
+            ));
+        }
+        Onboarding::Done => unreachable!(),
+    }
+    commands.spawn((
+        ChildOf(actions),
+        button("onboarding-open-navmesh", open_navmesh),
+    ));
+    commands.spawn((
+        ChildOf(actions),
+        button("onboarding-load-sample-scene", load_sample_scene),
+    ));
+    commands.spawn((
+        ChildOf(actions),
+        button("onboarding-skip", go_to(Onboarding::Done)),
+    ));
+
+    let content = commands
+        .spawn((
+            Name::new("Modal Content"),
+            Node {
+                max_width: Px(500.0),
+                padding: UiRect::all(Px(10.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Px(10.0),
+                ..default()
+            },
+            children![modal_text(message)],
+        ))
+        .add_child(actions)
+        .id();
+    commands
+        .spawn((
+            Name::new("Modal"),
+            ChildOf(wizard),
+            Node {
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(tailwind::GRAY_300.into()),
+            BorderRadius::all(Px(10.0)),
+            children![(
+                Name::new("Title Bar"),
+                Node {
+                    padding: UiRect::axes(Px(10.0), Px(5.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::BLACK.with_alpha(0.1)),
+                children![modal_title("onboarding-title")],
+            )],
+        ))
+        .add_child(content);
+}
+
+fn go_to(step: Onboarding) -> impl ObserverSystem<Pointer<Click>, (), ()> {
+    IntoSystem::into_system(
+        move |_: Trigger<Pointer<Click>>, mut next: ResMut<NextState<Onboarding>>| {
+            next.set(step);
+        },
+    )
+}
+
+fn fetch_scene(
+    _: Trigger<Pointer<Click>>,
+    mut next: ResMut<NextState<Onboarding>>,
+    mut commands: Commands,
+) {
+    next.set(Onboarding::Done);
+    commands.trigger(GetNavmeshInput);
+}
+
+#[derive(Component)]
+struct NavmeshFilePicker(Task<Option<rfd::FileHandle>>);
+
+/// Asks for a navmesh file to open, so that it can be inspected without its scene.
+/// The dialog starts in the directory the editor saves navmeshes to.
+fn open_navmesh(
+    _: Trigger<Pointer<Click>>,
+    localization: Res<Localization>,
+    mut commands: Commands,
+) {
+    let mut dialog = rfd::AsyncFileDialog::new()
+        .set_title(localization.get("onboarding-open-navmesh"))
+        .add_filter("Navmesh", &["nav"]);
+    // The dialogs expect an absolute path, which only exists once a navmesh was saved.
+    if let Some(directory) = Path::new(NAVMESH_PATH)
+        .parent()
+        .and_then(|directory| directory.canonicalize().ok())
+    {
+        dialog = dialog.set_directory(directory);
+    }
+    let task = IoTaskPool::get().spawn(dialog.pick_file());
+    commands.spawn((Name::new("Navmesh File Picker"), NavmeshFilePicker(task)));
+}
+
+fn finish_navmesh_file_picker(
+    mut pickers: Query<(Entity, &mut NavmeshFilePicker)>,
+    sessions: Res<Sessions>,
+    mut next: ResMut<NextState<Onboarding>>,
+    mut commands: Commands,
+) -> Result {
+    for (entity, mut picker) in &mut pickers {
+        let Some(file) = block_on(future::poll_once(&mut picker.0)) else {
+            continue;
+        };
+        commands.entity(entity).despawn();
+        // Closing the dialog leaves the wizard open.
+        let Some(file) = file else {
+            continue;
+        };
+        let path = file.path();
+        let mut navmesh = bevy_rerecast::Navmesh::decode(&std::fs::read(path)?)?;
+        let detail_mesh = navmesh.split_detail().unwrap_or_default();
+        commands.insert_resource(visualization::Navmesh {
+            poly_mesh: navmesh.polygon().clone(),
+            detail_mesh,
+            session: sessions.active_id(),
+        });
+        next.set(Onboarding::Done);
+        info!("Opened navmesh from {}", path.display());
+    }
+    Ok(())
+}
+
+/// Loads a small generated building into the active session, standing in for the scene of a game.
+fn load_sample_scene(
+    _: Trigger<Pointer<Click>>,
+    sessions: Res<Sessions>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut gizmos: ResMut<Assets<GizmoAsset>>,
+    mut next: ResMut<NextState<Onboarding>>,
+    mut commands: Commands,
+) {
+    let session = sessions.active_id();
+    let trimesh = StressScene::new(2).build();
+    commands.spawn((
+        Name::new("Sample Scene Affector"),
+        affector_bundle(
+            Transform::default(),
+            meshes.add(affector_mesh(trimesh.clone())),
+            NavmeshAffectorAreas::default(),
+            session,
+            &mut gizmos,
+        ),
+    ));
+
+    let mut visual = affector_mesh(trimesh);
+    visual.duplicate_vertices();
+    visual.compute_flat_normals();
+    commands.spawn((
+        Name::new("Sample Scene Visual"),
+        Mesh3d(meshes.add(visual)),
+        MeshMaterial3d(materials.add(Color::from(tailwind::GRAY_400))),
+        VisualMesh,
+        session,
+    ));
+    next.set(Onboarding::Done);
+}
@@ -8,14 +8,23 @@ use bevy::{
 };
 use bevy_rerecast::{
     TriMeshFromBevyMesh as _,
-    rerecast::{DetailNavmesh, PolygonNavmesh, TriMesh},
+    rerecast::{
+        AreaType, CompactHeightfield, ContourSet, DetailNavmesh, Heightfield, PolygonNavmesh,
+        TriMesh,
+    },
 };
 
-use crate::build::NavmeshAffector;
+use crate::{
+    build::{BuildNavmesh, NavmeshAffector},
+    localization::UiText,
+    session::{SessionId, Sessions},
+    theme::widget::{button_small, label},
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(Startup, spawn_gizmos);
     app.init_resource::<GizmosToDraw>();
+    app.init_resource::<StageView>();
     app.add_systems(
         Update,
         (
@@ -35,14 +44,23 @@ pub(super) fn plugin(app: &mut App) {
             hide_detail_mesh.run_if(toggled_gizmo_off(AvailableGizmos::DetailMesh)),
             hide_affector.run_if(toggled_gizmo_off(AvailableGizmos::Affector)),
             hide_visual.run_if(toggled_gizmo_off(AvailableGizmos::Visual)),
+            hide_navmesh.run_if(resource_removed::<Navmesh>),
+            update_stage_view_text.run_if(resource_changed::<StageView>),
+            draw_selected_stage.run_if(
+                resource_changed::<StageView>
+                    .or(resource_changed_or_removed::<StageSnapshots>)
+                    .or(resource_changed::<Sessions>),
+            ),
         ),
     );
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 pub(crate) struct Navmesh {
     pub(crate) poly_mesh: PolygonNavmesh,
     pub(crate) detail_mesh: DetailNavmesh,
+    /// The session whose scene this navmesh was built for.
+    pub(crate) session: SessionId,
 }
 
 #[derive(Resource, Deref, DerefMut)]
@@ -64,6 +82,8 @@ pub(crate) enum AvailableGizmos {
     Affector,
     PolyMesh,
     DetailMesh,
+    OffMeshLinks,
+    Volumes,
 }
 
 fn toggled_gizmo_on(gizmo: AvailableGizmos) -> impl Condition<()> {
@@ -85,9 +105,14 @@ fn gizmo_enabled(gizmo: AvailableGizmos) -> impl Condition<()> {
 impl Default for GizmosToDraw {
     fn default() -> Self {
         Self(
-            vec![AvailableGizmos::DetailMesh, AvailableGizmos::Visual]
-                .into_iter()
-                .collect(),
+            vec![
+                AvailableGizmos::DetailMesh,
+                AvailableGizmos::Visual,
+                AvailableGizmos::OffMeshLinks,
+                AvailableGizmos::Volumes,
+            ]
+            .into_iter()
+            .collect(),
         )
     }
 }
@@ -126,6 +151,19 @@ fn spawn_gizmos(mut gizmos: ResMut<Assets<GizmoAsset>>, mut commands: Commands)
             depth_bias: -0.001,
         },
     ));
+    commands.spawn((
+        Name::new("Stage View Gizmo"),
+        StageViewGizmo,
+        Gizmo {
+            handle: gizmos.add(GizmoAsset::new()),
+            line_config: GizmoLineConfig {
+                perspective: true,
+                width: 10.0,
+                ..default()
+            },
+            depth_bias: -0.001,
+        },
+    ));
 }
 
 fn draw_poly_mesh(
@@ -284,10 +322,14 @@ fn draw_detail_mesh(
 
 fn draw_navmesh_affector(
     mut gizmos: ResMut<Assets<GizmoAsset>>,
-    affector: Query<(&Mesh3d, &Gizmo), With<NavmeshAffector>>,
+    affector: Query<(&Mesh3d, &Gizmo, &SessionId), With<NavmeshAffector>>,
     meshes: Res<Assets<Mesh>>,
+    sessions: Res<Sessions>,
 ) {
-    for (mesh, gizmo) in &affector {
+    for (mesh, gizmo, session) in &affector {
+        if *session != sessions.active_id() {
+            continue;
+        }
         let Some(gizmo) = gizmos.get_mut(&gizmo.handle) else {
             error!("Failed to get gizmo asset");
             return;
@@ -313,9 +355,14 @@ fn draw_navmesh_affector(
     }
 }
 
-fn draw_visual(mut visibility: Query<&mut Visibility, With<VisualMesh>>) {
-    for mut visibility in visibility.iter_mut() {
-        *visibility = Visibility::Inherited;
+fn draw_visual(
+    mut visibility: Query<(&mut Visibility, &SessionId), With<VisualMesh>>,
+    sessions: Res<Sessions>,
+) {
+    for (mut visibility, session) in visibility.iter_mut() {
+        if *session == sessions.active_id() {
+            *visibility = Visibility::Inherited;
+        }
     }
 }
 
@@ -358,6 +405,20 @@ fn hide_detail_mesh(
     *visibility = Visibility::Hidden;
 }
 
+fn hide_navmesh(
+    gizmo: Query<(&Gizmo, &mut Visibility), Or<(With<PolyMeshGizmo>, With<DetailMeshGizmo>)>>,
+    mut gizmos: ResMut<Assets<GizmoAsset>>,
+) {
+    for (gizmo, mut visibility) in gizmo {
+        let Some(gizmo) = gizmos.get_mut(&gizmo.handle) else {
+            error!("Failed to get gizmo asset");
+            return;
+        };
+        gizmo.clear();
+        *visibility = Visibility::Hidden;
+    }
+}
+
 fn hide_visual(mut visibility: Query<&mut Visibility, With<VisualMesh>>) {
     for mut visibility in visibility.iter_mut() {
         *visibility = Visibility::Hidden;
@@ -366,3 +427,275 @@ fn hide_visual(mut visibility: Query<&mut Visibility, With<VisualMesh>>) {
 
 #[derive(Component)]
 pub(crate) struct VisualMesh;
+
+/// Which intermediate structure of the last build is drawn, to find out at which stage a bad navmesh goes wrong.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum StageView {
+    /// Only the navmesh itself.
+    #[default]
+    Navmesh,
+    /// The walkable and unwalkable spans of the filtered heightfield.
+    Heightfield,
+    /// The spans of the compact heightfield after erosion and marking the volumes, colored by area.
+    CompactHeightfield,
+    /// The distance of each span to the nearest border. Empty unless the regions are partitioned with watershed.
+    DistanceField,
+    /// The region of each span.
+    Regions,
+    /// The contours as traced along the region borders. Empty if they were built with `SKIP_RAW_VERTICES`.
+    RawContours,
+    /// The simplified contours the polygons are built from.
+    Contours,
+}
+
+impl StageView {
+    const ALL: [Self; 7] = [
+        Self::Navmesh,
+        Self::Heightfield,
+        Self::CompactHeightfield,
+        Self::DistanceField,
+        Self::Regions,
+        Self::RawContours,
+        Self::Contours,
+    ];
+
+    fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|view| *view == self)
+            .unwrap_or_default();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// The key of the name of the view in the [`Localization`].
+    fn message(self) -> &'static str {
+        match self {
+            Self::Navmesh => "stage-view-navmesh",
+            Self::Heightfield => "stage-view-heightfield",
+            Self::CompactHeightfield => "stage-view-compact-heightfield",
+            Self::DistanceField => "stage-view-distance-field",
+            Self::Regions => "stage-view-regions",
+            Self::RawContours => "stage-view-raw-contours",
+            Self::Contours => "stage-view-contours",
+        }
+    }
+}
+
+/// The intermediate structures of the last full build, for the [`StageView`].
+/// Keeping them costs a copy of each, so they are only kept while a view other than [`StageView::Navmesh`] is selected.
+#[derive(Resource)]
+pub(crate) struct StageSnapshots {
+    pub(crate) heightfield: Heightfield,
+    pub(crate) compact_heightfield: CompactHeightfield,
+    pub(crate) contours: ContourSet,
+    /// The session whose scene was built.
+    pub(crate) session: SessionId,
+}
+
+#[derive(Component)]
+struct StageViewGizmo;
+
+#[derive(Component)]
+struct StageViewText;
+
+/// The row of the property panel for selecting the [`StageView`].
+pub(crate) fn stage_view_section() -> impl Bundle {
+    (
+        Name::new("Stage View"),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(5.0),
+            ..default()
+        },
+        children![
+            label("stage-view"),
+            (StageViewText, label(String::new())),
+            button_small(String::from(">"), next_stage_view),
+        ],
+    )
+}
+
+/// Selects the next [`StageView`]. Builds the navmesh again if the last build didn't keep its [`StageSnapshots`],
+/// and drops them once the navmesh is shown on its own again.
+fn next_stage_view(
+    _: Trigger<Pointer<Click>>,
+    mut view: ResMut<StageView>,
+    snapshots: Option<Res<StageSnapshots>>,
+    navmesh: Option<Res<Navmesh>>,
+    mut commands: Commands,
+) {
+    *view = view.next();
+    if *view == StageView::Navmesh {
+        commands.remove_resource::<StageSnapshots>();
+    } else if snapshots.is_none() && navmesh.is_some() {
+        commands.trigger(BuildNavmesh);
+    }
+}
+
+fn update_stage_view_text(
+    view: Res<StageView>,
+    mut text: Single<&mut UiText, With<StageViewText>>,
+) {
+    **text = UiText::Message(view.message());
+}
+
+fn draw_selected_stage(
+    view: Res<StageView>,
+    snapshots: Option<Res<StageSnapshots>>,
+    sessions: Res<Sessions>,
+    gizmo: Single<&Gizmo, With<StageViewGizmo>>,
+    mut gizmos: ResMut<Assets<GizmoAsset>>,
+) {
+    let Some(gizmo) = gizmos.get_mut(&gizmo.handle) else {
+        error!("Failed to get gizmo asset");
+        return;
+    };
+    gizmo.clear();
+    let Some(snapshots) = snapshots.filter(|snapshots| snapshots.session == sessions.active_id())
+    else {
+        return;
+    };
+    draw_stage_view(
+        gizmo,
+        *view,
+        Some(&snapshots.heightfield),
+        Some(&snapshots.compact_heightfield),
+        Some(&snapshots.contours),
+    );
+}
+
+/// Draws the structure shown by `view`, if it is available.
+pub(crate) fn draw_stage_view(
+    gizmo: &mut GizmoAsset,
+    view: StageView,
+    heightfield: Option<&Heightfield>,
+    compact_heightfield: Option<&CompactHeightfield>,
+    contours: Option<&ContourSet>,
+) {
+    match view {
+        StageView::Navmesh => {}
+        StageView::Heightfield => {
+            if let Some(heightfield) = heightfield {
+                draw_heightfield(gizmo, heightfield);
+            }
+        }
+        StageView::CompactHeightfield | StageView::DistanceField | StageView::Regions => {
+            if let Some(compact_heightfield) = compact_heightfield {
+                draw_compact_heightfield(gizmo, compact_heightfield, view);
+            }
+        }
+        StageView::RawContours | StageView::Contours => {
+            if let Some(contours) = contours {
+                draw_contours(gizmo, contours, view == StageView::RawContours);
+            }
+        }
+    }
+}
+
+/// Draws the top of every span.
+fn draw_heightfield(gizmo: &mut GizmoAsset, heightfield: &Heightfield) {
+    for z in 0..heightfield.height {
+        for x in 0..heightfield.width {
+            let mut key = heightfield.span_key_at(x, z);
+            while let Some(span_key) = key {
+                let span = heightfield.span(span_key);
+                let corner = heightfield.aabb.min
+                    + Vec3::new(
+                        x as f32 * heightfield.cell_size,
+                        span.max as f32 * heightfield.cell_height,
+                        z as f32 * heightfield.cell_size,
+                    );
+                draw_cell(gizmo, corner, heightfield.cell_size, area_color(span.area));
+                key = span.next;
+            }
+        }
+    }
+}
+
+/// Draws the floor of every span, colored by its distance to the border or its region for the respective views,
+/// and by its area otherwise.
+fn draw_compact_heightfield(
+    gizmo: &mut GizmoAsset,
+    compact_heightfield: &CompactHeightfield,
+    view: StageView,
+) {
+    let max_distance = compact_heightfield.max_distance.max(1) as f32;
+    for z in 0..compact_heightfield.height {
+        for x in 0..compact_heightfield.width {
+            for index in compact_heightfield.cell_at(x, z).index_range() {
+                let span = &compact_heightfield.spans[index];
+                let area = compact_heightfield.areas[index];
+                let color = match view {
+                    StageView::DistanceField if !compact_heightfield.dist.is_empty() => {
+                        let distance = compact_heightfield.dist[index] as f32 / max_distance;
+                        Color::from(tailwind::SLATE_900).mix(&tailwind::AMBER_300.into(), distance)
+                    }
+                    StageView::Regions if span.region.bits() != 0 => {
+                        region_color(span.region.bits())
+                    }
+                    _ => area_color(area),
+                };
+                let corner = compact_heightfield.aabb.min
+                    + Vec3::new(
+                        x as f32 * compact_heightfield.cell_size,
+                        span.y as f32 * compact_heightfield.cell_height,
+                        z as f32 * compact_heightfield.cell_size,
+                    );
+                draw_cell(gizmo, corner, compact_heightfield.cell_size, color);
+            }
+        }
+    }
+}
+
+/// Draws the outline of every contour, either simplified or as traced along the region borders.
+fn draw_contours(gizmo: &mut GizmoAsset, contours: &ContourSet, raw: bool) {
+    let to_world = Vec3::new(contours.cell_size, contours.cell_height, contours.cell_size);
+    for contour in &contours.contours {
+        let vertices = if raw {
+            contour
+                .raw_vertices
+                .iter()
+                .map(|(vertex, _)| *vertex)
+                .collect::<Vec<_>>()
+        } else {
+            contour.vertices.iter().map(|(vertex, _)| *vertex).collect()
+        };
+        let Some(first) = vertices.first().copied() else {
+            continue;
+        };
+        let vertices = vertices
+            .into_iter()
+            .chain([first])
+            .map(|vertex| contours.aabb.min + vertex.as_vec3() * to_world);
+        gizmo.linestrip(vertices, region_color(contour.region.bits()));
+    }
+}
+
+/// Draws the outline of a cell, slightly inset so that neighboring cells stay apart.
+fn draw_cell(gizmo: &mut GizmoAsset, corner: Vec3, cell_size: f32, color: impl Into<Color>) {
+    let inset = cell_size * 0.1;
+    let (min, max) = (inset, cell_size - inset);
+    gizmo.linestrip(
+        [
+            corner + Vec3::new(min, 0.0, min),
+            corner + Vec3::new(max, 0.0, min),
+            corner + Vec3::new(max, 0.0, max),
+            corner + Vec3::new(min, 0.0, max),
+            corner + Vec3::new(min, 0.0, min),
+        ],
+        color,
+    );
+}
+
+fn area_color(area: AreaType) -> Color {
+    if area.is_walkable() {
+        tailwind::GREEN_500.into()
+    } else {
+        tailwind::RED_600.into()
+    }
+}
+
+/// A color that tells neighboring regions apart.
+pub(crate) fn region_color(region: u16) -> Color {
+    Color::hsl((region as f32 * 137.5) % 360.0, 0.7, 0.55)
+}
@@ -0,0 +1,426 @@
+//! Visualization and editing of the navmesh volumes sent by the game.
+//!
+//! Edited volumes only affect the navmeshes built in the editor. The game keeps its own [`NavmeshVolume`]s.
+
+use bevy::{
+    color::palettes::tailwind,
+    ecs::{spawn::SpawnWith, system::ObserverSystem},
+    prelude::*,
+    ui::Val::*,
+};
+use bevy_rerecast::{
+    rerecast::{AreaType, ConvexVolume},
+    volume::NavmeshVolume,
+};
+
+use crate::{
+    localization::{Localization, UiText},
+    session::{SessionId, Sessions},
+    theme::{
+        numeric_input::{NumericInput, NumericInputCommitted},
+        palette::LABEL_TEXT,
+        widget::{button, checkbox, label, numeric_input},
+    },
+    visualization::{AvailableGizmos, GizmosToDraw},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SelectedVolume>();
+    app.add_systems(
+        Update,
+        (
+            draw_volumes.run_if(
+                gizmo_enabled_and_changed
+                    .or(resource_changed::<SelectedVolume>)
+                    .or(resource_changed::<Sessions>),
+            ),
+            hide_volumes.run_if(gizmo_disabled_and_changed),
+            update_volume_list.run_if(
+                resource_changed::<Sessions>
+                    .or(resource_changed::<SelectedVolume>)
+                    .or(resource_changed::<Localization>)
+                    .or(volumes_changed),
+            ),
+            update_volume_inputs.run_if(
+                resource_changed::<SelectedVolume>
+                    .or(volumes_changed)
+                    .or(volume_inputs_changed),
+            ),
+        ),
+    );
+}
+
+/// The volume edited in the volume section.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub(crate) struct SelectedVolume(pub(crate) Option<Entity>);
+
+/// The components of an entity holding a volume of the given session.
+///
+/// The footprint is centered on the entity, so that moving, rotating and scaling the entity affects the volume as a whole.
+pub(crate) fn volume_bundle(
+    volume: ConvexVolume,
+    session: SessionId,
+    gizmos: &mut Assets<GizmoAsset>,
+) -> impl Bundle {
+    let center = volume.vertices.iter().sum::<Vec2>() / volume.vertices.len().max(1) as f32;
+    (
+        Name::new("Navmesh Volume"),
+        Transform::from_xyz(center.x, 0.0, center.y),
+        NavmeshVolume::new(
+            volume
+                .vertices
+                .iter()
+                .map(|vertex| *vertex - center)
+                .collect::<Vec<_>>(),
+            volume.min_y,
+            volume.max_y,
+            volume.area,
+        ),
+        session,
+        Gizmo {
+            handle: gizmos.add(GizmoAsset::new()),
+            line_config: GizmoLineConfig {
+                perspective: true,
+                width: 20.0,
+                ..default()
+            },
+            depth_bias: -0.001,
+        },
+    )
+}
+
+/// The section of the property panel listing the volumes of the active session and editing the selected one.
+pub(crate) fn volume_section() -> impl Bundle {
+    (
+        Name::new("Volumes"),
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Px(4.0),
+            ..default()
+        },
+        Children::spawn(SpawnWith(|parent: &mut ChildSpawner| {
+            parent.spawn(label("volumes"));
+            parent.spawn(checkbox("show-volumes", toggle_volume_gizmo));
+            parent.spawn((
+                Name::new("Volume List"),
+                VolumeList,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Px(5.0),
+                    padding: UiRect::vertical(Px(5.0)),
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                Name::new("Volume Buttons"),
+                Node {
+                    column_gap: Px(5.0),
+                    ..default()
+                },
+                children![
+                    button("volume-add", add_volume),
+                    button("volume-delete", delete_volume),
+                ],
+            ));
+            for input in VolumeInput::ALL {
+                parent.spawn(volume_input_row(input));
+            }
+        })),
+    )
+}
+
+#[derive(Component)]
+struct VolumeList;
+
+/// Which property of the [`SelectedVolume`] a [`NumericInput`] edits.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum VolumeInput {
+    X,
+    Y,
+    Z,
+    Yaw,
+    Scale,
+    MinY,
+    MaxY,
+    Area,
+}
+
+impl VolumeInput {
+    const ALL: [Self; 8] = [
+        Self::X,
+        Self::Y,
+        Self::Z,
+        Self::Yaw,
+        Self::Scale,
+        Self::MinY,
+        Self::MaxY,
+        Self::Area,
+    ];
+
+    /// The key of the label of the input in the [`Localization`].
+    fn message(self) -> &'static str {
+        match self {
+            Self::X => "volume-x",
+            Self::Y => "volume-y",
+            Self::Z => "volume-z",
+            Self::Yaw => "volume-yaw",
+            Self::Scale => "volume-scale",
+            Self::MinY => "volume-min-y",
+            Self::MaxY => "volume-max-y",
+            Self::Area => "volume-area",
+        }
+    }
+
+    fn range(self) -> (f32, f32) {
+        match self {
+            Self::Yaw => (-180.0, 180.0),
+            Self::Scale => (0.01, 1000.0),
+            Self::Area => (0.0, u8::MAX as f32),
+            _ => (f32::MIN, f32::MAX),
+        }
+    }
+
+    fn get(self, transform: &Transform, volume: &NavmeshVolume) -> f32 {
+        match self {
+            Self::X => transform.translation.x,
+            Self::Y => transform.translation.y,
+            Self::Z => transform.translation.z,
+            Self::Yaw => transform.rotation.to_euler(EulerRot::YXZ).0.to_degrees(),
+            Self::Scale => transform.scale.x,
+            Self::MinY => volume.min_y,
+            Self::MaxY => volume.max_y,
+            Self::Area => volume.area.0 as f32,
+        }
+    }
+
+    fn set(self, transform: &mut Transform, volume: &mut NavmeshVolume, value: f32) {
+        match self {
+            Self::X => transform.translation.x = value,
+            Self::Y => transform.translation.y = value,
+            Self::Z => transform.translation.z = value,
+            Self::Yaw => transform.rotation = Quat::from_rotation_y(value.to_radians()),
+            // Only the footprint is scaled, the height is set through the y range.
+            Self::Scale => {
+                transform.scale.x = value;
+                transform.scale.z = value;
+            }
+            Self::MinY => volume.min_y = value.min(volume.max_y),
+            Self::MaxY => volume.max_y = value.max(volume.min_y),
+            Self::Area => volume.area = AreaType(value as u8),
+        }
+    }
+}
+
+fn volume_input_row(input: VolumeInput) -> impl Bundle {
+    (
+        Name::new("Volume Input"),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Px(5.0),
+            ..default()
+        },
+        Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+            parent.spawn((
+                Node {
+                    width: Px(80.0),
+                    ..default()
+                },
+                UiText::from(input.message()),
+                TextFont::from_font_size(14.0),
+                TextColor(LABEL_TEXT),
+            ));
+            let (min, max) = input.range();
+            parent
+                .spawn((numeric_input(min, max, input == VolumeInput::Area), input))
+                .observe(set_volume_input(input));
+        })),
+    )
+}
+
+fn set_volume_input(input: VolumeInput) -> impl ObserverSystem<NumericInputCommitted, (), ()> {
+    IntoSystem::into_system(
+        move |trigger: Trigger<NumericInputCommitted>,
+              selected: Res<SelectedVolume>,
+              mut volumes: Query<(&mut Transform, &mut NavmeshVolume)>| {
+            let Some((mut transform, mut volume)) =
+                selected.and_then(|entity| volumes.get_mut(entity).ok())
+            else {
+                return;
+            };
+            input.set(&mut transform, &mut volume, **trigger.event());
+        },
+    )
+}
+
+/// Adds a box where the camera looks at the ground plane, and selects it.
+fn add_volume(
+    _: Trigger<Pointer<Click>>,
+    camera: Single<&GlobalTransform, With<Camera3d>>,
+    sessions: Res<Sessions>,
+    mut gizmos: ResMut<Assets<GizmoAsset>>,
+    mut selected: ResMut<SelectedVolume>,
+    mut commands: Commands,
+) {
+    let (origin, forward) = (camera.translation(), camera.forward());
+    let center = if forward.y < 0.0 {
+        origin + forward * (-origin.y / forward.y)
+    } else {
+        origin.with_y(0.0)
+    };
+    let volume = NavmeshVolume::rectangle(Vec2::splat(2.0), -1.0, 3.0, AreaType(1))
+        .to_world(&GlobalTransform::from_translation(center));
+    let entity = commands
+        .spawn(volume_bundle(volume, sessions.active_id(), &mut gizmos))
+        .id();
+    **selected = Some(entity);
+}
+
+fn delete_volume(
+    _: Trigger<Pointer<Click>>,
+    mut selected: ResMut<SelectedVolume>,
+    mut commands: Commands,
+) {
+    if let Some(entity) = selected.take() {
+        commands.entity(entity).try_despawn();
+    }
+}
+
+fn toggle_volume_gizmo(_: Trigger<Pointer<Click>>, mut gizmos: ResMut<GizmosToDraw>) {
+    gizmos.toggle(AvailableGizmos::Volumes);
+}
+
+fn volumes_changed(
+    changed: Query<
+        (),
+        Or<(
+            Changed<NavmeshVolume>,
+            (Changed<Transform>, With<NavmeshVolume>),
+        )>,
+    >,
+    mut removed: RemovedComponents<NavmeshVolume>,
+) -> bool {
+    !changed.is_empty() || removed.read().count() > 0
+}
+
+fn volume_inputs_changed(inputs: Query<(), (Changed<NumericInput>, With<VolumeInput>)>) -> bool {
+    !inputs.is_empty()
+}
+
+fn update_volume_list(
+    list: Single<Entity, With<VolumeList>>,
+    volumes: Query<(Entity, &NavmeshVolume, &SessionId)>,
+    sessions: Res<Sessions>,
+    selected: Res<SelectedVolume>,
+    localization: Res<Localization>,
+    mut commands: Commands,
+) {
+    let list = *list;
+    commands.entity(list).despawn_related::<Children>();
+    for (i, (entity, volume, session)) in volumes.iter().enumerate() {
+        if *session != sessions.active_id() {
+            continue;
+        }
+        let marker = if **selected == Some(entity) { ">" } else { " " };
+        let text = localization.format(
+            "volume-entry",
+            &[("marker", &marker), ("index", &i), ("area", &volume.area.0)],
+        );
+        commands.spawn((ChildOf(list), button(text, select_volume(entity))));
+    }
+}
+
+fn select_volume(entity: Entity) -> impl ObserverSystem<Pointer<Click>, (), ()> {
+    IntoSystem::into_system(
+        move |_: Trigger<Pointer<Click>>, mut selected: ResMut<SelectedVolume>| {
+            **selected = if **selected == Some(entity) {
+                None
+            } else {
+                Some(entity)
+            };
+        },
+    )
+}
+
+fn update_volume_inputs(
+    selected: Res<SelectedVolume>,
+    volumes: Query<(&Transform, &NavmeshVolume)>,
+    mut inputs: Query<(&VolumeInput, &NumericInput, &mut Text)>,
+) {
+    let volume = selected.and_then(|entity| volumes.get(entity).ok());
+    for (input, numeric_input, mut text) in &mut inputs {
+        // Don't overwrite what the user is typing.
+        if numeric_input.editing.is_some() {
+            continue;
+        }
+        text.0 = match volume {
+            Some((transform, volume)) if *input == VolumeInput::Area => {
+                format!("{:.0}", input.get(transform, volume))
+            }
+            Some((transform, volume)) => format!("{:.2}", input.get(transform, volume)),
+            None => String::new(),
+        };
+    }
+}
+
+fn gizmo_enabled_and_changed(
+    gizmos: Res<GizmosToDraw>,
+    volumes: Query<(), Changed<NavmeshVolume>>,
+) -> bool {
+    gizmos.contains(&AvailableGizmos::Volumes) && (gizmos.is_changed() || !volumes.is_empty())
+}
+
+fn gizmo_disabled_and_changed(gizmos: Res<GizmosToDraw>) -> bool {
+    gizmos.is_changed() && !gizmos.contains(&AvailableGizmos::Volumes)
+}
+
+/// Draws the volumes in the local space of their entities, so that moving them doesn't need a redraw.
+fn draw_volumes(
+    volumes: Query<(Entity, &NavmeshVolume, &SessionId, &Gizmo)>,
+    mut gizmo_assets: ResMut<Assets<GizmoAsset>>,
+    gizmos: Res<GizmosToDraw>,
+    selected: Res<SelectedVolume>,
+    sessions: Res<Sessions>,
+) {
+    for (entity, volume, session, gizmo) in &volumes {
+        let Some(gizmo) = gizmo_assets.get_mut(&gizmo.handle) else {
+            error!("Failed to get gizmo asset");
+            return;
+        };
+        gizmo.clear();
+        if *session != sessions.active_id() || !gizmos.contains(&AvailableGizmos::Volumes) {
+            continue;
+        }
+        let color = if **selected == Some(entity) {
+            tailwind::YELLOW_400
+        } else if volume.area == AreaType::NOT_WALKABLE {
+            tailwind::RED_600
+        } else {
+            tailwind::SKY_400
+        };
+        let corners = |y: f32| {
+            volume
+                .footprint
+                .iter()
+                .map(move |corner| Vec3::new(corner.x, y, corner.y))
+        };
+        for y in [volume.min_y, volume.max_y] {
+            gizmo.linestrip(corners(y).chain(corners(y).take(1)), color);
+        }
+        for (bottom, top) in corners(volume.min_y).zip(corners(volume.max_y)) {
+            gizmo.line(bottom, top, color);
+        }
+    }
+}
+
+fn hide_volumes(
+    volumes: Query<&Gizmo, With<NavmeshVolume>>,
+    mut gizmo_assets: ResMut<Assets<GizmoAsset>>,
+) {
+    for gizmo in &volumes {
+        let Some(gizmo) = gizmo_assets.get_mut(&gizmo.handle) else {
+            error!("Failed to get gizmo asset");
+            return;
+        };
+        gizmo.clear();
+    }
+}
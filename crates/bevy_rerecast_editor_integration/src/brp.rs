@@ -2,21 +2,30 @@
 
 use bevy_app::prelude::*;
 use bevy_asset::prelude::*;
-use bevy_ecs::prelude::*;
+use bevy_ecs::{component::ComponentId, prelude::*};
 use bevy_image::Image;
+use bevy_math::{Vec2, Vec3, Vec3A};
 use bevy_pbr::{MeshMaterial3d, StandardMaterial};
 use bevy_platform::collections::HashMap;
 use bevy_remote::{BrpError, BrpResult, RemoteMethodSystemId, RemoteMethods};
-use bevy_render::prelude::*;
-use bevy_rerecast_core::NavmeshAffectorBackend;
+use bevy_render::{mesh::MeshAabb as _, prelude::*};
+use bevy_rerecast_core::{
+    NavmeshAffectorArea, NavmeshAffectorAreas, NavmeshAffectorBackends, NavmeshObstruction,
+    fragment::NavmeshFragment, heightmap::HeightmapAffector, volume::NavmeshVolume,
+};
 use bevy_transform::prelude::*;
-use rerecast::TriMesh;
+use regex::Regex;
+use rerecast::{Aabb3d, AreaType, ConvexVolume, OffMeshConnection, TriMesh};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::{
     EditorVisible,
-    transmission::{SerializedImage, SerializedMesh, SerializedStandardMaterial, serialize},
+    transmission::{
+        SerializedImage, SerializedMesh, SerializedMeshV1, SerializedStandardMaterial, Transmitted,
+        decode_payload, serialize,
+    },
+    version::{BRP_GET_VERSION_METHOD, EditorCompatibility, IntegrationVersion},
 };
 
 pub(super) fn plugin(app: &mut App) {
@@ -31,42 +40,157 @@ fn setup_methods(mut methods: ResMut<RemoteMethods>, mut commands: Commands) {
         BRP_GET_NAVMESH_INPUT_METHOD,
         RemoteMethodSystemId::Instant(commands.register_system(get_navmesh_input)),
     );
+    methods.insert(
+        BRP_GET_VERSION_METHOD,
+        RemoteMethodSystemId::Instant(commands.register_system(get_version)),
+    );
 }
 
-fn get_navmesh_input(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+fn get_version(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let game = IntegrationVersion::current();
     if let Some(params) = params {
-        return Err(BrpError {
+        let editor: IntegrationVersion = serde_json::from_value(params).map_err(|e| BrpError {
             code: bevy_remote::error_codes::INVALID_PARAMS,
-            message: format!(
-                "BRP method `{BRP_GET_NAVMESH_INPUT_METHOD}` requires no parameters, but received {params}"
-            ),
+            message: format!("Invalid parameters for BRP method `{BRP_GET_VERSION_METHOD}`: {e}"),
             data: None,
-        });
+        })?;
+        let compatibility = world
+            .get_resource::<EditorCompatibility>()
+            .cloned()
+            .unwrap_or_default();
+        if let Err(err) = compatibility.check(&editor, &game) {
+            tracing::warn!(
+                "The connected editor cannot read the navmesh input of this game: {err}"
+            );
+        }
     }
+    serde_json::to_value(game).map_err(|e| BrpError {
+        code: bevy_remote::error_codes::INTERNAL_ERROR,
+        message: format!("Failed to serialize the integration version: {e}"),
+        data: None,
+    })
+}
 
-    let Some(backend_id) = world.get_resource::<NavmeshAffectorBackend>().cloned() else {
+fn get_navmesh_input(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+    let params = match params {
+        Some(params) => {
+            serde_json::from_value::<NavmeshInputParams>(params).map_err(|e| BrpError {
+                code: bevy_remote::error_codes::INVALID_PARAMS,
+                message: format!(
+                    "Invalid parameters for BRP method `{BRP_GET_NAVMESH_INPUT_METHOD}`: {e}"
+                ),
+                data: None,
+            })?
+        }
+        None => NavmeshInputParams::default(),
+    };
+    let filter = InputFilter::new(params, world)?;
+
+    // The editor only deals in triangles, so heightmaps are sent triangulated.
+    let heightmaps = world
+        .query::<(Entity, &GlobalTransform, &HeightmapAffector)>()
+        .iter(world)
+        .filter(|(_entity, _transform, heightmap)| heightmap.validate().is_ok())
+        .map(|(entity, transform, heightmap)| (entity, *transform, heightmap.to_trimesh()))
+        .collect::<Vec<_>>();
+    let backends = world
+        .get_resource::<NavmeshAffectorBackends>()
+        .map(|backends| backends.enabled().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    if backends.is_empty() && heightmaps.is_empty() {
         return Err(BrpError {
             code: bevy_remote::error_codes::INTERNAL_ERROR,
-            message: "No navmesh affector backend found. Did you forget to add one?".to_string(),
+            message: "No navmesh affector backend enabled. Did you forget to add one?".to_string(),
             data: None,
         });
-    };
-    let affectors = match world.run_system(*backend_id) {
-        Ok(result) => result,
-        Err(err) => {
-            return Err(BrpError {
-                code: bevy_remote::error_codes::INTERNAL_ERROR,
-                message: format!("Navmesh affector backend failed: {err}"),
-                data: None,
-            });
+    }
+    let default_areas = world
+        .get_resource::<NavmeshAffectorAreas>()
+        .copied()
+        .unwrap_or_default();
+    let mut affectors = Vec::new();
+    for backend in backends {
+        match world.run_system(*backend) {
+            Ok(result) => affectors.extend(
+                result
+                    .into_iter()
+                    .map(|(entity, transform, mesh)| (entity, transform, mesh, backend.areas())),
+            ),
+            Err(err) => {
+                return Err(BrpError {
+                    code: bevy_remote::error_codes::INTERNAL_ERROR,
+                    message: format!(
+                        "Navmesh affector backend `{}` failed: {err}",
+                        backend.name()
+                    ),
+                    data: None,
+                });
+            }
         }
-    };
+    }
+    affectors.extend(
+        heightmaps
+            .into_iter()
+            .map(|(entity, transform, mesh)| (entity, transform, mesh, None)),
+    );
     let affectors = affectors
         .into_iter()
-        .map(|(transform, mesh)| AffectorMesh { transform, mesh })
+        .filter(|(entity, transform, mesh, _areas)| {
+            filter.matches_entity(world, *entity)
+                && filter
+                    .matches_bounds(|| mesh.compute_aabb().map(|aabb| world_aabb(transform, aabb)))
+        })
+        .map(|(entity, transform, mesh, areas)| AffectorMesh {
+            transform,
+            mesh,
+            obstruction: world.get::<NavmeshObstruction>(entity).is_some(),
+            areas,
+            area: world.get::<NavmeshAffectorArea>(entity).map(|area| **area),
+            // Affectors of backends that don't return entities have no source.
+            source: (entity != Entity::PLACEHOLDER).then(|| SourceEntity::new(world, entity)),
+        })
+        .collect();
+
+    let off_mesh_links = world
+        .query::<(Entity, &NavmeshFragment)>()
+        .iter(world)
+        .filter(|(entity, _fragment)| filter.matches_entity(world, *entity))
+        .flat_map(|(_entity, fragment)| fragment.links.iter().copied())
+        .filter(|link| {
+            filter.matches_bounds(|| {
+                Some(Aabb3d {
+                    min: link.start.min(link.end),
+                    max: link.start.max(link.end),
+                })
+            })
+        })
+        .collect();
+
+    let volumes = world
+        .query::<(Entity, &NavmeshVolume, &GlobalTransform)>()
+        .iter(world)
+        .filter(|(entity, volume, _transform)| {
+            volume.footprint.len() >= 3 && filter.matches_entity(world, *entity)
+        })
+        .map(|(_entity, volume, transform)| volume.to_world(transform))
+        .filter(|volume| {
+            filter.matches_bounds(|| {
+                let (min, max) = volume
+                    .vertices
+                    .iter()
+                    .fold((Vec2::MAX, Vec2::MIN), |(min, max), vertex| {
+                        (min.min(*vertex), max.max(*vertex))
+                    });
+                Some(Aabb3d {
+                    min: Vec3::new(min.x, volume.min_y, min.y),
+                    max: Vec3::new(max.x, volume.max_y, max.y),
+                })
+            })
+        })
         .collect();
 
     let mut visuals = world.query_filtered::<(
+        Entity,
         &GlobalTransform,
         &Mesh3d,
         &InheritedVisibility,
@@ -103,57 +227,72 @@ fn get_navmesh_input(In(params): In<Option<Value>>, world: &mut World) -> BrpRes
 
     let visuals = visuals
         .iter(world)
-        .filter_map(|(transform, mesh_handle, visibility, material_handle)| {
-            if !matches!(*visibility, InheritedVisibility::VISIBLE) {
-                return None;
-            }
-            let transform = *transform;
-            let mesh_index = if let Some(&index) = mesh_indices.get(&mesh_handle.0) {
-                index
-            } else {
-                let mesh = meshes.get(mesh_handle)?;
-                let index = serialized_meshes.len() as u32;
-                serialized_meshes.push(SerializedMesh::from_mesh(mesh));
-                mesh_indices.insert(mesh_handle.0.clone(), index);
-                index
-            };
-            let material_index = if let Some(material_handle) = material_handle {
-                if let Some(&index) = material_indices.get(&material_handle.0) {
-                    Some(index)
+        .filter_map(
+            |(entity, transform, mesh_handle, visibility, material_handle)| {
+                if !matches!(*visibility, InheritedVisibility::VISIBLE)
+                    || !filter.matches_entity(world, entity)
+                {
+                    return None;
+                }
+                if !filter.matches_bounds(|| {
+                    let aabb = meshes.get(mesh_handle)?.compute_aabb()?;
+                    let aabb = Aabb3d::new(aabb.center, aabb.half_extents);
+                    Some(world_aabb(transform, aabb))
+                }) {
+                    return None;
+                }
+                let transform = *transform;
+                let mesh_index = if let Some(&index) = mesh_indices.get(&mesh_handle.0) {
+                    index
                 } else {
-                    match materials.get(material_handle) {
-                        Some(material) => {
-                            let index = serialized_materials.len() as u32;
-                            match SerializedStandardMaterial::try_from_standard_material(
-                                material.clone(),
-                                &mut image_indices,
-                                images,
-                                &mut serialized_images,
-                            ) {
-                                Ok(serialized_material) => {
-                                    serialized_materials.push(serialized_material);
-                                    material_indices.insert(material_handle.0.clone(), index);
-                                    Some(index)
+                    let mesh = meshes.get(mesh_handle)?;
+                    let index = serialized_meshes.len() as u32;
+                    serialized_meshes.push(SerializedMesh::from_mesh(mesh));
+                    mesh_indices.insert(mesh_handle.0.clone(), index);
+                    index
+                };
+                let material_index = if let Some(material_handle) = material_handle {
+                    if let Some(&index) = material_indices.get(&material_handle.0) {
+                        Some(index)
+                    } else {
+                        match materials.get(material_handle) {
+                            Some(material) => {
+                                let index = serialized_materials.len() as u32;
+                                match SerializedStandardMaterial::try_from_standard_material(
+                                    material.clone(),
+                                    &mut image_indices,
+                                    images,
+                                    &mut serialized_images,
+                                ) {
+                                    Ok(serialized_material) => {
+                                        serialized_materials.push(serialized_material);
+                                        material_indices.insert(material_handle.0.clone(), index);
+                                        Some(index)
+                                    }
+                                    Err(_e) => None,
                                 }
-                                Err(_e) => None,
                             }
+                            None => None,
                         }
-                        None => None,
                     }
-                }
-            } else {
-                None
-            };
-
-            Some(VisualMesh {
-                transform,
-                mesh: mesh_index,
-                material: material_index,
-            })
-        })
+                } else {
+                    None
+                };
+
+                Some(VisualMesh {
+                    transform,
+                    mesh: mesh_index,
+                    material: material_index,
+                    source: Some(SourceEntity::new(world, entity)),
+                })
+            },
+        )
         .collect::<Vec<_>>();
     let response = NavmeshInputResponse {
         affector_meshes: affectors,
+        affector_areas: default_areas,
+        off_mesh_links,
+        volumes,
         visual_meshes: visuals,
         materials: serialized_materials,
         meshes: serialized_meshes,
@@ -168,13 +307,117 @@ fn get_navmesh_input(In(params): In<Option<Value>>, world: &mut World) -> BrpRes
 }
 
 /// The BRP method that the navmesh editor uses to get the navmesh input.
+/// Optionally takes [`NavmeshInputParams`] to only return part of the scene.
 pub const BRP_GET_NAVMESH_INPUT_METHOD: &str = "bevy_rerecast/get_navmesh_input";
 
+/// The optional parameters of [`BRP_GET_NAVMESH_INPUT_METHOD`] requests.
+///
+/// Each filter that is set must match for an entity to be returned. Affectors, visual meshes,
+/// off-mesh links and volumes are all filtered the same way, so a sub-level can be targeted by e.g. giving all its entities a marker component.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NavmeshInputParams {
+    /// A regular expression that the [`Name`] of an entity must match.
+    /// Entities without a [`Name`] never match.
+    pub name: Option<String>,
+    /// The full type path of a component that an entity must have, e.g. `my_game::level::Dungeon`.
+    /// The component must be registered for reflection.
+    pub marker: Option<String>,
+    /// A world-space box that the bounds of a mesh or off-mesh link must intersect.
+    pub aabb: Option<Aabb3d>,
+}
+
+impl NavmeshInputParams {
+    /// Whether no filter is set, i.e. the entire scene is requested.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.marker.is_none() && self.aabb.is_none()
+    }
+}
+
+/// [`NavmeshInputParams`] resolved against the world.
+struct InputFilter {
+    name: Option<Regex>,
+    /// `Some(None)` if the marker is a known type that was never used as a component, so nothing can match.
+    marker: Option<Option<ComponentId>>,
+    aabb: Option<Aabb3d>,
+}
+
+impl InputFilter {
+    fn new(params: NavmeshInputParams, world: &World) -> Result<Self, BrpError> {
+        let invalid_params = |message: String| BrpError {
+            code: bevy_remote::error_codes::INVALID_PARAMS,
+            message,
+            data: None,
+        };
+        let name = params
+            .name
+            .map(|name| Regex::new(&name))
+            .transpose()
+            .map_err(|e| invalid_params(format!("Invalid name filter: {e}")))?;
+        let marker = match params.marker {
+            Some(type_path) => {
+                let registry = world.resource::<AppTypeRegistry>().read();
+                let registration = registry.get_with_type_path(&type_path).ok_or_else(|| {
+                    invalid_params(format!(
+                        "Unknown marker component `{type_path}`. Did you forget to register it?"
+                    ))
+                })?;
+                Some(world.components().get_id(registration.type_id()))
+            }
+            None => None,
+        };
+        Ok(Self {
+            name,
+            marker,
+            aabb: params.aabb,
+        })
+    }
+
+    fn matches_entity(&self, world: &World, entity: Entity) -> bool {
+        if self.name.is_none() && self.marker.is_none() {
+            return true;
+        }
+        let Ok(entity) = world.get_entity(entity) else {
+            return false;
+        };
+        let name_matches = self.name.as_ref().is_none_or(|regex| {
+            entity
+                .get::<Name>()
+                .is_some_and(|name| regex.is_match(name.as_str()))
+        });
+        let marker_matches = self
+            .marker
+            .is_none_or(|marker| marker.is_some_and(|component| entity.contains_id(component)));
+        name_matches && marker_matches
+    }
+
+    /// Only computes the bounds if an AABB filter is set. Items without bounds never match it.
+    fn matches_bounds(&self, bounds: impl FnOnce() -> Option<Aabb3d>) -> bool {
+        self.aabb
+            .is_none_or(|aabb| bounds().is_some_and(|bounds| bounds.intersects(&aabb)))
+    }
+}
+
+/// Transforms a local-space AABB into a world-space AABB that contains it.
+fn world_aabb(transform: &GlobalTransform, aabb: Aabb3d) -> Aabb3d {
+    let affine = transform.affine();
+    let center = affine.transform_point3((aabb.min + aabb.max) * 0.5);
+    let half_size = Vec3::from(affine.matrix3.abs() * Vec3A::from((aabb.max - aabb.min) * 0.5));
+    Aabb3d::new(center, half_size)
+}
+
 /// The response to [`BRP_GET_NAVMESH_INPUT_METHOD`] requests.
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct NavmeshInputResponse {
     /// The meshes that affect the navmesh.
     pub affector_meshes: Vec<AffectorMesh>,
+    /// How the triangles of [`Self::affector_meshes`] without [`AffectorMesh::areas`] are marked before rasterization.
+    pub affector_areas: NavmeshAffectorAreas,
+    /// The off-mesh links of all navmesh fragments, in world space.
+    #[serde(default)]
+    pub off_mesh_links: Vec<OffMeshConnection>,
+    /// The [`NavmeshVolume`]s in world space, in the order they are marked.
+    pub volumes: Vec<ConvexVolume>,
     /// Additional meshes that don't affect the navmesh, but are sent to the editor for visualization.
     pub visual_meshes: Vec<VisualMesh>,
     /// Materials indexed by [`Self::visual_meshes`].
@@ -185,6 +428,289 @@ pub struct NavmeshInputResponse {
     pub images: Vec<SerializedImage>,
 }
 
+impl Transmitted for NavmeshInputResponse {
+    fn decode_legacy(version: u16, payload: &[u8]) -> Result<Self> {
+        match version {
+            0 | 1 => {
+                Ok(decode_payload::<NavmeshInputResponseLegacy<SerializedMeshV1>>(payload)?.into())
+            }
+            2 => Ok(decode_payload::<NavmeshInputResponseLegacy<SerializedMesh>>(payload)?.into()),
+            3 => Ok(decode_payload::<NavmeshInputResponseV3>(payload)?.into()),
+            4 => Ok(decode_payload::<NavmeshInputResponseV4>(payload)?.into()),
+            5 => Ok(decode_payload::<NavmeshInputResponseV5>(payload)?.into()),
+            6 => Ok(decode_payload::<NavmeshInputResponseV6>(payload)?.into()),
+            7 => Ok(decode_payload::<NavmeshInputResponseV7>(payload)?.into()),
+            8 => Ok(decode_payload::<NavmeshInputResponseV8>(payload)?.into()),
+            _ => Err(anyhow::anyhow!("No legacy decoding for schema version {version}").into()),
+        }
+    }
+}
+
+/// [`NavmeshInputResponse`] as transmitted in schema versions 0 to 2, which had no [`NavmeshInputResponse::affector_areas`].
+/// The meshes are [`SerializedMeshV1`] in versions 0 and 1.
+#[derive(Deserialize)]
+struct NavmeshInputResponseLegacy<M> {
+    affector_meshes: Vec<AffectorMeshV3>,
+    #[serde(default)]
+    off_mesh_links: Vec<OffMeshLinkV8>,
+    visual_meshes: Vec<VisualMeshV4>,
+    materials: Vec<SerializedStandardMaterial>,
+    meshes: Vec<M>,
+    images: Vec<SerializedImage>,
+}
+
+impl<M: Into<SerializedMesh>> From<NavmeshInputResponseLegacy<M>> for NavmeshInputResponse {
+    fn from(response: NavmeshInputResponseLegacy<M>) -> Self {
+        Self {
+            affector_meshes: response
+                .affector_meshes
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            affector_areas: NavmeshAffectorAreas::default(),
+            off_mesh_links: response
+                .off_mesh_links
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            volumes: Vec::new(),
+            visual_meshes: response.visual_meshes.into_iter().map(Into::into).collect(),
+            materials: response.materials,
+            meshes: response.meshes.into_iter().map(Into::into).collect(),
+            images: response.images,
+        }
+    }
+}
+
+/// [`NavmeshInputResponse`] as transmitted in schema version 3, which had no [`AffectorMesh::obstruction`].
+#[derive(Deserialize)]
+struct NavmeshInputResponseV3 {
+    affector_meshes: Vec<AffectorMeshV3>,
+    affector_areas: NavmeshAffectorAreas,
+    off_mesh_links: Vec<OffMeshLinkV8>,
+    visual_meshes: Vec<VisualMeshV4>,
+    materials: Vec<SerializedStandardMaterial>,
+    meshes: Vec<SerializedMesh>,
+    images: Vec<SerializedImage>,
+}
+
+impl From<NavmeshInputResponseV3> for NavmeshInputResponse {
+    fn from(response: NavmeshInputResponseV3) -> Self {
+        Self {
+            affector_meshes: response
+                .affector_meshes
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            affector_areas: response.affector_areas,
+            off_mesh_links: response
+                .off_mesh_links
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            volumes: Vec::new(),
+            visual_meshes: response.visual_meshes.into_iter().map(Into::into).collect(),
+            materials: response.materials,
+            meshes: response.meshes,
+            images: response.images,
+        }
+    }
+}
+
+/// [`NavmeshInputResponse`] as transmitted in schema version 4, which had no [`AffectorMesh::source`] and [`VisualMesh::source`].
+#[derive(Deserialize)]
+struct NavmeshInputResponseV4 {
+    affector_meshes: Vec<AffectorMeshV4>,
+    affector_areas: NavmeshAffectorAreas,
+    off_mesh_links: Vec<OffMeshLinkV8>,
+    visual_meshes: Vec<VisualMeshV4>,
+    materials: Vec<SerializedStandardMaterial>,
+    meshes: Vec<SerializedMesh>,
+    images: Vec<SerializedImage>,
+}
+
+impl From<NavmeshInputResponseV4> for NavmeshInputResponse {
+    fn from(response: NavmeshInputResponseV4) -> Self {
+        Self {
+            affector_meshes: response
+                .affector_meshes
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            affector_areas: response.affector_areas,
+            off_mesh_links: response
+                .off_mesh_links
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            volumes: Vec::new(),
+            visual_meshes: response.visual_meshes.into_iter().map(Into::into).collect(),
+            materials: response.materials,
+            meshes: response.meshes,
+            images: response.images,
+        }
+    }
+}
+
+/// [`NavmeshInputResponse`] as transmitted in schema version 5, which had no [`AffectorMesh::area`].
+#[derive(Deserialize)]
+struct NavmeshInputResponseV5 {
+    affector_meshes: Vec<AffectorMeshV5>,
+    affector_areas: NavmeshAffectorAreas,
+    off_mesh_links: Vec<OffMeshLinkV8>,
+    visual_meshes: Vec<VisualMesh>,
+    materials: Vec<SerializedStandardMaterial>,
+    meshes: Vec<SerializedMesh>,
+    images: Vec<SerializedImage>,
+}
+
+impl From<NavmeshInputResponseV5> for NavmeshInputResponse {
+    fn from(response: NavmeshInputResponseV5) -> Self {
+        Self {
+            affector_meshes: response
+                .affector_meshes
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            affector_areas: response.affector_areas,
+            off_mesh_links: response
+                .off_mesh_links
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            volumes: Vec::new(),
+            visual_meshes: response.visual_meshes,
+            materials: response.materials,
+            meshes: response.meshes,
+            images: response.images,
+        }
+    }
+}
+
+/// [`NavmeshInputResponse`] as transmitted in schema version 6, which had no [`NavmeshInputResponse::volumes`].
+#[derive(Deserialize)]
+struct NavmeshInputResponseV6 {
+    affector_meshes: Vec<AffectorMeshV7>,
+    affector_areas: NavmeshAffectorAreas,
+    off_mesh_links: Vec<OffMeshLinkV8>,
+    visual_meshes: Vec<VisualMesh>,
+    materials: Vec<SerializedStandardMaterial>,
+    meshes: Vec<SerializedMesh>,
+    images: Vec<SerializedImage>,
+}
+
+impl From<NavmeshInputResponseV6> for NavmeshInputResponse {
+    fn from(response: NavmeshInputResponseV6) -> Self {
+        Self {
+            affector_meshes: response
+                .affector_meshes
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            affector_areas: response.affector_areas,
+            off_mesh_links: response
+                .off_mesh_links
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            volumes: Vec::new(),
+            visual_meshes: response.visual_meshes,
+            materials: response.materials,
+            meshes: response.meshes,
+            images: response.images,
+        }
+    }
+}
+
+/// [`NavmeshInputResponse`] as transmitted in schema version 7, which had no [`AffectorMesh::areas`].
+#[derive(Deserialize)]
+struct NavmeshInputResponseV7 {
+    affector_meshes: Vec<AffectorMeshV7>,
+    affector_areas: NavmeshAffectorAreas,
+    off_mesh_links: Vec<OffMeshLinkV8>,
+    volumes: Vec<ConvexVolume>,
+    visual_meshes: Vec<VisualMesh>,
+    materials: Vec<SerializedStandardMaterial>,
+    meshes: Vec<SerializedMesh>,
+    images: Vec<SerializedImage>,
+}
+
+impl From<NavmeshInputResponseV7> for NavmeshInputResponse {
+    fn from(response: NavmeshInputResponseV7) -> Self {
+        Self {
+            affector_meshes: response
+                .affector_meshes
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            affector_areas: response.affector_areas,
+            off_mesh_links: response
+                .off_mesh_links
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            volumes: response.volumes,
+            visual_meshes: response.visual_meshes,
+            materials: response.materials,
+            meshes: response.meshes,
+            images: response.images,
+        }
+    }
+}
+
+/// [`NavmeshInputResponse`] as transmitted in schema version 8, whose off-mesh links had no area and flags.
+#[derive(Deserialize)]
+struct NavmeshInputResponseV8 {
+    affector_meshes: Vec<AffectorMesh>,
+    affector_areas: NavmeshAffectorAreas,
+    off_mesh_links: Vec<OffMeshLinkV8>,
+    volumes: Vec<ConvexVolume>,
+    visual_meshes: Vec<VisualMesh>,
+    materials: Vec<SerializedStandardMaterial>,
+    meshes: Vec<SerializedMesh>,
+    images: Vec<SerializedImage>,
+}
+
+impl From<NavmeshInputResponseV8> for NavmeshInputResponse {
+    fn from(response: NavmeshInputResponseV8) -> Self {
+        Self {
+            affector_meshes: response.affector_meshes,
+            affector_areas: response.affector_areas,
+            off_mesh_links: response
+                .off_mesh_links
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            volumes: response.volumes,
+            visual_meshes: response.visual_meshes,
+            materials: response.materials,
+            meshes: response.meshes,
+            images: response.images,
+        }
+    }
+}
+
+/// An off-mesh link as transmitted in schema versions 0 to 8, before links were sent as [`OffMeshConnection`]s.
+#[derive(Deserialize)]
+struct OffMeshLinkV8 {
+    start: Vec3,
+    end: Vec3,
+    radius: f32,
+    bidirectional: bool,
+}
+
+impl From<OffMeshLinkV8> for OffMeshConnection {
+    fn from(link: OffMeshLinkV8) -> Self {
+        Self {
+            start: link.start,
+            end: link.end,
+            radius: link.radius,
+            bidirectional: link.bidirectional,
+            ..Default::default()
+        }
+    }
+}
+
 /// A mesh that affects the navmesh.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AffectorMesh {
@@ -192,6 +718,113 @@ pub struct AffectorMesh {
     pub transform: GlobalTransform,
     /// The mesh data.
     pub mesh: TriMesh,
+    /// Whether the mesh belongs to an entity with a [`NavmeshObstruction`],
+    /// in which case all of its triangles are unwalkable regardless of [`NavmeshInputResponse::affector_areas`].
+    pub obstruction: bool,
+    /// How the backend that returned the mesh marks its triangles, if it has its own areas.
+    /// `None` for heightmaps, backends without their own areas and data from before schema version 8,
+    /// which are marked according to [`NavmeshInputResponse::affector_areas`].
+    pub areas: Option<NavmeshAffectorAreas>,
+    /// The [`NavmeshAffectorArea`] of the entity the mesh belongs to, which overrides
+    /// [`NavmeshAffectorAreas::walkable_area`] of [`Self::areas`] or [`NavmeshInputResponse::affector_areas`].
+    pub area: Option<AreaType>,
+    /// The entity the mesh was collected from. `None` for data from before schema version 5.
+    pub source: Option<SourceEntity>,
+}
+
+impl AffectorMesh {
+    /// How the triangles of the mesh are marked, given the [`NavmeshInputResponse::affector_areas`] of the response it came with.
+    pub fn resolved_areas(&self, default: NavmeshAffectorAreas) -> NavmeshAffectorAreas {
+        self.areas
+            .unwrap_or(default)
+            .with_override(self.area.map(NavmeshAffectorArea))
+    }
+}
+
+/// [`AffectorMesh`] as transmitted in schema versions 0 to 3.
+#[derive(Deserialize)]
+struct AffectorMeshV3 {
+    transform: GlobalTransform,
+    mesh: TriMesh,
+}
+
+impl From<AffectorMeshV3> for AffectorMesh {
+    fn from(affector: AffectorMeshV3) -> Self {
+        Self {
+            transform: affector.transform,
+            mesh: affector.mesh,
+            obstruction: false,
+            areas: None,
+            area: None,
+            source: None,
+        }
+    }
+}
+
+/// [`AffectorMesh`] as transmitted in schema version 4.
+#[derive(Deserialize)]
+struct AffectorMeshV4 {
+    transform: GlobalTransform,
+    mesh: TriMesh,
+    obstruction: bool,
+}
+
+impl From<AffectorMeshV4> for AffectorMesh {
+    fn from(affector: AffectorMeshV4) -> Self {
+        Self {
+            transform: affector.transform,
+            mesh: affector.mesh,
+            obstruction: affector.obstruction,
+            areas: None,
+            area: None,
+            source: None,
+        }
+    }
+}
+
+/// [`AffectorMesh`] as transmitted in schema version 5.
+#[derive(Deserialize)]
+struct AffectorMeshV5 {
+    transform: GlobalTransform,
+    mesh: TriMesh,
+    obstruction: bool,
+    source: Option<SourceEntity>,
+}
+
+impl From<AffectorMeshV5> for AffectorMesh {
+    fn from(affector: AffectorMeshV5) -> Self {
+        Self {
+            transform: affector.transform,
+            mesh: affector.mesh,
+            obstruction: affector.obstruction,
+            areas: None,
+            area: None,
+            source: affector.source,
+        }
+    }
+}
+
+/// [`AffectorMesh`] as transmitted in schema versions 6 and 7.
+#[derive(Deserialize)]
+struct AffectorMeshV7 {
+    transform: GlobalTransform,
+    mesh: TriMesh,
+    obstruction: bool,
+    area: Option<AreaType>,
+    source: Option<SourceEntity>,
+}
+
+impl From<AffectorMeshV7> for AffectorMesh {
+    fn from(affector: AffectorMeshV7) -> Self {
+        Self {
+            transform: affector.transform,
+            mesh: affector.mesh,
+            obstruction: affector.obstruction,
+            areas: None,
+            area: affector.area,
+            source: affector.source,
+        }
+    }
 }
 
 /// A mesh that doesn't affect the navmesh, but is sent to the editor for visualization.
@@ -203,4 +836,162 @@ pub struct VisualMesh {
     pub mesh: u32,
     /// The index of the material in [`NavmeshInputResponse::materials`].
     pub material: Option<u32>,
+    /// The entity the mesh was collected from. `None` for data from before schema version 5.
+    pub source: Option<SourceEntity>,
+}
+
+/// [`VisualMesh`] as transmitted in schema versions 0 to 4.
+#[derive(Deserialize)]
+struct VisualMeshV4 {
+    transform: GlobalTransform,
+    mesh: u32,
+    material: Option<u32>,
+}
+
+impl From<VisualMeshV4> for VisualMesh {
+    fn from(visual: VisualMeshV4) -> Self {
+        Self {
+            transform: visual.transform,
+            mesh: visual.mesh,
+            material: visual.material,
+            source: None,
+        }
+    }
+}
+
+/// The entity in the game that an item of a [`NavmeshInputResponse`] was collected from,
+/// so that the editor can show where a mesh came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceEntity {
+    /// The bits of the entity in the game world, see [`Entity::to_bits`].
+    pub bits: u64,
+    /// The [`Name`] of the entity, if it has one.
+    pub name: Option<String>,
+}
+
+impl SourceEntity {
+    fn new(world: &World, entity: Entity) -> Self {
+        Self {
+            bits: entity.to_bits(),
+            name: world.get::<Name>(entity).map(|name| name.to_string()),
+        }
+    }
+
+    /// The entity in the game world. Only meaningful to the game the response came from.
+    pub fn entity(&self) -> Option<Entity> {
+        Entity::try_from_bits(self.bits).ok()
+    }
+}
+
+impl core::fmt::Display for SourceEntity {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match (&self.name, self.entity()) {
+            (Some(name), Some(entity)) => write!(f, "{name} ({entity})"),
+            (Some(name), None) => write!(f, "{name}"),
+            (None, Some(entity)) => write!(f, "{entity}"),
+            (None, None) => write!(f, "{:#x}", self.bits),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_reflect::prelude::*;
+
+    use super::*;
+
+    #[derive(Component, Reflect)]
+    #[reflect(Component)]
+    struct Dungeon;
+
+    #[test]
+    fn filters_by_name_marker_and_bounds() {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        world
+            .resource::<AppTypeRegistry>()
+            .write()
+            .register::<Dungeon>();
+        let cellar = world.spawn((Name::new("Cellar Floor"), Dungeon)).id();
+        let attic = world.spawn(Name::new("Attic Floor")).id();
+
+        let filter = InputFilter::new(
+            NavmeshInputParams {
+                name: Some("Floor$".to_string()),
+                marker: Some(std::any::type_name::<Dungeon>().to_string()),
+                aabb: Some(Aabb3d::new(Vec3::ZERO, Vec3::ONE)),
+            },
+            &world,
+        )
+        .unwrap();
+        assert!(filter.matches_entity(&world, cellar));
+        assert!(!filter.matches_entity(&world, attic));
+
+        let transform = GlobalTransform::from_translation(Vec3::new(3.0, 0.0, 0.0));
+        let unit = Aabb3d::new(Vec3::ZERO, Vec3::splat(0.5));
+        assert_eq!(
+            world_aabb(&transform, unit),
+            Aabb3d::new(Vec3::new(3.0, 0.0, 0.0), Vec3::splat(0.5))
+        );
+        assert!(!filter.matches_bounds(|| Some(world_aabb(&transform, unit))));
+        assert!(filter.matches_bounds(|| Some(unit)));
+        assert!(!filter.matches_bounds(|| None));
+
+        let unknown_marker = NavmeshInputParams {
+            marker: Some("my_game::Unknown".to_string()),
+            ..Default::default()
+        };
+        assert!(InputFilter::new(unknown_marker, &world).is_err());
+    }
+
+    #[test]
+    fn decodes_off_mesh_links_of_schema_8() {
+        #[derive(Serialize)]
+        struct LinkV8 {
+            start: Vec3,
+            end: Vec3,
+            radius: f32,
+            bidirectional: bool,
+        }
+
+        #[derive(Serialize)]
+        struct ResponseV8 {
+            affector_meshes: Vec<AffectorMesh>,
+            affector_areas: NavmeshAffectorAreas,
+            off_mesh_links: Vec<LinkV8>,
+            volumes: Vec<ConvexVolume>,
+            visual_meshes: Vec<VisualMesh>,
+            materials: Vec<SerializedStandardMaterial>,
+            meshes: Vec<SerializedMesh>,
+            images: Vec<SerializedImage>,
+        }
+
+        let legacy = ResponseV8 {
+            affector_meshes: Vec::new(),
+            affector_areas: NavmeshAffectorAreas::default(),
+            off_mesh_links: vec![LinkV8 {
+                start: Vec3::ZERO,
+                end: Vec3::new(1.0, 2.0, 3.0),
+                radius: 0.3,
+                bidirectional: false,
+            }],
+            volumes: Vec::new(),
+            visual_meshes: Vec::new(),
+            materials: Vec::new(),
+            meshes: Vec::new(),
+            images: Vec::new(),
+        };
+        let payload = bincode::serde::encode_to_vec(&legacy, bincode::config::standard()).unwrap();
+        let response = NavmeshInputResponse::decode_legacy(8, &payload).unwrap();
+        assert_eq!(
+            response.off_mesh_links,
+            vec![OffMeshConnection {
+                start: Vec3::ZERO,
+                end: Vec3::new(1.0, 2.0, 3.0),
+                radius: 0.3,
+                bidirectional: false,
+                ..Default::default()
+            }]
+        );
+    }
 }
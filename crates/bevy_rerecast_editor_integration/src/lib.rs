@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 
 pub mod brp;
 pub mod transmission;
+pub mod version;
 
 /// The optional editor integration for authoring the navmesh.
 #[derive(Debug, Default)]
@@ -21,6 +22,7 @@ impl Plugin for RerecastEditorIntegrationPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(brp::plugin);
         app.register_type::<EditorVisible>();
+        app.init_resource::<version::EditorCompatibility>();
         match self.visibility_settings {
             EditorVisibilitySettings::AllMeshes => {
                 app.add_observer(insert_editor_visible_to_meshes);
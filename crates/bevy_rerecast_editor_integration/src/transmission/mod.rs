@@ -1,10 +1,12 @@
 //! Types and functions needed for transmitting data between the editor and the running game.
 
+mod payload_sizes;
 mod serialization;
 mod serialized_image;
 mod serialized_mesh;
 mod serialized_standard_material;
 
+pub use payload_sizes::*;
 pub use serialization::*;
 pub use serialized_image::*;
 pub use serialized_mesh::*;
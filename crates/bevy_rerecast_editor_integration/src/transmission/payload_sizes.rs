@@ -0,0 +1,261 @@
+//! Measuring which parts of a [`NavmeshInputResponse`] take up the most space, so that users can see why fetching
+//! the navmesh input is slow and which entities are worth excluding from it.
+
+use bevy_platform::collections::{HashMap, HashSet};
+use bincode::{enc::write::SizeWriter, error::EncodeError};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::brp::{NavmeshInputResponse, SourceEntity};
+
+/// The sizes of the parts of a [`NavmeshInputResponse`] as encoded by [`serialize`](crate::transmission::serialize)
+/// before compression. `[Units: bytes]`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PayloadSizes {
+    /// The size of the compressed and base64-encoded payload that was actually transmitted.
+    pub transmitted: usize,
+    /// The size of the items of [`NavmeshInputResponse::affector_meshes`].
+    pub affector_meshes: usize,
+    /// The size of the items of [`NavmeshInputResponse::meshes`].
+    pub meshes: usize,
+    /// The size of the items of [`NavmeshInputResponse::materials`].
+    pub materials: usize,
+    /// The size of the items of [`NavmeshInputResponse::images`].
+    pub images: usize,
+    /// The size of everything else, e.g. the visual mesh instances, volumes, off-mesh links and the lengths of the lists.
+    pub other: usize,
+    /// The size of the meshes, materials and images of each entity, largest first.
+    pub entities: Vec<EntityPayloadSize>,
+}
+
+/// How much of a [`NavmeshInputResponse`] was collected from a single entity. `[Units: bytes]`
+///
+/// Meshes, materials and images shared by several entities are only counted for the first one referencing them,
+/// so that the sizes of all entities add up to the size of the payload.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntityPayloadSize {
+    /// The entity in the game. `None` for everything from games that don't send the source of the input.
+    pub source: Option<SourceEntity>,
+    /// The size of the affector meshes of the entity.
+    pub affector_meshes: usize,
+    /// The size of the visual meshes of the entity.
+    pub meshes: usize,
+    /// The size of the materials of the entity.
+    pub materials: usize,
+    /// The size of the images of the materials of the entity.
+    pub images: usize,
+}
+
+impl PayloadSizes {
+    /// Measures the parts of `response`, which was decoded from the `transmitted` value.
+    pub fn measure(
+        response: &NavmeshInputResponse,
+        transmitted: &Value,
+    ) -> Result<Self, EncodeError> {
+        let mut entities = Vec::<EntityPayloadSize>::new();
+        let mut entity_indices = HashMap::new();
+        let mut entity = |source: &Option<SourceEntity>| {
+            let key = source.as_ref().map(|source| source.bits);
+            *entity_indices.entry(key).or_insert_with(|| {
+                entities.push(EntityPayloadSize {
+                    source: source.clone(),
+                    ..Default::default()
+                });
+                entities.len() - 1
+            })
+        };
+
+        let mut sizes = Self {
+            transmitted: transmitted.as_str().map_or(0, str::len),
+            ..Default::default()
+        };
+        let mut affector_sizes = Vec::new();
+        for affector in &response.affector_meshes {
+            let size = encoded_size(affector)?;
+            sizes.affector_meshes += size;
+            affector_sizes.push((entity(&affector.source), size));
+        }
+
+        let mut counted_meshes = HashSet::new();
+        let mut counted_materials = HashSet::new();
+        let mut counted_images = HashSet::new();
+        let mut visual_sizes = Vec::new();
+        for visual in &response.visual_meshes {
+            let index = entity(&visual.source);
+            let mut size = EntityPayloadSize::default();
+            let mesh = Some(visual.mesh)
+                .filter(|mesh| counted_meshes.insert(*mesh))
+                .and_then(|mesh| response.meshes.get(mesh as usize));
+            if let Some(mesh) = mesh {
+                size.meshes += encoded_size(mesh)?;
+            }
+            let material = visual
+                .material
+                .filter(|material| counted_materials.insert(*material))
+                .and_then(|material| response.materials.get(material as usize));
+            if let Some(material) = material {
+                size.materials += encoded_size(material)?;
+                let images = material
+                    .images()
+                    .filter(|image| counted_images.insert(*image))
+                    .filter_map(|image| response.images.get(image as usize));
+                for image in images {
+                    size.images += encoded_size(image)?;
+                }
+            }
+            visual_sizes.push((index, size));
+        }
+
+        for (index, size) in affector_sizes {
+            entities[index].affector_meshes += size;
+        }
+        for (index, size) in visual_sizes {
+            let entity = &mut entities[index];
+            entity.meshes += size.meshes;
+            entity.materials += size.materials;
+            entity.images += size.images;
+        }
+        entities.retain(|entity| entity.total() > 0);
+        entities.sort_by_key(|entity| core::cmp::Reverse(entity.total()));
+        sizes.entities = entities;
+
+        sizes.meshes = total_encoded_size(&response.meshes)?;
+        sizes.materials = total_encoded_size(&response.materials)?;
+        sizes.images = total_encoded_size(&response.images)?;
+        sizes.other = encoded_size(response)?
+            - sizes.affector_meshes
+            - sizes.meshes
+            - sizes.materials
+            - sizes.images;
+        Ok(sizes)
+    }
+
+    /// The size of the payload before compression.
+    pub fn uncompressed(&self) -> usize {
+        self.affector_meshes + self.meshes + self.materials + self.images + self.other
+    }
+}
+
+impl EntityPayloadSize {
+    /// The size of everything collected from the entity.
+    pub fn total(&self) -> usize {
+        self.affector_meshes + self.meshes + self.materials + self.images
+    }
+}
+
+fn encoded_size<T: Serialize>(value: &T) -> Result<usize, EncodeError> {
+    let mut writer = SizeWriter::default();
+    bincode::serde::encode_into_writer(value, &mut writer, bincode::config::standard())?;
+    Ok(writer.bytes_written)
+}
+
+fn total_encoded_size<T: Serialize>(values: &[T]) -> Result<usize, EncodeError> {
+    values.iter().map(encoded_size).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::{Assets, RenderAssetUsages};
+    use bevy_image::Image;
+    use bevy_pbr::StandardMaterial;
+    use bevy_render::mesh::{Mesh, PrimitiveTopology};
+    use bevy_transform::components::GlobalTransform;
+    use rerecast::TriMesh;
+
+    use super::*;
+    use crate::{
+        brp::{AffectorMesh, VisualMesh},
+        transmission::{SerializedMesh, SerializedStandardMaterial, serialize},
+    };
+
+    fn source(bits: u64) -> Option<SourceEntity> {
+        Some(SourceEntity { bits, name: None })
+    }
+
+    fn visual(mesh: u32, material: Option<u32>, source: Option<SourceEntity>) -> VisualMesh {
+        VisualMesh {
+            transform: GlobalTransform::IDENTITY,
+            mesh,
+            material,
+            source,
+        }
+    }
+
+    #[test]
+    fn attributes_shared_assets_to_the_first_entity() {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0_f32; 3]; 300]);
+
+        let mut images = Assets::<Image>::default();
+        let material = StandardMaterial {
+            base_color_texture: Some(images.add(Image::default())),
+            ..Default::default()
+        };
+        let mut image_indices = HashMap::new();
+        let mut serialized_images = Vec::new();
+        let material = SerializedStandardMaterial::try_from_standard_material(
+            material,
+            &mut image_indices,
+            &images,
+            &mut serialized_images,
+        )
+        .unwrap();
+        assert_eq!(material.images().collect::<Vec<_>>(), vec![0]);
+
+        let response = NavmeshInputResponse {
+            affector_meshes: vec![AffectorMesh {
+                transform: GlobalTransform::IDENTITY,
+                mesh: TriMesh::default(),
+                obstruction: false,
+                areas: None,
+                area: None,
+                source: source(3),
+            }],
+            visual_meshes: vec![
+                visual(0, None, source(1)),
+                visual(0, Some(0), source(2)),
+                visual(1, Some(0), source(2)),
+            ],
+            meshes: vec![
+                SerializedMesh::from_mesh(&mesh),
+                SerializedMesh::from_mesh(&Mesh::new(
+                    PrimitiveTopology::TriangleList,
+                    RenderAssetUsages::all(),
+                )),
+            ],
+            materials: vec![material],
+            images: serialized_images,
+            ..Default::default()
+        };
+        let transmitted = serialize(&response).unwrap();
+        let sizes = PayloadSizes::measure(&response, &transmitted).unwrap();
+
+        assert_eq!(sizes.uncompressed(), encoded_size(&response).unwrap());
+        assert!(sizes.transmitted > 0);
+        assert!(sizes.transmitted < sizes.uncompressed());
+        assert_eq!(
+            sizes
+                .entities
+                .iter()
+                .map(EntityPayloadSize::total)
+                .sum::<usize>(),
+            sizes.affector_meshes + sizes.meshes + sizes.materials + sizes.images
+        );
+
+        // The first entity referencing the large mesh gets its size.
+        assert_eq!(sizes.entities[0].source, source(1));
+        assert_eq!(
+            sizes.entities[0].meshes,
+            encoded_size(&response.meshes[0]).unwrap()
+        );
+        let second = &sizes.entities[1];
+        assert_eq!(second.source, source(2));
+        assert_eq!(second.meshes, encoded_size(&response.meshes[1]).unwrap());
+        assert_eq!(
+            second.materials,
+            encoded_size(&response.materials[0]).unwrap()
+        );
+        assert_eq!(second.images, encoded_size(&response.images[0]).unwrap());
+        assert_eq!(sizes.entities[2].source, source(3));
+    }
+}
@@ -1,4 +1,8 @@
 //! Serialization and deserialization of data for the editor integration.
+//!
+//! Every value is prefixed with [`SCHEMA_VERSION`], so that an editor and a game built against
+//! different versions of the editor integration notice the mismatch instead of silently decoding garbage.
+//! Data from before the schema was versioned is decoded as schema version 0.
 
 use std::io::{Read as _, Write};
 
@@ -8,10 +12,44 @@ use bevy_ecs::prelude::*;
 use flate2::{Compression, read::GzDecoder, write::GzEncoder};
 use serde::{Serialize, de::DeserializeOwned};
 use serde_json::Value;
+use thiserror::Error;
+
+/// The version of the schema of all transmission types, i.e. the [`NavmeshInputResponse`](crate::brp::NavmeshInputResponse)
+/// and everything it contains.
+///
+/// Must be bumped whenever the serialized layout of any of these types changes, including the order of enum variants.
+///
+/// Version history:
+/// - 0: Unversioned data, as sent before the schema was versioned. Same layout as version 1.
+/// - 1: Added the version header.
+/// - 2: Custom vertex attributes in [`SerializedMesh`](crate::transmission::SerializedMesh).
+/// - 3: The affector areas of the backend in [`NavmeshInputResponse`](crate::brp::NavmeshInputResponse).
+/// - 4: Whether each [`AffectorMesh`](crate::brp::AffectorMesh) is an obstruction.
+/// - 5: The [`SourceEntity`](crate::brp::SourceEntity) of every affector and visual mesh.
+/// - 6: The area override of each [`AffectorMesh`](crate::brp::AffectorMesh).
+/// - 7: The navmesh volumes in [`NavmeshInputResponse`](crate::brp::NavmeshInputResponse).
+/// - 8: The areas of the backend of each [`AffectorMesh`](crate::brp::AffectorMesh).
+/// - 9: Off-mesh links as [`OffMeshConnection`](rerecast::OffMeshConnection)s, including their area and flags.
+pub const SCHEMA_VERSION: u16 = 9;
+
+/// Marks data that starts with a schema version.
+const MAGIC: [u8; 4] = *b"RRCT";
+
+/// A type that is transmitted between the editor and the game.
+pub trait Transmitted: Serialize + DeserializeOwned {
+    /// Decodes data that was serialized with an older schema version.
+    /// Only called for versions below [`SCHEMA_VERSION`].
+    fn decode_legacy(version: u16, payload: &[u8]) -> Result<Self>;
+}
 
 /// Serializes a value to a JSON value in the format expected by the editor integration.
 pub fn serialize<T: Serialize>(val: &T) -> Result<Value> {
-    let bincode_bytes = bincode::serde::encode_to_vec(val, bincode::config::standard())?;
+    let mut bincode_bytes = Vec::from(MAGIC);
+    bincode_bytes.extend(SCHEMA_VERSION.to_le_bytes());
+    bincode_bytes.extend(bincode::serde::encode_to_vec(
+        val,
+        bincode::config::standard(),
+    )?);
     let mut gz = GzEncoder::new(Vec::new(), Compression::default());
     gz.write_all(&bincode_bytes)?;
     let gz_bytes = gz.finish()?;
@@ -20,14 +58,107 @@ pub fn serialize<T: Serialize>(val: &T) -> Result<Value> {
 }
 
 /// Deserializes a JSON value in the format expected by the editor integration to a value.
-pub fn deserialize<T: DeserializeOwned>(value: &Value) -> Result<T> {
+///
+/// Data in an older schema version is decoded with a warning through [`Transmitted::decode_legacy`].
+/// Data in a newer schema version fails with a [`SchemaVersionError`].
+pub fn deserialize<T: Transmitted>(value: &Value) -> Result<T> {
     let value_string = value.as_str().context("Expected a string")?;
     let gz_bytes = BASE64_STANDARD.decode(value_string)?;
 
     let mut gz = GzDecoder::new(&gz_bytes[..]);
     let mut bincode_bytes = Vec::new();
     gz.read_to_end(&mut bincode_bytes)?;
+
+    let (version, payload) = match bincode_bytes.strip_prefix(&MAGIC) {
+        Some(rest) => {
+            let (version, payload) = rest
+                .split_first_chunk::<2>()
+                .context("Data ends before its schema version")?;
+            (u16::from_le_bytes(*version), payload)
+        }
+        None => (0, &bincode_bytes[..]),
+    };
+    if version > SCHEMA_VERSION {
+        return Err(SchemaVersionError { version }.into());
+    }
+    if version < SCHEMA_VERSION {
+        tracing::warn!(
+            "Received data in schema version {version} from an older version of the editor integration. \
+            Decoding it anyways, but consider updating both sides to the same version."
+        );
+        return T::decode_legacy(version, payload);
+    }
+    decode_payload(payload)
+}
+
+/// Decodes the bincode payload that follows the header.
+pub(crate) fn decode_payload<T: DeserializeOwned>(payload: &[u8]) -> Result<T> {
     let (val, _len): (T, usize) =
-        bincode::serde::decode_from_slice(&bincode_bytes, bincode::config::standard())?;
+        bincode::serde::decode_from_slice(payload, bincode::config::standard())?;
     Ok(val)
 }
+
+/// Happens when data was serialized with a schema version that this version of the editor integration cannot decode.
+#[derive(Error, Debug)]
+#[error(
+    "Received data in schema version {version}, but only versions up to {SCHEMA_VERSION} are supported. \
+    Make sure the editor and the game use the same version of the editor integration."
+)]
+pub struct SchemaVersionError {
+    /// The schema version of the received data.
+    pub version: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    /// Stands in for a type whose layout changed from a `u32` list in version 1 to a `u64` list.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Numbers(Vec<u64>);
+
+    impl Transmitted for Numbers {
+        fn decode_legacy(_version: u16, payload: &[u8]) -> Result<Self> {
+            let numbers: Vec<u32> = decode_payload(payload)?;
+            Ok(Self(numbers.into_iter().map(u64::from).collect()))
+        }
+    }
+
+    fn encode(bincode_bytes: &[u8]) -> Value {
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(bincode_bytes).unwrap();
+        Value::String(BASE64_STANDARD.encode(gz.finish().unwrap()))
+    }
+
+    #[test]
+    fn decodes_current_and_legacy_schema() {
+        let value = Numbers(vec![1, 2, u64::MAX]);
+        let current = serialize(&value).unwrap();
+        assert_eq!(deserialize::<Numbers>(&current).unwrap(), value);
+
+        let legacy_value = vec![1_u32, 2, 3];
+        let expected = Numbers(vec![1, 2, 3]);
+        let legacy = encode(
+            &bincode::serde::encode_to_vec(&legacy_value, bincode::config::standard()).unwrap(),
+        );
+        assert_eq!(deserialize::<Numbers>(&legacy).unwrap(), expected);
+
+        let mut bytes = Vec::from(MAGIC);
+        bytes.extend(1_u16.to_le_bytes());
+        bytes.extend(
+            bincode::serde::encode_to_vec(&legacy_value, bincode::config::standard()).unwrap(),
+        );
+        assert_eq!(deserialize::<Numbers>(&encode(&bytes)).unwrap(), expected);
+    }
+
+    #[test]
+    fn rejects_newer_schema() {
+        let mut bytes = Vec::from(MAGIC);
+        bytes.extend((SCHEMA_VERSION + 1).to_le_bytes());
+        bytes.extend(bincode::serde::encode_to_vec([0_u64], bincode::config::standard()).unwrap());
+        let error = deserialize::<Numbers>(&encode(&bytes)).unwrap_err();
+        assert!(error.downcast_ref::<SchemaVersionError>().is_some());
+    }
+}
@@ -3,19 +3,26 @@
 
 use bevy_asset::RenderAssetUsages;
 use bevy_derive::{Deref, DerefMut};
+use bevy_ecs::prelude::*;
+use bevy_platform::collections::HashMap;
 use bevy_reflect::prelude::*;
+
 use bevy_render::mesh::{
-    Indices, Mesh, MeshVertexAttributeId, PrimitiveTopology, VertexAttributeValues,
+    Indices, Mesh, MeshVertexAttribute, MeshVertexAttributeId, PrimitiveTopology,
+    VertexAttributeValues, VertexFormat,
 };
 use serde::{Deserialize, Serialize};
 
 /// Serialized version of [`Mesh`].
+///
+/// Custom vertex attributes are preserved along with their name.
+/// Their format is implied by the variant of their values.
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
 #[reflect(Serialize, Deserialize)]
 pub struct SerializedMesh {
     primitive_topology: SerializedPrimitiveTopology,
     attributes: Vec<(
-        SerializedMeshVertexAttributeId,
+        SerializedMeshVertexAttribute,
         SerializedVertexAttributeValues,
     )>,
     indices: Option<SerializedIndices>,
@@ -24,19 +31,23 @@ pub struct SerializedMesh {
 impl SerializedMesh {
     /// Serializes a [`Mesh`] to a [`SerializedMesh`].
     pub fn from_mesh(mesh: &Mesh) -> Self {
+        let mut custom_attributes = 0;
         SerializedMesh {
             primitive_topology: mesh.primitive_topology().into(),
             attributes: mesh
                 .attributes()
-                .filter_map(|(attribute, values)| {
-                    let Some(id) = attribute.id.try_into().ok() else {
-                        tracing::warn!(
-                            "Failed to serialize mesh: unknown attribute id: {:?}",
-                            attribute.id
-                        );
-                        return None;
+                .map(|(attribute, values)| {
+                    let attribute = match attribute.id.try_into() {
+                        Ok(id) => SerializedMeshVertexAttribute::Builtin(id),
+                        Err(()) => {
+                            custom_attributes += 1;
+                            SerializedMeshVertexAttribute::Custom {
+                                name: attribute.name.to_string(),
+                                id: custom_attributes - 1,
+                            }
+                        }
                     };
-                    Some((id, values.clone().into()))
+                    (attribute, values.clone().into())
                 })
                 .collect(),
             indices: mesh.indices().cloned().map(|indices| indices.into()),
@@ -44,31 +55,31 @@ impl SerializedMesh {
     }
 
     /// Deserializes a [`SerializedMesh`] to a [`Mesh`].
+    ///
+    /// Custom attributes get ids that are only known to this mesh.
+    /// Use [`SerializedMesh::into_mesh_with`] to give them the same ids in every mesh.
     pub fn into_mesh(self) -> Mesh {
+        self.into_mesh_with(&mut CustomMeshAttributes::default())
+    }
+
+    /// Deserializes a [`SerializedMesh`] to a [`Mesh`], looking up the ids of custom attributes by name in `custom_attributes`.
+    pub fn into_mesh_with(self, custom_attributes: &mut CustomMeshAttributes) -> Mesh {
         let mut mesh = Mesh::new(self.primitive_topology.into(), RenderAssetUsages::all());
-        let attributes = [
-            Mesh::ATTRIBUTE_POSITION,
-            Mesh::ATTRIBUTE_NORMAL,
-            Mesh::ATTRIBUTE_UV_0,
-            Mesh::ATTRIBUTE_UV_1,
-            Mesh::ATTRIBUTE_TANGENT,
-            Mesh::ATTRIBUTE_COLOR,
-            Mesh::ATTRIBUTE_JOINT_WEIGHT,
-            Mesh::ATTRIBUTE_JOINT_INDEX,
-        ];
         for (attribute, values) in self.attributes {
-            // Safety: this is just a newtype wrapper around a u64, so we can safely transmute it
-            let attribute_id: MeshVertexAttributeId = unsafe { std::mem::transmute(attribute) };
-            let Some(attribute) = attributes
-                .iter()
-                .find(|attribute| attribute.id == attribute_id)
+            let values = VertexAttributeValues::from(values);
+            let Some(attribute) = attribute.clone().into_attribute(&values, custom_attributes)
             else {
+                tracing::warn!("Failed to deserialize mesh: unknown attribute: {attribute:?}");
+                continue;
+            };
+            if mesh.contains_attribute(attribute.id) {
                 tracing::warn!(
-                    "Failed to deserialize mesh: unknown attribute id: {attribute_id:?}"
+                    "Failed to deserialize mesh: more than one attribute is named {}",
+                    attribute.name
                 );
                 continue;
-            };
-            mesh.insert_attribute(*attribute, values);
+            }
+            mesh.insert_attribute(attribute, values);
         }
         if let Some(indices) = self.indices {
             mesh.insert_indices(indices.into());
@@ -77,6 +88,114 @@ impl SerializedMesh {
     }
 }
 
+/// [`SerializedMesh`] as transmitted in schema versions 0 and 1, which only supported the builtin attributes.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct SerializedMeshV1 {
+    primitive_topology: SerializedPrimitiveTopology,
+    attributes: Vec<(
+        SerializedMeshVertexAttributeId,
+        SerializedVertexAttributeValues,
+    )>,
+    indices: Option<SerializedIndices>,
+}
+
+impl From<SerializedMeshV1> for SerializedMesh {
+    fn from(mesh: SerializedMeshV1) -> Self {
+        Self {
+            primitive_topology: mesh.primitive_topology,
+            attributes: mesh
+                .attributes
+                .into_iter()
+                .map(|(id, values)| (SerializedMeshVertexAttribute::Builtin(id), values))
+                .collect(),
+            indices: mesh.indices,
+        }
+    }
+}
+
+/// A vertex attribute of a [`SerializedMesh`].
+#[derive(Reflect, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+enum SerializedMeshVertexAttribute {
+    /// One of the attributes defined by Bevy, e.g. [`Mesh::ATTRIBUTE_POSITION`].
+    Builtin(SerializedMeshVertexAttributeId),
+    /// An attribute defined by the game.
+    Custom {
+        /// The name of the attribute.
+        name: String,
+        /// Numbers the custom attributes of a mesh in order.
+        /// Not needed to recreate the attribute, which is identified by its name.
+        id: u64,
+    },
+}
+
+impl SerializedMeshVertexAttribute {
+    const BUILTIN: [MeshVertexAttribute; 8] = [
+        Mesh::ATTRIBUTE_POSITION,
+        Mesh::ATTRIBUTE_NORMAL,
+        Mesh::ATTRIBUTE_UV_0,
+        Mesh::ATTRIBUTE_UV_1,
+        Mesh::ATTRIBUTE_TANGENT,
+        Mesh::ATTRIBUTE_COLOR,
+        Mesh::ATTRIBUTE_JOINT_WEIGHT,
+        Mesh::ATTRIBUTE_JOINT_INDEX,
+    ];
+
+    /// Recreates the attribute. The format of custom attributes is taken from their values.
+    fn into_attribute(
+        self,
+        values: &VertexAttributeValues,
+        custom_attributes: &mut CustomMeshAttributes,
+    ) -> Option<MeshVertexAttribute> {
+        match self {
+            Self::Builtin(id) => {
+                let id = MeshVertexAttributeId::try_from(id).ok()?;
+                Self::BUILTIN
+                    .into_iter()
+                    .find(|attribute| attribute.id == id)
+            }
+            Self::Custom { name, id: _ } => {
+                let (name, id) = custom_attributes.register(name);
+                Some(MeshVertexAttribute::new(
+                    name,
+                    id,
+                    VertexFormat::from(values),
+                ))
+            }
+        }
+    }
+}
+
+/// The custom vertex attributes of received [`SerializedMesh`]es, keyed by the name they were sent with.
+///
+/// Every attribute is recreated with the name it was sent with and an id that is the same in every mesh
+/// deserialized with [`SerializedMesh::into_mesh_with`], so it can be looked up with [`CustomMeshAttributes::id`].
+/// [`MeshVertexAttribute`] requires a static name, so each distinct name is leaked once when it is first received.
+#[derive(Resource, Debug, Default)]
+pub struct CustomMeshAttributes {
+    attributes: HashMap<String, (&'static str, u64)>,
+}
+
+impl CustomMeshAttributes {
+    /// The id of the first custom attribute. Far above the ids of Bevy's builtin attributes.
+    const FIRST_ID: u64 = 1 << 32;
+
+    fn register(&mut self, name: String) -> (&'static str, u64) {
+        let next = Self::FIRST_ID + self.attributes.len() as u64;
+        *self.attributes.entry(name).or_insert_with_key(|name| {
+            let name: &'static str = Box::leak(name.clone().into_boxed_str());
+            (name, next)
+        })
+    }
+
+    /// The id of the received custom attribute with the given name.
+    pub fn id(&self, name: &str) -> Option<MeshVertexAttributeId> {
+        self.attributes
+            .get(name)
+            .map(|&(name, id)| MeshVertexAttribute::new(name, id, VertexFormat::Float32).id)
+    }
+}
+
 #[derive(
     Reflect,
     Debug,
@@ -100,6 +219,7 @@ impl TryFrom<MeshVertexAttributeId> for SerializedMeshVertexAttributeId {
 
     fn try_from(id: MeshVertexAttributeId) -> Result<Self, Self::Error> {
         // Copy-pasted the constants from bevy_mesh, don't think there's a better way to do this ATM ;-;
+        // These numbers are part of the `SCHEMA_VERSION`, so they must never be reassigned.
         if id == Mesh::ATTRIBUTE_POSITION.id {
             Ok(Self(0))
         } else if id == Mesh::ATTRIBUTE_NORMAL.id {
@@ -347,6 +467,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn roundtrip_unindexed_triangle_strip() {
+        let mut strip = Mesh::new(PrimitiveTopology::TriangleStrip, RenderAssetUsages::all());
+        strip.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [0.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 0.0, 1.0],
+            ],
+        );
+        let deserialized_mesh = SerializedMesh::from_mesh(&strip).into_mesh();
+        let trimesh = TriMesh::from_mesh(&deserialized_mesh).unwrap();
+
+        assert_eq!(trimesh.vertices.len(), 4);
+        let indices: Vec<_> = trimesh.indices.iter().map(|i| i.to_array()).collect();
+        // The winding of every other triangle of a strip is flipped back.
+        assert_eq!(indices, vec![[0, 1, 2], [2, 1, 3]]);
+    }
+
+    #[test]
+    fn roundtrip_custom_attribute() {
+        const ATTRIBUTE_WETNESS: MeshVertexAttribute =
+            MeshVertexAttribute::new("Wetness", 988_540_917, VertexFormat::Float32);
+        let mut mesh = star();
+        let wetness: Vec<f32> = (0..11).map(|i| i as f32 / 10.0).collect();
+        mesh.insert_attribute(ATTRIBUTE_WETNESS, wetness.clone());
+
+        let mut custom_attributes = CustomMeshAttributes::default();
+        let deserialized_mesh =
+            SerializedMesh::from_mesh(&mesh).into_mesh_with(&mut custom_attributes);
+        let (attribute, values) = deserialized_mesh
+            .attributes()
+            .find(|(attribute, _)| attribute.name == ATTRIBUTE_WETNESS.name)
+            .unwrap();
+        assert_eq!(
+            custom_attributes.id(ATTRIBUTE_WETNESS.name),
+            Some(attribute.id)
+        );
+        assert_eq!(attribute.format, VertexFormat::Float32);
+        let VertexAttributeValues::Float32(values) = values else {
+            panic!("Expected Float32 values");
+        };
+        assert_eq!(values, &wetness);
+        assert!(deserialized_mesh.contains_attribute(Mesh::ATTRIBUTE_POSITION));
+
+        // Every mesh with the attribute gets the same name and id for it, even after another round trip.
+        let other_mesh =
+            SerializedMesh::from_mesh(&deserialized_mesh).into_mesh_with(&mut custom_attributes);
+        let (other_attribute, _) = other_mesh
+            .attributes()
+            .find(|(other, _)| other.id == attribute.id)
+            .unwrap();
+        assert_eq!(other_attribute.name, ATTRIBUTE_WETNESS.name);
+        assert_eq!(custom_attributes.id("Unknown"), None);
+    }
+
     /// Taken from <https://bevy.org/examples/2d-rendering/mesh2d-manual/>
     fn star() -> Mesh {
         let mut star = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
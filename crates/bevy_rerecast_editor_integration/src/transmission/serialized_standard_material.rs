@@ -363,6 +363,37 @@ impl SerializedStandardMaterial {
             uv_transform: self.uv_transform,
         }
     }
+
+    /// The indices of all images the material references, in no particular order.
+    pub fn images(&self) -> impl Iterator<Item = u32> {
+        let images = [
+            self.base_color_texture,
+            self.emissive_texture,
+            self.metallic_roughness_texture,
+            #[cfg(feature = "pbr_transmission_textures")]
+            self.diffuse_transmission_texture,
+            #[cfg(feature = "pbr_transmission_textures")]
+            self.specular_transmission_texture,
+            #[cfg(feature = "pbr_transmission_textures")]
+            self.thickness_texture,
+            self.normal_map_texture,
+            self.occlusion_texture,
+            #[cfg(feature = "pbr_specular_textures")]
+            self.specular_texture,
+            #[cfg(feature = "pbr_specular_textures")]
+            self.specular_tint_texture,
+            #[cfg(feature = "pbr_multi_layer_material_textures")]
+            self.clearcoat_texture,
+            #[cfg(feature = "pbr_multi_layer_material_textures")]
+            self.clearcoat_roughness_texture,
+            #[cfg(feature = "pbr_multi_layer_material_textures")]
+            self.clearcoat_normal_texture,
+            #[cfg(feature = "pbr_anisotropy_texture")]
+            self.anisotropy_texture,
+            self.depth_map,
+        ];
+        images.into_iter().flatten()
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -0,0 +1,191 @@
+//! The version handshake between a game and the editor.
+//!
+//! Before fetching anything, the editor asks the game for its [`IntegrationVersion`] through [`BRP_GET_VERSION_METHOD`]
+//! and looks both versions up in the [`EditorCompatibility`] table. That way, a game that is too new for the editor
+//! is reported with the versions that go together, instead of failing on data the editor cannot decode.
+//!
+//! Unlike the navmesh input, the handshake is plain JSON, so that every version of the editor can read it.
+
+use std::borrow::Cow;
+
+use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::transmission::SCHEMA_VERSION;
+
+/// The BRP method that the navmesh editor uses to get the [`IntegrationVersion`] of the game.
+/// Optionally takes the [`IntegrationVersion`] of the editor, so that the game can warn about an editor that cannot read its data.
+pub const BRP_GET_VERSION_METHOD: &str = "bevy_rerecast/get_version";
+
+/// The version of the editor integration a game or an editor was built with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IntegrationVersion {
+    /// The version of the `bevy_rerecast_editor_integration` crate, which is released together with `bevy_rerecast`.
+    pub crate_version: String,
+    /// The [`SCHEMA_VERSION`] of the transmitted data.
+    pub schema_version: u16,
+}
+
+impl IntegrationVersion {
+    /// The version this crate was built with, as embedded into games and the editor alike.
+    pub fn current() -> Self {
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            schema_version: SCHEMA_VERSION,
+        }
+    }
+}
+
+/// A release of the editor integration and the schema version it transmits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Release {
+    /// The version of the crate, e.g. `0.0.2`.
+    pub crate_version: Cow<'static, str>,
+    /// The [`SCHEMA_VERSION`] of the release.
+    pub schema_version: u16,
+}
+
+/// Every release that transmits a new [`SCHEMA_VERSION`], oldest first. Releases that keep the schema are compatible with the row above them.
+/// Must get a new row whenever the schema version is bumped for a release.
+const RELEASES: &[(&str, u16)] = &[("0.0.2", 9)];
+
+/// Which releases of the editor integration can be paired with each other.
+///
+/// Data only flows from the game to the editor, so an editor can be paired with every game whose schema version
+/// is at most its own. Older data is decoded through [`Transmitted::decode_legacy`](crate::transmission::Transmitted::decode_legacy).
+///
+/// Inserted by the [`RerecastEditorIntegrationPlugin`](crate::RerecastEditorIntegrationPlugin),
+/// so that games can query it too, e.g. with [`EditorCompatibility::compatible_editors`].
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct EditorCompatibility(Vec<Release>);
+
+impl Default for EditorCompatibility {
+    fn default() -> Self {
+        Self(
+            RELEASES
+                .iter()
+                .map(|&(crate_version, schema_version)| Release {
+                    crate_version: Cow::Borrowed(crate_version),
+                    schema_version,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl EditorCompatibility {
+    /// All known releases, oldest first.
+    pub fn releases(&self) -> &[Release] {
+        &self.0
+    }
+
+    /// The releases whose editor can read the data of a game transmitting `game_schema`.
+    pub fn compatible_editors(&self, game_schema: u16) -> impl Iterator<Item = &Release> {
+        self.0
+            .iter()
+            .filter(move |release| release.schema_version >= game_schema)
+    }
+
+    /// The oldest and newest crate versions of the games that an editor transmitting `editor_schema` supports,
+    /// or `None` if it supports no known release.
+    pub fn supported_games(&self, editor_schema: u16) -> Option<(&str, &str)> {
+        let mut supported = self
+            .0
+            .iter()
+            .filter(|release| release.schema_version <= editor_schema);
+        let oldest = supported.next()?;
+        let newest = supported.next_back().unwrap_or(oldest);
+        Some((&oldest.crate_version, &newest.crate_version))
+    }
+
+    /// Checks that `editor` can read the data sent by `game`.
+    pub fn check(
+        &self,
+        editor: &IntegrationVersion,
+        game: &IntegrationVersion,
+    ) -> Result<(), IncompatibleVersions> {
+        if game.schema_version <= editor.schema_version {
+            return Ok(());
+        }
+        let supported = match self.supported_games(editor.schema_version) {
+            Some((oldest, newest)) if oldest != newest => format!("{oldest}–{newest}"),
+            Some((oldest, _)) => oldest.to_string(),
+            None => editor.crate_version.clone(),
+        };
+        Err(IncompatibleVersions {
+            game: game.crate_version.clone(),
+            supported,
+        })
+    }
+}
+
+/// Happens when the game sends data in a schema version that is newer than the one of the editor.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "The game uses bevy_rerecast {game}, but the editor supports {supported}. \
+    Update the editor to the version of bevy_rerecast the game uses."
+)]
+pub struct IncompatibleVersions {
+    /// The crate version of the game.
+    pub game: String,
+    /// The crate versions of the games the editor supports, e.g. `0.3–0.4`.
+    pub supported: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(crate_version: &str, schema_version: u16) -> IntegrationVersion {
+        IntegrationVersion {
+            crate_version: crate_version.to_string(),
+            schema_version,
+        }
+    }
+
+    #[test]
+    fn newest_release_is_current() {
+        let compatibility = EditorCompatibility::default();
+        let newest = compatibility.releases().last().unwrap();
+        let current = IntegrationVersion::current();
+        assert_eq!(newest.crate_version, current.crate_version);
+        assert_eq!(newest.schema_version, current.schema_version);
+    }
+
+    #[test]
+    fn reports_supported_range_of_editor() {
+        let compatibility = EditorCompatibility(vec![
+            Release {
+                crate_version: "0.2".into(),
+                schema_version: 3,
+            },
+            Release {
+                crate_version: "0.3".into(),
+                schema_version: 5,
+            },
+            Release {
+                crate_version: "0.4".into(),
+                schema_version: 6,
+            },
+            Release {
+                crate_version: "0.5".into(),
+                schema_version: 8,
+            },
+        ]);
+        let editor = version("0.4", 6);
+        assert_eq!(compatibility.check(&editor, &version("0.3", 5)), Ok(()));
+        let error = compatibility
+            .check(&editor, &version("0.5", 8))
+            .unwrap_err();
+        assert_eq!(error.supported, "0.2–0.4");
+        assert!(error.to_string().contains("game uses bevy_rerecast 0.5"));
+        assert_eq!(
+            compatibility
+                .compatible_editors(6)
+                .map(|release| &*release.crate_version)
+                .collect::<Vec<_>>(),
+            ["0.4", "0.5"]
+        );
+    }
+}
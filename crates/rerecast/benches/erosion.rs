@@ -0,0 +1,96 @@
+//! Compares the methods of eroding the walkable area of a compact heightfield.
+#![expect(
+    missing_docs,
+    reason = "`criterion_group!` generates an undocumented public function"
+)]
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use glam::{UVec3, Vec3A};
+use rerecast::{CompactHeightfield, ErosionMethod, HeightfieldBuilder, TriMesh};
+
+/// A hilly terrain with a grid of pillars on it, `size` by `size` world units large.
+fn terrain(size: u32) -> TriMesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let height = |x: u32, z: u32| ((x as f32 * 0.3).sin() + (z as f32 * 0.2).cos()) * 0.5;
+    for z in 0..=size {
+        for x in 0..=size {
+            vertices.push(Vec3A::new(x as f32, height(x, z), z as f32));
+        }
+    }
+    let row = size + 1;
+    for z in 0..size {
+        for x in 0..size {
+            let i = z * row + x;
+            indices.push(UVec3::new(i, i + row, i + row + 1));
+            indices.push(UVec3::new(i, i + row + 1, i + 1));
+        }
+    }
+    for z in (4..size).step_by(8) {
+        for x in (4..size).step_by(8) {
+            let base = vertices.len() as u32;
+            let (x, z) = (x as f32, z as f32);
+            for y in [-1.0, 3.0] {
+                vertices.extend([
+                    Vec3A::new(x, y, z),
+                    Vec3A::new(x + 1.0, y, z),
+                    Vec3A::new(x + 1.0, y, z + 1.0),
+                    Vec3A::new(x, y, z + 1.0),
+                ]);
+            }
+            for side in 0..4 {
+                let a = base + side;
+                let b = base + (side + 1) % 4;
+                indices.push(UVec3::new(a, b, b + 4));
+                indices.push(UVec3::new(a, b + 4, a + 4));
+            }
+            indices.push(UVec3::new(base + 4, base + 5, base + 6));
+            indices.push(UVec3::new(base + 4, base + 6, base + 7));
+        }
+    }
+    TriMesh::from_parts(vertices, indices).unwrap()
+}
+
+fn compact_heightfield(size: u32) -> CompactHeightfield {
+    let mut trimesh = terrain(size);
+    trimesh.mark_walkable_triangles(45.0_f32.to_radians());
+    let mut heightfield = HeightfieldBuilder {
+        aabb: trimesh.compute_aabb().unwrap(),
+        cell_size: 0.3,
+        cell_height: 0.2,
+    }
+    .build()
+    .unwrap();
+    heightfield.rasterize_triangles(&trimesh, 4).unwrap();
+    heightfield.filter_low_hanging_walkable_obstacles(4);
+    heightfield.filter_ledge_spans(10, 4);
+    heightfield.filter_walkable_low_height_spans(10);
+    heightfield.into_compact(10, 4).unwrap()
+}
+
+fn erosion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("erode_walkable_area");
+    for size in [64, 256] {
+        let compact_heightfield = compact_heightfield(size);
+        for method in [ErosionMethod::Reference, ErosionMethod::FlatChamfer] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{method:?}"), size),
+                &compact_heightfield,
+                |b, compact_heightfield| {
+                    b.iter_batched(
+                        || compact_heightfield.clone(),
+                        |mut compact_heightfield| {
+                            compact_heightfield.erode_walkable_area_with(2, method);
+                            compact_heightfield
+                        },
+                        BatchSize::LargeInput,
+                    );
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, erosion);
+criterion_main!(benches);
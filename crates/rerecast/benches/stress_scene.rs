@@ -0,0 +1,37 @@
+//! Builds navmeshes for generated scenes of increasing size to see how the whole pipeline scales.
+#![expect(
+    missing_docs,
+    reason = "`criterion_group!` generates an undocumented public function"
+)]
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use rerecast::{NavmeshConfigBuilder, StressScene, build_navmesh};
+
+fn stress_scene(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build_navmesh");
+    group.sample_size(10);
+    for size in [2, 4, 8] {
+        let scene = StressScene::new(size);
+        let trimesh = scene.build();
+        let config = NavmeshConfigBuilder {
+            aabb: scene.aabb(),
+            ..Default::default()
+        }
+        .build();
+        group.bench_with_input(
+            BenchmarkId::new("stress_scene", size),
+            &trimesh,
+            |b, trimesh| {
+                b.iter_batched(
+                    || trimesh.clone(),
+                    |trimesh| build_navmesh(trimesh, &config).unwrap(),
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, stress_scene);
+criterion_main!(benches);
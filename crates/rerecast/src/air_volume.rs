@@ -0,0 +1,222 @@
+//! A coarse 3D grid of the free space in a [`Heightfield`], as groundwork for flying agents.
+//!
+//! While the navmesh describes the floors agents can walk on, an [`AirVolume`] describes the space agents can fly through.
+
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+use glam::{IVec3, U16Vec3, Vec3};
+use thiserror::Error;
+
+use crate::{Aabb3d, Heightfield};
+
+impl Heightfield {
+    /// Builds an [`AirVolume`] from the free space between and above the spans of this heightfield.
+    ///
+    /// The volume is coarser than the heightfield: each voxel covers `voxel_size` by `voxel_size` columns
+    /// and `voxel_height` cells along the y-axis. A voxel is only free if no span of any column it covers overlaps it,
+    /// so small gaps are closed off in favor of a volume that is safe to fly through.
+    ///
+    /// Should be called after the filters, but before [`Heightfield::into_compact`], which consumes the heightfield.
+    pub fn build_air_volume(
+        &self,
+        voxel_size: u16,
+        voxel_height: u16,
+    ) -> Result<AirVolume, AirVolumeError> {
+        if voxel_size == 0 || voxel_height == 0 {
+            return Err(AirVolumeError::ZeroVoxelSize);
+        }
+        let layers = ((self.aabb.max.y - self.aabb.min.y) / self.cell_height).ceil() as u32;
+        let size = U16Vec3::new(
+            self.width.div_ceil(voxel_size),
+            layers.div_ceil(voxel_height as u32) as u16,
+            self.height.div_ceil(voxel_size),
+        );
+        let mut volume = AirVolume {
+            size,
+            aabb: self.aabb,
+            voxel_size: Vec3::new(
+                voxel_size as f32 * self.cell_size,
+                voxel_height as f32 * self.cell_height,
+                voxel_size as f32 * self.cell_size,
+            ),
+            free: vec![true; size.x as usize * size.y as usize * size.z as usize],
+        };
+
+        for z in 0..self.height {
+            for x in 0..self.width {
+                let mut span_key = self.span_key_at(x, z);
+                while let Some(key) = span_key {
+                    let span = self.span(key);
+                    span_key = span.next;
+                    if span.max <= span.min {
+                        continue;
+                    }
+                    let min_y = span.min / voxel_height;
+                    let max_y = ((span.max - 1) / voxel_height).min(size.y.saturating_sub(1));
+                    for y in min_y..=max_y {
+                        let index = volume.index(U16Vec3::new(x / voxel_size, y, z / voxel_size));
+                        volume.free[index] = false;
+                    }
+                }
+            }
+        }
+        Ok(volume)
+    }
+}
+
+/// A coarse 3D grid marking which parts of a [`Heightfield`] are free of geometry.
+/// Built with [`Heightfield::build_air_volume`].
+///
+/// Voxels are addressed by their coordinates along the x-, y- and z-axis, starting at [`Aabb3d::min`].
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct AirVolume {
+    /// The number of voxels along each axis.
+    pub size: U16Vec3,
+    /// The bounds of the heightfield the volume was built from. The voxels start at [`Aabb3d::min`].
+    pub aabb: Aabb3d,
+    /// The size of a single voxel in world units.
+    pub voxel_size: Vec3,
+    /// Whether each voxel is free, in x, then z, then y order.
+    pub free: Vec<bool>,
+}
+
+impl AirVolume {
+    /// The six directions in which voxels are connected to their neighbors.
+    const DIRECTIONS: [IVec3; 6] = [
+        IVec3::NEG_X,
+        IVec3::X,
+        IVec3::NEG_Y,
+        IVec3::Y,
+        IVec3::NEG_Z,
+        IVec3::Z,
+    ];
+
+    #[inline]
+    fn index(&self, voxel: U16Vec3) -> usize {
+        let (width, depth) = (self.size.x as usize, self.size.z as usize);
+        voxel.x as usize + voxel.z as usize * width + voxel.y as usize * width * depth
+    }
+
+    /// Whether the given voxel coordinates lie within the volume.
+    #[inline]
+    pub fn contains(&self, voxel: U16Vec3) -> bool {
+        voxel.cmplt(self.size).all()
+    }
+
+    /// Whether the given voxel is free. Voxels outside the volume are not free.
+    #[inline]
+    pub fn is_free(&self, voxel: U16Vec3) -> bool {
+        self.contains(voxel) && self.free[self.index(voxel)]
+    }
+
+    /// Whether the voxel containing the given world position is free.
+    /// Positions outside the volume are not free.
+    pub fn is_free_at(&self, position: Vec3) -> bool {
+        self.voxel_at(position)
+            .is_some_and(|voxel| self.is_free(voxel))
+    }
+
+    /// The voxel containing the given world position, if any.
+    pub fn voxel_at(&self, position: Vec3) -> Option<U16Vec3> {
+        let voxel = ((position - self.aabb.min) / self.voxel_size).floor();
+        if voxel.cmplt(Vec3::ZERO).any() || voxel.cmpge(self.size.as_vec3()).any() {
+            return None;
+        }
+        Some(voxel.as_u16vec3())
+    }
+
+    /// The center of the given voxel in world space.
+    pub fn voxel_center(&self, voxel: U16Vec3) -> Vec3 {
+        self.aabb.min + (voxel.as_vec3() + 0.5) * self.voxel_size
+    }
+
+    /// Iterates over the free voxels sharing a face with the given voxel.
+    pub fn free_neighbors(&self, voxel: U16Vec3) -> impl Iterator<Item = U16Vec3> {
+        Self::DIRECTIONS.into_iter().filter_map(move |direction| {
+            let neighbor = voxel.as_ivec3() + direction;
+            if neighbor.cmplt(IVec3::ZERO).any() {
+                return None;
+            }
+            let neighbor = neighbor.as_u16vec3();
+            self.is_free(neighbor).then_some(neighbor)
+        })
+    }
+
+    /// The number of free voxels in the volume.
+    pub fn free_voxel_count(&self) -> usize {
+        self.free.iter().filter(|free| **free).count()
+    }
+}
+
+/// Errors that can occur when building an [`AirVolume`] with [`Heightfield::build_air_volume`].
+#[derive(Error, Debug)]
+pub enum AirVolumeError {
+    /// Happens when the voxel size or height is zero.
+    #[error("Voxel size and height must be at least one cell")]
+    ZeroVoxelSize,
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3A};
+
+    use super::*;
+    use crate::{AreaType, HeightfieldBuilder, TriMesh};
+
+    #[test]
+    fn space_above_floor_is_free() {
+        let mut heightfield = HeightfieldBuilder {
+            aabb: Aabb3d {
+                min: Vec3::ZERO,
+                max: Vec3::new(4.0, 4.0, 4.0),
+            },
+            cell_size: 0.5,
+            cell_height: 0.5,
+        }
+        .build()
+        .unwrap();
+        let floor = TriMesh {
+            vertices: vec![
+                Vec3A::new(0.0, 0.2, 0.0),
+                Vec3A::new(0.0, 0.2, 4.0),
+                Vec3A::new(4.0, 0.2, 4.0),
+                Vec3A::new(4.0, 0.2, 0.0),
+            ],
+            indices: vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+            area_types: vec![AreaType::DEFAULT_WALKABLE; 2],
+        };
+        heightfield.rasterize_triangles(&floor, 1).unwrap();
+
+        let volume = heightfield.build_air_volume(2, 2).unwrap();
+        assert_eq!(volume.size, U16Vec3::new(4, 4, 4));
+        assert_eq!(volume.voxel_size, Vec3::ONE);
+        // Only the bottom layer contains the floor.
+        assert_eq!(volume.free_voxel_count(), 4 * 4 * 3);
+        assert!(!volume.is_free_at(Vec3::new(2.0, 0.5, 2.0)));
+        assert!(volume.is_free_at(Vec3::new(2.0, 1.5, 2.0)));
+        assert!(!volume.is_free_at(Vec3::new(2.0, 10.0, 2.0)));
+
+        let corner = U16Vec3::new(0, 1, 0);
+        assert_eq!(volume.voxel_center(corner), Vec3::new(0.5, 1.5, 0.5));
+        let neighbors: Vec<_> = volume.free_neighbors(corner).collect();
+        assert_eq!(
+            neighbors,
+            vec![
+                U16Vec3::new(1, 1, 0),
+                U16Vec3::new(0, 2, 0),
+                U16Vec3::new(0, 1, 1)
+            ]
+        );
+
+        assert!(matches!(
+            heightfield.build_air_volume(0, 1),
+            Err(AirVolumeError::ZeroVoxelSize)
+        ));
+    }
+}
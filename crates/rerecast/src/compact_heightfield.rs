@@ -1,5 +1,5 @@
 use crate::{
-    Aabb3d,
+    Aabb3d, BuildScratch,
     compact_cell::CompactCell,
     compact_span::CompactSpan,
     heightfield::Heightfield,
@@ -124,6 +124,21 @@ impl Heightfield {
         self,
         walkable_height: u16,
         walkable_climb: u16,
+    ) -> Result<CompactHeightfield, CompactHeightfieldError> {
+        self.into_compact_with_scratch(
+            walkable_height,
+            walkable_climb,
+            &mut BuildScratch::default(),
+        )
+    }
+
+    /// Same as [`Heightfield::into_compact`], but takes the buffers of the compact heightfield from `scratch`
+    /// and gives the storage of this heightfield back to it.
+    pub(crate) fn into_compact_with_scratch(
+        self,
+        walkable_height: u16,
+        walkable_climb: u16,
+        scratch: &mut BuildScratch,
     ) -> Result<CompactHeightfield, CompactHeightfieldError> {
         let walkable_span_count = self
             .allocated_spans
@@ -142,10 +157,17 @@ impl Heightfield {
             max_region: RegionId::NONE,
             cell_size: self.cell_size,
             cell_height: self.cell_height,
-            cells: vec![CompactCell::default(); self.width as usize * self.height as usize],
-            spans: vec![CompactSpan::default(); walkable_span_count],
-            dist: vec![],
-            areas: vec![AreaType::NOT_WALKABLE; walkable_span_count],
+            cells: scratch.cells.take(
+                self.width as usize * self.height as usize,
+                CompactCell::default(),
+            ),
+            spans: scratch
+                .compact_spans
+                .take(walkable_span_count, CompactSpan::default()),
+            dist: scratch.dist.take(0, 0),
+            areas: scratch
+                .areas
+                .take(walkable_span_count, AreaType::NOT_WALKABLE),
         };
         compact_heightfield.aabb.max.y += walkable_height as f32 * compact_heightfield.cell_height;
 
@@ -212,7 +234,7 @@ impl Heightfield {
                         let neighbor_index_count =
                             neighbor_cell.index() as usize + neighbor_cell.count() as usize;
                         let span_clone = compact_heightfield.spans[i].clone();
-                        for k in neighbor_cell.index() as usize..neighbor_index_count as usize {
+                        for k in neighbor_cell.index() as usize..neighbor_index_count {
                             let neighbor_span = &compact_heightfield.spans[k];
                             let bot = span_clone.y.max(neighbor_span.y);
                             let top = (span_clone.y + span_clone.height() as u16)
@@ -246,6 +268,7 @@ impl Heightfield {
                 layer_index: max_layer_index,
             });
         }
+        scratch.recycle_heightfield(self.spans, self.allocated_spans);
         Ok(compact_heightfield)
     }
 }
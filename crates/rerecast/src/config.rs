@@ -1,4 +1,9 @@
-use crate::{Aabb3d, BuildContoursFlags};
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::{NamedField, StructInfo, Typed as _, prelude::*};
+
+use crate::{
+    Aabb3d, BuildContoursFlags, DetailSampling, ErosionMethod, LedgeFilter, RegionPartitioning,
+};
 
 /// Specifies a configuration to use when performing Recast builds. Usually built using [`NavmeshConfigBuilder`].
 ///
@@ -20,6 +25,7 @@ use crate::{Aabb3d, BuildContoursFlags};
 /// > If your game world uses meters as units, a reasonable starting point for a human-sized agent
 /// > might be a radius of 0.4 and a height of 2.0.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct NavmeshConfig {
     /// The width of the field along the x-axis. `[Limit: >= 0] [Units: vx]`
     pub width: u16,
@@ -36,6 +42,13 @@ pub struct NavmeshConfig {
     ///
     /// This value represents the the closest the walkable area of the heightfield should come to the xz-plane AABB of the field.
     /// It does not have any impact on the borders around internal obstructions.
+    ///
+    /// When building contours, the xz-plane AABB of the [`ContourSet`](crate::ContourSet) is shrunk by `border_size * cell_size`
+    /// on every side, so that the vertices of the resulting [`PolygonNavmesh`](crate::PolygonNavmesh) are relative to the
+    /// area inside the border. For tiled builds, this means that the border overlaps with the neighboring tiles,
+    /// which is what allows the tiles to line up seamlessly.
+    ///
+    /// [`NavmeshConfigBuilder`] derives this as `walkable_radius + 3` unless [`NavmeshConfigBuilder::border_size`] is set.
     pub border_size: u16,
 
     /// The xz-plane cell size to use for fields. `[Limit: > 0] [Units: wu]`.
@@ -177,6 +190,16 @@ pub struct NavmeshConfig {
     /// If you see small patches missing here and there, you could lower the [`Self::min_region_area`] value.
     pub merge_region_area: u16,
 
+    /// The maximum size of a region along the x- and z-axis. `[Limit: >0] [Units: vx]`
+    ///
+    /// A single huge region, like an open field, results in a single contour with thousands of vertices,
+    /// which hurts the quality of the triangulation and can exceed the vertex limits of the polygon mesh.
+    /// Regions larger than this are split along a grid with cells of this size, just like regions in tiled builds
+    /// can never be larger than a tile. See [`CompactHeightfield::split_large_regions`](crate::CompactHeightfield::split_large_regions).
+    ///
+    /// `None` disables splitting, which is the behavior of the original Recast for single-tile builds.
+    pub max_region_extent: Option<u16>,
+
     /// The maximum number of vertices allowed for polygons generated during the
     /// contour to polygon conversion process. `[Limit: >= 3]`
     pub max_vertices_per_polygon: u16,
@@ -189,14 +212,56 @@ pub struct NavmeshConfig {
     /// data. (For height detail only.) `[Limit: >=0] [Units: wu]`
     pub detail_sample_max_error: f32,
 
+    /// Where the detail mesh looks for height detail inside of the polygons. Defaults to [`DetailSampling::Grid`].
+    pub detail_sampling: DetailSampling,
+
     /// Flags controlling the [`ContourSet`](crate::ContourSet) generation process.
     pub contour_flags: BuildContoursFlags,
+
+    /// How spans on slopes too steep to walk are detected while filtering ledges. Defaults to [`LedgeFilter::Reference`].
+    ///
+    /// Use [`LedgeFilter::RampAware`] if ramps end up with holes, which happens when [`Self::cell_height`] is coarse
+    /// and the slope of the ramp is close to what [`Self::walkable_climb`] allows.
+    pub ledge_filter: LedgeFilter,
+
+    /// The algorithm used to erode the walkable area by [`Self::walkable_radius`].
+    /// All methods produce the same result, so this only affects build performance.
+    pub erosion_method: ErosionMethod,
+
+    /// Whether to compute the center and area of every polygon once at the end of the build and store them
+    /// in [`PolygonNavmesh::centers`](crate::PolygonNavmesh::centers) and [`PolygonNavmesh::surface_areas`](crate::PolygonNavmesh::surface_areas).
+    ///
+    /// Enable this when pathfinding or spawn sampling query them often. It costs 16 bytes per polygon.
+    pub cache_polygon_geometry: bool,
+
+    /// How much more expensive steep and uneven regions are for pathfinding. `[Limit: >=0]`
+    ///
+    /// If above zero, the slope of every region is measured while building and baked into
+    /// [`PolygonNavmesh::costs`](crate::PolygonNavmesh::costs) as `1 + slope_cost * (mean_gradient + roughness)`,
+    /// see [`RegionSlope`](crate::RegionSlope). With a value of 1, a region sloping at 45 degrees costs about twice as much as flat ground.
+    /// Zero disables the costs, which leaves every polygon at a cost of 1.
+    pub slope_cost: f32,
+
+    /// The algorithm used to partition the walkable area into regions. Defaults to [`RegionPartitioning::Watershed`].
+    ///
+    /// The other methods skip the distance field, which makes large builds a lot faster at the cost of worse polygons.
+    pub region_partitioning: RegionPartitioning,
 }
 
 /// A builder for [`NavmeshConfig`]. The config has lots of interdependent configurations,
 /// so this builder provides a convenient way to set all the necessary parameters.
 /// The default values are chosen to be reasonable for an agent resembling and adult human.
+///
+/// With the `bevy_reflect` feature, the numeric fields carry a `ConfigRange`, a `ConfigStep`, a `ConfigTooltip` and usually a `ConfigUnit`
+/// as custom reflect attributes, so that editors can generate their inputs for the fields from the reflection data.
+/// `ConfigField` reads them for every field.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
 pub struct NavmeshConfigBuilder {
     /// The xz-plane cell size to use for fields. `[Limit: > 0] [Units: wu]`.
     ///
@@ -216,7 +281,11 @@ pub struct NavmeshConfigBuilder {
     ///
     /// The minimum value for this parameter depends on the platform's floating point accuracy,
     /// with the practical minimum usually around 0.05.
-    cell_size: f32,
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.05, 10.0), @ConfigStep(0.05), @ConfigUnit::WorldUnits, @ConfigTooltip("The size of the cells on the xz-plane. Smaller cells add detail but slow down the build."))
+    )]
+    pub cell_size: f32,
     /// The y-axis cell size to use for fields. `[Limit: > 0] [Units: wu]`
     ///
     /// The voxelization cell height is defined separately in order to allow for greater precision in height tests.
@@ -228,26 +297,132 @@ pub struct NavmeshConfigBuilder {
     /// cell_size and cell_height define voxel/grid/cell size. So their values have significant side effects on all parameters defined in voxel units.
     ///
     /// The minimum value for this parameter depends on the platform's floating point accuracy, with the practical minimum usually around 0.05.
-    cell_height: f32,
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.05, 10.0), @ConfigStep(0.05), @ConfigUnit::WorldUnits, @ConfigTooltip("The height of the cells. Smaller cells keep small steps and curbs connected."))
+    )]
+    pub cell_height: f32,
     /// The height of the agent in meters. `[Limit: > 0] [Units: wu]`
     ///
     /// It's often a good idea to add a little bit of padding to the height. For example,
     /// an agent that is 1.8 meters tall might want to set this value to 2.0 meters.
-    agent_height: f32,
-    agent_radius: f32,
-    agent_max_climb: f32,
-    agent_max_slope: f32,
-    region_min_size: f32,
-    region_merge_size: f32,
-    edge_max_len: f32,
-    edge_max_error: f32,
-    verts_per_poly: f32,
-    detail_sample_dist: f32,
-    detail_sample_max_error: f32,
-    tile_size: u16,
-    aabb: Aabb3d,
-    contour_flags: BuildContoursFlags,
-    tiling: bool,
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.1, 100.0), @ConfigStep(0.1), @ConfigUnit::VerticalWorldUnits, @ConfigRounding::Ceil, @ConfigTooltip("The height of the agent. Spans with less clearance are not walkable."))
+    )]
+    pub agent_height: f32,
+    /// The radius of the agent. `[Limit: >= 0] [Units: wu]`
+    ///
+    /// Converted to [`NavmeshConfig::walkable_radius`] as `(agent_radius / cell_size).ceil()`.
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.0, 100.0), @ConfigStep(0.05), @ConfigUnit::HorizontalWorldUnits, @ConfigRounding::Ceil, @ConfigTooltip("The radius of the agent. The navmesh keeps this distance from walls."))
+    )]
+    pub agent_radius: f32,
+    /// The maximum height of ledges and steps the agent can climb. `[Limit: >= 0] [Units: wu]`
+    ///
+    /// Converted to [`NavmeshConfig::walkable_climb`] as `(agent_max_climb / cell_height).floor()`.
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.0, 100.0), @ConfigStep(0.05), @ConfigUnit::VerticalWorldUnits, @ConfigRounding::Floor, @ConfigTooltip("The highest ledge or step the agent can climb."))
+    )]
+    pub agent_max_climb: f32,
+    /// The maximum slope the agent can walk on. `[Limits: 0 <= value < 0.5*π] [Units: Radians]`
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.0, 1.569), @ConfigStep(0.087_266_46), @ConfigUnit::Radians, @ConfigTooltip("The steepest slope the agent can walk on."))
+    )]
+    pub agent_max_slope: f32,
+    /// The minimum side length of an isolated region. `[Limit: >= 0] [Units: vx]`
+    ///
+    /// Squared into [`NavmeshConfig::min_region_area`].
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.0, 255.0), @ConfigStep(1.0), @ConfigUnit::Voxels, @ConfigTooltip("Isolated regions smaller than this are removed."))
+    )]
+    pub region_min_size: f32,
+    /// The side length below which regions are merged into larger neighbors. `[Limit: >= 0] [Units: vx]`
+    ///
+    /// Squared into [`NavmeshConfig::merge_region_area`].
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.0, 255.0), @ConfigStep(1.0), @ConfigUnit::Voxels, @ConfigTooltip("Regions smaller than this are merged into their neighbors."))
+    )]
+    pub region_merge_size: f32,
+    /// The maximum length of contour edges along the border of the mesh. `[Limit: >= 0] [Units: wu]`
+    ///
+    /// Converted to [`NavmeshConfig::max_edge_len`] as `edge_max_len / cell_size`.
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.0, 1000.0), @ConfigStep(1.0), @ConfigUnit::HorizontalWorldUnits, @ConfigRounding::Floor, @ConfigTooltip("Longer edges along the border are split. 0 disables splitting."))
+    )]
+    pub edge_max_len: f32,
+    /// See [`NavmeshConfig::max_simplification_error`]. `[Limit: >= 0] [Units: vx]`
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.0, 10.0), @ConfigStep(0.1), @ConfigUnit::Voxels, @ConfigTooltip("How far the simplified border may deviate from the cells."))
+    )]
+    pub edge_max_error: f32,
+    /// See [`NavmeshConfig::max_vertices_per_polygon`]. `[Limit: >= 3]`
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(3.0, 12.0), @ConfigStep(1.0), @ConfigUnit::Count, @ConfigTooltip("The maximum number of vertices per polygon."))
+    )]
+    pub verts_per_poly: f32,
+    /// The sampling distance of the detail mesh. `[Limits: 0 or >= 0.9] [Units: cell_size]`
+    ///
+    /// Multiplied by `cell_size` into [`NavmeshConfig::detail_sample_dist`]. Values below 0.9 disable sampling.
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.0, 100.0), @ConfigStep(0.5), @ConfigUnit::CellSizes, @ConfigDisabledBelow(0.9), @ConfigTooltip("The sampling distance of the detail mesh. Values below 0.9 disable sampling."))
+    )]
+    pub detail_sample_dist: f32,
+    /// The maximum deviation of the detail mesh from the heightfield. `[Limit: >= 0] [Units: cell_height]`
+    ///
+    /// Multiplied by `cell_height` into [`NavmeshConfig::detail_sample_max_error`].
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.0, 100.0), @ConfigStep(0.5), @ConfigUnit::CellHeights, @ConfigTooltip("How far the detail mesh may deviate from the heightfield."))
+    )]
+    pub detail_sample_max_error: f32,
+    /// See [`NavmeshConfig::detail_sampling`].
+    pub detail_sampling: DetailSampling,
+    /// See [`NavmeshConfig::max_region_extent`]. `[Limit: >0] [Units: vx]`
+    pub max_region_extent: Option<u16>,
+    /// See [`NavmeshConfig::tile_size`]. Only used if [`Self::tiling`] is enabled.
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(1.0, 4096.0), @ConfigStep(8.0), @ConfigUnit::Voxels, @ConfigTooltip("The size of the tiles of multi-tile navmeshes."))
+    )]
+    pub tile_size: u16,
+    /// See [`NavmeshConfig::aabb`].
+    pub aabb: Aabb3d,
+    /// See [`NavmeshConfig::contour_flags`].
+    #[cfg_attr(feature = "bevy_reflect", reflect(ignore))]
+    pub contour_flags: BuildContoursFlags,
+    /// See [`NavmeshConfig::ledge_filter`].
+    pub ledge_filter: LedgeFilter,
+    /// See [`NavmeshConfig::erosion_method`].
+    pub erosion_method: ErosionMethod,
+    /// See [`NavmeshConfig::cache_polygon_geometry`].
+    pub cache_polygon_geometry: bool,
+    /// See [`NavmeshConfig::slope_cost`].
+    #[cfg_attr(
+        feature = "bevy_reflect",
+        reflect(@ConfigRange::new(0.0, 10.0), @ConfigStep(0.5), @ConfigTooltip("How much more expensive steep and uneven regions are for pathfinding. 0 disables the costs."))
+    )]
+    pub slope_cost: f32,
+    /// See [`NavmeshConfig::region_partitioning`].
+    pub region_partitioning: RegionPartitioning,
+    /// Whether the config is used to build a single tile of a multi-tile mesh.
+    /// If enabled, the field is sized to [`Self::tile_size`] plus the border on every side instead of to the [`Self::aabb`].
+    pub tiling: bool,
+    /// Overrides the derived [`NavmeshConfig::border_size`]. `[Limit: >=0] [Units: vx]`
+    ///
+    /// If `None`, the border size is derived as `walkable_radius + 3`, which reserves enough padding
+    /// for the erosion by the agent radius plus the neighbor lookups of the later build stages.
+    /// Tiled builds and custom erosion schemes may need a different padding.
+    pub border_size: Option<u16>,
 }
 
 impl Default for NavmeshConfigBuilder {
@@ -266,10 +441,18 @@ impl Default for NavmeshConfigBuilder {
             verts_per_poly: 6.0,
             detail_sample_dist: 6.0,
             detail_sample_max_error: 1.0,
+            detail_sampling: DetailSampling::default(),
+            max_region_extent: None,
             tile_size: 32,
             aabb: Aabb3d::default(),
             contour_flags: BuildContoursFlags::default(),
+            ledge_filter: LedgeFilter::default(),
+            erosion_method: ErosionMethod::default(),
+            cache_polygon_geometry: false,
+            slope_cost: 0.0,
+            region_partitioning: RegionPartitioning::default(),
             tiling: false,
+            border_size: None,
         }
     }
 }
@@ -279,7 +462,7 @@ impl NavmeshConfigBuilder {
     pub fn build(self) -> NavmeshConfig {
         let walkable_radius = (self.agent_radius / self.cell_size).ceil() as u16;
         // Reserve enough padding.
-        let border_size = walkable_radius + 3;
+        let border_size = self.border_size.unwrap_or(walkable_radius + 3);
         NavmeshConfig {
             width: if self.tiling {
                 self.tile_size + border_size * 2
@@ -304,6 +487,7 @@ impl NavmeshConfigBuilder {
             max_simplification_error: self.edge_max_error,
             min_region_area: (self.region_min_size * self.region_min_size) as u16,
             merge_region_area: (self.region_merge_size * self.region_merge_size) as u16,
+            max_region_extent: self.max_region_extent,
             max_vertices_per_polygon: self.verts_per_poly as u16,
             detail_sample_dist: if self.detail_sample_dist < 0.9 {
                 0.0
@@ -311,7 +495,318 @@ impl NavmeshConfigBuilder {
                 self.cell_size * self.detail_sample_dist
             },
             detail_sample_max_error: self.cell_height * self.detail_sample_max_error,
+            detail_sampling: self.detail_sampling,
             contour_flags: self.contour_flags,
+            ledge_filter: self.ledge_filter,
+            erosion_method: self.erosion_method,
+            cache_polygon_geometry: self.cache_polygon_geometry,
+            slope_cost: self.slope_cost,
+            region_partitioning: self.region_partitioning,
+        }
+    }
+}
+
+/// The range of sensible values of a numeric field of [`NavmeshConfigBuilder`], in the units of the field.
+///
+/// Attached to the fields as a custom reflect attribute, e.g. to clamp the input of an editor.
+#[cfg(feature = "bevy_reflect")]
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct ConfigRange {
+    /// The smallest sensible value.
+    pub min: f32,
+    /// The largest sensible value.
+    pub max: f32,
+}
+
+#[cfg(feature = "bevy_reflect")]
+impl ConfigRange {
+    /// Creates a range from `min` to `max`, both inclusive.
+    pub const fn new(min: f32, max: f32) -> Self {
+        Self { min, max }
+    }
+}
+
+/// How much an editor should change a numeric field of [`NavmeshConfigBuilder`] per step, in the units of the field.
+/// Attached to the fields as a custom reflect attribute.
+#[cfg(feature = "bevy_reflect")]
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct ConfigStep(pub f32);
+
+/// A one-line description of a numeric field of [`NavmeshConfigBuilder`], e.g. for a tooltip.
+/// Attached to the fields as a custom reflect attribute.
+#[cfg(feature = "bevy_reflect")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct ConfigTooltip(pub &'static str);
+
+/// The unit of a numeric field of [`NavmeshConfigBuilder`], attached to the fields as a custom reflect attribute.
+/// Fields without one have no unit.
+#[cfg(feature = "bevy_reflect")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum ConfigUnit {
+    /// World units that are not converted to cells, e.g. the size of the cells themselves.
+    WorldUnits,
+    /// World units along the xz-plane, which the build converts to cells with the cell size.
+    HorizontalWorldUnits,
+    /// World units along the y-axis, which the build converts to cells with the cell height.
+    VerticalWorldUnits,
+    /// Cells along the xz-plane.
+    Voxels,
+    /// Multiples of the cell size.
+    CellSizes,
+    /// Multiples of the cell height.
+    CellHeights,
+    /// An angle in radians.
+    Radians,
+    /// A whole number, e.g. of vertices.
+    Count,
+}
+
+/// How the build rounds a field in [`ConfigUnit::HorizontalWorldUnits`] or [`ConfigUnit::VerticalWorldUnits`] to whole cells,
+/// attached to the fields as a custom reflect attribute. Fields without one are not rounded.
+#[cfg(feature = "bevy_reflect")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum ConfigRounding {
+    /// Rounded up, e.g. so that the agent always fits.
+    Ceil,
+    /// Rounded down, e.g. so that the agent never climbs higher than it can.
+    Floor,
+}
+
+/// The value below which a numeric field of [`NavmeshConfigBuilder`] turns off what it configures,
+/// attached to the fields as a custom reflect attribute.
+#[cfg(feature = "bevy_reflect")]
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct ConfigDisabledBelow(pub f32);
+
+/// A numeric field of [`NavmeshConfigBuilder`], described by its custom reflect attributes.
+///
+/// Every `f32` or `u16` field with a [`ConfigRange`] is one. Values are read and written in the [`ConfigUnit`] of the field,
+/// except for [`ConfigUnit::Radians`], which are converted to degrees.
+#[cfg(feature = "bevy_reflect")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConfigField(usize);
+
+#[cfg(feature = "bevy_reflect")]
+impl ConfigField {
+    /// All numeric fields, in the order they are declared in the [`NavmeshConfigBuilder`].
+    pub fn all() -> impl Iterator<Item = Self> {
+        builder_info()
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| {
+                (field.is::<f32>() || field.is::<u16>()) && field.has_attribute::<ConfigRange>()
+            })
+            .map(|(index, _)| Self(index))
+    }
+
+    /// The reflection data of the field.
+    pub fn info(self) -> &'static NamedField {
+        builder_info()
+            .field_at(self.0)
+            .expect("config fields are indices into the builder fields")
+    }
+
+    /// The name of the field in title case, e.g. "Agent Max Climb".
+    pub fn title(self) -> String {
+        self.info()
+            .name()
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// The [`ConfigTooltip`] of the field.
+    pub fn tooltip(self) -> &'static str {
+        self.info()
+            .get_attribute::<ConfigTooltip>()
+            .map(|tooltip| tooltip.0)
+            .unwrap_or_default()
+    }
+
+    /// The [`ConfigUnit`] of the field.
+    pub fn unit(self) -> Option<ConfigUnit> {
+        self.info().get_attribute::<ConfigUnit>().copied()
+    }
+
+    /// The smallest and largest sensible value, in the units of [`Self::get`].
+    pub fn range(self) -> (f32, f32) {
+        let range = self
+            .info()
+            .get_attribute::<ConfigRange>()
+            .copied()
+            .unwrap_or(ConfigRange::new(f32::MIN, f32::MAX));
+        if self.unit() == Some(ConfigUnit::Radians) {
+            (range.min.to_degrees(), range.max.to_degrees())
+        } else {
+            (range.min, range.max)
+        }
+    }
+
+    /// How much a step of the value changes it, in the units of [`Self::get`]. Defaults to 1.
+    pub fn step(self) -> f32 {
+        let step = self
+            .info()
+            .get_attribute::<ConfigStep>()
+            .map_or(1.0, |step| step.0);
+        if self.unit() == Some(ConfigUnit::Radians) {
+            step.to_degrees()
+        } else {
+            step
+        }
+    }
+
+    /// Whether the field only takes whole numbers.
+    pub fn is_integer(self) -> bool {
+        self.info().is::<u16>() || self.unit() == Some(ConfigUnit::Count)
+    }
+
+    /// Reads the value of the field.
+    pub fn get(self, config: &NavmeshConfigBuilder) -> f32 {
+        let Some(field) = config.field_at(self.0) else {
+            return 0.0;
+        };
+        let value = match field.try_downcast_ref::<u16>() {
+            Some(value) => f32::from(*value),
+            None => field.try_downcast_ref::<f32>().copied().unwrap_or_default(),
+        };
+        if self.unit() == Some(ConfigUnit::Radians) {
+            value.to_degrees()
+        } else {
+            value
+        }
+    }
+
+    /// Writes the value of the field, rounding it for `u16` fields.
+    pub fn set(self, config: &mut NavmeshConfigBuilder, value: f32) {
+        let value = if self.unit() == Some(ConfigUnit::Radians) {
+            value.to_radians()
+        } else {
+            value
+        };
+        let Some(field) = config.field_at_mut(self.0) else {
+            return;
+        };
+        if let Some(target) = field.try_downcast_mut::<u16>() {
+            *target = value.round() as u16;
+        } else if let Some(target) = field.try_downcast_mut::<f32>() {
+            *target = value;
         }
     }
+
+    /// The value converted to cells the way [`NavmeshConfigBuilder::build`] does, honoring the [`ConfigRounding`].
+    ///
+    /// Returns `None` for fields that are not in [`ConfigUnit::HorizontalWorldUnits`] or [`ConfigUnit::VerticalWorldUnits`].
+    pub fn cells(self, config: &NavmeshConfigBuilder) -> Option<f32> {
+        let cells = match self.unit()? {
+            ConfigUnit::HorizontalWorldUnits => self.get(config) / config.cell_size,
+            ConfigUnit::VerticalWorldUnits => self.get(config) / config.cell_height,
+            _ => return None,
+        };
+        Some(match self.info().get_attribute::<ConfigRounding>() {
+            Some(ConfigRounding::Ceil) => cells.ceil(),
+            Some(ConfigRounding::Floor) => cells.floor(),
+            None => cells,
+        })
+    }
+
+    /// The value converted to world units, for fields in [`ConfigUnit::Voxels`], [`ConfigUnit::CellSizes`]
+    /// or [`ConfigUnit::CellHeights`].
+    pub fn world_units(self, config: &NavmeshConfigBuilder) -> Option<f32> {
+        match self.unit()? {
+            ConfigUnit::Voxels | ConfigUnit::CellSizes => Some(self.get(config) * config.cell_size),
+            ConfigUnit::CellHeights => Some(self.get(config) * config.cell_height),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is below the [`ConfigDisabledBelow`] of the field.
+    pub fn is_disabled(self, config: &NavmeshConfigBuilder) -> bool {
+        self.info()
+            .get_attribute::<ConfigDisabledBelow>()
+            .is_some_and(|threshold| self.get(config) < threshold.0)
+    }
+}
+
+#[cfg(feature = "bevy_reflect")]
+fn builder_info() -> &'static StructInfo {
+    NavmeshConfigBuilder::type_info()
+        .as_struct()
+        .expect("`NavmeshConfigBuilder` is a struct")
+}
+
+#[cfg(all(test, feature = "bevy_reflect"))]
+mod tests {
+    use bevy_reflect::{Struct as _, Typed as _};
+
+    use super::*;
+
+    #[test]
+    fn numeric_builder_fields_are_annotated() {
+        let info = NavmeshConfigBuilder::type_info().as_struct().unwrap();
+        let config = NavmeshConfigBuilder::default();
+        for (index, field) in info.iter().enumerate() {
+            let value = config.field_at(index).unwrap();
+            let value = if let Some(value) = value.try_downcast_ref::<f32>() {
+                *value
+            } else if let Some(value) = value.try_downcast_ref::<u16>() {
+                f32::from(*value)
+            } else {
+                continue;
+            };
+            let name = field.name();
+            let range = field
+                .get_attribute::<ConfigRange>()
+                .unwrap_or_else(|| panic!("`{name}` has no `ConfigRange`"));
+            assert!(
+                (range.min..=range.max).contains(&value),
+                "the default of `{name}` is outside of its `ConfigRange`"
+            );
+            assert!(
+                field.has_attribute::<ConfigTooltip>(),
+                "`{name}` has no `ConfigTooltip`"
+            );
+            assert!(
+                field.has_attribute::<ConfigStep>(),
+                "`{name}` has no `ConfigStep`"
+            );
+        }
+    }
+
+    #[test]
+    fn fields_are_converted_to_cells_like_the_build_does() {
+        let config = NavmeshConfigBuilder {
+            cell_size: 0.3,
+            cell_height: 0.2,
+            agent_height: 1.7,
+            agent_radius: 0.5,
+            agent_max_climb: 0.5,
+            edge_max_len: 10.0,
+            detail_sample_dist: 0.5,
+            ..Default::default()
+        };
+        let built = config.build();
+        let cells = |name: &str| {
+            ConfigField::all()
+                .find(|field| field.info().name() == name)
+                .and_then(|field| field.cells(&config))
+                .unwrap()
+        };
+        assert_eq!(cells("agent_height"), f32::from(built.walkable_height));
+        assert_eq!(cells("agent_radius"), f32::from(built.walkable_radius));
+        assert_eq!(cells("agent_max_climb"), f32::from(built.walkable_climb));
+        assert_eq!(cells("edge_max_len"), f32::from(built.max_edge_len));
+
+        let detail_sample_dist = ConfigField::all()
+            .find(|field| field.info().name() == "detail_sample_dist")
+            .unwrap();
+        assert!(detail_sample_dist.is_disabled(&config));
+        assert_eq!(built.detail_sample_dist, 0.0);
+    }
 }
@@ -0,0 +1,854 @@
+//! Versioned wrappers around [`NavmeshConfig`] and [`NavmeshConfigBuilder`] for storing them across versions of rerecast.
+//!
+//! Each layout of a config that was ever saved gets its own variant. Loading an older variant and calling
+//! `into_latest` migrates it one version at a time, filling in the defaults for everything that was added since.
+//!
+//! The variants are externally tagged, so the wrappers work with both self-describing formats like JSON or RON
+//! and compact ones like bincode. New versions must therefore only ever be appended.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Aabb3d, BuildContoursFlags, DetailSampling, ErosionMethod, LedgeFilter, NavmeshConfig,
+    NavmeshConfigBuilder, RegionPartitioning,
+};
+
+/// A [`NavmeshConfig`] in any layout it was ever saved in.
+///
+/// Save configs as `VersionedNavmeshConfig::from(config)` and load them with [`Self::into_latest`]
+/// instead of using [`NavmeshConfig`] directly, so that files keep loading when fields are added.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VersionedNavmeshConfig {
+    /// The layout before [`NavmeshConfig::erosion_method`], [`NavmeshConfig::max_region_extent`]
+    /// and [`NavmeshConfig::cache_polygon_geometry`] were added.
+    V1(NavmeshConfigV1),
+    /// The layout before [`NavmeshConfig::slope_cost`] was added.
+    V2(NavmeshConfigV2),
+    /// The layout before [`NavmeshConfig::region_partitioning`] was added.
+    V3(NavmeshConfigV3),
+    /// The layout before [`NavmeshConfig::detail_sampling`] was added.
+    V4(NavmeshConfigV4),
+    /// The layout before [`NavmeshConfig::ledge_filter`] was added.
+    V5(NavmeshConfigV5),
+    /// The current layout.
+    V6(NavmeshConfig),
+}
+
+impl VersionedNavmeshConfig {
+    /// The version of the layout of [`NavmeshConfig`] itself.
+    pub const LATEST_VERSION: u16 = 6;
+
+    /// The version of the layout this config was saved in.
+    pub fn version(&self) -> u16 {
+        match self {
+            Self::V1(_) => 1,
+            Self::V2(_) => 2,
+            Self::V3(_) => 3,
+            Self::V4(_) => 4,
+            Self::V5(_) => 5,
+            Self::V6(_) => 6,
+        }
+    }
+
+    /// Migrates the config to the current layout.
+    pub fn into_latest(self) -> NavmeshConfig {
+        match self {
+            Self::V1(config) => Self::V2(config.into()).into_latest(),
+            Self::V2(config) => Self::V3(config.into()).into_latest(),
+            Self::V3(config) => Self::V4(config.into()).into_latest(),
+            Self::V4(config) => Self::V5(config.into()).into_latest(),
+            Self::V5(config) => Self::V6(config.into()).into_latest(),
+            Self::V6(config) => config,
+        }
+    }
+}
+
+impl From<NavmeshConfig> for VersionedNavmeshConfig {
+    fn from(config: NavmeshConfig) -> Self {
+        Self::V6(config)
+    }
+}
+
+/// Version 1 of [`NavmeshConfig`]. See there for the meaning of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[expect(missing_docs, reason = "The fields are documented on `NavmeshConfig`.")]
+pub struct NavmeshConfigV1 {
+    pub width: u16,
+    pub height: u16,
+    pub tile_size: u16,
+    pub border_size: u16,
+    pub cell_size: f32,
+    pub cell_height: f32,
+    pub aabb: Aabb3d,
+    pub walkable_slope_angle: f32,
+    pub walkable_height: u16,
+    pub walkable_climb: u16,
+    pub walkable_radius: u16,
+    pub max_edge_len: u16,
+    pub max_simplification_error: f32,
+    pub min_region_area: u16,
+    pub merge_region_area: u16,
+    pub max_vertices_per_polygon: u16,
+    pub detail_sample_dist: f32,
+    pub detail_sample_max_error: f32,
+    pub contour_flags: BuildContoursFlags,
+}
+
+impl From<NavmeshConfigV1> for NavmeshConfigV2 {
+    fn from(config: NavmeshConfigV1) -> Self {
+        Self {
+            width: config.width,
+            height: config.height,
+            tile_size: config.tile_size,
+            border_size: config.border_size,
+            cell_size: config.cell_size,
+            cell_height: config.cell_height,
+            aabb: config.aabb,
+            walkable_slope_angle: config.walkable_slope_angle,
+            walkable_height: config.walkable_height,
+            walkable_climb: config.walkable_climb,
+            walkable_radius: config.walkable_radius,
+            max_edge_len: config.max_edge_len,
+            max_simplification_error: config.max_simplification_error,
+            min_region_area: config.min_region_area,
+            merge_region_area: config.merge_region_area,
+            // Regions were never split before.
+            max_region_extent: None,
+            max_vertices_per_polygon: config.max_vertices_per_polygon,
+            detail_sample_dist: config.detail_sample_dist,
+            detail_sample_max_error: config.detail_sample_max_error,
+            contour_flags: config.contour_flags,
+            erosion_method: ErosionMethod::default(),
+            cache_polygon_geometry: false,
+        }
+    }
+}
+
+/// Version 2 of [`NavmeshConfig`]. See there for the meaning of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[expect(missing_docs, reason = "The fields are documented on `NavmeshConfig`.")]
+pub struct NavmeshConfigV2 {
+    pub width: u16,
+    pub height: u16,
+    pub tile_size: u16,
+    pub border_size: u16,
+    pub cell_size: f32,
+    pub cell_height: f32,
+    pub aabb: Aabb3d,
+    pub walkable_slope_angle: f32,
+    pub walkable_height: u16,
+    pub walkable_climb: u16,
+    pub walkable_radius: u16,
+    pub max_edge_len: u16,
+    pub max_simplification_error: f32,
+    pub min_region_area: u16,
+    pub merge_region_area: u16,
+    pub max_region_extent: Option<u16>,
+    pub max_vertices_per_polygon: u16,
+    pub detail_sample_dist: f32,
+    pub detail_sample_max_error: f32,
+    pub contour_flags: BuildContoursFlags,
+    pub erosion_method: ErosionMethod,
+    pub cache_polygon_geometry: bool,
+}
+
+impl From<NavmeshConfigV2> for NavmeshConfigV3 {
+    fn from(config: NavmeshConfigV2) -> Self {
+        Self {
+            width: config.width,
+            height: config.height,
+            tile_size: config.tile_size,
+            border_size: config.border_size,
+            cell_size: config.cell_size,
+            cell_height: config.cell_height,
+            aabb: config.aabb,
+            walkable_slope_angle: config.walkable_slope_angle,
+            walkable_height: config.walkable_height,
+            walkable_climb: config.walkable_climb,
+            walkable_radius: config.walkable_radius,
+            max_edge_len: config.max_edge_len,
+            max_simplification_error: config.max_simplification_error,
+            min_region_area: config.min_region_area,
+            merge_region_area: config.merge_region_area,
+            max_region_extent: config.max_region_extent,
+            max_vertices_per_polygon: config.max_vertices_per_polygon,
+            detail_sample_dist: config.detail_sample_dist,
+            detail_sample_max_error: config.detail_sample_max_error,
+            contour_flags: config.contour_flags,
+            erosion_method: config.erosion_method,
+            cache_polygon_geometry: config.cache_polygon_geometry,
+            // Slopes were never measured before.
+            slope_cost: 0.0,
+        }
+    }
+}
+
+/// Version 3 of [`NavmeshConfig`]. See there for the meaning of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[expect(missing_docs, reason = "The fields are documented on `NavmeshConfig`.")]
+pub struct NavmeshConfigV3 {
+    pub width: u16,
+    pub height: u16,
+    pub tile_size: u16,
+    pub border_size: u16,
+    pub cell_size: f32,
+    pub cell_height: f32,
+    pub aabb: Aabb3d,
+    pub walkable_slope_angle: f32,
+    pub walkable_height: u16,
+    pub walkable_climb: u16,
+    pub walkable_radius: u16,
+    pub max_edge_len: u16,
+    pub max_simplification_error: f32,
+    pub min_region_area: u16,
+    pub merge_region_area: u16,
+    pub max_region_extent: Option<u16>,
+    pub max_vertices_per_polygon: u16,
+    pub detail_sample_dist: f32,
+    pub detail_sample_max_error: f32,
+    pub contour_flags: BuildContoursFlags,
+    pub erosion_method: ErosionMethod,
+    pub cache_polygon_geometry: bool,
+    pub slope_cost: f32,
+}
+
+impl From<NavmeshConfigV3> for NavmeshConfigV4 {
+    fn from(config: NavmeshConfigV3) -> Self {
+        Self {
+            width: config.width,
+            height: config.height,
+            tile_size: config.tile_size,
+            border_size: config.border_size,
+            cell_size: config.cell_size,
+            cell_height: config.cell_height,
+            aabb: config.aabb,
+            walkable_slope_angle: config.walkable_slope_angle,
+            walkable_height: config.walkable_height,
+            walkable_climb: config.walkable_climb,
+            walkable_radius: config.walkable_radius,
+            max_edge_len: config.max_edge_len,
+            max_simplification_error: config.max_simplification_error,
+            min_region_area: config.min_region_area,
+            merge_region_area: config.merge_region_area,
+            max_region_extent: config.max_region_extent,
+            max_vertices_per_polygon: config.max_vertices_per_polygon,
+            detail_sample_dist: config.detail_sample_dist,
+            detail_sample_max_error: config.detail_sample_max_error,
+            contour_flags: config.contour_flags,
+            erosion_method: config.erosion_method,
+            cache_polygon_geometry: config.cache_polygon_geometry,
+            slope_cost: config.slope_cost,
+            // Regions were always partitioned with the watershed before.
+            region_partitioning: RegionPartitioning::Watershed,
+        }
+    }
+}
+
+/// Version 4 of [`NavmeshConfig`]. See there for the meaning of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[expect(missing_docs, reason = "The fields are documented on `NavmeshConfig`.")]
+pub struct NavmeshConfigV4 {
+    pub width: u16,
+    pub height: u16,
+    pub tile_size: u16,
+    pub border_size: u16,
+    pub cell_size: f32,
+    pub cell_height: f32,
+    pub aabb: Aabb3d,
+    pub walkable_slope_angle: f32,
+    pub walkable_height: u16,
+    pub walkable_climb: u16,
+    pub walkable_radius: u16,
+    pub max_edge_len: u16,
+    pub max_simplification_error: f32,
+    pub min_region_area: u16,
+    pub merge_region_area: u16,
+    pub max_region_extent: Option<u16>,
+    pub max_vertices_per_polygon: u16,
+    pub detail_sample_dist: f32,
+    pub detail_sample_max_error: f32,
+    pub contour_flags: BuildContoursFlags,
+    pub erosion_method: ErosionMethod,
+    pub cache_polygon_geometry: bool,
+    pub slope_cost: f32,
+    pub region_partitioning: RegionPartitioning,
+}
+
+impl From<NavmeshConfigV4> for NavmeshConfigV5 {
+    fn from(config: NavmeshConfigV4) -> Self {
+        Self {
+            width: config.width,
+            height: config.height,
+            tile_size: config.tile_size,
+            border_size: config.border_size,
+            cell_size: config.cell_size,
+            cell_height: config.cell_height,
+            aabb: config.aabb,
+            walkable_slope_angle: config.walkable_slope_angle,
+            walkable_height: config.walkable_height,
+            walkable_climb: config.walkable_climb,
+            walkable_radius: config.walkable_radius,
+            max_edge_len: config.max_edge_len,
+            max_simplification_error: config.max_simplification_error,
+            min_region_area: config.min_region_area,
+            merge_region_area: config.merge_region_area,
+            max_region_extent: config.max_region_extent,
+            max_vertices_per_polygon: config.max_vertices_per_polygon,
+            detail_sample_dist: config.detail_sample_dist,
+            detail_sample_max_error: config.detail_sample_max_error,
+            detail_sampling: DetailSampling::Grid,
+            contour_flags: config.contour_flags,
+            erosion_method: config.erosion_method,
+            cache_polygon_geometry: config.cache_polygon_geometry,
+            slope_cost: config.slope_cost,
+            region_partitioning: config.region_partitioning,
+        }
+    }
+}
+
+/// Version 5 of [`NavmeshConfig`]. See there for the meaning of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[expect(missing_docs, reason = "The fields are documented on `NavmeshConfig`.")]
+pub struct NavmeshConfigV5 {
+    pub width: u16,
+    pub height: u16,
+    pub tile_size: u16,
+    pub border_size: u16,
+    pub cell_size: f32,
+    pub cell_height: f32,
+    pub aabb: Aabb3d,
+    pub walkable_slope_angle: f32,
+    pub walkable_height: u16,
+    pub walkable_climb: u16,
+    pub walkable_radius: u16,
+    pub max_edge_len: u16,
+    pub max_simplification_error: f32,
+    pub min_region_area: u16,
+    pub merge_region_area: u16,
+    pub max_region_extent: Option<u16>,
+    pub max_vertices_per_polygon: u16,
+    pub detail_sample_dist: f32,
+    pub detail_sample_max_error: f32,
+    pub detail_sampling: DetailSampling,
+    pub contour_flags: BuildContoursFlags,
+    pub erosion_method: ErosionMethod,
+    pub cache_polygon_geometry: bool,
+    pub slope_cost: f32,
+    pub region_partitioning: RegionPartitioning,
+}
+
+impl From<NavmeshConfigV5> for NavmeshConfig {
+    fn from(config: NavmeshConfigV5) -> Self {
+        Self {
+            width: config.width,
+            height: config.height,
+            tile_size: config.tile_size,
+            border_size: config.border_size,
+            cell_size: config.cell_size,
+            cell_height: config.cell_height,
+            aabb: config.aabb,
+            walkable_slope_angle: config.walkable_slope_angle,
+            walkable_height: config.walkable_height,
+            walkable_climb: config.walkable_climb,
+            walkable_radius: config.walkable_radius,
+            max_edge_len: config.max_edge_len,
+            max_simplification_error: config.max_simplification_error,
+            min_region_area: config.min_region_area,
+            merge_region_area: config.merge_region_area,
+            max_region_extent: config.max_region_extent,
+            max_vertices_per_polygon: config.max_vertices_per_polygon,
+            detail_sample_dist: config.detail_sample_dist,
+            detail_sample_max_error: config.detail_sample_max_error,
+            detail_sampling: config.detail_sampling,
+            contour_flags: config.contour_flags,
+            // Ramps were never treated differently before.
+            ledge_filter: LedgeFilter::Reference,
+            erosion_method: config.erosion_method,
+            cache_polygon_geometry: config.cache_polygon_geometry,
+            slope_cost: config.slope_cost,
+            region_partitioning: config.region_partitioning,
+        }
+    }
+}
+
+/// A [`NavmeshConfigBuilder`] in any layout it was ever saved in, e.g. as a preset of the editor.
+///
+/// Works just like [`VersionedNavmeshConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VersionedNavmeshConfigBuilder {
+    /// The layout before [`NavmeshConfigBuilder::max_region_extent`], [`NavmeshConfigBuilder::erosion_method`],
+    /// [`NavmeshConfigBuilder::cache_polygon_geometry`] and [`NavmeshConfigBuilder::border_size`] were added.
+    V1(NavmeshConfigBuilderV1),
+    /// The layout before [`NavmeshConfigBuilder::slope_cost`] was added.
+    V2(NavmeshConfigBuilderV2),
+    /// The layout before [`NavmeshConfigBuilder::region_partitioning`] was added.
+    V3(NavmeshConfigBuilderV3),
+    /// The layout before [`NavmeshConfigBuilder::detail_sampling`] was added.
+    V4(NavmeshConfigBuilderV4),
+    /// The layout before [`NavmeshConfigBuilder::ledge_filter`] was added.
+    V5(NavmeshConfigBuilderV5),
+    /// The current layout.
+    V6(NavmeshConfigBuilder),
+}
+
+impl VersionedNavmeshConfigBuilder {
+    /// The version of the layout of [`NavmeshConfigBuilder`] itself.
+    pub const LATEST_VERSION: u16 = 6;
+
+    /// The version of the layout this builder was saved in.
+    pub fn version(&self) -> u16 {
+        match self {
+            Self::V1(_) => 1,
+            Self::V2(_) => 2,
+            Self::V3(_) => 3,
+            Self::V4(_) => 4,
+            Self::V5(_) => 5,
+            Self::V6(_) => 6,
+        }
+    }
+
+    /// Migrates the builder to the current layout.
+    pub fn into_latest(self) -> NavmeshConfigBuilder {
+        match self {
+            Self::V1(builder) => Self::V2(builder.into()).into_latest(),
+            Self::V2(builder) => Self::V3(builder.into()).into_latest(),
+            Self::V3(builder) => Self::V4(builder.into()).into_latest(),
+            Self::V4(builder) => Self::V5(builder.into()).into_latest(),
+            Self::V5(builder) => Self::V6(builder.into()).into_latest(),
+            Self::V6(builder) => builder,
+        }
+    }
+}
+
+impl From<NavmeshConfigBuilder> for VersionedNavmeshConfigBuilder {
+    fn from(builder: NavmeshConfigBuilder) -> Self {
+        Self::V6(builder)
+    }
+}
+
+/// Version 1 of [`NavmeshConfigBuilder`]. See there for the meaning of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[expect(
+    missing_docs,
+    reason = "The fields are documented on `NavmeshConfigBuilder`."
+)]
+pub struct NavmeshConfigBuilderV1 {
+    pub cell_size: f32,
+    pub cell_height: f32,
+    pub agent_height: f32,
+    pub agent_radius: f32,
+    pub agent_max_climb: f32,
+    pub agent_max_slope: f32,
+    pub region_min_size: f32,
+    pub region_merge_size: f32,
+    pub edge_max_len: f32,
+    pub edge_max_error: f32,
+    pub verts_per_poly: f32,
+    pub detail_sample_dist: f32,
+    pub detail_sample_max_error: f32,
+    pub tile_size: u16,
+    pub aabb: Aabb3d,
+    pub contour_flags: BuildContoursFlags,
+    pub tiling: bool,
+}
+
+impl From<NavmeshConfigBuilderV1> for NavmeshConfigBuilderV2 {
+    fn from(builder: NavmeshConfigBuilderV1) -> Self {
+        Self {
+            cell_size: builder.cell_size,
+            cell_height: builder.cell_height,
+            agent_height: builder.agent_height,
+            agent_radius: builder.agent_radius,
+            agent_max_climb: builder.agent_max_climb,
+            agent_max_slope: builder.agent_max_slope,
+            region_min_size: builder.region_min_size,
+            region_merge_size: builder.region_merge_size,
+            edge_max_len: builder.edge_max_len,
+            edge_max_error: builder.edge_max_error,
+            verts_per_poly: builder.verts_per_poly,
+            detail_sample_dist: builder.detail_sample_dist,
+            detail_sample_max_error: builder.detail_sample_max_error,
+            tile_size: builder.tile_size,
+            aabb: builder.aabb,
+            contour_flags: builder.contour_flags,
+            tiling: builder.tiling,
+            max_region_extent: None,
+            erosion_method: ErosionMethod::default(),
+            cache_polygon_geometry: false,
+            border_size: None,
+        }
+    }
+}
+
+/// Version 2 of [`NavmeshConfigBuilder`]. See there for the meaning of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[expect(
+    missing_docs,
+    reason = "The fields are documented on `NavmeshConfigBuilder`."
+)]
+pub struct NavmeshConfigBuilderV2 {
+    pub cell_size: f32,
+    pub cell_height: f32,
+    pub agent_height: f32,
+    pub agent_radius: f32,
+    pub agent_max_climb: f32,
+    pub agent_max_slope: f32,
+    pub region_min_size: f32,
+    pub region_merge_size: f32,
+    pub edge_max_len: f32,
+    pub edge_max_error: f32,
+    pub verts_per_poly: f32,
+    pub detail_sample_dist: f32,
+    pub detail_sample_max_error: f32,
+    pub max_region_extent: Option<u16>,
+    pub tile_size: u16,
+    pub aabb: Aabb3d,
+    pub contour_flags: BuildContoursFlags,
+    pub erosion_method: ErosionMethod,
+    pub cache_polygon_geometry: bool,
+    pub tiling: bool,
+    pub border_size: Option<u16>,
+}
+
+impl From<NavmeshConfigBuilderV2> for NavmeshConfigBuilderV3 {
+    fn from(builder: NavmeshConfigBuilderV2) -> Self {
+        Self {
+            cell_size: builder.cell_size,
+            cell_height: builder.cell_height,
+            agent_height: builder.agent_height,
+            agent_radius: builder.agent_radius,
+            agent_max_climb: builder.agent_max_climb,
+            agent_max_slope: builder.agent_max_slope,
+            region_min_size: builder.region_min_size,
+            region_merge_size: builder.region_merge_size,
+            edge_max_len: builder.edge_max_len,
+            edge_max_error: builder.edge_max_error,
+            verts_per_poly: builder.verts_per_poly,
+            detail_sample_dist: builder.detail_sample_dist,
+            detail_sample_max_error: builder.detail_sample_max_error,
+            max_region_extent: builder.max_region_extent,
+            tile_size: builder.tile_size,
+            aabb: builder.aabb,
+            contour_flags: builder.contour_flags,
+            erosion_method: builder.erosion_method,
+            cache_polygon_geometry: builder.cache_polygon_geometry,
+            slope_cost: 0.0,
+            tiling: builder.tiling,
+            border_size: builder.border_size,
+        }
+    }
+}
+
+/// Version 3 of [`NavmeshConfigBuilder`]. See there for the meaning of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[expect(
+    missing_docs,
+    reason = "The fields are documented on `NavmeshConfigBuilder`."
+)]
+pub struct NavmeshConfigBuilderV3 {
+    pub cell_size: f32,
+    pub cell_height: f32,
+    pub agent_height: f32,
+    pub agent_radius: f32,
+    pub agent_max_climb: f32,
+    pub agent_max_slope: f32,
+    pub region_min_size: f32,
+    pub region_merge_size: f32,
+    pub edge_max_len: f32,
+    pub edge_max_error: f32,
+    pub verts_per_poly: f32,
+    pub detail_sample_dist: f32,
+    pub detail_sample_max_error: f32,
+    pub max_region_extent: Option<u16>,
+    pub tile_size: u16,
+    pub aabb: Aabb3d,
+    pub contour_flags: BuildContoursFlags,
+    pub erosion_method: ErosionMethod,
+    pub cache_polygon_geometry: bool,
+    pub slope_cost: f32,
+    pub tiling: bool,
+    pub border_size: Option<u16>,
+}
+
+impl From<NavmeshConfigBuilderV3> for NavmeshConfigBuilderV4 {
+    fn from(builder: NavmeshConfigBuilderV3) -> Self {
+        Self {
+            cell_size: builder.cell_size,
+            cell_height: builder.cell_height,
+            agent_height: builder.agent_height,
+            agent_radius: builder.agent_radius,
+            agent_max_climb: builder.agent_max_climb,
+            agent_max_slope: builder.agent_max_slope,
+            region_min_size: builder.region_min_size,
+            region_merge_size: builder.region_merge_size,
+            edge_max_len: builder.edge_max_len,
+            edge_max_error: builder.edge_max_error,
+            verts_per_poly: builder.verts_per_poly,
+            detail_sample_dist: builder.detail_sample_dist,
+            detail_sample_max_error: builder.detail_sample_max_error,
+            max_region_extent: builder.max_region_extent,
+            tile_size: builder.tile_size,
+            aabb: builder.aabb,
+            contour_flags: builder.contour_flags,
+            erosion_method: builder.erosion_method,
+            cache_polygon_geometry: builder.cache_polygon_geometry,
+            slope_cost: builder.slope_cost,
+            // Regions were always partitioned with the watershed before.
+            region_partitioning: RegionPartitioning::Watershed,
+            tiling: builder.tiling,
+            border_size: builder.border_size,
+        }
+    }
+}
+
+/// Version 4 of [`NavmeshConfigBuilder`]. See there for the meaning of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[expect(
+    missing_docs,
+    reason = "The fields are documented on `NavmeshConfigBuilder`."
+)]
+pub struct NavmeshConfigBuilderV4 {
+    pub cell_size: f32,
+    pub cell_height: f32,
+    pub agent_height: f32,
+    pub agent_radius: f32,
+    pub agent_max_climb: f32,
+    pub agent_max_slope: f32,
+    pub region_min_size: f32,
+    pub region_merge_size: f32,
+    pub edge_max_len: f32,
+    pub edge_max_error: f32,
+    pub verts_per_poly: f32,
+    pub detail_sample_dist: f32,
+    pub detail_sample_max_error: f32,
+    pub max_region_extent: Option<u16>,
+    pub tile_size: u16,
+    pub aabb: Aabb3d,
+    pub contour_flags: BuildContoursFlags,
+    pub erosion_method: ErosionMethod,
+    pub cache_polygon_geometry: bool,
+    pub slope_cost: f32,
+    pub region_partitioning: RegionPartitioning,
+    pub tiling: bool,
+    pub border_size: Option<u16>,
+}
+
+impl From<NavmeshConfigBuilderV4> for NavmeshConfigBuilderV5 {
+    fn from(builder: NavmeshConfigBuilderV4) -> Self {
+        Self {
+            cell_size: builder.cell_size,
+            cell_height: builder.cell_height,
+            agent_height: builder.agent_height,
+            agent_radius: builder.agent_radius,
+            agent_max_climb: builder.agent_max_climb,
+            agent_max_slope: builder.agent_max_slope,
+            region_min_size: builder.region_min_size,
+            region_merge_size: builder.region_merge_size,
+            edge_max_len: builder.edge_max_len,
+            edge_max_error: builder.edge_max_error,
+            verts_per_poly: builder.verts_per_poly,
+            detail_sample_dist: builder.detail_sample_dist,
+            detail_sample_max_error: builder.detail_sample_max_error,
+            detail_sampling: DetailSampling::Grid,
+            max_region_extent: builder.max_region_extent,
+            tile_size: builder.tile_size,
+            aabb: builder.aabb,
+            contour_flags: builder.contour_flags,
+            erosion_method: builder.erosion_method,
+            cache_polygon_geometry: builder.cache_polygon_geometry,
+            slope_cost: builder.slope_cost,
+            region_partitioning: builder.region_partitioning,
+            tiling: builder.tiling,
+            border_size: builder.border_size,
+        }
+    }
+}
+
+/// Version 5 of [`NavmeshConfigBuilder`]. See there for the meaning of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[expect(
+    missing_docs,
+    reason = "The fields are documented on `NavmeshConfigBuilder`."
+)]
+pub struct NavmeshConfigBuilderV5 {
+    pub cell_size: f32,
+    pub cell_height: f32,
+    pub agent_height: f32,
+    pub agent_radius: f32,
+    pub agent_max_climb: f32,
+    pub agent_max_slope: f32,
+    pub region_min_size: f32,
+    pub region_merge_size: f32,
+    pub edge_max_len: f32,
+    pub edge_max_error: f32,
+    pub verts_per_poly: f32,
+    pub detail_sample_dist: f32,
+    pub detail_sample_max_error: f32,
+    pub detail_sampling: DetailSampling,
+    pub max_region_extent: Option<u16>,
+    pub tile_size: u16,
+    pub aabb: Aabb3d,
+    pub contour_flags: BuildContoursFlags,
+    pub erosion_method: ErosionMethod,
+    pub cache_polygon_geometry: bool,
+    pub slope_cost: f32,
+    pub region_partitioning: RegionPartitioning,
+    pub tiling: bool,
+    pub border_size: Option<u16>,
+}
+
+impl From<NavmeshConfigBuilderV5> for NavmeshConfigBuilder {
+    fn from(builder: NavmeshConfigBuilderV5) -> Self {
+        Self {
+            cell_size: builder.cell_size,
+            cell_height: builder.cell_height,
+            agent_height: builder.agent_height,
+            agent_radius: builder.agent_radius,
+            agent_max_climb: builder.agent_max_climb,
+            agent_max_slope: builder.agent_max_slope,
+            region_min_size: builder.region_min_size,
+            region_merge_size: builder.region_merge_size,
+            edge_max_len: builder.edge_max_len,
+            edge_max_error: builder.edge_max_error,
+            verts_per_poly: builder.verts_per_poly,
+            detail_sample_dist: builder.detail_sample_dist,
+            detail_sample_max_error: builder.detail_sample_max_error,
+            detail_sampling: builder.detail_sampling,
+            max_region_extent: builder.max_region_extent,
+            tile_size: builder.tile_size,
+            aabb: builder.aabb,
+            contour_flags: builder.contour_flags,
+            // Ramps were never treated differently before.
+            ledge_filter: LedgeFilter::Reference,
+            erosion_method: builder.erosion_method,
+            cache_polygon_geometry: builder.cache_polygon_geometry,
+            slope_cost: builder.slope_cost,
+            region_partitioning: builder.region_partitioning,
+            tiling: builder.tiling,
+            border_size: builder.border_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    #[test]
+    fn old_builders_load_with_defaults() {
+        let v1 = NavmeshConfigBuilderV1 {
+            cell_size: 0.25,
+            cell_height: 0.1,
+            agent_height: 1.8,
+            agent_radius: 0.4,
+            agent_max_climb: 0.5,
+            agent_max_slope: 0.7,
+            region_min_size: 4.0,
+            region_merge_size: 10.0,
+            edge_max_len: 8.0,
+            edge_max_error: 1.1,
+            verts_per_poly: 5.0,
+            detail_sample_dist: 3.0,
+            detail_sample_max_error: 2.0,
+            tile_size: 64,
+            aabb: Aabb3d::new(Vec3::ZERO, Vec3::ONE),
+            contour_flags: BuildContoursFlags::empty(),
+            tiling: true,
+        };
+        let json = serde_json::to_string(&VersionedNavmeshConfigBuilder::V1(v1)).unwrap();
+        let loaded: VersionedNavmeshConfigBuilder = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.version(), 1);
+
+        let builder = loaded.into_latest();
+        assert_eq!(builder.cell_size, 0.25);
+        assert_eq!(builder.tile_size, 64);
+        assert!(builder.tiling);
+        assert_eq!(builder.contour_flags, BuildContoursFlags::empty());
+        assert_eq!(builder.max_region_extent, None);
+        assert_eq!(builder.border_size, None);
+        assert_eq!(builder.erosion_method, ErosionMethod::default());
+        assert!(!builder.cache_polygon_geometry);
+        assert_eq!(builder.slope_cost, 0.0);
+        assert_eq!(builder.region_partitioning, RegionPartitioning::Watershed);
+        assert_eq!(builder.detail_sampling, DetailSampling::Grid);
+        assert_eq!(builder.ledge_filter, LedgeFilter::Reference);
+
+        // Builders build the same config no matter which version they were loaded from.
+        let config = VersionedNavmeshConfig::V1(NavmeshConfigV1 {
+            width: 4,
+            height: 4,
+            tile_size: 64,
+            border_size: 7,
+            cell_size: 0.25,
+            cell_height: 0.1,
+            aabb: v1.aabb,
+            walkable_slope_angle: 0.7,
+            walkable_height: 18,
+            walkable_climb: 5,
+            walkable_radius: 2,
+            max_edge_len: 32,
+            max_simplification_error: 1.1,
+            min_region_area: 16,
+            merge_region_area: 100,
+            max_vertices_per_polygon: 5,
+            detail_sample_dist: 0.75,
+            detail_sample_max_error: 0.2,
+            contour_flags: BuildContoursFlags::empty(),
+        });
+        assert_eq!(
+            config.into_latest().walkable_radius,
+            builder.build().walkable_radius
+        );
+
+        let latest = VersionedNavmeshConfigBuilder::from(builder);
+        let json = serde_json::to_string(&latest).unwrap();
+        let loaded: VersionedNavmeshConfigBuilder = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            loaded.version(),
+            VersionedNavmeshConfigBuilder::LATEST_VERSION
+        );
+        assert_eq!(loaded.into_latest(), builder);
+    }
+
+    #[test]
+    fn configs_with_slope_costs_keep_them_and_use_watershed_regions() {
+        let v3 = NavmeshConfigV3 {
+            width: 4,
+            height: 4,
+            tile_size: 0,
+            border_size: 0,
+            cell_size: 0.25,
+            cell_height: 0.1,
+            aabb: Aabb3d::new(Vec3::ZERO, Vec3::ONE),
+            walkable_slope_angle: 0.7,
+            walkable_height: 18,
+            walkable_climb: 5,
+            walkable_radius: 2,
+            max_edge_len: 32,
+            max_simplification_error: 1.1,
+            min_region_area: 16,
+            merge_region_area: 100,
+            max_region_extent: Some(32),
+            max_vertices_per_polygon: 5,
+            detail_sample_dist: 0.75,
+            detail_sample_max_error: 0.2,
+            contour_flags: BuildContoursFlags::empty(),
+            erosion_method: ErosionMethod::default(),
+            cache_polygon_geometry: true,
+            slope_cost: 2.5,
+        };
+        let json = serde_json::to_string(&VersionedNavmeshConfig::V3(v3)).unwrap();
+        let loaded: VersionedNavmeshConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.version(), 3);
+
+        let config = loaded.into_latest();
+        assert_eq!(config.slope_cost, 2.5);
+        assert_eq!(config.max_region_extent, Some(32));
+        assert!(config.cache_polygon_geometry);
+        assert_eq!(config.region_partitioning, RegionPartitioning::Watershed);
+        assert_eq!(config.detail_sampling, DetailSampling::Grid);
+        assert_eq!(config.ledge_filter, LedgeFilter::Reference);
+    }
+}
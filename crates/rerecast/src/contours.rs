@@ -1,8 +1,11 @@
 use glam::{U16Vec3, Vec3Swizzles};
 
 use crate::{
-    Aabb3d, AreaType, CompactHeightfield, RegionId,
-    math::{dir_offset_x, dir_offset_z, distance_squared_between_point_and_line_u16vec2},
+    Aabb3d, AreaType, BuildScratch, CompactHeightfield, RegionId,
+    math::{
+        dir_offset_x, dir_offset_z, distance_squared_between_point_and_line_u16vec2, next, prev,
+    },
+    poly_mesh::{intersect, left, left_on, vequal},
 };
 
 impl CompactHeightfield {
@@ -18,6 +21,22 @@ impl CompactHeightfield {
         max_error: f32,
         max_edge_len: u16,
         build_flags: BuildContoursFlags,
+    ) -> ContourSet {
+        self.build_contours_with_scratch(
+            max_error,
+            max_edge_len,
+            build_flags,
+            &mut BuildScratch::default(),
+        )
+    }
+
+    /// Same as [`CompactHeightfield::build_contours`], but takes the temporary buffers from `scratch`.
+    pub(crate) fn build_contours_with_scratch(
+        &self,
+        max_error: f32,
+        max_edge_len: u16,
+        build_flags: BuildContoursFlags,
+        scratch: &mut BuildScratch,
     ) -> ContourSet {
         let mut cset = ContourSet {
             contours: Vec::new(),
@@ -43,7 +62,7 @@ impl CompactHeightfield {
         cset.contours = vec![Contour::default(); max_contours as usize];
         // We will shrink contours to this value later
         let mut contour_count = 0;
-        let mut flags = vec![0_u8; self.spans.len()];
+        let mut flags = scratch.bytes.take(self.spans.len(), 0);
 
         // Mark boundaries
         for z in 0..self.height {
@@ -116,7 +135,8 @@ impl CompactHeightfield {
                             // This happens when a region has holes.
                             let old_max = max_contours;
                             max_contours *= 2;
-                            cset.contours.truncate(max_contours as usize);
+                            cset.contours
+                                .resize_with(max_contours as usize, Contour::default);
 
                             tracing::warn!(
                                 "Region has holes. Expanding contour set from max {old_max} to max {max_contours}"
@@ -133,12 +153,15 @@ impl CompactHeightfield {
                                 vert.z = vert.z.saturating_sub(self.border_size);
                             }
                         }
-                        cont.raw_vertices = verts.clone();
-                        if self.border_size > 0 {
-                            // If the heightfield was build with bordersize, remove the offset.
-                            for (vert, _) in &mut cont.raw_vertices {
-                                vert.x = vert.x.saturating_sub(self.border_size);
-                                vert.z = vert.z.saturating_sub(self.border_size);
+                        // Recast always keeps the raw vertices, but nothing after this stage reads them.
+                        if !build_flags.contains(BuildContoursFlags::SKIP_RAW_VERTICES) {
+                            cont.raw_vertices = verts.clone();
+                            if self.border_size > 0 {
+                                // If the heightfield was build with bordersize, remove the offset.
+                                for (vert, _) in &mut cont.raw_vertices {
+                                    vert.x = vert.x.saturating_sub(self.border_size);
+                                    vert.z = vert.z.saturating_sub(self.border_size);
+                                }
                             }
                         }
                         cont.region = reg;
@@ -148,6 +171,60 @@ impl CompactHeightfield {
             }
         }
         cset.contours.resize_with(contour_count, Contour::default);
+        scratch.bytes.give(flags);
+
+        // Merge holes if needed.
+        if !cset.contours.is_empty() {
+            // Calculate winding of all polygons.
+            // If the contour is wound backwards, it is a hole.
+            let is_hole = cset
+                .contours
+                .iter()
+                .map(|contour| calc_area_of_polygon_2d(&contour.vertices) < 0)
+                .collect::<Vec<_>>();
+            if is_hole.contains(&true) {
+                // Collect outline contour and holes contours per region.
+                // We assume that there is one outline and multiple holes.
+                // We store indices into the contour set instead of pointers.
+                let mut regions =
+                    vec![ContourRegion::default(); self.max_region.bits() as usize + 1];
+                for (i, contour) in cset.contours.iter().enumerate() {
+                    let region = &mut regions[contour.region.bits() as usize];
+                    // Positively wound contours are outlines, negative holes.
+                    if is_hole[i] {
+                        region.holes.push(ContourHole {
+                            contour: i,
+                            ..Default::default()
+                        });
+                    } else {
+                        if region.outline.is_some() {
+                            tracing::error!(
+                                "build_contours: Multiple outlines for region {}.",
+                                contour.region.bits()
+                            );
+                        }
+                        region.outline = Some(i);
+                    }
+                }
+
+                // Finally merge each regions holes into the outline.
+                for (i, region) in regions.iter_mut().enumerate() {
+                    if region.holes.is_empty() {
+                        continue;
+                    }
+                    if region.outline.is_some() {
+                        merge_region_holes(&mut cset.contours, region);
+                    } else {
+                        // The region does not have an outline.
+                        // This can happen if the contour becomes selfoverlapping because of
+                        // too aggressive simplification settings.
+                        tracing::error!(
+                            "build_contours: Bad outline for region {i}, contour simplification is likely too aggressive."
+                        );
+                    }
+                }
+            }
+        }
         cset
     }
 
@@ -306,7 +383,7 @@ impl CompactHeightfield {
     }
 }
 
-fn simplify_contour(
+pub(crate) fn simplify_contour(
     points: &[(U16Vec3, RegionVertexId)],
     simplified: &mut Vec<(U16Vec3, u32)>,
     max_error: f32,
@@ -491,7 +568,7 @@ fn simplify_contour(
     }
 }
 
-fn remove_degenerate_segments(simplified: &mut Vec<(U16Vec3, u32)>) {
+pub(crate) fn remove_degenerate_segments(simplified: &mut Vec<(U16Vec3, u32)>) {
     // Remove adjacent vertices which are equal on xz-plane,
     // or else the triangulator will get confused.
 
@@ -510,6 +587,178 @@ fn remove_degenerate_segments(simplified: &mut Vec<(U16Vec3, u32)>) {
     }
 }
 
+fn calc_area_of_polygon_2d(vertices: &[(U16Vec3, u32)]) -> i32 {
+    let n = vertices.len();
+    let mut area = 0;
+    for i in 0..n {
+        let vi = vertices[i].0.as_ivec3();
+        let vj = vertices[prev(i, n)].0.as_ivec3();
+        area += vi.x * vj.z - vj.x * vi.z;
+    }
+    (area + 1) / 2
+}
+
+/// Returns true if the segment `d0`-`d1` intersects any edge of the contour that is not incident to vertex `i`.
+fn intersect_seg_contour(
+    d0: U16Vec3,
+    d1: U16Vec3,
+    i: Option<usize>,
+    vertices: &[(U16Vec3, u32)],
+) -> bool {
+    let n = vertices.len();
+    // For each edge (k,k+1) of P
+    for k in 0..n {
+        let k1 = next(k, n);
+        // Skip edges incident to i.
+        if i == Some(k) || i == Some(k1) {
+            continue;
+        }
+        let p0 = vertices[k].0;
+        let p1 = vertices[k1].0;
+        if vequal(d0, p0) || vequal(d1, p0) || vequal(d0, p1) || vequal(d1, p1) {
+            continue;
+        }
+        if intersect(d0, d1, p0, p1) {
+            return true;
+        }
+    }
+    false
+}
+
+fn in_cone(i: usize, vertices: &[(U16Vec3, u32)], pj: U16Vec3) -> bool {
+    let n = vertices.len();
+    let pi = vertices[i].0;
+    let pi1 = vertices[next(i, n)].0;
+    let pin1 = vertices[prev(i, n)].0;
+
+    // If P[i] is a convex vertex [ i+1 left or on (i-1,i) ].
+    if left_on(pin1, pi, pi1) {
+        return left(pi, pj, pin1) && left(pj, pi, pi1);
+    }
+    // Assume (i-1,i,i+1) not collinear.
+    // else P[i] is reflex.
+    !(left_on(pi, pj, pi1) && left_on(pj, pi, pin1))
+}
+
+/// Appends the hole `b` to the outline `a`, connecting vertex `ia` of `a` with vertex `ib` of `b`.
+/// Leaves `b` without vertices.
+fn merge_contours(a: &mut Contour, b: &mut Contour, ia: usize, ib: usize) {
+    let mut vertices = Vec::with_capacity(a.vertices.len() + b.vertices.len() + 2);
+
+    // Copy contour A.
+    for i in 0..=a.vertices.len() {
+        vertices.push(a.vertices[(ia + i) % a.vertices.len()]);
+    }
+    // Copy contour B
+    for i in 0..=b.vertices.len() {
+        vertices.push(b.vertices[(ib + i) % b.vertices.len()]);
+    }
+
+    a.vertices = vertices;
+    b.vertices.clear();
+}
+
+#[derive(Debug, Clone, Default)]
+struct ContourHole {
+    /// The index of the hole in the contour set.
+    contour: usize,
+    min_x: u16,
+    min_z: u16,
+    leftmost: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ContourRegion {
+    /// The index of the outline in the contour set.
+    outline: Option<usize>,
+    holes: Vec<ContourHole>,
+}
+
+/// Finds the lowest leftmost vertex of a contour.
+fn find_left_most_vertex(contour: &Contour) -> (u16, u16, usize) {
+    let mut min_x = contour.vertices[0].0.x;
+    let mut min_z = contour.vertices[0].0.z;
+    let mut leftmost = 0;
+    for (i, (vertex, _)) in contour.vertices.iter().enumerate().skip(1) {
+        if vertex.x < min_x || (vertex.x == min_x && vertex.z < min_z) {
+            min_x = vertex.x;
+            min_z = vertex.z;
+            leftmost = i;
+        }
+    }
+    (min_x, min_z, leftmost)
+}
+
+fn merge_region_holes(contours: &mut [Contour], region: &mut ContourRegion) {
+    let Some(outline) = region.outline else {
+        return;
+    };
+
+    // Sort holes from left to right.
+    for hole in &mut region.holes {
+        (hole.min_x, hole.min_z, hole.leftmost) = find_left_most_vertex(&contours[hole.contour]);
+    }
+    region.holes.sort_by_key(|hole| (hole.min_x, hole.min_z));
+
+    let mut diagonals = Vec::new();
+
+    // Merge holes into the outline one by one.
+    for i in 0..region.holes.len() {
+        let hole = &contours[region.holes[i].contour];
+
+        let mut index = None;
+        let mut best_vertex = region.holes[i].leftmost;
+        for _ in 0..hole.vertices.len() {
+            // Find potential diagonals.
+            // The 'best' vertex must be in the cone described by 3 consecutive vertices of the outline.
+            // ..o j-1
+            //   |
+            //   |   * best
+            //   |
+            // j o-----o j+1
+            //         :
+            diagonals.clear();
+            let corner = hole.vertices[best_vertex].0;
+            let outline_vertices = &contours[outline].vertices;
+            for j in 0..outline_vertices.len() {
+                if in_cone(j, outline_vertices, corner) {
+                    let d = outline_vertices[j].0.as_ivec3() - corner.as_ivec3();
+                    diagonals.push((j, d.x * d.x + d.z * d.z));
+                }
+            }
+            // Sort potential diagonals by distance, we want to make the connection as short as possible.
+            diagonals.sort_by_key(|(_, distance)| *distance);
+
+            // Find a diagonal that is not intersecting the outline not the remaining holes.
+            index = diagonals.iter().map(|(j, _)| *j).find(|&j| {
+                let pt = outline_vertices[j].0;
+                // The original passes the vertex of the i-th diagonal here, which is likely a typo.
+                !intersect_seg_contour(pt, corner, Some(j), outline_vertices)
+                    && region.holes[i..].iter().all(|other| {
+                        !intersect_seg_contour(pt, corner, None, &contours[other.contour].vertices)
+                    })
+            });
+            // If found non-intersecting diagonal, stop looking.
+            if index.is_some() {
+                break;
+            }
+            // All the potential diagonals for the current vertex were intersecting, try next vertex.
+            best_vertex = (best_vertex + 1) % hole.vertices.len();
+        }
+
+        let Some(index) = index else {
+            tracing::warn!(
+                "merge_holes: Failed to find merge points for contours {outline} and {}.",
+                region.holes[i].contour
+            );
+            continue;
+        };
+        let mut hole = std::mem::take(&mut contours[region.holes[i].contour]);
+        merge_contours(&mut contours[outline], &mut hole, index, best_vertex);
+        contours[region.holes[i].contour] = hole;
+    }
+}
+
 /// Represents a group of related contours.
 /// All contours within the set share the minimum bounds and cell sizes of the set.
 ///
@@ -634,6 +883,8 @@ pub struct Contour {
     /// ```
     pub vertices: Vec<(U16Vec3, u32)>,
     /// Raw contour vertex and connection data.
+    ///
+    /// Only needed for debugging and visualization. Empty if the contours were built with [`BuildContoursFlags::SKIP_RAW_VERTICES`].
     pub raw_vertices: Vec<(U16Vec3, RegionVertexId)>,
     /// Region ID of the contour.
     pub region: RegionId,
@@ -644,12 +895,16 @@ pub struct Contour {
 bitflags::bitflags! {
     /// Contour build flags used in [`CompactHeightfield::build_contours`]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
     #[repr(transparent)]
     pub struct BuildContoursFlags: u8 {
         /// Tessellate solid (impassable) edges during contour simplification.
         const TESSELLATE_SOLID_WALL_EDGES = 1;
         /// Tessellate edges between areas during contour simplification.
         const TESSELLATE_AREA_EDGES = 2;
+        /// Don't store [`Contour::raw_vertices`], which roughly halves the memory of the contours.
+        /// Set this for production builds, as only debugging and visualization need the raw vertices.
+        const SKIP_RAW_VERTICES = 4;
 
         /// Default flags for building contours.
         const DEFAULT = Self::TESSELLATE_SOLID_WALL_EDGES.bits();
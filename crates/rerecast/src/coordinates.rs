@@ -0,0 +1,283 @@
+//! Converting geometry between the coordinate system of the host engine and the one of Recast.
+//!
+//! Recast is Y-up and right-handed, and its cells lie on the xz-plane. Geometry from Z-up tools like Blender or Unreal
+//! has to be converted before it is rasterized, otherwise the floors end up as walls and no navmesh is built.
+
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+use glam::{Vec3, Vec3A};
+
+use crate::{Aabb3d, TriMesh};
+
+/// Which axis points up in a [`CoordinateSystem`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum UpAxis {
+    /// The y-axis points up, like in Recast, Bevy, Godot and Unity.
+    #[default]
+    Y,
+    /// The z-axis points up, like in Blender, 3ds Max and Unreal.
+    Z,
+}
+
+/// The handedness of a [`CoordinateSystem`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum Handedness {
+    /// Right-handed, like Recast, Bevy, Godot and Blender.
+    #[default]
+    Right,
+    /// Left-handed, like Unity and Unreal.
+    Left,
+}
+
+/// The coordinate system of the geometry passed to the pipeline, used to convert it to the one of Recast and the results back.
+///
+/// The conversion keeps the horizontal axes where possible: a right-handed Z-up point `(x, y, z)` becomes `(x, z, -y)`,
+/// a left-handed Z-up point becomes `(x, z, y)` and a left-handed Y-up point becomes `(x, y, -z)`.
+/// Converting between systems of different handedness mirrors the geometry, so [`TriMesh::to_recast_coordinates`]
+/// also reverses the winding of the triangles, which keeps the floors facing up.
+///
+/// ```
+/// # use glam::Vec3;
+/// # use rerecast::CoordinateSystem;
+/// let blender = CoordinateSystem::Z_UP;
+/// let up = blender.to_recast(Vec3::Z);
+/// assert_eq!(up, Vec3::Y);
+/// assert_eq!(blender.from_recast(up), Vec3::Z);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct CoordinateSystem {
+    /// The axis that points up.
+    pub up: UpAxis,
+    /// Whether the system is right- or left-handed.
+    pub handedness: Handedness,
+}
+
+impl CoordinateSystem {
+    /// Y-up and right-handed, the coordinate system of Recast. Converting from it changes nothing.
+    pub const RECAST: Self = Self {
+        up: UpAxis::Y,
+        handedness: Handedness::Right,
+    };
+    /// Z-up and right-handed, e.g. Blender and 3ds Max.
+    pub const Z_UP: Self = Self {
+        up: UpAxis::Z,
+        handedness: Handedness::Right,
+    };
+    /// Z-up and left-handed, e.g. Unreal.
+    pub const Z_UP_LEFT_HANDED: Self = Self {
+        up: UpAxis::Z,
+        handedness: Handedness::Left,
+    };
+    /// Y-up and left-handed, e.g. Unity.
+    pub const Y_UP_LEFT_HANDED: Self = Self {
+        up: UpAxis::Y,
+        handedness: Handedness::Left,
+    };
+
+    /// Converts a point or direction from this coordinate system to the one of Recast.
+    pub fn to_recast(self, point: Vec3) -> Vec3 {
+        match (self.up, self.handedness) {
+            (UpAxis::Y, Handedness::Right) => point,
+            (UpAxis::Y, Handedness::Left) => Vec3::new(point.x, point.y, -point.z),
+            (UpAxis::Z, Handedness::Right) => Vec3::new(point.x, point.z, -point.y),
+            (UpAxis::Z, Handedness::Left) => Vec3::new(point.x, point.z, point.y),
+        }
+    }
+
+    /// Converts a point or direction from the coordinate system of Recast to this one, e.g. the corners of a path.
+    /// The inverse of [`CoordinateSystem::to_recast`].
+    pub fn from_recast(self, point: Vec3) -> Vec3 {
+        match (self.up, self.handedness) {
+            (UpAxis::Y, Handedness::Right) => point,
+            (UpAxis::Y, Handedness::Left) => Vec3::new(point.x, point.y, -point.z),
+            (UpAxis::Z, Handedness::Right) => Vec3::new(point.x, -point.z, point.y),
+            (UpAxis::Z, Handedness::Left) => Vec3::new(point.x, point.z, point.y),
+        }
+    }
+
+    /// Same as [`CoordinateSystem::to_recast`], but for a [`Vec3A`].
+    pub fn to_recast_a(self, point: Vec3A) -> Vec3A {
+        self.to_recast(point.into()).into()
+    }
+
+    /// Same as [`CoordinateSystem::from_recast`], but for a [`Vec3A`].
+    pub fn from_recast_a(self, point: Vec3A) -> Vec3A {
+        self.from_recast(point.into()).into()
+    }
+
+    /// Converts a bounding box from this coordinate system to the one of Recast, e.g. to set [`NavmeshConfig::aabb`](crate::NavmeshConfig::aabb).
+    pub fn aabb_to_recast(self, aabb: Aabb3d) -> Aabb3d {
+        let (a, b) = (self.to_recast(aabb.min), self.to_recast(aabb.max));
+        Aabb3d {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// Converts a bounding box from the coordinate system of Recast to this one, e.g. the [`PolygonNavmesh::aabb`](crate::PolygonNavmesh::aabb).
+    pub fn aabb_from_recast(self, aabb: Aabb3d) -> Aabb3d {
+        let (a, b) = (self.from_recast(aabb.min), self.from_recast(aabb.max));
+        Aabb3d {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// Whether converting between this coordinate system and the one of Recast mirrors the geometry,
+    /// which reverses the winding of its triangles.
+    pub fn is_mirrored(self) -> bool {
+        self.handedness == Handedness::Left
+    }
+}
+
+impl TriMesh {
+    /// Converts the vertices from `system` to the coordinate system of Recast, so that the trimesh can be rasterized.
+    ///
+    /// Reverses the winding of the triangles if the conversion mirrors them, so that the normals used by
+    /// [`TriMesh::mark_walkable_triangles`] keep pointing the same way relative to the geometry.
+    pub fn to_recast_coordinates(&mut self, system: CoordinateSystem) {
+        for vertex in &mut self.vertices {
+            *vertex = system.to_recast_a(*vertex);
+        }
+        if system.is_mirrored() {
+            self.reverse_winding();
+        }
+    }
+
+    /// Swaps the y- and z-coordinates of all vertices and reverses the winding of the triangles to keep their normals consistent.
+    ///
+    /// This converts left-handed Z-up geometry to Recast's coordinate system and back.
+    /// Use [`TriMesh::to_recast_coordinates`] for other coordinate systems.
+    pub fn swap_yz(&mut self) {
+        self.to_recast_coordinates(CoordinateSystem::Z_UP_LEFT_HANDED);
+    }
+
+    /// Reverses the winding of all triangles, which flips their normals.
+    pub fn reverse_winding(&mut self) {
+        for indices in &mut self.indices {
+            core::mem::swap(&mut indices.y, &mut indices.z);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::UVec3;
+
+    use super::*;
+    use crate::{
+        AreaType, NavmeshConfig, NavmeshConfigBuilder, build_navmesh, math::TriangleIndices as _,
+    };
+
+    const SYSTEMS: [CoordinateSystem; 4] = [
+        CoordinateSystem::RECAST,
+        CoordinateSystem::Z_UP,
+        CoordinateSystem::Z_UP_LEFT_HANDED,
+        CoordinateSystem::Y_UP_LEFT_HANDED,
+    ];
+
+    /// A 10x10 floor in `system`, facing up.
+    fn floor(system: CoordinateSystem) -> TriMesh {
+        let mut trimesh = TriMesh::from_parts(
+            vec![
+                Vec3A::new(-5.0, 0.0, -5.0),
+                Vec3A::new(-5.0, 0.0, 5.0),
+                Vec3A::new(5.0, 0.0, 5.0),
+                Vec3A::new(5.0, 0.0, -5.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        for vertex in &mut trimesh.vertices {
+            *vertex = system.from_recast_a(*vertex);
+        }
+        if system.is_mirrored() {
+            trimesh.reverse_winding();
+        }
+        trimesh
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        let point = Vec3::new(1.0, 2.0, 3.0);
+        for system in SYSTEMS {
+            assert_eq!(system.from_recast(system.to_recast(point)), point);
+            assert_eq!(system.to_recast(system.from_recast(point)), point);
+        }
+        assert_eq!(
+            CoordinateSystem::Z_UP.to_recast(point),
+            Vec3::new(1.0, 3.0, -2.0)
+        );
+    }
+
+    #[test]
+    fn up_axis_becomes_y() {
+        for system in SYSTEMS {
+            let up = match system.up {
+                UpAxis::Y => Vec3::Y,
+                UpAxis::Z => Vec3::Z,
+            };
+            assert_eq!(system.to_recast(up), Vec3::Y);
+        }
+    }
+
+    #[test]
+    fn converted_floors_face_up() {
+        for system in SYSTEMS {
+            let mut trimesh = floor(system);
+            trimesh.to_recast_coordinates(system);
+            for indices in &trimesh.indices {
+                assert!(indices.normal(&trimesh.vertices).y > 0.99);
+            }
+        }
+
+        let mut trimesh = floor(CoordinateSystem::Z_UP_LEFT_HANDED);
+        let original = trimesh.clone();
+        trimesh.swap_yz();
+        assert_eq!(trimesh.vertices[1], Vec3A::new(-5.0, 0.0, 5.0));
+        trimesh.swap_yz();
+        assert_eq!(trimesh, original);
+    }
+
+    #[test]
+    fn z_up_geometry_builds_navmesh() {
+        let system = CoordinateSystem::Z_UP;
+        let mut trimesh = floor(system);
+        let aabb = system.aabb_to_recast(Aabb3d::new(Vec3::ZERO, [6.0, 6.0, 2.0]));
+        assert_eq!(aabb, Aabb3d::new(Vec3::ZERO, [6.0, 2.0, 6.0]));
+        let config = NavmeshConfig {
+            aabb,
+            ..NavmeshConfigBuilder::default().build()
+        };
+
+        let (polygon, _detail) = build_navmesh(trimesh.clone(), &config).unwrap();
+        assert_eq!(polygon.polygon_count(), 0);
+
+        trimesh.to_recast_coordinates(system);
+        let (polygon, _detail) = build_navmesh(trimesh, &config).unwrap();
+        assert!(polygon.polygon_count() > 0);
+        assert!(polygon.areas.contains(&AreaType::DEFAULT_WALKABLE));
+        assert_eq!(
+            system.aabb_from_recast(aabb),
+            Aabb3d::new(Vec3::ZERO, [6.0, 6.0, 2.0])
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Volumes that make traversing parts of a navmesh more or less expensive, without changing the navmesh itself.
+//!
+//! These are meant for short-lived gameplay effects like grenades or fire patches, which would be
+//! too expensive to bake into the navmesh through area types. A pathfinder multiplies the cost of a polygon
+//! with [`CostVolumes::polygon_multiplier`] to avoid them.
+
+use std::collections::HashMap;
+
+use glam::{IVec2, Vec3, Vec3Swizzles as _};
+use slotmap::SlotMap;
+
+use crate::{PolyId, PolygonNavmesh};
+
+slotmap::new_key_type! {
+    /// A key for a volume in [`CostVolumes`].
+    pub struct CostVolumeId;
+}
+
+/// A vertical cylinder that multiplies the cost of traversing the polygons within it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct CostVolume {
+    /// The center of the bottom of the cylinder, in world space.
+    pub base: Vec3,
+    /// The radius of the cylinder on the xz-plane.
+    pub radius: f32,
+    /// The height of the cylinder above [`Self::base`].
+    pub height: f32,
+    /// The factor the cost within the volume is multiplied with.
+    /// Values above 1 make the volume more expensive, values between 0 and 1 make it cheaper.
+    /// Use [`f32::INFINITY`] to make it impassable.
+    pub multiplier: f32,
+}
+
+impl CostVolume {
+    /// Whether the given world-space point lies within the volume.
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.y >= self.base.y
+            && point.y <= self.base.y + self.height
+            && point.xz().distance_squared(self.base.xz()) <= self.radius * self.radius
+    }
+}
+
+/// A set of [`CostVolume`]s, indexed by a uniform grid on the xz-plane so that
+/// looking up the volumes at a point only checks the volumes nearby.
+///
+/// Where volumes overlap, their multipliers are multiplied with each other.
+#[derive(Debug, Clone)]
+pub struct CostVolumes {
+    cell_size: f32,
+    volumes: SlotMap<CostVolumeId, CostVolume>,
+    grid: HashMap<IVec2, Vec<CostVolumeId>>,
+}
+
+impl Default for CostVolumes {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CELL_SIZE)
+    }
+}
+
+impl CostVolumes {
+    /// The grid cell size used by [`CostVolumes::default`], in world units.
+    pub const DEFAULT_CELL_SIZE: f32 = 4.0;
+
+    /// Creates an empty set whose grid has the given cell size in world units.
+    /// A good cell size is about the radius of a typical volume.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell_size` is not positive.
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "cell size must be positive");
+        Self {
+            cell_size,
+            volumes: SlotMap::with_key(),
+            grid: HashMap::new(),
+        }
+    }
+
+    /// Adds a volume and returns the key to remove it with.
+    pub fn insert(&mut self, volume: CostVolume) -> CostVolumeId {
+        let id = self.volumes.insert(volume);
+        for cell in self.cells(&volume) {
+            self.grid.entry(cell).or_default().push(id);
+        }
+        id
+    }
+
+    /// Removes a volume, returning it if it was still present.
+    pub fn remove(&mut self, id: CostVolumeId) -> Option<CostVolume> {
+        let volume = self.volumes.remove(id)?;
+        for cell in self.cells(&volume) {
+            if let Some(ids) = self.grid.get_mut(&cell) {
+                ids.retain(|other| *other != id);
+                if ids.is_empty() {
+                    self.grid.remove(&cell);
+                }
+            }
+        }
+        Some(volume)
+    }
+
+    /// Returns the volume with the given key, if it is present.
+    pub fn get(&self, id: CostVolumeId) -> Option<&CostVolume> {
+        self.volumes.get(id)
+    }
+
+    /// Iterates over all volumes along with their keys.
+    pub fn iter(&self) -> impl Iterator<Item = (CostVolumeId, &CostVolume)> {
+        self.volumes.iter()
+    }
+
+    /// The number of volumes in the set.
+    pub fn len(&self) -> usize {
+        self.volumes.len()
+    }
+
+    /// Whether the set contains no volumes.
+    pub fn is_empty(&self) -> bool {
+        self.volumes.is_empty()
+    }
+
+    /// Removes all volumes.
+    pub fn clear(&mut self) {
+        self.volumes.clear();
+        self.grid.clear();
+    }
+
+    /// Iterates over the volumes that contain the given world-space point.
+    pub fn volumes_at(&self, point: Vec3) -> impl Iterator<Item = (CostVolumeId, &CostVolume)> {
+        self.grid
+            .get(&self.cell(point))
+            .into_iter()
+            .flatten()
+            .map(|id| (*id, &self.volumes[*id]))
+            .filter(move |(_, volume)| volume.contains(point))
+    }
+
+    /// The product of the multipliers of all volumes containing the given world-space point.
+    /// This is 1 if no volume contains the point.
+    pub fn multiplier_at(&self, point: Vec3) -> f32 {
+        self.volumes_at(point)
+            .map(|(_, volume)| volume.multiplier)
+            .product()
+    }
+
+    /// The multiplier for traversing the given polygon, taken at the center of its vertices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `polygon` is out of bounds.
+    pub fn polygon_multiplier(&self, navmesh: &PolygonNavmesh, polygon: PolyId) -> f32 {
+        if self.is_empty() {
+            return 1.0;
+        }
+        let (sum, count) = navmesh
+            .polygon_vertices(polygon)
+            .map(|vertex| navmesh.vertex_world(vertex))
+            .fold((Vec3::ZERO, 0), |(sum, count), vertex| {
+                (sum + vertex, count + 1)
+            });
+        if count == 0 {
+            return 1.0;
+        }
+        self.multiplier_at(sum / count as f32)
+    }
+
+    fn cell(&self, point: Vec3) -> IVec2 {
+        (point.xz() / self.cell_size).floor().as_ivec2()
+    }
+
+    /// The grid cells overlapped by the bounding square of the volume.
+    fn cells(&self, volume: &CostVolume) -> impl Iterator<Item = IVec2> + use<> {
+        let min = self.cell(volume.base - Vec3::splat(volume.radius));
+        let max = self.cell(volume.base + Vec3::splat(volume.radius));
+        (min.y..=max.y).flat_map(move |z| (min.x..=max.x).map(move |x| IVec2::new(x, z)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fire(x: f32, multiplier: f32) -> CostVolume {
+        CostVolume {
+            base: Vec3::new(x, 0.0, 0.0),
+            radius: 2.0,
+            height: 3.0,
+            multiplier,
+        }
+    }
+
+    #[test]
+    fn overlapping_volumes_multiply() {
+        let mut volumes = CostVolumes::new(1.0);
+        let a = volumes.insert(fire(0.0, 2.0));
+        let b = volumes.insert(fire(3.0, 5.0));
+
+        assert_eq!(volumes.multiplier_at(Vec3::new(-1.5, 1.0, 0.0)), 2.0);
+        assert_eq!(volumes.multiplier_at(Vec3::new(1.5, 1.0, 0.0)), 10.0);
+        assert_eq!(volumes.multiplier_at(Vec3::new(1.5, 4.0, 0.0)), 1.0);
+        assert_eq!(volumes.multiplier_at(Vec3::new(0.0, 1.0, 2.5)), 1.0);
+
+        assert_eq!(volumes.remove(a), Some(fire(0.0, 2.0)));
+        assert_eq!(volumes.remove(a), None);
+        assert_eq!(volumes.multiplier_at(Vec3::new(1.5, 1.0, 0.0)), 5.0);
+        assert_eq!(volumes.volumes_at(Vec3::new(3.0, 1.0, 0.0)).count(), 1);
+
+        volumes.remove(b);
+        assert!(volumes.is_empty());
+        assert!(volumes.grid.is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+//! Generation of cover annotations for shooter AI.
+//!
+//! Cover points are placed along the boundary edges of a [`PolygonNavmesh`] wherever the [`Heightfield`]
+//! the navmesh was built from contains a tall enough obstruction right behind the edge.
+
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+use glam::{Vec2, Vec3, Vec3Swizzles as _};
+
+use crate::{EdgeConnection, Heightfield, PolyId, PolygonNavmesh, VertexId};
+
+/// Parameters for [`PolygonNavmesh::generate_cover`]. All values are in world units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoverConfig {
+    /// The distance between two cover points along the same edge.
+    pub spacing: f32,
+    /// How far beyond the navmesh edge to look for obstructions.
+    /// Should be a bit larger than the agent radius the navmesh was eroded by.
+    pub probe_distance: f32,
+    /// How far above the edge an obstruction may start and still count as cover.
+    /// Obstructions starting higher than this are overhangs that an agent cannot duck behind.
+    pub max_gap: f32,
+    /// The minimum height of an obstruction above the edge to count as cover at all.
+    pub min_height: f32,
+    /// The minimum height of an obstruction above the edge to count as [`CoverKind::High`] cover.
+    pub high_height: f32,
+}
+
+impl Default for CoverConfig {
+    fn default() -> Self {
+        Self {
+            spacing: 1.0,
+            probe_distance: 1.0,
+            max_gap: 0.5,
+            min_height: 0.8,
+            high_height: 1.6,
+        }
+    }
+}
+
+/// The cover points of a navmesh, as generated by [`PolygonNavmesh::generate_cover`].
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct CoverAnnotations {
+    /// The cover points, grouped by the polygon they lie on.
+    pub points: Vec<CoverPoint>,
+}
+
+/// A position on the navmesh where an agent can take cover behind an obstruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct CoverPoint {
+    /// The position on the boundary edge of the navmesh, in world space.
+    pub position: Vec3,
+    /// The horizontal direction from the position towards the obstruction. Normalized.
+    pub facing: Vec3,
+    /// The height of the obstruction above [`Self::position`].
+    pub height: f32,
+    /// Whether the cover is low or high.
+    pub kind: CoverKind,
+    /// The polygon the point lies on.
+    pub polygon: PolyId,
+}
+
+/// The height classification of a [`CoverPoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum CoverKind {
+    /// Cover that an agent has to crouch behind, but can shoot over.
+    Low,
+    /// Cover that an agent can stand behind, but has to lean around to shoot.
+    High,
+}
+
+impl PolygonNavmesh {
+    /// Generates cover points along the boundary edges of this navmesh.
+    ///
+    /// `heightfield` must be the heightfield this navmesh was built from, before it was turned into a
+    /// [`CompactHeightfield`](crate::CompactHeightfield). Since [`Heightfield::into_compact`] consumes the heightfield,
+    /// clone it beforehand if you want to generate cover.
+    pub fn generate_cover(
+        &self,
+        heightfield: &Heightfield,
+        config: &CoverConfig,
+    ) -> CoverAnnotations {
+        let to_world = |vertex: VertexId| self.vertex_world(vertex);
+
+        let mut points = Vec::new();
+        for polygon in self.polygon_ids() {
+            let vertices = self
+                .polygon_vertices(polygon)
+                .map(to_world)
+                .collect::<Vec<_>>();
+            let centroid = vertices.iter().map(|v| v.xz()).sum::<Vec2>() / vertices.len() as f32;
+            for edge in self.edges(polygon) {
+                // Portal edges continue on another tile, so they are not a boundary.
+                if self.edge_connection(edge) != EdgeConnection::Border {
+                    continue;
+                }
+                let [a, b] = self.edge_vertices(edge).map(to_world);
+                let edge = (b - a).xz();
+                let length = edge.length();
+                if length <= f32::EPSILON {
+                    continue;
+                }
+                let mut normal = edge.perp() / length;
+                if normal.dot((a.xz() + b.xz()) * 0.5 - centroid) < 0.0 {
+                    normal = -normal;
+                }
+
+                let count = ((length / config.spacing) as usize).max(1);
+                for j in 0..count {
+                    let position = a.lerp(b, (j as f32 + 0.5) / count as f32);
+                    let Some(height) = obstruction_height(heightfield, position, normal, config)
+                    else {
+                        continue;
+                    };
+                    if height < config.min_height {
+                        continue;
+                    }
+                    points.push(CoverPoint {
+                        position,
+                        facing: Vec3::new(normal.x, 0.0, normal.y),
+                        height,
+                        kind: if height < config.high_height {
+                            CoverKind::Low
+                        } else {
+                            CoverKind::High
+                        },
+                        polygon,
+                    });
+                }
+            }
+        }
+        CoverAnnotations { points }
+    }
+}
+
+/// Walks from `position` along `direction` and returns the height above `position` of the first obstruction found.
+fn obstruction_height(
+    heightfield: &Heightfield,
+    position: Vec3,
+    direction: Vec2,
+    config: &CoverConfig,
+) -> Option<f32> {
+    let steps = (config.probe_distance / heightfield.cell_size)
+        .ceil()
+        .max(1.0) as usize;
+    (1..=steps).find_map(|step| {
+        let probe = position.xz() + direction * (step as f32 * heightfield.cell_size);
+        let cell = ((probe - heightfield.aabb.min.xz()) / heightfield.cell_size).floor();
+        if cell.x < 0.0
+            || cell.y < 0.0
+            || cell.x >= heightfield.width as f32
+            || cell.y >= heightfield.height as f32
+        {
+            return None;
+        }
+
+        let mut height = None;
+        let mut span_key = heightfield.span_key_at(cell.x as u16, cell.y as u16);
+        while let Some(key) = span_key {
+            let span = heightfield.span(key);
+            let bottom = heightfield.aabb.min.y + span.min as f32 * heightfield.cell_height;
+            let top = heightfield.aabb.min.y + span.max as f32 * heightfield.cell_height;
+            if bottom <= position.y + config.max_gap {
+                let span_height = top - position.y;
+                // The floor the edge stands on is not an obstruction.
+                if span_height > config.max_gap {
+                    height = Some(height.map_or(span_height, |h: f32| h.max(span_height)));
+                }
+            }
+            span_key = span.next;
+        }
+        height
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{U16Vec3, UVec3, Vec3A};
+
+    use super::*;
+    use crate::{AreaType, HeightfieldBuilder, RegionId, TriMesh};
+
+    fn cuboid(min: Vec3, max: Vec3) -> TriMesh {
+        let vertices = (0..8)
+            .map(|i| {
+                Vec3A::new(
+                    if i & 1 == 0 { min.x } else { max.x },
+                    if i & 2 == 0 { min.y } else { max.y },
+                    if i & 4 == 0 { min.z } else { max.z },
+                )
+            })
+            .collect();
+        let faces = [
+            [0, 1, 3, 2],
+            [4, 6, 7, 5],
+            [0, 4, 5, 1],
+            [2, 3, 7, 6],
+            [0, 2, 6, 4],
+            [1, 5, 7, 3],
+        ];
+        let indices = faces
+            .iter()
+            .flat_map(|[a, b, c, d]| [UVec3::new(*a, *b, *c), UVec3::new(*a, *c, *d)])
+            .collect();
+        TriMesh::from_parts(vertices, indices).unwrap()
+    }
+
+    #[test]
+    fn low_wall_behind_edge_is_low_cover() {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        // A 2x2 floor polygon, with a 1.2 high wall right behind its edge at x = 2.
+        let navmesh = PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 0, 0),
+                U16Vec3::new(0, 0, 4),
+                U16Vec3::new(4, 0, 4),
+                U16Vec3::new(4, 0, 0),
+            ],
+            polygons: vec![0, 1, 2, 3],
+            polygon_neighbors: vec![N; 4],
+            flags: vec![0],
+            regions: vec![RegionId::NONE],
+            areas: vec![AreaType::DEFAULT_WALKABLE],
+            max_vertices_per_polygon: 4,
+            aabb: crate::Aabb3d::new(Vec3::new(1.0, 0.0, 1.0), [1.0, 0.0, 1.0]),
+            cell_size: 0.5,
+            cell_height: 0.1,
+            ..Default::default()
+        };
+        let mut heightfield = HeightfieldBuilder {
+            aabb: crate::Aabb3d::new(Vec3::new(2.5, 1.0, 2.5), [2.5, 2.0, 2.5]),
+            cell_size: 0.5,
+            cell_height: 0.1,
+        }
+        .build()
+        .unwrap();
+        heightfield
+            .rasterize_triangles(
+                &cuboid(Vec3::new(2.3, 0.0, 0.0), Vec3::new(2.7, 1.2, 2.0)),
+                1,
+            )
+            .unwrap();
+
+        let cover = navmesh.generate_cover(&heightfield, &CoverConfig::default());
+        assert_eq!(cover.points.len(), 2);
+        for point in &cover.points {
+            assert_eq!(point.position.x, 2.0);
+            assert_eq!(point.facing, Vec3::X);
+            assert_eq!(point.kind, CoverKind::Low);
+            assert!((point.height - 1.2).abs() < 0.11, "{}", point.height);
+        }
+    }
+}
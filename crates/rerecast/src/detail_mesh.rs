@@ -1,6 +1,6 @@
 #[cfg(feature = "bevy_reflect")]
 use bevy_reflect::prelude::*;
-use glam::{U16Vec3, Vec2, Vec3, Vec3A, Vec3Swizzles as _, u16vec3};
+use glam::{Vec2, Vec3, Vec3A, Vec3Swizzles as _};
 use std::{
     f32,
     ops::{Deref, DerefMut},
@@ -8,7 +8,7 @@ use std::{
 use thiserror::Error;
 
 use crate::{
-    Aabb3d, CompactHeightfield, PolygonNavmesh, RegionId,
+    Aabb3d, CompactHeightfield, PolyId, PolygonNavmesh, RegionId,
     math::{
         dir_offset, dir_offset_x, dir_offset_z, distance_squared_between_point_and_line_vec2,
         distance_squared_between_point_and_line_vec3, next, prev,
@@ -132,6 +132,33 @@ pub struct SubMesh {
     pub triangle_count: u32,
 }
 
+/// How [`DetailNavmesh::new_with`] picks the candidate points for the height detail inside of each polygon.
+///
+/// All strategies add the candidates with the largest height error first, until the error is below the `sample_max_error`
+/// or the sub-mesh is full, so they only differ in where the candidates are. The edges of the polygons are always sampled
+/// the same way, so that neighboring sub-meshes line up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum DetailSampling {
+    /// A grid with `sample_distance` between the candidates, slightly jittered. This is what the original Recast does.
+    #[default]
+    Grid,
+    /// Candidates that are spread evenly but irregularly, at least `sample_distance` apart.
+    ///
+    /// Avoids the long, thin triangles that the grid tends to produce along diagonal slopes.
+    BlueNoise,
+    /// The grid, plus rows of candidates half as far apart along every edge, `sample_distance` inside of the polygon.
+    ///
+    /// Spends more of the vertex budget near the edges, where rough terrain most often deviates from the polygon,
+    /// e.g. at the foot of a slope or the rim of a ledge.
+    EdgeBiased,
+}
+
 impl DetailNavmesh {
     /// The maximum number of vertices per entry in [`DetailNavmesh::meshes`]
     pub const MAX_VERTICES_PER_SUBMESH: usize = 127;
@@ -141,175 +168,276 @@ impl DetailNavmesh {
     const MAX_VERTS_PER_EDGE: usize = 32;
 
     /// Builds a detail mesh from the provided polygon mesh.
+    ///
+    /// With the `rayon` feature, the sub-meshes of the polygons are built in parallel.
     pub fn new(
         mesh: &PolygonNavmesh,
         heightfield: &CompactHeightfield,
         sample_distance: f32,
         sample_max_error: f32,
+    ) -> Result<Self, DetailNavmeshError> {
+        Self::new_with(
+            mesh,
+            heightfield,
+            sample_distance,
+            sample_max_error,
+            DetailSampling::Grid,
+        )
+    }
+
+    /// Builds a detail mesh from the provided polygon mesh, sampling the height detail with the given [`DetailSampling`].
+    pub fn new_with(
+        mesh: &PolygonNavmesh,
+        heightfield: &CompactHeightfield,
+        sample_distance: f32,
+        sample_max_error: f32,
+        sampling: DetailSampling,
     ) -> Result<Self, DetailNavmeshError> {
         let mut dmesh = DetailNavmesh::default();
         if mesh.vertices.is_empty() || mesh.polygon_count() == 0 {
             return Ok(dmesh);
         }
         let chf = heightfield;
-        let nvp = mesh.max_vertices_per_polygon as usize;
-        let cs = mesh.cell_size;
-        let ch = mesh.cell_height;
-        let orig = Vec3A::from(mesh.aabb.min);
-        let border_size = mesh.border_size;
-        let height_search_radius = 1.max(mesh.max_edge_error.ceil() as u32);
-
-        let mut edges = Vec::with_capacity(64 / 4);
-        let mut tris = Vec::with_capacity((512 / 4) * 3);
-        let mut flags = Vec::with_capacity(512 / 4);
-        let mut arr = Vec::with_capacity(512 / 3);
-        let mut samples = Vec::with_capacity(512 / 4);
-        let mut verts = [Vec3A::default(); 256];
-        let mut hp = HeightPatch::default();
         let mut poly_vert_count = 0;
         let mut maxhw = 0;
         let mut maxhh = 0;
 
         let mut bounds = vec![Bounds::default(); mesh.polygon_count()];
-        let mut poly = vec![Vec3A::default(); nvp];
 
         // Find max size for a polygon area.
         for (i, b) in bounds.iter_mut().enumerate() {
-            let p = &mesh.polygons[i * nvp..];
-            let Bounds {
-                xmin,
-                xmax,
-                zmin,
-                zmax,
-            } = b;
-            *xmin = chf.width;
-            *xmax = 0;
-            *zmin = chf.height;
-            *zmax = 0;
-            for pj in &p[..nvp] {
-                if *pj == PolygonNavmesh::NO_INDEX {
-                    break;
-                }
-                let v = &mesh.vertices[*pj as usize];
-                *xmin = (*xmin).min(v.x);
-                *xmax = (*xmax).max(v.x);
-                *zmin = (*zmin).min(v.z);
-                *zmax = (*zmax).max(v.z);
-                poly_vert_count += 1;
-            }
-            *xmin = xmin.saturating_sub(1);
-            *xmax = chf.width.min(*xmax + 1);
-            *zmin = zmin.saturating_sub(1);
-            *zmax = chf.height.min(*zmax + 1);
-            if xmin >= xmax || zmin >= zmax {
+            let npoly;
+            (*b, npoly) = polygon_bounds(mesh, chf, i);
+            poly_vert_count += npoly;
+            if b.xmin >= b.xmax || b.zmin >= b.zmax {
                 continue;
             }
-            maxhw = maxhw.max(*xmax - *xmin);
-            maxhh = maxhh.max(*zmax - *zmin);
+            maxhw = maxhw.max(b.width());
+            maxhh = maxhh.max(b.height());
         }
-        hp.data = vec![0; maxhw as usize * maxhh as usize];
+        let builder = || {
+            let mut builder =
+                SubmeshBuilder::new(mesh, chf, sample_distance, sample_max_error, sampling);
+            builder.hp.data = vec![0; maxhw as usize * maxhh as usize];
+            builder
+        };
         dmesh.meshes = vec![SubMesh::default(); mesh.polygon_count()];
 
-        let mut vcap = poly_vert_count + poly_vert_count / 2;
-        let mut tcap = vcap * 2;
+        let vcap = poly_vert_count + poly_vert_count / 2;
+        let tcap = vcap * 2;
 
         dmesh.vertices = Vec::with_capacity(vcap);
         dmesh.triangles = Vec::with_capacity(tcap);
 
-        for (i, bounds_i) in bounds.iter().enumerate().take(mesh.polygon_count()) {
-            let p = &mesh.polygons[i * nvp..];
-
-            // Store polygon vertices for processing.
-            let mut npoly = 0;
-            for j in 0..nvp {
-                if p[j] == PolygonNavmesh::NO_INDEX {
-                    break;
-                }
-                let v = mesh.vertices[p[j] as usize].as_vec3();
-                poly[j].x = v.x * cs;
-                poly[j].y = v.y * ch;
-                poly[j].z = v.z * cs;
-                npoly += 1;
+        #[cfg(not(feature = "rayon"))]
+        {
+            let mut builder = builder();
+            for (i, bounds_i) in bounds.iter().enumerate() {
+                let nverts = builder.build(i, bounds_i)?;
+                dmesh.push_submesh(i, &builder.verts[..nverts], &builder.tris, &builder.flags);
             }
+        }
+        // The sub-meshes only read the polygon mesh and the heightfield, so they can be built in parallel
+        // and appended in polygon order afterwards.
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+
+            let submeshes = bounds
+                .par_iter()
+                .enumerate()
+                .map_init(builder, |builder, (i, bounds_i)| {
+                    let nverts = builder.build(i, bounds_i)?;
+                    Ok((
+                        builder.verts[..nverts].to_vec(),
+                        builder.tris.clone(),
+                        builder.flags.clone(),
+                    ))
+                })
+                .collect::<Result<Vec<_>, DetailNavmeshError>>()?;
+            for (i, (verts, tris, flags)) in submeshes.iter().enumerate() {
+                dmesh.push_submesh(i, verts, tris, flags);
+            }
+        }
 
-            // Get the height data from the area of the polygon.
-            hp.xmin = bounds_i.xmin;
-            hp.zmin = bounds_i.zmin;
-            hp.width = bounds_i.width();
-            hp.height = bounds_i.height();
-            hp.get_height_data(
-                chf,
-                p,
-                npoly,
-                &verts,
-                border_size,
-                &mut arr,
-                mesh.regions[i],
-            );
+        Ok(dmesh)
+    }
 
-            // Build detail mesh.
-            let mut nverts = 0;
-            build_poly_detail(
-                &poly,
-                npoly,
-                sample_distance,
-                sample_max_error,
-                height_search_radius,
-                chf,
-                &hp,
-                &mut verts,
-                &mut nverts,
-                &mut tris,
-                &mut flags,
-                &mut edges,
-                &mut samples,
-            )?;
-
-            // Move detail verts to world space.
-            for vert in &mut verts[..nverts] {
-                *vert += orig;
-                // [sic] Is this offset necessary?
-                vert.y += chf.cell_height;
-            }
-            // Offset poly too, will be used to flag checking.
-            for poly in &mut poly[..npoly] {
-                *poly += orig;
-            }
+    /// Appends the sub-mesh of polygon `i`, which must come after the sub-meshes of all previous polygons.
+    fn push_submesh(&mut self, i: usize, verts: &[Vec3A], tris: &[[u8; 3]], flags: &[u8]) {
+        let submesh = &mut self.meshes[i];
+        submesh.base_vertex_index = self.vertices.len() as u32;
+        submesh.vertex_count = verts.len() as u32;
+        submesh.base_triangle_index = self.triangles.len() as u32;
+        submesh.triangle_count = tris.len() as u32;
+
+        self.vertices
+            .extend(verts.iter().map(|vert| Vec3::from(*vert)));
+        self.triangles.extend_from_slice(tris);
+        self.triangle_flags.extend_from_slice(flags);
+    }
 
-            // Store detail submesh
-            let submesh = &mut dmesh.meshes[i];
-            submesh.base_vertex_index = dmesh.vertices.len() as u32;
-            submesh.vertex_count = nverts as u32;
-            submesh.base_triangle_index = dmesh.triangles.len() as u32;
-            submesh.triangle_count = tris.len() as u32;
+    /// Regenerates only the sub-meshes of the given polygons, e.g. after an obstacle was carved into them or their
+    /// heights changed at runtime. All other sub-meshes are kept as they are.
+    ///
+    /// `mesh` must have the same polygons as the one this detail mesh was built from, and `heightfield` must contain the
+    /// current geometry. The parameters are the same as for [`DetailNavmesh::new`]; passing the ones used originally
+    /// gives the same result as rebuilding the whole detail mesh.
+    ///
+    /// The vertex and triangle buffers are patched in place. If a sub-mesh changes its size, the later parts of the
+    /// buffers are shifted and the base indices of the following sub-meshes are updated.
+    pub fn rebuild_submeshes(
+        &mut self,
+        polygons: &[PolyId],
+        mesh: &PolygonNavmesh,
+        heightfield: &CompactHeightfield,
+        sample_distance: f32,
+        sample_max_error: f32,
+    ) -> Result<(), DetailNavmeshError> {
+        self.rebuild_submeshes_with(
+            polygons,
+            mesh,
+            heightfield,
+            sample_distance,
+            sample_max_error,
+            DetailSampling::Grid,
+        )
+    }
 
-            // Store vertices, allocate more memory if necessary.
-            if dmesh.vertices.len() + nverts > vcap {
-                while dmesh.vertices.len() + nverts > vcap {
-                    vcap += 256;
-                }
-                dmesh.vertices.reserve(vcap - dmesh.vertices.capacity());
-            }
-            for vert in &verts[..nverts] {
-                dmesh.vertices.push(Vec3::from(*vert));
-            }
+    /// Same as [`DetailNavmesh::rebuild_submeshes`], but samples the height detail with the given [`DetailSampling`],
+    /// like [`DetailNavmesh::new_with`].
+    pub fn rebuild_submeshes_with(
+        &mut self,
+        polygons: &[PolyId],
+        mesh: &PolygonNavmesh,
+        heightfield: &CompactHeightfield,
+        sample_distance: f32,
+        sample_max_error: f32,
+        sampling: DetailSampling,
+    ) -> Result<(), DetailNavmeshError> {
+        if self.meshes.len() != mesh.polygon_count() {
+            return Err(DetailNavmeshError::PolygonCountMismatch {
+                detail: self.meshes.len(),
+                polygon: mesh.polygon_count(),
+            });
+        }
+        if let Some(polygon) = polygons
+            .iter()
+            .find(|polygon| polygon.index() >= mesh.polygon_count())
+        {
+            return Err(DetailNavmeshError::PolygonOutOfBounds(*polygon));
+        }
+        let mut polygons = polygons.to_vec();
+        polygons.sort_unstable();
+        polygons.dedup();
 
-            // Store triangles, allocate more memory if necessary.
-            if dmesh.triangles.len() + tris.len() > tcap {
-                while dmesh.triangles.len() + tris.len() > tcap {
-                    tcap += 256;
+        let chf = heightfield;
+        let mut builder =
+            SubmeshBuilder::new(mesh, chf, sample_distance, sample_max_error, sampling);
+        let bounds = polygons
+            .iter()
+            .map(|polygon| polygon_bounds(mesh, chf, polygon.index()).0)
+            .collect::<Vec<_>>();
+        let (maxhw, maxhh) = bounds
+            .iter()
+            .filter(|b| b.xmin < b.xmax && b.zmin < b.zmax)
+            .fold((0, 0), |(w, h), b| (b.width().max(w), b.height().max(h)));
+        builder.hp.data = vec![0; maxhw as usize * maxhh as usize];
+
+        for (polygon, bounds) in polygons.iter().zip(&bounds) {
+            let i = polygon.index();
+            let nverts = builder.build(i, bounds)?;
+            let old = self.meshes[i].clone();
+            let (vert_start, tri_start) = (
+                old.base_vertex_index as usize,
+                old.base_triangle_index as usize,
+            );
+            let vert_range = vert_start..vert_start + old.vertex_count as usize;
+            let tri_range = tri_start..tri_start + old.triangle_count as usize;
+            self.vertices.splice(
+                vert_range,
+                builder.verts[..nverts].iter().map(|v| Vec3::from(*v)),
+            );
+            self.triangles
+                .splice(tri_range.clone(), builder.tris.iter().copied());
+            self.triangle_flags
+                .splice(tri_range, builder.flags.iter().copied());
+
+            let vert_delta = nverts as i64 - old.vertex_count as i64;
+            let tri_delta = builder.tris.len() as i64 - old.triangle_count as i64;
+            for (j, other) in self.meshes.iter_mut().enumerate() {
+                // Sub-meshes are stored in polygon order, so empty sub-meshes at the same base only move if they come later.
+                let after =
+                    |base: u32, old_base: u32| base > old_base || (base == old_base && j > i);
+                if after(other.base_vertex_index, old.base_vertex_index) {
+                    other.base_vertex_index = (other.base_vertex_index as i64 + vert_delta) as u32;
+                }
+                if after(other.base_triangle_index, old.base_triangle_index) {
+                    other.base_triangle_index =
+                        (other.base_triangle_index as i64 + tri_delta) as u32;
                 }
-                dmesh.triangles.reserve(tcap - dmesh.triangles.capacity());
-            }
-            for tri in &tris {
-                dmesh.triangles.push([tri[0], tri[1], tri[2]]);
-            }
-            for flag in &flags {
-                dmesh.triangle_flags.push(*flag);
             }
+            let submesh = &mut self.meshes[i];
+            submesh.vertex_count = nverts as u32;
+            submesh.triangle_count = builder.tris.len() as u32;
         }
+        Ok(())
+    }
 
-        Ok(dmesh)
+    /// The sub-mesh associated with the given polygon of the [`PolygonNavmesh`], if it exists.
+    #[inline]
+    pub fn submesh(&self, polygon: PolyId) -> Option<&SubMesh> {
+        self.meshes.get(polygon.index())
+    }
+
+    /// Returns the height of the detail surface of `polygon` at the world-space `pos_xz`.
+    ///
+    /// The height is interpolated from the triangles of the sub-mesh associated with the polygon,
+    /// so it lies exactly on the walkable surface.
+    /// Returns `None` if `polygon` is out of bounds or `pos_xz` is not inside any of the sub-mesh's triangles.
+    pub fn height_at(&self, polygon: PolyId, pos_xz: Vec2) -> Option<f32> {
+        let submesh = self.submesh(polygon)?;
+        let verts = self
+            .vertices
+            .get(submesh.base_vertex_index as usize..)?
+            .get(..submesh.vertex_count as usize)?;
+        let tris = self
+            .triangles
+            .get(submesh.base_triangle_index as usize..)?
+            .get(..submesh.triangle_count as usize)?;
+        tris.iter().find_map(|tri| {
+            let [a, b, c] = tri.map(|i| verts[i as usize]);
+            closest_height_point_triangle(pos_xz, a, b, c)
+        })
+    }
+}
+
+/// Height of the triangle defined by vertices a, b, and c at the point p on the xz-plane.
+/// Returns None if the point is outside the triangle.
+fn closest_height_point_triangle(p: Vec2, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+    let v0 = c - a;
+    let v1 = b - a;
+    let v2 = p - a.xz();
+
+    // Compute scaled barycentric coordinates
+    let denom = v0.x * v1.z - v0.z * v1.x;
+    if denom.abs() < f32::EPSILON {
+        return None;
+    }
+    let mut u = v1.z * v2.x - v1.x * v2.y;
+    let mut v = v0.x * v2.y - v0.z * v2.x;
+    if denom < 0.0 {
+        u = -u;
+        v = -v;
+    }
+    let denom = denom.abs();
+
+    // If point lies inside the triangle, return interpolated y-coord.
+    const EPS: f32 = 1.0e-6;
+    if u >= -EPS * denom && v >= -EPS * denom && (u + v) <= denom * (1.0 + EPS) {
+        Some(a.y + (v0.y * u + v1.y * v) / denom)
+    } else {
+        None
     }
 }
 
@@ -318,6 +446,7 @@ fn build_poly_detail(
     nin: usize,
     sample_dist: f32,
     sample_max_error: f32,
+    sampling: DetailSampling,
     height_search_radius: u32,
     chf: &CompactHeightfield,
     hp: &HeightPatch,
@@ -326,7 +455,7 @@ fn build_poly_detail(
     tris: &mut Vec<[u8; 3]>,
     flags: &mut Vec<u8>,
     edges: &mut Vec<Edges>,
-    samples: &mut Vec<(U16Vec3, bool)>,
+    samples: &mut Vec<(Vec3A, bool)>,
 ) -> Result<(), DetailNavmeshError> {
     let mut edge = [Vec3A::default(); DetailNavmesh::MAX_VERTS_PER_EDGE + 1];
     let mut hull = [0; DetailNavmesh::MAX_VERTICES_PER_SUBMESH];
@@ -461,7 +590,6 @@ fn build_poly_detail(
     }
 
     if sample_dist > 0.0 {
-        // Create sample locations in a grid.
         let mut aabb = Aabb3d {
             min: in_[0].into(),
             max: in_[0].into(),
@@ -470,24 +598,100 @@ fn build_poly_detail(
             aabb.min = aabb.min.min(in_.into());
             aabb.max = aabb.max.max(in_.into());
         }
-        let x0 = (aabb.min.x / sample_dist).floor() as i32;
-        let x1 = (aabb.max.x / sample_dist).ceil() as i32;
-        let z0 = (aabb.min.z / sample_dist).floor() as i32;
-        let z1 = (aabb.max.z / sample_dist).ceil() as i32;
         samples.clear();
-        for z in z0..z1 {
-            for x in x0..x1 {
-                let mut pt = Vec3A::default();
-                pt.x = x as f32 * sample_dist;
-                pt.y = (aabb.max.y + aabb.min.y) * 0.5;
-                pt.z = z as f32 * sample_dist;
-                // Make sure the samples are not too close to the edges.
-                // Jan: I believe this check is bugged, see https://github.com/recastnavigation/recastnavigation/issues/788
-                if dist_to_poly(nin, in_, pt) > -sample_dist / 2.0 {
-                    continue;
+        // The samples are stored in world units rather than in grid coordinates,
+        // so that the other strategies can place them anywhere.
+        let mid_y = (aabb.max.y + aabb.min.y) * 0.5;
+        let height = |x: f32, z: f32| {
+            let y = get_height(
+                Vec3A::new(x, mid_y, z),
+                ics,
+                chf.cell_height,
+                height_search_radius,
+                hp,
+            );
+            y as f32 * chf.cell_height
+        };
+        // Make sure the samples are not too close to the edges.
+        // Jan: I believe this check is bugged, see https://github.com/recastnavigation/recastnavigation/issues/788
+        let too_close =
+            |x: f32, z: f32| dist_to_poly(nin, in_, Vec3A::new(x, mid_y, z)) > -sample_dist / 2.0;
+        let grid = |samples: &mut Vec<(Vec3A, bool)>| {
+            // Create sample locations in a grid.
+            let x0 = (aabb.min.x / sample_dist).floor() as i32;
+            let x1 = (aabb.max.x / sample_dist).ceil() as i32;
+            let z0 = (aabb.min.z / sample_dist).floor() as i32;
+            let z1 = (aabb.max.z / sample_dist).ceil() as i32;
+            for z in z0..z1 {
+                for x in x0..x1 {
+                    let (x, z) = (x as f32 * sample_dist, z as f32 * sample_dist);
+                    if too_close(x, z) {
+                        continue;
+                    }
+                    let y = height(x, z);
+                    // The sample location is jittered to get rid of some bad triangulations
+                    // which are cause by symmetrical data from the grid structure.
+                    let i = samples.len();
+                    let pt = Vec3A::new(
+                        x + get_jitter_x(i) * cs * 0.1,
+                        y,
+                        z + get_jitter_y(i) * cs * 0.1,
+                    );
+                    samples.push((pt, false));
+                }
+            }
+        };
+        match sampling {
+            DetailSampling::Grid => grid(samples),
+            DetailSampling::BlueNoise => {
+                // The R2 sequence covers the bounds evenly without the structure of a grid. Rejecting the candidates
+                // that are too close to an accepted one turns it into blue noise.
+                const PLASTIC_NUMBER: f32 = 1.324_718;
+                let alpha = Vec2::new(
+                    1.0 / PLASTIC_NUMBER,
+                    1.0 / (PLASTIC_NUMBER * PLASTIC_NUMBER),
+                );
+                let size = aabb.max.xz() - aabb.min.xz();
+                let min_distance_squared = sample_dist * sample_dist;
+                let attempts = (4.0 * size.x * size.y / min_distance_squared).ceil() as usize;
+                for n in 0..attempts.min(MAX_BLUE_NOISE_ATTEMPTS) {
+                    let offset = (Vec2::splat(0.5) + alpha * n as f32).fract_gl() * size;
+                    let (x, z) = (aabb.min.x + offset.x, aabb.min.z + offset.y);
+                    let crowded = samples.iter().any(|(sample, _)| {
+                        sample.xz().distance_squared(Vec2::new(x, z)) < min_distance_squared
+                    });
+                    if crowded || too_close(x, z) {
+                        continue;
+                    }
+                    samples.push((Vec3A::new(x, height(x, z), z), false));
+                }
+            }
+            DetailSampling::EdgeBiased => {
+                grid(samples);
+                // Polygons are convex, so the inside of every edge faces the centroid.
+                let centroid = in_[..nin].iter().map(|v| v.xz()).sum::<Vec2>() / nin as f32;
+                let mut j = nin - 1;
+                for i in 0..nin {
+                    let (start, end) = (in_[j].xz(), in_[i].xz());
+                    j = i;
+                    let along = end - start;
+                    let length = along.length();
+                    if length <= f32::EPSILON {
+                        continue;
+                    }
+                    let mut inward = along.perp() / length;
+                    if inward.dot(centroid - start) < 0.0 {
+                        inward = -inward;
+                    }
+                    let count = (length / (sample_dist * 0.5)).floor() as usize;
+                    for k in 1..count {
+                        let pt = start + along * (k as f32 / count as f32) + inward * sample_dist;
+                        if too_close(pt.x, pt.y) {
+                            continue;
+                        }
+                        samples.push((Vec3A::new(pt.x, height(pt.x, pt.y), pt.y), false));
+                    }
                 }
-                let y = get_height(pt, ics, chf.cell_height, height_search_radius, hp);
-                samples.push((u16vec3(x as u16, y, z as u16), false));
             }
         }
 
@@ -503,16 +707,11 @@ fn build_poly_detail(
             let mut bestpt = Vec3A::default();
             let mut bestd = 0.0;
             let mut besti = None;
-            for (i, (s, added)) in samples.iter().enumerate() {
+            for (i, (pt, added)) in samples.iter().enumerate() {
                 if *added {
                     continue;
                 }
-                let mut pt = Vec3A::default();
-                // The sample location is jittered to get rid of some bad triangulations
-                // which are cause by symmetrical data from the grid structure.
-                pt.x = s.x as f32 * sample_dist + get_jitter_x(i) * cs * 0.1;
-                pt.y = s.y as f32 * chf.cell_height;
-                pt.z = s.z as f32 * sample_dist + get_jitter_y(i) * cs * 0.1;
+                let pt = *pt;
                 let d = dist_to_tri_mesh(pt, verts, tris);
                 let Some(d) = d else {
                     // did not hit the mesh.
@@ -993,6 +1192,9 @@ fn dist_pt_tri(p: Vec3A, a: Vec3A, b: Vec3A, c: Vec3A) -> Option<f32> {
     }
 }
 
+/// The most candidates [`DetailSampling::BlueNoise`] tries per polygon, so that huge polygons don't take forever.
+const MAX_BLUE_NOISE_ATTEMPTS: usize = 4096;
+
 fn get_jitter_x(i: usize) -> f32 {
     (((i * 0x8da6b343) & 0xffff) as f32 / 65535.0 * 2.0) - 1.0
 }
@@ -1219,8 +1421,171 @@ fn poly_min_extent_squared(verts: &[Vec3A], nverts: usize) -> f32 {
     min_dist
 }
 
+/// The bounds of polygon `i` in cells, padded by one cell and clamped to the heightfield,
+/// along with the number of vertices of the polygon.
+fn polygon_bounds(mesh: &PolygonNavmesh, chf: &CompactHeightfield, i: usize) -> (Bounds, usize) {
+    let nvp = mesh.max_vertices_per_polygon as usize;
+    let p = &mesh.polygons[i * nvp..];
+    let mut npoly = 0;
+    let mut b = Bounds {
+        xmin: chf.width,
+        xmax: 0,
+        zmin: chf.height,
+        zmax: 0,
+    };
+    for pj in &p[..nvp] {
+        if *pj == PolygonNavmesh::NO_INDEX {
+            break;
+        }
+        let v = &mesh.vertices[*pj as usize];
+        b.xmin = b.xmin.min(v.x);
+        b.xmax = b.xmax.max(v.x);
+        b.zmin = b.zmin.min(v.z);
+        b.zmax = b.zmax.max(v.z);
+        npoly += 1;
+    }
+    b.xmin = b.xmin.saturating_sub(1);
+    b.xmax = chf.width.min(b.xmax + 1);
+    b.zmin = b.zmin.saturating_sub(1);
+    b.zmax = chf.height.min(b.zmax + 1);
+    (b, npoly)
+}
+
+/// Builds the sub-meshes of single polygons, reusing its scratch buffers between them.
+/// Shared by [`DetailNavmesh::new`] and [`DetailNavmesh::rebuild_submeshes`].
+struct SubmeshBuilder<'a> {
+    mesh: &'a PolygonNavmesh,
+    chf: &'a CompactHeightfield,
+    sample_distance: f32,
+    sample_max_error: f32,
+    sampling: DetailSampling,
+    height_search_radius: u32,
+    edges: Vec<Edges>,
+    tris: Vec<[u8; 3]>,
+    flags: Vec<u8>,
+    arr: Vec<(i32, i32, usize)>,
+    samples: Vec<(Vec3A, bool)>,
+    verts: [Vec3A; 256],
+    hp: HeightPatch,
+    poly: Vec<Vec3A>,
+}
+
+impl<'a> SubmeshBuilder<'a> {
+    fn new(
+        mesh: &'a PolygonNavmesh,
+        chf: &'a CompactHeightfield,
+        sample_distance: f32,
+        sample_max_error: f32,
+        sampling: DetailSampling,
+    ) -> Self {
+        Self {
+            mesh,
+            chf,
+            sample_distance,
+            sample_max_error,
+            sampling,
+            height_search_radius: 1.max(mesh.max_edge_error.ceil() as u32),
+            edges: Vec::with_capacity(64 / 4),
+            tris: Vec::with_capacity((512 / 4) * 3),
+            flags: Vec::with_capacity(512 / 4),
+            arr: Vec::with_capacity(512 / 3),
+            samples: Vec::with_capacity(512 / 4),
+            verts: [Vec3A::default(); 256],
+            hp: HeightPatch::default(),
+            poly: vec![Vec3A::default(); mesh.max_vertices_per_polygon as usize],
+        }
+    }
+
+    /// Builds the sub-mesh of polygon `i` into [`Self::verts`], [`Self::tris`] and [`Self::flags`].
+    /// Returns the number of vertices, which are already in world space.
+    ///
+    /// [`HeightPatch::data`] must be large enough for `bounds`.
+    fn build(&mut self, i: usize, bounds: &Bounds) -> Result<usize, DetailNavmeshError> {
+        let mesh = self.mesh;
+        let chf = self.chf;
+        let nvp = mesh.max_vertices_per_polygon as usize;
+        let cs = mesh.cell_size;
+        let ch = mesh.cell_height;
+        let orig = Vec3A::from(mesh.aabb.min);
+        let p = &mesh.polygons[i * nvp..];
+
+        // Store polygon vertices for processing.
+        let mut npoly = 0;
+        for (pj, poly) in p[..nvp].iter().zip(&mut self.poly) {
+            if *pj == PolygonNavmesh::NO_INDEX {
+                break;
+            }
+            let v = mesh.vertices[*pj as usize].as_vec3();
+            poly.x = v.x * cs;
+            poly.y = v.y * ch;
+            poly.z = v.z * cs;
+            npoly += 1;
+        }
+
+        // Get the height data from the area of the polygon.
+        self.hp.xmin = bounds.xmin;
+        self.hp.zmin = bounds.zmin;
+        self.hp.width = bounds.width();
+        self.hp.height = bounds.height();
+        self.hp.get_height_data(
+            chf,
+            p,
+            npoly,
+            &self.verts,
+            mesh.border_size,
+            &mut self.arr,
+            mesh.regions[i],
+        );
+
+        // Build detail mesh.
+        let mut nverts = 0;
+        build_poly_detail(
+            &self.poly,
+            npoly,
+            self.sample_distance,
+            self.sample_max_error,
+            self.sampling,
+            self.height_search_radius,
+            chf,
+            &self.hp,
+            &mut self.verts,
+            &mut nverts,
+            &mut self.tris,
+            &mut self.flags,
+            &mut self.edges,
+            &mut self.samples,
+        )?;
+
+        // Move detail verts to world space.
+        for vert in &mut self.verts[..nverts] {
+            *vert += orig;
+            // [sic] Is this offset necessary?
+            vert.y += chf.cell_height;
+        }
+        // Offset poly too, will be used to flag checking.
+        for poly in &mut self.poly[..npoly] {
+            *poly += orig;
+        }
+        Ok(nverts)
+    }
+}
+
+/// Errors that can occur when building a [`DetailNavmesh`] with [`DetailNavmesh::new`]
+/// or updating it with [`DetailNavmesh::rebuild_submeshes`].
 #[derive(Error, Debug)]
-pub enum DetailNavmeshError {}
+pub enum DetailNavmeshError {
+    /// Happens when the detail mesh does not have one sub-mesh per polygon of the polygon mesh.
+    #[error("The detail mesh has {detail} sub-meshes, but the polygon mesh has {polygon} polygons")]
+    PolygonCountMismatch {
+        /// The number of sub-meshes of the detail mesh.
+        detail: usize,
+        /// The number of polygons of the polygon mesh.
+        polygon: usize,
+    },
+    /// Happens when a polygon to rebuild does not exist in the polygon mesh.
+    #[error("Polygon {0:?} does not exist in the polygon mesh")]
+    PolygonOutOfBounds(PolyId),
+}
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 struct HeightPatch {
@@ -1444,7 +1809,8 @@ impl HeightPatch {
                 dir_offset(0, if pcz > cz { 1 } else { -1 })
             } else {
                 dir_offset(if pcx > cx { 1 } else { -1 }, 0)
-            } as usize;
+            }
+            .expect("The offset is along one axis") as usize;
 
             // Push the direct dir last so we start with this on next iteration
             dirs.swap(direct_dir, 3);
@@ -1525,3 +1891,190 @@ impl Bounds {
         self.zmax - self.zmin
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sloped_quad() -> DetailNavmesh {
+        DetailNavmesh {
+            meshes: vec![SubMesh {
+                base_vertex_index: 0,
+                vertex_count: 4,
+                base_triangle_index: 0,
+                triangle_count: 2,
+            }],
+            vertices: vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 2.0),
+                Vec3::new(2.0, 1.0, 2.0),
+                Vec3::new(2.0, 1.0, 0.0),
+            ],
+            triangles: vec![[0, 1, 2], [0, 2, 3]],
+            triangle_flags: vec![0, 0],
+        }
+    }
+
+    #[test]
+    fn height_at_interpolates_triangles() {
+        let dmesh = sloped_quad();
+        assert_eq!(dmesh.height_at(PolyId(0), Vec2::new(0.0, 1.0)), Some(0.0));
+        assert_eq!(dmesh.height_at(PolyId(0), Vec2::new(1.0, 1.0)), Some(0.5));
+        assert_eq!(dmesh.height_at(PolyId(0), Vec2::new(1.5, 0.5)), Some(0.75));
+        assert_eq!(dmesh.height_at(PolyId(0), Vec2::new(2.0, 2.0)), Some(1.0));
+    }
+
+    /// A 10x10 floor with a ramp rising along the x-axis on its second half.
+    fn bumpy_floor() -> (PolygonNavmesh, CompactHeightfield) {
+        use crate::{AreaType, HeightfieldBuilder, NavmeshConfigBuilder, TriMesh};
+        use glam::UVec3;
+
+        let config = NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::new(5.0, 1.0, 5.0), [5.0, 2.0, 5.0]),
+            ..Default::default()
+        }
+        .build();
+        let vertices = vec![
+            Vec3A::new(0.0, 0.0, 0.0),
+            Vec3A::new(0.0, 0.0, 10.0),
+            Vec3A::new(5.0, 0.0, 10.0),
+            Vec3A::new(5.0, 0.0, 0.0),
+            Vec3A::new(10.0, 1.5, 10.0),
+            Vec3A::new(10.0, 1.5, 0.0),
+        ];
+        let trimesh = TriMesh {
+            vertices,
+            indices: vec![
+                UVec3::new(0, 1, 2),
+                UVec3::new(0, 2, 3),
+                UVec3::new(3, 2, 4),
+                UVec3::new(3, 4, 5),
+            ],
+            area_types: vec![AreaType::DEFAULT_WALKABLE; 4],
+        };
+        let mut heightfield = HeightfieldBuilder {
+            aabb: config.aabb,
+            cell_size: config.cell_size,
+            cell_height: config.cell_height,
+        }
+        .build()
+        .unwrap();
+        heightfield
+            .rasterize_triangles(&trimesh, config.walkable_climb)
+            .unwrap();
+        let mut chf = heightfield
+            .into_compact(config.walkable_height, config.walkable_climb)
+            .unwrap();
+        chf.build_distance_field();
+        chf.build_regions(config.border_size, 0, config.merge_region_area)
+            .unwrap();
+        let polygons = chf
+            .build_contours(
+                config.max_simplification_error,
+                config.max_edge_len,
+                config.contour_flags,
+            )
+            // Triangles, so that there are several polygons.
+            .into_polygon_mesh(3)
+            .unwrap();
+        (polygons, chf)
+    }
+
+    #[test]
+    fn rebuilding_submeshes_matches_full_build() {
+        let (polygons, chf) = bumpy_floor();
+        assert_eq!(polygons.polygon_count(), 2);
+        let coarse = DetailNavmesh::new(&polygons, &chf, 6.0, 1.0).unwrap();
+        let fine = DetailNavmesh::new(&polygons, &chf, 0.5, 0.01).unwrap();
+        assert_ne!(coarse.vertices.len(), fine.vertices.len());
+
+        // Rebuilding every polygon with other parameters is the same as building the detail mesh with them.
+        let all = polygons.polygon_ids().collect::<Vec<_>>();
+        let mut rebuilt = coarse.clone();
+        rebuilt
+            .rebuild_submeshes(&all, &polygons, &chf, 0.5, 0.01)
+            .unwrap();
+        assert_eq!(rebuilt, fine);
+
+        // Rebuilding the first polygon only changes its sub-mesh and shifts the following one.
+        let mut partial = coarse.clone();
+        partial
+            .rebuild_submeshes(&[PolyId(0), PolyId(0)], &polygons, &chf, 0.5, 0.01)
+            .unwrap();
+        let triangles = |dmesh: &DetailNavmesh, polygon: PolyId| {
+            let submesh = dmesh.submesh(polygon).unwrap();
+            let verts = &dmesh.vertices[submesh.base_vertex_index as usize..]
+                [..submesh.vertex_count as usize];
+            dmesh.triangles[submesh.base_triangle_index as usize..]
+                [..submesh.triangle_count as usize]
+                .iter()
+                .map(|tri| tri.map(|i| verts[i as usize]))
+                .collect::<Vec<_>>()
+        };
+        for polygon in polygons.polygon_ids() {
+            let expected = if polygon == PolyId(0) { &fine } else { &coarse };
+            assert_eq!(triangles(&partial, polygon), triangles(expected, polygon));
+        }
+        assert_eq!(partial.triangles.len(), partial.triangle_flags.len());
+
+        assert!(matches!(
+            partial.rebuild_submeshes(&[PolyId(u16::MAX)], &polygons, &chf, 0.5, 0.01),
+            Err(DetailNavmeshError::PolygonOutOfBounds(_))
+        ));
+    }
+
+    #[test]
+    fn sampling_strategies_add_detail_within_polygons() {
+        let (polygons, chf) = bumpy_floor();
+        let grid = DetailNavmesh::new(&polygons, &chf, 0.5, 0.01).unwrap();
+        assert_eq!(
+            DetailNavmesh::new_with(&polygons, &chf, 0.5, 0.01, DetailSampling::Grid).unwrap(),
+            grid
+        );
+        let coarse = DetailNavmesh::new(&polygons, &chf, 6.0, 1.0).unwrap();
+        for sampling in [DetailSampling::BlueNoise, DetailSampling::EdgeBiased] {
+            let dmesh = DetailNavmesh::new_with(&polygons, &chf, 0.5, 0.01, sampling).unwrap();
+            assert_ne!(dmesh, grid, "{sampling:?}");
+            assert!(dmesh.vertices.len() > coarse.vertices.len(), "{sampling:?}");
+            assert_eq!(dmesh.triangles.len(), dmesh.triangle_flags.len());
+            for polygon in polygons.polygon_ids() {
+                let submesh = dmesh.submesh(polygon).unwrap();
+                let vertices = &dmesh.vertices[submesh.base_vertex_index as usize..]
+                    [..submesh.vertex_count as usize];
+                let corners = polygons
+                    .polygon_vertices(polygon)
+                    .map(|vertex| {
+                        polygons.aabb.min + polygons.vertex(vertex).as_vec3() * polygons.cell_size
+                    })
+                    .collect::<Vec<_>>();
+                let min = corners
+                    .iter()
+                    .fold(Vec3::MAX, |min, corner| min.min(*corner));
+                let max = corners
+                    .iter()
+                    .fold(Vec3::MIN, |max, corner| max.max(*corner));
+                for vertex in vertices {
+                    assert!(
+                        vertex.x >= min.x - 1e-3
+                            && vertex.x <= max.x + 1e-3
+                            && vertex.z >= min.z - 1e-3
+                            && vertex.z <= max.z + 1e-3,
+                        "{sampling:?}: {vertex} outside of polygon {polygon:?}"
+                    );
+                }
+                for triangle in &dmesh.triangles[submesh.base_triangle_index as usize..]
+                    [..submesh.triangle_count as usize]
+                {
+                    assert!(triangle.iter().all(|&i| (i as usize) < vertices.len()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn height_at_outside_is_none() {
+        let dmesh = sloped_quad();
+        assert_eq!(dmesh.height_at(PolyId(0), Vec2::new(3.0, 1.0)), None);
+        assert_eq!(dmesh.height_at(PolyId(1), Vec2::new(1.0, 1.0)), None);
+    }
+}
@@ -1,12 +1,71 @@
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+
 use crate::{
-    AreaType, CompactHeightfield,
+    AreaType, BuildScratch, CompactHeightfield,
     math::{dir_offset_x, dir_offset_z},
 };
 
+/// The algorithm used by [`CompactHeightfield::erode_walkable_area_with`] to compute the distance of each span to the boundary of the walkable area.
+///
+/// All methods produce identical results and only differ in performance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum ErosionMethod {
+    /// A direct port of the C++ implementation, which looks up the neighbors of a span through its cell on every access.
+    #[default]
+    Reference,
+    /// Resolves the neighbors of all spans into a flat array up front, and then runs both passes of the
+    /// chamfer distance transform over that array with branchless min updates.
+    ///
+    /// Temporarily allocates 16 bytes per span.
+    FlatChamfer,
+}
+
 impl CompactHeightfield {
     /// Erode the walkable area by agent radius.
     pub fn erode_walkable_area(&mut self, erosion_radius: u16) {
-        let mut distance_to_boundary = vec![u8::MAX; self.spans.len()];
+        self.erode_walkable_area_with(erosion_radius, ErosionMethod::Reference);
+    }
+
+    /// Erode the walkable area by agent radius, using the given method to compute the distances to the boundary.
+    pub fn erode_walkable_area_with(&mut self, erosion_radius: u16, method: ErosionMethod) {
+        self.erode_walkable_area_with_scratch(erosion_radius, method, &mut BuildScratch::default());
+    }
+
+    /// Same as [`CompactHeightfield::erode_walkable_area_with`], but takes the temporary buffers from `scratch`.
+    pub(crate) fn erode_walkable_area_with_scratch(
+        &mut self,
+        erosion_radius: u16,
+        method: ErosionMethod,
+        scratch: &mut BuildScratch,
+    ) {
+        let distance_to_boundary = match method {
+            ErosionMethod::Reference => self.boundary_distances_reference(scratch),
+            ErosionMethod::FlatChamfer => self.boundary_distances_flat(scratch),
+        };
+
+        // Jan: This just wraps on overflow. Is that intentional???
+        let min_boundary_distance = (erosion_radius * 2) as u8;
+        #[expect(
+            clippy::needless_range_loop,
+            reason = "lol the alternative suggestion is really unreadable"
+        )]
+        for span_index in 0..self.spans.len() {
+            if distance_to_boundary[span_index] < min_boundary_distance {
+                self.areas[span_index] = AreaType::NOT_WALKABLE;
+            }
+        }
+        scratch.bytes.give(distance_to_boundary);
+    }
+
+    fn boundary_distances_reference(&self, scratch: &mut BuildScratch) -> Vec<u8> {
+        let mut distance_to_boundary = scratch.bytes.take(self.spans.len(), u8::MAX);
 
         // Mark boundary cells.
         for z in 0..self.height {
@@ -163,16 +222,78 @@ impl CompactHeightfield {
             }
         }
 
-        // Jan: This just wraps on overflow. Is that intentional???
-        let min_boundary_distance = (erosion_radius * 2) as u8;
-        #[expect(
-            clippy::needless_range_loop,
-            reason = "lol the alternative suggestion is really unreadable"
-        )]
-        for span_index in 0..self.spans.len() {
-            if distance_to_boundary[span_index] < min_boundary_distance {
-                self.areas[span_index] = AreaType::NOT_WALKABLE;
+        distance_to_boundary
+    }
+
+    fn boundary_distances_flat(&self, scratch: &mut BuildScratch) -> Vec<u8> {
+        let span_count = self.spans.len();
+        // A virtual span that stands in for all missing neighbors. Its distance stays at `u8::MAX`
+        // and its own neighbors are itself, so it never wins a min update and needs no branches.
+        let sentinel = span_count as u32;
+        let mut neighbors = scratch.neighbors.take(span_count + 1, [sentinel; 4]);
+        for z in 0..self.height {
+            for x in 0..self.width {
+                for span_index in self.cell_at(x, z).index_range() {
+                    let span = &self.spans[span_index];
+                    for direction in 0..4 {
+                        let Some(con) = span.con(direction) else {
+                            continue;
+                        };
+                        let neighbor_x = (x as i32 + dir_offset_x(direction) as i32) as u16;
+                        let neighbor_z = (z as i32 + dir_offset_z(direction) as i32) as u16;
+                        neighbors[span_index][direction as usize] =
+                            self.cell_at(neighbor_x, neighbor_z).index() + con as u32;
+                    }
+                }
             }
         }
+
+        let mut distance_to_boundary = scratch.bytes.take(span_count + 1, u8::MAX);
+
+        // Mark boundary cells.
+        for (span_index, span_neighbors) in neighbors[..span_count].iter().enumerate() {
+            let is_interior = self.areas[span_index].is_walkable()
+                && span_neighbors.iter().all(|&neighbor| {
+                    neighbor != sentinel && self.areas[neighbor as usize].is_walkable()
+                });
+            if !is_interior {
+                distance_to_boundary[span_index] = 0;
+            }
+        }
+
+        // The spans of a cell never neighbor each other, so their order within a cell does not matter.
+        // The cells however must be visited in the same order as in the reference implementation.
+
+        // Pass 1
+        for cell in &self.cells {
+            for span_index in cell.index_range() {
+                let [left, _, _, down] = neighbors[span_index];
+                let left_down = neighbors[left as usize][3];
+                let down_right = neighbors[down as usize][2];
+                distance_to_boundary[span_index] = distance_to_boundary[span_index]
+                    .min(distance_to_boundary[left as usize].saturating_add(2))
+                    .min(distance_to_boundary[left_down as usize].saturating_add(3))
+                    .min(distance_to_boundary[down as usize].saturating_add(2))
+                    .min(distance_to_boundary[down_right as usize].saturating_add(3));
+            }
+        }
+
+        // Pass 2
+        for cell in self.cells.iter().rev() {
+            for span_index in cell.index_range() {
+                let [_, up, right, _] = neighbors[span_index];
+                let right_up = neighbors[right as usize][1];
+                let up_left = neighbors[up as usize][0];
+                distance_to_boundary[span_index] = distance_to_boundary[span_index]
+                    .min(distance_to_boundary[right as usize].saturating_add(2))
+                    .min(distance_to_boundary[right_up as usize].saturating_add(3))
+                    .min(distance_to_boundary[up as usize].saturating_add(2))
+                    .min(distance_to_boundary[up_left as usize].saturating_add(3));
+            }
+        }
+
+        scratch.neighbors.give(neighbors);
+        distance_to_boundary.truncate(span_count);
+        distance_to_boundary
     }
 }
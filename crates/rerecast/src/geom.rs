@@ -0,0 +1,177 @@
+//! Geometry helpers used throughout the navmesh pipeline, for writing custom processing steps
+//! that work on the same grids and polygons as rerecast itself.
+//!
+//! Directions on the grid are numbered like in Recast: `0` is -x, `1` is +z, `2` is +x and `3` is -z.
+
+use glam::{Vec2, Vec3};
+
+pub use crate::math::{Aabb2d, Aabb3d};
+
+/// Gets the standard width (x-axis) offset for the specified direction.
+/// # Arguments
+/// - `direction`: The direction. Only the lowest two bits are used, so e.g. `4` is the same as `0`.
+/// # Returns
+///
+/// The width offset to apply to the current cell position to move in the direction.
+///
+/// ```
+/// # use rerecast::geom::{dir_offset_x, dir_offset_z};
+/// // Moving in direction 2 goes one cell along +x.
+/// assert_eq!((dir_offset_x(2), dir_offset_z(2)), (1, 0));
+/// ```
+#[inline]
+pub fn dir_offset_x(direction: u8) -> i8 {
+    const OFFSET: [i8; 4] = [-1, 0, 1, 0];
+    OFFSET[direction as usize & 0x03]
+}
+
+/// Gets the standard height (z-axis) offset for the specified direction.
+/// # Arguments
+/// - `direction`: The direction. Only the lowest two bits are used, so e.g. `4` is the same as `0`.
+/// # Returns
+///
+/// The height offset to apply to the current cell position to move in the direction.
+#[inline]
+pub fn dir_offset_z(direction: u8) -> i8 {
+    const OFFSET: [i8; 4] = [0, 1, 0, -1];
+    OFFSET[direction as usize & 0x03]
+}
+
+/// Gets the direction for the specified offset. The inverse of [`dir_offset_x`] and [`dir_offset_z`].
+/// # Arguments
+/// - `offset_x`: The width offset.
+/// - `offset_z`: The height offset.
+/// # Returns
+///
+/// The direction that moves by the offset, or `None` if the offset does not move by exactly one cell along one axis,
+/// i.e. if both offsets are `0`, the offset is diagonal, or it is longer than one cell.
+///
+/// ```
+/// # use rerecast::geom::dir_offset;
+/// assert_eq!(dir_offset(1, 0), Some(2));
+/// assert_eq!(dir_offset(1, 1), None);
+/// ```
+#[inline]
+pub fn dir_offset(offset_x: i32, offset_z: i32) -> Option<u8> {
+    match (offset_x, offset_z) {
+        (-1, 0) => Some(0),
+        (0, 1) => Some(1),
+        (1, 0) => Some(2),
+        (0, -1) => Some(3),
+        _ => None,
+    }
+}
+
+/// The index before `i` in a polygon with `n` vertices, wrapping around to the last one.
+///
+/// ```
+/// # use rerecast::geom::{next, prev};
+/// assert_eq!(prev(0, 4), 3);
+/// assert_eq!(next(3, 4), 0);
+/// ```
+#[inline]
+pub fn prev(i: usize, n: usize) -> usize {
+    (i + n - 1) % n
+}
+
+/// The index after `i` in a polygon with `n` vertices, wrapping around to the first one.
+#[inline]
+pub fn next(i: usize, n: usize) -> usize {
+    (i + 1) % n
+}
+
+/// The squared distance from `point` to the closest point on the segment from `start` to `end` on a plane.
+/// Segments of zero length are treated as a single point.
+///
+/// ```
+/// # use glam::Vec2;
+/// # use rerecast::geom::distance_squared_to_segment;
+/// let distance = distance_squared_to_segment(Vec2::new(1.0, 2.0), Vec2::ZERO, Vec2::new(4.0, 0.0));
+/// assert_eq!(distance, 4.0);
+/// ```
+pub fn distance_squared_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    let t = if length_squared > f32::EPSILON {
+        ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance_squared(start + segment * t)
+}
+
+/// Same as [`distance_squared_to_segment`], but in 3D.
+pub fn distance_squared_to_segment_3d(point: Vec3, start: Vec3, end: Vec3) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    let t = if length_squared > f32::EPSILON {
+        ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance_squared(start + segment * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prev() {
+        assert_eq!(prev(0, 4), 3);
+        assert_eq!(prev(1, 4), 0);
+        assert_eq!(prev(2, 4), 1);
+        assert_eq!(prev(3, 4), 2);
+    }
+
+    #[test]
+    fn test_next() {
+        assert_eq!(next(0, 4), 1);
+        assert_eq!(next(1, 4), 2);
+        assert_eq!(next(2, 4), 3);
+        assert_eq!(next(3, 4), 0);
+    }
+
+    #[test]
+    fn dir_offset_is_the_inverse_of_the_axis_offsets() {
+        for direction in 0..4 {
+            let (x, z) = (dir_offset_x(direction), dir_offset_z(direction));
+            assert_eq!(dir_offset(x.into(), z.into()), Some(direction));
+        }
+        assert_eq!(dir_offset(0, 0), None);
+        assert_eq!(dir_offset(-1, 1), None);
+        assert_eq!(dir_offset(0, 2), None);
+    }
+
+    #[test]
+    fn distance_to_segment_is_clamped_to_its_ends() {
+        let (start, end) = (Vec2::ZERO, Vec2::new(2.0, 0.0));
+        assert_eq!(
+            distance_squared_to_segment(Vec2::new(1.0, 1.0), start, end),
+            1.0
+        );
+        assert_eq!(
+            distance_squared_to_segment(Vec2::new(-3.0, 0.0), start, end),
+            9.0
+        );
+        assert_eq!(
+            distance_squared_to_segment(Vec2::new(5.0, 4.0), start, end),
+            25.0
+        );
+        assert_eq!(
+            distance_squared_to_segment(Vec2::new(0.0, 2.0), start, start),
+            4.0
+        );
+
+        let (start, end) = (Vec3::ZERO, Vec3::new(0.0, 0.0, 2.0));
+        assert_eq!(
+            distance_squared_to_segment_3d(Vec3::new(0.0, 3.0, 1.0), start, end),
+            9.0
+        );
+        assert_eq!(
+            distance_squared_to_segment_3d(Vec3::new(0.0, 0.0, 4.0), start, end),
+            4.0
+        );
+        assert_eq!(distance_squared_to_segment_3d(Vec3::ONE, start, start), 3.0);
+    }
+}
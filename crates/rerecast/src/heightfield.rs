@@ -5,9 +5,9 @@
 use thiserror::Error;
 
 use crate::{
-    Aabb3d, TriMesh,
+    Aabb3d, BuildScratch, TriMesh,
     rasterize::RasterizationError,
-    span::{Span, SpanKey, Spans},
+    span::{AreaMergePolicy, Span, SpanKey, Spans},
 };
 
 /// A dynamic heightfield representing obstructed space.
@@ -147,9 +147,10 @@ impl Heightfield {
             if (new_span.max as i32 - current_span.max as i32).unsigned_abs()
                 <= insertion.flag_merge_threshold as u32
             {
-                // Higher area ID numbers indicate higher resolution priority.
-                let area = new_span.area.max(current_span.area.0);
-                new_span.area = area.into();
+                // The original always lets higher area ID numbers win here.
+                new_span.area = insertion
+                    .merge_policy
+                    .merge(current_span.area, new_span.area);
             }
 
             // Remove the current span since it's now merged with newSpan.
@@ -239,6 +240,20 @@ impl Heightfield {
     pub fn span_mut(&mut self, key: SpanKey) -> &mut Span {
         &mut self.allocated_spans[key]
     }
+
+    /// Defragments [`Heightfield::allocated_spans`], which accumulates holes when spans are merged during rasterization.
+    /// Afterwards, the spans are stored in column order, which makes walking the columns more cache friendly.
+    ///
+    /// All [`SpanKey`]s change, so keys obtained before calling this are invalid afterwards.
+    /// See [`Spans::compact`].
+    pub fn compact_spans(&mut self) {
+        self.allocated_spans.compact(&mut self.spans);
+    }
+
+    /// Same as [`Heightfield::compact_spans`], but compacts into spare storage of `scratch`.
+    pub(crate) fn compact_spans_with_scratch(&mut self, scratch: &mut BuildScratch) {
+        scratch.compact_spans(&mut self.allocated_spans, &mut self.spans);
+    }
 }
 
 /// A builder for [`Heightfield`]s.
@@ -258,6 +273,14 @@ impl HeightfieldBuilder {
     ///
     /// Panics if the column count is above `usize::MAX`.
     pub fn build(self) -> Result<Heightfield, HeightfieldBuilderError> {
+        self.build_with_scratch(&mut BuildScratch::default())
+    }
+
+    /// Same as [`HeightfieldBuilder::build`], but takes the columns and span storage from `scratch`.
+    pub(crate) fn build_with_scratch(
+        self,
+        scratch: &mut BuildScratch,
+    ) -> Result<Heightfield, HeightfieldBuilderError> {
         let width = (self.aabb.max.x - self.aabb.min.x) / self.cell_size + 0.5;
         let height = (self.aabb.max.z - self.aabb.min.z) / self.cell_size + 0.5;
         let column_count = width as u128 * height as u128;
@@ -271,8 +294,8 @@ impl HeightfieldBuilder {
             aabb: self.aabb,
             cell_size: self.cell_size,
             cell_height: self.cell_height,
-            spans: vec![None; column_count],
-            allocated_spans: Spans::with_min_capacity(column_count),
+            spans: scratch.columns.take(column_count, None),
+            allocated_spans: scratch.take_spans(column_count),
         })
     }
 }
@@ -310,6 +333,8 @@ pub(crate) struct SpanInsertion {
     pub(crate) z: u16,
     /// Maximum difference between the ceilings of two spans to merge area type IDs
     pub(crate) flag_merge_threshold: u16,
+    /// How to resolve the area type when merging with an existing span
+    pub(crate) merge_policy: AreaMergePolicy,
     /// The span to insert
     pub(crate) span: Span,
 }
@@ -377,6 +402,7 @@ mod tests {
                 x: 1,
                 z: 3,
                 flag_merge_threshold: 0,
+                merge_policy: AreaMergePolicy::MaxWins,
                 span: expected_span.clone(),
             })
             .unwrap();
@@ -396,6 +422,7 @@ mod tests {
                 x: 1,
                 z: 3,
                 flag_merge_threshold: 0,
+                merge_policy: AreaMergePolicy::MaxWins,
                 span: expected_span_1.clone(),
             })
             .unwrap();
@@ -406,6 +433,7 @@ mod tests {
                 x: 2,
                 z: 3,
                 flag_merge_threshold: 0,
+                merge_policy: AreaMergePolicy::MaxWins,
                 span: expected_span_2.clone(),
             })
             .unwrap();
@@ -428,6 +456,7 @@ mod tests {
                 x: 1,
                 z: 3,
                 flag_merge_threshold: 0,
+                merge_policy: AreaMergePolicy::MaxWins,
                 span: span_low.clone(),
             })
             .unwrap();
@@ -438,6 +467,7 @@ mod tests {
                 x: 1,
                 z: 3,
                 flag_merge_threshold: 0,
+                merge_policy: AreaMergePolicy::MaxWins,
                 span: span_high.clone(),
             })
             .unwrap();
@@ -461,6 +491,7 @@ mod tests {
                 x: 1,
                 z: 3,
                 flag_merge_threshold: 0,
+                merge_policy: AreaMergePolicy::MaxWins,
                 span: span_high.clone(),
             })
             .unwrap();
@@ -471,6 +502,7 @@ mod tests {
                 x: 1,
                 z: 3,
                 flag_merge_threshold: 0,
+                merge_policy: AreaMergePolicy::MaxWins,
                 span: span_low.clone(),
             })
             .unwrap();
@@ -494,6 +526,7 @@ mod tests {
                 x: 1,
                 z: 3,
                 flag_merge_threshold: 0,
+                merge_policy: AreaMergePolicy::MaxWins,
                 span: span_low.clone(),
             })
             .unwrap();
@@ -504,6 +537,7 @@ mod tests {
                 x: 1,
                 z: 3,
                 flag_merge_threshold: 0,
+                merge_policy: AreaMergePolicy::MaxWins,
                 span: span_mid.clone(),
             })
             .unwrap();
@@ -523,10 +557,88 @@ mod tests {
         assert_eq!(empty_span, None);
     }
 
+    fn merged_area(merge_policy: AreaMergePolicy) -> AreaType {
+        let mut heightfield = height_field();
+        for area in [AreaType(5), AreaType(2)] {
+            heightfield
+                .add_span(SpanInsertion {
+                    x: 1,
+                    z: 3,
+                    flag_merge_threshold: 1,
+                    merge_policy,
+                    span: SpanBuilder { area, ..span_low() }.build(),
+                })
+                .unwrap();
+        }
+        heightfield.span_at(1, 3).unwrap().area
+    }
+
+    #[test]
+    fn merge_policy_decides_area() {
+        assert_eq!(merged_area(AreaMergePolicy::MaxWins), AreaType(5));
+        assert_eq!(merged_area(AreaMergePolicy::NewWins), AreaType(2));
+        assert_eq!(merged_area(AreaMergePolicy::OldWins), AreaType(5));
+        assert_eq!(
+            merged_area(AreaMergePolicy::Custom(|_old, _new| AreaType::NOT_WALKABLE)),
+            AreaType::NOT_WALKABLE
+        );
+    }
+
     #[track_caller]
     fn assert_eq_without_next(span: &Span, expected_span: &Span) {
         assert_eq!(span.min, expected_span.min, "min is not equal");
         assert_eq!(span.max, expected_span.max, "max is not equal");
         assert_eq!(span.area, expected_span.area, "area is not equal");
     }
+
+    #[test]
+    fn compacting_spans_keeps_columns_and_removes_holes() {
+        let mut heightfield = height_field();
+        // The mid span overlaps both others, so the first span is merged away and leaves a hole.
+        for (x, z, span) in [
+            (1, 3, span_low()),
+            (2, 2, span_high()),
+            (1, 3, span_high()),
+            (1, 3, span_mid()),
+            (0, 0, span_low()),
+        ] {
+            heightfield
+                .add_span(SpanInsertion {
+                    x,
+                    z,
+                    flag_merge_threshold: 0,
+                    merge_policy: AreaMergePolicy::MaxWins,
+                    span: span.build(),
+                })
+                .unwrap();
+        }
+        let columns = |heightfield: &Heightfield| {
+            (0..heightfield.spans.len())
+                .map(|i| {
+                    let mut spans = Vec::new();
+                    let mut key = heightfield.spans[i];
+                    while let Some(span_key) = key {
+                        let span = heightfield.span(span_key);
+                        spans.push((span.min, span.max));
+                        key = span.next;
+                    }
+                    spans
+                })
+                .collect::<Vec<_>>()
+        };
+        let before = columns(&heightfield);
+        assert_eq!(heightfield.allocated_spans.len(), 3);
+
+        heightfield.compact_spans();
+
+        assert_eq!(columns(&heightfield), before);
+        assert_eq!(heightfield.allocated_spans.len(), 3);
+        // The spans are stored in column order.
+        let stored: Vec<_> = heightfield
+            .allocated_spans
+            .iter()
+            .map(|(_, span)| (span.min, span.max))
+            .collect();
+        assert_eq!(stored, vec![(2, 4), (7, 10), (2, 10)]);
+    }
 }
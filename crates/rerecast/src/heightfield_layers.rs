@@ -0,0 +1,673 @@
+//! Splitting a [`CompactHeightfield`] into 2D layers of walkable floors that don't overlap each other.
+//!
+//! A port of `rcBuildHeightfieldLayers` of the original Recast. Every layer stores at most one floor per column,
+//! so multi-story buildings end up as one layer per story. The layers are small enough to be kept around
+//! and rebuilt into navmesh tiles at runtime, which is what Detour's `dtTileCache` does.
+
+use std::collections::VecDeque;
+
+use crate::{Aabb3d, AreaType, CompactHeightfield};
+
+/// A 2D layer of walkable floors built with [`CompactHeightfield::build_heightfield_layers`].
+///
+/// Every cell of the layer holds at most one span, so the layer can be stored as flat grids of [`Self::width`] × [`Self::height`] cells.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeightfieldLayer {
+    /// The AABB of the layer in world space. Excludes the border of the heightfield it was built from.
+    pub aabb: Aabb3d,
+    /// The size of each cell on the xz-plane
+    pub cell_size: f32,
+    /// The size of each cell along the y-axis
+    pub cell_height: f32,
+    /// The width of the layer along the x-axis in cell units
+    pub width: u16,
+    /// The height of the layer along the z-axis in cell units
+    pub height: u16,
+    /// The minimum x-coordinate of the cells that hold a span.
+    pub min_x: u16,
+    /// The maximum x-coordinate of the cells that hold a span.
+    pub max_x: u16,
+    /// The minimum z-coordinate of the cells that hold a span.
+    pub min_z: u16,
+    /// The maximum z-coordinate of the cells that hold a span.
+    pub max_z: u16,
+    /// The minimum height of the spans of the layer in cell units, relative to the heightfield it was built from.
+    pub min_y: u16,
+    /// The maximum height of the spans of the layer in cell units, relative to the heightfield it was built from.
+    pub max_y: u16,
+    /// The height of the span of every cell relative to [`Self::min_y`],
+    /// or [`HeightfieldLayer::NO_SPAN`] if the cell has no span in this layer. [Size: `width * height`]
+    pub heights: Vec<u8>,
+    /// The area type of the span of every cell. [Size: `width * height`]
+    pub areas: Vec<AreaType>,
+    /// The connections of the span of every cell. [Size: `width * height`]
+    ///
+    /// The lower 4 bits mark the directions in which the span connects to a span of the same layer,
+    /// the upper 4 bits the directions in which it connects to a span of a different layer, i.e. a portal.
+    /// Use [`Self::connection_mask`] and [`Self::portal_mask`] to read them.
+    pub connections: Vec<u8>,
+}
+
+impl HeightfieldLayer {
+    /// The value of [`Self::heights`] for cells without a span.
+    pub const NO_SPAN: u8 = 0xff;
+
+    /// The index of the cell at the given coordinates into [`Self::heights`], [`Self::areas`] and [`Self::connections`].
+    #[inline]
+    pub fn cell_index(&self, x: u16, z: u16) -> usize {
+        x as usize + z as usize * self.width as usize
+    }
+
+    /// The height of the span at the given cell relative to [`Self::min_y`], if the cell has a span in this layer.
+    #[inline]
+    pub fn height_at(&self, x: u16, z: u16) -> Option<u8> {
+        let height = self.heights[self.cell_index(x, z)];
+        (height != Self::NO_SPAN).then_some(height)
+    }
+
+    /// The directions in which the span at the given cell connects to a span of the same layer, one bit per direction.
+    #[inline]
+    pub fn connection_mask(&self, x: u16, z: u16) -> u8 {
+        self.connections[self.cell_index(x, z)] & 0xf
+    }
+
+    /// The directions in which the span at the given cell connects to a span of a different layer, one bit per direction.
+    #[inline]
+    pub fn portal_mask(&self, x: u16, z: u16) -> u8 {
+        self.connections[self.cell_index(x, z)] >> 4
+    }
+
+    /// The number of cells that hold a span.
+    pub fn span_count(&self) -> usize {
+        self.heights
+            .iter()
+            .filter(|height| **height != Self::NO_SPAN)
+            .count()
+    }
+}
+
+/// The layers of a [`CompactHeightfield`], built with [`CompactHeightfield::build_heightfield_layers`].
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeightfieldLayerSet {
+    /// The layers, ordered by the first monotone region they contain.
+    pub layers: Vec<HeightfieldLayer>,
+}
+
+/// A monotone region found while sweeping the heightfield.
+#[derive(Debug, Clone)]
+struct LayerRegion {
+    /// The regions that overlap this one in at least one column.
+    layers: Vec<usize>,
+    /// The regions next to this one.
+    neighbors: Vec<usize>,
+    y_min: u16,
+    y_max: u16,
+    layer_id: Option<usize>,
+    /// Whether the region is the root of its layer.
+    base: bool,
+}
+
+/// A run of spans in the current row.
+#[derive(Debug, Clone, Copy, Default)]
+struct LayerSweepSpan {
+    /// The number of spans of the run that connect to [`Self::neighbor`].
+    samples: usize,
+    /// The final region id of the run.
+    id: usize,
+    /// The region in the previous row that all connected spans of the run connect to, if there is exactly one.
+    neighbor: Option<usize>,
+}
+
+/// Whether the ranges `a_min..=a_max` and `b_min..=b_max` overlap.
+fn overlap_range(a_min: u16, a_max: u16, b_min: u16, b_max: u16) -> bool {
+    !(a_min > b_max || a_max < b_min)
+}
+
+fn add_unique(values: &mut Vec<usize>, value: usize) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+impl CompactHeightfield {
+    /// Splits the walkable spans into layers that don't overlap each other.
+    ///
+    /// The heightfield is partitioned into monotone regions, which are then merged into layers
+    /// as long as no two regions of a layer share a column and the height range of the layer stays below 255 cells.
+    /// Layers that are close in height are merged as well, which keeps stairs and ramps on the same layer as their floor.
+    ///
+    /// - `border_size`: The size of the non-navigable border around the heightfield. `[Limit: >=0] [Units: vx]`
+    /// - `walkable_height`: The minimum floor to 'ceiling' height that will still allow the floor area to be considered walkable. `[Limit: >= 3] [Units: vx]`
+    ///
+    /// The layers cover the heightfield without its border. Neither the distance field nor the regions are needed.
+    pub fn build_heightfield_layers(
+        &self,
+        border_size: u16,
+        walkable_height: u16,
+    ) -> HeightfieldLayerSet {
+        let w = self.width;
+        let h = self.height;
+
+        let mut src_reg: Vec<Option<usize>> = vec![None; self.spans.len()];
+        // The original stores region and layer ids as bytes and fails once there are more than 255 regions.
+        // We use indices instead, so only the height range of a layer is limited.
+        let mut sweeps = Vec::<LayerSweepSpan>::new();
+        let mut prev_count = Vec::new();
+        let mut region_id = 0;
+
+        // Partition walkable area into monotone regions.
+        for z in border_size..h.saturating_sub(border_size) {
+            prev_count.clear();
+            prev_count.resize(region_id, 0_usize);
+            sweeps.clear();
+
+            for x in border_size..w.saturating_sub(border_size) {
+                let cell = self.cell_at(x, z);
+                for i in cell.index_range() {
+                    let span = &self.spans[i];
+                    if !self.areas[i].is_walkable() {
+                        continue;
+                    }
+
+                    // -x
+                    let mut sweep_id = None;
+                    if let Some(con) = span.con(0) {
+                        let (_, _, a_i) = self.con_indices(x as i32, z as i32, 0, con);
+                        if self.areas[a_i].is_walkable() {
+                            sweep_id = src_reg[a_i];
+                        }
+                    }
+                    let sweep_id = sweep_id.unwrap_or_else(|| {
+                        sweeps.push(LayerSweepSpan::default());
+                        sweeps.len() - 1
+                    });
+
+                    // -z
+                    if let Some(con) = span.con(3) {
+                        let (_, _, a_i) = self.con_indices(x as i32, z as i32, 3, con);
+                        if let Some(neighbor) = src_reg[a_i] {
+                            let sweep = &mut sweeps[sweep_id];
+                            // Set neighbour when first valid neighbour is encoutered.
+                            if sweep.samples == 0 {
+                                sweep.neighbor = Some(neighbor);
+                            }
+                            if sweep.neighbor == Some(neighbor) {
+                                // Update existing neighbour
+                                sweep.samples += 1;
+                                prev_count[neighbor] += 1;
+                            } else {
+                                // This is hit if there is more than one neighbour.
+                                // Invalidate the neighbour.
+                                sweep.neighbor = None;
+                            }
+                        }
+                    }
+
+                    src_reg[i] = Some(sweep_id);
+                }
+            }
+
+            // Create unique ID.
+            for sweep in &mut sweeps {
+                // If the neighbour is set and there is only one continuous connection to it,
+                // the sweep will be merged with the previous one, else new region is created.
+                match sweep.neighbor {
+                    Some(neighbor) if prev_count[neighbor] == sweep.samples => {
+                        sweep.id = neighbor;
+                    }
+                    _ => {
+                        sweep.id = region_id;
+                        region_id += 1;
+                    }
+                }
+            }
+
+            // Remap local sweep ids to region ids.
+            for x in border_size..w.saturating_sub(border_size) {
+                let cell = self.cell_at(x, z);
+                for i in cell.index_range() {
+                    if let Some(sweep_id) = src_reg[i] {
+                        src_reg[i] = Some(sweeps[sweep_id].id);
+                    }
+                }
+            }
+        }
+
+        // Allocate and init layer regions.
+        let region_count = region_id;
+        let mut regions = vec![
+            LayerRegion {
+                layers: Vec::new(),
+                neighbors: Vec::new(),
+                y_min: u16::MAX,
+                y_max: 0,
+                layer_id: None,
+                base: false,
+            };
+            region_count
+        ];
+
+        // Find region neighbours and overlapping regions.
+        // The original caps the neighbors at 16 and the overlapping regions at 63 and fails on overflow.
+        // We store as many as there are.
+        let mut column_regions = Vec::new();
+        for z in 0..h {
+            for x in 0..w {
+                let cell = self.cell_at(x, z);
+                column_regions.clear();
+                for i in cell.index_range() {
+                    let span = &self.spans[i];
+                    let Some(ri) = src_reg[i] else {
+                        continue;
+                    };
+                    let region = &mut regions[ri];
+                    region.y_min = region.y_min.min(span.y);
+                    region.y_max = region.y_max.max(span.y);
+
+                    // Collect all region layers.
+                    column_regions.push(ri);
+
+                    // Update neighbours
+                    for dir in 0..4 {
+                        let Some(con) = span.con(dir) else {
+                            continue;
+                        };
+                        let (_, _, a_i) = self.con_indices(x as i32, z as i32, dir, con);
+                        if let Some(rai) = src_reg[a_i]
+                            && rai != ri
+                        {
+                            add_unique(&mut region.neighbors, rai);
+                        }
+                    }
+                }
+
+                // Update overlapping regions.
+                for (i, &a) in column_regions.iter().enumerate() {
+                    for &b in &column_regions[i + 1..] {
+                        if a != b {
+                            add_unique(&mut regions[a].layers, b);
+                            add_unique(&mut regions[b].layers, a);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Create 2D layers from regions.
+        // The original skips neighbors once its stack of 64 regions is full, which splits large layers.
+        // We use a growable queue instead.
+        let mut layer_id = 0;
+        let mut stack = VecDeque::new();
+        for i in 0..region_count {
+            // Skip already visited.
+            if regions[i].layer_id.is_some() {
+                continue;
+            }
+
+            // Start search.
+            regions[i].layer_id = Some(layer_id);
+            regions[i].base = true;
+            stack.clear();
+            stack.push_back(i);
+
+            while let Some(current) = stack.pop_front() {
+                for j in 0..regions[current].neighbors.len() {
+                    let neighbor = regions[current].neighbors[j];
+                    // Skip already visited.
+                    if regions[neighbor].layer_id.is_some() {
+                        continue;
+                    }
+                    // Skip if the neighbour is overlapping root region.
+                    if regions[i].layers.contains(&neighbor) {
+                        continue;
+                    }
+                    // Skip if the height range would become too large.
+                    let y_min = regions[i].y_min.min(regions[neighbor].y_min);
+                    let y_max = regions[i].y_max.max(regions[neighbor].y_max);
+                    if y_max - y_min >= 255 {
+                        continue;
+                    }
+
+                    // Deepen
+                    stack.push_back(neighbor);
+
+                    // Mark layer id
+                    regions[neighbor].layer_id = Some(layer_id);
+                    // Merge current layers to root.
+                    let merged = std::mem::take(&mut regions[neighbor].layers);
+                    let root = &mut regions[i];
+                    for &layer in &merged {
+                        add_unique(&mut root.layers, layer);
+                    }
+                    root.y_min = y_min;
+                    root.y_max = y_max;
+                    regions[neighbor].layers = merged;
+                }
+            }
+
+            layer_id += 1;
+        }
+
+        // Merge non-overlapping regions that are close in height.
+        let merge_height = walkable_height * 4;
+        for i in 0..region_count {
+            if !regions[i].base {
+                continue;
+            }
+            let new_id = regions[i].layer_id;
+            loop {
+                let mut old_id = None;
+                let ri = &regions[i];
+                for (j, rj) in regions.iter().enumerate() {
+                    if i == j || !rj.base {
+                        continue;
+                    }
+
+                    // Skip if the regions are not close to each other.
+                    if !overlap_range(
+                        ri.y_min,
+                        ri.y_max + merge_height,
+                        rj.y_min,
+                        rj.y_max + merge_height,
+                    ) {
+                        continue;
+                    }
+                    // Skip if the height range would become too large.
+                    let y_min = ri.y_min.min(rj.y_min);
+                    let y_max = ri.y_max.max(rj.y_max);
+                    if y_max - y_min >= 255 {
+                        continue;
+                    }
+
+                    // Make sure that there is no overlap when merging 'ri' and 'rj'.
+                    // Iterate over all regions which have the same layer id as 'rj'
+                    // and check if they are overlapping region 'ri'.
+                    let overlap = regions
+                        .iter()
+                        .enumerate()
+                        .any(|(k, rk)| rk.layer_id == rj.layer_id && ri.layers.contains(&k));
+                    // Cannot merge of regions overlap.
+                    if overlap {
+                        continue;
+                    }
+
+                    // Can merge i and j.
+                    old_id = rj.layer_id;
+                    break;
+                }
+
+                // Could not find anything to merge with, stop.
+                let Some(old_id) = old_id else {
+                    break;
+                };
+
+                // Merge
+                for j in 0..region_count {
+                    if regions[j].layer_id != Some(old_id) {
+                        continue;
+                    }
+                    regions[j].base = false;
+                    // Remap layer ids.
+                    regions[j].layer_id = new_id;
+                    // Add overlaid layers from 'rj' to 'ri'.
+                    let merged = std::mem::take(&mut regions[j].layers);
+                    let (y_min, y_max) = (regions[j].y_min, regions[j].y_max);
+                    let ri = &mut regions[i];
+                    for &layer in &merged {
+                        add_unique(&mut ri.layers, layer);
+                    }
+                    // Update height bounds.
+                    ri.y_min = ri.y_min.min(y_min);
+                    ri.y_max = ri.y_max.max(y_max);
+                    regions[j].layers = merged;
+                }
+            }
+        }
+
+        // Compact layer ids
+        let mut remap = vec![None; layer_id];
+        // Find number of unique layers.
+        for region in &regions {
+            if let Some(id) = region.layer_id {
+                remap[id] = Some(0);
+            }
+        }
+        let mut layer_count = 0;
+        for id in remap.iter_mut().flatten() {
+            *id = layer_count;
+            layer_count += 1;
+        }
+        // Remap ids.
+        for region in &mut regions {
+            region.layer_id = region.layer_id.and_then(|id| remap[id]);
+        }
+
+        // No layers, return empty.
+        let mut set = HeightfieldLayerSet::default();
+        if layer_count == 0 {
+            return set;
+        }
+
+        // Create layers.
+        let lw = w.saturating_sub(border_size * 2);
+        let lh = h.saturating_sub(border_size * 2);
+
+        // Build contracted bbox for layers.
+        let mut aabb = self.aabb;
+        let border = border_size as f32 * self.cell_size;
+        aabb.min.x += border;
+        aabb.min.z += border;
+        aabb.max.x -= border;
+        aabb.max.z -= border;
+
+        // Store layers.
+        for current_id in 0..layer_count {
+            let grid_size = lw as usize * lh as usize;
+
+            // Find layer height bounds.
+            let (min_y, max_y) = regions
+                .iter()
+                .rfind(|region| region.base && region.layer_id == Some(current_id))
+                .map_or((0, 0), |region| (region.y_min, region.y_max));
+
+            let mut layer = HeightfieldLayer {
+                aabb,
+                cell_size: self.cell_size,
+                cell_height: self.cell_height,
+                width: lw,
+                height: lh,
+                // Update usable data region.
+                min_x: lw,
+                max_x: 0,
+                min_z: lh,
+                max_z: 0,
+                min_y,
+                max_y,
+                heights: vec![HeightfieldLayer::NO_SPAN; grid_size],
+                areas: vec![AreaType::NOT_WALKABLE; grid_size],
+                connections: vec![0; grid_size],
+            };
+            // Adjust the bbox to fit the heightfield.
+            layer.aabb.min.y = aabb.min.y + min_y as f32 * self.cell_height;
+            layer.aabb.max.y = aabb.min.y + max_y as f32 * self.cell_height;
+
+            // Copy height and area from compact heightfield.
+            for z in 0..lh {
+                for x in 0..lw {
+                    let cx = border_size + x;
+                    let cz = border_size + z;
+                    let cell = self.cell_at(cx, cz);
+                    for j in cell.index_range() {
+                        let span = &self.spans[j];
+                        // Skip unassigned regions.
+                        let Some(region) = src_reg[j] else {
+                            continue;
+                        };
+                        // Skip if it does not belong to current layer.
+                        let lid = regions[region].layer_id;
+                        if lid != Some(current_id) {
+                            continue;
+                        }
+
+                        // Update data bounds.
+                        layer.min_x = layer.min_x.min(x);
+                        layer.max_x = layer.max_x.max(x);
+                        layer.min_z = layer.min_z.min(z);
+                        layer.max_z = layer.max_z.max(z);
+
+                        // Store height and area type.
+                        let index = layer.cell_index(x, z);
+                        layer.heights[index] = (span.y - min_y) as u8;
+                        layer.areas[index] = self.areas[j];
+
+                        // Check connection.
+                        let mut portal = 0;
+                        let mut con_mask = 0;
+                        for dir in 0..4 {
+                            let Some(con) = span.con(dir) else {
+                                continue;
+                            };
+                            let (a_x, a_z, a_i) = self.con_indices(cx as i32, cz as i32, dir, con);
+                            if !self.areas[a_i].is_walkable() {
+                                continue;
+                            }
+                            let alid = src_reg[a_i].and_then(|region| regions[region].layer_id);
+                            if lid != alid {
+                                // Portal mask
+                                portal |= 1 << dir;
+                                // Update height so that it matches on both sides of the portal.
+                                let a_span = &self.spans[a_i];
+                                if a_span.y > min_y {
+                                    // The original truncates the height to a byte, which wraps around
+                                    // for portals to spans more than 255 cells above the layer. We saturate instead.
+                                    let portal_height = (a_span.y - min_y)
+                                        .min(HeightfieldLayer::NO_SPAN as u16 - 1);
+                                    layer.heights[index] =
+                                        layer.heights[index].max(portal_height as u8);
+                                }
+                            } else {
+                                // Valid connection mask
+                                let nx = a_x - border_size as i32;
+                                let nz = a_z - border_size as i32;
+                                if nx >= 0 && nz >= 0 && nx < lw as i32 && nz < lh as i32 {
+                                    con_mask |= 1 << dir;
+                                }
+                            }
+                        }
+                        layer.connections[index] = (portal << 4) | con_mask;
+                    }
+                }
+            }
+
+            if layer.min_x > layer.max_x {
+                layer.min_x = 0;
+                layer.max_x = 0;
+            }
+            if layer.min_z > layer.max_z {
+                layer.min_z = 0;
+                layer.max_z = 0;
+            }
+            set.layers.push(layer);
+        }
+
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3, Vec3A};
+
+    use super::*;
+    use crate::{HeightfieldBuilder, TriMesh};
+
+    fn quad(y: f32, min: [f32; 2], max: [f32; 2]) -> [Vec3A; 4] {
+        [
+            Vec3A::new(min[0], y, min[1]),
+            Vec3A::new(min[0], y, max[1]),
+            Vec3A::new(max[0], y, max[1]),
+            Vec3A::new(max[0], y, min[1]),
+        ]
+    }
+
+    fn compact(quads: &[[Vec3A; 4]]) -> CompactHeightfield {
+        let vertices = quads.concat();
+        let indices = (0..quads.len() as u32)
+            .flat_map(|quad| {
+                let base = quad * 4;
+                [
+                    UVec3::new(base, base + 1, base + 2),
+                    UVec3::new(base, base + 2, base + 3),
+                ]
+            })
+            .collect();
+        let mut trimesh = TriMesh::from_parts(vertices, indices).unwrap();
+        trimesh.mark_walkable_triangles(45.0_f32.to_radians());
+        let mut heightfield = HeightfieldBuilder {
+            aabb: Aabb3d {
+                min: Vec3::new(-6.0, -1.0, -6.0),
+                max: Vec3::new(6.0, 20.0, 6.0),
+            },
+            cell_size: 0.5,
+            cell_height: 0.25,
+        }
+        .build()
+        .unwrap();
+        heightfield.rasterize_triangles(&trimesh, 1).unwrap();
+        heightfield.into_compact(8, 2).unwrap()
+    }
+
+    #[test]
+    fn single_floor_is_a_single_layer() {
+        let compact = compact(&[quad(0.0, [-5.0, -5.0], [5.0, 5.0])]);
+        let set = compact.build_heightfield_layers(2, 8);
+        assert_eq!(set.layers.len(), 1);
+
+        let layer = &set.layers[0];
+        assert_eq!(layer.width, compact.width - 4);
+        let inner_spans = (2..compact.height - 2)
+            .flat_map(|z| (2..compact.width - 2).map(move |x| (x, z)))
+            .map(|(x, z)| compact.cell_at(x, z).count() as usize)
+            .sum::<usize>();
+        assert_eq!(layer.span_count(), inner_spans);
+        assert!(
+            (0..layer.height)
+                .flat_map(|z| (0..layer.width).map(move |x| (x, z)))
+                .all(|(x, z)| layer.portal_mask(x, z) == 0)
+        );
+        // Spans in the middle connect in every direction.
+        assert_eq!(
+            layer.connection_mask(layer.width / 2, layer.height / 2),
+            0xf
+        );
+    }
+
+    #[test]
+    fn stories_are_separate_layers() {
+        let compact = compact(&[
+            quad(0.0, [-5.0, -5.0], [5.0, 5.0]),
+            quad(10.0, [-5.0, -5.0], [5.0, 5.0]),
+        ]);
+        let set = compact.build_heightfield_layers(2, 8);
+        assert_eq!(set.layers.len(), 2);
+
+        let (lower, upper) = (&set.layers[0], &set.layers[1]);
+        assert_eq!(lower.span_count(), upper.span_count());
+        assert!(upper.min_y > lower.max_y);
+        assert!(upper.aabb.min.y > lower.aabb.max.y);
+        let center = (lower.width / 2, lower.height / 2);
+        assert_eq!(lower.height_at(center.0, center.1), Some(0));
+        assert_eq!(upper.height_at(center.0, center.1), Some(0));
+    }
+
+    #[test]
+    fn empty_heightfield_has_no_layers() {
+        let compact = compact(&[]);
+        assert_eq!(
+            compact.build_heightfield_layers(0, 8),
+            HeightfieldLayerSet::default()
+        );
+    }
+}
@@ -0,0 +1,496 @@
+//! Rasterization of regular-grid heightmaps, e.g. terrain chunks, into a [`Heightfield`].
+//!
+//! A heightmap can be converted into a [`TriMesh`] with [`Heightmap::to_trimesh`], but that needs two triangles per sample,
+//! each of which is then clipped against every column it touches. Since the samples already lie on a grid,
+//! [`Heightfield::rasterize_heightmap`] instead clips the quads between them to the columns directly,
+//! which is much faster and never allocates the triangle soup.
+
+use std::f32::consts::FRAC_PI_2;
+
+use glam::{UVec2, UVec3, Vec2, Vec3, Vec3A};
+use thiserror::Error;
+
+use crate::{
+    Aabb3d, AreaMergePolicy, AreaType, Heightfield, RasterizationError, Span, TriMesh,
+    heightfield::SpanInsertion, span::SpanBuilder,
+};
+
+/// A regular grid of height samples, such as a terrain chunk.
+///
+/// The sample at `(x, z)` lies at `origin + (x * spacing.x, heights[z * size.x + x], z * spacing.y)`.
+/// Samples with a height of `NaN` are holes: the quads touching them are not rasterized.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Heightmap {
+    /// The position of the first sample. The heights are relative to its y-coordinate.
+    pub origin: Vec3,
+    /// The distance between neighboring samples along the x- and z-axis. `[Limit: >0] [Units: wu]`
+    pub spacing: Vec2,
+    /// The number of samples along the x- and z-axis.
+    pub size: UVec2,
+    /// The heights of the samples in row-major order, i.e. with the x-coordinate changing fastest.
+    pub heights: Vec<f32>,
+}
+
+impl Heightmap {
+    /// Creates a new heightmap, checking that there is one height per sample,
+    /// that there is at least one quad, and that the spacing is positive.
+    pub fn new(
+        origin: Vec3,
+        spacing: Vec2,
+        size: UVec2,
+        heights: Vec<f32>,
+    ) -> Result<Self, HeightmapError> {
+        let heightmap = Self {
+            origin,
+            spacing,
+            size,
+            heights,
+        };
+        heightmap.validate()?;
+        Ok(heightmap)
+    }
+
+    /// Checks the invariants described in [`Heightmap::new`].
+    ///
+    /// Rasterizing a heightmap that does not uphold them panics.
+    pub fn validate(&self) -> Result<(), HeightmapError> {
+        if self.size.x < 2 || self.size.y < 2 {
+            return Err(HeightmapError::TooSmall(self.size));
+        }
+        if !(self.spacing.x > 0.0 && self.spacing.y > 0.0 && self.spacing.is_finite()) {
+            return Err(HeightmapError::InvalidSpacing(self.spacing));
+        }
+        let expected = self.size.x as usize * self.size.y as usize;
+        if self.heights.len() != expected {
+            return Err(HeightmapError::SizeMismatch {
+                expected,
+                found: self.heights.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the relative height of the sample at `(x, z)`.
+    #[inline]
+    fn sample(&self, x: u32, z: u32) -> f32 {
+        self.heights[z as usize * self.size.x as usize + x as usize]
+    }
+
+    /// Returns the world-space height of the surface at the given position on the xz-plane,
+    /// interpolating bilinearly between the surrounding samples.
+    ///
+    /// Returns `None` outside the heightmap and within holes.
+    pub fn height_at(&self, xz: Vec2) -> Option<f32> {
+        let local = (xz - Vec2::new(self.origin.x, self.origin.z)) / self.spacing;
+        let last = (self.size - 1).as_vec2();
+        if !(local.cmpge(Vec2::ZERO).all() && local.cmple(last).all()) {
+            return None;
+        }
+        // Positions on the far edges belong to the last quad.
+        let quad = local.floor().min(last - 1.0).as_uvec2();
+        let [h00, h10, h01, h11] = self.quad_heights(quad)?;
+        let t = local - quad.as_vec2();
+        let height = bilinear(h00, h10, h01, h11, t.x, t.y);
+        Some(self.origin.y + height)
+    }
+
+    /// Returns the relative heights of the corners of the quad whose first sample is at `quad`,
+    /// in the order `(0, 0)`, `(1, 0)`, `(0, 1)`, `(1, 1)`. Returns `None` if any of them is a hole.
+    fn quad_heights(&self, quad: UVec2) -> Option<[f32; 4]> {
+        let heights = [
+            self.sample(quad.x, quad.y),
+            self.sample(quad.x + 1, quad.y),
+            self.sample(quad.x, quad.y + 1),
+            self.sample(quad.x + 1, quad.y + 1),
+        ];
+        heights.iter().all(|h| !h.is_nan()).then_some(heights)
+    }
+
+    /// Computes the bounding box of all samples that are not holes.
+    ///
+    /// Returns `None` if every sample is a hole.
+    pub fn aabb(&self) -> Option<Aabb3d> {
+        let (min_height, max_height) = self.heights.iter().filter(|h| !h.is_nan()).fold(
+            None,
+            |range: Option<(f32, f32)>, &h| match range {
+                Some((min, max)) => Some((min.min(h), max.max(h))),
+                None => Some((h, h)),
+            },
+        )?;
+        let extent = (self.size - 1).as_vec2() * self.spacing;
+        Some(Aabb3d {
+            min: self.origin + Vec3::Y * min_height,
+            max: self.origin + Vec3::new(extent.x, max_height, extent.y),
+        })
+    }
+
+    /// Triangulates the heightmap, skipping the quads that touch a hole.
+    ///
+    /// All triangles are [`AreaType::NOT_WALKABLE`], like in [`TriMesh::from_parts`].
+    /// This is only needed where a [`TriMesh`] is required, as [`Heightfield::rasterize_heightmap`] is much cheaper.
+    pub fn to_trimesh(&self) -> TriMesh {
+        let mut vertex_indices = vec![None; self.heights.len()];
+        let mut vertices = Vec::new();
+        let mut vertex = |x: u32, z: u32| -> u32 {
+            let index = z as usize * self.size.x as usize + x as usize;
+            *vertex_indices[index].get_or_insert_with(|| {
+                vertices.push(Vec3A::from(
+                    self.origin
+                        + Vec3::new(
+                            x as f32 * self.spacing.x,
+                            self.heights[index],
+                            z as f32 * self.spacing.y,
+                        ),
+                ));
+                vertices.len() as u32 - 1
+            })
+        };
+        let mut indices = Vec::new();
+        for z in 0..self.size.y - 1 {
+            for x in 0..self.size.x - 1 {
+                if self.quad_heights(UVec2::new(x, z)).is_none() {
+                    continue;
+                }
+                let [a, b, c, d] = [
+                    vertex(x, z),
+                    vertex(x + 1, z),
+                    vertex(x, z + 1),
+                    vertex(x + 1, z + 1),
+                ];
+                // Wound so that the normals point up.
+                indices.push(UVec3::new(a, c, b));
+                indices.push(UVec3::new(b, c, d));
+            }
+        }
+        let area_types = vec![AreaType::NOT_WALKABLE; indices.len()];
+        TriMesh {
+            vertices,
+            indices,
+            area_types,
+        }
+    }
+}
+
+/// Errors that can occur when creating a [`Heightmap`] with [`Heightmap::new`].
+#[derive(Error, Debug)]
+pub enum HeightmapError {
+    /// The number of heights does not match the number of samples.
+    #[error("Expected {expected} heights, but found {found}")]
+    SizeMismatch {
+        /// The number of samples.
+        expected: usize,
+        /// The number of heights.
+        found: usize,
+    },
+    /// The heightmap has less than two samples along an axis, so it has no quads.
+    #[error("A heightmap needs at least 2 × 2 samples, but has {0}")]
+    TooSmall(UVec2),
+    /// The spacing between samples is not positive and finite.
+    #[error("The spacing between samples must be positive, but is {0}")]
+    InvalidSpacing(Vec2),
+}
+
+/// Interpolates between the corner heights of a quad at the relative position `(u, v)`.
+#[inline]
+fn bilinear(h00: f32, h10: f32, h01: f32, h11: f32, u: f32, v: f32) -> f32 {
+    let near = h00 + (h10 - h00) * u;
+    let far = h01 + (h11 - h01) * u;
+    near + (far - near) * v
+}
+
+impl Heightfield {
+    /// Rasterizes a [`Heightmap`] into the heightfield.
+    ///
+    /// Quads between the samples that are flatter than `walkable_slope_angle` get the given `area`,
+    /// the others are marked as [`AreaType::NOT_WALKABLE`]. An angle of `0.5*π` or more makes every quad walkable. `[Limit: >=0] [Units: Radians]`
+    ///
+    /// The result matches rasterizing [`Heightmap::to_trimesh`] after [`TriMesh::mark_walkable_triangles_as`],
+    /// except that the slope is judged per quad instead of per triangle.
+    pub fn rasterize_heightmap(
+        &mut self,
+        heightmap: &Heightmap,
+        walkable_slope_angle: f32,
+        area: AreaType,
+        flag_merge_threshold: u16,
+    ) -> Result<(), RasterizationError> {
+        self.rasterize_heightmap_with_merge_policy(
+            heightmap,
+            walkable_slope_angle,
+            area,
+            flag_merge_threshold,
+            AreaMergePolicy::default(),
+        )
+    }
+
+    /// Same as [`Heightfield::rasterize_heightmap`], but merges overlapping spans with the given [`AreaMergePolicy`],
+    /// like [`Heightfield::rasterize_triangle_with_merge_policy`].
+    pub fn rasterize_heightmap_with_merge_policy(
+        &mut self,
+        heightmap: &Heightmap,
+        walkable_slope_angle: f32,
+        area: AreaType,
+        flag_merge_threshold: u16,
+        merge_policy: AreaMergePolicy,
+    ) -> Result<(), RasterizationError> {
+        let inverse_cell_size = 1.0 / self.cell_size;
+        let inverse_cell_height = 1.0 / self.cell_height;
+        let max_y = self.aabb.max.y - self.aabb.min.y;
+        let max_gradient = if walkable_slope_angle < FRAC_PI_2 {
+            walkable_slope_angle.tan()
+        } else {
+            f32::INFINITY
+        };
+        // The position of the first sample relative to the heightfield.
+        let origin = heightmap.origin - self.aabb.min;
+        let column = |position: f32| position * inverse_cell_size;
+
+        for quad_z in 0..heightmap.size.y - 1 {
+            let z0 = origin.z + quad_z as f32 * heightmap.spacing.y;
+            let z1 = z0 + heightmap.spacing.y;
+            // Columns that only touch the edge of the quad are left to the neighboring quad.
+            let first_z = (column(z0).floor() as i32).max(0);
+            let last_z = (column(z1).ceil() as i32 - 1).min(self.height as i32 - 1);
+            if first_z > last_z {
+                continue;
+            }
+            for quad_x in 0..heightmap.size.x - 1 {
+                let x0 = origin.x + quad_x as f32 * heightmap.spacing.x;
+                let x1 = x0 + heightmap.spacing.x;
+                let first_x = (column(x0).floor() as i32).max(0);
+                let last_x = (column(x1).ceil() as i32 - 1).min(self.width as i32 - 1);
+                if first_x > last_x {
+                    continue;
+                }
+                let Some([h00, h10, h01, h11]) = heightmap.quad_heights(UVec2::new(quad_x, quad_z))
+                else {
+                    continue;
+                };
+                let gradient = Vec2::new(
+                    (h10 - h00 + h11 - h01) / (2.0 * heightmap.spacing.x),
+                    (h01 - h00 + h11 - h10) / (2.0 * heightmap.spacing.y),
+                );
+                let area = if gradient.length() < max_gradient {
+                    area
+                } else {
+                    AreaType::NOT_WALKABLE
+                };
+                let height = |x: f32, z: f32| {
+                    let u = (x - x0) / heightmap.spacing.x;
+                    let v = (z - z0) / heightmap.spacing.y;
+                    origin.y + bilinear(h00, h10, h01, h11, u, v)
+                };
+
+                for z in first_z..=last_z {
+                    let cell_z0 = (z as f32 * self.cell_size).max(z0);
+                    let cell_z1 = ((z + 1) as f32 * self.cell_size).min(z1);
+                    for x in first_x..=last_x {
+                        let cell_x0 = (x as f32 * self.cell_size).max(x0);
+                        let cell_x1 = ((x + 1) as f32 * self.cell_size).min(x1);
+                        // A bilinear patch has no extrema in its interior,
+                        // so the corners of the clipped quad bound its heights within the column.
+                        let corners = [
+                            height(cell_x0, cell_z0),
+                            height(cell_x1, cell_z0),
+                            height(cell_x0, cell_z1),
+                            height(cell_x1, cell_z1),
+                        ];
+                        let span_min = corners.into_iter().fold(f32::MAX, f32::min);
+                        let span_max = corners.into_iter().fold(f32::MIN, f32::max);
+                        // Skip the span if it's completely outside the heightfield bounding box
+                        if span_max < 0.0 || span_min > max_y {
+                            continue;
+                        }
+                        let span_min = span_min.max(0.0);
+                        let span_max = span_max.min(max_y);
+
+                        // Snap the span to the heightfield height grid, like triangles do.
+                        // The minimum stays below the top so that the span is at least one cell tall.
+                        let min = ((span_min * inverse_cell_height).floor() as i32)
+                            .clamp(0, Span::MAX_HEIGHT as i32 - 1)
+                            as u16;
+                        let max = ((span_max * inverse_cell_height).ceil() as i32)
+                            .clamp(min as i32 + 1, Span::MAX_HEIGHT as i32)
+                            as u16;
+                        self.add_span(SpanInsertion {
+                            x: x as u16,
+                            z: z as u16,
+                            span: SpanBuilder {
+                                min,
+                                max,
+                                area,
+                                next: None,
+                            }
+                            .build(),
+                            flag_merge_threshold,
+                            merge_policy,
+                        })?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        BuildContext, BuildScratch, HeightfieldBuilder, NavmeshConfig, NavmeshConfigBuilder,
+        PolygonNavmesh, build_navmesh, build_navmesh_with_rasterizer,
+    };
+
+    fn heightfield() -> Heightfield {
+        HeightfieldBuilder {
+            aabb: Aabb3d {
+                min: Vec3::new(0.0, 0.0, 0.0),
+                max: Vec3::new(4.0, 4.0, 4.0),
+            },
+            cell_size: 0.25,
+            cell_height: 0.1,
+        }
+        .build()
+        .unwrap()
+    }
+
+    fn heightmap(size: u32, spacing: f32, height: impl Fn(u32, u32) -> f32) -> Heightmap {
+        let heights = (0..size)
+            .flat_map(|z| (0..size).map(move |x| (x, z)))
+            .map(|(x, z)| height(x, z))
+            .collect();
+        Heightmap::new(
+            Vec3::new(0.5, 1.0, 0.5),
+            Vec2::splat(spacing),
+            UVec2::splat(size),
+            heights,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn flat_heightmap_fills_the_covered_columns() {
+        let mut heightfield = heightfield();
+        let heightmap = heightmap(5, 0.5, |_, _| 0.25);
+        heightfield
+            .rasterize_heightmap(
+                &heightmap,
+                45_f32.to_radians(),
+                AreaType::DEFAULT_WALKABLE,
+                1,
+            )
+            .unwrap();
+        for z in 0..heightfield.height {
+            for x in 0..heightfield.width {
+                let covered = (2..10).contains(&x) && (2..10).contains(&z);
+                let span = heightfield.span_at(x, z);
+                assert_eq!(span.is_some(), covered, "column ({x}, {z})");
+                if let Some(span) = span {
+                    assert_eq!((span.min, span.max), (12, 13));
+                    assert_eq!(span.area, AreaType::DEFAULT_WALKABLE);
+                    assert!(span.next.is_none());
+                }
+            }
+        }
+        assert_eq!(heightmap.height_at(Vec2::new(2.5, 2.5)), Some(1.25));
+        assert_eq!(heightmap.height_at(Vec2::new(2.6, 2.5)), None);
+    }
+
+    #[test]
+    fn steep_quads_are_not_walkable_and_holes_are_skipped() {
+        let mut heightfield = heightfield();
+        // A ramp rising by 1 wu every 0.5 wu along the x-axis from the third sample on, with a hole at its start.
+        let heightmap = heightmap(5, 0.5, |x, z| match (x, z) {
+            (0, 0) => f32::NAN,
+            _ => x.saturating_sub(2) as f32,
+        });
+        heightfield
+            .rasterize_heightmap(
+                &heightmap,
+                45_f32.to_radians(),
+                AreaType::DEFAULT_WALKABLE,
+                1,
+            )
+            .unwrap();
+        assert!(heightfield.span_at(2, 2).is_none());
+        assert_eq!(
+            heightfield.span_at(3, 4).unwrap().area,
+            AreaType::DEFAULT_WALKABLE
+        );
+        let steep = heightfield.span_at(7, 4).unwrap();
+        assert_eq!(steep.area, AreaType::NOT_WALKABLE);
+        assert_eq!((steep.min, steep.max), (15, 20));
+        assert_eq!(heightmap.height_at(Vec2::new(2.25, 1.0)), Some(2.5));
+        assert_eq!(heightmap.height_at(Vec2::new(0.6, 0.6)), None);
+    }
+
+    #[test]
+    fn spans_above_the_highest_height_are_clamped() {
+        // Fine enough that the top of the heightfield lies far above the highest height a span can have.
+        let mut heightfield = HeightfieldBuilder {
+            aabb: Aabb3d {
+                min: Vec3::new(0.0, 0.0, 0.0),
+                max: Vec3::new(4.0, 8.0, 4.0),
+            },
+            cell_size: 0.25,
+            cell_height: 0.0001,
+        }
+        .build()
+        .unwrap();
+        let heightmap = heightmap(5, 0.5, |_, _| 6.0);
+        heightfield
+            .rasterize_heightmap(
+                &heightmap,
+                45_f32.to_radians(),
+                AreaType::DEFAULT_WALKABLE,
+                1,
+            )
+            .unwrap();
+        let span = heightfield.span_at(4, 4).unwrap();
+        assert_eq!(
+            (span.min, span.max),
+            (Span::MAX_HEIGHT - 1, Span::MAX_HEIGHT)
+        );
+    }
+
+    fn surface_area(polygon: &PolygonNavmesh) -> f32 {
+        polygon
+            .polygon_ids()
+            .map(|id| polygon.surface_area(id))
+            .sum()
+    }
+
+    #[test]
+    fn rasterized_heightmap_matches_its_trimesh() {
+        let heightmap = heightmap(33, 0.25, |x, z| {
+            ((x as f32 * 0.3).sin() + (z as f32 * 0.2).cos()) * 0.4
+        });
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3::new(4.5, 1.0, 4.5), [4.5, 2.0, 4.5]),
+            ..NavmeshConfigBuilder::default().build()
+        };
+        let (from_trimesh, _) = build_navmesh(heightmap.to_trimesh(), &config).unwrap();
+        let (from_heightmap, _) = build_navmesh_with_rasterizer(
+            &config,
+            &[],
+            &mut BuildScratch::default(),
+            &BuildContext::default(),
+            |heightfield| {
+                heightfield.rasterize_heightmap(
+                    &heightmap,
+                    config.walkable_slope_angle,
+                    AreaType::DEFAULT_WALKABLE,
+                    config.walkable_climb,
+                )
+            },
+        )
+        .unwrap();
+        let expected = surface_area(&from_trimesh);
+        let area = surface_area(&from_heightmap);
+        assert!(expected > 30.0, "{expected} wu²");
+        assert!(
+            (area - expected).abs() < expected * 0.02,
+            "{area} wu² instead of {expected} wu²"
+        );
+    }
+}
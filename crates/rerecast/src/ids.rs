@@ -0,0 +1,187 @@
+//! Typed indices into a [`PolygonNavmesh`](crate::PolygonNavmesh) and [`DetailNavmesh`](crate::DetailNavmesh),
+//! so that polygon indices, vertex indices and neighbor encodings cannot be mixed up.
+
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+
+use crate::PolygonNavmesh;
+
+/// The index of a polygon in a [`PolygonNavmesh`].
+/// Also identifies the sub-mesh of the polygon in the associated [`DetailNavmesh`](crate::DetailNavmesh).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[repr(transparent)]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct PolyId(pub u16);
+
+impl PolyId {
+    /// Creates an ID from the raw polygon index.
+    #[inline]
+    pub const fn new(index: u16) -> Self {
+        Self(index)
+    }
+
+    /// The raw polygon index.
+    #[inline]
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+
+    /// The raw polygon index, for indexing into the per-polygon arrays of the meshes.
+    #[inline]
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl From<u16> for PolyId {
+    #[inline]
+    fn from(index: u16) -> Self {
+        Self(index)
+    }
+}
+
+impl From<PolyId> for u16 {
+    #[inline]
+    fn from(id: PolyId) -> Self {
+        id.0
+    }
+}
+
+/// The index of a vertex in [`PolygonNavmesh::vertices`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[repr(transparent)]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct VertexId(pub u16);
+
+impl VertexId {
+    /// Creates an ID from the raw vertex index.
+    #[inline]
+    pub const fn new(index: u16) -> Self {
+        Self(index)
+    }
+
+    /// The raw vertex index.
+    #[inline]
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+
+    /// The raw vertex index, for indexing into [`PolygonNavmesh::vertices`].
+    #[inline]
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl From<u16> for VertexId {
+    #[inline]
+    fn from(index: u16) -> Self {
+        Self(index)
+    }
+}
+
+impl From<VertexId> for u16 {
+    #[inline]
+    fn from(id: VertexId) -> Self {
+        id.0
+    }
+}
+
+/// An edge of a polygon in a [`PolygonNavmesh`].
+///
+/// The edge starts at the vertex with index [`EdgeId::edge`] within the polygon and ends at the next vertex of the polygon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct EdgeId {
+    /// The polygon the edge belongs to.
+    pub polygon: PolyId,
+    /// The index of the edge within the polygon.
+    pub edge: u8,
+}
+
+impl EdgeId {
+    /// Creates an ID for the given edge of the given polygon.
+    #[inline]
+    pub const fn new(polygon: PolyId, edge: u8) -> Self {
+        Self { polygon, edge }
+    }
+}
+
+/// What lies on the other side of an edge, decoded from [`PolygonNavmesh::polygon_neighbors`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+pub enum EdgeConnection {
+    /// The edge is shared with another polygon of the same mesh.
+    Polygon(PolyId),
+    /// The edge lies on the border of the tile and continues on the neighboring tile on the given side.
+    ///
+    /// The sides are numbered like the directions of a [`CompactHeightfield`](crate::CompactHeightfield):
+    /// 0 is -x, 1 is +z, 2 is +x and 3 is -z.
+    Portal(u8),
+    /// The edge is a solid border.
+    Border,
+}
+
+impl EdgeConnection {
+    /// The bit that marks a portal in [`PolygonNavmesh::polygon_neighbors`].
+    pub const PORTAL_FLAG: u16 = 0x8000;
+
+    /// Decodes an entry of [`PolygonNavmesh::polygon_neighbors`].
+    #[inline]
+    pub const fn from_raw(raw: u16) -> Self {
+        if raw == PolygonNavmesh::NO_CONNECTION {
+            Self::Border
+        } else if raw & Self::PORTAL_FLAG != 0 {
+            Self::Portal((raw & 0xf) as u8)
+        } else {
+            Self::Polygon(PolyId(raw))
+        }
+    }
+
+    /// Encodes the connection as an entry of [`PolygonNavmesh::polygon_neighbors`].
+    #[inline]
+    pub const fn to_raw(self) -> u16 {
+        match self {
+            Self::Polygon(polygon) => polygon.0,
+            Self::Portal(side) => Self::PORTAL_FLAG | side as u16,
+            Self::Border => PolygonNavmesh::NO_CONNECTION,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edge_connections_roundtrip() {
+        for raw in [0, 42, 0x8000, 0x8003, PolygonNavmesh::NO_CONNECTION] {
+            assert_eq!(EdgeConnection::from_raw(raw).to_raw(), raw);
+        }
+        assert_eq!(
+            EdgeConnection::from_raw(42),
+            EdgeConnection::Polygon(PolyId(42))
+        );
+        assert_eq!(EdgeConnection::from_raw(0x8002), EdgeConnection::Portal(2));
+        assert_eq!(
+            EdgeConnection::from_raw(PolygonNavmesh::NO_CONNECTION),
+            EdgeConnection::Border
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! Invariant checks run after each stage of the pipeline in debug builds.
+//!
+//! A broken invariant usually only surfaces stages later, e.g. as a missing polygon or a panic deep inside the triangulation.
+//! Checking right after the stage that produced the data points at the actual culprit instead.
+
+use crate::{
+    BuildStage, CompactHeightfield, ContourSet, EdgeConnection, Heightfield, PolygonNavmesh,
+    RegionId,
+    geom::{dir_offset_x, dir_offset_z},
+};
+
+/// Panics with the stage and a description of the broken invariant if `$cond` does not hold.
+macro_rules! check {
+    ($stage:expr, $cond:expr, $($arg:tt)+) => {
+        assert!(
+            $cond,
+            "Navmesh integrity check after the {} stage failed: {}",
+            $stage.name(),
+            format_args!($($arg)+)
+        );
+    };
+}
+
+impl Heightfield {
+    /// Checks that the spans of every column are sorted from bottom to top without touching each other.
+    ///
+    /// If `defragmented` is set, also checks that every allocated span belongs to a column.
+    pub(crate) fn assert_integrity(&self, stage: BuildStage, defragmented: bool) {
+        check!(
+            stage,
+            self.spans.len() == self.width as usize * self.height as usize,
+            "expected {} columns, found {}",
+            self.width as usize * self.height as usize,
+            self.spans.len()
+        );
+        let mut reachable = 0;
+        for z in 0..self.height {
+            for x in 0..self.width {
+                let mut key = self.spans[self.column_index(x, z)];
+                let mut previous_max = None;
+                while let Some(current) = key {
+                    let span = self.allocated_spans.get(current);
+                    check!(
+                        stage,
+                        span.is_some(),
+                        "column ({x}, {z}) links to a span that does not exist"
+                    );
+                    let span = span.unwrap();
+                    reachable += 1;
+                    check!(
+                        stage,
+                        reachable <= self.allocated_spans.len(),
+                        "column ({x}, {z}) links its spans in a cycle"
+                    );
+                    check!(
+                        stage,
+                        span.min < span.max,
+                        "span at ({x}, {z}) is empty or inverted: {}..{}",
+                        span.min,
+                        span.max
+                    );
+                    if let Some(previous_max) = previous_max {
+                        check!(
+                            stage,
+                            previous_max < span.min,
+                            "spans of column ({x}, {z}) are unsorted or overlapping: span ending at {previous_max} is followed by span starting at {}",
+                            span.min
+                        );
+                    }
+                    previous_max = Some(span.max);
+                    key = span.next;
+                }
+            }
+        }
+        if defragmented {
+            check!(
+                stage,
+                reachable == self.allocated_spans.len(),
+                "{} allocated spans do not belong to any column",
+                self.allocated_spans.len() - reachable
+            );
+        }
+    }
+}
+
+impl CompactHeightfield {
+    /// Checks that the cells cover the spans in order, that the spans of every cell are sorted,
+    /// and that every connection leads to a span in the neighboring cell.
+    ///
+    /// Connections are also checked for symmetry when [`Self::walkable_climb`] is below [`Self::walkable_height`].
+    /// Otherwise, a span may legitimately connect to a neighbor that connects to a lower span of the same cell.
+    pub(crate) fn assert_integrity(&self, stage: BuildStage) {
+        check!(
+            stage,
+            self.cells.len() == self.width as usize * self.height as usize,
+            "expected {} cells, found {}",
+            self.width as usize * self.height as usize,
+            self.cells.len()
+        );
+        check!(
+            stage,
+            self.areas.len() == self.spans.len(),
+            "{} spans have {} areas",
+            self.spans.len(),
+            self.areas.len()
+        );
+        let mut next_index = 0;
+        for (cell_index, cell) in self.cells.iter().enumerate() {
+            check!(
+                stage,
+                cell.count() == 0 || cell.index() as usize == next_index,
+                "cell {cell_index} starts at span {} instead of {next_index}",
+                cell.index()
+            );
+            next_index += cell.count() as usize;
+        }
+        check!(
+            stage,
+            next_index == self.spans.len(),
+            "cells cover {next_index} spans, but there are {}",
+            self.spans.len()
+        );
+
+        let symmetric = self.walkable_climb < self.walkable_height;
+        for z in 0..self.height {
+            for x in 0..self.width {
+                let cell = self.cell_at(x, z);
+                for i in cell.index_range() {
+                    let span = &self.spans[i];
+                    if i > cell.index_range().start {
+                        check!(
+                            stage,
+                            self.spans[i - 1].y < span.y,
+                            "spans of cell ({x}, {z}) are not sorted by height"
+                        );
+                    }
+                    for dir in 0..4 {
+                        let Some(con) = span.con(dir) else {
+                            continue;
+                        };
+                        let neighbor_x = x as i32 + dir_offset_x(dir) as i32;
+                        let neighbor_z = z as i32 + dir_offset_z(dir) as i32;
+                        check!(
+                            stage,
+                            (0..self.width as i32).contains(&neighbor_x)
+                                && (0..self.height as i32).contains(&neighbor_z),
+                            "span {i} at ({x}, {z}) connects out of bounds in direction {dir}"
+                        );
+                        let neighbor_cell = self.cell_at(neighbor_x as u16, neighbor_z as u16);
+                        check!(
+                            stage,
+                            con < neighbor_cell.count(),
+                            "span {i} at ({x}, {z}) connects to layer {con} in direction {dir}, but the neighboring cell only has {} spans",
+                            neighbor_cell.count()
+                        );
+                        if !symmetric {
+                            continue;
+                        }
+                        let neighbor = &self.spans[neighbor_cell.index() as usize + con as usize];
+                        let back = neighbor.con((dir + 2) & 0x3);
+                        check!(
+                            stage,
+                            back.map(|back| cell.index() as usize + back as usize) == Some(i),
+                            "span {i} at ({x}, {z}) connects in direction {dir}, but the neighbor does not connect back"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks [`Self::assert_integrity`] and that the distance field covers every span.
+    pub(crate) fn assert_distance_field_integrity(&self, stage: BuildStage) {
+        self.assert_integrity(stage);
+        check!(
+            stage,
+            self.dist.len() == self.spans.len(),
+            "{} spans have {} distances",
+            self.spans.len(),
+            self.dist.len()
+        );
+        check!(
+            stage,
+            self.dist.iter().all(|dist| *dist <= self.max_distance),
+            "a distance exceeds the maximum distance {}",
+            self.max_distance
+        );
+    }
+
+    /// Checks [`Self::assert_integrity`] and that every region id is at most [`Self::max_region`].
+    ///
+    /// The distance field is not checked, as only the watershed partitioning needs it.
+    pub(crate) fn assert_region_integrity(&self, stage: BuildStage) {
+        self.assert_integrity(stage);
+        for (i, span) in self.spans.iter().enumerate() {
+            let region = span.region & !RegionId::BORDER_REGION;
+            check!(
+                stage,
+                region <= self.max_region,
+                "span {i} is in region {}, but the highest region is {}",
+                region.bits(),
+                self.max_region.bits()
+            );
+        }
+    }
+}
+
+impl ContourSet {
+    /// Checks that every contour encloses an area.
+    ///
+    /// If `raw_vertices` is set, also checks that the raw outline of every contour is a closed ring of cell corners.
+    pub(crate) fn assert_integrity(&self, stage: BuildStage, raw_vertices: bool) {
+        for (index, contour) in self.contours.iter().enumerate() {
+            if contour.vertices.is_empty() {
+                // A hole that was merged into the outline of its region.
+                continue;
+            }
+            check!(
+                stage,
+                contour.vertices.len() >= 3,
+                "contour {index} of region {} has only {} vertices",
+                contour.region.bits(),
+                contour.vertices.len()
+            );
+            check!(
+                stage,
+                contour.region != RegionId::NONE
+                    && !contour.region.contains(RegionId::BORDER_REGION),
+                "contour {index} belongs to the border or no region"
+            );
+            if !raw_vertices {
+                continue;
+            }
+            let raw = &contour.raw_vertices;
+            check!(
+                stage,
+                !raw.is_empty(),
+                "contour {index} has no raw vertices"
+            );
+            for (i, (vertex, _)) in raw.iter().enumerate() {
+                let (next, _) = raw[(i + 1) % raw.len()];
+                let step = vertex.as_ivec3() - next.as_ivec3();
+                check!(
+                    stage,
+                    step.x.abs() <= 1 && step.z.abs() <= 1,
+                    "raw contour {index} is not closed: vertex {i} at {vertex} is not next to the following vertex at {next}"
+                );
+            }
+        }
+    }
+}
+
+impl PolygonNavmesh {
+    /// Checks that every polygon has at least three valid vertices and that neighboring polygons link to each other.
+    pub(crate) fn assert_integrity(&self, stage: BuildStage) {
+        let count = self.polygon_count();
+        let nvp = self.max_vertices_per_polygon as usize;
+        check!(
+            stage,
+            self.polygon_neighbors.len() == count * nvp
+                && self.regions.len() == count
+                && self.areas.len() == count
+                && self.flags.len() == count
+                && (self.costs.is_empty() || self.costs.len() == count),
+            "the per-polygon data does not match the {count} polygons"
+        );
+        for polygon in self.polygon_ids() {
+            let vertices = self.polygon_vertices(polygon).collect::<Vec<_>>();
+            check!(
+                stage,
+                vertices.len() >= 3,
+                "polygon {} has only {} vertices",
+                polygon.index(),
+                vertices.len()
+            );
+            check!(
+                stage,
+                vertices
+                    .iter()
+                    .all(|vertex| vertex.index() < self.vertices.len()),
+                "polygon {} references a vertex that does not exist",
+                polygon.index()
+            );
+            for edge in self.edges(polygon) {
+                let EdgeConnection::Polygon(neighbor) = self.edge_connection(edge) else {
+                    continue;
+                };
+                check!(
+                    stage,
+                    neighbor.index() < count,
+                    "polygon {} neighbors polygon {}, which does not exist",
+                    polygon.index(),
+                    neighbor.index()
+                );
+                check!(
+                    stage,
+                    self.neighbors(neighbor)
+                        .any(|back| back == EdgeConnection::Polygon(polygon)),
+                    "polygon {} neighbors polygon {}, but not the other way around",
+                    polygon.index(),
+                    neighbor.index()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3A;
+
+    use super::*;
+    use crate::{
+        Aabb3d, AreaMergePolicy, AreaType, HeightfieldBuilder, heightfield::SpanInsertion,
+        span::SpanBuilder,
+    };
+
+    #[test]
+    #[should_panic(
+        expected = "Navmesh integrity check after the filtering stage failed: spans of column (1, 3) are unsorted or overlapping"
+    )]
+    fn overlapping_spans_name_the_stage() {
+        let mut heightfield = HeightfieldBuilder {
+            aabb: Aabb3d::new(Vec3A::ZERO, [5.0, 5.0, 5.0]),
+            cell_size: 1.0,
+            cell_height: 1.0,
+        }
+        .build()
+        .unwrap();
+        for (min, max) in [(2, 4), (7, 10)] {
+            heightfield
+                .add_span(SpanInsertion {
+                    x: 1,
+                    z: 3,
+                    flag_merge_threshold: 0,
+                    merge_policy: AreaMergePolicy::MaxWins,
+                    span: SpanBuilder {
+                        min,
+                        max,
+                        area: AreaType(2),
+                        next: None,
+                    }
+                    .build(),
+                })
+                .unwrap();
+        }
+        heightfield.assert_integrity(BuildStage::Filtering, true);
+
+        let upper = heightfield.span_at(1, 3).unwrap().next.unwrap();
+        heightfield.span_mut(upper).min = 3;
+        heightfield.assert_integrity(BuildStage::Filtering, true);
+    }
+}
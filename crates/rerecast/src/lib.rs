@@ -1,34 +1,116 @@
 #![doc = include_str!("../../../readme.md")]
 
+mod air_volume;
 mod compact_cell;
 mod compact_heightfield;
 mod compact_span;
 mod config;
+#[cfg(feature = "serialize")]
+mod config_migration;
 mod contours;
+mod coordinates;
+mod cost_volumes;
+mod cover;
 mod detail_mesh;
 mod erosion;
+pub mod geom;
 mod heightfield;
+mod heightfield_layers;
+mod heightmap;
+mod ids;
+#[cfg(debug_assertions)]
+mod integrity;
 mod mark_convex_poly_area;
 pub(crate) mod math;
+mod monotone_regions;
+mod off_mesh;
+mod outlines;
+mod picking;
+mod pipeline;
+mod placement;
+mod poly_geometry;
+mod poly_graph;
 mod poly_mesh;
 mod pre_filter;
 mod rasterize;
+mod rasterize_points;
+mod rasterize_shapes;
 mod region;
+#[cfg(feature = "recording")]
+mod replay;
+mod scatter;
+mod scratch;
+mod slope_cost;
 mod span;
+mod stepped_build;
+mod stress_scene;
+mod teleport;
+mod tile_cache;
+#[cfg(feature = "streaming")]
+mod tile_files;
+mod tiled;
 mod trimesh;
+mod vertical_analysis;
+mod walkability;
 mod watershed_build_regions;
 mod watershed_distance_field;
 
+pub use air_volume::{AirVolume, AirVolumeError};
 pub use compact_cell::CompactCell;
-pub use compact_heightfield::CompactHeightfield;
+pub use compact_heightfield::{CompactHeightfield, CompactHeightfieldError};
 pub use compact_span::CompactSpan;
+#[cfg(feature = "bevy_reflect")]
+pub use config::{
+    ConfigDisabledBelow, ConfigField, ConfigRange, ConfigRounding, ConfigStep, ConfigTooltip,
+    ConfigUnit,
+};
 pub use config::{NavmeshConfig, NavmeshConfigBuilder};
+#[cfg(feature = "serialize")]
+pub use config_migration::{
+    NavmeshConfigBuilderV1, NavmeshConfigBuilderV2, NavmeshConfigBuilderV3, NavmeshConfigBuilderV4,
+    NavmeshConfigBuilderV5, NavmeshConfigV1, NavmeshConfigV2, NavmeshConfigV3, NavmeshConfigV4,
+    NavmeshConfigV5, VersionedNavmeshConfig, VersionedNavmeshConfigBuilder,
+};
 pub use contours::{BuildContoursFlags, Contour, ContourSet, RegionVertexId};
-pub use detail_mesh::{DetailNavmesh, SubMesh};
+pub use coordinates::{CoordinateSystem, Handedness, UpAxis};
+pub use cost_volumes::{CostVolume, CostVolumeId, CostVolumes};
+pub use cover::{CoverAnnotations, CoverConfig, CoverKind, CoverPoint};
+pub use detail_mesh::{DetailNavmesh, DetailNavmeshError, DetailSampling, SubMesh};
+pub use erosion::ErosionMethod;
 pub use heightfield::{Heightfield, HeightfieldBuilder, HeightfieldBuilderError};
+pub use heightfield_layers::{HeightfieldLayer, HeightfieldLayerSet};
+pub use heightmap::{Heightmap, HeightmapError};
+pub use ids::{EdgeConnection, EdgeId, PolyId, VertexId};
 pub use mark_convex_poly_area::ConvexVolume;
 pub use math::{Aabb2d, Aabb3d};
-pub use poly_mesh::PolygonNavmesh;
+pub use monotone_regions::RegionPartitioning;
+pub use off_mesh::{BakedOffMeshConnection, OffMeshConnection};
+pub use outlines::AreaOutline;
+pub use picking::{NavmeshPick, PickingNavmesh};
+pub use pipeline::{
+    BuildContext, BuildNavmeshError, BuildStage, CachedBuild, build_navmesh,
+    build_navmesh_from_points, build_navmesh_from_spans_with_scratch, build_navmesh_with_progress,
+    build_navmesh_with_rasterizer, build_premarked_navmesh_with_progress,
+    build_premarked_navmesh_with_scratch,
+};
+pub use poly_graph::{PolygonGraph, PolygonGraphEdge};
+pub use poly_mesh::{PolygonNavmesh, PolygonNavmeshError};
+pub use pre_filter::LedgeFilter;
+pub use rasterize::{RasterizationError, RecordedSpans};
 pub use region::RegionId;
-pub use span::{AreaType, Span, SpanKey, Spans};
-pub use trimesh::TriMesh;
+#[cfg(feature = "recording")]
+pub use replay::{InputRecording, ReplayError, replay};
+pub use scatter::{ScatterConfig, ScatterPoint};
+pub use scratch::{BuildScratch, BuildScratchStats};
+pub use slope_cost::RegionSlope;
+pub use span::{AreaMergePolicy, AreaType, Span, SpanKey, Spans};
+pub use stepped_build::SteppedBuild;
+pub use stress_scene::StressScene;
+pub use teleport::{TeleportAgent, TeleportDestination, TeleportError};
+pub use tile_cache::{Obstacle, ObstacleId, TileCache, TileCacheObstacles};
+#[cfg(feature = "streaming")]
+pub use tile_files::{TILE_FILE_VERSION, TileFileError, TileManifest};
+pub use tiled::{NavmeshTile, TileLink, TilePolygon, TiledNavmesh, TiledNavmeshError};
+pub use trimesh::{TriMesh, TriMeshError};
+pub use vertical_analysis::{VerticalAnalysis, VerticalAnalysisError};
+pub use watershed_build_regions::BuildRegionsError;
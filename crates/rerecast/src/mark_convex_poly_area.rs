@@ -1,6 +1,6 @@
-use glam::{IVec3, Vec2};
+use glam::{IVec3, Vec2, Vec3, Vec3Swizzles as _};
 
-use crate::{Aabb2d, AreaType, CompactHeightfield};
+use crate::{Aabb2d, Aabb3d, AreaType, CompactHeightfield, PolyId, PolygonNavmesh};
 
 impl CompactHeightfield {
     /// Sets the [`AreaType`] of the spans within the given convex volume.
@@ -66,6 +66,124 @@ impl CompactHeightfield {
             }
         }
     }
+
+    /// Sets the [`AreaType`] of the walkable spans whose floor lies within the given box.
+    pub fn mark_box_area(&mut self, aabb: Aabb3d, area: AreaType) {
+        self.mark_area_in_bounds(aabb, area, |_| true);
+    }
+
+    /// Sets the [`AreaType`] of the walkable spans whose floor lies within the given vertical cylinder.
+    /// `base` is the center of the bottom of the cylinder.
+    pub fn mark_cylinder_area(&mut self, base: Vec3, radius: f32, height: f32, area: AreaType) {
+        let aabb = Aabb3d {
+            min: base - Vec3::new(radius, 0.0, radius),
+            max: base + Vec3::new(radius, height, radius),
+        };
+        let radius_squared = radius * radius;
+        self.mark_area_in_bounds(aabb, area, |point| {
+            point.distance_squared(base.xz()) < radius_squared
+        });
+    }
+
+    /// Sets the [`AreaType`] of the walkable spans whose floor lies within `aabb` and whose cell center passes `contains`.
+    fn mark_area_in_bounds(
+        &mut self,
+        aabb: Aabb3d,
+        area: AreaType,
+        contains: impl Fn(Vec2) -> bool,
+    ) {
+        let min = ((aabb.min - self.aabb.min)
+            / Vec3::new(self.cell_size, self.cell_height, self.cell_size))
+        .floor()
+        .as_ivec3();
+        let max = ((aabb.max - self.aabb.min)
+            / Vec3::new(self.cell_size, self.cell_height, self.cell_size))
+        .floor()
+        .as_ivec3();
+        if max.x < 0 || min.x >= self.width as i32 || max.z < 0 || min.z >= self.height as i32 {
+            return;
+        }
+        for z in min.z.max(0)..=max.z.min(self.height as i32 - 1) {
+            for x in min.x.max(0)..=max.x.min(self.width as i32 - 1) {
+                let point = Vec2::new(
+                    self.aabb.min.x + (x as f32 + 0.5) * self.cell_size,
+                    self.aabb.min.z + (z as f32 + 0.5) * self.cell_size,
+                );
+                if !contains(point) {
+                    continue;
+                }
+                let cell = &self.cells[(x + z * self.width as i32) as usize];
+                for i in cell.index() as usize..cell.index() as usize + cell.count() as usize {
+                    let y = self.spans[i].y as i32;
+                    if self.areas[i].is_walkable() && y >= min.y && y <= max.y {
+                        self.areas[i] = area;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl PolygonNavmesh {
+    /// Sets the [`AreaType`] of the polygons within the given convex volume, without running the rest of the pipeline again.
+    /// This is useful when only the semantics of an area change, e.g. when a lake freezes and becomes walkable.
+    ///
+    /// A polygon is considered to be within the volume if its center lies within the volume on the xz-plane
+    /// and its vertices overlap the y extents of the volume. Polygons are never split, so the volume should follow
+    /// the borders of the polygons to remark, which is the case when it was also used for
+    /// [`CompactHeightfield::mark_convex_poly_area`] during the original build.
+    ///
+    /// Only the shape of the volume is used, [`ConvexVolume::area`] is ignored. This way, the same volume can be
+    /// used to switch the area back and forth. The flags of the polygons are left untouched,
+    /// use [`PolygonNavmesh::remark_area_in_volume_with_flags`] to update them as well.
+    ///
+    /// Returns the polygons that were remarked.
+    pub fn remark_area_in_volume(&mut self, volume: &ConvexVolume, area: AreaType) -> Vec<PolyId> {
+        let polygons = self.polygons_in_volume(volume);
+        for polygon in &polygons {
+            self.areas[polygon.index()] = area;
+        }
+        polygons
+    }
+
+    /// Like [`PolygonNavmesh::remark_area_in_volume`], but also sets the flags of the remarked polygons.
+    pub fn remark_area_in_volume_with_flags(
+        &mut self,
+        volume: &ConvexVolume,
+        area: AreaType,
+        flags: u16,
+    ) -> Vec<PolyId> {
+        let polygons = self.remark_area_in_volume(volume, area);
+        for polygon in &polygons {
+            self.flags[polygon.index()] = flags;
+        }
+        polygons
+    }
+
+    fn polygons_in_volume(&self, volume: &ConvexVolume) -> Vec<PolyId> {
+        if volume.vertices.len() < 3 {
+            // The volume is empty
+            return Vec::new();
+        }
+        self.polygon_ids()
+            .filter(|polygon| {
+                let vertices = self
+                    .polygon_vertices(*polygon)
+                    .map(|vertex| self.vertex_world(vertex))
+                    .collect::<Vec<_>>();
+                if vertices.is_empty() {
+                    return false;
+                }
+                let center = self.center(*polygon);
+                let (min_y, max_y) = vertices.iter().fold((f32::MAX, f32::MIN), |(min, max), v| {
+                    (min.min(v.y), max.max(v.y))
+                });
+                min_y <= volume.max_y
+                    && max_y >= volume.min_y
+                    && point_in_poly(&center.xz(), &volume.vertices)
+            })
+            .collect()
+    }
 }
 
 fn point_in_poly(point: &Vec2, vertices: &[Vec2]) -> bool {
@@ -87,6 +205,12 @@ fn point_in_poly(point: &Vec2, vertices: &[Vec2]) -> bool {
 }
 
 /// A convex volume that marks an area within a [`CompactHeightfield`] as belonging to a specific [`AreaType`] through [`CompactHeightfield::mark_convex_poly_area`].
+/// Can also be used to remark the polygons of an already built navmesh through [`PolygonNavmesh::remark_area_in_volume`].
+///
+/// Remarking is deterministic, so the volumes applied to a navmesh at runtime can be saved along with the game
+/// and applied again in the same order after loading to restore the same areas.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConvexVolume {
     /// The vertices of the convex volume. In 3D, these represent the X and Z coordinates of the vertices.
     pub vertices: Vec<Vec2>,
@@ -97,3 +221,62 @@ pub struct ConvexVolume {
     /// The area type of the convex volume.
     pub area: AreaType,
 }
+
+#[cfg(test)]
+mod tests {
+    use glam::U16Vec3;
+
+    use super::*;
+    use crate::{Aabb3d, RegionId};
+
+    #[test]
+    fn remarks_polygons_within_volume() {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        // Two triangles in a row, the second one being a frozen lake.
+        let mut mesh = PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 0, 0),
+                U16Vec3::new(0, 0, 2),
+                U16Vec3::new(2, 0, 0),
+                U16Vec3::new(2, 0, 2),
+            ],
+            polygons: vec![0, 1, 2, 1, 3, 2],
+            polygon_neighbors: vec![N, 1, N, N, N, 0],
+            flags: vec![1, 2],
+            regions: vec![RegionId::NONE; 2],
+            areas: vec![AreaType::DEFAULT_WALKABLE, AreaType::NOT_WALKABLE],
+            max_vertices_per_polygon: 3,
+            aabb: Aabb3d {
+                min: Vec3::new(10.0, 5.0, 10.0),
+                max: Vec3::new(12.0, 6.0, 12.0),
+            },
+            cell_size: 1.0,
+            cell_height: 0.5,
+            ..Default::default()
+        };
+        let lake = ConvexVolume {
+            vertices: vec![
+                Vec2::new(11.0, 11.0),
+                Vec2::new(13.0, 11.0),
+                Vec2::new(13.0, 13.0),
+                Vec2::new(11.0, 13.0),
+            ],
+            min_y: 4.0,
+            max_y: 6.0,
+            area: AreaType::NOT_WALKABLE,
+        };
+
+        let ice = AreaType(1);
+        let remarked = mesh.remark_area_in_volume_with_flags(&lake, ice, 3);
+        assert_eq!(remarked, vec![PolyId(1)]);
+        assert_eq!(mesh.areas, vec![AreaType::DEFAULT_WALKABLE, ice]);
+        assert_eq!(mesh.flags, vec![1, 3]);
+
+        let above = ConvexVolume {
+            min_y: 7.0,
+            max_y: 8.0,
+            ..lake
+        };
+        assert!(mesh.remark_area_in_volume(&above, ice).is_empty());
+    }
+}
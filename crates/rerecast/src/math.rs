@@ -2,6 +2,10 @@
 use bevy_reflect::prelude::*;
 use glam::{U16Vec2, UVec3, Vec2, Vec3, Vec3A};
 
+pub(crate) use crate::geom::{
+    dir_offset, dir_offset_x, dir_offset_z, distance_squared_to_segment, next, prev,
+};
+
 /// A 3D axis-aligned bounding box
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
@@ -51,7 +55,7 @@ impl Aabb3d {
 
     /// Checks if this AABB intersects with another AABB.
     #[inline]
-    pub(crate) fn intersects(&self, other: &Aabb3d) -> bool {
+    pub fn intersects(&self, other: &Aabb3d) -> bool {
         self.min.cmple(other.max).all() && self.max.cmpge(other.min).all()
     }
 }
@@ -132,46 +136,6 @@ impl TriangleVertices for [Vec3; 3] {
     }
 }
 
-/// Gets the standard width (x-axis) offset for the specified direction.
-/// # Arguments
-/// - `direction`: The direction. [Limits: 0 <= value < 4]
-/// # Returns
-///
-/// The width offset to apply to the current cell position to move in the direction.
-#[inline]
-pub(crate) fn dir_offset_x(direction: u8) -> i8 {
-    const OFFSET: [i8; 4] = [-1, 0, 1, 0];
-    OFFSET[direction as usize & 0x03]
-}
-
-/// Gets the standard height (z-axis) offset for the specified direction.
-/// # Arguments
-/// - `direction`: The direction. [Limits: 0 <= value < 4]
-/// # Returns
-///
-/// The height offset to apply to the current cell position to move in the direction.
-#[inline]
-pub(crate) fn dir_offset_z(direction: u8) -> i8 {
-    const OFFSET: [i8; 4] = [0, 1, 0, -1];
-    OFFSET[direction as usize & 0x03]
-}
-
-#[inline]
-pub(crate) fn dir_offset(offset_x: i32, offset_z: i32) -> i8 {
-    const DIRS: [i8; 5] = [3, 0, -1, 2, 1];
-    DIRS[(((offset_z + 1) << 1) + offset_x) as usize]
-}
-
-#[inline]
-pub(crate) fn prev(i: usize, n: usize) -> usize {
-    (i + n - 1) % n
-}
-
-#[inline]
-pub(crate) fn next(i: usize, n: usize) -> usize {
-    (i + 1) % n
-}
-
 pub(crate) fn distance_squared_between_point_and_line_u16vec2(
     point: U16Vec2,
     (p, q): (U16Vec2, U16Vec2),
@@ -215,24 +179,3 @@ pub(crate) fn distance_squared_between_point_and_line_vec3(
     let dt = p + t * pq - pt;
     dt.length_squared()
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_prev() {
-        assert_eq!(prev(0, 4), 3);
-        assert_eq!(prev(1, 4), 0);
-        assert_eq!(prev(2, 4), 1);
-        assert_eq!(prev(3, 4), 2);
-    }
-
-    #[test]
-    fn test_next() {
-        assert_eq!(next(0, 4), 1);
-        assert_eq!(next(1, 4), 2);
-        assert_eq!(next(2, 4), 3);
-        assert_eq!(next(3, 4), 0);
-    }
-}
@@ -0,0 +1,611 @@
+//! Region partitioning by sweeping the heightfield row by row, as an alternative to the watershed partitioning.
+//!
+//! Both methods are ports of `rcBuildRegionsMonotone` and `rcBuildLayerRegions` of the original Recast.
+//! They don't need a distance field, which makes them a lot faster than the watershed partitioning on large fields.
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+
+use crate::{
+    BuildRegionsError, BuildScratch, CompactHeightfield, RegionId, watershed_build_regions::Region,
+};
+
+/// The algorithm used by [`CompactHeightfield::build_regions_with`] to partition the walkable area into regions.
+///
+/// All methods produce regions that satisfy the same contract, so the contours and polygons can be built from any of them.
+/// See the [Recast documentation](https://github.com/recastnavigation/recastnavigation/blob/main/Docs/_99_Extras/Region_Partitioning.md)
+/// for pictures of how the partitions differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum RegionPartitioning {
+    /// Partitions the walkable area along the ridges of the distance field. See [`CompactHeightfield::build_regions`].
+    ///
+    /// Creates the nicest tessellation, but is the slowest method and needs the distance field.
+    #[default]
+    Watershed,
+    /// Sweeps the field row by row and joins the spans of each row with the regions of the previous row.
+    /// See [`CompactHeightfield::build_regions_monotone`].
+    ///
+    /// The fastest method, and it never creates holes or overlaps. However, it tends to create long, thin polygons,
+    /// which can lead to detours when pathfinding.
+    Monotone,
+    /// Merges the regions of the monotone sweep into non-overlapping layers. See [`CompactHeightfield::build_layer_regions`].
+    ///
+    /// About as fast as [`Self::Monotone`], but with fewer and larger regions. Works best with tiled navmeshes with small tiles,
+    /// as regions are never merged by area.
+    Layers,
+}
+
+/// A run of spans in the current row, as found by [`CompactHeightfield::sweep_monotone_regions`].
+#[derive(Debug, Clone, Copy, Default)]
+struct SweepSpan {
+    /// The id of the region in the current row.
+    row_id: u16,
+    /// The final id of the region.
+    id: RegionId,
+    /// The number of spans of the region that connect to the neighbor region in the previous row.
+    samples: usize,
+    /// The region in the previous row that all connected spans of the region connect to,
+    /// [`SweepSpan::NO_NEIGHBOR`] if they connect to different regions, or [`RegionId::NONE`] if they connect to none.
+    neighbor: RegionId,
+}
+
+impl SweepSpan {
+    const NO_NEIGHBOR: RegionId = RegionId::MAX;
+}
+
+impl CompactHeightfield {
+    /// Builds the regions with the given partitioning. See the methods linked by the variants of [`RegionPartitioning`].
+    ///
+    /// `merge_region_area` is ignored by [`RegionPartitioning::Layers`].
+    ///
+    /// Warning: The distance field must be created using [`CompactHeightfield::build_distance_field`] before
+    /// attempting to build regions with [`RegionPartitioning::Watershed`].
+    pub fn build_regions_with(
+        &mut self,
+        partitioning: RegionPartitioning,
+        border_size: u16,
+        min_region_area: u16,
+        merge_region_area: u16,
+    ) -> Result<(), BuildRegionsError> {
+        self.build_regions_with_scratch(
+            partitioning,
+            border_size,
+            min_region_area,
+            merge_region_area,
+            &mut BuildScratch::default(),
+        )
+    }
+
+    /// Same as [`CompactHeightfield::build_regions_with`], but takes the temporary buffers from `scratch`.
+    pub(crate) fn build_regions_with_scratch(
+        &mut self,
+        partitioning: RegionPartitioning,
+        border_size: u16,
+        min_region_area: u16,
+        merge_region_area: u16,
+        scratch: &mut BuildScratch,
+    ) -> Result<(), BuildRegionsError> {
+        match partitioning {
+            RegionPartitioning::Watershed => self.build_watershed_regions_with_scratch(
+                border_size,
+                min_region_area,
+                merge_region_area,
+                scratch,
+            ),
+            RegionPartitioning::Monotone => self.build_monotone_regions_with_scratch(
+                border_size,
+                min_region_area,
+                merge_region_area,
+                scratch,
+            ),
+            RegionPartitioning::Layers => {
+                self.build_layer_regions_with_scratch(border_size, min_region_area, scratch)
+            }
+        }
+    }
+
+    /// Builds the regions with the same contract as [`CompactHeightfield::build_regions`], but partitions the walkable area
+    /// by sweeping the field row by row instead of along the distance field. See [`RegionPartitioning::Monotone`].
+    ///
+    /// The distance field is not needed.
+    pub fn build_regions_monotone(
+        &mut self,
+        border_size: u16,
+        min_region_area: u16,
+        merge_region_area: u16,
+    ) -> Result<(), BuildRegionsError> {
+        self.build_monotone_regions_with_scratch(
+            border_size,
+            min_region_area,
+            merge_region_area,
+            &mut BuildScratch::default(),
+        )
+    }
+
+    fn build_monotone_regions_with_scratch(
+        &mut self,
+        border_size: u16,
+        min_region_area: u16,
+        merge_region_area: u16,
+        scratch: &mut BuildScratch,
+    ) -> Result<(), BuildRegionsError> {
+        let mut src_reg = scratch.regions.take(self.spans.len(), RegionId::NONE);
+        let result = self.sweep_monotone_regions(border_size, &mut src_reg);
+        if let Ok(region_id) = result {
+            // Merge regions and filter out small regions.
+            self.max_region = region_id;
+            // Monotone partitioning does not generate overlapping regions.
+            self.merge_and_filter_regions(min_region_area, merge_region_area, &mut src_reg);
+            for (span, region) in self.spans.iter_mut().zip(&src_reg) {
+                span.region = *region;
+            }
+        }
+        scratch.regions.give(src_reg);
+        result.map(|_| ())
+    }
+
+    /// Builds the regions with the same contract as [`CompactHeightfield::build_regions`], but merges the regions of the
+    /// monotone sweep into non-overlapping layers instead of merging small regions. See [`RegionPartitioning::Layers`].
+    ///
+    /// The distance field is not needed.
+    pub fn build_layer_regions(
+        &mut self,
+        border_size: u16,
+        min_region_area: u16,
+    ) -> Result<(), BuildRegionsError> {
+        self.build_layer_regions_with_scratch(
+            border_size,
+            min_region_area,
+            &mut BuildScratch::default(),
+        )
+    }
+
+    fn build_layer_regions_with_scratch(
+        &mut self,
+        border_size: u16,
+        min_region_area: u16,
+        scratch: &mut BuildScratch,
+    ) -> Result<(), BuildRegionsError> {
+        let mut src_reg = scratch.regions.take(self.spans.len(), RegionId::NONE);
+        let result = self.sweep_monotone_regions(border_size, &mut src_reg);
+        if let Ok(region_id) = result {
+            // Merge monotone regions to layers and remove small regions.
+            self.max_region = region_id;
+            self.merge_and_filter_layer_regions(min_region_area, &mut src_reg);
+            for (span, region) in self.spans.iter_mut().zip(&src_reg) {
+                span.region = *region;
+            }
+        }
+        scratch.regions.give(src_reg);
+        result.map(|_| ())
+    }
+
+    /// Paints the border regions and sweeps the rest of the field row by row, assigning every span to the region
+    /// of its neighbor in the previous row if the whole run of spans it belongs to connects only to that region.
+    ///
+    /// Returns the next free region id.
+    fn sweep_monotone_regions(
+        &mut self,
+        border_size: u16,
+        src_reg: &mut [RegionId],
+    ) -> Result<RegionId, BuildRegionsError> {
+        let w = self.width;
+        let h = self.height;
+        let mut id = RegionId::from(1);
+
+        // Mark border regions.
+        if border_size > 0 {
+            let bw = border_size.min(w);
+            let bh = border_size.min(h);
+            self.paint_rect_region(0, bw, 0, h, id | RegionId::BORDER_REGION, src_reg);
+            id += 1;
+            self.paint_rect_region(w - bw, w, 0, h, id | RegionId::BORDER_REGION, src_reg);
+            id += 1;
+            self.paint_rect_region(0, w, 0, bh, id | RegionId::BORDER_REGION, src_reg);
+            id += 1;
+            self.paint_rect_region(0, w, h - bh, h, id | RegionId::BORDER_REGION, src_reg);
+            id += 1;
+        }
+        self.border_size = border_size;
+
+        // The original allocates `max(width, height)` sweep spans up front, which overflows
+        // when a row has more runs than that, e.g. on fields with many layers. We grow the buffer instead.
+        let mut sweeps = vec![SweepSpan::default()];
+        let mut prev = Vec::new();
+
+        // Sweep one line at a time.
+        for z in border_size..h.saturating_sub(border_size) {
+            // Collect spans from this row.
+            prev.clear();
+            prev.resize(id.bits() as usize + 1, 0_usize);
+            sweeps.truncate(1);
+
+            for x in border_size..w.saturating_sub(border_size) {
+                let cell = self.cell_at(x, z);
+                for i in cell.index_range() {
+                    let span = &self.spans[i];
+                    if !self.areas[i].is_walkable() {
+                        continue;
+                    }
+
+                    // -x
+                    let mut prev_id = 0;
+                    if let Some(con) = span.con(0) {
+                        let (_, _, a_i) = self.con_indices(x as i32, z as i32, 0, con);
+                        if !src_reg[a_i].intersects(RegionId::BORDER_REGION)
+                            && self.areas[i] == self.areas[a_i]
+                        {
+                            prev_id = src_reg[a_i].bits();
+                        }
+                    }
+
+                    if prev_id == 0 {
+                        prev_id = sweeps.len() as u16;
+                        sweeps.push(SweepSpan {
+                            row_id: prev_id,
+                            ..Default::default()
+                        });
+                    }
+
+                    // -z
+                    if let Some(con) = span.con(3) {
+                        let (_, _, a_i) = self.con_indices(x as i32, z as i32, 3, con);
+                        let neighbor = src_reg[a_i];
+                        if neighbor != RegionId::NONE
+                            && !neighbor.intersects(RegionId::BORDER_REGION)
+                            && self.areas[i] == self.areas[a_i]
+                        {
+                            let sweep = &mut sweeps[prev_id as usize];
+                            if sweep.neighbor == RegionId::NONE || sweep.neighbor == neighbor {
+                                sweep.neighbor = neighbor;
+                                sweep.samples += 1;
+                                prev[neighbor.bits() as usize] += 1;
+                            } else {
+                                sweep.neighbor = SweepSpan::NO_NEIGHBOR;
+                            }
+                        }
+                    }
+
+                    src_reg[i] = RegionId::from(prev_id);
+                }
+            }
+
+            // Create unique ID.
+            for sweep in &mut sweeps[1..] {
+                if sweep.neighbor != SweepSpan::NO_NEIGHBOR
+                    && sweep.neighbor != RegionId::NONE
+                    && prev[sweep.neighbor.bits() as usize] == sweep.samples
+                {
+                    sweep.id = sweep.neighbor;
+                } else {
+                    if id == RegionId::BORDER_REGION {
+                        return Err(BuildRegionsError::RegionIdOverflow);
+                    }
+                    sweep.id = id;
+                    id += 1;
+                }
+            }
+
+            // Remap IDs
+            for x in border_size..w.saturating_sub(border_size) {
+                let cell = self.cell_at(x, z);
+                for i in cell.index_range() {
+                    let row_id = src_reg[i].bits() as usize;
+                    if row_id > 0 && row_id < sweeps.len() {
+                        src_reg[i] = sweeps[row_id].id;
+                    }
+                }
+            }
+        }
+        debug_assert!(
+            sweeps
+                .iter()
+                .enumerate()
+                .all(|(i, sweep)| sweep.row_id as usize == i)
+        );
+        Ok(id)
+    }
+
+    /// Merges the monotone regions into layers of connected regions that don't overlap each other,
+    /// then removes layers with less than `min_region_area` spans and compacts the region ids.
+    fn merge_and_filter_layer_regions(&mut self, min_region_area: u16, src_reg: &mut [RegionId]) {
+        let w = self.width;
+        let h = self.height;
+        let nreg = self.max_region.bits() as usize + 1;
+
+        // Construct regions
+        let mut regions = (0..nreg)
+            .map(|i| Region::new(RegionId::from(i as u16)))
+            .collect::<Vec<_>>();
+
+        // Find region neighbours and overlapping regions.
+        let mut layers = Vec::with_capacity(32);
+        for z in 0..h {
+            for x in 0..w {
+                let cell = self.cell_at(x, z);
+                layers.clear();
+
+                for i in cell.index_range() {
+                    let span = &self.spans[i];
+                    let ri = src_reg[i];
+                    if ri == RegionId::NONE || ri.bits() as usize >= nreg {
+                        continue;
+                    }
+                    let reg = &mut regions[ri.bits() as usize];
+                    reg.span_count += 1;
+                    reg.area = self.areas[i];
+                    reg.y_min = reg.y_min.min(span.y);
+                    reg.y_max = reg.y_max.max(span.y);
+
+                    // Collect all region layers.
+                    layers.push(ri);
+
+                    // Update neighbours
+                    for dir in 0..4 {
+                        let Some(con) = span.con(dir) else {
+                            continue;
+                        };
+                        let (_, _, a_i) = self.con_indices(x as i32, z as i32, dir, con);
+                        let rai = src_reg[a_i];
+                        if rai != RegionId::NONE
+                            && (rai.bits() as usize) < nreg
+                            && rai != ri
+                            && !reg.connections.contains(&rai)
+                        {
+                            reg.connections.push(rai);
+                        }
+                        if rai.intersects(RegionId::BORDER_REGION) {
+                            reg.connects_to_border = true;
+                        }
+                    }
+                }
+
+                // Update overlapping regions.
+                for (i, &a) in layers.iter().enumerate() {
+                    for &b in &layers[i + 1..] {
+                        if a != b {
+                            regions[a.bits() as usize].add_unique_floor_region(b);
+                            regions[b.bits() as usize].add_unique_floor_region(a);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Create 2D layers from regions.
+        let mut layer_id = RegionId::from(1);
+        for region in &mut regions {
+            region.id = RegionId::NONE;
+        }
+
+        // Merge montone regions to create non-overlapping areas.
+        let mut stack = VecDeque::with_capacity(32);
+        for i in 1..nreg {
+            // Skip already visited.
+            if regions[i].id != RegionId::NONE {
+                continue;
+            }
+
+            // Start search.
+            regions[i].id = layer_id;
+            stack.clear();
+            stack.push_back(i);
+
+            while let Some(current) = stack.pop_front() {
+                for j in 0..regions[current].connections.len() {
+                    let neighbor = regions[current].connections[j].bits() as usize;
+                    // Skip already visited.
+                    if regions[neighbor].id != RegionId::NONE {
+                        continue;
+                    }
+                    // Skip if different area type, do not connect regions with different area type.
+                    if regions[current].area != regions[neighbor].area {
+                        continue;
+                    }
+                    // Skip if the neighbour is overlapping root region.
+                    if regions[i].floors.contains(&RegionId::from(neighbor as u16)) {
+                        continue;
+                    }
+
+                    // Deepen
+                    stack.push_back(neighbor);
+
+                    // Mark layer id
+                    regions[neighbor].id = layer_id;
+                    // Merge current layers to root.
+                    let merged = std::mem::take(&mut regions[neighbor].floors);
+                    let (y_min, y_max, span_count, connects_to_border) = {
+                        let neighbor = &regions[neighbor];
+                        (
+                            neighbor.y_min,
+                            neighbor.y_max,
+                            neighbor.span_count,
+                            neighbor.connects_to_border,
+                        )
+                    };
+                    let root = &mut regions[i];
+                    for floor in &merged {
+                        root.add_unique_floor_region(*floor);
+                    }
+                    root.y_min = root.y_min.min(y_min);
+                    root.y_max = root.y_max.max(y_max);
+                    root.span_count += span_count;
+                    root.connects_to_border |= connects_to_border;
+                    regions[neighbor].floors = merged;
+                    regions[neighbor].span_count = 0;
+                }
+            }
+
+            layer_id += 1;
+        }
+
+        // Remove small regions
+        for i in 0..nreg {
+            let region = &regions[i];
+            if region.span_count > 0
+                && region.span_count < min_region_area as usize
+                && !region.connects_to_border
+            {
+                let removed = region.id;
+                for region in &mut regions {
+                    if region.id == removed {
+                        region.id = RegionId::NONE;
+                    }
+                }
+            }
+        }
+
+        // Compress region Ids.
+        for region in &mut regions {
+            // Skip nil regions and external regions.
+            region.remap =
+                region.id != RegionId::NONE && !region.id.intersects(RegionId::BORDER_REGION);
+        }
+        let mut reg_id_gen = 0;
+        for i in 0..nreg {
+            if !regions[i].remap {
+                continue;
+            }
+            let old_id = regions[i].id;
+            reg_id_gen += 1;
+            let new_id = RegionId::from(reg_id_gen);
+            for region in &mut regions[i..] {
+                if region.id == old_id {
+                    region.id = new_id;
+                    region.remap = false;
+                }
+            }
+        }
+        self.max_region = RegionId::from(reg_id_gen);
+
+        // Remap regions.
+        for reg in src_reg.iter_mut() {
+            if !reg.intersects(RegionId::BORDER_REGION) {
+                *reg = regions[reg.bits() as usize].id;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use glam::{UVec3, Vec3A};
+
+    use super::*;
+    use crate::{Aabb3d, NavmeshConfig, NavmeshConfigBuilder, TriMesh};
+
+    /// A floor with a platform above its center, connected by a ramp.
+    fn floor_with_platform() -> TriMesh {
+        let quad = |y: f32, min: [f32; 2], max: [f32; 2]| {
+            [
+                Vec3A::new(min[0], y, min[1]),
+                Vec3A::new(min[0], y, max[1]),
+                Vec3A::new(max[0], y, max[1]),
+                Vec3A::new(max[0], y, min[1]),
+            ]
+        };
+        let mut vertices = [
+            quad(0.0, [-10.0, -10.0], [10.0, 10.0]),
+            quad(3.0, [-3.0, -3.0], [3.0, 3.0]),
+        ]
+        .concat();
+        vertices.extend([
+            Vec3A::new(3.0, 3.0, -1.0),
+            Vec3A::new(3.0, 3.0, 1.0),
+            Vec3A::new(9.0, 0.0, 1.0),
+            Vec3A::new(9.0, 0.0, -1.0),
+        ]);
+        let indices = (0..3)
+            .flat_map(|quad| {
+                let base = quad * 4;
+                [
+                    UVec3::new(base, base + 1, base + 2),
+                    UVec3::new(base, base + 2, base + 3),
+                ]
+            })
+            .collect();
+        TriMesh::from_parts(vertices, indices).unwrap()
+    }
+
+    fn config(region_partitioning: RegionPartitioning) -> NavmeshConfig {
+        NavmeshConfig {
+            aabb: Aabb3d::new(Vec3A::new(0.0, 2.0, 0.0), [11.0, 3.0, 11.0]),
+            ..NavmeshConfigBuilder {
+                region_partitioning,
+                ..Default::default()
+            }
+            .build()
+        }
+    }
+
+    #[test]
+    fn all_partitionings_cover_the_same_area() {
+        let area = |partitioning| {
+            let (polygon, _detail) =
+                crate::build_navmesh(floor_with_platform(), &config(partitioning)).unwrap();
+            assert!(polygon.polygon_count() > 0, "{partitioning:?}");
+            polygon
+                .polygon_ids()
+                .map(|id| polygon.surface_area(id))
+                .sum::<f32>()
+        };
+        let watershed = area(RegionPartitioning::Watershed);
+        for partitioning in [RegionPartitioning::Monotone, RegionPartitioning::Layers] {
+            let other = area(partitioning);
+            assert!(
+                (other - watershed).abs() < watershed * 0.02,
+                "{partitioning:?} covers {other} wu², watershed {watershed} wu²"
+            );
+        }
+    }
+
+    #[test]
+    fn layers_merge_monotone_regions() {
+        let region_count = |partitioning| {
+            let (polygon, _detail) =
+                crate::build_navmesh(floor_with_platform(), &config(partitioning)).unwrap();
+            polygon.regions.iter().collect::<HashSet<_>>().len()
+        };
+        let monotone = region_count(RegionPartitioning::Monotone);
+        let layers = region_count(RegionPartitioning::Layers);
+        assert!(
+            layers < monotone,
+            "{layers} layers, {monotone} monotone regions"
+        );
+    }
+
+    #[test]
+    fn monotone_regions_skip_the_distance_field() {
+        let mut heightfield = crate::HeightfieldBuilder {
+            aabb: Aabb3d::new(Vec3A::ZERO, [5.0, 5.0, 5.0]),
+            cell_size: 0.5,
+            cell_height: 0.5,
+        }
+        .build()
+        .unwrap();
+        let mut trimesh = floor_with_platform();
+        trimesh.mark_walkable_triangles(45.0_f32.to_radians());
+        heightfield.rasterize_triangles(&trimesh, 1).unwrap();
+        let mut compact = heightfield.into_compact(4, 1).unwrap();
+        compact.build_regions_monotone(0, 0, 0).unwrap();
+        assert!(compact.dist.is_empty());
+        assert!(compact.max_region.bits() > 0);
+        assert!(
+            compact
+                .spans
+                .iter()
+                .zip(&compact.areas)
+                .all(|(span, area)| !area.is_walkable() || span.region != RegionId::NONE)
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! Off-mesh connections, which let agents move between polygons that don't share an edge, e.g. by jumping,
+//! climbing a ladder or using a teleporter.
+
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+use glam::{Vec3, Vec3Swizzles as _};
+
+use crate::{
+    AreaType, PolyId, PolygonNavmesh, math::distance_squared_to_segment,
+    walkability::contains_point,
+};
+
+/// A connection between two points on a navmesh that agents can traverse without walking,
+/// e.g. a jump, a ladder or a teleporter.
+///
+/// Bake connections into a [`PolygonNavmesh`] with [`PolygonNavmesh::bake_off_mesh_connections`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct OffMeshConnection {
+    /// The start of the connection in world space.
+    pub start: Vec3,
+    /// The end of the connection in world space.
+    pub end: Vec3,
+    /// How far the endpoints may be from the navmesh to still be connected to it. `[Units: wu]`
+    pub radius: f32,
+    /// Whether the connection can also be traversed from [`Self::end`] to [`Self::start`].
+    pub bidirectional: bool,
+    /// The area of the connection, e.g. to make jumps more expensive than walking.
+    pub area: AreaType,
+    /// User-defined flags, like [`PolygonNavmesh::flags`].
+    pub flags: u16,
+}
+
+impl Default for OffMeshConnection {
+    fn default() -> Self {
+        Self {
+            start: Vec3::ZERO,
+            end: Vec3::ZERO,
+            radius: 0.5,
+            bidirectional: true,
+            area: AreaType::DEFAULT_WALKABLE,
+            flags: 0,
+        }
+    }
+}
+
+/// An [`OffMeshConnection`] baked into a [`PolygonNavmesh`], i.e. with both endpoints resolved to polygons.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct BakedOffMeshConnection {
+    /// The connection as it was authored.
+    pub connection: OffMeshConnection,
+    /// The polygon the connection starts on.
+    pub start_polygon: PolyId,
+    /// The polygon the connection ends on.
+    pub end_polygon: PolyId,
+}
+
+impl PolygonNavmesh {
+    /// Bakes off-mesh connections into [`Self::off_mesh_connections`], replacing the ones baked before.
+    ///
+    /// Each endpoint is connected to the closest polygon within [`OffMeshConnection::radius`] of it, both horizontally and vertically.
+    /// Connections with an endpoint that is not within the radius of any polygon are skipped.
+    /// Returns the indices of the skipped connections in `connections`.
+    pub fn bake_off_mesh_connections(&mut self, connections: &[OffMeshConnection]) -> Vec<usize> {
+        let mut skipped = Vec::new();
+        let mut baked = Vec::with_capacity(connections.len());
+        for (index, connection) in connections.iter().enumerate() {
+            let start_polygon = self.nearest_polygon(connection.start, connection.radius);
+            let end_polygon = self.nearest_polygon(connection.end, connection.radius);
+            match (start_polygon, end_polygon) {
+                (Some(start_polygon), Some(end_polygon)) => baked.push(BakedOffMeshConnection {
+                    connection: *connection,
+                    start_polygon,
+                    end_polygon,
+                }),
+                _ => skipped.push(index),
+            }
+        }
+        self.off_mesh_connections = baked;
+        skipped
+    }
+
+    /// The off-mesh connections that can be traversed from the given polygon, along with the polygon each of them leads to.
+    pub fn off_mesh_connections_from(
+        &self,
+        polygon: PolyId,
+    ) -> impl Iterator<Item = (&BakedOffMeshConnection, PolyId)> {
+        self.off_mesh_connections.iter().filter_map(move |baked| {
+            if baked.start_polygon == polygon {
+                Some((baked, baked.end_polygon))
+            } else if baked.connection.bidirectional && baked.end_polygon == polygon {
+                Some((baked, baked.start_polygon))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// The polygon closest to `point` that is at most `radius` away from it on the xz-plane and along the y-axis.
+    fn nearest_polygon(&self, point: Vec3, radius: f32) -> Option<PolyId> {
+        self.polygon_ids()
+            .filter_map(|polygon| {
+                let vertices = self
+                    .polygon_vertices(polygon)
+                    .map(|vertex| self.vertex_world(vertex))
+                    .collect::<Vec<_>>();
+                let outline = vertices
+                    .iter()
+                    .map(|vertex| vertex.xz())
+                    .collect::<Vec<_>>();
+                let horizontal = if contains_point(&outline, point.xz()) {
+                    0.0
+                } else {
+                    outline
+                        .iter()
+                        .zip(outline.iter().cycle().skip(1))
+                        .map(|(a, b)| distance_squared_to_segment(point.xz(), *a, *b))
+                        .fold(f32::INFINITY, f32::min)
+                        .sqrt()
+                };
+                let (min, max) = vertices
+                    .iter()
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), vertex| {
+                        (min.min(vertex.y), max.max(vertex.y))
+                    });
+                let vertical = (min - point.y).max(point.y - max).max(0.0);
+                (horizontal <= radius && vertical <= radius)
+                    .then_some((polygon, horizontal * horizontal + vertical * vertical))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(polygon, _)| polygon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::U16Vec3;
+
+    use super::*;
+    use crate::{Aabb3d, RegionId};
+
+    /// Two 2x2 squares with a gap of 2 wu between them, the second one 1 wu higher.
+    fn ledges() -> PolygonNavmesh {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 0, 0),
+                U16Vec3::new(0, 0, 2),
+                U16Vec3::new(2, 0, 2),
+                U16Vec3::new(2, 0, 0),
+                U16Vec3::new(4, 1, 0),
+                U16Vec3::new(4, 1, 2),
+                U16Vec3::new(6, 1, 2),
+                U16Vec3::new(6, 1, 0),
+            ],
+            polygons: vec![0, 1, 2, 3, N, N, 4, 5, 6, 7, N, N],
+            polygon_neighbors: vec![N; 12],
+            flags: vec![0; 2],
+            regions: vec![RegionId::from(1); 2],
+            areas: vec![AreaType::DEFAULT_WALKABLE; 2],
+            max_vertices_per_polygon: 6,
+            aabb: Aabb3d {
+                min: Vec3::ZERO,
+                max: Vec3::new(6.0, 1.0, 2.0),
+            },
+            cell_size: 1.0,
+            cell_height: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn connections_link_the_polygons_at_their_endpoints() {
+        let mut mesh = ledges();
+        let jump = OffMeshConnection {
+            // Slightly off the edge of the lower ledge and above the higher one.
+            start: Vec3::new(2.2, 0.0, 1.0),
+            end: Vec3::new(5.0, 1.3, 1.0),
+            bidirectional: false,
+            ..Default::default()
+        };
+        let into_the_void = OffMeshConnection {
+            start: Vec3::new(1.0, 0.0, 1.0),
+            end: Vec3::new(10.0, 0.0, 1.0),
+            ..Default::default()
+        };
+        let skipped = mesh.bake_off_mesh_connections(&[jump, into_the_void]);
+        assert_eq!(skipped, vec![1]);
+        assert_eq!(
+            mesh.off_mesh_connections,
+            vec![BakedOffMeshConnection {
+                connection: jump,
+                start_polygon: PolyId(0),
+                end_polygon: PolyId(1),
+            }]
+        );
+
+        let from_lower = mesh
+            .off_mesh_connections_from(PolyId(0))
+            .map(|(_, to)| to)
+            .collect::<Vec<_>>();
+        assert_eq!(from_lower, vec![PolyId(1)]);
+        // The jump is one-way.
+        assert_eq!(mesh.off_mesh_connections_from(PolyId(1)).count(), 0);
+
+        let bidirectional = OffMeshConnection {
+            bidirectional: true,
+            ..jump
+        };
+        mesh.bake_off_mesh_connections(&[bidirectional]);
+        let from_higher = mesh
+            .off_mesh_connections_from(PolyId(1))
+            .map(|(_, to)| to)
+            .collect::<Vec<_>>();
+        assert_eq!(from_higher, vec![PolyId(0)]);
+    }
+}
@@ -0,0 +1,305 @@
+//! Outlines of the walkable areas of a [`PolygonNavmesh`] projected onto the xz-plane,
+//! e.g. for vector minimaps, fog-of-war meshes or zone overlays.
+//!
+//! The outlines are traced along the border edges of each group of connected polygons of the same area type
+//! and then simplified with the same Ramer–Douglas–Peucker pass that simplifies the contours of regions.
+
+use std::collections::HashMap;
+
+use glam::{Vec2, Vec3Swizzles as _};
+
+use crate::{
+    AreaType, BuildContoursFlags, EdgeConnection, PolygonNavmesh, RegionVertexId, VertexId,
+    contours::{remove_degenerate_segments, simplify_contour},
+};
+
+/// The outline of a group of connected polygons of the same area type, projected onto the xz-plane.
+///
+/// All points are in world space, with the x- and z-coordinates stored as x and y.
+/// Outlines of overlapping floors overlap as well, as the heights are dropped.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct AreaOutline {
+    /// The area type of all polygons within the outline.
+    pub area: AreaType,
+    /// The outer border. Wound counter-clockwise when the x-axis points right and the z-axis points up.
+    pub outline: Vec<Vec2>,
+    /// The borders of the holes within the outline, e.g. around obstacles or polygons of other area types.
+    /// Wound clockwise, i.e. opposite to [`Self::outline`].
+    pub holes: Vec<Vec<Vec2>>,
+}
+
+impl PolygonNavmesh {
+    /// Traces the outlines of the polygons of each area type, ordered by area type.
+    ///
+    /// Every group of polygons that are connected through edges and share an area type becomes one [`AreaOutline`].
+    /// Edges leading to other tiles count as borders, so the outlines of a tiled navmesh end at the tile borders.
+    ///
+    /// The outlines are simplified such that they deviate at most `max_error` from the polygon edges,
+    /// like the contours are with [`NavmeshConfig::max_simplification_error`](crate::NavmeshConfig::max_simplification_error).
+    /// A `max_error` of zero only removes vertices in the middle of straight edges. `[Limit: >=0] [Units: vx]`
+    /// Holes that collapse during the simplification are dropped. Large errors may let holes cross their outline.
+    pub fn area_outlines(&self, max_error: f32) -> Vec<AreaOutline> {
+        let (component_of, component_areas) = self.area_components();
+
+        // The border edges of each component, in the winding of the polygons.
+        let mut borders = vec![Vec::new(); component_areas.len()];
+        for polygon in self.polygon_ids() {
+            let component = component_of[polygon.index()];
+            for edge in self.edges(polygon) {
+                let inner = matches!(
+                    self.edge_connection(edge),
+                    EdgeConnection::Polygon(neighbor) if component_of[neighbor.index()] == component
+                );
+                if !inner {
+                    borders[component].push(self.edge_vertices(edge));
+                }
+            }
+        }
+
+        let mut outlines = Vec::new();
+        let mut simplified = Vec::new();
+        for (area, edges) in component_areas.into_iter().zip(borders) {
+            let mut loops = trace_loops(&edges)
+                .into_iter()
+                .filter_map(|vertices| {
+                    let points = vertices
+                        .iter()
+                        .map(|vertex| (self.vertex(*vertex), RegionVertexId::empty()))
+                        .collect::<Vec<_>>();
+                    simplified.clear();
+                    simplify_contour(
+                        &points,
+                        &mut simplified,
+                        max_error,
+                        0,
+                        BuildContoursFlags::empty(),
+                    );
+                    remove_degenerate_segments(&mut simplified);
+                    (simplified.len() >= 3).then(|| {
+                        simplified
+                            .iter()
+                            .map(|(vertex, _)| {
+                                self.aabb.min.xz() + vertex.xz().as_vec2() * self.cell_size
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .map(|points| (signed_area(&points), points))
+                .collect::<Vec<_>>();
+
+            // The outer border encloses all holes, so it covers the largest area.
+            let Some(outer) = loops
+                .iter()
+                .enumerate()
+                .max_by(|(_, (a, _)), (_, (b, _))| a.abs().total_cmp(&b.abs()))
+                .map(|(index, _)| index)
+            else {
+                continue;
+            };
+            let (outer_area, mut outline) = loops.swap_remove(outer);
+            if outer_area < 0.0 {
+                outline.reverse();
+            }
+            let holes = loops
+                .into_iter()
+                .map(|(hole_area, mut hole)| {
+                    if hole_area > 0.0 {
+                        hole.reverse();
+                    }
+                    hole
+                })
+                .collect();
+            outlines.push(AreaOutline {
+                area,
+                outline,
+                holes,
+            });
+        }
+        outlines.sort_by_key(|outline| outline.area.0);
+        outlines
+    }
+
+    /// Groups the polygons into components of the same area type that are connected through edges.
+    /// Returns the component of each polygon and the area type of each component.
+    fn area_components(&self) -> (Vec<usize>, Vec<AreaType>) {
+        let mut component_of = vec![usize::MAX; self.polygon_count()];
+        let mut component_areas = Vec::new();
+        let mut stack = Vec::new();
+        for start in self.polygon_ids() {
+            if component_of[start.index()] != usize::MAX {
+                continue;
+            }
+            let component = component_areas.len();
+            let area = self.areas[start.index()];
+            component_areas.push(area);
+            component_of[start.index()] = component;
+            stack.push(start);
+            while let Some(polygon) = stack.pop() {
+                for neighbor in self.neighbors(polygon) {
+                    if let EdgeConnection::Polygon(neighbor) = neighbor
+                        && self.areas[neighbor.index()] == area
+                        && component_of[neighbor.index()] == usize::MAX
+                    {
+                        component_of[neighbor.index()] = component;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+        }
+        (component_of, component_areas)
+    }
+}
+
+/// Chains directed border edges into closed loops of vertices.
+///
+/// Where several loops touch at a vertex, the edges are chained in the order they were found.
+fn trace_loops(edges: &[[VertexId; 2]]) -> Vec<Vec<VertexId>> {
+    let mut outgoing: HashMap<VertexId, Vec<usize>> = HashMap::new();
+    for (index, [start, _]) in edges.iter().enumerate() {
+        outgoing.entry(*start).or_default().push(index);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut loops = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        let mut vertices = Vec::new();
+        let mut current = first;
+        loop {
+            used[current] = true;
+            let [start, end] = edges[current];
+            vertices.push(start);
+            if end == edges[first][0] {
+                break;
+            }
+            let Some(next) = outgoing
+                .get(&end)
+                .and_then(|candidates| candidates.iter().copied().find(|&next| !used[next]))
+            else {
+                // The border is not closed, which only happens for broken meshes.
+                vertices.clear();
+                break;
+            };
+            current = next;
+        }
+        if vertices.len() >= 3 {
+            loops.push(vertices);
+        }
+    }
+    loops
+}
+
+/// Twice the signed area of the polygon, positive if it winds counter-clockwise.
+fn signed_area(points: &[Vec2]) -> f32 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{U16Vec3, Vec3};
+
+    use super::*;
+    use crate::{Aabb3d, RegionId};
+
+    /// A 3 × 3 grid of 4 × 4 quads, with the center quad of the given area type.
+    fn grid(center: AreaType) -> PolygonNavmesh {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        let vertex = |x: u16, z: u16| z * 4 + x;
+        let quad = |x: u16, z: u16| if x < 3 && z < 3 { z * 3 + x } else { N };
+        let mut polygons = Vec::new();
+        let mut polygon_neighbors = Vec::new();
+        for z in 0..3 {
+            for x in 0..3 {
+                polygons.extend([
+                    vertex(x, z),
+                    vertex(x, z + 1),
+                    vertex(x + 1, z + 1),
+                    vertex(x + 1, z),
+                ]);
+                polygon_neighbors.extend([
+                    x.checked_sub(1).map_or(N, |x| quad(x, z)),
+                    quad(x, z + 1),
+                    quad(x + 1, z),
+                    z.checked_sub(1).map_or(N, |z| quad(x, z)),
+                ]);
+            }
+        }
+        let mut areas = vec![AreaType::DEFAULT_WALKABLE; 9];
+        areas[4] = center;
+        PolygonNavmesh {
+            vertices: (0..4)
+                .flat_map(|z| (0..4).map(move |x| U16Vec3::new(x * 4, 0, z * 4)))
+                .collect(),
+            polygons,
+            polygon_neighbors,
+            flags: vec![0; 9],
+            regions: vec![RegionId::from(1); 9],
+            areas,
+            max_vertices_per_polygon: 4,
+            aabb: Aabb3d {
+                min: Vec3::new(-6.0, 0.0, -6.0),
+                max: Vec3::new(6.0, 0.0, 6.0),
+            },
+            cell_size: 1.0,
+            cell_height: 1.0,
+            ..Default::default()
+        }
+    }
+
+    /// The points of `polygon` rotated to start at the smallest point, so that loops can be compared.
+    fn normalized(polygon: &[Vec2]) -> Vec<Vec2> {
+        let start = polygon
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)))
+            .unwrap()
+            .0;
+        polygon[start..]
+            .iter()
+            .chain(&polygon[..start])
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn other_area_type_becomes_a_hole() {
+        let water = AreaType(3);
+        let outlines = grid(water).area_outlines(0.0);
+        assert_eq!(outlines.len(), 2);
+
+        // Ordered by area type.
+        let (water_outline, ground) = (&outlines[0], &outlines[1]);
+        assert_eq!(ground.area, AreaType::DEFAULT_WALKABLE);
+        assert_eq!(
+            normalized(&ground.outline),
+            [[-6.0, -6.0], [6.0, -6.0], [6.0, 6.0], [-6.0, 6.0]].map(Vec2::from)
+        );
+        assert_eq!(ground.holes.len(), 1);
+        assert_eq!(
+            normalized(&ground.holes[0]),
+            [[-2.0, -2.0], [-2.0, 2.0], [2.0, 2.0], [2.0, -2.0]].map(Vec2::from)
+        );
+
+        assert_eq!(water_outline.area, water);
+        assert!(water_outline.holes.is_empty());
+        assert_eq!(
+            normalized(&water_outline.outline),
+            [[-2.0, -2.0], [2.0, -2.0], [2.0, 2.0], [-2.0, 2.0]].map(Vec2::from)
+        );
+    }
+
+    #[test]
+    fn same_area_type_is_one_outline() {
+        let outlines = grid(AreaType::DEFAULT_WALKABLE).area_outlines(0.0);
+        assert_eq!(outlines.len(), 1);
+        assert!(outlines[0].holes.is_empty());
+        assert_eq!(outlines[0].outline.len(), 4);
+        assert_eq!(signed_area(&outlines[0].outline), 2.0 * 144.0);
+    }
+}
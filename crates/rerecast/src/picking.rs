@@ -0,0 +1,248 @@
+//! A dilated copy of a navmesh for resolving clicks that land slightly off its edges, e.g. for click-to-move.
+//!
+//! The navmesh ends an agent radius away from walls, so clicking right next to a wall or at the foot of a ledge
+//! often misses it. Picking against a copy whose borders are pushed outward resolves such clicks to the polygon
+//! next to them, while the navmesh the agents walk on stays untouched.
+
+use glam::{Vec2, Vec3, Vec3Swizzles as _};
+
+use crate::{
+    EdgeConnection, PolyId, PolygonNavmesh,
+    walkability::{contains_point, winding},
+};
+
+/// A copy of a [`PolygonNavmesh`] in world space whose solid borders are pushed outward by a margin on the xz-plane.
+///
+/// Only edges without a neighbor are moved, so the polygons still meet along their shared edges and at tile portals.
+/// Created with [`PolygonNavmesh::dilated_for_picking`]. Has to be created again whenever the navmesh changes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PickingNavmesh {
+    /// The outlines of the polygons before dilation, in world space.
+    outlines: Vec<Vec<Vec3>>,
+    /// The outlines of the polygons after dilation, in world space.
+    dilated: Vec<Vec<Vec3>>,
+    margin: f32,
+}
+
+/// A point resolved to a polygon by [`PickingNavmesh::pick`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NavmeshPick {
+    /// The polygon the point was resolved to.
+    pub polygon: PolyId,
+    /// The point moved onto [`Self::polygon`] on the xz-plane, so that it can be used as the target of a path.
+    /// Equals the picked point if it already lies on the navmesh.
+    pub point: Vec3,
+}
+
+impl PolygonNavmesh {
+    /// Creates a copy of the navmesh for picking, with its solid borders pushed outward by `margin` on the xz-plane. `[Units: wu]`
+    ///
+    /// Corners where two borders meet are mitered, but moved by at most three times the margin,
+    /// so that sharp corners don't turn into long spikes.
+    pub fn dilated_for_picking(&self, margin: f32) -> PickingNavmesh {
+        let outlines = self
+            .polygon_ids()
+            .map(|polygon| {
+                self.polygon_vertices(polygon)
+                    .map(|vertex| self.vertex_world(vertex))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let dilated = self
+            .polygon_ids()
+            .zip(&outlines)
+            .map(|(polygon, outline)| {
+                let borders = self
+                    .edges(polygon)
+                    .map(|edge| self.edge_connection(edge) == EdgeConnection::Border)
+                    .collect::<Vec<_>>();
+                dilate(outline, &borders, margin)
+            })
+            .collect();
+        PickingNavmesh {
+            outlines,
+            dilated,
+            margin,
+        }
+    }
+}
+
+impl PickingNavmesh {
+    /// Resolves `point` to the polygon whose dilated outline contains it on the xz-plane.
+    /// Where several polygons overlap, e.g. on multiple floors, the one closest to `point` in height is picked.
+    ///
+    /// Returns `None` if the point is further than the margin away from the navmesh.
+    pub fn pick(&self, point: Vec3) -> Option<NavmeshPick> {
+        let (polygon, _) = self
+            .dilated
+            .iter()
+            .enumerate()
+            .filter(|(_, outline)| {
+                let outline = outline.iter().map(|vertex| vertex.xz()).collect::<Vec<_>>();
+                contains_point(&outline, point.xz())
+            })
+            .map(|(polygon, outline)| {
+                let (min, max) = outline
+                    .iter()
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), vertex| {
+                        (min.min(vertex.y), max.max(vertex.y))
+                    });
+                (polygon, (min - point.y).max(point.y - max).max(0.0))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        Some(NavmeshPick {
+            polygon: PolyId::from(polygon as u16),
+            point: clamp_to_outline(&self.outlines[polygon], point),
+        })
+    }
+
+    /// The outline of the polygon after dilation, in world space.
+    pub fn dilated_outline(&self, polygon: PolyId) -> &[Vec3] {
+        &self.dilated[polygon.index()]
+    }
+
+    /// The margin the navmesh was dilated by. `[Units: wu]`
+    pub fn margin(&self) -> f32 {
+        self.margin
+    }
+}
+
+/// Moves the vertices of the convex `outline` so that the edges marked in `borders` move outward by `margin`.
+/// `borders[i]` belongs to the edge from vertex `i` to vertex `i + 1`.
+fn dilate(outline: &[Vec3], borders: &[bool], margin: f32) -> Vec<Vec3> {
+    let count = outline.len();
+    let orientation = winding(&outline.iter().map(|vertex| vertex.xz()).collect::<Vec<_>>());
+    let direction =
+        |edge: usize| (outline[(edge + 1) % count].xz() - outline[edge].xz()).normalize_or_zero();
+    let outward = |edge: usize| -direction(edge).perp() * orientation;
+
+    (0..count)
+        .map(|vertex| {
+            let (previous, next) = ((vertex + count - 1) % count, vertex);
+            let offset = match (borders[previous], borders[next]) {
+                (false, false) => Vec2::ZERO,
+                (true, true) => {
+                    let (a, b) = (outward(previous), outward(next));
+                    let miter = margin / (1.0 + a.dot(b)).max(f32::EPSILON);
+                    (a + b) * miter.min(3.0 * margin / (a + b).length().max(f32::EPSILON))
+                }
+                // Slide along the inner edge, so that it stays in line with the neighboring polygon.
+                (true, false) => slide(-direction(next), outward(previous), margin),
+                (false, true) => slide(direction(previous), outward(next), margin),
+            };
+            outline[vertex] + Vec3::new(offset.x, 0.0, offset.y)
+        })
+        .collect()
+}
+
+/// The offset along `along` that moves a point by `margin` in the direction of `outward`,
+/// or `outward * margin` if `along` barely moves outward.
+fn slide(along: Vec2, outward: Vec2, margin: f32) -> Vec2 {
+    let speed = along.dot(outward);
+    if speed > 1.0 / 3.0 {
+        along * margin / speed
+    } else {
+        outward * margin
+    }
+}
+
+/// The closest point to `point` on the convex `outline` on the xz-plane, with the height interpolated along the outline.
+/// Returns `point` itself if the outline contains it.
+fn clamp_to_outline(outline: &[Vec3], point: Vec3) -> Vec3 {
+    let outline_xz = outline.iter().map(|vertex| vertex.xz()).collect::<Vec<_>>();
+    if contains_point(&outline_xz, point.xz()) {
+        return point;
+    }
+    outline
+        .iter()
+        .zip(outline.iter().cycle().skip(1))
+        .map(|(a, b)| {
+            let along = b.xz() - a.xz();
+            let t = (along.dot(point.xz() - a.xz()) / along.length_squared().max(f32::EPSILON))
+                .clamp(0.0, 1.0);
+            a.lerp(*b, t)
+        })
+        .min_by(|a, b| {
+            let distance = |vertex: &Vec3| vertex.xz().distance_squared(point.xz());
+            distance(a).total_cmp(&distance(b))
+        })
+        .unwrap_or(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::U16Vec3;
+
+    use super::*;
+    use crate::{Aabb3d, AreaType, RegionId};
+
+    /// Two 4x4 quads next to each other along the x-axis.
+    fn two_quads() -> PolygonNavmesh {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 0, 0),
+                U16Vec3::new(0, 0, 4),
+                U16Vec3::new(4, 0, 4),
+                U16Vec3::new(4, 0, 0),
+                U16Vec3::new(8, 0, 4),
+                U16Vec3::new(8, 0, 0),
+            ],
+            polygons: vec![0, 1, 2, 3, 3, 2, 4, 5],
+            polygon_neighbors: vec![N, N, 1, N, 0, N, N, N],
+            flags: vec![0; 2],
+            regions: vec![RegionId::from(1); 2],
+            areas: vec![AreaType::DEFAULT_WALKABLE; 2],
+            max_vertices_per_polygon: 4,
+            aabb: Aabb3d {
+                min: Vec3::ZERO,
+                max: Vec3::new(8.0, 0.0, 4.0),
+            },
+            cell_size: 1.0,
+            cell_height: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn clicks_near_the_border_resolve_to_the_polygon() {
+        let navmesh = two_quads();
+        let picking = navmesh.dilated_for_picking(0.5);
+
+        let pick = picking.pick(Vec3::new(1.0, 0.0, 2.0)).unwrap();
+        assert_eq!(pick.polygon, PolyId::from(0));
+        assert_eq!(pick.point, Vec3::new(1.0, 0.0, 2.0));
+
+        let pick = picking.pick(Vec3::new(8.3, 0.0, 2.0)).unwrap();
+        assert_eq!(pick.polygon, PolyId::from(1));
+        assert_eq!(pick.point, Vec3::new(8.0, 0.0, 2.0));
+
+        let pick = picking.pick(Vec3::new(-0.3, 0.0, -0.3)).unwrap();
+        assert_eq!(pick.polygon, PolyId::from(0));
+        assert_eq!(pick.point, Vec3::ZERO);
+
+        // Along the shared edge, the border is moved outward without a gap.
+        let pick = picking.pick(Vec3::new(4.0, 0.0, 4.4)).unwrap();
+        assert_eq!(pick.point, Vec3::new(4.0, 0.0, 4.0));
+
+        assert_eq!(picking.pick(Vec3::new(8.6, 0.0, 2.0)), None);
+        assert_eq!(
+            navmesh
+                .dilated_for_picking(0.0)
+                .pick(Vec3::new(8.3, 0.0, 2.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn shared_edges_stay_in_place() {
+        let picking = two_quads().dilated_for_picking(0.5);
+        let first = picking.dilated_outline(PolyId::from(0));
+        let second = picking.dilated_outline(PolyId::from(1));
+        assert_eq!(first[2], Vec3::new(4.0, 0.0, 4.5));
+        assert_eq!(first[3], Vec3::new(4.0, 0.0, -0.5));
+        assert_eq!(second[0], first[3]);
+        assert_eq!(second[1], first[2]);
+        assert_eq!(first[0], Vec3::new(-0.5, 0.0, -0.5));
+    }
+}
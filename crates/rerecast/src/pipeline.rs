@@ -0,0 +1,1040 @@
+//! Runs all steps needed to go from a [`TriMesh`] to a [`PolygonNavmesh`] and [`DetailNavmesh`].
+
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use glam::Vec3;
+use thiserror::Error;
+
+use crate::{
+    AreaMergePolicy, AreaType, BuildContoursFlags, BuildRegionsError, BuildScratch,
+    CompactHeightfield, CompactHeightfieldError, ContourSet, ConvexVolume, DetailNavmesh,
+    DetailNavmeshError, Heightfield, HeightfieldBuilder, HeightfieldBuilderError, NavmeshConfig,
+    PolygonNavmesh, PolygonNavmeshError, RasterizationError, RecordedSpans, RegionPartitioning,
+    RegionSlope, TriMesh,
+};
+
+/// Builds a navmesh from world-space geometry by running the standard Recast pipeline with the given config.
+///
+/// The heightfield covers [`NavmeshConfig::aabb`], so geometry outside of it is ignored.
+/// The area types of `trimesh` are kept for all triangles that are too steep to walk on
+/// according to [`NavmeshConfig::walkable_slope_angle`]. All other triangles are marked as [`AreaType::DEFAULT_WALKABLE`](crate::AreaType::DEFAULT_WALKABLE).
+pub fn build_navmesh(
+    trimesh: TriMesh,
+    config: &NavmeshConfig,
+) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+    build_navmesh_with_progress(trimesh, config, &BuildContext::default())
+}
+
+/// Same as [`build_navmesh`], but reports its progress to and can be cancelled through `context`.
+///
+/// This can be used to e.g. measure how long each stage takes, to report progress to the user,
+/// or to stop a build that is no longer needed from another thread.
+pub fn build_navmesh_with_progress(
+    mut trimesh: TriMesh,
+    config: &NavmeshConfig,
+    context: &BuildContext,
+) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+    context.begin(BuildStage::Rasterization)?;
+    trimesh.mark_walkable_triangles(config.walkable_slope_angle);
+    build_marked_navmesh(trimesh, config, &mut BuildScratch::default(), context)
+}
+
+/// Same as [`build_navmesh_with_progress`], but uses the area types of `trimesh` as they are
+/// instead of marking triangles as walkable by their slope.
+///
+/// Use this when the triangles were already marked, e.g. with [`TriMesh::mark_walkable_triangles_as`]
+/// to give walkable triangles another area than [`AreaType::DEFAULT_WALKABLE`](crate::AreaType::DEFAULT_WALKABLE),
+/// or to mark triangles as walkable regardless of their slope.
+pub fn build_premarked_navmesh_with_progress(
+    trimesh: TriMesh,
+    config: &NavmeshConfig,
+    context: &BuildContext,
+) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+    build_premarked_navmesh_with_scratch(trimesh, config, &mut BuildScratch::default(), context)
+}
+
+/// Same as [`build_premarked_navmesh_with_progress`], but takes the temporary buffers of the build from `scratch`
+/// and returns them afterwards.
+///
+/// Reusing the same scratch for repeated builds, e.g. when rebuilding a navmesh at runtime,
+/// avoids allocating these buffers again for every build. Use [`BuildScratch::stats`] to check how well it is reused.
+pub fn build_premarked_navmesh_with_scratch(
+    trimesh: TriMesh,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+    context: &BuildContext,
+) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+    context.begin(BuildStage::Rasterization)?;
+    build_marked_navmesh(trimesh, config, scratch, context)
+}
+
+/// Same as [`build_premarked_navmesh_with_scratch`], but inserts spans that were rasterized ahead of time
+/// instead of rasterizing a [`TriMesh`], e.g. spans cached from an earlier build or produced by a rasterizer on the GPU.
+///
+/// The spans must have been recorded for a heightfield covering [`NavmeshConfig::aabb`]
+/// with the cell size and cell height of `config`, otherwise [`RasterizationError::LayoutMismatch`] is returned.
+pub fn build_navmesh_from_spans_with_scratch(
+    spans: &RecordedSpans,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+    context: &BuildContext,
+) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+    context.begin(BuildStage::Rasterization)?;
+    build_rasterized_navmesh(config, scratch, context, |heightfield| {
+        heightfield.replay_spans(spans, config.walkable_climb, AreaMergePolicy::default())
+    })
+}
+
+/// Same as [`build_navmesh_with_progress`], but rasterizes a point cloud with [`Heightfield::rasterize_points`]
+/// instead of a [`TriMesh`], e.g. a LiDAR scan of the environment.
+///
+/// Every point fills the columns within `radius` of it on the xz-plane. All points are marked as [`AreaType::DEFAULT_WALKABLE`],
+/// so whether the agent can stand on them is decided by the filters alone, which remove spans below too little clearance.
+pub fn build_navmesh_from_points(
+    points: &[Vec3],
+    radius: f32,
+    config: &NavmeshConfig,
+    context: &BuildContext,
+) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+    context.begin(BuildStage::Rasterization)?;
+    build_rasterized_navmesh(
+        config,
+        &mut BuildScratch::default(),
+        context,
+        |heightfield| {
+            heightfield.rasterize_points_with_merge_policy(
+                points,
+                radius,
+                AreaType::DEFAULT_WALKABLE,
+                config.walkable_climb,
+                AreaMergePolicy::default(),
+            )
+        },
+    )
+}
+
+/// Same as [`build_premarked_navmesh_with_scratch`], but lets `rasterize` fill the empty heightfield,
+/// e.g. to combine triangles with [`Heightfield::rasterize_heightmap`] or [`Heightfield::replay_spans`].
+///
+/// The heightfield covers [`NavmeshConfig::aabb`] with the cell size and cell height of `config`.
+/// Pass [`NavmeshConfig::walkable_climb`] as the flag merge threshold to match the other entry points.
+///
+/// Once the walkable area is eroded, the walkable spans within each of the `volumes` are marked with its [`ConvexVolume::area`]
+/// through [`CompactHeightfield::mark_convex_poly_area`], in order, so later volumes win where they overlap.
+///
+/// Use [`SteppedBuild`](crate::SteppedBuild) to run the same pipeline one [`BuildStage`] at a time.
+pub fn build_navmesh_with_rasterizer(
+    config: &NavmeshConfig,
+    volumes: &[ConvexVolume],
+    scratch: &mut BuildScratch,
+    context: &BuildContext,
+    rasterize: impl FnOnce(&mut Heightfield) -> Result<(), RasterizationError>,
+) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+    context.begin(BuildStage::Rasterization)?;
+    let compact_heightfield =
+        build_eroded_heightfield(config, volumes, scratch, context, rasterize)?;
+    let result = build_navmesh_from_eroded(compact_heightfield, config, scratch, context)?;
+    context.finish();
+    Ok(result)
+}
+
+/// The eroded [`CompactHeightfield`] of a build, kept so that the navmesh can be rebuilt with other [`ConvexVolume`]s
+/// without rasterizing, filtering, compacting and eroding the geometry again.
+///
+/// The volumes are only marked on the compact heightfield after erosion, so toggling a volume, e.g. marking a zone as water,
+/// does not change anything before it. [`CachedBuild::rebuild`] marks the volumes on a copy of the snapshot
+/// and only runs the stages from [`BuildStage::DistanceField`] on.
+///
+/// ```
+/// # use glam::{UVec3, Vec2, Vec3A};
+/// # use rerecast::*;
+/// let mut trimesh = TriMesh::from_parts(
+///     vec![
+///         Vec3A::new(-5.0, 0.0, -5.0),
+///         Vec3A::new(-5.0, 0.0, 5.0),
+///         Vec3A::new(5.0, 0.0, 5.0),
+///         Vec3A::new(5.0, 0.0, -5.0),
+///     ],
+///     vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+/// )
+/// .unwrap();
+/// let config = NavmeshConfig {
+///     aabb: Aabb3d::new(Vec3A::ZERO, [6.0, 2.0, 6.0]),
+///     ..NavmeshConfigBuilder::default().build()
+/// };
+/// trimesh.mark_walkable_triangles(config.walkable_slope_angle);
+/// let mut scratch = BuildScratch::new();
+/// let cached = CachedBuild::new(config, &mut scratch, &BuildContext::default(), |heightfield| {
+///     heightfield.rasterize_triangles(&trimesh, config.walkable_climb)
+/// })
+/// .unwrap();
+///
+/// let water = ConvexVolume {
+///     vertices: vec![Vec2::new(0.0, -6.0), Vec2::new(0.0, 6.0), Vec2::new(6.0, 6.0), Vec2::new(6.0, -6.0)],
+///     min_y: -1.0,
+///     max_y: 1.0,
+///     area: AreaType(3),
+/// };
+/// let (polygon, _detail) = cached
+///     .rebuild(&[water], &mut scratch, &BuildContext::default())
+///     .unwrap();
+/// assert!(polygon.areas.contains(&AreaType(3)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedBuild {
+    compact_heightfield: CompactHeightfield,
+    config: NavmeshConfig,
+}
+
+impl CachedBuild {
+    /// Runs the stages of [`build_navmesh_with_rasterizer`] up to and including [`BuildStage::Erosion`] without marking any volumes,
+    /// and keeps the eroded compact heightfield. Use [`CachedBuild::rebuild`] to finish the build.
+    pub fn new(
+        config: NavmeshConfig,
+        scratch: &mut BuildScratch,
+        context: &BuildContext,
+        rasterize: impl FnOnce(&mut Heightfield) -> Result<(), RasterizationError>,
+    ) -> Result<Self, BuildNavmeshError> {
+        context.begin(BuildStage::Rasterization)?;
+        let compact_heightfield =
+            build_eroded_heightfield(&config, &[], scratch, context, rasterize)?;
+        Ok(Self {
+            compact_heightfield,
+            config,
+        })
+    }
+
+    /// Marks the `volumes` on a copy of the cached compact heightfield, like [`build_navmesh_with_rasterizer`] does,
+    /// and runs the remaining stages of the pipeline on it.
+    ///
+    /// Results in the same navmesh as a full build with the same geometry and `volumes`.
+    /// The progress is reported from [`BuildStage::Erosion`] on, as the stages before it are skipped.
+    pub fn rebuild(
+        &self,
+        volumes: &[ConvexVolume],
+        scratch: &mut BuildScratch,
+        context: &BuildContext,
+    ) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+        context.begin(BuildStage::Erosion)?;
+        scratch.begin_build();
+        self.finish_build(volumes, scratch, context)
+    }
+
+    /// Like [`CachedBuild::new`] followed by [`CachedBuild::rebuild`], but reports the stages and counts the build in the
+    /// [`BuildScratch`] only once, like [`build_navmesh_with_rasterizer`] does.
+    pub fn build(
+        config: NavmeshConfig,
+        volumes: &[ConvexVolume],
+        scratch: &mut BuildScratch,
+        context: &BuildContext,
+        rasterize: impl FnOnce(&mut Heightfield) -> Result<(), RasterizationError>,
+    ) -> Result<(Self, (PolygonNavmesh, DetailNavmesh)), BuildNavmeshError> {
+        let build = Self::new(config, scratch, context, rasterize)?;
+        let result = build.finish_build(volumes, scratch, context)?;
+        Ok((build, result))
+    }
+
+    /// Runs the stages after [`BuildStage::Erosion`] on a copy of the cached compact heightfield with the `volumes` marked.
+    fn finish_build(
+        &self,
+        volumes: &[ConvexVolume],
+        scratch: &mut BuildScratch,
+        context: &BuildContext,
+    ) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+        let mut compact_heightfield = self.compact_heightfield.clone();
+        mark_volumes(&mut compact_heightfield, volumes);
+        let result =
+            build_navmesh_from_eroded(compact_heightfield, &self.config, scratch, context)?;
+        context.finish();
+        Ok(result)
+    }
+
+    /// The config the cached compact heightfield was built with, which is also used by [`CachedBuild::rebuild`].
+    pub fn config(&self) -> &NavmeshConfig {
+        &self.config
+    }
+
+    /// The eroded compact heightfield, before any volumes are marked on it.
+    pub fn compact_heightfield(&self) -> &CompactHeightfield {
+        &self.compact_heightfield
+    }
+}
+
+/// Runs the pipeline after the triangles were marked, starting within [`BuildStage::Rasterization`].
+fn build_marked_navmesh(
+    trimesh: TriMesh,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+    context: &BuildContext,
+) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+    build_rasterized_navmesh(config, scratch, context, |heightfield| {
+        heightfield.rasterize_triangles(&trimesh, config.walkable_climb)
+    })
+}
+
+/// Runs the pipeline with `rasterize` filling the empty heightfield, starting within [`BuildStage::Rasterization`].
+fn build_rasterized_navmesh(
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+    context: &BuildContext,
+    rasterize: impl FnOnce(&mut Heightfield) -> Result<(), RasterizationError>,
+) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+    let compact_heightfield = build_eroded_heightfield(config, &[], scratch, context, rasterize)?;
+    let result = build_navmesh_from_eroded(compact_heightfield, config, scratch, context)?;
+    context.finish();
+    Ok(result)
+}
+
+/// Runs the stages of the pipeline up to and including [`BuildStage::Erosion`], starting within [`BuildStage::Rasterization`].
+pub(crate) fn build_eroded_heightfield(
+    config: &NavmeshConfig,
+    volumes: &[ConvexVolume],
+    scratch: &mut BuildScratch,
+    context: &BuildContext,
+    rasterize: impl FnOnce(&mut Heightfield) -> Result<(), RasterizationError>,
+) -> Result<CompactHeightfield, BuildNavmeshError> {
+    let mut heightfield = run_rasterization(config, scratch, rasterize)?;
+
+    context.begin(BuildStage::Filtering)?;
+    run_filtering(&mut heightfield, config, scratch);
+
+    context.begin(BuildStage::Compaction)?;
+    let mut compact_heightfield = run_compaction(heightfield, config, scratch)?;
+
+    context.begin(BuildStage::Erosion)?;
+    run_erosion(&mut compact_heightfield, config, volumes, scratch);
+    Ok(compact_heightfield)
+}
+
+/// Runs the stages of the pipeline after [`BuildStage::Erosion`] on an eroded compact heightfield.
+pub(crate) fn build_navmesh_from_eroded(
+    mut compact_heightfield: CompactHeightfield,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+    context: &BuildContext,
+) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+    context.begin(BuildStage::DistanceField)?;
+    run_distance_field(&mut compact_heightfield, config, scratch);
+
+    context.begin(BuildStage::Regions)?;
+    let slopes = run_regions(&mut compact_heightfield, config, scratch)?;
+
+    context.begin(BuildStage::Contours)?;
+    let contours = run_contours(&compact_heightfield, config, scratch);
+
+    context.begin(BuildStage::PolygonMesh)?;
+    let poly_mesh = run_polygon_mesh(contours, config, slopes.as_deref())?;
+
+    context.begin(BuildStage::DetailMesh)?;
+    let detail_mesh = run_detail_mesh(&poly_mesh, &compact_heightfield, config)?;
+    scratch.recycle_compact(compact_heightfield);
+
+    Ok((poly_mesh, detail_mesh))
+}
+
+/// [`BuildStage::Rasterization`]: creates the heightfield covering [`NavmeshConfig::aabb`] and lets `rasterize` fill it.
+pub(crate) fn run_rasterization(
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+    rasterize: impl FnOnce(&mut Heightfield) -> Result<(), RasterizationError>,
+) -> Result<Heightfield, BuildNavmeshError> {
+    scratch.begin_build();
+    let mut heightfield = HeightfieldBuilder {
+        aabb: config.aabb,
+        cell_size: config.cell_size,
+        cell_height: config.cell_height,
+    }
+    .build_with_scratch(scratch)?;
+
+    rasterize(&mut heightfield)?;
+    #[cfg(debug_assertions)]
+    heightfield.assert_integrity(BuildStage::Rasterization, false);
+    Ok(heightfield)
+}
+
+/// [`BuildStage::Filtering`]
+pub(crate) fn run_filtering(
+    heightfield: &mut Heightfield,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+) {
+    // Once all geometry is rasterized, we do initial pass of filtering to
+    // remove unwanted overhangs caused by the conservative rasterization
+    // as well as filter spans where the character cannot possibly stand.
+    heightfield.filter_low_hanging_walkable_obstacles(config.walkable_climb);
+    heightfield.filter_ledge_spans_with(
+        config.walkable_height,
+        config.walkable_climb,
+        config.ledge_filter,
+    );
+    heightfield.filter_walkable_low_height_spans(config.walkable_height);
+    // Not in the original. Merging spans during rasterization leaves holes in the span storage,
+    // so we defragment it before walking it again.
+    heightfield.compact_spans_with_scratch(scratch);
+    #[cfg(debug_assertions)]
+    heightfield.assert_integrity(BuildStage::Filtering, true);
+}
+
+/// [`BuildStage::Compaction`]
+pub(crate) fn run_compaction(
+    heightfield: Heightfield,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+) -> Result<CompactHeightfield, BuildNavmeshError> {
+    let compact_heightfield = heightfield.into_compact_with_scratch(
+        config.walkable_height,
+        config.walkable_climb,
+        scratch,
+    )?;
+    #[cfg(debug_assertions)]
+    compact_heightfield.assert_integrity(BuildStage::Compaction);
+    Ok(compact_heightfield)
+}
+
+/// [`BuildStage::Erosion`], followed by marking the `volumes` on the eroded walkable area.
+pub(crate) fn run_erosion(
+    compact_heightfield: &mut CompactHeightfield,
+    config: &NavmeshConfig,
+    volumes: &[ConvexVolume],
+    scratch: &mut BuildScratch,
+) {
+    compact_heightfield.erode_walkable_area_with_scratch(
+        config.walkable_radius,
+        config.erosion_method,
+        scratch,
+    );
+    mark_volumes(compact_heightfield, volumes);
+}
+
+/// Marks the `volumes` on the eroded walkable area, in order, so later volumes win where they overlap.
+fn mark_volumes(compact_heightfield: &mut CompactHeightfield, volumes: &[ConvexVolume]) {
+    for volume in volumes {
+        compact_heightfield.mark_convex_poly_area(volume.clone());
+    }
+    #[cfg(debug_assertions)]
+    compact_heightfield.assert_integrity(BuildStage::Erosion);
+}
+
+/// [`BuildStage::DistanceField`]
+pub(crate) fn run_distance_field(
+    compact_heightfield: &mut CompactHeightfield,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+) {
+    // Only the watershed partitioning needs the distance field.
+    if config.region_partitioning == RegionPartitioning::Watershed {
+        compact_heightfield.build_distance_field_with_scratch(scratch);
+        #[cfg(debug_assertions)]
+        compact_heightfield.assert_distance_field_integrity(BuildStage::DistanceField);
+    }
+}
+
+/// [`BuildStage::Regions`]. Returns the slopes of the regions if [`NavmeshConfig::slope_cost`] needs them.
+pub(crate) fn run_regions(
+    compact_heightfield: &mut CompactHeightfield,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+) -> Result<Option<Vec<RegionSlope>>, BuildNavmeshError> {
+    compact_heightfield.build_regions_with_scratch(
+        config.region_partitioning,
+        config.border_size,
+        config.min_region_area,
+        config.merge_region_area,
+        scratch,
+    )?;
+    if let Some(max_extent) = config.max_region_extent {
+        compact_heightfield.split_large_regions(max_extent)?;
+    }
+    #[cfg(debug_assertions)]
+    compact_heightfield.assert_region_integrity(BuildStage::Regions);
+    Ok((config.slope_cost > 0.0).then(|| compact_heightfield.region_slopes()))
+}
+
+/// [`BuildStage::Contours`]
+pub(crate) fn run_contours(
+    compact_heightfield: &CompactHeightfield,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+) -> ContourSet {
+    let contours = compact_heightfield.build_contours_with_scratch(
+        config.max_simplification_error,
+        config.max_edge_len,
+        config.contour_flags,
+        scratch,
+    );
+    #[cfg(debug_assertions)]
+    contours.assert_integrity(
+        BuildStage::Contours,
+        !config
+            .contour_flags
+            .contains(BuildContoursFlags::SKIP_RAW_VERTICES),
+    );
+    contours
+}
+
+/// [`BuildStage::PolygonMesh`], baking the `slopes` returned by [`run_regions`] into the costs of the polygons.
+pub(crate) fn run_polygon_mesh(
+    contours: ContourSet,
+    config: &NavmeshConfig,
+    slopes: Option<&[RegionSlope]>,
+) -> Result<PolygonNavmesh, BuildNavmeshError> {
+    let mut poly_mesh = contours.into_polygon_mesh(config.max_vertices_per_polygon)?;
+    if config.cache_polygon_geometry {
+        poly_mesh.cache_polygon_geometry();
+    }
+    if let Some(slopes) = slopes {
+        poly_mesh.bake_slope_costs(slopes, config.slope_cost);
+    }
+    #[cfg(debug_assertions)]
+    poly_mesh.assert_integrity(BuildStage::PolygonMesh);
+    Ok(poly_mesh)
+}
+
+/// [`BuildStage::DetailMesh`]
+pub(crate) fn run_detail_mesh(
+    poly_mesh: &PolygonNavmesh,
+    compact_heightfield: &CompactHeightfield,
+    config: &NavmeshConfig,
+) -> Result<DetailNavmesh, BuildNavmeshError> {
+    Ok(DetailNavmesh::new_with(
+        poly_mesh,
+        compact_heightfield,
+        config.detail_sample_dist,
+        config.detail_sample_max_error,
+        config.detail_sampling,
+    )?)
+}
+
+/// A step of the pipeline run by [`build_navmesh`], in the order they are run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+pub enum BuildStage {
+    /// Marking walkable triangles and rasterizing them into a [`Heightfield`](crate::Heightfield).
+    Rasterization,
+    /// Filtering out spans an agent cannot stand on.
+    Filtering,
+    /// Turning the heightfield into a [`CompactHeightfield`](crate::CompactHeightfield).
+    Compaction,
+    /// Eroding the walkable area by the agent radius.
+    Erosion,
+    /// Building the distance field used for region partitioning.
+    DistanceField,
+    /// Partitioning the walkable area into regions.
+    Regions,
+    /// Tracing the contours of the regions.
+    Contours,
+    /// Building the [`PolygonNavmesh`] from the contours.
+    PolygonMesh,
+    /// Building the [`DetailNavmesh`].
+    DetailMesh,
+}
+
+impl BuildStage {
+    /// All stages, in the order they are run.
+    pub const ALL: [BuildStage; 9] = [
+        BuildStage::Rasterization,
+        BuildStage::Filtering,
+        BuildStage::Compaction,
+        BuildStage::Erosion,
+        BuildStage::DistanceField,
+        BuildStage::Regions,
+        BuildStage::Contours,
+        BuildStage::PolygonMesh,
+        BuildStage::DetailMesh,
+    ];
+
+    /// The position of the stage in [`Self::ALL`].
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// A short, snake case name of the stage.
+    pub const fn name(self) -> &'static str {
+        match self {
+            BuildStage::Rasterization => "rasterization",
+            BuildStage::Filtering => "filtering",
+            BuildStage::Compaction => "compaction",
+            BuildStage::Erosion => "erosion",
+            BuildStage::DistanceField => "distance_field",
+            BuildStage::Regions => "regions",
+            BuildStage::Contours => "contours",
+            BuildStage::PolygonMesh => "polygon_mesh",
+            BuildStage::DetailMesh => "detail_mesh",
+        }
+    }
+}
+
+/// Reports the progress of a navmesh build and lets other threads cancel it.
+///
+/// Passed to the entry points of the pipeline like [`build_navmesh_with_progress`], so that hosts without Bevy,
+/// e.g. command line tools or other engines, can show a progress bar and abort builds that are no longer needed.
+/// The default context reports nothing and is never cancelled.
+///
+/// ```
+/// # use std::sync::atomic::{AtomicBool, Ordering};
+/// # use rerecast::*;
+/// let cancel = AtomicBool::new(false);
+/// let progress = |stage: BuildStage, done: f32| println!("{:.0}%: {}", done * 100.0, stage.name());
+/// let context = BuildContext::new(&progress, &cancel);
+///
+/// // Usually set from another thread, e.g. when the player left the level the navmesh was built for.
+/// cancel.store(true, Ordering::Relaxed);
+/// let config = NavmeshConfigBuilder::default().build();
+/// let result = build_navmesh_with_progress(TriMesh::default(), &config, &context);
+/// assert!(matches!(result, Err(BuildNavmeshError::Cancelled)));
+/// ```
+#[derive(Clone, Copy)]
+pub struct BuildContext<'a> {
+    /// Called whenever a [`BuildStage`] begins, with the fraction of the build that is done, from 0 to 1.
+    /// Called one last time with [`BuildStage::DetailMesh`] and 1 once the build succeeded.
+    pub progress: &'a (dyn Fn(BuildStage, f32) + Sync),
+    /// Checked whenever a [`BuildStage`] begins. Once set, the build stops with [`BuildNavmeshError::Cancelled`].
+    pub cancel: &'a AtomicBool,
+}
+
+/// Reports nothing, see [`BuildContext::default`].
+static NO_PROGRESS: fn(BuildStage, f32) = |_, _| {};
+/// Never set, see [`BuildContext::default`].
+static NEVER_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+impl Default for BuildContext<'_> {
+    fn default() -> Self {
+        Self {
+            progress: &NO_PROGRESS,
+            cancel: &NEVER_CANCELLED,
+        }
+    }
+}
+
+impl fmt::Debug for BuildContext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BuildContext")
+            .field("cancel", &self.cancel)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> BuildContext<'a> {
+    /// Creates a context that reports to `progress` and is cancelled by setting `cancel`.
+    pub fn new(progress: &'a (dyn Fn(BuildStage, f32) + Sync), cancel: &'a AtomicBool) -> Self {
+        Self { progress, cancel }
+    }
+
+    /// Replaces [`Self::progress`] with `progress`.
+    pub fn with_progress(self, progress: &'a (dyn Fn(BuildStage, f32) + Sync)) -> Self {
+        Self { progress, ..self }
+    }
+
+    /// Replaces [`Self::cancel`] with `cancel`.
+    pub fn with_cancel(self, cancel: &'a AtomicBool) -> Self {
+        Self { cancel, ..self }
+    }
+
+    /// Whether [`Self::cancel`] was set.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Reports that `stage` begins, unless the build was cancelled.
+    pub(crate) fn begin(&self, stage: BuildStage) -> Result<(), BuildNavmeshError> {
+        if self.is_cancelled() {
+            return Err(BuildNavmeshError::Cancelled);
+        }
+        (self.progress)(stage, stage.index() as f32 / BuildStage::ALL.len() as f32);
+        Ok(())
+    }
+
+    /// Reports that the build succeeded.
+    pub(crate) fn finish(&self) {
+        (self.progress)(BuildStage::DetailMesh, 1.0);
+    }
+}
+
+/// Errors that can occur when building a navmesh with [`build_navmesh`].
+#[derive(Error, Debug)]
+pub enum BuildNavmeshError {
+    /// Happens when the heightfield cannot be created.
+    #[error("Failed to build heightfield: {0}")]
+    Heightfield(#[from] HeightfieldBuilderError),
+    /// Happens when the geometry cannot be rasterized.
+    #[error("Failed to rasterize geometry: {0}")]
+    Rasterization(#[from] RasterizationError),
+    /// Happens when the compact heightfield cannot be created.
+    #[error("Failed to build compact heightfield: {0}")]
+    CompactHeightfield(#[from] CompactHeightfieldError),
+    /// Happens when the regions cannot be built.
+    #[error("Failed to build regions: {0}")]
+    Regions(#[from] BuildRegionsError),
+    /// Happens when the polygon mesh cannot be built.
+    #[error("Failed to build polygon mesh: {0}")]
+    PolygonNavmesh(#[from] PolygonNavmeshError),
+    /// Happens when the detail mesh cannot be built.
+    #[error("Failed to build detail mesh: {0}")]
+    DetailNavmesh(#[from] DetailNavmeshError),
+    /// Happens when [`BuildContext::cancel`] was set during the build.
+    #[error("The navmesh build was cancelled")]
+    Cancelled,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use glam::{UVec3, Vec2, Vec3A};
+
+    use super::*;
+    use crate::{Aabb3d, AreaType, NavmeshConfigBuilder};
+
+    #[test]
+    fn empty_input_builds_empty_navmesh() {
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3A::ZERO, [5.0, 5.0, 5.0]),
+            ..NavmeshConfigBuilder::default().build()
+        };
+        let (polygon, detail) = build_navmesh(TriMesh::default(), &config).unwrap();
+        assert_eq!(polygon.polygon_count(), 0);
+        assert!(detail.meshes.is_empty());
+    }
+
+    #[test]
+    fn progress_is_reported_until_cancelled() {
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3A::ZERO, [5.0, 5.0, 5.0]),
+            ..NavmeshConfigBuilder::default().build()
+        };
+        let reported = Mutex::new(Vec::new());
+        let progress = |stage, done| reported.lock().unwrap().push((stage, done));
+        build_navmesh_with_progress(
+            TriMesh::default(),
+            &config,
+            &BuildContext::default().with_progress(&progress),
+        )
+        .unwrap();
+        let done = reported.lock().unwrap().drain(..).collect::<Vec<_>>();
+        assert_eq!(done.len(), BuildStage::ALL.len() + 1);
+        assert!(done.windows(2).all(|pair| pair[0].1 < pair[1].1));
+        assert_eq!(done.last(), Some(&(BuildStage::DetailMesh, 1.0)));
+
+        let cancel = AtomicBool::new(false);
+        let progress = |stage, _| {
+            reported.lock().unwrap().push((stage, 0.0));
+            if stage == BuildStage::Contours {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        };
+        let result = build_navmesh_with_progress(
+            TriMesh::default(),
+            &config,
+            &BuildContext::new(&progress, &cancel),
+        );
+        assert!(matches!(result, Err(BuildNavmeshError::Cancelled)));
+        let stages = reported
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(stage, _)| *stage)
+            .collect::<Vec<_>>();
+        assert_eq!(stages, BuildStage::ALL[..=BuildStage::Contours.index()]);
+    }
+
+    #[test]
+    fn premarked_areas_skip_slope_marking() {
+        // A 10x10 ramp with a slope of about 11 degrees, steeper than the configured walkable slope.
+        let mut trimesh = TriMesh::from_parts(
+            vec![
+                Vec3A::new(-5.0, -1.0, -5.0),
+                Vec3A::new(-5.0, 1.0, 5.0),
+                Vec3A::new(5.0, 1.0, 5.0),
+                Vec3A::new(5.0, -1.0, -5.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3A::ZERO, [6.0, 2.0, 6.0]),
+            ..NavmeshConfigBuilder {
+                agent_max_slope: 5.0_f32.to_radians(),
+                ..Default::default()
+            }
+            .build()
+        };
+
+        let (polygon, _detail) = build_navmesh(trimesh.clone(), &config).unwrap();
+        assert_eq!(polygon.polygon_count(), 0);
+
+        let road = AreaType(7);
+        trimesh.area_types.fill(road);
+        let (polygon, _detail) =
+            build_premarked_navmesh_with_progress(trimesh, &config, &BuildContext::default())
+                .unwrap();
+        assert!(polygon.polygon_count() > 0);
+        assert!(polygon.areas.iter().all(|area| *area == road));
+    }
+
+    #[test]
+    fn volumes_mark_walkable_area() {
+        let trimesh = TriMesh::from_parts(
+            vec![
+                Vec3A::new(-5.0, 0.0, -5.0),
+                Vec3A::new(-5.0, 0.0, 5.0),
+                Vec3A::new(5.0, 0.0, 5.0),
+                Vec3A::new(5.0, 0.0, -5.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3A::ZERO, [6.0, 2.0, 6.0]),
+            ..NavmeshConfigBuilder::default().build()
+        };
+        let water = AreaType(3);
+        // Covers the half of the floor with positive x.
+        let volume = ConvexVolume {
+            vertices: vec![
+                Vec2::new(0.0, -6.0),
+                Vec2::new(0.0, 6.0),
+                Vec2::new(6.0, 6.0),
+                Vec2::new(6.0, -6.0),
+            ],
+            min_y: -1.0,
+            max_y: 1.0,
+            area: water,
+        };
+        let rasterize = |heightfield: &mut Heightfield| {
+            let mut trimesh = trimesh.clone();
+            trimesh.mark_walkable_triangles(config.walkable_slope_angle);
+            heightfield.rasterize_triangles(&trimesh, config.walkable_climb)
+        };
+
+        let (polygon, _detail) = build_navmesh_with_rasterizer(
+            &config,
+            std::slice::from_ref(&volume),
+            &mut BuildScratch::new(),
+            &BuildContext::default(),
+            rasterize,
+        )
+        .unwrap();
+        assert!(polygon.areas.contains(&water));
+        assert!(polygon.areas.contains(&AreaType::DEFAULT_WALKABLE));
+        for poly in polygon.polygon_ids() {
+            let center = polygon.center(poly);
+            let expected = if center.x > 0.0 {
+                water
+            } else {
+                AreaType::DEFAULT_WALKABLE
+            };
+            assert_eq!(polygon.areas[poly.index()], expected);
+        }
+
+        let (polygon, _detail) = build_navmesh_with_rasterizer(
+            &config,
+            &[],
+            &mut BuildScratch::new(),
+            &BuildContext::default(),
+            rasterize,
+        )
+        .unwrap();
+        assert!(!polygon.areas.contains(&water));
+
+        let mut scratch = BuildScratch::new();
+        let cached =
+            CachedBuild::new(config, &mut scratch, &BuildContext::default(), rasterize).unwrap();
+        for volumes in [
+            std::slice::from_ref(&volume),
+            &[],
+            std::slice::from_ref(&volume),
+        ] {
+            let expected = build_navmesh_with_rasterizer(
+                &config,
+                volumes,
+                &mut BuildScratch::new(),
+                &BuildContext::default(),
+                rasterize,
+            )
+            .unwrap();
+            let rebuilt = cached
+                .rebuild(volumes, &mut scratch, &BuildContext::default())
+                .unwrap();
+            assert_eq!(rebuilt, expected);
+        }
+
+        let reported = Mutex::new(Vec::new());
+        let progress = |stage, _| reported.lock().unwrap().push(stage);
+        let mut scratch = BuildScratch::new();
+        let (_cached, built) = CachedBuild::build(
+            config,
+            std::slice::from_ref(&volume),
+            &mut scratch,
+            &BuildContext::default().with_progress(&progress),
+            rasterize,
+        )
+        .unwrap();
+        assert_eq!(
+            built,
+            cached
+                .rebuild(
+                    &[volume],
+                    &mut BuildScratch::new(),
+                    &BuildContext::default()
+                )
+                .unwrap()
+        );
+        let mut stages = reported.into_inner().unwrap();
+        assert_eq!(stages.pop(), Some(BuildStage::DetailMesh));
+        assert_eq!(stages, BuildStage::ALL);
+        assert_eq!(scratch.stats().builds, 1);
+    }
+
+    #[test]
+    fn reused_scratch_stops_growing() {
+        // A floor with a platform above it, so that rasterization merges spans.
+        let quad = |y: f32, extent: f32| {
+            [
+                Vec3A::new(-extent, y, -extent),
+                Vec3A::new(-extent, y, extent),
+                Vec3A::new(extent, y, extent),
+                Vec3A::new(extent, y, -extent),
+            ]
+        };
+        let mut trimesh = TriMesh::from_parts(
+            [quad(0.0, 5.0), quad(3.0, 2.0)].concat(),
+            vec![
+                UVec3::new(0, 1, 2),
+                UVec3::new(0, 2, 3),
+                UVec3::new(4, 5, 6),
+                UVec3::new(4, 6, 7),
+            ],
+        )
+        .unwrap();
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3A::ZERO, [6.0, 4.0, 6.0]),
+            ..NavmeshConfigBuilder::default().build()
+        };
+        trimesh.mark_walkable_triangles(config.walkable_slope_angle);
+        let expected = build_navmesh(trimesh.clone(), &config).unwrap();
+
+        let mut scratch = BuildScratch::new();
+        for builds in 1..=3 {
+            let navmesh = build_premarked_navmesh_with_scratch(
+                trimesh.clone(),
+                &config,
+                &mut scratch,
+                &BuildContext::default(),
+            )
+            .unwrap();
+            assert_eq!(navmesh, expected);
+            let stats = scratch.stats();
+            assert_eq!(stats.builds, builds);
+            assert!(stats.capacity_bytes > 0);
+            if builds == 1 {
+                assert!(stats.growths > 0);
+            } else {
+                assert_eq!(stats.growths, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn skipping_raw_contour_vertices_builds_same_navmesh() {
+        let mut trimesh = TriMesh::from_parts(
+            vec![
+                Vec3A::new(-5.0, 0.0, -5.0),
+                Vec3A::new(-5.0, 0.0, 5.0),
+                Vec3A::new(5.0, 0.0, 5.0),
+                Vec3A::new(5.0, 0.0, -5.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3A::ZERO, [6.0, 2.0, 6.0]),
+            ..NavmeshConfigBuilder::default().build()
+        };
+        let skipping = NavmeshConfig {
+            contour_flags: config.contour_flags | BuildContoursFlags::SKIP_RAW_VERTICES,
+            ..config
+        };
+        let expected = build_navmesh(trimesh.clone(), &config).unwrap();
+        assert_eq!(build_navmesh(trimesh.clone(), &skipping).unwrap(), expected);
+
+        trimesh.mark_walkable_triangles(config.walkable_slope_angle);
+        let cached = CachedBuild::new(
+            config,
+            &mut BuildScratch::new(),
+            &BuildContext::default(),
+            |heightfield| heightfield.rasterize_triangles(&trimesh, config.walkable_climb),
+        )
+        .unwrap();
+        let mut compact_heightfield = cached.compact_heightfield().clone();
+        let mut scratch = BuildScratch::new();
+        run_distance_field(&mut compact_heightfield, &config, &mut scratch);
+        run_regions(&mut compact_heightfield, &config, &mut scratch).unwrap();
+        let kept = run_contours(&compact_heightfield, &config, &mut scratch);
+        let skipped = run_contours(&compact_heightfield, &skipping, &mut scratch);
+        assert!(!kept.contours.is_empty());
+        for (kept, skipped) in kept.contours.iter().zip(&skipped.contours) {
+            assert!(!kept.raw_vertices.is_empty());
+            assert!(skipped.raw_vertices.is_empty());
+            assert_eq!(kept.vertices, skipped.vertices);
+        }
+    }
+
+    #[test]
+    fn recorded_spans_build_same_navmesh() {
+        let mut trimesh = TriMesh::from_parts(
+            vec![
+                Vec3A::new(-5.0, 0.0, -5.0),
+                Vec3A::new(-5.0, 0.5, 5.0),
+                Vec3A::new(5.0, 0.5, 5.0),
+                Vec3A::new(5.0, 0.0, -5.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3A::ZERO, [6.0, 2.0, 6.0]),
+            ..NavmeshConfigBuilder::default().build()
+        };
+        trimesh.mark_walkable_triangles(config.walkable_slope_angle);
+        let expected = build_navmesh(trimesh.clone(), &config).unwrap();
+
+        let heightfield = HeightfieldBuilder {
+            aabb: config.aabb,
+            cell_size: config.cell_size,
+            cell_height: config.cell_height,
+        }
+        .build()
+        .unwrap();
+        let spans = heightfield.record_triangles(&trimesh).unwrap();
+        let mut scratch = BuildScratch::new();
+        let navmesh = build_navmesh_from_spans_with_scratch(
+            &spans,
+            &config,
+            &mut scratch,
+            &BuildContext::default(),
+        )
+        .unwrap();
+        assert_eq!(navmesh, expected);
+
+        let other_layout = NavmeshConfig {
+            cell_size: config.cell_size * 2.0,
+            ..config
+        };
+        assert!(matches!(
+            build_navmesh_from_spans_with_scratch(
+                &spans,
+                &other_layout,
+                &mut scratch,
+                &BuildContext::default()
+            ),
+            Err(BuildNavmeshError::Rasterization(
+                RasterizationError::LayoutMismatch
+            ))
+        ));
+    }
+}
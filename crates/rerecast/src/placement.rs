@@ -0,0 +1,352 @@
+//! Finding points on a navmesh, e.g. for spawning agents or snapping them back onto the navmesh after they were pushed off it.
+//!
+//! Like the `frand` callbacks of Detour, the random queries take a closure returning uniformly distributed values in `[0, 1)`,
+//! so that any random number generator can be plugged in.
+
+use std::{collections::HashSet, f32::consts::TAU};
+
+use glam::{Vec2, Vec3, Vec3Swizzles as _};
+
+use crate::{
+    EdgeConnection, PolyId, PolygonNavmesh, math::distance_squared_to_segment,
+    walkability::contains_point,
+};
+
+/// How many candidates [`PolygonNavmesh::random_point_in_circle`] tries before giving up.
+const MAX_CIRCLE_ATTEMPTS: usize = 64;
+
+impl PolygonNavmesh {
+    /// The point on the navmesh closest to `position`, along with the polygon it lies on.
+    /// Returns `None` if the navmesh has no polygons.
+    ///
+    /// The height of the point lies on the polygon, which can be off by up to [`NavmeshConfig::detail_sample_max_error`](crate::NavmeshConfig::detail_sample_max_error)
+    /// from the detail surface. Use [`DetailNavmesh::height_at`](crate::DetailNavmesh::height_at) for the exact height.
+    pub fn closest_point(&self, position: Vec3) -> Option<(PolyId, Vec3)> {
+        self.polygon_ids()
+            .map(|polygon| {
+                let point = self.closest_point_on_polygon(polygon, position);
+                (polygon, point, point.distance_squared(position))
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+            .map(|(polygon, point, _)| (polygon, point))
+    }
+
+    /// A random point on the navmesh, along with the polygon it lies on. Every part of the navmesh is equally likely on the xz-plane.
+    /// Returns `None` if the navmesh has no area.
+    ///
+    /// `frand` must return uniformly distributed values in `[0, 1)`. Its heights follow the same rules as [`PolygonNavmesh::closest_point`].
+    pub fn random_point(&self, mut frand: impl FnMut() -> f32) -> Option<(PolyId, Vec3)> {
+        let polygons = self.polygon_ids().collect::<Vec<_>>();
+        self.random_point_on(&polygons, &mut frand)
+    }
+
+    /// A random point on the navmesh within `radius` of `center` on the xz-plane, along with the polygon it lies on.
+    /// Every part of the navmesh within the circle is equally likely.
+    ///
+    /// Only the polygons connected to the polygon closest to `center` are considered, so that on multiple floors,
+    /// the point stays on the floor of `center`. Returns `None` if no point was found, e.g. when barely any of the circle
+    /// lies on the navmesh.
+    ///
+    /// `frand` must return uniformly distributed values in `[0, 1)`. Its heights follow the same rules as [`PolygonNavmesh::closest_point`].
+    pub fn random_point_in_circle(
+        &self,
+        center: Vec3,
+        radius: f32,
+        mut frand: impl FnMut() -> f32,
+    ) -> Option<(PolyId, Vec3)> {
+        let (start, _) = self.closest_point(center)?;
+        let polygons = self.polygons_in_circle(start, center.xz(), radius);
+        let polygon_area: f32 = polygons
+            .iter()
+            .map(|polygon| self.surface_area(*polygon))
+            .sum();
+        let circle_area = TAU * 0.5 * radius * radius;
+
+        for _ in 0..MAX_CIRCLE_ATTEMPTS {
+            // Sample whichever is smaller, the circle or the polygons, and reject the points outside of the other.
+            let candidate = if circle_area < polygon_area {
+                let (distance, angle) = (radius * frand().sqrt(), TAU * frand());
+                let point = center.xz() + Vec2::from_angle(angle) * distance;
+                polygons
+                    .iter()
+                    .filter(|polygon| contains_point(&self.outline_xz(**polygon), point))
+                    .map(|polygon| {
+                        let position = Vec3::new(point.x, center.y, point.y);
+                        (*polygon, self.closest_point_on_polygon(*polygon, position))
+                    })
+                    .min_by(|(_, a), (_, b)| {
+                        (a.y - center.y).abs().total_cmp(&(b.y - center.y).abs())
+                    })
+            } else {
+                self.random_point_on(&polygons, &mut frand)
+                    .filter(|(_, point)| {
+                        point.xz().distance_squared(center.xz()) <= radius * radius
+                    })
+            };
+            if candidate.is_some() {
+                return candidate;
+            }
+        }
+        None
+    }
+
+    /// A point on one of the `polygons`, uniformly distributed over their area on the xz-plane.
+    fn random_point_on(
+        &self,
+        polygons: &[PolyId],
+        frand: &mut impl FnMut() -> f32,
+    ) -> Option<(PolyId, Vec3)> {
+        let mut total_area = 0.0;
+        let cumulative_areas = polygons
+            .iter()
+            .map(|polygon| {
+                total_area += self.surface_area(*polygon);
+                total_area
+            })
+            .collect::<Vec<_>>();
+        if total_area <= 0.0 {
+            return None;
+        }
+        let target = frand() * total_area;
+        let index = cumulative_areas
+            .partition_point(|cumulative_area| *cumulative_area <= target)
+            .min(polygons.len() - 1);
+        let polygon = polygons[index];
+        Some((polygon, self.random_point_on_polygon(polygon, frand)))
+    }
+
+    /// A point uniformly distributed over the area of the convex `polygon` on the xz-plane.
+    fn random_point_on_polygon(&self, polygon: PolyId, frand: &mut impl FnMut() -> f32) -> Vec3 {
+        let vertices = self.outline(polygon);
+        // Fan triangulation from the first vertex, picking a triangle weighted by its area.
+        let areas = vertices
+            .windows(2)
+            .skip(1)
+            .map(|pair| {
+                (pair[0].xz() - vertices[0].xz())
+                    .perp_dot(pair[1].xz() - vertices[0].xz())
+                    .abs()
+            })
+            .collect::<Vec<_>>();
+        let total: f32 = areas.iter().sum();
+        let mut target = frand() * total;
+        let mut triangle = 0;
+        while triangle + 1 < areas.len() && target >= areas[triangle] {
+            target -= areas[triangle];
+            triangle += 1;
+        }
+        let (a, b, c) = (vertices[0], vertices[triangle + 1], vertices[triangle + 2]);
+        let (mut u, mut v) = (frand(), frand());
+        if u + v > 1.0 {
+            (u, v) = (1.0 - u, 1.0 - v);
+        }
+        a + (b - a) * u + (c - a) * v
+    }
+
+    /// The point on `polygon` closest to `position`: directly below or above it if the polygon contains it on the xz-plane,
+    /// otherwise the closest point on its outline.
+    pub(crate) fn closest_point_on_polygon(&self, polygon: PolyId, position: Vec3) -> Vec3 {
+        closest_point_on_outline(&self.outline(polygon), position)
+    }
+
+    /// The polygons connected to `start` through edges that come within `radius` of `center` on the xz-plane, including `start`.
+    fn polygons_in_circle(&self, start: PolyId, center: Vec2, radius: f32) -> Vec<PolyId> {
+        let mut visited = HashSet::from([start]);
+        let mut polygons = vec![start];
+        let mut next = 0;
+        while let Some(&polygon) = polygons.get(next) {
+            next += 1;
+            for edge in self.edges(polygon) {
+                let EdgeConnection::Polygon(neighbor) = self.edge_connection(edge) else {
+                    continue;
+                };
+                let [a, b] = self
+                    .edge_vertices(edge)
+                    .map(|vertex| self.vertex_world(vertex).xz());
+                if distance_squared_to_segment(center, a, b) <= radius * radius
+                    && visited.insert(neighbor)
+                {
+                    polygons.push(neighbor);
+                }
+            }
+        }
+        polygons
+    }
+
+    /// The vertices of `polygon` in world space.
+    pub(crate) fn outline(&self, polygon: PolyId) -> Vec<Vec3> {
+        self.polygon_vertices(polygon)
+            .map(|vertex| self.vertex_world(vertex))
+            .collect()
+    }
+
+    pub(crate) fn outline_xz(&self, polygon: PolyId) -> Vec<Vec2> {
+        self.polygon_vertices(polygon)
+            .map(|vertex| self.vertex_world(vertex).xz())
+            .collect()
+    }
+}
+
+/// The point on the convex polygon with the given world-space vertices closest to `position`,
+/// see [`PolygonNavmesh::closest_point_on_polygon`].
+pub(crate) fn closest_point_on_outline(vertices: &[Vec3], position: Vec3) -> Vec3 {
+    let outline = vertices
+        .iter()
+        .map(|vertex| vertex.xz())
+        .collect::<Vec<_>>();
+    if contains_point(&outline, position.xz()) {
+        let height = fan_height(vertices, position.xz()).unwrap_or(vertices[0].y);
+        return Vec3::new(position.x, height, position.z);
+    }
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| {
+            let along = b.xz() - a.xz();
+            let t = (along.dot(position.xz() - a.xz()) / along.length_squared().max(f32::EPSILON))
+                .clamp(0.0, 1.0);
+            a.lerp(*b, t)
+        })
+        .min_by(|a, b| {
+            a.distance_squared(position)
+                .total_cmp(&b.distance_squared(position))
+        })
+        .unwrap_or(position)
+}
+
+/// The height of the fan triangulation of the convex polygon at `point`, or `None` if no triangle contains it.
+pub(crate) fn fan_height(vertices: &[Vec3], point: Vec2) -> Option<f32> {
+    vertices.windows(2).skip(1).find_map(|pair| {
+        let (a, b, c) = (vertices[0], pair[0], pair[1]);
+        let (v0, v1, v2) = (b.xz() - a.xz(), c.xz() - a.xz(), point - a.xz());
+        let denominator = v0.perp_dot(v1);
+        if denominator.abs() <= f32::EPSILON {
+            return None;
+        }
+        let u = v2.perp_dot(v1) / denominator;
+        let v = v0.perp_dot(v2) / denominator;
+        const TOLERANCE: f32 = 1e-4;
+        (u >= -TOLERANCE && v >= -TOLERANCE && u + v <= 1.0 + TOLERANCE)
+            .then_some(a.y + (b.y - a.y) * u + (c.y - a.y) * v)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::U16Vec3;
+
+    use super::*;
+    use crate::{Aabb3d, AreaType, RegionId};
+
+    /// Two 4x4 quads next to each other along the x-axis, with a third one 4 units above the first one.
+    fn two_floors() -> PolygonNavmesh {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 0, 0),
+                U16Vec3::new(0, 0, 4),
+                U16Vec3::new(4, 0, 4),
+                U16Vec3::new(4, 0, 0),
+                U16Vec3::new(8, 2, 4),
+                U16Vec3::new(8, 2, 0),
+                U16Vec3::new(0, 4, 0),
+                U16Vec3::new(0, 4, 4),
+                U16Vec3::new(4, 4, 4),
+                U16Vec3::new(4, 4, 0),
+            ],
+            polygons: vec![0, 1, 2, 3, 3, 2, 4, 5, 6, 7, 8, 9],
+            polygon_neighbors: vec![N, N, 1, N, 0, N, N, N, N, N, N, N],
+            flags: vec![0; 3],
+            regions: vec![RegionId::from(1); 3],
+            areas: vec![AreaType::DEFAULT_WALKABLE; 3],
+            max_vertices_per_polygon: 4,
+            aabb: Aabb3d {
+                min: Vec3::ZERO,
+                max: Vec3::new(8.0, 4.0, 4.0),
+            },
+            cell_size: 1.0,
+            cell_height: 1.0,
+            ..Default::default()
+        }
+    }
+
+    /// A deterministic stand-in for a random number generator.
+    fn frand() -> impl FnMut() -> f32 {
+        let mut state = 1_u32;
+        move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 24) as f32
+        }
+    }
+
+    #[test]
+    fn closest_point_snaps_onto_navmesh() {
+        let navmesh = two_floors();
+        assert_eq!(
+            navmesh.closest_point(Vec3::new(1.0, 0.5, 2.0)),
+            Some((PolyId::from(0), Vec3::new(1.0, 0.0, 2.0)))
+        );
+        assert_eq!(
+            navmesh.closest_point(Vec3::new(1.0, 3.5, 2.0)),
+            Some((PolyId::from(2), Vec3::new(1.0, 4.0, 2.0)))
+        );
+        // On the ramp, the height is interpolated.
+        assert_eq!(
+            navmesh.closest_point(Vec3::new(6.0, 1.0, 2.0)),
+            Some((PolyId::from(1), Vec3::new(6.0, 1.0, 2.0)))
+        );
+        // Beyond the end of the ramp.
+        assert_eq!(
+            navmesh.closest_point(Vec3::new(10.0, 2.0, 2.0)),
+            Some((PolyId::from(1), Vec3::new(8.0, 2.0, 2.0)))
+        );
+        assert_eq!(PolygonNavmesh::default().closest_point(Vec3::ZERO), None);
+    }
+
+    #[test]
+    fn random_points_lie_on_navmesh() {
+        let navmesh = two_floors();
+        let mut frand = frand();
+        let mut counts = [0; 3];
+        for _ in 0..300 {
+            let (polygon, point) = navmesh.random_point(&mut frand).unwrap();
+            counts[polygon.index()] += 1;
+            assert!(
+                navmesh
+                    .closest_point_on_polygon(polygon, point)
+                    .abs_diff_eq(point, 1e-5)
+            );
+        }
+        // The polygons have the same area on the xz-plane.
+        assert!(counts.iter().all(|count| (70..130).contains(count)));
+        assert_eq!(PolygonNavmesh::default().random_point(frand), None);
+    }
+
+    #[test]
+    fn random_points_in_circle_stay_on_floor() {
+        let navmesh = two_floors();
+        let mut frand = frand();
+        for (center, radius) in [
+            (Vec3::new(3.0, 0.0, 2.0), 1.5),
+            (Vec3::new(3.0, 0.0, 2.0), 20.0),
+            (Vec3::new(2.0, 4.0, 2.0), 1.0),
+        ] {
+            for _ in 0..50 {
+                let (polygon, point) = navmesh
+                    .random_point_in_circle(center, radius, &mut frand)
+                    .unwrap();
+                assert!(point.xz().distance(center.xz()) <= radius);
+                assert_eq!(polygon == PolyId::from(2), center.y > 2.0);
+                assert!(
+                    navmesh
+                        .closest_point_on_polygon(polygon, point)
+                        .abs_diff_eq(point, 1e-5)
+                );
+            }
+        }
+        assert_eq!(
+            navmesh.random_point_in_circle(Vec3::new(20.0, 0.0, 2.0), 1.0, frand),
+            None
+        );
+    }
+}
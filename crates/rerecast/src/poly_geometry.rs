@@ -0,0 +1,177 @@
+//! The centers and areas of the polygons of a [`PolygonNavmesh`], which pathfinding and spawn sampling query over and over.
+
+use glam::{Vec3, Vec3Swizzles as _};
+
+use crate::{PolyId, PolygonNavmesh};
+
+impl PolygonNavmesh {
+    /// The center of the given polygon in world space, i.e. the average of its vertices.
+    ///
+    /// Reads [`Self::centers`] if the geometry is cached, otherwise computes the center.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `polygon` is out of bounds.
+    pub fn center(&self, polygon: PolyId) -> Vec3 {
+        if self.has_cached_geometry() {
+            return self.centers[polygon.index()];
+        }
+        self.compute_center(polygon)
+    }
+
+    /// The area of the given polygon projected onto the xz-plane. `[Units: wu²]`
+    ///
+    /// Reads [`Self::surface_areas`] if the geometry is cached, otherwise computes the area.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `polygon` is out of bounds.
+    pub fn surface_area(&self, polygon: PolyId) -> f32 {
+        if self.has_cached_geometry() {
+            return self.surface_areas[polygon.index()];
+        }
+        self.compute_surface_area(polygon)
+    }
+
+    /// Computes the center and area of every polygon and stores them in [`Self::centers`] and [`Self::surface_areas`].
+    ///
+    /// None of the methods of the mesh move vertices or change polygons, so the cache stays valid when e.g.
+    /// areas are remarked with [`Self::remark_area_in_volume`]. After editing [`Self::vertices`] or [`Self::polygons`]
+    /// by hand, call this again or call [`Self::clear_polygon_geometry`] to go back to computing them on demand.
+    pub fn cache_polygon_geometry(&mut self) {
+        let centers = self
+            .polygon_ids()
+            .map(|polygon| self.compute_center(polygon))
+            .collect();
+        let surface_areas = self
+            .polygon_ids()
+            .map(|polygon| self.compute_surface_area(polygon))
+            .collect();
+        self.centers = centers;
+        self.surface_areas = surface_areas;
+    }
+
+    /// Removes the cached [`Self::centers`] and [`Self::surface_areas`].
+    pub fn clear_polygon_geometry(&mut self) {
+        self.centers = Vec::new();
+        self.surface_areas = Vec::new();
+    }
+
+    /// Whether [`Self::centers`] and [`Self::surface_areas`] hold a value for every polygon.
+    /// A cache that does not match the polygon count, e.g. because polygons were added by hand, is ignored.
+    #[inline]
+    pub fn has_cached_geometry(&self) -> bool {
+        let count = self.polygon_count();
+        self.centers.len() == count && self.surface_areas.len() == count
+    }
+
+    fn compute_center(&self, polygon: PolyId) -> Vec3 {
+        let mut sum = Vec3::ZERO;
+        let mut count = 0;
+        for vertex in self.polygon_vertices(polygon) {
+            sum += self.vertex_world(vertex);
+            count += 1;
+        }
+        if count == 0 {
+            return self.aabb.min;
+        }
+        sum / count as f32
+    }
+
+    fn compute_surface_area(&self, polygon: PolyId) -> f32 {
+        let vertices = self
+            .polygon_vertices(polygon)
+            .map(|vertex| self.vertex_world(vertex).xz())
+            .collect::<Vec<_>>();
+        let twice_area: f32 = vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .map(|(a, b)| a.perp_dot(*b))
+            .sum();
+        twice_area.abs() * 0.5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::U16Vec3;
+
+    use super::*;
+    use crate::{Aabb3d, AreaType, RegionId};
+
+    /// A 2x2 square and a right triangle next to it, with 1 wu per cell and the mesh starting at `(10, 0, 10)`.
+    fn mesh() -> PolygonNavmesh {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 1, 0),
+                U16Vec3::new(0, 1, 2),
+                U16Vec3::new(2, 1, 2),
+                U16Vec3::new(2, 1, 0),
+                U16Vec3::new(4, 1, 0),
+            ],
+            polygons: vec![0, 1, 2, 3, 3, 2, 4, N],
+            polygon_neighbors: vec![N, N, 1, N, 0, N, N, N],
+            flags: vec![0; 2],
+            regions: vec![RegionId::from(1); 2],
+            areas: vec![AreaType::DEFAULT_WALKABLE, AreaType(2)],
+            max_vertices_per_polygon: 4,
+            aabb: Aabb3d {
+                min: Vec3::new(10.0, 0.0, 10.0),
+                max: Vec3::new(14.0, 1.0, 12.0),
+            },
+            cell_size: 1.0,
+            cell_height: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cached_geometry_matches_computed() {
+        let mut mesh = mesh();
+        assert!(!mesh.has_cached_geometry());
+        assert_eq!(mesh.center(PolyId(0)), Vec3::new(11.0, 1.0, 11.0));
+        assert_eq!(mesh.surface_area(PolyId(0)), 4.0);
+        assert_eq!(mesh.surface_area(PolyId(1)), 2.0);
+
+        let computed = mesh
+            .polygon_ids()
+            .map(|polygon| (mesh.center(polygon), mesh.surface_area(polygon)))
+            .collect::<Vec<_>>();
+        mesh.cache_polygon_geometry();
+        assert!(mesh.has_cached_geometry());
+        let cached = mesh
+            .polygon_ids()
+            .map(|polygon| (mesh.center(polygon), mesh.surface_area(polygon)))
+            .collect::<Vec<_>>();
+        assert_eq!(cached, computed);
+
+        // Splitting keeps the cache of the polygons that end up in each mesh.
+        let split = mesh.split_by_area();
+        assert!(split.iter().all(|(_, mesh)| mesh.has_cached_geometry()));
+        let (_, triangle) = split.iter().find(|(area, _)| *area == AreaType(2)).unwrap();
+        assert_eq!(triangle.center(PolyId(0)), computed[1].0);
+
+        mesh.clear_polygon_geometry();
+        assert!(mesh.centers.is_empty());
+        assert_eq!(mesh.center(PolyId(1)), computed[1].0);
+    }
+
+    #[test]
+    fn cached_geometry_is_kept_until_recached() {
+        let mut mesh = mesh();
+        mesh.cache_polygon_geometry();
+        // Stretch the square to 2x4 cells by hand, which the cache doesn't know about.
+        mesh.vertices[1].z = 4;
+        mesh.vertices[2].z = 4;
+        assert_eq!(mesh.center(PolyId(0)), Vec3::new(11.0, 1.0, 11.0));
+
+        mesh.cache_polygon_geometry();
+        assert_eq!(mesh.center(PolyId(0)), Vec3::new(11.0, 1.0, 12.0));
+        assert_eq!(mesh.surface_area(PolyId(0)), 8.0);
+
+        // Remarking areas keeps the geometry, and with it the cache.
+        mesh.areas[0] = AreaType(3);
+        assert!(mesh.has_cached_geometry());
+    }
+}
@@ -0,0 +1,160 @@
+//! Contains [`PolygonGraph`], the adjacency of the polygons in a [`PolygonNavmesh`].
+
+use crate::{EdgeConnection, EdgeId, PolyId, PolygonNavmesh};
+
+/// The adjacency of the polygons of a [`PolygonNavmesh`], built with [`PolygonNavmesh::as_graph`].
+///
+/// Each polygon is a node, identified by its index in the polygon mesh.
+/// Two polygons are connected if they share an edge.
+/// Since the adjacency is symmetric, every connection is stored once for each of the two polygons.
+///
+/// Edges that lead to other tiles are not part of the graph.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PolygonGraph {
+    /// `edges[offsets[i]..offsets[i + 1]]` are the edges of polygon `i`.
+    offsets: Vec<u32>,
+    edges: Vec<PolygonGraphEdge>,
+}
+
+/// A connection from a polygon to one of its neighbors in a [`PolygonGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PolygonGraphEdge {
+    /// The neighboring polygon.
+    pub neighbor: PolyId,
+    /// The shared edge, as seen from the source polygon.
+    pub edge: EdgeId,
+}
+
+impl PolygonNavmesh {
+    /// Builds the adjacency graph of the polygons from [`PolygonNavmesh::polygon_neighbors`].
+    pub fn as_graph(&self) -> PolygonGraph {
+        let nvp = self.max_vertices_per_polygon as usize;
+        let polygon_count = self.polygon_count();
+        let mut offsets = Vec::with_capacity(polygon_count + 1);
+        let mut edges = Vec::new();
+        offsets.push(0);
+        for i in 0..polygon_count {
+            let vertices = &self.polygons[i * nvp..][..nvp];
+            let neighbors = &self.polygon_neighbors[i * nvp..][..nvp];
+            for (j, (vertex, neighbor)) in vertices.iter().zip(neighbors).enumerate() {
+                if *vertex == Self::NO_INDEX {
+                    break;
+                }
+                // Also skips portal edges.
+                let EdgeConnection::Polygon(neighbor) = EdgeConnection::from_raw(*neighbor) else {
+                    continue;
+                };
+                edges.push(PolygonGraphEdge {
+                    neighbor,
+                    edge: EdgeId::new(PolyId(i as u16), j as u8),
+                });
+            }
+            offsets.push(edges.len() as u32);
+        }
+        PolygonGraph { offsets, edges }
+    }
+}
+
+impl PolygonGraph {
+    /// The number of nodes, i.e. polygons, in the graph.
+    #[inline]
+    pub fn node_count(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    /// The number of connections between polygons in the graph.
+    /// Each connection is counted once, even though it is stored for both polygons.
+    #[inline]
+    pub fn connection_count(&self) -> usize {
+        self.edges.len() / 2
+    }
+
+    /// The edges of the given polygon.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `polygon` is not a node of the graph.
+    #[inline]
+    pub fn edges(&self, polygon: PolyId) -> &[PolygonGraphEdge] {
+        let i = polygon.index();
+        &self.edges[self.offsets[i] as usize..self.offsets[i + 1] as usize]
+    }
+
+    /// The polygons that share an edge with the given polygon.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `polygon` is not a node of the graph.
+    #[inline]
+    pub fn neighbors(&self, polygon: PolyId) -> impl Iterator<Item = PolyId> {
+        self.edges(polygon).iter().map(|edge| edge.neighbor)
+    }
+
+    /// Converts the graph into an undirected [`petgraph`] graph.
+    /// The node indices are the same as the polygon indices, and each edge is weighted with its [`PolygonGraphEdge`]
+    /// as seen from the polygon with the lower index.
+    #[cfg(feature = "petgraph")]
+    pub fn to_petgraph(&self) -> petgraph::graph::UnGraph<(), PolygonGraphEdge, u32> {
+        let mut graph =
+            petgraph::graph::UnGraph::with_capacity(self.node_count(), self.connection_count());
+        for _ in 0..self.node_count() {
+            graph.add_node(());
+        }
+        for polygon in 0..self.node_count() {
+            let polygon = PolyId(polygon as u16);
+            for edge in self.edges(polygon) {
+                if polygon < edge.neighbor {
+                    graph.add_edge(
+                        petgraph::graph::NodeIndex::new(polygon.index()),
+                        petgraph::graph::NodeIndex::new(edge.neighbor.index()),
+                        *edge,
+                    );
+                }
+            }
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::U16Vec3;
+
+    use super::*;
+
+    /// Two triangles sharing the edge 0->2, and a third triangle with a portal edge.
+    fn mesh() -> PolygonNavmesh {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 0, 0),
+                U16Vec3::new(0, 0, 1),
+                U16Vec3::new(1, 0, 1),
+                U16Vec3::new(1, 0, 0),
+            ],
+            polygons: vec![0, 1, 2, N, 0, 2, 3, N, 1, 2, 3, N],
+            polygon_neighbors: vec![N, N, 1, N, 0, N, 0x8001, N, N, N, N, N],
+            max_vertices_per_polygon: 4,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn graph_contains_shared_edges() {
+        let graph = mesh().as_graph();
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.connection_count(), 1);
+        assert_eq!(
+            graph.neighbors(PolyId(0)).collect::<Vec<_>>(),
+            vec![PolyId(1)]
+        );
+        assert_eq!(
+            graph.edges(PolyId(1)),
+            &[PolygonGraphEdge {
+                neighbor: PolyId(0),
+                edge: EdgeId::new(PolyId(1), 0)
+            }]
+        );
+        assert_eq!(graph.neighbors(PolyId(2)).count(), 0);
+    }
+}
@@ -1,11 +1,11 @@
 use crate::{
-    Aabb3d, AreaType, RegionId,
+    Aabb3d, AreaType, BakedOffMeshConnection, EdgeConnection, EdgeId, PolyId, RegionId, VertexId,
     contours::{ContourSet, RegionVertexId},
     math::{next, prev},
 };
 #[cfg(feature = "bevy_reflect")]
 use bevy_reflect::prelude::*;
-use glam::{U16Vec2, U16Vec3, Vec3Swizzles as _, u16vec3, uvec3};
+use glam::{U16Vec2, U16Vec3, Vec3, Vec3Swizzles as _, u16vec3, uvec3};
 use thiserror::Error;
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -112,12 +112,22 @@ pub struct PolygonNavmesh {
     /// (1, 3, 4, 8) defines a polygon with 4 vertices.
     /// Edge 1->3 is shared with polygon 18.
     /// Edge 4->8 is shared with polygon 21.
-    /// Edges 3->4 and 4->8 are border edges not shared with any other polygon.
+    /// Edges 3->4 and 8->1 are border edges not shared with any other polygon.
     /// ```
+    ///
+    /// Prefer [`Self::polygon_vertices`] over indexing this directly.
     pub polygons: Vec<u16>,
-    /// Contains indices to each edge's connected polygons.
-    /// A value of [`Self::NO_CONNECTION`] indicates no connection for the associated edge.
-    /// (i.e. The edge is a solid border.)
+    /// Contains indices to each edge's connected polygons, laid out like [`Self::polygons`].
+    /// The entry at `i` belongs to the edge starting at vertex `i` of the polygon.
+    ///
+    /// Each entry is one of the following:
+    /// - [`Self::NO_CONNECTION`]: The edge is a solid border.
+    /// - [`EdgeConnection::PORTAL_FLAG`] combined with a side in the lowest four bits: The edge lies on the border of the tile
+    ///   and continues on the neighboring tile on that side. See [`EdgeConnection::Portal`].
+    /// - Anything else: The index of the polygon that shares the edge.
+    ///
+    /// Since portals and polygon indices share the same value range, prefer [`Self::neighbors`] or
+    /// [`Self::edge_connection`] over indexing this directly, as they decode the entries into an [`EdgeConnection`].
     pub polygon_neighbors: Vec<u16>,
     /// The user-defined flags assigned to each polygon.
     pub flags: Vec<u16>,
@@ -140,6 +150,26 @@ pub struct PolygonNavmesh {
     pub border_size: u16,
     /// The max error of the polygon edges in the mesh.
     pub max_edge_error: f32,
+    /// The center of each polygon in world space, i.e. the average of its vertices. [Length: 0 or [Self::polygon_count]]
+    ///
+    /// Only filled if [`NavmeshConfig::cache_polygon_geometry`](crate::NavmeshConfig::cache_polygon_geometry) was enabled for the build,
+    /// or after calling [`Self::cache_polygon_geometry`]. Prefer [`Self::center`], which computes the center if it is not cached.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub centers: Vec<Vec3>,
+    /// The area of each polygon projected onto the xz-plane. [Length: 0 or [Self::polygon_count]] `[Units: wu²]`
+    ///
+    /// Cached alongside [`Self::centers`]. Prefer [`Self::surface_area`], which computes the area if it is not cached.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub surface_areas: Vec<f32>,
+    /// The factor the pathfinding cost of each polygon is multiplied with. [Length: 0 or [Self::polygon_count]]
+    ///
+    /// Only filled if [`NavmeshConfig::slope_cost`](crate::NavmeshConfig::slope_cost) was above zero for the build,
+    /// or after calling [`Self::bake_slope_costs`]. Prefer [`Self::cost`], which is 1 if no costs were baked.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub costs: Vec<f32>,
+    /// The off-mesh connections baked with [`Self::bake_off_mesh_connections`].
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub off_mesh_connections: Vec<BakedOffMeshConnection>,
 }
 
 impl PolygonNavmesh {
@@ -147,7 +177,11 @@ impl PolygonNavmesh {
     /// as each polygon in that collection is represented by [`Self::max_vertices_per_polygon`] vertices.
     #[inline]
     pub fn polygon_count(&self) -> usize {
-        self.polygons.len() / self.max_vertices_per_polygon as usize
+        // A default mesh, e.g. that of an empty tile, has no polygons and no `max_vertices_per_polygon`.
+        self.polygons
+            .len()
+            .checked_div(self.max_vertices_per_polygon as usize)
+            .unwrap_or_default()
     }
 
     /// A value which indicates that a polygon in [`Self::polygons`] has no more vertices starting from this vertex with this value.
@@ -156,11 +190,197 @@ impl PolygonNavmesh {
     /// A value which indicates that an edge of a polygon in [`Self::polygon_neighbors`] has no connection.
     pub const NO_CONNECTION: u16 = 0xffff;
 
-    /// Iterates over all polygons in the mesh.
-    pub fn polygons(&self) -> impl Iterator<Item = impl Iterator<Item = u16>> {
+    /// Iterates over the vertices of all polygons in the mesh.
+    pub fn polygons(&self) -> impl Iterator<Item = impl Iterator<Item = VertexId>> {
         self.polygons
             .chunks_exact(self.max_vertices_per_polygon as usize)
-            .map(|chunk| chunk.iter().take_while(|i| **i != Self::NO_INDEX).copied())
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .take_while(|i| **i != Self::NO_INDEX)
+                    .map(|i| VertexId(*i))
+            })
+    }
+
+    /// Iterates over the IDs of all polygons in the mesh.
+    pub fn polygon_ids(&self) -> impl Iterator<Item = PolyId> + use<> {
+        (0..self.polygon_count() as u16).map(PolyId)
+    }
+
+    /// The vertex with the given ID, in voxel coordinates. Use [`Self::vertex_world`] to get it in world space.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vertex` is out of bounds.
+    #[inline]
+    pub fn vertex(&self, vertex: VertexId) -> U16Vec3 {
+        self.vertices[vertex.index()]
+    }
+
+    /// The vertex with the given ID in world space, converted as described in [`Self::vertices`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vertex` is out of bounds.
+    #[inline]
+    pub fn vertex_world(&self, vertex: VertexId) -> Vec3 {
+        self.aabb.min
+            + self.vertex(vertex).as_vec3()
+                * Vec3::new(self.cell_size, self.cell_height, self.cell_size)
+    }
+
+    /// Iterates over the vertices of the given polygon.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `polygon` is out of bounds.
+    pub fn polygon_vertices(&self, polygon: PolyId) -> impl Iterator<Item = VertexId> {
+        let nvp = self.max_vertices_per_polygon as usize;
+        self.polygons[polygon.index() * nvp..][..nvp]
+            .iter()
+            .take_while(|i| **i != Self::NO_INDEX)
+            .map(|i| VertexId(*i))
+    }
+
+    /// Iterates over the edges of the given polygon.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `polygon` is out of bounds.
+    pub fn edges(&self, polygon: PolyId) -> impl Iterator<Item = EdgeId> + use<> {
+        let count = self.polygon_vertices(polygon).count() as u8;
+        (0..count).map(move |edge| EdgeId::new(polygon, edge))
+    }
+
+    /// The start and end vertex of the given edge.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `edge` is out of bounds.
+    pub fn edge_vertices(&self, edge: EdgeId) -> [VertexId; 2] {
+        let nvp = self.max_vertices_per_polygon as usize;
+        let vertices = &self.polygons[edge.polygon.index() * nvp..][..nvp];
+        let start = edge.edge as usize;
+        let end = if start + 1 >= nvp || vertices[start + 1] == Self::NO_INDEX {
+            0
+        } else {
+            start + 1
+        };
+        [VertexId(vertices[start]), VertexId(vertices[end])]
+    }
+
+    /// What lies on the other side of the given edge.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `edge` is out of bounds.
+    #[inline]
+    pub fn edge_connection(&self, edge: EdgeId) -> EdgeConnection {
+        let nvp = self.max_vertices_per_polygon as usize;
+        EdgeConnection::from_raw(
+            self.polygon_neighbors[edge.polygon.index() * nvp + edge.edge as usize],
+        )
+    }
+
+    /// Iterates over what lies on the other side of each edge of the given polygon, in the order of [`Self::edges`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `polygon` is out of bounds.
+    pub fn neighbors(&self, polygon: PolyId) -> impl Iterator<Item = EdgeConnection> {
+        let nvp = self.max_vertices_per_polygon as usize;
+        let start = polygon.index() * nvp;
+        self.polygons[start..][..nvp]
+            .iter()
+            .zip(&self.polygon_neighbors[start..][..nvp])
+            .take_while(|(vertex, _)| **vertex != Self::NO_INDEX)
+            .map(|(_, neighbor)| EdgeConnection::from_raw(*neighbor))
+    }
+
+    /// Splits the mesh into one mesh per area type, e.g. to render water differently from the ground.
+    /// The meshes are ordered by area type.
+    ///
+    /// Each mesh only contains the vertices used by its own polygons, and the polygons keep their flags and regions.
+    /// Neighbors of the same area type stay connected with remapped indices, while edges shared with polygons
+    /// of other area types become [`Self::NO_CONNECTION`]. Portal edges are kept as they are.
+    /// Cached [`Self::centers`] and [`Self::surface_areas`] are carried over, as are baked [`Self::costs`]
+    /// and [`Self::off_mesh_connections`] between polygons of the same area type.
+    pub fn split_by_area(&self) -> Vec<(AreaType, PolygonNavmesh)> {
+        let nvp = self.max_vertices_per_polygon as usize;
+        let mut area_types = self.areas.clone();
+        area_types.sort_unstable_by_key(|area| area.0);
+        area_types.dedup();
+
+        let mut polygon_map = vec![Self::NO_INDEX; self.polygon_count()];
+        let mut vertex_map = vec![Self::NO_INDEX; self.vertices.len()];
+        let cached_geometry = self.has_cached_geometry();
+        area_types
+            .into_iter()
+            .map(|area| {
+                polygon_map.fill(Self::NO_INDEX);
+                vertex_map.fill(Self::NO_INDEX);
+                let mut mesh = PolygonNavmesh {
+                    max_vertices_per_polygon: self.max_vertices_per_polygon,
+                    aabb: self.aabb,
+                    cell_size: self.cell_size,
+                    cell_height: self.cell_height,
+                    border_size: self.border_size,
+                    max_edge_error: self.max_edge_error,
+                    ..Default::default()
+                };
+                for (i, _) in self.areas.iter().enumerate().filter(|(_, a)| **a == area) {
+                    polygon_map[i] = mesh.areas.len() as u16;
+                    mesh.areas.push(area);
+                    mesh.flags.push(self.flags[i]);
+                    mesh.regions.push(self.regions[i]);
+                    if cached_geometry {
+                        mesh.centers.push(self.centers[i]);
+                        mesh.surface_areas.push(self.surface_areas[i]);
+                    }
+                    if self.has_costs() {
+                        mesh.costs.push(self.costs[i]);
+                    }
+                    for &vertex in &self.polygons[i * nvp..][..nvp] {
+                        if vertex == Self::NO_INDEX {
+                            mesh.polygons.push(Self::NO_INDEX);
+                            continue;
+                        }
+                        let mapped = &mut vertex_map[vertex as usize];
+                        if *mapped == Self::NO_INDEX {
+                            *mapped = mesh.vertices.len() as u16;
+                            mesh.vertices.push(self.vertices[vertex as usize]);
+                        }
+                        mesh.polygons.push(*mapped);
+                    }
+                }
+                for (i, _) in self.areas.iter().enumerate().filter(|(_, a)| **a == area) {
+                    for &neighbor in &self.polygon_neighbors[i * nvp..][..nvp] {
+                        // Also keeps portal edges, which are marked with the highest bit.
+                        let mapped = if neighbor & 0x8000 != 0 {
+                            neighbor
+                        } else if polygon_map[neighbor as usize] != Self::NO_INDEX {
+                            polygon_map[neighbor as usize]
+                        } else {
+                            Self::NO_CONNECTION
+                        };
+                        mesh.polygon_neighbors.push(mapped);
+                    }
+                }
+                // Off-mesh connections are kept if both of their polygons ended up in this mesh.
+                for baked in &self.off_mesh_connections {
+                    let start = polygon_map[baked.start_polygon.index()];
+                    let end = polygon_map[baked.end_polygon.index()];
+                    if start != Self::NO_INDEX && end != Self::NO_INDEX {
+                        mesh.off_mesh_connections.push(BakedOffMeshConnection {
+                            start_polygon: PolyId(start),
+                            end_polygon: PolyId(end),
+                            ..*baked
+                        });
+                    }
+                }
+                (area, mesh)
+            })
+            .collect()
     }
 }
 
@@ -177,6 +397,7 @@ impl From<InternalPolygonNavmesh> for PolygonNavmesh {
         }
         value.vertices.truncate(value.nvertices as usize);
         value.areas.truncate(value.npolys);
+        value.regions.truncate(value.npolys);
         PolygonNavmesh {
             vertices: value.vertices,
             polygons,
@@ -190,6 +411,10 @@ impl From<InternalPolygonNavmesh> for PolygonNavmesh {
             cell_height: value.cell_height,
             border_size: value.border_size,
             max_edge_error: value.max_edge_error,
+            centers: Vec::new(),
+            surface_areas: Vec::new(),
+            costs: Vec::new(),
+            off_mesh_connections: Vec::new(),
         }
     }
 }
@@ -1215,13 +1440,13 @@ fn is_diagonal_internal_or_external(
 const INDEX_MASK: usize = 0x0fffffff;
 
 #[inline]
-fn vequal(a: U16Vec3, b: U16Vec3) -> bool {
+pub(crate) fn vequal(a: U16Vec3, b: U16Vec3) -> bool {
     a.xz() == b.xz()
 }
 
 /// Returns true iff segments ab and cd intersect, properly or improperly.
 #[inline]
-fn intersect(a: U16Vec3, b: U16Vec3, c: U16Vec3, d: U16Vec3) -> bool {
+pub(crate) fn intersect(a: U16Vec3, b: U16Vec3, c: U16Vec3, d: U16Vec3) -> bool {
     if intersect_prop(a, b, c, d) {
         return true;
     }
@@ -1248,12 +1473,12 @@ fn collinear(a: U16Vec3, b: U16Vec3, c: U16Vec3) -> bool {
 /// Returns true iff c is strictly to the left of the directed
 /// line through a to b.
 #[inline]
-fn left(a: U16Vec3, b: U16Vec3, c: U16Vec3) -> bool {
+pub(crate) fn left(a: U16Vec3, b: U16Vec3, c: U16Vec3) -> bool {
     area2(a, b, c) < 0
 }
 
 #[inline]
-fn left_on(a: U16Vec3, b: U16Vec3, c: U16Vec3) -> bool {
+pub(crate) fn left_on(a: U16Vec3, b: U16Vec3, c: U16Vec3) -> bool {
     area2(a, b, c) <= 0
 }
 
@@ -1331,14 +1556,115 @@ fn is_diagonal_internal_or_external_loose(
     true
 }
 
+/// Errors that can occur when building a [`PolygonNavmesh`] with [`ContourSet::into_polygon_mesh`].
 #[derive(Error, Debug)]
 pub enum PolygonNavmeshError {
+    /// Happens when the contours have more vertices than can be indexed.
     #[error("Too many vertices: {actual} > {max}")]
-    TooManyVertices { actual: usize, max: usize },
+    TooManyVertices {
+        /// The number of vertices in the contours
+        actual: usize,
+        /// The maximum number of vertices
+        max: usize,
+    },
+    /// Happens when the contours result in more polygons than can be indexed.
     #[error("Too many polygons: {actual} > {max}")]
-    TooManyPolygons { actual: usize, max: usize },
+    TooManyPolygons {
+        /// The number of polygons
+        actual: usize,
+        /// The maximum number of polygons
+        max: usize,
+    },
+    /// Happens when a contour cannot be triangulated.
     #[error(
         "Invalid contour. This sometimes happens if the contour simplification is too aggressive."
     )]
     InvalidContour,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbors_decode_each_edge() {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        // A quad next to a triangle, with the padding of the triangle holding garbage neighbors.
+        let mesh = PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 0, 0),
+                U16Vec3::new(0, 0, 1),
+                U16Vec3::new(1, 0, 1),
+                U16Vec3::new(1, 0, 0),
+                U16Vec3::new(2, 0, 0),
+            ],
+            polygons: vec![0, 1, 2, 3, 3, 2, 4, N],
+            polygon_neighbors: vec![0x8000, N, 1, N, 0, 0x8001, N, 7],
+            max_vertices_per_polygon: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            mesh.neighbors(PolyId(0)).collect::<Vec<_>>(),
+            vec![
+                EdgeConnection::Portal(0),
+                EdgeConnection::Border,
+                EdgeConnection::Polygon(PolyId(1)),
+                EdgeConnection::Border,
+            ]
+        );
+        assert_eq!(
+            mesh.neighbors(PolyId(1)).collect::<Vec<_>>(),
+            vec![
+                EdgeConnection::Polygon(PolyId(0)),
+                EdgeConnection::Portal(1),
+                EdgeConnection::Border,
+            ]
+        );
+        for polygon in mesh.polygon_ids() {
+            assert!(
+                mesh.edges(polygon)
+                    .map(|edge| mesh.edge_connection(edge))
+                    .eq(mesh.neighbors(polygon))
+            );
+        }
+    }
+
+    #[test]
+    fn split_by_area_remaps_indices() {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        let water = AreaType(1);
+        // Three triangles in a row: ground, water, water.
+        let mesh = PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 0, 0),
+                U16Vec3::new(0, 0, 1),
+                U16Vec3::new(1, 0, 0),
+                U16Vec3::new(1, 0, 1),
+                U16Vec3::new(2, 0, 0),
+            ],
+            polygons: vec![0, 1, 2, 1, 3, 2, 2, 3, 4],
+            polygon_neighbors: vec![N, 1, N, N, 2, 0, 1, N, 0x8002],
+            flags: vec![0, 1, 2],
+            regions: vec![RegionId::NONE; 3],
+            areas: vec![AreaType::DEFAULT_WALKABLE, water, water],
+            max_vertices_per_polygon: 3,
+            ..Default::default()
+        };
+
+        let split = mesh.split_by_area();
+        assert_eq!(split.len(), 2);
+
+        let (area, water_mesh) = &split[0];
+        assert_eq!(*area, water);
+        assert_eq!(water_mesh.polygon_count(), 2);
+        assert_eq!(water_mesh.vertices.len(), 4);
+        assert_eq!(water_mesh.polygons, vec![0, 1, 2, 2, 1, 3]);
+        assert_eq!(water_mesh.polygon_neighbors, vec![N, 1, N, 0, N, 0x8002]);
+        assert_eq!(water_mesh.flags, vec![1, 2]);
+
+        let (area, ground_mesh) = &split[1];
+        assert_eq!(*area, AreaType::DEFAULT_WALKABLE);
+        assert_eq!(ground_mesh.polygons, vec![0, 1, 2]);
+        assert_eq!(ground_mesh.polygon_neighbors, vec![N, N, N]);
+    }
+}
@@ -1,9 +1,47 @@
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+
 use crate::{
     heightfield::Heightfield,
     math::{dir_offset_x, dir_offset_z},
     span::{AreaType, Span},
 };
 
+/// How [`Heightfield::filter_ledge_spans_with`] decides whether a span is on a slope too steep to walk.
+///
+/// Spans next to a drop of more than `walkable_climb` are filtered by all methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum LedgeFilter {
+    /// A direct port of the C++ implementation, which filters a span if the floors of its traversable neighbors
+    /// differ by more than `walkable_climb`.
+    ///
+    /// On a ramp, the neighbors in front of and behind a span differ by twice the rise per cell. If that is a fraction
+    /// of a voxel more than `walkable_climb`, rounding the floors to a coarse `cell_height` makes the difference
+    /// `walkable_climb` for some spans and one more for others, so only some spans of the ramp are filtered, leaving holes.
+    #[default]
+    Reference,
+    /// Only filters a span the [`Reference`](Self::Reference) method would filter if the floor also rises by at least
+    /// `walkable_climb + 1` over two cells on average along the x- or z-axis. Such slopes are filtered everywhere,
+    /// while the ramps in between are kept as a whole. Since `walkable_climb` is rounded down from the climb of the agent,
+    /// these ramps are usually within what the agent can climb anyway.
+    ///
+    /// The average is taken over up to `window` cells on both sides of the span, following the floor the agent could walk on.
+    /// Larger windows smooth out the rounding of the floors better, but also let short, steep bumps on otherwise
+    /// flat ground through. A window of 2 to 4 cells works well for most ramps.
+    RampAware {
+        /// The number of cells on each side of a span to average the slope over. `[Limit: >0] [Units: vx]`
+        ///
+        /// A window of 0 is treated as 1, as no slope could be measured over it.
+        window: u16,
+    },
+}
+
 impl Heightfield {
     /// Adds the walkable flag to spans which are adjacent to a walkable span and the height difference is small enough for the agent to walk over.
     pub fn filter_low_hanging_walkable_obstacles(&mut self, walkable_climb: u16) {
@@ -42,6 +80,16 @@ impl Heightfield {
 
     /// Removes the walkable flag from spans which are adjacent to a ledge.
     pub fn filter_ledge_spans(&mut self, walkable_height: u16, walkable_climb: u16) {
+        self.filter_ledge_spans_with(walkable_height, walkable_climb, LedgeFilter::Reference);
+    }
+
+    /// Removes the walkable flag from spans which are adjacent to a ledge, using the given method to detect steep slopes.
+    pub fn filter_ledge_spans_with(
+        &mut self,
+        walkable_height: u16,
+        walkable_climb: u16,
+        method: LedgeFilter,
+    ) {
         // Mark spans that are adjacent to a ledge as unwalkable..
         for z in 0..self.height {
             for x in 0..self.width {
@@ -146,8 +194,25 @@ impl Heightfield {
                             true
                         } else {
                             // If the difference between all neighbor floors is too large, this is a steep slope, so mark the span as an unwalkable ledge.
-                            highest_traversable_neighbor_floor - lowest_traversable_neighbor_floor
-                                > walkable_climb as i32
+                            let steep = highest_traversable_neighbor_floor
+                                - lowest_traversable_neighbor_floor
+                                > walkable_climb as i32;
+                            match method {
+                                LedgeFilter::Reference => steep,
+                                // Not in the original. The neighbors only tell the slope over two cells,
+                                // which is too short to tell a ramp from the rounding of its floors, so we take a longer look.
+                                LedgeFilter::RampAware { window } => {
+                                    steep
+                                        && self.is_steep_slope(
+                                            x,
+                                            z,
+                                            (floor, ceiling),
+                                            walkable_height,
+                                            walkable_climb,
+                                            window.max(1),
+                                        )
+                                }
+                            }
                         }
                     };
                     let span = self.span_mut(current_span_key);
@@ -159,6 +224,79 @@ impl Heightfield {
         }
     }
 
+    /// Whether the floor of the span with the given floor and ceiling in the column at (`x`, `z`) rises by at least
+    /// `walkable_climb + 1` over two cells on average over up to `window` cells on both sides of it, along the x- or z-axis.
+    fn is_steep_slope(
+        &self,
+        x: u16,
+        z: u16,
+        gap: (i32, i32),
+        walkable_height: u16,
+        walkable_climb: u16,
+        window: u16,
+    ) -> bool {
+        // Directions 0 and 2 run along the x-axis, 1 and 3 along the z-axis.
+        [(0, 2), (1, 3)].into_iter().any(|(backward, forward)| {
+            let (backward_floor, backward_steps) =
+                self.follow_floor(x, z, gap, backward, walkable_height, walkable_climb, window);
+            let (forward_floor, forward_steps) =
+                self.follow_floor(x, z, gap, forward, walkable_height, walkable_climb, window);
+            let steps = (backward_steps + forward_steps) as i32;
+            steps > 0
+                && (forward_floor - backward_floor).abs() * 2 >= (walkable_climb as i32 + 1) * steps
+        })
+    }
+
+    /// Walks up to `window` cells from the column at (`x`, `z`) in the given direction, always stepping onto the
+    /// neighboring floor the agent could walk to from the current one.
+    ///
+    /// Returns the last floor reached and the number of cells walked.
+    fn follow_floor(
+        &self,
+        mut x: u16,
+        mut z: u16,
+        (mut floor, mut ceiling): (i32, i32),
+        direction: u8,
+        walkable_height: u16,
+        walkable_climb: u16,
+        window: u16,
+    ) -> (i32, u16) {
+        let mut steps = 0;
+        while steps < window {
+            let neighbor_x = x as i32 + dir_offset_x(direction) as i32;
+            let neighbor_z = z as i32 + dir_offset_z(direction) as i32;
+            if !self.contains(neighbor_x, neighbor_z) {
+                break;
+            }
+            x = neighbor_x as u16;
+            z = neighbor_z as u16;
+
+            let mut neighbor_span = self.span_at(x, z);
+            let mut next_gap = None;
+            while let Some(span) = neighbor_span {
+                let neighbor_floor = span.max as i32;
+                let neighbor_ceiling = span
+                    .next
+                    .map(|key| self.span(key).min as i32)
+                    .unwrap_or(Self::MAX_HEIGHTFIELD_HEIGHT as i32);
+                if ceiling.min(neighbor_ceiling) - floor.max(neighbor_floor)
+                    >= walkable_height as i32
+                    && (neighbor_floor - floor).abs() <= walkable_climb as i32
+                {
+                    next_gap = Some((neighbor_floor, neighbor_ceiling));
+                    break;
+                }
+                neighbor_span = span.next.map(|key| self.span(key));
+            }
+            let Some(next_gap) = next_gap else {
+                break;
+            };
+            (floor, ceiling) = next_gap;
+            steps += 1;
+        }
+        (floor, steps)
+    }
+
     /// Taken 1:1 from the original implementation.
     const MAX_HEIGHTFIELD_HEIGHT: u16 = u16::MAX;
 
@@ -1,14 +1,16 @@
 //! Contains methods for rasterizing triangles of a [`TrimeshedCollider`] into a [`Heightfield`].
 
 use glam::Vec3A;
-use std::fmt::Display;
+use std::{fmt::Display, ops::Range};
 use thiserror::Error;
 
+#[cfg(feature = "rayon")]
+use crate::span::{SpanKey, Spans};
 use crate::{
-    TriMesh,
+    Aabb3d, TriMesh,
     heightfield::{Heightfield, SpanInsertion, SpanInsertionError},
     math::TriangleVertices as _,
-    span::{AreaType, Span, SpanBuilder},
+    span::{AreaMergePolicy, AreaType, Span, SpanBuilder},
 };
 
 impl Heightfield {
@@ -18,6 +20,29 @@ impl Heightfield {
         trimesh: &TriMesh,
         walkable_climb: u16,
     ) -> Result<(), RasterizationError> {
+        self.rasterize_triangles_with_merge_policy(
+            trimesh,
+            walkable_climb,
+            AreaMergePolicy::default(),
+        )
+    }
+
+    /// Rasterizes the triangles of a [`TriMesh`] into a [`Heightfield`],
+    /// resolving the area types of overlapping spans with the given [`AreaMergePolicy`].
+    ///
+    /// With the `rayon` feature, large meshes are rasterized in parallel.
+    /// The result is the same as when rasterizing the triangles one after another.
+    pub fn rasterize_triangles_with_merge_policy(
+        &mut self,
+        trimesh: &TriMesh,
+        walkable_climb: u16,
+        merge_policy: AreaMergePolicy,
+    ) -> Result<(), RasterizationError> {
+        #[cfg(feature = "rayon")]
+        if trimesh.indices.len() >= Self::MIN_PARALLEL_TRIANGLES && rayon::current_num_threads() > 1
+        {
+            return self.rasterize_triangles_in_stripes(trimesh, walkable_climb, merge_policy);
+        }
         for (i, triangle) in trimesh.indices.iter().enumerate() {
             let triangle = [
                 trimesh.vertices[triangle[0] as usize],
@@ -25,17 +50,377 @@ impl Heightfield {
                 trimesh.vertices[triangle[2] as usize],
             ];
             let area_type = trimesh.area_types[i];
-            self.rasterize_triangle(triangle, area_type, walkable_climb)?;
+            self.rasterize_triangle_with_merge_policy(
+                triangle,
+                area_type,
+                walkable_climb,
+                merge_policy,
+            )?;
         }
         Ok(())
     }
 
+    /// The number of triangles from which on [`Heightfield::rasterize_triangles`] runs in parallel.
+    #[cfg(feature = "rayon")]
+    const MIN_PARALLEL_TRIANGLES: usize = 4096;
+
+    /// How many stripes of rows each thread gets, so that threads with stripes of cheap geometry can help out with the rest.
+    #[cfg(feature = "rayon")]
+    const STRIPES_PER_THREAD: usize = 4;
+
+    /// Splits the heightfield into stripes of rows along the z-axis and rasterizes the triangles into each stripe in parallel.
+    ///
+    /// Every stripe has its own span storage and inserts the spans of the triangles overlapping it in the original order,
+    /// so the spans of every column are merged exactly like when rasterizing sequentially.
+    /// Afterwards, the stripes are stitched back together in column order.
+    #[cfg(feature = "rayon")]
+    fn rasterize_triangles_in_stripes(
+        &mut self,
+        trimesh: &TriMesh,
+        walkable_climb: u16,
+        merge_policy: AreaMergePolicy,
+    ) -> Result<(), RasterizationError> {
+        use rayon::prelude::*;
+
+        let grid = RasterGrid::from(&*self);
+        let width = self.width as usize;
+        let stripe_count = (rayon::current_num_threads() * Self::STRIPES_PER_THREAD)
+            .clamp(1, (self.height as usize).max(1));
+        let rows_per_stripe = (self.height as usize).div_ceil(stripe_count).max(1) as u16;
+
+        let triangles = trimesh
+            .indices
+            .par_iter()
+            .zip(&trimesh.area_types)
+            .map(|(indices, area)| {
+                let triangle = indices.to_array().map(|i| trimesh.vertices[i as usize]);
+                (triangle, *area, grid.touched_rows(&triangle))
+            })
+            .collect::<Vec<_>>();
+
+        let stripes = (0..self.height)
+            .step_by(rows_per_stripe as usize)
+            .map(|start| start..start.saturating_add(rows_per_stripe).min(self.height))
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|rows| {
+                let mut stripe = self.stripe(rows.clone());
+                for (triangle, area, touched) in &triangles {
+                    if touched.start >= rows.end || touched.end <= rows.start {
+                        continue;
+                    }
+                    grid.rasterize_triangle_rows(*triangle, rows.clone(), |x, z, min, max| {
+                        stripe.add_span(SpanInsertion {
+                            x,
+                            z: z - rows.start,
+                            span: SpanBuilder {
+                                min,
+                                max,
+                                area: *area,
+                                next: None,
+                            }
+                            .build(),
+                            flag_merge_threshold: walkable_climb,
+                            merge_policy,
+                        })
+                    })?;
+                }
+                Ok((rows, stripe))
+            })
+            .collect::<Result<Vec<_>, RasterizationError>>()?;
+
+        let span_count = stripes
+            .iter()
+            .map(|(_, stripe)| stripe.allocated_spans.len())
+            .sum();
+        let mut spans = Spans::with_min_capacity(span_count);
+        for (rows, stripe) in stripes {
+            let offset = rows.start as usize * width;
+            for (i, column) in stripe.spans.iter().enumerate() {
+                self.spans[offset + i] = copy_column(&stripe.allocated_spans, *column, &mut spans);
+            }
+        }
+        self.allocated_spans = spans;
+        Ok(())
+    }
+
+    /// A heightfield holding only the given rows of this one, including the spans they already contain.
+    /// Only meant for inserting spans, as its AABB still covers all rows.
+    #[cfg(feature = "rayon")]
+    fn stripe(&self, rows: Range<u16>) -> Self {
+        let width = self.width as usize;
+        let columns = &self.spans[rows.start as usize * width..rows.end as usize * width];
+        let mut allocated_spans = Spans::default();
+        let spans = columns
+            .iter()
+            .map(|column| copy_column(&self.allocated_spans, *column, &mut allocated_spans))
+            .collect();
+        Self {
+            width: self.width,
+            height: rows.end - rows.start,
+            aabb: self.aabb,
+            cell_size: self.cell_size,
+            cell_height: self.cell_height,
+            spans,
+            allocated_spans,
+        }
+    }
+
     /// Rasterizes a triangle into a [`Heightfield`].
     pub fn rasterize_triangle(
         &mut self,
         triangle: [Vec3A; 3],
         area_type: AreaType,
         flag_merge_threshold: u16,
+    ) -> Result<(), RasterizationError> {
+        self.rasterize_triangle_with_merge_policy(
+            triangle,
+            area_type,
+            flag_merge_threshold,
+            AreaMergePolicy::default(),
+        )
+    }
+
+    /// Rasterizes a triangle into a [`Heightfield`],
+    /// resolving the area types of overlapping spans with the given [`AreaMergePolicy`].
+    pub fn rasterize_triangle_with_merge_policy(
+        &mut self,
+        triangle: [Vec3A; 3],
+        area_type: AreaType,
+        flag_merge_threshold: u16,
+        merge_policy: AreaMergePolicy,
+    ) -> Result<(), RasterizationError> {
+        let grid = RasterGrid::from(&*self);
+        grid.rasterize_triangle(triangle, |x, z, min, max| {
+            self.add_span(SpanInsertion {
+                x,
+                z,
+                span: SpanBuilder {
+                    min,
+                    max,
+                    area: area_type,
+                    next: None,
+                }
+                .build(),
+                flag_merge_threshold,
+                merge_policy,
+            })
+        })
+    }
+
+    /// Rasterizes the triangles of a [`TriMesh`] without inserting them into this [`Heightfield`].
+    ///
+    /// The returned [`RecordedSpans`] can be inserted later with [`Heightfield::replay_spans`]
+    /// into any heightfield with the same layout, which gives the same result as rasterizing the triangles into it.
+    /// This allows caching the rasterization of geometry that did not change between builds.
+    pub fn record_triangles(&self, trimesh: &TriMesh) -> Result<RecordedSpans, RasterizationError> {
+        let grid = RasterGrid::from(self);
+        let mut recorded = RecordedSpans::new(self);
+        for (i, triangle) in trimesh.indices.iter().enumerate() {
+            let triangle = [
+                trimesh.vertices[triangle[0] as usize],
+                trimesh.vertices[triangle[1] as usize],
+                trimesh.vertices[triangle[2] as usize],
+            ];
+            let area = trimesh.area_types[i];
+            grid.rasterize_triangle(triangle, |x, z, min, max| {
+                recorded.push(x, z, min, max, area);
+                Ok(())
+            })?;
+        }
+        Ok(recorded)
+    }
+
+    /// Inserts spans that were previously recorded with [`Heightfield::record_triangles`].
+    pub fn replay_spans(
+        &mut self,
+        recorded: &RecordedSpans,
+        flag_merge_threshold: u16,
+        merge_policy: AreaMergePolicy,
+    ) -> Result<(), RasterizationError> {
+        if !recorded.matches(self) {
+            return Err(RasterizationError::LayoutMismatch);
+        }
+        for span in &recorded.spans {
+            self.add_span(SpanInsertion {
+                x: span.x,
+                z: span.z,
+                span: SpanBuilder {
+                    min: span.min,
+                    max: span.max,
+                    area: span.area,
+                    next: None,
+                }
+                .build(),
+                flag_merge_threshold,
+                merge_policy,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Merges the spans of `other` into this heightfield column by column,
+    /// as if they had been rasterized into this heightfield directly.
+    ///
+    /// This allows rasterizing chunks of geometry into separate heightfields, e.g. in parallel or while streaming,
+    /// and cheaply combining them afterwards. Both heightfields must have been built with the same [`HeightfieldBuilder`](crate::HeightfieldBuilder) settings.
+    ///
+    /// Note that area types are merged per span of `other` rather than per triangle, so when spans of different area types
+    /// overlap within `other` itself, the result can differ slightly from rasterizing everything into one heightfield.
+    pub fn merge(
+        &mut self,
+        other: &Heightfield,
+        flag_merge_threshold: u16,
+    ) -> Result<(), RasterizationError> {
+        self.merge_with_merge_policy(other, flag_merge_threshold, AreaMergePolicy::default())
+    }
+
+    /// Same as [`Heightfield::merge`], but uses the given [`AreaMergePolicy`] when merging overlapping spans.
+    pub fn merge_with_merge_policy(
+        &mut self,
+        other: &Heightfield,
+        flag_merge_threshold: u16,
+        merge_policy: AreaMergePolicy,
+    ) -> Result<(), RasterizationError> {
+        if self.width != other.width
+            || self.height != other.height
+            || self.aabb != other.aabb
+            || self.cell_size != other.cell_size
+            || self.cell_height != other.cell_height
+        {
+            return Err(RasterizationError::LayoutMismatch);
+        }
+        for z in 0..other.height {
+            for x in 0..other.width {
+                let mut span_key = other.span_key_at(x, z);
+                while let Some(key) = span_key {
+                    let span = other.span(key);
+                    self.add_span(SpanInsertion {
+                        x,
+                        z,
+                        span: SpanBuilder {
+                            min: span.min,
+                            max: span.max,
+                            area: span.area,
+                            next: None,
+                        }
+                        .build(),
+                        flag_merge_threshold,
+                        merge_policy,
+                    })?;
+                    span_key = span.next;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Spans produced by [`Heightfield::record_triangles`], ready to be inserted with [`Heightfield::replay_spans`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedSpans {
+    aabb: Aabb3d,
+    cell_size: f32,
+    cell_height: f32,
+    spans: Vec<RecordedSpan>,
+}
+
+impl RecordedSpans {
+    /// Creates an empty recording for heightfields with the same layout as `heightfield`.
+    ///
+    /// Together with [`RecordedSpans::push`], this allows rasterizing geometry with a custom rasterizer, e.g. on the GPU,
+    /// and inserting the result with [`Heightfield::replay_spans`] like spans from [`Heightfield::record_triangles`].
+    pub fn new(heightfield: &Heightfield) -> Self {
+        Self {
+            aabb: heightfield.aabb,
+            cell_size: heightfield.cell_size,
+            cell_height: heightfield.cell_height,
+            spans: Vec::new(),
+        }
+    }
+
+    /// Records the insertion of a span from `min` to `max` into the column at `x` and `z`. `[Units: vx]`
+    ///
+    /// Spans are replayed in the order they were pushed. As the area types of overlapping spans are merged in insertion order,
+    /// push them in the order of the triangles they belong to for the same result as [`Heightfield::rasterize_triangles`].
+    pub fn push(&mut self, x: u16, z: u16, min: u16, max: u16, area: AreaType) {
+        self.spans.push(RecordedSpan {
+            x,
+            z,
+            min,
+            max,
+            area,
+        });
+    }
+
+    /// Returns whether these spans were recorded for a heightfield with the same layout as `heightfield`.
+    pub fn matches(&self, heightfield: &Heightfield) -> bool {
+        self.aabb == heightfield.aabb
+            && self.cell_size == heightfield.cell_size
+            && self.cell_height == heightfield.cell_height
+    }
+
+    /// The number of recorded span insertions.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns whether no spans were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RecordedSpan {
+    x: u16,
+    z: u16,
+    min: u16,
+    max: u16,
+    area: AreaType,
+}
+
+/// The parts of a [`Heightfield`] needed to compute which spans a triangle covers.
+#[derive(Clone, Copy)]
+struct RasterGrid {
+    width: u16,
+    height: u16,
+    aabb: Aabb3d,
+    cell_size: f32,
+    cell_height: f32,
+}
+
+impl From<&Heightfield> for RasterGrid {
+    fn from(heightfield: &Heightfield) -> Self {
+        Self {
+            width: heightfield.width,
+            height: heightfield.height,
+            aabb: heightfield.aabb,
+            cell_size: heightfield.cell_size,
+            cell_height: heightfield.cell_height,
+        }
+    }
+}
+
+impl RasterGrid {
+    /// Calls `insert_span` with the column and the snapped min and max height of every span covered by the triangle.
+    fn rasterize_triangle(
+        &self,
+        triangle: [Vec3A; 3],
+        insert_span: impl FnMut(u16, u16, u16, u16) -> Result<(), SpanInsertionError>,
+    ) -> Result<(), RasterizationError> {
+        self.rasterize_triangle_rows(triangle, 0..self.height, insert_span)
+    }
+
+    /// Same as [`RasterGrid::rasterize_triangle`], but only calls `insert_span` for the spans within `rows`.
+    ///
+    /// The triangle is still divided along all rows before `rows`, so that the spans within them are exactly
+    /// the same as when rasterizing all rows.
+    fn rasterize_triangle_rows(
+        &self,
+        triangle: [Vec3A; 3],
+        rows: Range<u16>,
+        mut insert_span: impl FnMut(u16, u16, u16, u16) -> Result<(), SpanInsertionError>,
     ) -> Result<(), RasterizationError> {
         let aabb = triangle.aabb();
         // If the triangle does not touch the bounding box of the heightfield, skip the triangle.
@@ -51,16 +436,6 @@ impl Heightfield {
         // The height of the heightfield AABB
         let by = self.aabb.max[1] - self.aabb.min[1];
 
-        // Calculate the footprint of the triangle on the grid's z-axis
-        // z0 is the first z cell that the triangle touches
-        // z1 is the last z cell that the triangle touches
-        let z0 = ((aabb.min[2] - self.aabb.min[2]) * inverse_cell_size) as i16;
-        let z1 = ((aabb.max[2] - self.aabb.min[2]) * inverse_cell_size) as i16;
-
-        // use -1 rather than 0 to cut the polygon properly at the start of the tile
-        let z0 = z0.clamp(-1, h - 1);
-        let z1 = z1.clamp(0, h - 1);
-
         // Clip the triangle into all grid cells it touches.
         const MAX_VERTICES_AFTER_CLIPPING: usize = 7;
         // x-min, x-max, z-min, z-max
@@ -80,6 +455,35 @@ impl Heightfield {
         let mut nv_row = 0_u8;
         let mut nv_in = 3_u8;
 
+        // The original only clips against the bounds of the grid as part of the per-row and per-column divisions.
+        // For triangles much larger than the heightfield, e.g. when baking a small tile of a large map,
+        // we clip them against the grid bounds once up front instead, so that the per-row and per-column divisions
+        // only deal with the part of the triangle that can produce spans.
+        // Triangles within the grid bounds are not touched, so they are rasterized exactly like in the original.
+        let (min_z, max_z) = if self.contains_xz(&aabb) {
+            (aabb.min.z, aabb.max.z)
+        } else {
+            nv_in = self.clip_to_grid(in_tri, nv_in, p1, p2)?;
+            if nv_in < 3 {
+                return Ok(());
+            }
+            in_tri[..nv_in as usize]
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(min, max), v| {
+                    (min.min(v.z), max.max(v.z))
+                })
+        };
+
+        // Calculate the footprint of the triangle on the grid's z-axis
+        // z0 is the first z cell that the triangle touches
+        // z1 is the last z cell that the triangle touches
+        let z0 = ((min_z - self.aabb.min[2]) * inverse_cell_size) as i16;
+        let z1 = ((max_z - self.aabb.min[2]) * inverse_cell_size) as i16;
+
+        // use -1 rather than 0 to cut the polygon properly at the start of the tile
+        let z0 = z0.clamp(-1, h - 1);
+        let z1 = z1.clamp(0, h - 1).min(rows.end as i16 - 1);
+
         for z in z0..=z1 {
             // Clip polygon to row. Store the remaining polygon as well
             let cell_z = self.aabb.min[2] + z as f32 * self.cell_size;
@@ -95,7 +499,7 @@ impl Heightfield {
             )?;
             std::mem::swap(&mut in_tri, &mut p1);
 
-            if nv_row < 3 || z < 0 {
+            if nv_row < 3 || z < rows.start as i16 {
                 continue;
             }
 
@@ -162,22 +566,115 @@ impl Heightfield {
                     .clamp(span_min_cell_index as i32 + 1, Span::MAX_HEIGHT as i32)
                     as u16;
 
-                self.add_span(SpanInsertion {
-                    x: x as u16,
-                    z: z as u16,
-                    span: SpanBuilder {
-                        min: span_min_cell_index,
-                        max: span_max_cell_index,
-                        area: area_type,
-                        next: None,
-                    }
-                    .build(),
-                    flag_merge_threshold,
-                })?;
+                insert_span(x as u16, z as u16, span_min_cell_index, span_max_cell_index)?;
             }
         }
         Ok(())
     }
+
+    /// The rows that [`RasterGrid::rasterize_triangle`] can produce spans in for the triangle. Errs on the side of too many rows.
+    #[cfg(feature = "rayon")]
+    fn touched_rows(&self, triangle: &[Vec3A; 3]) -> Range<u16> {
+        let aabb = triangle.aabb();
+        let row = |z: f32| ((z - self.aabb.min.z) / self.cell_size).floor();
+        let start = (row(aabb.min.z) - 1.0).clamp(0.0, self.height as f32) as u16;
+        let end = (row(aabb.max.z) + 2.0).clamp(0.0, self.height as f32) as u16;
+        start..end
+    }
+
+    /// The maximum x and z coordinates covered by the cells of the grid.
+    /// These can lie beyond the maximum of the AABB, as the grid is rounded up to whole cells.
+    fn max_xz(&self) -> (f32, f32) {
+        (
+            self.aabb.min.x + self.width as f32 * self.cell_size,
+            self.aabb.min.z + self.height as f32 * self.cell_size,
+        )
+    }
+
+    /// Whether the given AABB lies within the cells of the grid on the xz-plane.
+    fn contains_xz(&self, aabb: &Aabb3d) -> bool {
+        let (max_x, max_z) = self.max_xz();
+        aabb.min.x >= self.aabb.min.x
+            && aabb.min.z >= self.aabb.min.z
+            && aabb.max.x <= max_x
+            && aabb.max.z <= max_z
+    }
+
+    /// Clips the convex polygon in `verts` against the bounds of the grid on the xz-plane, in place.
+    /// `scratch_1` and `scratch_2` are used as intermediate buffers. Returns the new vertex count.
+    fn clip_to_grid(
+        &self,
+        verts: &mut [Vec3A],
+        mut vert_count: u8,
+        scratch_1: &mut [Vec3A],
+        scratch_2: &mut [Vec3A],
+    ) -> Result<u8, PolygonDivisionError> {
+        let (max_x, max_z) = self.max_xz();
+        // The offset of each bound, and whether the part of the polygon to keep lies below it.
+        let bounds = [
+            (self.aabb.min.x, false, DivisionAxis::X),
+            (max_x, true, DivisionAxis::X),
+            (self.aabb.min.z, false, DivisionAxis::Z),
+            (max_z, true, DivisionAxis::Z),
+        ];
+        for (offset, keep_below, axis) in bounds {
+            let axis_index = axis as usize;
+            let verts_slice = &verts[..vert_count as usize];
+            let crosses = if keep_below {
+                verts_slice.iter().any(|v| v[axis_index] > offset)
+            } else {
+                verts_slice.iter().any(|v| v[axis_index] < offset)
+            };
+            if !crosses {
+                continue;
+            }
+            let (mut below_count, mut above_count) = (0, 0);
+            divide_poly(
+                verts,
+                vert_count,
+                scratch_1,
+                &mut below_count,
+                scratch_2,
+                &mut above_count,
+                offset,
+                axis,
+            )?;
+            let (kept, kept_count) = if keep_below {
+                (&*scratch_1, below_count)
+            } else {
+                (&*scratch_2, above_count)
+            };
+            verts[..kept_count as usize].copy_from_slice(&kept[..kept_count as usize]);
+            vert_count = kept_count;
+            if vert_count < 3 {
+                break;
+            }
+        }
+        Ok(vert_count)
+    }
+}
+
+/// Copies the spans of the column starting at `head` from `from` into `into`, keeping their order.
+/// Returns the key of the lowest copied span.
+#[cfg(feature = "rayon")]
+fn copy_column(from: &Spans, head: Option<SpanKey>, into: &mut Spans) -> Option<SpanKey> {
+    let mut copied_head = None;
+    let mut previous: Option<SpanKey> = None;
+    let mut key = head;
+    while let Some(current) = key {
+        let span = &from[current];
+        key = span.next;
+        let copied = into.insert(Span {
+            next: None,
+            ..span.clone()
+        });
+        match previous {
+            Some(previous) => into[previous].next = Some(copied),
+            None => copied_head = Some(copied),
+        }
+        previous = Some(copied);
+    }
+    copied_head
 }
 
 /// Errors that can occur when rasterizing a triangle into a heightfield with [`Heightfield::populate_from_trimesh`].
@@ -189,6 +686,10 @@ pub enum RasterizationError {
     /// Happens when the span insertion fails.
     #[error("Failed to add span: {0}")]
     SpanInsertionError(#[from] SpanInsertionError),
+    /// Happens when replaying [`RecordedSpans`] into a heightfield with a different layout than they were recorded for,
+    /// or when merging two heightfields with different layouts.
+    #[error("Spans do not match the layout of the heightfield")]
+    LayoutMismatch,
 }
 
 /// Divides a convex polygon of max 12 vertices into two convex polygons
@@ -207,6 +708,10 @@ fn divide_poly(
     if in_vert_count > 12 {
         return Err(PolygonDivisionError::TooManyVertices(in_vert_count));
     } else if in_vert_count == 0 {
+        // The original has no early return here, so it always writes the counts.
+        // They must be reset, otherwise the caller keeps using the stale vertices of the previous division.
+        *out_vert_count_1 = 0;
+        *out_vert_count_2 = 0;
         return Ok(());
     }
     let in_vert_count = in_vert_count as usize;
@@ -278,7 +783,7 @@ pub enum PolygonDivisionError {
     NoVertices,
 }
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, Copy)]
 enum DivisionAxis {
     X = 0,
     Z = 2,
@@ -289,3 +794,181 @@ impl Display for DivisionAxis {
         write!(f, "{self:?}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3A};
+
+    use super::*;
+    use crate::HeightfieldBuilder;
+
+    fn heightfield() -> Heightfield {
+        HeightfieldBuilder {
+            aabb: Aabb3d::new(Vec3A::ZERO, [5.0, 5.0, 5.0]),
+            cell_size: 0.5,
+            cell_height: 0.25,
+        }
+        .build()
+        .unwrap()
+    }
+
+    fn trimesh() -> TriMesh {
+        TriMesh {
+            vertices: vec![
+                Vec3A::new(-4.0, 0.0, -4.0),
+                Vec3A::new(-4.0, 0.0, 4.0),
+                Vec3A::new(4.0, 1.0, 4.0),
+                Vec3A::new(4.0, 0.2, -4.0),
+            ],
+            indices: vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+            area_types: vec![AreaType(3), AreaType::DEFAULT_WALKABLE],
+        }
+    }
+
+    fn columns(heightfield: &Heightfield) -> Vec<Vec<(u16, u16, AreaType)>> {
+        heightfield
+            .spans
+            .iter()
+            .map(|column| {
+                let mut spans = Vec::new();
+                let mut key = *column;
+                while let Some(span_key) = key {
+                    let span = heightfield.span(span_key);
+                    spans.push((span.min, span.max, span.area));
+                    key = span.next;
+                }
+                spans
+            })
+            .collect()
+    }
+
+    #[test]
+    fn replaying_recorded_spans_matches_rasterization() {
+        let mut rasterized = heightfield();
+        rasterized.rasterize_triangles(&trimesh(), 2).unwrap();
+
+        let mut replayed = heightfield();
+        let recorded = replayed.record_triangles(&trimesh()).unwrap();
+        assert!(!recorded.is_empty());
+        replayed
+            .replay_spans(&recorded, 2, AreaMergePolicy::default())
+            .unwrap();
+
+        assert_eq!(columns(&rasterized), columns(&replayed));
+    }
+
+    #[test]
+    fn replaying_into_different_layout_fails() {
+        let recorded = heightfield().record_triangles(&trimesh()).unwrap();
+        let mut other = HeightfieldBuilder {
+            aabb: Aabb3d::new(Vec3A::ZERO, [5.0, 5.0, 5.0]),
+            cell_size: 1.0,
+            cell_height: 0.25,
+        }
+        .build()
+        .unwrap();
+        assert!(matches!(
+            other.replay_spans(&recorded, 2, AreaMergePolicy::default()),
+            Err(RasterizationError::LayoutMismatch)
+        ));
+    }
+
+    #[test]
+    fn triangles_larger_than_heightfield_are_clipped_to_it() {
+        let mut heightfield = heightfield();
+        heightfield
+            .rasterize_triangle(
+                [
+                    Vec3A::new(-1000.0, 1.0, -1000.0),
+                    Vec3A::new(-1000.0, 1.0, 1000.0),
+                    Vec3A::new(1000.0, 1.0, 0.0),
+                ],
+                AreaType::DEFAULT_WALKABLE,
+                2,
+            )
+            .unwrap();
+
+        for column in columns(&heightfield) {
+            assert_eq!(column, vec![(24, 25, AreaType::DEFAULT_WALKABLE)]);
+        }
+    }
+
+    #[test]
+    fn vertical_triangles_ending_on_a_row_boundary_stay_in_their_column() {
+        let mut heightfield = HeightfieldBuilder {
+            aabb: Aabb3d::new(Vec3A::new(10.0, 4.0, 10.0), [10.0, 4.0, 10.0]),
+            cell_size: 0.3,
+            cell_height: 0.2,
+        }
+        .build()
+        .unwrap();
+        // The face of a wall, ending exactly where the row after it begins.
+        heightfield
+            .rasterize_triangle(
+                [
+                    Vec3A::new(6.5, 0.0, 10.2),
+                    Vec3A::new(6.5, 4.0, 10.2),
+                    Vec3A::new(6.5, 4.0, 9.8),
+                ],
+                AreaType::NOT_WALKABLE,
+                4,
+            )
+            .unwrap();
+
+        let column = (6.5 / 0.3) as usize;
+        for (index, spans) in columns(&heightfield).iter().enumerate() {
+            let x = index % heightfield.width as usize;
+            assert!(spans.is_empty() || x == column, "Span in column {x}");
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn rasterizing_in_stripes_matches_sequential_rasterization() {
+        let scene = crate::StressScene::new(3);
+        let mut geometry = scene.build();
+        geometry.mark_walkable_triangles(45.0_f32.to_radians());
+        let empty = HeightfieldBuilder {
+            aabb: scene.aabb(),
+            cell_size: 0.3,
+            cell_height: 0.2,
+        }
+        .build()
+        .unwrap();
+
+        let mut sequential = empty.clone();
+        // Existing spans have to be merged in the same order as well.
+        sequential.rasterize_triangles(&trimesh(), 4).unwrap();
+        let mut striped = sequential.clone();
+        for (indices, area) in geometry.indices.iter().zip(&geometry.area_types) {
+            let triangle = indices.to_array().map(|i| geometry.vertices[i as usize]);
+            sequential.rasterize_triangle(triangle, *area, 4).unwrap();
+        }
+        striped
+            .rasterize_triangles_in_stripes(&geometry, 4, AreaMergePolicy::default())
+            .unwrap();
+
+        assert_eq!(columns(&striped), columns(&sequential));
+    }
+
+    #[test]
+    fn merging_separately_rasterized_batches_matches_rasterization() {
+        let mut rasterized = heightfield();
+        rasterized.rasterize_triangles(&trimesh(), 2).unwrap();
+
+        let mesh = trimesh();
+        let mut merged = heightfield();
+        let mut other = heightfield();
+        for (i, heightfield) in [&mut merged, &mut other].into_iter().enumerate() {
+            let batch = TriMesh {
+                vertices: mesh.vertices.clone(),
+                indices: vec![mesh.indices[i]],
+                area_types: vec![mesh.area_types[i]],
+            };
+            heightfield.rasterize_triangles(&batch, 2).unwrap();
+        }
+        merged.merge(&other, 2).unwrap();
+
+        assert_eq!(columns(&rasterized), columns(&merged));
+    }
+}
@@ -0,0 +1,203 @@
+//! Rasterization of point clouds, e.g. from LiDAR scans, into a [`Heightfield`].
+//!
+//! Scanned environments usually come without a mesh, and meshing them is slow and brittle.
+//! Instead, every point is splatted into the columns around it as a span that is one cell thick.
+//! The top of the densest layer of points then acts as the floor, just like the surface of a rasterized triangle.
+
+use glam::Vec3;
+
+use crate::{
+    AreaMergePolicy, AreaType, Heightfield, RasterizationError, Span, heightfield::SpanInsertion,
+    span::SpanBuilder,
+};
+
+impl Heightfield {
+    /// Rasterizes a point cloud into the heightfield. All points get the given `area`.
+    ///
+    /// Every point fills the columns whose center lies within `radius` of the point on the xz-plane, as well as the column it lies in.
+    /// Choose a radius of about half the spacing of the points, so that neighboring points close the gaps between them. `[Limit: >=0] [Units: wu]`
+    ///
+    /// Points outside the heightfield are ignored. The walkability of the area is not derived from the points,
+    /// so noise below the floor should be removed beforehand, while sparse noise above it is filtered out later
+    /// like any other obstacle that is too small to block the agent.
+    pub fn rasterize_points(
+        &mut self,
+        points: &[Vec3],
+        radius: f32,
+        area: AreaType,
+    ) -> Result<(), RasterizationError> {
+        self.rasterize_points_with_merge_policy(points, radius, area, 0, AreaMergePolicy::default())
+    }
+
+    /// Same as [`Heightfield::rasterize_points`], but merges overlapping spans with the given
+    /// `flag_merge_threshold` and [`AreaMergePolicy`], like [`Heightfield::rasterize_triangle_with_merge_policy`].
+    pub fn rasterize_points_with_merge_policy(
+        &mut self,
+        points: &[Vec3],
+        radius: f32,
+        area: AreaType,
+        flag_merge_threshold: u16,
+        merge_policy: AreaMergePolicy,
+    ) -> Result<(), RasterizationError> {
+        let inverse_cell_size = 1.0 / self.cell_size;
+        let inverse_cell_height = 1.0 / self.cell_height;
+        let max_y = self.aabb.max.y - self.aabb.min.y;
+        let radius = radius.max(0.0);
+        let radius_squared = radius * radius;
+        // The number of columns the footprint reaches beyond the column of the point.
+        let reach = (radius * inverse_cell_size).ceil() as i32;
+
+        for point in points {
+            let local = *point - self.aabb.min;
+            if !local.is_finite() || local.y < 0.0 || local.y > max_y {
+                continue;
+            }
+            let column_x = (local.x * inverse_cell_size).floor() as i32;
+            let column_z = (local.z * inverse_cell_size).floor() as i32;
+            let min = ((local.y * inverse_cell_height).floor() as i32)
+                .clamp(0, Span::MAX_HEIGHT as i32 - 1) as u16;
+
+            for z in column_z - reach..=column_z + reach {
+                if z < 0 || z >= self.height as i32 {
+                    continue;
+                }
+                for x in column_x - reach..=column_x + reach {
+                    if x < 0 || x >= self.width as i32 {
+                        continue;
+                    }
+                    let center_x = (x as f32 + 0.5) * self.cell_size;
+                    let center_z = (z as f32 + 0.5) * self.cell_size;
+                    let distance_squared =
+                        (center_x - local.x).powi(2) + (center_z - local.z).powi(2);
+                    let own_column = x == column_x && z == column_z;
+                    if !own_column && distance_squared > radius_squared {
+                        continue;
+                    }
+                    self.add_span(SpanInsertion {
+                        x: x as u16,
+                        z: z as u16,
+                        span: SpanBuilder {
+                            min,
+                            max: min + 1,
+                            area,
+                            next: None,
+                        }
+                        .build(),
+                        flag_merge_threshold,
+                        merge_policy,
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Aabb3d, BuildContext, HeightfieldBuilder, NavmeshConfig, NavmeshConfigBuilder,
+        build_navmesh_from_points,
+    };
+
+    fn heightfield() -> Heightfield {
+        HeightfieldBuilder {
+            aabb: Aabb3d {
+                min: Vec3::new(0.0, 0.0, 0.0),
+                max: Vec3::new(4.0, 4.0, 4.0),
+            },
+            cell_size: 0.25,
+            cell_height: 0.1,
+        }
+        .build()
+        .unwrap()
+    }
+
+    /// A scan of a flat floor, with a point in the center of every other column.
+    fn floor_scan() -> Vec<Vec3> {
+        (0..8)
+            .flat_map(|z| (0..8).map(move |x| Vec3::new(x as f32, 0.0, z as f32) * 0.5))
+            .map(|point| point + Vec3::new(0.125, 1.05, 0.125))
+            .collect()
+    }
+
+    fn filled_columns(heightfield: &Heightfield) -> usize {
+        (0..heightfield.height)
+            .flat_map(|z| (0..heightfield.width).map(move |x| (x, z)))
+            .filter(|(x, z)| heightfield.span_at(*x, *z).is_some())
+            .count()
+    }
+
+    #[test]
+    fn radius_closes_gaps_between_points() {
+        let mut sparse = heightfield();
+        sparse
+            .rasterize_points(&floor_scan(), 0.0, AreaType::DEFAULT_WALKABLE)
+            .unwrap();
+        assert_eq!(filled_columns(&sparse), 64);
+
+        let mut dense = heightfield();
+        dense
+            .rasterize_points(&floor_scan(), 0.36, AreaType::DEFAULT_WALKABLE)
+            .unwrap();
+        assert_eq!(filled_columns(&dense), 256);
+
+        let span = dense.span_at(5, 9).unwrap();
+        assert_eq!((span.min, span.max), (10, 11));
+        assert_eq!(span.area, AreaType::DEFAULT_WALKABLE);
+        assert!(span.next.is_none());
+    }
+
+    #[test]
+    fn points_outside_the_heightfield_are_ignored() {
+        let mut heightfield = heightfield();
+        heightfield
+            .rasterize_points(
+                &[
+                    Vec3::new(-1.0, 1.0, 1.0),
+                    Vec3::new(1.0, 5.0, 1.0),
+                    Vec3::new(1.0, -0.5, 1.0),
+                    Vec3::NAN,
+                ],
+                0.5,
+                AreaType::DEFAULT_WALKABLE,
+            )
+            .unwrap();
+        assert_eq!(filled_columns(&heightfield), 0);
+
+        // Footprints are clipped at the edge of the heightfield.
+        heightfield
+            .rasterize_points(&[Vec3::new(0.1, 1.0, 0.1)], 0.5, AreaType::DEFAULT_WALKABLE)
+            .unwrap();
+        assert_eq!(filled_columns(&heightfield), 4);
+    }
+
+    #[test]
+    fn scanned_floor_builds_a_navmesh() {
+        // A noisy scan of a 10 × 10 floor with a point every 0.2 world units.
+        let points = (0..50)
+            .flat_map(|z| (0..50).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let noise = ((x * 7 + z * 13) % 5) as f32 * 0.01;
+                Vec3::new(x as f32 * 0.2 - 5.0, noise, z as f32 * 0.2 - 5.0)
+            })
+            .collect::<Vec<_>>();
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3::new(0.0, 1.0, 0.0), [6.0, 2.0, 6.0]),
+            ..NavmeshConfigBuilder::default().build()
+        };
+        let (polygon, _detail) =
+            build_navmesh_from_points(&points, 0.15, &config, &BuildContext::default()).unwrap();
+        let area = polygon
+            .polygon_ids()
+            .map(|id| polygon.surface_area(id))
+            .sum::<f32>();
+        // The floor shrinks by the agent radius on every side.
+        let side = 9.8 - 2.0 * config.walkable_radius as f32 * config.cell_size;
+        assert!(
+            (area - side * side).abs() < side * side * 0.05,
+            "{area} wu²"
+        );
+    }
+}
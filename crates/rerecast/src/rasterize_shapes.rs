@@ -0,0 +1,265 @@
+//! Rasterization of simple solids, namely upright cylinders and axis-aligned boxes, into a [`Heightfield`].
+//!
+//! Dynamic props and simple colliders are usually described by a handful of numbers rather than a mesh.
+//! Tessellating them into triangles only for [`Heightfield::rasterize_triangles`] to clip those against every column again
+//! is wasteful, so they are stamped into the columns they cover directly. The columns are chosen conservatively,
+//! like for triangles: every column whose cell overlaps the footprint of the solid is filled from its bottom to its top.
+
+use glam::{Vec2, Vec3, Vec3Swizzles as _};
+
+use crate::{
+    Aabb3d, AreaMergePolicy, AreaType, Heightfield, RasterizationError, Span,
+    heightfield::SpanInsertion, span::SpanBuilder,
+};
+
+impl Heightfield {
+    /// Rasterizes an upright cylinder into the heightfield, with `center` being the center of its base.
+    /// The cylinder reaches from `center.y` up to `center.y + height`. `[Limit: >=0] [Units: wu]`
+    ///
+    /// The top of the cylinder is walkable if `area` is, e.g. for a barrel that agents may climb onto.
+    /// Use [`AreaType::NOT_WALKABLE`] for props that should only block the agent.
+    /// Cylinders outside the heightfield, as well as ones with a negative `radius` or `height`, are ignored.
+    pub fn rasterize_cylinder(
+        &mut self,
+        center: Vec3,
+        radius: f32,
+        height: f32,
+        area: AreaType,
+    ) -> Result<(), RasterizationError> {
+        self.rasterize_cylinder_with_merge_policy(
+            center,
+            radius,
+            height,
+            area,
+            0,
+            AreaMergePolicy::default(),
+        )
+    }
+
+    /// Same as [`Heightfield::rasterize_cylinder`], but merges overlapping spans with the given
+    /// `flag_merge_threshold` and [`AreaMergePolicy`], like [`Heightfield::rasterize_triangle_with_merge_policy`].
+    pub fn rasterize_cylinder_with_merge_policy(
+        &mut self,
+        center: Vec3,
+        radius: f32,
+        height: f32,
+        area: AreaType,
+        flag_merge_threshold: u16,
+        merge_policy: AreaMergePolicy,
+    ) -> Result<(), RasterizationError> {
+        if !(radius >= 0.0 && height >= 0.0 && radius.is_finite() && height.is_finite()) {
+            return Ok(());
+        }
+        let local = center - self.aabb.min;
+        if !local.is_finite() {
+            return Ok(());
+        }
+        let Some((min, max)) = self.span_range(local.y, local.y + height) else {
+            return Ok(());
+        };
+        let center = local.xz();
+        let radius_squared = radius * radius;
+        for (x, z) in self.overlapped_columns(center - radius, center + radius) {
+            // The point of the cell that is closest to the axis of the cylinder.
+            let cell_min = Vec2::new(x as f32, z as f32) * self.cell_size;
+            let closest = center.clamp(cell_min, cell_min + self.cell_size);
+            if closest.distance_squared(center) > radius_squared {
+                continue;
+            }
+            self.add_span(SpanInsertion {
+                x,
+                z,
+                span: SpanBuilder {
+                    min,
+                    max,
+                    area,
+                    next: None,
+                }
+                .build(),
+                flag_merge_threshold,
+                merge_policy,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Rasterizes an axis-aligned box into the heightfield.
+    ///
+    /// The top of the box is walkable if `area` is, e.g. for a crate that agents may climb onto.
+    /// Use [`AreaType::NOT_WALKABLE`] for props that should only block the agent.
+    /// Boxes outside the heightfield, as well as ones whose `min` is not below or equal to their `max`, are ignored.
+    pub fn rasterize_aabb(
+        &mut self,
+        aabb: Aabb3d,
+        area: AreaType,
+    ) -> Result<(), RasterizationError> {
+        self.rasterize_aabb_with_merge_policy(aabb, area, 0, AreaMergePolicy::default())
+    }
+
+    /// Same as [`Heightfield::rasterize_aabb`], but merges overlapping spans with the given
+    /// `flag_merge_threshold` and [`AreaMergePolicy`], like [`Heightfield::rasterize_triangle_with_merge_policy`].
+    pub fn rasterize_aabb_with_merge_policy(
+        &mut self,
+        aabb: Aabb3d,
+        area: AreaType,
+        flag_merge_threshold: u16,
+        merge_policy: AreaMergePolicy,
+    ) -> Result<(), RasterizationError> {
+        let min = aabb.min - self.aabb.min;
+        let max = aabb.max - self.aabb.min;
+        if !(min.is_finite() && max.is_finite() && min.cmple(max).all()) {
+            return Ok(());
+        }
+        let Some((span_min, span_max)) = self.span_range(min.y, max.y) else {
+            return Ok(());
+        };
+        for (x, z) in self.overlapped_columns(min.xz(), max.xz()) {
+            self.add_span(SpanInsertion {
+                x,
+                z,
+                span: SpanBuilder {
+                    min: span_min,
+                    max: span_max,
+                    area,
+                    next: None,
+                }
+                .build(),
+                flag_merge_threshold,
+                merge_policy,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// The span that fills the columns from `bottom` to `top`, relative to the bottom of the heightfield,
+    /// or `None` if that range lies outside the heightfield.
+    fn span_range(&self, bottom: f32, top: f32) -> Option<(u16, u16)> {
+        let max_y = self.aabb.max.y - self.aabb.min.y;
+        if top < 0.0 || bottom > max_y {
+            return None;
+        }
+        let inverse_cell_height = 1.0 / self.cell_height;
+        let min = ((bottom.max(0.0) * inverse_cell_height).floor() as i32)
+            .clamp(0, Span::MAX_HEIGHT as i32 - 1) as u16;
+        let max = ((top.min(max_y) * inverse_cell_height).ceil() as i32)
+            .clamp(min as i32 + 1, Span::MAX_HEIGHT as i32) as u16;
+        Some((min, max))
+    }
+
+    /// The columns whose cells overlap the rectangle from `min` to `max` on the xz-plane,
+    /// relative to the corner of the heightfield. Columns outside the heightfield are skipped.
+    fn overlapped_columns(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = (u16, u16)> + use<> {
+        let inverse_cell_size = 1.0 / self.cell_size;
+        let first = (min * inverse_cell_size).floor();
+        // Cells that only touch the rectangle at its far edge don't overlap it.
+        let last = ((max * inverse_cell_size).ceil() - 1.0).max(first);
+        let first_x = (first.x as i32).max(0);
+        let first_z = (first.y as i32).max(0);
+        let last_x = (last.x as i32).min(self.width as i32 - 1);
+        let last_z = (last.y as i32).min(self.height as i32 - 1);
+        (first_z..=last_z).flat_map(move |z| (first_x..=last_x).map(move |x| (x as u16, z as u16)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HeightfieldBuilder;
+
+    fn heightfield() -> Heightfield {
+        HeightfieldBuilder {
+            aabb: Aabb3d {
+                min: Vec3::new(0.0, 0.0, 0.0),
+                max: Vec3::new(4.0, 4.0, 4.0),
+            },
+            cell_size: 0.25,
+            cell_height: 0.1,
+        }
+        .build()
+        .unwrap()
+    }
+
+    fn filled_columns(heightfield: &Heightfield) -> usize {
+        (0..heightfield.height)
+            .flat_map(|z| (0..heightfield.width).map(move |x| (x, z)))
+            .filter(|(x, z)| heightfield.span_at(*x, *z).is_some())
+            .count()
+    }
+
+    #[test]
+    fn aabb_fills_overlapped_columns() {
+        let mut heightfield = heightfield();
+        heightfield
+            .rasterize_aabb(
+                Aabb3d {
+                    min: Vec3::new(1.0, 0.5, 1.1),
+                    max: Vec3::new(1.9, 1.55, 1.4),
+                },
+                AreaType::NOT_WALKABLE,
+            )
+            .unwrap();
+        // Columns 4..=7 on the x-axis and 4..=5 on the z-axis.
+        assert_eq!(filled_columns(&heightfield), 8);
+        let span = heightfield.span_at(7, 5).unwrap();
+        assert_eq!((span.min, span.max), (5, 16));
+        assert_eq!(span.area, AreaType::NOT_WALKABLE);
+        assert!(heightfield.span_at(8, 5).is_none());
+        assert!(heightfield.span_at(7, 6).is_none());
+    }
+
+    #[test]
+    fn cylinder_fills_columns_overlapping_its_footprint() {
+        let mut heightfield = heightfield();
+        heightfield
+            .rasterize_cylinder(
+                Vec3::new(2.0, 1.0, 2.0),
+                0.6,
+                1.0,
+                AreaType::DEFAULT_WALKABLE,
+            )
+            .unwrap();
+        // The 6 × 6 columns of the bounding square, except for its corners.
+        assert_eq!(filled_columns(&heightfield), 36 - 4);
+        let span = heightfield.span_at(8, 8).unwrap();
+        assert_eq!((span.min, span.max), (10, 20));
+        assert_eq!(span.area, AreaType::DEFAULT_WALKABLE);
+        // The corners of the bounding square of the circle stay empty.
+        assert!(heightfield.span_at(5, 5).is_none());
+        assert!(heightfield.span_at(10, 10).is_none());
+        assert!(heightfield.span_at(5, 6).is_some());
+    }
+
+    #[test]
+    fn shapes_are_clipped_to_the_heightfield() {
+        let mut heightfield = heightfield();
+        heightfield
+            .rasterize_cylinder(Vec3::new(1.0, 5.0, 1.0), 1.0, 1.0, AreaType::NOT_WALKABLE)
+            .unwrap();
+        heightfield
+            .rasterize_cylinder(Vec3::new(1.0, 1.0, 1.0), -1.0, 1.0, AreaType::NOT_WALKABLE)
+            .unwrap();
+        heightfield
+            .rasterize_aabb(
+                Aabb3d {
+                    min: Vec3::new(-2.0, 0.0, -2.0),
+                    max: Vec3::new(-1.0, 1.0, -1.0),
+                },
+                AreaType::NOT_WALKABLE,
+            )
+            .unwrap();
+        assert_eq!(filled_columns(&heightfield), 0);
+
+        heightfield
+            .rasterize_aabb(
+                Aabb3d {
+                    min: Vec3::new(-1.0, -1.0, -1.0),
+                    max: Vec3::new(0.3, 10.0, 0.3),
+                },
+                AreaType::NOT_WALKABLE,
+            )
+            .unwrap();
+        assert_eq!(filled_columns(&heightfield), 4);
+        let span = heightfield.span_at(0, 0).unwrap();
+        assert_eq!((span.min, span.max), (0, 40));
+    }
+}
@@ -0,0 +1,125 @@
+//! Recording the inputs of a navmesh build to a file and replaying them later.
+//!
+//! This allows reproducing a build, e.g. for a bug report, without access to the project it was made in.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write as _},
+    path::Path,
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use thiserror::Error;
+
+use crate::{AreaType, BuildNavmeshError, DetailNavmesh, NavmeshConfig, PolygonNavmesh, TriMesh};
+
+/// The inputs of a navmesh build, as passed to [`build_premarked_navmesh_with_progress`](crate::build_premarked_navmesh_with_progress).
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct InputRecording {
+    /// The world-space geometry of every affector that was collected for the build,
+    /// with its triangles already marked.
+    pub trimeshes: Vec<TriMesh>,
+    /// The config used for the build.
+    pub config: NavmeshConfig,
+}
+
+impl InputRecording {
+    /// Writes the recording to a gzip-compressed file at `path`, overwriting any existing file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let file = File::create(path)?;
+        let mut gz = GzEncoder::new(BufWriter::new(file), Compression::default());
+        bincode::serde::encode_into_std_write(self, &mut gz, bincode::config::standard())?;
+        gz.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Reads a recording that was written with [`InputRecording::save`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let file = File::open(path)?;
+        let mut gz = GzDecoder::new(BufReader::new(file));
+        let recording = bincode::serde::decode_from_std_read(&mut gz, bincode::config::standard())?;
+        Ok(recording)
+    }
+
+    /// Runs the navmesh build with the recorded inputs.
+    pub fn replay(self) -> Result<(PolygonNavmesh, DetailNavmesh), BuildNavmeshError> {
+        let mut trimesh = TriMesh::default();
+        for affector in &self.trimeshes {
+            trimesh.extend(affector);
+        }
+        // Recordings from before the triangles were marked ahead of the build contain no walkable triangles at all,
+        // so they are still marked by slope.
+        if trimesh
+            .area_types
+            .iter()
+            .all(|area| *area == AreaType::NOT_WALKABLE)
+        {
+            return crate::build_navmesh(trimesh, &self.config);
+        }
+        crate::build_premarked_navmesh_with_progress(
+            trimesh,
+            &self.config,
+            &crate::BuildContext::default(),
+        )
+    }
+}
+
+/// Loads the [`InputRecording`] at `path` and runs the navmesh build with it.
+pub fn replay(path: impl AsRef<Path>) -> Result<(PolygonNavmesh, DetailNavmesh), ReplayError> {
+    let recording = InputRecording::load(path)?;
+    Ok(recording.replay()?)
+}
+
+/// Errors that can occur when saving, loading or replaying an [`InputRecording`].
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    /// Happens when the file cannot be read or written.
+    #[error("Failed to access recording: {0}")]
+    Io(#[from] std::io::Error),
+    /// Happens when the recording cannot be encoded.
+    #[error("Failed to encode recording: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    /// Happens when the file does not contain a valid recording.
+    #[error("Failed to decode recording: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+    /// Happens when the replayed build fails.
+    #[error("Failed to build navmesh: {0}")]
+    Build(#[from] BuildNavmeshError),
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3A};
+
+    use super::*;
+    use crate::{Aabb3d, NavmeshConfigBuilder};
+
+    #[test]
+    fn recording_roundtrips() {
+        let recording = InputRecording {
+            trimeshes: vec![
+                TriMesh::from_parts(
+                    vec![
+                        Vec3A::new(-5.0, 0.0, -5.0),
+                        Vec3A::new(-5.0, 0.0, 5.0),
+                        Vec3A::new(5.0, 0.0, 5.0),
+                        Vec3A::new(5.0, 0.0, -5.0),
+                    ],
+                    vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+                )
+                .unwrap(),
+            ],
+            config: NavmeshConfig {
+                aabb: Aabb3d::new(Vec3A::ZERO, [6.0, 2.0, 6.0]),
+                ..NavmeshConfigBuilder::default().build()
+            },
+        };
+        let path = std::env::temp_dir().join("rerecast_recording_roundtrips.bin.gz");
+        recording.save(&path).unwrap();
+        let loaded = InputRecording::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(recording, loaded);
+        let (polygon, _detail) = loaded.replay().unwrap();
+        assert!(polygon.polygon_count() > 0);
+    }
+}
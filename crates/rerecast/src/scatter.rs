@@ -0,0 +1,293 @@
+//! Scattering of points on a navmesh, e.g. for spawning foliage, loot or patrol points.
+//!
+//! Points are distributed by dart throwing: random candidates are sampled uniformly on the surface of the
+//! [`DetailNavmesh`] and accepted in order as long as they keep the configured distance to all points accepted so far,
+//! which results in a Poisson disk distribution.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+use glam::{IVec3, Vec3, Vec3Swizzles as _};
+
+use crate::{
+    AreaType, DetailNavmesh, EdgeConnection, PolyId, PolygonNavmesh, VertexId,
+    math::distance_squared_to_segment,
+};
+
+/// Parameters for [`PolygonNavmesh::scatter_points`]. All distances are in world units.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScatterConfig {
+    /// The minimum distance between two points.
+    pub min_spacing: f32,
+    /// The minimum horizontal distance between a point and the boundary of the navmesh.
+    /// Edges to polygons whose area is not in [`Self::areas`] count as boundary as well.
+    pub min_boundary_distance: f32,
+    /// The area types points may be placed on. If empty, points are placed on all polygons.
+    pub areas: Vec<AreaType>,
+    /// The maximum number of points to place.
+    pub max_points: usize,
+    /// How many candidates are sampled per `min_spacing * min_spacing` of surface.
+    /// Higher values fill the surface more densely, at the cost of speed.
+    pub candidates_per_area: f32,
+    /// The seed of the random number generator. The same seed on the same navmesh places the same points.
+    pub seed: u64,
+}
+
+impl Default for ScatterConfig {
+    fn default() -> Self {
+        Self {
+            min_spacing: 1.0,
+            min_boundary_distance: 0.0,
+            areas: Vec::new(),
+            max_points: usize::MAX,
+            candidates_per_area: 10.0,
+            seed: 0,
+        }
+    }
+}
+
+/// A point placed by [`PolygonNavmesh::scatter_points`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct ScatterPoint {
+    /// The position on the detail surface, in world space.
+    pub position: Vec3,
+    /// The normal of the detail triangle the point lies on. Normalized and pointing upwards.
+    pub normal: Vec3,
+    /// The polygon the point lies on.
+    pub polygon: PolyId,
+}
+
+impl PolygonNavmesh {
+    /// Scatters points on this navmesh according to `config`.
+    ///
+    /// `detail` must be the detail mesh built from this navmesh. The points are returned in the order they were placed,
+    /// so taking a prefix of them keeps the spacing intact.
+    pub fn scatter_points(
+        &self,
+        detail: &DetailNavmesh,
+        config: &ScatterConfig,
+    ) -> Vec<ScatterPoint> {
+        let allowed = |polygon: PolyId| {
+            config.areas.is_empty() || config.areas.contains(&self.areas[polygon.index()])
+        };
+        let to_world = |vertex: VertexId| self.vertex_world(vertex);
+
+        let mut triangles = Vec::new();
+        let mut boundary = Vec::new();
+        let mut total_area = 0.0;
+        for polygon in self.polygon_ids().filter(|polygon| allowed(*polygon)) {
+            if config.min_boundary_distance > 0.0 {
+                for edge in self.edges(polygon) {
+                    let is_boundary = match self.edge_connection(edge) {
+                        EdgeConnection::Polygon(neighbor) => !allowed(neighbor),
+                        // Portal edges continue on another tile, so they are not a boundary.
+                        EdgeConnection::Portal(_) => false,
+                        EdgeConnection::Border => true,
+                    };
+                    if is_boundary {
+                        boundary.push(self.edge_vertices(edge).map(|v| to_world(v).xz()));
+                    }
+                }
+            }
+
+            let Some(submesh) = detail.submesh(polygon) else {
+                continue;
+            };
+            let vertices = &detail.vertices[submesh.base_vertex_index as usize..]
+                [..submesh.vertex_count as usize];
+            let tris = &detail.triangles[submesh.base_triangle_index as usize..]
+                [..submesh.triangle_count as usize];
+            for tri in tris {
+                let [a, b, c] = tri.map(|i| vertices[i as usize]);
+                let area = (b - a).cross(c - a).length() * 0.5;
+                if area <= f32::EPSILON {
+                    continue;
+                }
+                total_area += area;
+                triangles.push(([a, b, c], polygon, total_area));
+            }
+        }
+        if triangles.is_empty() || config.max_points == 0 || config.min_spacing <= 0.0 {
+            return Vec::new();
+        }
+
+        let spacing_squared = config.min_spacing * config.min_spacing;
+        let candidates =
+            (total_area / spacing_squared * config.candidates_per_area).ceil() as usize;
+        let mut rng = SplitMix64(config.seed);
+        let mut grid: HashMap<IVec3, Vec<usize>> = HashMap::new();
+        let cell = |position: Vec3| (position / config.min_spacing).floor().as_ivec3();
+        let mut points: Vec<ScatterPoint> = Vec::new();
+        for _ in 0..candidates {
+            // Pick a triangle weighted by its area, then a uniformly distributed point in it.
+            let target = rng.next_f32() * total_area;
+            let index = triangles
+                .partition_point(|(_, _, cumulative_area)| *cumulative_area <= target)
+                .min(triangles.len() - 1);
+            let ([a, b, c], polygon, _) = triangles[index];
+            let (mut u, mut v) = (rng.next_f32(), rng.next_f32());
+            if u + v > 1.0 {
+                (u, v) = (1.0 - u, 1.0 - v);
+            }
+            let position = a + (b - a) * u + (c - a) * v;
+
+            let near_boundary = boundary.iter().any(|[start, end]| {
+                distance_squared_to_segment(position.xz(), *start, *end)
+                    < config.min_boundary_distance * config.min_boundary_distance
+            });
+            if near_boundary {
+                continue;
+            }
+            let center = cell(position);
+            let too_close = (-1..=1).any(|x| {
+                (-1..=1).any(|y| {
+                    (-1..=1).any(|z| {
+                        grid.get(&(center + IVec3::new(x, y, z)))
+                            .is_some_and(|indices| {
+                                indices.iter().any(|&i| {
+                                    points[i].position.distance_squared(position) < spacing_squared
+                                })
+                            })
+                    })
+                })
+            });
+            if too_close {
+                continue;
+            }
+
+            let mut normal = (b - a).cross(c - a).normalize();
+            if normal.y < 0.0 {
+                normal = -normal;
+            }
+            grid.entry(center).or_default().push(points.len());
+            points.push(ScatterPoint {
+                position,
+                normal,
+                polygon,
+            });
+            if points.len() >= config.max_points {
+                break;
+            }
+        }
+        points
+    }
+}
+
+/// A tiny deterministic random number generator, so that scattering needs no extra dependency
+/// and gives the same result on every platform.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly distributed value in `[0, 1)`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1_u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::U16Vec3;
+
+    use super::*;
+    use crate::{Aabb3d, RegionId, SubMesh};
+
+    /// Two 4x4 quads next to each other along the x-axis, the second one marked with another area.
+    fn two_quads() -> (PolygonNavmesh, DetailNavmesh) {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        let navmesh = PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 0, 0),
+                U16Vec3::new(0, 0, 4),
+                U16Vec3::new(4, 0, 4),
+                U16Vec3::new(4, 0, 0),
+                U16Vec3::new(8, 0, 4),
+                U16Vec3::new(8, 0, 0),
+            ],
+            polygons: vec![0, 1, 2, 3, 3, 2, 4, 5],
+            polygon_neighbors: vec![N, N, 1, N, 0, N, N, N],
+            flags: vec![0, 0],
+            regions: vec![RegionId::from(1), RegionId::from(2)],
+            areas: vec![AreaType::DEFAULT_WALKABLE, AreaType(1)],
+            max_vertices_per_polygon: 4,
+            aabb: Aabb3d {
+                min: Vec3::ZERO,
+                max: Vec3::new(8.0, 0.0, 4.0),
+            },
+            cell_size: 1.0,
+            cell_height: 1.0,
+            ..Default::default()
+        };
+        let quad = |x: f32| {
+            [
+                Vec3::new(x, 0.0, 0.0),
+                Vec3::new(x, 0.0, 4.0),
+                Vec3::new(x + 4.0, 0.0, 4.0),
+                Vec3::new(x + 4.0, 0.0, 0.0),
+            ]
+        };
+        let detail = DetailNavmesh {
+            meshes: (0..2)
+                .map(|i| SubMesh {
+                    base_vertex_index: i * 4,
+                    vertex_count: 4,
+                    base_triangle_index: i * 2,
+                    triangle_count: 2,
+                })
+                .collect(),
+            vertices: [quad(0.0), quad(4.0)].concat(),
+            triangles: vec![[0, 1, 2], [0, 2, 3], [0, 1, 2], [0, 2, 3]],
+            triangle_flags: vec![0; 4],
+        };
+        (navmesh, detail)
+    }
+
+    #[test]
+    fn scattered_points_respect_constraints() {
+        let (navmesh, detail) = two_quads();
+        let config = ScatterConfig {
+            min_spacing: 0.5,
+            min_boundary_distance: 0.5,
+            areas: vec![AreaType::DEFAULT_WALKABLE],
+            seed: 42,
+            ..Default::default()
+        };
+        let points = navmesh.scatter_points(&detail, &config);
+        assert!(points.len() > 10, "{}", points.len());
+        for (i, point) in points.iter().enumerate() {
+            assert_eq!(point.polygon, PolyId(0));
+            assert_eq!(point.normal, Vec3::Y);
+            // The edge to the second quad is a boundary as well, since its area is not allowed.
+            let p = point.position;
+            assert!(p.x >= 0.5 && p.x <= 3.5 && p.z >= 0.5 && p.z <= 3.5, "{p}");
+            for other in &points[i + 1..] {
+                assert!(p.distance(other.position) >= 0.5);
+            }
+        }
+        assert_eq!(navmesh.scatter_points(&detail, &config), points);
+
+        let limited = navmesh.scatter_points(
+            &detail,
+            &ScatterConfig {
+                max_points: 3,
+                ..config
+            },
+        );
+        assert_eq!(limited, points[..3]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Buffers that are reused across navmesh builds.
+//!
+//! Every build allocates a few large temporary buffers, like the heightfield columns, the distance field or the region ids.
+//! Passing the same [`BuildScratch`] to [`build_premarked_navmesh_with_scratch`](crate::build_premarked_navmesh_with_scratch)
+//! for every build keeps these buffers alive, so that once the scratch has grown to the size of the largest build,
+//! rebuilds no longer allocate them.
+
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+use std::mem;
+
+use crate::{AreaType, CompactCell, CompactHeightfield, CompactSpan, RegionId, SpanKey, Spans};
+
+/// Temporary buffers that can be reused across navmesh builds.
+///
+/// See [`build_premarked_navmesh_with_scratch`](crate::build_premarked_navmesh_with_scratch).
+#[derive(Debug, Default)]
+pub struct BuildScratch {
+    pub(crate) columns: Pool<Option<SpanKey>>,
+    spans: Spans,
+    spare_spans: Spans,
+    span_capacity: usize,
+    span_growths: u32,
+    pub(crate) cells: Pool<CompactCell>,
+    pub(crate) compact_spans: Pool<CompactSpan>,
+    pub(crate) areas: Pool<AreaType>,
+    pub(crate) dist: Pool<u16>,
+    pub(crate) distances: Pool<u16>,
+    pub(crate) regions: Pool<RegionId>,
+    pub(crate) bytes: Pool<u8>,
+    pub(crate) neighbors: Pool<[u32; 4]>,
+    builds: u32,
+}
+
+/// How well a [`BuildScratch`] is being reused. Returned by [`BuildScratch::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+pub struct BuildScratchStats {
+    /// The number of builds that used the scratch so far.
+    pub builds: u32,
+    /// The number of buffers that had to be allocated or grown during the last build.
+    /// Zero once the scratch is large enough for the builds it is used for.
+    pub growths: u32,
+    /// The memory currently held by the scratch, in bytes.
+    pub capacity_bytes: usize,
+}
+
+impl BuildScratch {
+    /// Creates an empty scratch. The buffers are allocated by the first build that uses it.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns how well the scratch is being reused.
+    pub fn stats(&self) -> BuildScratchStats {
+        BuildScratchStats {
+            builds: self.builds,
+            growths: self.span_growths
+                + self.columns.growths
+                + self.cells.growths
+                + self.compact_spans.growths
+                + self.areas.growths
+                + self.dist.growths
+                + self.distances.growths
+                + self.regions.growths
+                + self.bytes.growths
+                + self.neighbors.growths,
+            capacity_bytes: (self.spans.capacity() + self.spare_spans.capacity())
+                * size_of::<crate::Span>()
+                + self.columns.capacity_bytes()
+                + self.cells.capacity_bytes()
+                + self.compact_spans.capacity_bytes()
+                + self.areas.capacity_bytes()
+                + self.dist.capacity_bytes()
+                + self.distances.capacity_bytes()
+                + self.regions.capacity_bytes()
+                + self.bytes.capacity_bytes()
+                + self.neighbors.capacity_bytes(),
+        }
+    }
+
+    /// Drops all buffers, releasing their memory. The statistics are kept.
+    pub fn clear(&mut self) {
+        *self = Self {
+            builds: self.builds,
+            ..Self::default()
+        };
+    }
+
+    /// Starts counting growths for a new build.
+    pub(crate) fn begin_build(&mut self) {
+        self.builds += 1;
+        self.span_growths = 0;
+        for growths in [
+            &mut self.columns.growths,
+            &mut self.cells.growths,
+            &mut self.compact_spans.growths,
+            &mut self.areas.growths,
+            &mut self.dist.growths,
+            &mut self.distances.growths,
+            &mut self.regions.growths,
+            &mut self.bytes.growths,
+            &mut self.neighbors.growths,
+        ] {
+            *growths = 0;
+        }
+    }
+
+    /// Takes the span storage for a new heightfield, with room for at least `min_capacity` spans.
+    pub(crate) fn take_spans(&mut self, min_capacity: usize) -> Spans {
+        let mut spans = mem::take(&mut self.spans);
+        if spans.capacity() == 0 {
+            spans = Spans::with_min_capacity(min_capacity);
+            self.span_growths += 1;
+        } else {
+            spans.clear();
+            let capacity = spans.capacity();
+            spans.reserve(min_capacity);
+            if spans.capacity() > capacity {
+                self.span_growths += 1;
+            }
+        }
+        self.span_capacity = spans.capacity() + self.spare_spans.capacity();
+        spans
+    }
+
+    /// Compacts `spans` into the spare span storage and keeps the old storage as the new spare.
+    ///
+    /// The spans end up next to each other just like with [`Spans::compact`], but not necessarily in column order.
+    pub(crate) fn compact_spans(&mut self, spans: &mut Spans, columns: &mut [Option<SpanKey>]) {
+        spans.compact_into(columns, &mut self.spare_spans);
+    }
+
+    /// Returns the storage of a heightfield that is no longer needed.
+    pub(crate) fn recycle_heightfield(&mut self, columns: Vec<Option<SpanKey>>, spans: Spans) {
+        if spans.capacity() + self.spare_spans.capacity() > self.span_capacity {
+            self.span_growths += 1;
+        }
+        self.columns.give(columns);
+        self.spans = spans;
+    }
+
+    /// Returns the storage of a compact heightfield that is no longer needed.
+    pub(crate) fn recycle_compact(&mut self, compact_heightfield: CompactHeightfield) {
+        self.cells.give(compact_heightfield.cells);
+        self.compact_spans.give(compact_heightfield.spans);
+        self.areas.give(compact_heightfield.areas);
+        self.dist.give(compact_heightfield.dist);
+    }
+}
+
+/// A single reusable buffer.
+#[derive(Debug)]
+pub(crate) struct Pool<T> {
+    buffer: Vec<T>,
+    growths: u32,
+}
+
+impl<T> Default for Pool<T> {
+    fn default() -> Self {
+        Self {
+            buffer: Vec::new(),
+            growths: 0,
+        }
+    }
+}
+
+impl<T: Clone> Pool<T> {
+    /// Takes the buffer out of the pool, filled with `len` copies of `value`.
+    pub(crate) fn take(&mut self, len: usize, value: T) -> Vec<T> {
+        let mut buffer = mem::take(&mut self.buffer);
+        if buffer.capacity() < len {
+            self.growths += 1;
+        }
+        buffer.clear();
+        buffer.resize(len, value);
+        buffer
+    }
+
+    /// Puts a buffer back into the pool. If the pool already holds a larger buffer, the given one is dropped.
+    pub(crate) fn give(&mut self, buffer: Vec<T>) {
+        if buffer.capacity() >= self.buffer.capacity() {
+            self.buffer = buffer;
+        }
+    }
+
+    fn capacity_bytes(&self) -> usize {
+        self.buffer.capacity() * size_of::<T>()
+    }
+}
@@ -0,0 +1,222 @@
+//! Pathfinding costs derived from how steep and uneven the terrain of each region is.
+//!
+//! Hilly terrain is walkable, but usually slower to cross than a flat road. [`CompactHeightfield::region_slopes`]
+//! measures the slope of every region while the heightfield is still around, and [`PolygonNavmesh::bake_slope_costs`]
+//! stores a cost for every polygon of the region. A pathfinder multiplies the cost of a polygon with [`PolygonNavmesh::cost`],
+//! just like with [`CostVolumes::polygon_multiplier`](crate::CostVolumes::polygon_multiplier).
+
+use crate::{
+    CompactHeightfield, PolyId, PolygonNavmesh, RegionId,
+    geom::{dir_offset_x, dir_offset_z},
+};
+
+/// How steep and uneven a region of a [`CompactHeightfield`] is, measured with [`CompactHeightfield::region_slopes`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegionSlope {
+    /// The average gradient of the spans of the region, i.e. the rise per horizontal distance. `[Units: wu/wu]`
+    ///
+    /// Flat ground has a gradient of 0, a slope of 45 degrees a gradient of 1.
+    pub mean_gradient: f32,
+    /// The standard deviation of the gradients of the spans of the region. `[Units: wu/wu]`
+    ///
+    /// Even ramps are smooth, while bumpy terrain is rough even if it is flat on average.
+    /// Since the heights of the spans are quantized to [`CompactHeightfield::cell_height`], gentle ramps are slightly rough as well.
+    pub roughness: f32,
+}
+
+impl RegionSlope {
+    /// The cost of traversing the region with the given [`NavmeshConfig::slope_cost`](crate::NavmeshConfig::slope_cost),
+    /// i.e. `1 + weight * (mean_gradient + roughness)`.
+    pub fn cost(&self, weight: f32) -> f32 {
+        1.0 + weight * (self.mean_gradient + self.roughness)
+    }
+}
+
+impl CompactHeightfield {
+    /// Measures the [`RegionSlope`] of every region. Must be called after the regions were built.
+    ///
+    /// The result is indexed by the region id and holds an entry for every id up to and including [`Self::max_region`].
+    /// The gradient of a span is taken from the heights of its connected neighbors.
+    /// Border spans, spans without a region and spans without neighbors are ignored,
+    /// so regions without any measured span keep the default of a flat region.
+    pub fn region_slopes(&self) -> Vec<RegionSlope> {
+        // The sum of the gradients, the sum of their squares, and the number of spans of every region.
+        let mut sums = vec![(0.0_f64, 0.0_f64, 0_u32); self.max_region.bits() as usize + 1];
+        let rise_per_run = self.cell_height / self.cell_size;
+        for z in 0..self.height {
+            for x in 0..self.width {
+                let cell = self.cell_at(x, z);
+                for i in cell.index_range() {
+                    let span = &self.spans[i];
+                    if span.region == RegionId::NONE
+                        || span.region.contains(RegionId::BORDER_REGION)
+                    {
+                        continue;
+                    }
+                    // Central differences where both neighbors along an axis exist, one-sided differences otherwise.
+                    let mut slope = [0.0_f32; 2];
+                    let mut samples = [0_u8; 2];
+                    for dir in 0..4 {
+                        let Some(con) = span.con(dir) else {
+                            continue;
+                        };
+                        let (_, _, a_i) = self.con_indices(x as i32, z as i32, dir, con);
+                        let rise = self.spans[a_i].y as f32 - span.y as f32;
+                        let (axis, sign) = if dir_offset_x(dir) != 0 {
+                            (0, dir_offset_x(dir))
+                        } else {
+                            (1, dir_offset_z(dir))
+                        };
+                        slope[axis] += rise * sign as f32;
+                        samples[axis] += 1;
+                    }
+                    if samples == [0, 0] {
+                        continue;
+                    }
+                    let [slope_x, slope_z] =
+                        [0, 1].map(|axis| slope[axis] / samples[axis].max(1) as f32);
+                    let gradient =
+                        ((slope_x * slope_x + slope_z * slope_z).sqrt() * rise_per_run) as f64;
+                    let sum = &mut sums[span.region.bits() as usize];
+                    sum.0 += gradient;
+                    sum.1 += gradient * gradient;
+                    sum.2 += 1;
+                }
+            }
+        }
+        sums.into_iter()
+            .map(|(sum, sum_of_squares, count)| {
+                if count == 0 {
+                    return RegionSlope::default();
+                }
+                let mean = sum / count as f64;
+                let variance = (sum_of_squares / count as f64 - mean * mean).max(0.0);
+                RegionSlope {
+                    mean_gradient: mean as f32,
+                    roughness: variance.sqrt() as f32,
+                }
+            })
+            .collect()
+    }
+}
+
+impl PolygonNavmesh {
+    /// The factor the pathfinding cost of the given polygon is multiplied with.
+    ///
+    /// Reads [`Self::costs`] if costs were baked, otherwise returns 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `polygon` is out of bounds.
+    pub fn cost(&self, polygon: PolyId) -> f32 {
+        if self.has_costs() {
+            self.costs[polygon.index()]
+        } else {
+            1.0
+        }
+    }
+
+    /// Stores the [`RegionSlope::cost`] of the region of every polygon in [`Self::costs`].
+    ///
+    /// `slopes` are the slopes measured with [`CompactHeightfield::region_slopes`] on the heightfield this mesh was built from.
+    /// Polygons whose region has no entry in `slopes` get a cost of 1.
+    pub fn bake_slope_costs(&mut self, slopes: &[RegionSlope], weight: f32) {
+        self.costs = self
+            .regions
+            .iter()
+            .map(|region| {
+                slopes
+                    .get(region.bits() as usize)
+                    .map_or(1.0, |slope| slope.cost(weight))
+            })
+            .collect();
+    }
+
+    /// Removes the baked [`Self::costs`], which makes every polygon cost 1 again.
+    pub fn clear_costs(&mut self) {
+        self.costs = Vec::new();
+    }
+
+    /// Whether [`Self::costs`] holds a value for every polygon.
+    /// Costs that do not match the polygon count, e.g. because polygons were added by hand, are ignored.
+    pub fn has_costs(&self) -> bool {
+        self.costs.len() == self.polygon_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3A};
+
+    use crate::{Aabb3d, NavmeshConfig, NavmeshConfigBuilder, TriMesh, build_navmesh};
+
+    /// A flat floor on the left and a ramp rising at about 17 degrees on the right, with a gap between them.
+    fn floor_and_ramp() -> TriMesh {
+        TriMesh::from_parts(
+            vec![
+                Vec3A::new(-10.0, 0.0, -5.0),
+                Vec3A::new(-10.0, 0.0, 5.0),
+                Vec3A::new(-1.0, 0.0, 5.0),
+                Vec3A::new(-1.0, 0.0, -5.0),
+                Vec3A::new(1.0, 0.0, -5.0),
+                Vec3A::new(1.0, 0.0, 5.0),
+                Vec3A::new(10.0, 2.7, 5.0),
+                Vec3A::new(10.0, 2.7, -5.0),
+            ],
+            vec![
+                UVec3::new(0, 1, 2),
+                UVec3::new(0, 2, 3),
+                UVec3::new(4, 5, 6),
+                UVec3::new(4, 6, 7),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn ramps_cost_more_than_flat_ground() {
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3A::new(0.0, 1.5, 0.0), [11.0, 2.0, 6.0]),
+            ..NavmeshConfigBuilder {
+                slope_cost: 1.0,
+                ..Default::default()
+            }
+            .build()
+        };
+        let (polygon, _detail) = build_navmesh(floor_and_ramp(), &config).unwrap();
+        assert!(polygon.has_costs());
+
+        let mut flat = Vec::new();
+        let mut ramp = Vec::new();
+        for id in polygon.polygon_ids() {
+            if polygon.center(id).x < 0.0 {
+                flat.push(polygon.cost(id));
+            } else {
+                ramp.push(polygon.cost(id));
+            }
+        }
+        assert!(!flat.is_empty() && !ramp.is_empty());
+        assert!(
+            flat.iter().all(|cost| (*cost - 1.0).abs() < 0.05),
+            "{flat:?}"
+        );
+        // The ramp has a gradient of 0.3.
+        assert!(
+            ramp.iter().all(|cost| *cost > 1.2 && *cost < 1.6),
+            "{ramp:?}"
+        );
+    }
+
+    #[test]
+    fn costs_are_only_baked_if_enabled() {
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3A::new(0.0, 1.5, 0.0), [11.0, 2.0, 6.0]),
+            ..NavmeshConfigBuilder::default().build()
+        };
+        let (polygon, _detail) = build_navmesh(floor_and_ramp(), &config).unwrap();
+        assert!(polygon.polygon_count() > 0);
+        assert!(polygon.costs.is_empty());
+        assert!(polygon.polygon_ids().all(|id| polygon.cost(id) == 1.0));
+    }
+}
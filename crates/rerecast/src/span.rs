@@ -40,6 +40,71 @@ impl Spans {
         let capacity = min_capacity.max(Self::DEFAULT_CAPACITY);
         Self(SlotMap::with_capacity_and_key(capacity))
     }
+
+    /// The number of spans in the collection.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the collection contains no spans.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The number of spans the collection can hold without reallocating.
+    /// Spans that were removed while merging leave holes behind, so this can be much larger than [`Spans::len`]
+    /// until the collection is compacted with [`Spans::compact`].
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
+    /// Iterates over all spans along with their keys, in storage order.
+    /// Use [`Span::next`] and the columns of the [`Heightfield`](crate::Heightfield) to iterate in column order instead.
+    pub fn iter(&self) -> impl Iterator<Item = (SpanKey, &Span)> {
+        self.0.iter()
+    }
+
+    /// Defragments the storage by moving all spans reachable from the given columns next to each other,
+    /// in column order and from bottom to top within each column. Spans not reachable from any column are dropped.
+    ///
+    /// All [`SpanKey`]s are remapped, which includes the given columns and the [`Span::next`] links.
+    /// Keys obtained before the compaction are invalid afterwards.
+    ///
+    /// Called by [`Heightfield::compact_spans`](crate::Heightfield::compact_spans).
+    pub fn compact(&mut self, columns: &mut [Option<SpanKey>]) {
+        let mut compacted = Self(SlotMap::with_capacity_and_key(self.0.len()));
+        self.compact_into(columns, &mut compacted);
+    }
+
+    /// Same as [`Spans::compact`], but moves the spans into the storage of `spare` and leaves the old storage in `spare`.
+    ///
+    /// If `spare` held spans before, their slots are reused in the order they were freed,
+    /// so the spans end up next to each other but not necessarily in column order.
+    pub(crate) fn compact_into(&mut self, columns: &mut [Option<SpanKey>], spare: &mut Spans) {
+        let compacted = &mut spare.0;
+        compacted.clear();
+        for column in columns {
+            let mut previous_key: Option<SpanKey> = None;
+            let mut old_key = *column;
+            while let Some(key) = old_key {
+                let span = &self.0[key];
+                old_key = span.next;
+                let new_key = compacted.insert(Span {
+                    next: None,
+                    ..span.clone()
+                });
+                match previous_key {
+                    Some(previous_key) => compacted[previous_key].next = Some(new_key),
+                    None => *column = Some(new_key),
+                }
+                previous_key = Some(new_key);
+            }
+        }
+        std::mem::swap(self, spare);
+    }
 }
 
 pub(crate) struct SpanBuilder {
@@ -142,6 +207,36 @@ impl AreaType {
     }
 }
 
+/// Decides which [`AreaType`] a span gets when it is merged with an overlapping span during rasterization.
+///
+/// Only used when the ceilings of both spans are within the flag merge threshold (usually `walkable_climb`) of each other.
+/// Otherwise, the area type of the higher span always wins.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum AreaMergePolicy {
+    /// Higher area type values take priority. This is the behavior of the original Recast.
+    #[default]
+    MaxWins,
+    /// The area type of the span being inserted takes priority.
+    NewWins,
+    /// The area type of the span that was already in the heightfield takes priority.
+    OldWins,
+    /// Calls the function with the old and the new area type, in that order, and uses the returned area type.
+    Custom(fn(AreaType, AreaType) -> AreaType),
+}
+
+impl AreaMergePolicy {
+    /// Returns the area type of the merged span.
+    #[inline]
+    pub fn merge(self, old: AreaType, new: AreaType) -> AreaType {
+        match self {
+            Self::MaxWins => old.max(*new).into(),
+            Self::NewWins => new,
+            Self::OldWins => old,
+            Self::Custom(merge) => merge(old, new),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
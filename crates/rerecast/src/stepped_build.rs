@@ -0,0 +1,346 @@
+//! Runs the pipeline of [`build_navmesh_with_rasterizer`](crate::build_navmesh_with_rasterizer) one [`BuildStage`] at a time,
+//! e.g. for an editor that lets users step through a build and inspect what every stage produced.
+
+use std::fmt;
+
+use crate::{
+    BuildContext, BuildNavmeshError, BuildScratch, BuildStage, CompactHeightfield, ContourSet,
+    ConvexVolume, DetailNavmesh, Heightfield, NavmeshConfig, PolygonNavmesh, RasterizationError,
+    RegionSlope,
+    pipeline::{
+        run_compaction, run_contours, run_detail_mesh, run_distance_field, run_erosion,
+        run_filtering, run_polygon_mesh, run_rasterization, run_regions,
+    },
+};
+
+type Rasterizer<'a> =
+    Box<dyn FnOnce(&mut Heightfield) -> Result<(), RasterizationError> + Send + Sync + 'a>;
+
+/// A navmesh build that runs one [`BuildStage`] per call to [`SteppedBuild::step`] and keeps the result of every stage around.
+///
+/// Runs the same stages as [`build_navmesh_with_rasterizer`](crate::build_navmesh_with_rasterizer), so stepping through to the end
+/// builds the same navmesh. In between, the intermediate results can be inspected:
+///
+/// - [`SteppedBuild::heightfield`] after [`BuildStage::Rasterization`] and [`BuildStage::Filtering`],
+/// - [`SteppedBuild::compact_heightfield`] from [`BuildStage::Compaction`] on,
+/// - [`SteppedBuild::contours`] from [`BuildStage::Contours`] on,
+/// - [`SteppedBuild::polygon_mesh`] from [`BuildStage::PolygonMesh`] on,
+/// - [`SteppedBuild::detail_mesh`] after [`BuildStage::DetailMesh`].
+///
+/// Unlike the other entry points, a stepped build holds on to all of these until it is dropped,
+/// so it needs more memory and is meant for debugging and tooling rather than for builds at runtime.
+///
+/// ```
+/// # use rerecast::*;
+/// # use glam::Vec3A;
+/// let config = NavmeshConfig {
+///     aabb: Aabb3d::new(Vec3A::ZERO, [5.0, 5.0, 5.0]),
+///     ..NavmeshConfigBuilder::default().build()
+/// };
+/// let mut build = SteppedBuild::new(config, |heightfield| {
+///     heightfield.rasterize_triangles(&TriMesh::default(), config.walkable_climb)
+/// });
+/// assert_eq!(build.next_stage(), Some(BuildStage::Rasterization));
+///
+/// build.step(&BuildContext::default()).unwrap();
+/// assert!(build.heightfield().is_some());
+/// assert_eq!(build.next_stage(), Some(BuildStage::Filtering));
+///
+/// build.run_to_end(&BuildContext::default()).unwrap();
+/// let (polygon_mesh, detail_mesh) = build.into_navmesh().unwrap();
+/// ```
+pub struct SteppedBuild<'a> {
+    config: NavmeshConfig,
+    volumes: Vec<ConvexVolume>,
+    scratch: BuildScratch,
+    rasterize: Option<Rasterizer<'a>>,
+    next_stage: Option<BuildStage>,
+    heightfield: Option<Heightfield>,
+    compact_heightfield: Option<CompactHeightfield>,
+    slopes: Option<Vec<RegionSlope>>,
+    contours: Option<ContourSet>,
+    polygon_mesh: Option<PolygonNavmesh>,
+    detail_mesh: Option<DetailNavmesh>,
+}
+
+impl fmt::Debug for SteppedBuild<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SteppedBuild")
+            .field("config", &self.config)
+            .field("volumes", &self.volumes)
+            .field("next_stage", &self.next_stage)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> SteppedBuild<'a> {
+    /// Creates a build that lets `rasterize` fill the empty heightfield during [`BuildStage::Rasterization`],
+    /// like [`build_navmesh_with_rasterizer`](crate::build_navmesh_with_rasterizer). No stage runs before [`SteppedBuild::step`] is called.
+    pub fn new(
+        config: NavmeshConfig,
+        rasterize: impl FnOnce(&mut Heightfield) -> Result<(), RasterizationError> + Send + Sync + 'a,
+    ) -> Self {
+        Self {
+            config,
+            volumes: Vec::new(),
+            scratch: BuildScratch::default(),
+            rasterize: Some(Box::new(rasterize)),
+            next_stage: Some(BuildStage::Rasterization),
+            heightfield: None,
+            compact_heightfield: None,
+            slopes: None,
+            contours: None,
+            polygon_mesh: None,
+            detail_mesh: None,
+        }
+    }
+
+    /// Marks the walkable spans within each of the `volumes` once the walkable area is eroded,
+    /// like the volumes passed to [`build_navmesh_with_rasterizer`](crate::build_navmesh_with_rasterizer).
+    pub fn with_volumes(mut self, volumes: impl Into<Vec<ConvexVolume>>) -> Self {
+        self.volumes = volumes.into();
+        self
+    }
+
+    /// The config the navmesh is built with.
+    pub fn config(&self) -> &NavmeshConfig {
+        &self.config
+    }
+
+    /// The stage the next call to [`SteppedBuild::step`] runs, or `None` if the build is done or failed.
+    pub fn next_stage(&self) -> Option<BuildStage> {
+        self.next_stage
+    }
+
+    /// Runs the next stage and returns it, or `None` if there is nothing left to run.
+    ///
+    /// `context` is told that the stage begins, and that the build succeeded once [`BuildStage::DetailMesh`] is done.
+    /// If the stage fails or the build was cancelled, the error is returned and the build cannot be continued,
+    /// while the results of the previous stages stay available.
+    pub fn step(
+        &mut self,
+        context: &BuildContext,
+    ) -> Result<Option<BuildStage>, BuildNavmeshError> {
+        let Some(stage) = self.next_stage.take() else {
+            return Ok(None);
+        };
+        context.begin(stage)?;
+        const MISSING: &str = "The previous stage produces the input of the next one";
+        match stage {
+            BuildStage::Rasterization => {
+                let rasterize = self.rasterize.take().expect(MISSING);
+                self.heightfield = Some(run_rasterization(
+                    &self.config,
+                    &mut self.scratch,
+                    rasterize,
+                )?);
+            }
+            BuildStage::Filtering => {
+                let heightfield = self.heightfield.as_mut().expect(MISSING);
+                run_filtering(heightfield, &self.config, &mut self.scratch);
+            }
+            BuildStage::Compaction => {
+                let heightfield = self.heightfield.take().expect(MISSING);
+                self.compact_heightfield = Some(run_compaction(
+                    heightfield,
+                    &self.config,
+                    &mut self.scratch,
+                )?);
+            }
+            BuildStage::Erosion => {
+                let compact_heightfield = self.compact_heightfield.as_mut().expect(MISSING);
+                run_erosion(
+                    compact_heightfield,
+                    &self.config,
+                    &self.volumes,
+                    &mut self.scratch,
+                );
+            }
+            BuildStage::DistanceField => {
+                let compact_heightfield = self.compact_heightfield.as_mut().expect(MISSING);
+                run_distance_field(compact_heightfield, &self.config, &mut self.scratch);
+            }
+            BuildStage::Regions => {
+                let compact_heightfield = self.compact_heightfield.as_mut().expect(MISSING);
+                self.slopes = run_regions(compact_heightfield, &self.config, &mut self.scratch)?;
+            }
+            BuildStage::Contours => {
+                let compact_heightfield = self.compact_heightfield.as_ref().expect(MISSING);
+                self.contours = Some(run_contours(
+                    compact_heightfield,
+                    &self.config,
+                    &mut self.scratch,
+                ));
+            }
+            BuildStage::PolygonMesh => {
+                // Building the polygons consumes the contours, which should stay inspectable.
+                let contours = self.contours.clone().expect(MISSING);
+                self.polygon_mesh = Some(run_polygon_mesh(
+                    contours,
+                    &self.config,
+                    self.slopes.as_deref(),
+                )?);
+            }
+            BuildStage::DetailMesh => {
+                let polygon_mesh = self.polygon_mesh.as_ref().expect(MISSING);
+                let compact_heightfield = self.compact_heightfield.as_ref().expect(MISSING);
+                self.detail_mesh = Some(run_detail_mesh(
+                    polygon_mesh,
+                    compact_heightfield,
+                    &self.config,
+                )?);
+                context.finish();
+            }
+        }
+        self.next_stage = BuildStage::ALL.get(stage.index() + 1).copied();
+        Ok(Some(stage))
+    }
+
+    /// Runs all remaining stages, see [`SteppedBuild::step`].
+    pub fn run_to_end(&mut self, context: &BuildContext) -> Result<(), BuildNavmeshError> {
+        while self.step(context)?.is_some() {}
+        Ok(())
+    }
+
+    /// The heightfield after [`BuildStage::Rasterization`] and [`BuildStage::Filtering`].
+    /// `None` before the first stage and once [`BuildStage::Compaction`] turned it into the [`SteppedBuild::compact_heightfield`].
+    pub fn heightfield(&self) -> Option<&Heightfield> {
+        self.heightfield.as_ref()
+    }
+
+    /// The compact heightfield from [`BuildStage::Compaction`] on, updated by every stage up to [`BuildStage::Regions`].
+    pub fn compact_heightfield(&self) -> Option<&CompactHeightfield> {
+        self.compact_heightfield.as_ref()
+    }
+
+    /// The contours of the regions from [`BuildStage::Contours`] on.
+    pub fn contours(&self) -> Option<&ContourSet> {
+        self.contours.as_ref()
+    }
+
+    /// The polygon mesh from [`BuildStage::PolygonMesh`] on.
+    pub fn polygon_mesh(&self) -> Option<&PolygonNavmesh> {
+        self.polygon_mesh.as_ref()
+    }
+
+    /// The detail mesh once [`BuildStage::DetailMesh`] is done.
+    pub fn detail_mesh(&self) -> Option<&DetailNavmesh> {
+        self.detail_mesh.as_ref()
+    }
+
+    /// The finished navmesh, or `None` if the build is not done yet or failed.
+    pub fn into_navmesh(self) -> Option<(PolygonNavmesh, DetailNavmesh)> {
+        Some((self.polygon_mesh?, self.detail_mesh?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use glam::{UVec3, Vec2, Vec3A};
+
+    use super::*;
+    use crate::{Aabb3d, AreaType, NavmeshConfigBuilder, TriMesh, build_navmesh_with_rasterizer};
+
+    fn floor() -> (TriMesh, NavmeshConfig) {
+        let mut trimesh = TriMesh::from_parts(
+            vec![
+                Vec3A::new(-5.0, 0.0, -5.0),
+                Vec3A::new(-5.0, 0.5, 5.0),
+                Vec3A::new(5.0, 0.5, 5.0),
+                Vec3A::new(5.0, 0.0, -5.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        let config = NavmeshConfig {
+            aabb: Aabb3d::new(Vec3A::ZERO, [6.0, 2.0, 6.0]),
+            ..NavmeshConfigBuilder {
+                slope_cost: 1.0,
+                ..Default::default()
+            }
+            .build()
+        };
+        trimesh.mark_walkable_triangles(config.walkable_slope_angle);
+        (trimesh, config)
+    }
+
+    #[test]
+    fn stepping_builds_same_navmesh() {
+        let (trimesh, config) = floor();
+        let volume = ConvexVolume {
+            vertices: vec![
+                Vec2::new(0.0, -6.0),
+                Vec2::new(0.0, 6.0),
+                Vec2::new(6.0, 6.0),
+                Vec2::new(6.0, -6.0),
+            ],
+            min_y: -1.0,
+            max_y: 2.0,
+            area: AreaType(3),
+        };
+        let rasterize = |heightfield: &mut Heightfield| {
+            heightfield.rasterize_triangles(&trimesh, config.walkable_climb)
+        };
+        let expected = build_navmesh_with_rasterizer(
+            &config,
+            std::slice::from_ref(&volume),
+            &mut BuildScratch::new(),
+            &BuildContext::default(),
+            rasterize,
+        )
+        .unwrap();
+
+        let reported = Mutex::new(Vec::new());
+        let progress = |stage, _| reported.lock().unwrap().push(stage);
+        let context = BuildContext::default().with_progress(&progress);
+        let mut build = SteppedBuild::new(config, rasterize).with_volumes([volume]);
+        for stage in BuildStage::ALL {
+            assert_eq!(build.next_stage(), Some(stage));
+            assert_eq!(build.step(&context).unwrap(), Some(stage));
+            assert_eq!(reported.lock().unwrap().last(), Some(&stage));
+
+            let inspectable = [
+                build.heightfield().is_some(),
+                build.compact_heightfield().is_some(),
+                build.contours().is_some(),
+                build.polygon_mesh().is_some(),
+                build.detail_mesh().is_some(),
+            ];
+            let expected = match stage {
+                BuildStage::Rasterization | BuildStage::Filtering => {
+                    [true, false, false, false, false]
+                }
+                BuildStage::Compaction
+                | BuildStage::Erosion
+                | BuildStage::DistanceField
+                | BuildStage::Regions => [false, true, false, false, false],
+                BuildStage::Contours => [false, true, true, false, false],
+                BuildStage::PolygonMesh => [false, true, true, true, false],
+                BuildStage::DetailMesh => [false, true, true, true, true],
+            };
+            assert_eq!(inspectable, expected, "after {}", stage.name());
+        }
+        assert_eq!(build.next_stage(), None);
+        assert_eq!(build.step(&context).unwrap(), None);
+        assert_eq!(reported.lock().unwrap().len(), BuildStage::ALL.len() + 1);
+        assert_eq!(build.into_navmesh().unwrap(), expected);
+    }
+
+    #[test]
+    fn failed_step_ends_the_build() {
+        let (trimesh, config) = floor();
+        let mut build = SteppedBuild::new(config, |heightfield| {
+            heightfield.rasterize_triangles(&trimesh, config.walkable_climb)
+        });
+        build.step(&BuildContext::default()).unwrap();
+
+        let cancel = std::sync::atomic::AtomicBool::new(true);
+        let result = build.step(&BuildContext::default().with_cancel(&cancel));
+        assert!(matches!(result, Err(BuildNavmeshError::Cancelled)));
+        assert_eq!(build.next_stage(), None);
+        assert!(build.heightfield().is_some());
+        assert!(build.into_navmesh().is_none());
+    }
+}
@@ -0,0 +1,291 @@
+//! Procedurally generated scenes for stress testing navmesh builds, so that benchmarks and tests can use
+//! standardized inputs of any size instead of only the checked-in reference data.
+
+use glam::{UVec2, UVec3, Vec2, Vec3, Vec3A, Vec3Swizzles as _};
+
+use crate::{Aabb3d, TriMesh};
+
+/// A building with stacked floors, each divided into a grid of rooms connected by doorways.
+/// Each floor is connected to the one above it by a ramp in its first room, and props of random sizes are scattered across all rooms.
+///
+/// The scene spans from the origin to [`StressScene::aabb`] and is fully determined by its fields, including [`StressScene::seed`].
+///
+/// ```
+/// # use rerecast::{NavmeshConfigBuilder, StressScene, build_navmesh};
+/// let scene = StressScene::new(2);
+/// let config = NavmeshConfigBuilder {
+///     aabb: scene.aabb(),
+///     ..Default::default()
+/// }
+/// .build();
+/// let (polygon_mesh, _detail_mesh) = build_navmesh(scene.build(), &config).unwrap();
+/// assert!(polygon_mesh.polygon_count() > 0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StressScene {
+    /// The number of rooms along the x- and z-axis of each floor.
+    pub rooms: UVec2,
+    /// The size of a room along the x- and z-axis, including its walls. Should be at least `2.0 * floor_height`
+    /// so that the ramp fits into the first room. `[Units: wu]`
+    pub room_size: f32,
+    /// The number of stacked floors.
+    pub floors: u32,
+    /// The distance between two floors, which is also the height of the walls. `[Units: wu]`
+    pub floor_height: f32,
+    /// The number of props scattered across all floors.
+    pub props: u32,
+    /// The seed for placing the props. Scenes with the same fields, including the seed, have the same geometry.
+    pub seed: u64,
+}
+
+impl StressScene {
+    /// The width of the doorways between rooms. `[Units: wu]`
+    const DOORWAY_WIDTH: f32 = 2.0;
+    /// The thickness of the walls between rooms. `[Units: wu]`
+    const WALL_THICKNESS: f32 = 0.4;
+    /// The width of the ramps between floors. `[Units: wu]`
+    const RAMP_WIDTH: f32 = 4.0;
+    /// How far props are placed from the walls, so that they never block a doorway. `[Units: wu]`
+    const PROP_MARGIN: f32 = 2.0;
+
+    /// A scene with `size` by `size` rooms on each of two floors and four props per room.
+    pub fn new(size: u32) -> Self {
+        Self {
+            rooms: UVec2::splat(size),
+            room_size: 10.0,
+            floors: 2,
+            floor_height: 4.0,
+            props: size * size * 4,
+            seed: 0,
+        }
+    }
+
+    /// Sets [`StressScene::floors`].
+    pub fn with_floors(mut self, floors: u32) -> Self {
+        self.floors = floors;
+        self
+    }
+
+    /// Sets [`StressScene::props`].
+    pub fn with_props(mut self, props: u32) -> Self {
+        self.props = props;
+        self
+    }
+
+    /// Sets [`StressScene::seed`].
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// The bounds of the whole scene, e.g. for [`NavmeshConfigBuilder::aabb`](crate::NavmeshConfigBuilder::aabb).
+    pub fn aabb(&self) -> Aabb3d {
+        let extent = self.rooms.as_vec2() * self.room_size;
+        Aabb3d {
+            min: Vec3::ZERO,
+            max: Vec3::new(extent.x, self.floors as f32 * self.floor_height, extent.y),
+        }
+    }
+
+    /// The xz-bounds of the ramps between floors, which are also the holes in the floors above them.
+    fn ramp_bounds(&self) -> (Vec2, Vec2) {
+        // Centered in the first room, so that agents can walk around both ends of the ramp.
+        let size = Vec2::new(Self::RAMP_WIDTH, 1.5 * self.floor_height);
+        let min = (Vec2::splat(self.room_size) - size) / 2.0;
+        (min, min + size)
+    }
+
+    /// Generates the geometry of the scene.
+    pub fn build(&self) -> TriMesh {
+        let mut builder = SceneBuilder::default();
+        let extent = self.rooms.as_vec2() * self.room_size;
+        let (ramp_min, ramp_max) = self.ramp_bounds();
+
+        for floor in 0..self.floors {
+            let y = floor as f32 * self.floor_height;
+            if floor == 0 {
+                builder.quad(y, Vec2::ZERO, extent);
+            } else {
+                // Leave a hole for the ramp from the floor below, split into the four quads around it.
+                builder.quad(y, Vec2::ZERO, Vec2::new(ramp_min.x, extent.y));
+                builder.quad(y, Vec2::new(ramp_max.x, 0.0), extent);
+                builder.quad(
+                    y,
+                    Vec2::new(ramp_min.x, 0.0),
+                    Vec2::new(ramp_max.x, ramp_min.y),
+                );
+                builder.quad(
+                    y,
+                    Vec2::new(ramp_min.x, ramp_max.y),
+                    Vec2::new(ramp_max.x, extent.y),
+                );
+            }
+            if floor + 1 < self.floors {
+                builder.ramp(ramp_min, ramp_max, y, y + self.floor_height);
+            }
+            self.walls(&mut builder, y);
+        }
+
+        let mut rng = SplitMix64(self.seed);
+        let inner = self.room_size - 2.0 * Self::PROP_MARGIN;
+        for _ in 0..self.props {
+            let floor = rng.below(self.floors);
+            let room = UVec2::new(rng.below(self.rooms.x), rng.below(self.rooms.y));
+            if room == UVec2::ZERO {
+                // Keep the ramps clear.
+                continue;
+            }
+            let size = Vec2::new(rng.range(0.5, 1.5), rng.range(0.5, 1.5));
+            let height = rng.range(0.3, 1.2);
+            let corner = room.as_vec2() * self.room_size
+                + Self::PROP_MARGIN
+                + Vec2::new(
+                    rng.range(0.0, (inner - size.x).max(0.0)),
+                    rng.range(0.0, (inner - size.y).max(0.0)),
+                );
+            let y = floor as f32 * self.floor_height;
+            builder.cuboid(
+                Vec3::new(corner.x, y, corner.y),
+                Vec3::new(corner.x + size.x, y + height, corner.y + size.y),
+            );
+        }
+
+        TriMesh::from_parts(builder.vertices, builder.indices)
+            .expect("The generated indices are always in bounds")
+    }
+
+    /// Adds the walls between the rooms of the floor at height `y`, with a doorway in the middle of each wall.
+    fn walls(&self, builder: &mut SceneBuilder, y: f32) {
+        let half = Self::WALL_THICKNESS / 2.0;
+        let top = y + self.floor_height;
+        let segments = |start: f32| {
+            let door_min = start + (self.room_size - Self::DOORWAY_WIDTH) / 2.0;
+            let door_max = door_min + Self::DOORWAY_WIDTH;
+            [(start, door_min), (door_max, start + self.room_size)]
+        };
+        for x in 1..self.rooms.x {
+            let wall_x = x as f32 * self.room_size;
+            for z in 0..self.rooms.y {
+                for (min, max) in segments(z as f32 * self.room_size) {
+                    builder.cuboid(
+                        Vec3::new(wall_x - half, y, min),
+                        Vec3::new(wall_x + half, top, max),
+                    );
+                }
+            }
+        }
+        for z in 1..self.rooms.y {
+            let wall_z = z as f32 * self.room_size;
+            for x in 0..self.rooms.x {
+                for (min, max) in segments(x as f32 * self.room_size) {
+                    builder.cuboid(
+                        Vec3::new(min, y, wall_z - half),
+                        Vec3::new(max, top, wall_z + half),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct SceneBuilder {
+    vertices: Vec<Vec3A>,
+    indices: Vec<UVec3>,
+}
+
+impl SceneBuilder {
+    /// Adds a quad with the given corners, wound so that it faces up if the corners go around clockwise when seen from above.
+    fn face(&mut self, corners: [Vec3; 4]) {
+        let base = self.vertices.len() as u32;
+        self.vertices.extend(corners.map(Vec3A::from));
+        self.indices.push(UVec3::new(base, base + 1, base + 2));
+        self.indices.push(UVec3::new(base, base + 2, base + 3));
+    }
+
+    /// Adds an upward facing horizontal quad at height `y`.
+    fn quad(&mut self, y: f32, min: Vec2, max: Vec2) {
+        if min.cmpge(max).any() {
+            return;
+        }
+        self.face([
+            Vec3::new(min.x, y, min.y),
+            Vec3::new(min.x, y, max.y),
+            Vec3::new(max.x, y, max.y),
+            Vec3::new(max.x, y, min.y),
+        ]);
+    }
+
+    /// Adds a ramp rising from `bottom` at `min.y` to `top` at `max.y` along the z-axis.
+    fn ramp(&mut self, min: Vec2, max: Vec2, bottom: f32, top: f32) {
+        self.face([
+            Vec3::new(min.x, bottom, min.y),
+            Vec3::new(min.x, top, max.y),
+            Vec3::new(max.x, top, max.y),
+            Vec3::new(max.x, bottom, min.y),
+        ]);
+    }
+
+    /// Adds a closed axis-aligned box.
+    fn cuboid(&mut self, min: Vec3, max: Vec3) {
+        let corner = |x: f32, y: f32, z: f32| Vec3::new(x, y, z);
+        let (a, b) = (min, max);
+        // Top and bottom.
+        self.quad(b.y, a.xz(), b.xz());
+        self.face([
+            corner(a.x, a.y, a.z),
+            corner(b.x, a.y, a.z),
+            corner(b.x, a.y, b.z),
+            corner(a.x, a.y, b.z),
+        ]);
+        // Sides.
+        self.face([
+            corner(a.x, a.y, a.z),
+            corner(a.x, b.y, a.z),
+            corner(b.x, b.y, a.z),
+            corner(b.x, a.y, a.z),
+        ]);
+        self.face([
+            corner(b.x, a.y, b.z),
+            corner(b.x, b.y, b.z),
+            corner(a.x, b.y, b.z),
+            corner(a.x, a.y, b.z),
+        ]);
+        self.face([
+            corner(a.x, a.y, b.z),
+            corner(a.x, b.y, b.z),
+            corner(a.x, b.y, a.z),
+            corner(a.x, a.y, a.z),
+        ]);
+        self.face([
+            corner(b.x, a.y, a.z),
+            corner(b.x, b.y, a.z),
+            corner(b.x, b.y, b.z),
+            corner(b.x, a.y, b.z),
+        ]);
+    }
+}
+
+/// A tiny deterministic random number generator, so that scenes are reproducible without pulling in a dependency.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A random float in `[min, max)`.
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        let unit = (self.next() >> 40) as f32 / (1_u64 << 24) as f32;
+        min + unit * (max - min)
+    }
+
+    /// A random integer in `[0, max)`, or `0` if `max` is `0`.
+    fn below(&mut self, max: u32) -> u32 {
+        (self.next() % max.max(1) as u64) as u32
+    }
+}
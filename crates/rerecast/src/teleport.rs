@@ -0,0 +1,466 @@
+//! Validating the destination of a teleport, e.g. of a blink ability or a respawn, before moving an agent there.
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use glam::{Vec2, Vec3, Vec3Swizzles as _};
+use thiserror::Error;
+
+use crate::{
+    EdgeConnection, NavmeshConfigBuilder, PolyId, PolygonNavmesh, TilePolygon, TiledNavmesh,
+    placement::{closest_point_on_outline, fan_height},
+    walkability::contains_point,
+};
+
+/// The size of an agent teleported with [`PolygonNavmesh::validate_teleport`]. `[Units: wu]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TeleportAgent {
+    /// How far the destination may be off the navmesh on the xz-plane to still be moved onto it.
+    pub radius: f32,
+    /// How much room the agent needs above the navmesh.
+    pub height: f32,
+    /// How far the destination may be above or below the navmesh.
+    pub max_climb: f32,
+}
+
+impl From<&NavmeshConfigBuilder> for TeleportAgent {
+    fn from(config: &NavmeshConfigBuilder) -> Self {
+        Self {
+            radius: config.agent_radius,
+            height: config.agent_height,
+            max_climb: config.agent_max_climb,
+        }
+    }
+}
+
+/// A destination accepted by [`PolygonNavmesh::validate_teleport`] or [`TiledNavmesh::validate_teleport`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TeleportDestination<P = PolyId> {
+    /// The polygon the destination lies on, a [`TilePolygon`] for a [`TiledNavmesh`].
+    pub polygon: P,
+    /// Where to move the agent to, on the surface of [`Self::polygon`].
+    pub position: Vec3,
+    /// Whether the destination had to be moved on the xz-plane to get onto the navmesh.
+    pub corrected: bool,
+}
+
+/// Errors that can occur when validating a teleport with [`PolygonNavmesh::validate_teleport`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeleportError {
+    /// No position on the navmesh with enough room for the agent is within its radius and climb of the destination.
+    #[error("No position on the navmesh with enough room for the agent is near the destination")]
+    OffNavmesh,
+    /// The destination lies on a part of the navmesh that can't be reached from where the agent teleports from.
+    #[error("The destination is not connected to the navmesh the agent teleports from")]
+    Unreachable,
+}
+
+impl PolygonNavmesh {
+    /// Checks whether an agent standing at `from` can be teleported to `to`, both in world space.
+    ///
+    /// The destination has to lie on the navmesh within [`TeleportAgent::max_climb`] of its surface,
+    /// and no other floor of the navmesh may hang less than [`TeleportAgent::height`] above it.
+    /// Destinations up to [`TeleportAgent::radius`] off the navmesh are moved onto its closest edge, which covers
+    /// destinations right next to walls, as the navmesh ends an agent radius away from them.
+    ///
+    /// If `from` lies on the navmesh, the destination also has to be connected to it through neighboring polygons
+    /// or baked off-mesh connections, so that agents don't end up on islands they can never walk off of.
+    ///
+    /// Obstacles carved by a [`TileCache`](crate::TileCache) only exist in its [`TiledNavmesh`],
+    /// use [`TiledNavmesh::validate_teleport`] to keep agents out of them.
+    pub fn validate_teleport(
+        &self,
+        from: Vec3,
+        to: Vec3,
+        agent: TeleportAgent,
+    ) -> Result<TeleportDestination, TeleportError> {
+        let nearby = self.polygons_near(to, agent.radius, |polygon| polygon);
+        let (polygon, position) = closest_destination(&nearby, to, agent)?;
+        let start = self.standing_polygon(from, agent);
+        if start.is_some_and(|(start, _)| !self.is_connected(start, polygon)) {
+            return Err(TeleportError::Unreachable);
+        }
+        Ok(TeleportDestination {
+            polygon,
+            position,
+            corrected: position.xz() != to.xz(),
+        })
+    }
+
+    /// The polygons whose bounds on the xz-plane come within `radius` of `point`, identified by `id`.
+    /// These are the only polygons a teleport to `point` can end up on or be blocked by.
+    fn polygons_near<P>(
+        &self,
+        point: Vec3,
+        radius: f32,
+        id: impl Fn(PolyId) -> P,
+    ) -> Vec<NearbyPolygon<P>> {
+        self.polygon_ids()
+            .filter_map(|polygon| {
+                let vertices = self.outline(polygon);
+                let outline = vertices
+                    .iter()
+                    .map(|vertex| vertex.xz())
+                    .collect::<Vec<_>>();
+                let (min, max) = outline.iter().fold(
+                    (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+                    |(min, max), vertex| (min.min(*vertex), max.max(*vertex)),
+                );
+                let closest = point.xz().clamp(min, max);
+                (closest.distance(point.xz()) <= radius).then(|| NearbyPolygon {
+                    polygon: id(polygon),
+                    vertices,
+                    outline,
+                })
+            })
+            .collect()
+    }
+
+    /// The polygon that `from` stands on within the agent's climb, along with the vertical distance to it.
+    fn standing_polygon(&self, from: Vec3, agent: TeleportAgent) -> Option<(PolyId, f32)> {
+        self.polygon_ids()
+            .filter_map(|polygon| {
+                if !contains_point(&self.outline_xz(polygon), from.xz()) {
+                    return None;
+                }
+                let height = fan_height(&self.outline(polygon), from.xz())?;
+                let distance = (height - from.y).abs();
+                (distance <= agent.max_climb).then_some((polygon, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// The polygons directly reachable from `polygon` within this mesh, through neighboring polygons and baked off-mesh connections.
+    fn reachable_from(&self, polygon: PolyId) -> impl Iterator<Item = PolyId> + '_ {
+        self.neighbors(polygon)
+            .filter_map(|connection| match connection {
+                EdgeConnection::Polygon(neighbor) => Some(neighbor),
+                EdgeConnection::Portal(_) | EdgeConnection::Border => None,
+            })
+            .chain(
+                self.off_mesh_connections_from(polygon)
+                    .map(|(_, neighbor)| neighbor),
+            )
+    }
+
+    /// Whether `goal` can be reached from `start` through neighboring polygons and baked off-mesh connections.
+    fn is_connected(&self, start: PolyId, goal: PolyId) -> bool {
+        is_connected(start, goal, |polygon| {
+            self.reachable_from(polygon).collect::<Vec<_>>()
+        })
+    }
+}
+
+impl TiledNavmesh {
+    /// Same as [`PolygonNavmesh::validate_teleport`], but across all tiles.
+    ///
+    /// Destinations may lie in a different tile than `from`, as long as they are connected through the portal edges
+    /// that [`TiledNavmesh::links`] stitch together. This also works on the navmesh of a [`TileCache`](crate::TileCache),
+    /// whose carved obstacles are holes that destinations are moved out of like out of any other unwalkable space.
+    pub fn validate_teleport(
+        &self,
+        from: Vec3,
+        to: Vec3,
+        agent: TeleportAgent,
+    ) -> Result<TeleportDestination<TilePolygon>, TeleportError> {
+        let nearby = self
+            .tiles
+            .iter()
+            .enumerate()
+            .flat_map(|(tile, navmesh_tile)| {
+                navmesh_tile
+                    .polygon_mesh
+                    .polygons_near(to, agent.radius, |polygon| TilePolygon { tile, polygon })
+            })
+            .collect::<Vec<_>>();
+        let (polygon, position) = closest_destination(&nearby, to, agent)?;
+
+        let start = self
+            .tiles
+            .iter()
+            .enumerate()
+            .filter_map(|(tile, navmesh_tile)| {
+                let (polygon, distance) =
+                    navmesh_tile.polygon_mesh.standing_polygon(from, agent)?;
+                Some((TilePolygon { tile, polygon }, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(polygon, _)| polygon);
+        if let Some(start) = start {
+            let mut links: HashMap<TilePolygon, Vec<TilePolygon>> = HashMap::new();
+            for link in &self.links {
+                let from = TilePolygon {
+                    tile: link.from_tile,
+                    polygon: link.edge.polygon,
+                };
+                links.entry(from).or_default().push(link.to);
+            }
+            let connected = is_connected(start, polygon, |current| {
+                let mesh = &self.tiles[current.tile].polygon_mesh;
+                mesh.reachable_from(current.polygon)
+                    .map(|polygon| TilePolygon {
+                        tile: current.tile,
+                        polygon,
+                    })
+                    .chain(links.get(&current).into_iter().flatten().copied())
+                    .collect()
+            });
+            if !connected {
+                return Err(TeleportError::Unreachable);
+            }
+        }
+
+        Ok(TeleportDestination {
+            polygon,
+            position,
+            corrected: position.xz() != to.xz(),
+        })
+    }
+}
+
+/// A polygon near the destination of a teleport, along with its vertices in world space.
+struct NearbyPolygon<P> {
+    polygon: P,
+    vertices: Vec<Vec3>,
+    outline: Vec<Vec2>,
+}
+
+/// The point closest to `to` within the agent's radius and climb on any of the `nearby` polygons that has enough headroom.
+fn closest_destination<P: Copy + PartialEq>(
+    nearby: &[NearbyPolygon<P>],
+    to: Vec3,
+    agent: TeleportAgent,
+) -> Result<(P, Vec3), TeleportError> {
+    nearby
+        .iter()
+        .filter_map(|candidate| {
+            let point = closest_point_on_outline(&candidate.vertices, to);
+            let horizontal = point.xz().distance(to.xz());
+            let vertical = (point.y - to.y).abs();
+            (horizontal <= agent.radius
+                && vertical <= agent.max_climb
+                && has_headroom(nearby, candidate.polygon, point, agent))
+            .then_some((candidate.polygon, point, horizontal, vertical))
+        })
+        .min_by(|a, b| a.2.total_cmp(&b.2).then(a.3.total_cmp(&b.3)))
+        .map(|(polygon, point, _, _)| (polygon, point))
+        .ok_or(TeleportError::OffNavmesh)
+}
+
+/// Whether no polygon other than `polygon` lies above `point` between the agent's climb and height.
+/// Polygons within the climb are part of the same floor, e.g. the next step of a staircase.
+fn has_headroom<P: PartialEq>(
+    nearby: &[NearbyPolygon<P>],
+    polygon: P,
+    point: Vec3,
+    agent: TeleportAgent,
+) -> bool {
+    let (min, max) = (point.y + agent.max_climb, point.y + agent.height);
+    nearby
+        .iter()
+        .filter(|other| other.polygon != polygon)
+        .filter(|other| contains_point(&other.outline, point.xz()))
+        .filter_map(|other| fan_height(&other.vertices, point.xz()))
+        .all(|height| height <= min || height >= max)
+}
+
+/// Whether `goal` can be reached from `start` by repeatedly moving to the polygons returned by `neighbors`.
+fn is_connected<P: Copy + Eq + Hash>(start: P, goal: P, neighbors: impl Fn(P) -> Vec<P>) -> bool {
+    let mut visited = HashSet::from([start]);
+    let mut stack = vec![start];
+    while let Some(polygon) = stack.pop() {
+        if polygon == goal {
+            return true;
+        }
+        for neighbor in neighbors(polygon) {
+            if visited.insert(neighbor) {
+                stack.push(neighbor);
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{U16Vec3, UVec2, UVec3, Vec3A};
+
+    use super::*;
+    use crate::{
+        Aabb3d, AreaType, BuildContext, NavmeshConfig, Obstacle, RegionId, TileCache, TriMesh,
+    };
+
+    const AGENT: TeleportAgent = TeleportAgent {
+        radius: 0.5,
+        height: 2.0,
+        max_climb: 0.5,
+    };
+
+    /// Two 4x4 quads next to each other along the x-axis, and an unconnected 2x4 quad one unit above the first half of them.
+    fn two_floors() -> PolygonNavmesh {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 0, 0),
+                U16Vec3::new(0, 0, 4),
+                U16Vec3::new(4, 0, 4),
+                U16Vec3::new(4, 0, 0),
+                U16Vec3::new(8, 0, 4),
+                U16Vec3::new(8, 0, 0),
+                U16Vec3::new(0, 1, 0),
+                U16Vec3::new(0, 1, 4),
+                U16Vec3::new(2, 1, 4),
+                U16Vec3::new(2, 1, 0),
+            ],
+            polygons: vec![0, 1, 2, 3, 3, 2, 4, 5, 6, 7, 8, 9],
+            polygon_neighbors: vec![N, N, 1, N, 0, N, N, N, N, N, N, N],
+            flags: vec![0; 3],
+            regions: vec![RegionId::from(1); 3],
+            areas: vec![AreaType::DEFAULT_WALKABLE; 3],
+            max_vertices_per_polygon: 4,
+            aabb: Aabb3d {
+                min: Vec3::ZERO,
+                max: Vec3::new(8.0, 1.0, 4.0),
+            },
+            cell_size: 1.0,
+            cell_height: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn destinations_are_snapped_onto_the_navmesh() {
+        let navmesh = two_floors();
+        let from = Vec3::new(6.0, 0.0, 2.0);
+
+        let destination = navmesh
+            .validate_teleport(from, Vec3::new(7.0, 0.3, 1.0), AGENT)
+            .unwrap();
+        assert_eq!(destination.polygon, PolyId::from(1));
+        assert_eq!(destination.position, Vec3::new(7.0, 0.0, 1.0));
+        assert!(!destination.corrected);
+
+        let destination = navmesh
+            .validate_teleport(from, Vec3::new(8.3, 0.0, 2.0), AGENT)
+            .unwrap();
+        assert_eq!(destination.position, Vec3::new(8.0, 0.0, 2.0));
+        assert!(destination.corrected);
+
+        assert_eq!(
+            navmesh.validate_teleport(from, Vec3::new(9.0, 0.0, 2.0), AGENT),
+            Err(TeleportError::OffNavmesh)
+        );
+        assert_eq!(
+            navmesh.validate_teleport(from, Vec3::new(7.0, 3.0, 1.0), AGENT),
+            Err(TeleportError::OffNavmesh)
+        );
+    }
+
+    #[test]
+    fn low_ceilings_and_islands_are_rejected() {
+        let navmesh = two_floors();
+        let from = Vec3::new(6.0, 0.0, 2.0);
+
+        // The upper floor hangs a unit above the first half of the lower one.
+        assert_eq!(
+            navmesh.validate_teleport(from, Vec3::new(1.0, 0.0, 2.0), AGENT),
+            Err(TeleportError::OffNavmesh)
+        );
+        let short = TeleportAgent {
+            height: 0.9,
+            ..AGENT
+        };
+        assert!(
+            navmesh
+                .validate_teleport(from, Vec3::new(1.0, 0.0, 2.0), short)
+                .is_ok()
+        );
+        assert!(
+            navmesh
+                .validate_teleport(from, Vec3::new(3.0, 0.0, 2.0), AGENT)
+                .is_ok()
+        );
+
+        // The upper floor is not connected to the lower one.
+        assert_eq!(
+            navmesh.validate_teleport(from, Vec3::new(1.0, 1.0, 2.0), AGENT),
+            Err(TeleportError::Unreachable)
+        );
+        let flying = Vec3::new(6.0, 5.0, 2.0);
+        let destination = navmesh
+            .validate_teleport(flying, Vec3::new(1.0, 1.0, 2.0), AGENT)
+            .unwrap();
+        assert_eq!(destination.polygon, PolyId::from(2));
+    }
+
+    /// Two floors on either side of a gap along the x-axis, built as a grid of 3x3 tiles.
+    fn split_floor() -> (TriMesh, NavmeshConfig) {
+        let quad = |min_x: f32, max_x: f32| {
+            TriMesh::from_parts(
+                vec![
+                    Vec3A::new(min_x, 0.0, -10.0),
+                    Vec3A::new(min_x, 0.0, 10.0),
+                    Vec3A::new(max_x, 0.0, 10.0),
+                    Vec3A::new(max_x, 0.0, -10.0),
+                ],
+                vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+            )
+            .unwrap()
+        };
+        let mut trimesh = quad(-10.0, -2.0);
+        trimesh.extend(&quad(2.0, 10.0));
+        let config = NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::ZERO, [10.0, 2.0, 10.0]),
+            tile_size: 24,
+            ..Default::default()
+        }
+        .build();
+        (trimesh, config)
+    }
+
+    #[test]
+    fn teleports_follow_portals_between_tiles() {
+        let (trimesh, config) = split_floor();
+        let navmesh = TiledNavmesh::build(trimesh, &config, &BuildContext::default()).unwrap();
+        let from = Vec3::new(-8.0, 0.0, -8.0);
+
+        let destination = navmesh
+            .validate_teleport(from, Vec3::new(-4.0, 0.0, 8.0), AGENT)
+            .unwrap();
+        let start = navmesh.tile_index(UVec2::ZERO).unwrap();
+        assert_ne!(destination.polygon.tile, start);
+        assert!(!destination.corrected);
+
+        assert_eq!(
+            navmesh.validate_teleport(from, Vec3::new(8.0, 0.0, 8.0), AGENT),
+            Err(TeleportError::Unreachable)
+        );
+    }
+
+    #[test]
+    fn carved_obstacles_are_holes_for_teleports() {
+        let (trimesh, config) = split_floor();
+        let mut cache = TileCache::build(trimesh, &config, &BuildContext::default()).unwrap();
+        let from = Vec3::new(-8.0, 0.0, -8.0);
+        let inside = Vec3::new(-6.0, 0.0, 0.0);
+        assert!(
+            cache
+                .navmesh()
+                .validate_teleport(from, inside, AGENT)
+                .is_ok()
+        );
+
+        cache.add_obstacle(Obstacle::Cylinder {
+            base: inside,
+            radius: 1.0,
+            height: 1.0,
+        });
+        cache.update().unwrap();
+        assert_eq!(
+            cache.navmesh().validate_teleport(from, inside, AGENT),
+            Err(TeleportError::OffNavmesh)
+        );
+    }
+}
@@ -0,0 +1,497 @@
+//! Temporary obstacles like crates or turrets that are carved into a [`TiledNavmesh`] at runtime without rasterizing any geometry again.
+//!
+//! A [`TileCache`] keeps the eroded [`CompactHeightfield`] of every tile around, similar to the layers of Detour's `dtTileCache`.
+//! When obstacles are added or removed, only the tiles they overlap are rebuilt, starting at the distance field of the stored heightfield.
+//!
+//! With the `serialize` feature, a whole [`TileCache`] can be saved, or only its [`TileCacheObstacles`],
+//! which is all a saved game needs to restore the carved navmesh on top of a freshly built cache.
+
+use glam::{UVec2, Vec3};
+use slotmap::SlotMap;
+
+use crate::{
+    Aabb3d, AreaType, BuildContext, BuildScratch, CompactHeightfield, Heightfield, NavmeshConfig,
+    PolygonNavmesh, RasterizationError, TiledNavmesh, TiledNavmeshError, TriMesh,
+    tiled::{bin_triangles, build_tile_from_layer, build_tile_layer, tile_counts, tile_progress},
+};
+
+slotmap::new_key_type! {
+    /// A key for an obstacle in a [`TileCache`].
+    pub struct ObstacleId;
+}
+
+/// A temporary obstacle carved into a [`TileCache`]. All coordinates are in world space.
+///
+/// Obstacles are widened by the agent radius, just like the static geometry is during erosion.
+/// They carve out every walkable span whose floor lies within them or at most [`NavmeshConfig::walkable_climb`] below them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum Obstacle {
+    /// A vertical cylinder, e.g. a barrel or a turret.
+    Cylinder {
+        /// The center of the bottom of the cylinder.
+        base: Vec3,
+        /// The radius of the cylinder on the xz-plane.
+        radius: f32,
+        /// The height of the cylinder above `base`.
+        height: f32,
+    },
+    /// An axis-aligned box, e.g. a crate.
+    Box(Aabb3d),
+}
+
+impl Obstacle {
+    /// The bounds of the obstacle.
+    pub fn bounds(&self) -> Aabb3d {
+        match *self {
+            Obstacle::Cylinder {
+                base,
+                radius,
+                height,
+            } => Aabb3d {
+                min: base - Vec3::new(radius, 0.0, radius),
+                max: base + Vec3::new(radius, height, radius),
+            },
+            Obstacle::Box(aabb) => aabb,
+        }
+    }
+
+    /// The obstacle widened by `radius` on the xz-plane and extended downwards by `climb`.
+    fn expanded(&self, radius: f32, climb: f32) -> Self {
+        match *self {
+            Obstacle::Cylinder {
+                base,
+                radius: own_radius,
+                height,
+            } => Obstacle::Cylinder {
+                base: base - Vec3::Y * climb,
+                radius: own_radius + radius,
+                height: height + climb,
+            },
+            Obstacle::Box(aabb) => Obstacle::Box(Aabb3d {
+                min: aabb.min - Vec3::new(radius, climb, radius),
+                max: aabb.max + Vec3::new(radius, 0.0, radius),
+            }),
+        }
+    }
+
+    /// Marks the spans within the obstacle as [`AreaType::NOT_WALKABLE`].
+    fn carve(&self, layer: &mut CompactHeightfield) {
+        match *self {
+            Obstacle::Cylinder {
+                base,
+                radius,
+                height,
+            } => layer.mark_cylinder_area(base, radius, height, AreaType::NOT_WALKABLE),
+            Obstacle::Box(aabb) => layer.mark_box_area(aabb, AreaType::NOT_WALKABLE),
+        }
+    }
+}
+
+/// A [`TiledNavmesh`] that temporary [`Obstacle`]s can be carved into.
+///
+/// Build it with [`TileCache::build`], then add and remove obstacles with [`TileCache::add_obstacle`] and
+/// [`TileCache::remove_obstacle`]. These only record the change; [`TileCache::update`] rebuilds the affected tiles.
+/// This way, many obstacles changing at once only rebuild every tile a single time.
+///
+/// Rebuilding a tile skips rasterization, filtering and erosion, which makes it much cheaper than
+/// [`TiledNavmesh::rebuild_tiles_premarked`], at the cost of keeping the [`CompactHeightfield`] of every tile in memory.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileCache {
+    navmesh: TiledNavmesh,
+    config: NavmeshConfig,
+    layers: Vec<CompactHeightfield>,
+    obstacles: SlotMap<ObstacleId, Obstacle>,
+    dirty: Vec<bool>,
+}
+
+impl TileCache {
+    /// Builds the tiles from world-space geometry like [`TiledNavmesh::build`] does, keeping their heightfields for carving obstacles.
+    pub fn build(
+        mut trimesh: TriMesh,
+        config: &NavmeshConfig,
+        context: &BuildContext,
+    ) -> Result<Self, TiledNavmeshError> {
+        trimesh.mark_walkable_triangles(config.walkable_slope_angle);
+        Self::build_premarked(trimesh, config, context)
+    }
+
+    /// Same as [`TileCache::build`], but uses the area types of `trimesh` as they are
+    /// instead of marking triangles as walkable by their slope.
+    pub fn build_premarked(
+        trimesh: TriMesh,
+        config: &NavmeshConfig,
+        context: &BuildContext,
+    ) -> Result<Self, TiledNavmeshError> {
+        Self::build_premarked_with_rasterizer(trimesh, config, context, |_| Ok(()))
+    }
+
+    /// Same as [`TileCache::build_premarked`], but calls `rasterize` on the heightfield of every tile
+    /// after its triangles were rasterized, like [`TiledNavmesh::build_premarked_with_rasterizer`].
+    pub fn build_premarked_with_rasterizer(
+        trimesh: TriMesh,
+        config: &NavmeshConfig,
+        context: &BuildContext,
+        rasterize: impl Fn(&mut Heightfield) -> Result<(), RasterizationError>,
+    ) -> Result<Self, TiledNavmeshError> {
+        let tile_counts = tile_counts(config)?;
+        let mut navmesh = TiledNavmesh::with_empty_tiles(config)?;
+        let mut scratch = BuildScratch::new();
+        let mut layers = Vec::with_capacity(navmesh.tiles.len());
+        let tile_count = navmesh.tiles.len();
+        for (index, triangles) in bin_triangles(&trimesh, config, tile_counts)
+            .iter()
+            .enumerate()
+        {
+            let coord = navmesh.tiles[index].coord;
+            let progress = tile_progress(context, index, tile_count);
+            let tile_context = context.with_progress(&progress);
+            let layer = build_tile_layer(
+                &trimesh,
+                triangles,
+                coord,
+                config,
+                &mut scratch,
+                &tile_context,
+                &rasterize,
+            )?;
+            navmesh.tiles[index] =
+                build_tile_from_layer(layer.clone(), coord, config, &mut scratch, &tile_context)?;
+            layers.push(layer);
+        }
+        context.finish();
+        let dirty = vec![true; navmesh.tiles.len()];
+        navmesh.restitch(&dirty, config.walkable_climb);
+        Ok(Self {
+            dirty: vec![false; navmesh.tiles.len()],
+            navmesh,
+            config: *config,
+            layers,
+            obstacles: SlotMap::with_key(),
+        })
+    }
+
+    /// The navmesh with all obstacles carved into it as of the last [`TileCache::update`].
+    pub fn navmesh(&self) -> &TiledNavmesh {
+        &self.navmesh
+    }
+
+    /// The polygons of the tile at `coord`, e.g. to set their [flags](PolygonNavmesh::flags) after an update.
+    ///
+    /// Changes are lost once the tile is rebuilt by [`TileCache::update`]. Returns `None` if `coord` is outside of the grid.
+    pub fn polygon_mesh_mut(&mut self, coord: UVec2) -> Option<&mut PolygonNavmesh> {
+        let index = self.navmesh.tile_index(coord)?;
+        Some(&mut self.navmesh.tiles[index].polygon_mesh)
+    }
+
+    /// The config the navmesh was built with.
+    pub fn config(&self) -> &NavmeshConfig {
+        &self.config
+    }
+
+    /// Adds an obstacle and returns the key to remove it with. It is carved into the navmesh on the next [`TileCache::update`].
+    pub fn add_obstacle(&mut self, obstacle: Obstacle) -> ObstacleId {
+        self.mark_dirty(&obstacle);
+        self.obstacles.insert(obstacle)
+    }
+
+    /// Removes an obstacle, returning it if it was still present. The navmesh is restored on the next [`TileCache::update`].
+    pub fn remove_obstacle(&mut self, id: ObstacleId) -> Option<Obstacle> {
+        let obstacle = self.obstacles.remove(id)?;
+        self.mark_dirty(&obstacle);
+        Some(obstacle)
+    }
+
+    /// The obstacle with the given key, if it is still present.
+    pub fn obstacle(&self, id: ObstacleId) -> Option<&Obstacle> {
+        self.obstacles.get(id)
+    }
+
+    /// All obstacles along with their keys.
+    pub fn obstacles(&self) -> impl Iterator<Item = (ObstacleId, &Obstacle)> {
+        self.obstacles.iter()
+    }
+
+    /// A copy of the current obstacles, keeping their keys. Save it to restore them later with [`TileCache::restore_obstacles`].
+    pub fn saved_obstacles(&self) -> TileCacheObstacles {
+        TileCacheObstacles {
+            obstacles: self.obstacles.clone(),
+        }
+    }
+
+    /// Replaces all obstacles with saved ones. They are carved into the navmesh on the next [`TileCache::update`].
+    ///
+    /// The obstacles keep the keys they had when they were saved, so [`ObstacleId`]s stored alongside them stay valid.
+    /// Since carving only depends on the set of obstacles, a cache built from the same geometry and config ends up
+    /// with exactly the navmesh it had when the obstacles were saved.
+    pub fn restore_obstacles(&mut self, saved: TileCacheObstacles) {
+        let previous = std::mem::replace(&mut self.obstacles, saved.obstacles);
+        for obstacle in previous.values() {
+            self.mark_dirty(obstacle);
+        }
+        let restored = self.obstacles.values().copied().collect::<Vec<_>>();
+        for obstacle in &restored {
+            self.mark_dirty(obstacle);
+        }
+    }
+
+    /// Whether obstacles were added or removed since the last [`TileCache::update`].
+    pub fn needs_update(&self) -> bool {
+        self.dirty.contains(&true)
+    }
+
+    /// Rebuilds the tiles overlapping the obstacles that were added or removed since the last update,
+    /// and stitches them to their neighbors again. Returns the coordinates of the rebuilt tiles.
+    ///
+    /// If any tile fails to build, the navmesh is left unchanged and the tiles are tried again on the next update.
+    pub fn update(&mut self) -> Result<Vec<UVec2>, TiledNavmeshError> {
+        if !self.needs_update() {
+            return Ok(Vec::new());
+        }
+        let radius = self.config.walkable_radius as f32 * self.config.cell_size;
+        let climb = self.config.walkable_climb as f32 * self.config.cell_height;
+        let obstacles = self
+            .obstacles
+            .values()
+            .map(|obstacle| obstacle.expanded(radius, climb))
+            .collect::<Vec<_>>();
+
+        let mut scratch = BuildScratch::new();
+        let mut rebuilt = Vec::new();
+        for (index, layer) in self.layers.iter().enumerate() {
+            if !self.dirty[index] {
+                continue;
+            }
+            let coord = self.navmesh.tiles[index].coord;
+            let mut layer = layer.clone();
+            for obstacle in &obstacles {
+                if obstacle.bounds().intersects(&layer.aabb) {
+                    obstacle.carve(&mut layer);
+                }
+            }
+            rebuilt.push((
+                index,
+                build_tile_from_layer(
+                    layer,
+                    coord,
+                    &self.config,
+                    &mut scratch,
+                    &BuildContext::default(),
+                )?,
+            ));
+        }
+
+        let coords = rebuilt.iter().map(|(_, tile)| tile.coord).collect();
+        for (index, tile) in rebuilt {
+            self.navmesh.tiles[index] = tile;
+        }
+        self.navmesh
+            .restitch(&self.dirty, self.config.walkable_climb);
+        self.dirty.fill(false);
+        Ok(coords)
+    }
+
+    /// Marks the tiles whose heightfield the obstacle overlaps, including the agent radius it is widened by.
+    fn mark_dirty(&mut self, obstacle: &Obstacle) {
+        let radius = self.config.walkable_radius as f32 * self.config.cell_size;
+        let mut bounds = obstacle.bounds();
+        bounds.min -= Vec3::new(radius, 0.0, radius);
+        bounds.max += Vec3::new(radius, 0.0, radius);
+        let coords = self
+            .navmesh
+            .tiles_overlapping(&bounds, &self.config)
+            .collect::<Vec<_>>();
+        for coord in coords {
+            if let Some(index) = self.navmesh.tile_index(coord) {
+                self.dirty[index] = true;
+            }
+        }
+    }
+}
+
+/// The obstacles of a [`TileCache`] along with their keys, as returned by [`TileCache::saved_obstacles`].
+///
+/// Unlike the whole cache, this does not contain any heightfields, so it is small enough to be stored in a saved game.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileCacheObstacles {
+    obstacles: SlotMap<ObstacleId, Obstacle>,
+}
+
+impl TileCacheObstacles {
+    /// The saved obstacles along with their keys.
+    pub fn iter(&self) -> impl Iterator<Item = (ObstacleId, &Obstacle)> {
+        self.obstacles.iter()
+    }
+
+    /// The number of saved obstacles.
+    pub fn len(&self) -> usize {
+        self.obstacles.len()
+    }
+
+    /// Whether no obstacles were saved.
+    pub fn is_empty(&self) -> bool {
+        self.obstacles.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3A};
+
+    use super::*;
+    use crate::{EdgeConnection, NavmeshConfigBuilder};
+
+    fn config() -> NavmeshConfig {
+        NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::ZERO, [10.0, 2.0, 10.0]),
+            tile_size: 24,
+            ..Default::default()
+        }
+        .build()
+    }
+
+    fn floor() -> TriMesh {
+        TriMesh::from_parts(
+            vec![
+                Vec3A::new(-10.0, 0.0, -10.0),
+                Vec3A::new(-10.0, 0.0, 10.0),
+                Vec3A::new(10.0, 0.0, 10.0),
+                Vec3A::new(10.0, 0.0, -10.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap()
+    }
+
+    /// The number of polygon edges that border unwalkable space, i.e. the amount of holes and outlines.
+    fn border_edges(navmesh: &TiledNavmesh) -> usize {
+        navmesh
+            .tiles
+            .iter()
+            .map(|tile| {
+                let mesh = &tile.polygon_mesh;
+                mesh.polygon_ids()
+                    .flat_map(|polygon| mesh.edges(polygon))
+                    .filter(|&edge| mesh.edge_connection(edge) == EdgeConnection::Border)
+                    .count()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn cache_without_obstacles_matches_a_tiled_build() {
+        let config = config();
+        let cache = TileCache::build(floor(), &config, &BuildContext::default()).unwrap();
+        let navmesh = TiledNavmesh::build(floor(), &config, &BuildContext::default()).unwrap();
+        assert_eq!(cache.navmesh().tiles, navmesh.tiles);
+    }
+
+    #[test]
+    fn obstacles_are_carved_and_restored() {
+        let config = config();
+        let mut cache = TileCache::build(floor(), &config, &BuildContext::default()).unwrap();
+        let original = cache.navmesh().clone();
+
+        let id = cache.add_obstacle(Obstacle::Cylinder {
+            base: Vec3::new(1.0, 0.0, 1.0),
+            radius: 1.0,
+            height: 1.0,
+        });
+        assert!(cache.needs_update());
+        let rebuilt = cache.update().unwrap();
+        assert!(!rebuilt.is_empty());
+        assert!(rebuilt.len() < original.tiles.len());
+        assert!(!cache.needs_update());
+        assert!(border_edges(cache.navmesh()) > border_edges(&original));
+
+        assert!(cache.remove_obstacle(id).is_some());
+        cache.update().unwrap();
+        assert_eq!(cache.navmesh().tiles, original.tiles);
+        assert_eq!(cache.navmesh().links.len(), original.links.len());
+    }
+
+    #[test]
+    fn restoring_obstacles_replaces_the_current_ones() {
+        let config = config();
+        let mut cache = TileCache::build(floor(), &config, &BuildContext::default()).unwrap();
+        let original = cache.navmesh().clone();
+        let crate_box = Obstacle::Box(Aabb3d::new(Vec3::new(-5.0, 0.5, -5.0), [1.0, 0.5, 1.0]));
+        let id = cache.add_obstacle(crate_box);
+        cache.update().unwrap();
+        let saved = cache.saved_obstacles();
+        let carved = cache.navmesh().clone();
+        assert_ne!(carved.tiles, original.tiles);
+
+        cache.remove_obstacle(id);
+        cache.update().unwrap();
+        assert_eq!(cache.navmesh().tiles, original.tiles);
+
+        cache.restore_obstacles(saved);
+        assert!(cache.needs_update());
+        cache.update().unwrap();
+        assert_eq!(cache.obstacle(id), Some(&crate_box));
+        assert_eq!(cache.navmesh().tiles, carved.tiles);
+
+        cache.restore_obstacles(TileCacheObstacles::default());
+        cache.update().unwrap();
+        assert_eq!(cache.navmesh().tiles, original.tiles);
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn saved_obstacles_round_trip_into_a_fresh_cache() {
+        let config = config();
+        let mut cache = TileCache::build(floor(), &config, &BuildContext::default()).unwrap();
+        let barrel = cache.add_obstacle(Obstacle::Cylinder {
+            base: Vec3::new(1.0, 0.0, 1.0),
+            radius: 1.0,
+            height: 1.0,
+        });
+        let removed = cache.add_obstacle(Obstacle::Box(Aabb3d::new(Vec3::ZERO, [1.0; 3])));
+        let crate_box = cache.add_obstacle(Obstacle::Box(Aabb3d::new(
+            Vec3::new(-5.0, 0.5, -5.0),
+            [1.0, 0.5, 1.0],
+        )));
+        cache.remove_obstacle(removed);
+        cache.update().unwrap();
+
+        let json = serde_json::to_string(&cache.saved_obstacles()).unwrap();
+        let saved: TileCacheObstacles = serde_json::from_str(&json).unwrap();
+        assert_eq!(saved.len(), 2);
+
+        let mut loaded = TileCache::build(floor(), &config, &BuildContext::default()).unwrap();
+        loaded.restore_obstacles(saved);
+        loaded.update().unwrap();
+        assert_eq!(loaded.obstacle(barrel), cache.obstacle(barrel));
+        assert_eq!(loaded.obstacle(crate_box), cache.obstacle(crate_box));
+        assert_eq!(loaded.obstacle(removed), None);
+        assert_eq!(loaded.navmesh().tiles, cache.navmesh().tiles);
+        assert_eq!(loaded.navmesh().links, cache.navmesh().links);
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn whole_cache_round_trips() {
+        let config = config();
+        let mut cache = TileCache::build(floor(), &config, &BuildContext::default()).unwrap();
+        let id = cache.add_obstacle(Obstacle::Cylinder {
+            base: Vec3::new(1.0, 0.0, 1.0),
+            radius: 1.0,
+            height: 1.0,
+        });
+        cache.update().unwrap();
+
+        let json = serde_json::to_string(&cache).unwrap();
+        let mut loaded: TileCache = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.navmesh().tiles, cache.navmesh().tiles);
+        assert!(!loaded.needs_update());
+
+        loaded.remove_obstacle(id);
+        cache.remove_obstacle(id);
+        loaded.update().unwrap();
+        cache.update().unwrap();
+        assert_eq!(loaded.navmesh().tiles, cache.navmesh().tiles);
+    }
+}
@@ -0,0 +1,227 @@
+//! Saving the tiles of a [`TiledNavmesh`] to individual files, so that they can be streamed in and out at runtime.
+//!
+//! [`TiledNavmesh::save_tiles`] writes a [`TileManifest`] describing the grid of tiles, plus one file per tile that has polygons.
+//! Every file starts with a magic number and [`TILE_FILE_VERSION`], so that files written by a different version of rerecast
+//! are rejected instead of silently decoding garbage.
+
+use std::{
+    fs,
+    io::{Read as _, Write as _},
+    path::Path,
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use glam::UVec2;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use thiserror::Error;
+
+use crate::{NavmeshConfig, NavmeshTile, TiledNavmesh, VersionedNavmeshConfig};
+
+/// The version of the layout of tile files. Must be bumped whenever the serialized layout of [`NavmeshTile`] changes.
+pub const TILE_FILE_VERSION: u16 = 3;
+
+/// Marks a [`TileManifest`] file.
+const MANIFEST_MAGIC: [u8; 4] = *b"RRTM";
+/// Marks a [`NavmeshTile`] file.
+const TILE_MAGIC: [u8; 4] = *b"RRTL";
+
+/// Describes the tiles of a [`TiledNavmesh`] saved with [`TiledNavmesh::save_tiles`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileManifest {
+    /// The config the navmesh was built with.
+    pub config: NavmeshConfig,
+    /// The coordinates of the tiles that were saved. Tiles without polygons are not saved.
+    pub tiles: Vec<UVec2>,
+}
+
+/// The layout of a [`TileManifest`] on disk.
+#[derive(Serialize, Deserialize)]
+struct StoredManifest {
+    config: VersionedNavmeshConfig,
+    tiles: Vec<UVec2>,
+}
+
+impl TileManifest {
+    /// The name of the manifest file within the directory passed to [`TiledNavmesh::save_tiles`].
+    pub const FILE_NAME: &str = "manifest.navtiles";
+
+    /// The name of the file of the tile at `coord` within the directory passed to [`TiledNavmesh::save_tiles`].
+    pub fn tile_file_name(coord: UVec2) -> String {
+        format!("{}_{}.navtile", coord.x, coord.y)
+    }
+
+    /// Encodes the manifest to the contents of its file.
+    pub fn encode(&self) -> Result<Vec<u8>, TileFileError> {
+        let stored = StoredManifest {
+            config: self.config.into(),
+            tiles: self.tiles.clone(),
+        };
+        encode(MANIFEST_MAGIC, &stored)
+    }
+
+    /// Decodes the contents of a file written by [`TileManifest::encode`].
+    pub fn decode(bytes: &[u8]) -> Result<Self, TileFileError> {
+        let stored: StoredManifest = decode(MANIFEST_MAGIC, bytes)?;
+        Ok(Self {
+            config: stored.config.into_latest(),
+            tiles: stored.tiles,
+        })
+    }
+}
+
+impl NavmeshTile {
+    /// Encodes the tile to the contents of its file.
+    pub fn encode(&self) -> Result<Vec<u8>, TileFileError> {
+        encode(TILE_MAGIC, self)
+    }
+
+    /// Decodes the contents of a file written by [`NavmeshTile::encode`].
+    pub fn decode(bytes: &[u8]) -> Result<Self, TileFileError> {
+        decode(TILE_MAGIC, bytes)
+    }
+}
+
+impl TiledNavmesh {
+    /// Writes a [`TileManifest`] and every tile that has polygons to the directory at `dir`, creating it if needed.
+    /// Existing files are overwritten. `config` must be the config the navmesh was built with.
+    ///
+    /// Load the files with [`TileManifest::decode`] and [`NavmeshTile::decode`], create an empty navmesh with
+    /// [`TiledNavmesh::with_empty_tiles`] and fill it with [`TiledNavmesh::insert_tile`].
+    pub fn save_tiles(
+        &self,
+        config: &NavmeshConfig,
+        dir: impl AsRef<Path>,
+    ) -> Result<TileManifest, TileFileError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let mut manifest = TileManifest {
+            config: *config,
+            tiles: Vec::new(),
+        };
+        for tile in self.tiles.iter().filter(|tile| !tile.is_empty()) {
+            fs::write(
+                dir.join(TileManifest::tile_file_name(tile.coord)),
+                tile.encode()?,
+            )?;
+            manifest.tiles.push(tile.coord);
+        }
+        fs::write(dir.join(TileManifest::FILE_NAME), manifest.encode()?)?;
+        Ok(manifest)
+    }
+}
+
+fn encode<T: Serialize>(magic: [u8; 4], value: &T) -> Result<Vec<u8>, TileFileError> {
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(&magic)?;
+    gz.write_all(&TILE_FILE_VERSION.to_le_bytes())?;
+    bincode::serde::encode_into_std_write(value, &mut gz, bincode::config::standard())?;
+    Ok(gz.finish()?)
+}
+
+fn decode<T: DeserializeOwned>(magic: [u8; 4], bytes: &[u8]) -> Result<T, TileFileError> {
+    let mut gz = GzDecoder::new(bytes);
+    let mut header = [0; 6];
+    gz.read_exact(&mut header)
+        .map_err(|_| TileFileError::InvalidHeader)?;
+    if header[..4] != magic {
+        return Err(TileFileError::InvalidHeader);
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != TILE_FILE_VERSION {
+        return Err(TileFileError::UnsupportedVersion(version));
+    }
+    Ok(bincode::serde::decode_from_std_read(
+        &mut gz,
+        bincode::config::standard(),
+    )?)
+}
+
+/// Errors that can occur when saving or loading the files of a [`TiledNavmesh`].
+#[derive(Error, Debug)]
+pub enum TileFileError {
+    /// Happens when a file cannot be read or written.
+    #[error("Failed to access tile file: {0}")]
+    Io(#[from] std::io::Error),
+    /// Happens when a tile or manifest cannot be encoded.
+    #[error("Failed to encode tile file: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    /// Happens when the contents of a file are corrupted.
+    #[error("Failed to decode tile file: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+    /// Happens when the file is not a tile file of the expected kind, e.g. a manifest is decoded as a tile.
+    #[error("Not a tile file of the expected kind")]
+    InvalidHeader,
+    /// Happens when the file was written with a different [`TILE_FILE_VERSION`].
+    #[error("Unsupported tile file version {0}, expected {TILE_FILE_VERSION}")]
+    UnsupportedVersion(u16),
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3, Vec3A};
+
+    use super::*;
+    use crate::{Aabb3d, BuildContext, NavmeshConfigBuilder, TriMesh};
+
+    #[test]
+    fn saved_tiles_load_into_the_same_navmesh() {
+        let config = NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::ZERO, [10.0, 2.0, 10.0]),
+            tile_size: 24,
+            ..Default::default()
+        }
+        .build();
+        // A floor that only covers the lower half of the world, so that some tiles stay empty.
+        let floor = TriMesh::from_parts(
+            vec![
+                Vec3A::new(-10.0, 0.0, -10.0),
+                Vec3A::new(-10.0, 0.0, -1.0),
+                Vec3A::new(10.0, 0.0, -1.0),
+                Vec3A::new(10.0, 0.0, -10.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        let navmesh = TiledNavmesh::build(floor, &config, &BuildContext::default()).unwrap();
+
+        let dir = std::env::temp_dir().join("rerecast_saved_tiles_load_into_the_same_navmesh");
+        let manifest = navmesh.save_tiles(&config, &dir).unwrap();
+        assert!(manifest.tiles.len() < navmesh.tiles.len());
+
+        let loaded_manifest =
+            TileManifest::decode(&fs::read(dir.join(TileManifest::FILE_NAME)).unwrap()).unwrap();
+        assert_eq!(loaded_manifest, manifest);
+        let mut loaded = TiledNavmesh::with_empty_tiles(&loaded_manifest.config).unwrap();
+        for &coord in &loaded_manifest.tiles {
+            let bytes = fs::read(dir.join(TileManifest::tile_file_name(coord))).unwrap();
+            loaded
+                .insert_tile(NavmeshTile::decode(&bytes).unwrap(), config.walkable_climb)
+                .unwrap();
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Tiles that were not saved stay empty, but don't have the metadata of an empty tile built by the pipeline.
+        let non_empty = |navmesh: &TiledNavmesh| {
+            navmesh
+                .tiles
+                .iter()
+                .filter(|tile| !tile.is_empty())
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(non_empty(&loaded), non_empty(&navmesh));
+        let links = |navmesh: &TiledNavmesh| {
+            navmesh
+                .links
+                .iter()
+                .copied()
+                .collect::<std::collections::HashSet<_>>()
+        };
+        assert_eq!(links(&loaded), links(&navmesh));
+
+        assert!(matches!(
+            NavmeshTile::decode(&manifest.encode().unwrap()),
+            Err(TileFileError::InvalidHeader)
+        ));
+    }
+}
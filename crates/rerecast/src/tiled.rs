@@ -0,0 +1,1032 @@
+//! Builds a navmesh as a grid of independent tiles, so that large worlds don't need a single huge heightfield.
+//!
+//! Every tile runs the standard pipeline on a heightfield that covers the tile plus [`NavmeshConfig::border_size`]
+//! on every side. The border makes the polygons of neighboring tiles line up exactly, so their portal edges
+//! can be stitched together afterwards.
+
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+use std::collections::HashMap;
+
+use glam::{UVec2, UVec3, Vec2, Vec3, Vec3A, Vec3Swizzles as _};
+use thiserror::Error;
+
+use crate::{
+    Aabb3d, BuildContext, BuildNavmeshError, BuildScratch, BuildStage, CompactHeightfield,
+    DetailNavmesh, EdgeConnection, EdgeId, Heightfield, NavmeshConfig, PolyId, PolygonNavmesh,
+    RasterizationError, TriMesh,
+    pipeline::{build_eroded_heightfield, build_navmesh_from_eroded},
+};
+
+/// A navmesh made of a grid of tiles on the xz-plane, each with its own [`PolygonNavmesh`] and [`DetailNavmesh`].
+///
+/// Build it with [`TiledNavmesh::build`]. Tiles are [`NavmeshConfig::tile_size`] cells wide, starting at the minimum of
+/// [`NavmeshConfig::aabb`]. Polygons on the border of a tile have [`EdgeConnection::Portal`] edges,
+/// which are connected to the polygons of the neighboring tile through [`TiledNavmesh::links`].
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct TiledNavmesh {
+    /// The tiles in row-major order, i.e. the tile at `(x, z)` is at index `z * tile_counts.x + x`.
+    pub tiles: Vec<NavmeshTile>,
+    /// The number of tiles along the x- and z-axis.
+    pub tile_counts: UVec2,
+    /// The size of a tile along the x- and z-axis, excluding the border. `[Units: vx]`
+    pub tile_size: u16,
+    /// The connections between polygons of neighboring tiles, one per pair of overlapping portal edges and direction.
+    pub links: Vec<TileLink>,
+}
+
+/// A single tile of a [`TiledNavmesh`].
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct NavmeshTile {
+    /// The position of the tile in the grid of tiles.
+    pub coord: UVec2,
+    /// The polygons of the tile. Its [`PolygonNavmesh::aabb`] starts at the minimum corner of the tile, excluding the border.
+    pub polygon_mesh: PolygonNavmesh,
+    /// The detail mesh of [`Self::polygon_mesh`].
+    pub detail_mesh: DetailNavmesh,
+}
+
+impl NavmeshTile {
+    /// A tile without any polygons, e.g. one that is not loaded.
+    pub fn empty(coord: UVec2) -> Self {
+        Self {
+            coord,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the tile has no polygons.
+    pub fn is_empty(&self) -> bool {
+        self.polygon_mesh.polygon_count() == 0
+    }
+}
+
+/// A polygon of a specific tile of a [`TiledNavmesh`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct TilePolygon {
+    /// The index of the tile in [`TiledNavmesh::tiles`].
+    pub tile: usize,
+    /// The polygon within the tile.
+    pub polygon: PolyId,
+}
+
+/// A connection from a portal edge of one tile to a polygon of the neighboring tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct TileLink {
+    /// The tile of the portal edge.
+    pub from_tile: usize,
+    /// The portal edge.
+    pub edge: EdgeId,
+    /// The polygon on the other side of the edge.
+    pub to: TilePolygon,
+}
+
+impl TiledNavmesh {
+    /// Builds a tiled navmesh from world-space geometry. Triangles are marked as walkable by their slope, like [`build_navmesh`](crate::build_navmesh) does.
+    ///
+    /// `config` describes the whole world: its [`NavmeshConfig::aabb`] is partitioned into tiles of [`NavmeshConfig::tile_size`] cells,
+    /// while [`NavmeshConfig::width`] and [`NavmeshConfig::height`] are ignored.
+    /// [`NavmeshConfig::border_size`] must not be zero, since the border is what lets the tiles be stitched together.
+    ///
+    /// `context` is told about the stages of every tile, with the progress of the whole build,
+    /// and cancels it with [`TiledNavmeshError::Cancelled`].
+    pub fn build(
+        mut trimesh: TriMesh,
+        config: &NavmeshConfig,
+        context: &BuildContext,
+    ) -> Result<Self, TiledNavmeshError> {
+        trimesh.mark_walkable_triangles(config.walkable_slope_angle);
+        Self::build_premarked(trimesh, config, context)
+    }
+
+    /// Same as [`TiledNavmesh::build`], but uses the area types of `trimesh` as they are
+    /// instead of marking triangles as walkable by their slope.
+    pub fn build_premarked(
+        trimesh: TriMesh,
+        config: &NavmeshConfig,
+        context: &BuildContext,
+    ) -> Result<Self, TiledNavmeshError> {
+        Self::build_premarked_with_rasterizer(trimesh, config, context, |_| Ok(()))
+    }
+
+    /// Same as [`TiledNavmesh::build_premarked`], but calls `rasterize` on the heightfield of every tile
+    /// after its triangles were rasterized, e.g. to add [`Heightfield::rasterize_heightmap`].
+    ///
+    /// Each heightfield covers the tile including its border, see [`TiledNavmesh::tile_bounds`].
+    pub fn build_premarked_with_rasterizer(
+        trimesh: TriMesh,
+        config: &NavmeshConfig,
+        context: &BuildContext,
+        rasterize: impl Fn(&mut Heightfield) -> Result<(), RasterizationError>,
+    ) -> Result<Self, TiledNavmeshError> {
+        let tile_counts = tile_counts(config)?;
+        let triangles_per_tile = bin_triangles(&trimesh, config, tile_counts);
+        let mut scratch = BuildScratch::new();
+        let mut tiles = Vec::with_capacity(triangles_per_tile.len());
+        for (index, triangles) in triangles_per_tile.iter().enumerate() {
+            let coord = UVec2::new(index as u32 % tile_counts.x, index as u32 / tile_counts.x);
+            let progress = tile_progress(context, index, triangles_per_tile.len());
+            tiles.push(build_tile(
+                &trimesh,
+                triangles,
+                coord,
+                config,
+                &mut scratch,
+                &context.with_progress(&progress),
+                &rasterize,
+            )?);
+        }
+        context.finish();
+
+        let mut navmesh = Self {
+            tiles,
+            tile_counts,
+            tile_size: config.tile_size,
+            links: Vec::new(),
+        };
+        navmesh.links = (0..navmesh.tiles.len())
+            .flat_map(|from_tile| navmesh.links_from(from_tile, config.walkable_climb, |_| true))
+            .collect();
+        Ok(navmesh)
+    }
+
+    /// Rebuilds only the given tiles from premarked geometry and stitches them to their neighbors again,
+    /// e.g. after a door opened or a crate was moved.
+    ///
+    /// `trimesh` must contain all geometry overlapping the tiles, including their border as returned by [`TiledNavmesh::tile_bounds`].
+    /// Geometry outside of them is ignored. Coordinates outside of the grid are ignored as well.
+    /// `config` must be the config the navmesh was built with.
+    ///
+    /// If any tile fails to build, the navmesh is left unchanged.
+    pub fn rebuild_tiles_premarked(
+        &mut self,
+        trimesh: TriMesh,
+        config: &NavmeshConfig,
+        coords: impl IntoIterator<Item = UVec2>,
+        context: &BuildContext,
+    ) -> Result<(), TiledNavmeshError> {
+        self.rebuild_tiles_premarked_with_rasterizer(trimesh, config, coords, context, |_| Ok(()))
+    }
+
+    /// Same as [`TiledNavmesh::rebuild_tiles_premarked`], but calls `rasterize` on the heightfield of every rebuilt tile
+    /// after its triangles were rasterized, like [`TiledNavmesh::build_premarked_with_rasterizer`].
+    pub fn rebuild_tiles_premarked_with_rasterizer(
+        &mut self,
+        trimesh: TriMesh,
+        config: &NavmeshConfig,
+        coords: impl IntoIterator<Item = UVec2>,
+        context: &BuildContext,
+        rasterize: impl Fn(&mut Heightfield) -> Result<(), RasterizationError>,
+    ) -> Result<(), TiledNavmeshError> {
+        if config.tile_size != self.tile_size || tile_counts(config)? != self.tile_counts {
+            return Err(TiledNavmeshError::MismatchedConfig);
+        }
+        let mut dirty = vec![false; self.tiles.len()];
+        for coord in coords {
+            if let Some(index) = self.tile_index(coord) {
+                dirty[index] = true;
+            }
+        }
+        if !dirty.contains(&true) {
+            return Ok(());
+        }
+
+        let triangles_per_tile = bin_triangles(&trimesh, config, self.tile_counts);
+        let dirty_count = dirty.iter().filter(|&&dirty| dirty).count();
+        let mut scratch = BuildScratch::new();
+        let mut rebuilt = Vec::new();
+        for (index, triangles) in triangles_per_tile.iter().enumerate() {
+            if dirty[index] {
+                let coord = self.tiles[index].coord;
+                let progress = tile_progress(context, rebuilt.len(), dirty_count);
+                let tile = build_tile(
+                    &trimesh,
+                    triangles,
+                    coord,
+                    config,
+                    &mut scratch,
+                    &context.with_progress(&progress),
+                    &rasterize,
+                )?;
+                rebuilt.push((index, tile));
+            }
+        }
+        context.finish();
+        for (index, tile) in rebuilt {
+            self.tiles[index] = tile;
+        }
+        self.restitch(&dirty, config.walkable_climb);
+        Ok(())
+    }
+
+    /// Creates a navmesh with the grid of tiles described by `config`, like [`TiledNavmesh::build`] would,
+    /// but with every tile empty. Fill it tile by tile with [`TiledNavmesh::insert_tile`], e.g. when streaming tiles from disk.
+    pub fn with_empty_tiles(config: &NavmeshConfig) -> Result<Self, TiledNavmeshError> {
+        let tile_counts = tile_counts(config)?;
+        let tiles = (0..tile_counts.y)
+            .flat_map(|z| (0..tile_counts.x).map(move |x| UVec2::new(x, z)))
+            .map(NavmeshTile::empty)
+            .collect();
+        Ok(Self {
+            tiles,
+            tile_counts,
+            tile_size: config.tile_size,
+            links: Vec::new(),
+        })
+    }
+
+    /// Replaces the tile at [`NavmeshTile::coord`] and stitches it to its neighbors, e.g. after loading it from disk.
+    ///
+    /// `walkable_climb` must be the [`NavmeshConfig::walkable_climb`] the navmesh was built with.
+    pub fn insert_tile(
+        &mut self,
+        tile: NavmeshTile,
+        walkable_climb: u16,
+    ) -> Result<(), TiledNavmeshError> {
+        let Some(index) = self.tile_index(tile.coord) else {
+            return Err(TiledNavmeshError::TileOutOfBounds(tile.coord));
+        };
+        self.tiles[index] = tile;
+        let mut dirty = vec![false; self.tiles.len()];
+        dirty[index] = true;
+        self.restitch(&dirty, walkable_climb);
+        Ok(())
+    }
+
+    /// Replaces the tile at `coord` with an empty one and removes all links to and from it, e.g. to free the memory of tiles far away.
+    /// Returns the removed tile, or `None` if `coord` is outside of the grid.
+    ///
+    /// The tile can be brought back with [`TiledNavmesh::insert_tile`].
+    pub fn remove_tile(&mut self, coord: UVec2) -> Option<NavmeshTile> {
+        let index = self.tile_index(coord)?;
+        self.links
+            .retain(|link| link.from_tile != index && link.to.tile != index);
+        Some(std::mem::replace(
+            &mut self.tiles[index],
+            NavmeshTile::empty(coord),
+        ))
+    }
+
+    /// The bounds of the heightfield of a tile, i.e. the tile plus [`NavmeshConfig::border_size`] on every side.
+    /// All geometry within them influences the tile.
+    pub fn tile_bounds(coord: UVec2, config: &NavmeshConfig) -> Aabb3d {
+        let tile_world_size = config.tile_size as f32 * config.cell_size;
+        let border = config.border_size as f32 * config.cell_size;
+        let min = config.aabb.min
+            + Vec3::new(
+                coord.x as f32 * tile_world_size - border,
+                0.0,
+                coord.y as f32 * tile_world_size - border,
+            );
+        let size = tile_world_size + 2.0 * border;
+        Aabb3d {
+            min,
+            max: Vec3::new(min.x + size, config.aabb.max.y, min.z + size),
+        }
+    }
+
+    /// The coordinates of the tiles that geometry within `bounds` influences, i.e. whose [`TiledNavmesh::tile_bounds`] it overlaps.
+    /// `config` must be the config the navmesh was built with.
+    pub fn tiles_overlapping(
+        &self,
+        bounds: &Aabb3d,
+        config: &NavmeshConfig,
+    ) -> impl Iterator<Item = UVec2> + '_ {
+        let tile_world_size = config.tile_size as f32 * config.cell_size;
+        let border = config.border_size as f32 * config.cell_size;
+        let max_coord = self.tile_counts.saturating_sub(UVec2::ONE).as_vec2();
+        let min = bounds.min.xz() - config.aabb.min.xz() - border;
+        let max = bounds.max.xz() - config.aabb.min.xz() + border;
+        let outside = self.tiles.is_empty()
+            || max.cmplt(Vec2::ZERO).any()
+            || min.cmpgt((max_coord + 1.0) * tile_world_size).any()
+            || bounds.max.y < config.aabb.min.y
+            || bounds.min.y > config.aabb.max.y;
+        let first = (min / tile_world_size).floor().clamp(Vec2::ZERO, max_coord);
+        let last = (max / tile_world_size).floor().clamp(Vec2::ZERO, max_coord);
+        let (first, last) = if outside {
+            (UVec2::ONE, UVec2::ZERO)
+        } else {
+            (first.as_uvec2(), last.as_uvec2())
+        };
+        (first.y..=last.y).flat_map(move |z| (first.x..=last.x).map(move |x| UVec2::new(x, z)))
+    }
+
+    /// The tile at the given position in the grid, if it exists.
+    pub fn tile(&self, coord: UVec2) -> Option<&NavmeshTile> {
+        self.tile_index(coord).map(|index| &self.tiles[index])
+    }
+
+    /// The polygons of neighboring tiles that the given polygon is connected to through its portal edges.
+    pub fn linked_polygons(&self, polygon: TilePolygon) -> impl Iterator<Item = TilePolygon> + '_ {
+        self.links
+            .iter()
+            .filter(move |link| {
+                link.from_tile == polygon.tile && link.edge.polygon == polygon.polygon
+            })
+            .map(|link| link.to)
+    }
+
+    pub(crate) fn tile_index(&self, coord: UVec2) -> Option<usize> {
+        (coord.x < self.tile_counts.x && coord.y < self.tile_counts.y)
+            .then(|| (coord.y * self.tile_counts.x + coord.x) as usize)
+    }
+
+    /// Connects every portal edge of `from_tile` to the polygons of the neighboring tile whose portal edges overlap it
+    /// and are at most `walkable_climb` apart vertically, skipping neighbors rejected by `to_filter`.
+    fn links_from(
+        &self,
+        from_tile: usize,
+        walkable_climb: u16,
+        to_filter: impl Fn(usize) -> bool,
+    ) -> Vec<TileLink> {
+        let mut links = Vec::new();
+        let tile = &self.tiles[from_tile];
+        let mesh = &tile.polygon_mesh;
+        // The portal edges of each neighbor facing this tile, built on first use.
+        let mut neighbor_edges: [Option<PortalEdges>; 4] = Default::default();
+        let mut candidates = Vec::new();
+        for polygon in mesh.polygon_ids() {
+            for edge in mesh.edges(polygon) {
+                let EdgeConnection::Portal(side) = mesh.edge_connection(edge) else {
+                    continue;
+                };
+                let Some(neighbor_coord) = neighbor(tile.coord, side) else {
+                    continue;
+                };
+                let Some(to_tile) = self.tile_index(neighbor_coord) else {
+                    continue;
+                };
+                if !to_filter(to_tile) {
+                    continue;
+                }
+                let from_edge = self.global_edge(from_tile, edge);
+                let portal_edges = neighbor_edges[side as usize]
+                    .get_or_insert_with(|| self.portal_edges(to_tile, (side + 2) % 4));
+
+                candidates.clear();
+                let (start, end) = span_along(from_edge, side);
+                for cell in start..end {
+                    let Some(edges) = portal_edges.get(&cell) else {
+                        continue;
+                    };
+                    candidates.extend(edges.iter().filter_map(|&(neighbor_polygon, to_edge)| {
+                        edges_connect(from_edge, to_edge, side, walkable_climb)
+                            .then_some(neighbor_polygon)
+                    }));
+                }
+                candidates.sort_unstable();
+                candidates.dedup();
+                links.extend(candidates.iter().map(|&neighbor_polygon| TileLink {
+                    from_tile,
+                    edge,
+                    to: TilePolygon {
+                        tile: to_tile,
+                        polygon: neighbor_polygon,
+                    },
+                }));
+            }
+        }
+        links
+    }
+
+    /// The portal edges of a tile on the given side, hashed by every cell along the tile border that they cover.
+    fn portal_edges(&self, tile: usize, side: u8) -> PortalEdges {
+        let mesh = &self.tiles[tile].polygon_mesh;
+        let mut edges = PortalEdges::new();
+        for polygon in mesh.polygon_ids() {
+            for edge in mesh.edges(polygon) {
+                if mesh.edge_connection(edge) != EdgeConnection::Portal(side) {
+                    continue;
+                }
+                let global = self.global_edge(tile, edge);
+                let (start, end) = span_along(global, side);
+                for cell in start..end {
+                    edges.entry(cell).or_default().push((polygon, global));
+                }
+            }
+        }
+        edges
+    }
+    /// Replaces all links from or to the dirty tiles with freshly stitched ones.
+    pub(crate) fn restitch(&mut self, dirty: &[bool], walkable_climb: u16) {
+        // Links of the dirty tiles point to polygons that no longer exist, in both directions.
+        self.links
+            .retain(|link| !dirty[link.from_tile] && !dirty[link.to.tile]);
+        let mut links = Vec::new();
+        for (from_tile, tile) in self.tiles.iter().enumerate() {
+            if dirty[from_tile] {
+                links.extend(self.links_from(from_tile, walkable_climb, |_| true));
+            } else if (0..4)
+                .filter_map(|side| neighbor(tile.coord, side))
+                .filter_map(|coord| self.tile_index(coord))
+                .any(|index| dirty[index])
+            {
+                links.extend(self.links_from(from_tile, walkable_climb, |to_tile| dirty[to_tile]));
+            }
+        }
+        self.links.extend(links);
+    }
+
+    /// The vertices of an edge in cells relative to the minimum of the whole navmesh.
+    fn global_edge(&self, tile: usize, edge: EdgeId) -> [UVec3; 2] {
+        let tile = &self.tiles[tile];
+        // Large worlds have more cells than fit into the `u16` coordinates of a single tile.
+        let offset = UVec3::new(tile.coord.x, 0, tile.coord.y) * self.tile_size as u32;
+        tile.polygon_mesh
+            .edge_vertices(edge)
+            .map(|vertex| tile.polygon_mesh.vertex(vertex).as_uvec3() + offset)
+    }
+}
+
+/// The number of tiles along the x- and z-axis of the grid described by `config`.
+pub(crate) fn tile_counts(config: &NavmeshConfig) -> Result<UVec2, TiledNavmeshError> {
+    if config.tile_size == 0 {
+        return Err(TiledNavmeshError::ZeroTileSize);
+    }
+    if config.border_size == 0 {
+        return Err(TiledNavmeshError::ZeroBorderSize);
+    }
+    let tile_size = config.tile_size as u32;
+    let extent = config.aabb.max - config.aabb.min;
+    Ok(UVec2::new(
+        ((extent.x / config.cell_size + 0.5) as u32).div_ceil(tile_size),
+        ((extent.z / config.cell_size + 0.5) as u32).div_ceil(tile_size),
+    ))
+}
+
+/// Reports the stages of the tile at `index` of `count` tiles to `context` as part of the progress of all of them.
+pub(crate) fn tile_progress<'a>(
+    context: &'a BuildContext,
+    index: usize,
+    count: usize,
+) -> impl Fn(BuildStage, f32) + Sync + 'a {
+    move |stage, fraction| (context.progress)(stage, (index as f32 + fraction) / count as f32)
+}
+
+/// Builds a single tile from the given triangles of `trimesh`, followed by whatever `rasterize` adds.
+fn build_tile(
+    trimesh: &TriMesh,
+    triangles: &[usize],
+    coord: UVec2,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+    context: &BuildContext,
+    rasterize: &impl Fn(&mut Heightfield) -> Result<(), RasterizationError>,
+) -> Result<NavmeshTile, TiledNavmeshError> {
+    let layer = build_tile_layer(
+        trimesh, triangles, coord, config, scratch, context, rasterize,
+    )?;
+    build_tile_from_layer(layer, coord, config, scratch, context)
+}
+
+/// Runs the pipeline of a single tile up to and including erosion.
+pub(crate) fn build_tile_layer(
+    trimesh: &TriMesh,
+    triangles: &[usize],
+    coord: UVec2,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+    context: &BuildContext,
+    rasterize: &impl Fn(&mut Heightfield) -> Result<(), RasterizationError>,
+) -> Result<CompactHeightfield, TiledNavmeshError> {
+    // Only copy the vertices of the triangles touching this tile, not the whole world for every tile.
+    let mut remapped = HashMap::new();
+    let mut vertices: Vec<Vec3A> = Vec::new();
+    let indices = triangles
+        .iter()
+        .map(|&i| {
+            UVec3::from_array(trimesh.indices[i].to_array().map(|index| {
+                *remapped.entry(index).or_insert_with(|| {
+                    vertices.push(trimesh.vertices[index as usize]);
+                    vertices.len() as u32 - 1
+                })
+            }))
+        })
+        .collect();
+    let tile_trimesh = TriMesh {
+        vertices,
+        indices,
+        area_types: triangles.iter().map(|&i| trimesh.area_types[i]).collect(),
+    };
+    let tile_config = tile_config(coord, config);
+    context
+        .begin(BuildStage::Rasterization)
+        .and_then(|()| {
+            build_eroded_heightfield(&tile_config, &[], scratch, context, |heightfield| {
+                heightfield.rasterize_triangles(&tile_trimesh, tile_config.walkable_climb)?;
+                rasterize(heightfield)
+            })
+        })
+        .map_err(tile_error(coord))
+}
+
+/// Runs the rest of the pipeline of a single tile on the result of [`build_tile_layer`].
+pub(crate) fn build_tile_from_layer(
+    layer: CompactHeightfield,
+    coord: UVec2,
+    config: &NavmeshConfig,
+    scratch: &mut BuildScratch,
+    context: &BuildContext,
+) -> Result<NavmeshTile, TiledNavmeshError> {
+    let (polygon_mesh, detail_mesh) =
+        build_navmesh_from_eroded(layer, &tile_config(coord, config), scratch, context)
+            .map_err(tile_error(coord))?;
+    Ok(NavmeshTile {
+        coord,
+        polygon_mesh,
+        detail_mesh,
+    })
+}
+
+/// Wraps an error of the pipeline of the tile at `coord`, unless the build was cancelled.
+fn tile_error(coord: UVec2) -> impl FnOnce(BuildNavmeshError) -> TiledNavmeshError {
+    move |source| match source {
+        BuildNavmeshError::Cancelled => TiledNavmeshError::Cancelled,
+        source => TiledNavmeshError::Tile { coord, source },
+    }
+}
+
+/// The config of the standard pipeline for a single tile, covering the tile and its border.
+fn tile_config(coord: UVec2, config: &NavmeshConfig) -> NavmeshConfig {
+    NavmeshConfig {
+        width: config.tile_size + config.border_size * 2,
+        height: config.tile_size + config.border_size * 2,
+        aabb: TiledNavmesh::tile_bounds(coord, config),
+        ..*config
+    }
+}
+
+/// The tile on the given portal side of a tile, using the sides of [`EdgeConnection::Portal`].
+fn neighbor(coord: UVec2, side: u8) -> Option<UVec2> {
+    match side {
+        0 => coord.x.checked_sub(1).map(|x| UVec2::new(x, coord.y)),
+        1 => Some(UVec2::new(coord.x, coord.y + 1)),
+        2 => Some(UVec2::new(coord.x + 1, coord.y)),
+        3 => coord.y.checked_sub(1).map(|z| UVec2::new(coord.x, z)),
+        _ => None,
+    }
+}
+
+/// The portal edges of one side of a tile along with their polygons, keyed by the cells along the tile border they cover.
+type PortalEdges = HashMap<u32, Vec<(PolyId, [UVec3; 2])>>;
+
+/// The position of a vertex along a tile border. Edges on the -x and +x sides run along z, the others along x.
+fn along(vertex: UVec3, side: u8) -> u32 {
+    if matches!(side, 0 | 2) {
+        vertex.z
+    } else {
+        vertex.x
+    }
+}
+
+/// The range of cells along the tile border that a portal edge covers.
+fn span_along(edge: [UVec3; 2], side: u8) -> (u32, u32) {
+    let (a, b) = (along(edge[0], side), along(edge[1], side));
+    (a.min(b), a.max(b))
+}
+
+/// Whether two portal edges on the same tile border overlap along it and are close enough vertically to walk across.
+fn edges_connect(a: [UVec3; 2], b: [UVec3; 2], side: u8, walkable_climb: u16) -> bool {
+    let along = |v: UVec3| along(v, side) as f32;
+    let (a0, a1) = (along(a[0]), along(a[1]));
+    let (b0, b1) = (along(b[0]), along(b[1]));
+    let start = a0.min(a1).max(b0.min(b1));
+    let end = a0.max(a1).min(b0.max(b1));
+    if end - start <= 0.0 {
+        return false;
+    }
+    let height_at = |edge: [UVec3; 2], (t0, t1): (f32, f32), t: f32| {
+        let s = if t1 == t0 { 0.0 } else { (t - t0) / (t1 - t0) };
+        edge[0].y as f32 + (edge[1].y as f32 - edge[0].y as f32) * s
+    };
+    [start, end].into_iter().all(|t| {
+        (height_at(a, (a0, a1), t) - height_at(b, (b0, b1), t)).abs() <= walkable_climb as f32
+    })
+}
+
+/// The indices of the triangles whose bounds on the xz-plane touch each tile, including its border.
+pub(crate) fn bin_triangles(
+    trimesh: &TriMesh,
+    config: &NavmeshConfig,
+    tile_counts: UVec2,
+) -> Vec<Vec<usize>> {
+    let mut bins = vec![Vec::new(); (tile_counts.x * tile_counts.y) as usize];
+    if bins.is_empty() {
+        return bins;
+    }
+    let tile_world_size = config.tile_size as f32 * config.cell_size;
+    let border = config.border_size as f32 * config.cell_size;
+    let max_coord = (tile_counts - 1).as_vec2();
+    for (triangle, indices) in trimesh.indices.iter().enumerate() {
+        let vertices = indices
+            .to_array()
+            .map(|i| Vec3::from(trimesh.vertices[i as usize]).xz() - config.aabb.min.xz());
+        let min = vertices[0].min(vertices[1]).min(vertices[2]) - border;
+        let max = vertices[0].max(vertices[1]).max(vertices[2]) + border;
+        // Skip triangles that lie outside of all tiles.
+        if max.cmplt(Vec2::ZERO).any() || min.cmpgt((max_coord + 1.0) * tile_world_size).any() {
+            continue;
+        }
+        let first = (min / tile_world_size).floor().clamp(Vec2::ZERO, max_coord);
+        let last = (max / tile_world_size).floor().clamp(Vec2::ZERO, max_coord);
+        for z in first.y as u32..=last.y as u32 {
+            for x in first.x as u32..=last.x as u32 {
+                bins[(z * tile_counts.x + x) as usize].push(triangle);
+            }
+        }
+    }
+    bins
+}
+
+/// Errors that can occur when building a [`TiledNavmesh`].
+#[derive(Error, Debug)]
+pub enum TiledNavmeshError {
+    /// Happens when [`NavmeshConfig::tile_size`] is zero.
+    #[error("The tile size must not be zero")]
+    ZeroTileSize,
+    /// Happens when [`NavmeshConfig::border_size`] is zero, since tiles without a border cannot be stitched together.
+    #[error("The border size must not be zero for tiled builds")]
+    ZeroBorderSize,
+    /// Happens when rebuilding tiles with a config that describes a different grid of tiles than the navmesh was built with.
+    #[error("The config describes a different grid of tiles than the navmesh was built with")]
+    MismatchedConfig,
+    /// Happens when inserting a tile whose coordinates are outside of the grid of tiles.
+    #[error("Tile {0} is outside of the grid of tiles")]
+    TileOutOfBounds(UVec2),
+    /// Happens when [`BuildContext::cancel`] was set during the build.
+    #[error("The navmesh build was cancelled")]
+    Cancelled,
+    /// Happens when building one of the tiles fails.
+    #[error("Failed to build tile {coord}: {source}")]
+    Tile {
+        /// The position of the tile in the grid of tiles.
+        coord: UVec2,
+        /// Why building the tile failed.
+        source: BuildNavmeshError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    use glam::{UVec3, Vec3A};
+
+    use super::*;
+    use crate::NavmeshConfigBuilder;
+
+    fn floor(extent: f32) -> TriMesh {
+        TriMesh::from_parts(
+            vec![
+                Vec3A::new(-extent, 0.0, -extent),
+                Vec3A::new(-extent, 0.0, extent),
+                Vec3A::new(extent, 0.0, extent),
+                Vec3A::new(extent, 0.0, -extent),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn global_edges_of_far_away_tiles_do_not_overflow() {
+        let config = NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::ZERO, [10.0, 2.0, 10.0]),
+            tile_size: 24,
+            ..Default::default()
+        }
+        .build();
+        let mut navmesh =
+            TiledNavmesh::build(floor(10.0), &config, &BuildContext::default()).unwrap();
+        // The second tile now starts at the very end of the range of a single tile.
+        navmesh.tile_size = u16::MAX;
+        let tile = navmesh.tile_index(UVec2::new(1, 0)).unwrap();
+        let mesh = &navmesh.tiles[tile].polygon_mesh;
+        let edge = mesh
+            .polygon_ids()
+            .flat_map(|polygon| mesh.edges(polygon))
+            .find(|&edge| mesh.vertex(mesh.edge_vertices(edge)[0]).x > 0)
+            .unwrap();
+        let local = mesh.vertex(mesh.edge_vertices(edge)[0]);
+        let [start, _] = navmesh.global_edge(tile, edge);
+        assert_eq!(start.x, u16::MAX as u32 + local.x as u32);
+        assert_eq!(start.z, local.z as u32);
+    }
+
+    #[test]
+    fn tiles_are_stitched_together() {
+        let config = NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::ZERO, [10.0, 2.0, 10.0]),
+            tile_size: 24,
+            ..Default::default()
+        }
+        .build();
+        let navmesh = TiledNavmesh::build(floor(10.0), &config, &BuildContext::default()).unwrap();
+        // 20 wu at 0.3 wu per cell are 67 cells, so three tiles per axis.
+        assert_eq!(navmesh.tile_counts, UVec2::new(3, 3));
+        assert_eq!(navmesh.tiles.len(), 9);
+        assert!(
+            navmesh
+                .tiles
+                .iter()
+                .all(|tile| tile.polygon_mesh.polygon_count() > 0)
+        );
+
+        // Every portal edge on an inner tile border is linked, and every link goes both ways.
+        for (tile_index, tile) in navmesh.tiles.iter().enumerate() {
+            let mesh = &tile.polygon_mesh;
+            for polygon in mesh.polygon_ids() {
+                for edge in mesh.edges(polygon) {
+                    let EdgeConnection::Portal(side) = mesh.edge_connection(edge) else {
+                        continue;
+                    };
+                    let has_neighbor = neighbor(tile.coord, side)
+                        .is_some_and(|coord| navmesh.tile(coord).is_some());
+                    let linked = navmesh
+                        .links
+                        .iter()
+                        .any(|link| link.from_tile == tile_index && link.edge == edge);
+                    assert_eq!(linked, has_neighbor);
+                }
+            }
+        }
+        for link in &navmesh.links {
+            let from = TilePolygon {
+                tile: link.from_tile,
+                polygon: link.edge.polygon,
+            };
+            assert!(
+                navmesh
+                    .linked_polygons(link.to)
+                    .any(|polygon| polygon == from)
+            );
+        }
+
+        // Splitting the world into tiles loses no walkable area along the seams.
+        let single_tile = TiledNavmesh::build(
+            floor(10.0),
+            &NavmeshConfig {
+                tile_size: 100,
+                ..config
+            },
+            &BuildContext::default(),
+        )
+        .unwrap();
+        assert_eq!(single_tile.tiles.len(), 1);
+        assert!(single_tile.links.is_empty());
+        let area = |navmesh: &TiledNavmesh| -> f32 {
+            navmesh
+                .tiles
+                .iter()
+                .flat_map(|tile| {
+                    let mesh = &tile.polygon_mesh;
+                    mesh.polygon_ids().map(|polygon| mesh.surface_area(polygon))
+                })
+                .sum()
+        };
+        assert!((area(&navmesh) - area(&single_tile)).abs() < 0.01 * area(&single_tile));
+    }
+
+    #[test]
+    fn rebuilding_changed_tiles_matches_a_full_build() {
+        let config = NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::ZERO, [10.0, 2.0, 10.0]),
+            tile_size: 24,
+            ..Default::default()
+        }
+        .build();
+        let mut navmesh =
+            TiledNavmesh::build(floor(10.0), &config, &BuildContext::default()).unwrap();
+
+        // A platform appears in the corner of the world.
+        let mut platform = TriMesh::from_parts(
+            vec![
+                Vec3A::new(6.0, 1.0, 6.0),
+                Vec3A::new(6.0, 1.0, 9.0),
+                Vec3A::new(9.0, 1.0, 9.0),
+                Vec3A::new(9.0, 1.0, 6.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        let bounds = platform.compute_aabb().unwrap();
+        let mut trimesh = floor(10.0);
+        trimesh.extend(&platform);
+        trimesh.mark_walkable_triangles(config.walkable_slope_angle);
+        platform.mark_walkable_triangles(config.walkable_slope_angle);
+
+        let changed = navmesh
+            .tiles_overlapping(&bounds, &config)
+            .collect::<Vec<_>>();
+        assert_eq!(changed, vec![UVec2::new(2, 2)]);
+        navmesh
+            .rebuild_tiles_premarked(trimesh.clone(), &config, changed, &BuildContext::default())
+            .unwrap();
+
+        let expected =
+            TiledNavmesh::build_premarked(trimesh, &config, &BuildContext::default()).unwrap();
+        assert_eq!(navmesh.tiles, expected.tiles);
+        let links = |navmesh: &TiledNavmesh| {
+            navmesh
+                .links
+                .iter()
+                .copied()
+                .collect::<std::collections::HashSet<_>>()
+        };
+        assert_eq!(links(&navmesh), links(&expected));
+
+        let other_grid = NavmeshConfig {
+            tile_size: 32,
+            ..config
+        };
+        assert!(matches!(
+            navmesh.rebuild_tiles_premarked(
+                platform,
+                &other_grid,
+                [UVec2::ZERO],
+                &BuildContext::default()
+            ),
+            Err(TiledNavmeshError::MismatchedConfig)
+        ));
+    }
+
+    #[test]
+    fn heightmaps_are_rasterized_into_every_tile_they_touch() {
+        let config = NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::ZERO, [10.0, 2.0, 10.0]),
+            tile_size: 24,
+            ..Default::default()
+        }
+        .build();
+        let heightmap = crate::Heightmap::new(
+            Vec3::new(-10.0, 0.0, -10.0),
+            Vec2::ONE,
+            UVec2::splat(21),
+            vec![0.0; 21 * 21],
+        )
+        .unwrap();
+        let rasterize = |heightfield: &mut Heightfield| {
+            heightfield.rasterize_heightmap(
+                &heightmap,
+                config.walkable_slope_angle,
+                crate::AreaType::DEFAULT_WALKABLE,
+                config.walkable_climb,
+            )
+        };
+        let mut navmesh = TiledNavmesh::build_premarked_with_rasterizer(
+            TriMesh::default(),
+            &config,
+            &BuildContext::default(),
+            rasterize,
+        )
+        .unwrap();
+        let expected = TiledNavmesh::build(floor(10.0), &config, &BuildContext::default()).unwrap();
+        assert_eq!(navmesh, expected);
+
+        navmesh
+            .rebuild_tiles_premarked_with_rasterizer(
+                TriMesh::default(),
+                &config,
+                [UVec2::new(1, 1)],
+                &BuildContext::default(),
+                rasterize,
+            )
+            .unwrap();
+        assert_eq!(navmesh.tiles, expected.tiles);
+    }
+
+    #[test]
+    fn progress_covers_all_tiles_until_cancelled() {
+        let config = NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::ZERO, [10.0, 2.0, 10.0]),
+            tile_size: 24,
+            ..Default::default()
+        }
+        .build();
+        let reported = Mutex::new(Vec::new());
+        let progress = |stage, done| reported.lock().unwrap().push((stage, done));
+        let navmesh = TiledNavmesh::build(
+            floor(10.0),
+            &config,
+            &BuildContext::default().with_progress(&progress),
+        )
+        .unwrap();
+        let done = reported.lock().unwrap().drain(..).collect::<Vec<_>>();
+        assert_eq!(done.len(), navmesh.tiles.len() * BuildStage::ALL.len() + 1);
+        assert!(done.windows(2).all(|pair| pair[0].1 < pair[1].1));
+        assert_eq!(done.last(), Some(&(BuildStage::DetailMesh, 1.0)));
+
+        // Cancel the build while the second tile is being built.
+        let cancel = AtomicBool::new(false);
+        let progress = |_, done| {
+            if done > 1.5 / navmesh.tiles.len() as f32 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        };
+        let result =
+            TiledNavmesh::build(floor(10.0), &config, &BuildContext::new(&progress, &cancel));
+        assert!(matches!(result, Err(TiledNavmeshError::Cancelled)));
+    }
+
+    #[test]
+    fn removed_tiles_can_be_inserted_again() {
+        let config = NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::ZERO, [10.0, 2.0, 10.0]),
+            tile_size: 24,
+            ..Default::default()
+        }
+        .build();
+        let full = TiledNavmesh::build(floor(10.0), &config, &BuildContext::default()).unwrap();
+        let mut streamed = full.clone();
+
+        let center = UVec2::new(1, 1);
+        let removed = streamed.remove_tile(center).unwrap();
+        assert!(streamed.tile(center).unwrap().is_empty());
+        let center_index = streamed.tile_index(center).unwrap();
+        assert!(
+            streamed
+                .links
+                .iter()
+                .all(|link| link.from_tile != center_index && link.to.tile != center_index)
+        );
+        streamed
+            .insert_tile(removed, config.walkable_climb)
+            .unwrap();
+        let links = |navmesh: &TiledNavmesh| {
+            navmesh
+                .links
+                .iter()
+                .copied()
+                .collect::<std::collections::HashSet<_>>()
+        };
+        assert_eq!(streamed.tiles, full.tiles);
+        assert_eq!(links(&streamed), links(&full));
+
+        // Filling an empty navmesh tile by tile in any order ends up with the same navmesh.
+        let mut filled = TiledNavmesh::with_empty_tiles(&config).unwrap();
+        assert!(filled.tiles.iter().all(NavmeshTile::is_empty));
+        for tile in full.tiles.iter().rev() {
+            filled
+                .insert_tile(tile.clone(), config.walkable_climb)
+                .unwrap();
+        }
+        assert_eq!(filled.tiles, full.tiles);
+        assert_eq!(links(&filled), links(&full));
+
+        assert!(matches!(
+            filled.insert_tile(NavmeshTile::empty(UVec2::new(3, 0)), config.walkable_climb),
+            Err(TiledNavmeshError::TileOutOfBounds(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_configs_that_cannot_be_tiled() {
+        let config = NavmeshConfigBuilder {
+            aabb: Aabb3d::new(Vec3::ZERO, [10.0, 2.0, 10.0]),
+            tile_size: 0,
+            ..Default::default()
+        }
+        .build();
+        assert!(matches!(
+            TiledNavmesh::build(floor(10.0), &config, &BuildContext::default()),
+            Err(TiledNavmeshError::ZeroTileSize)
+        ));
+        let config = NavmeshConfig {
+            tile_size: 24,
+            border_size: 0,
+            ..config
+        };
+        assert!(matches!(
+            TiledNavmesh::build(floor(10.0), &config, &BuildContext::default()),
+            Err(TiledNavmeshError::ZeroBorderSize)
+        ));
+    }
+}
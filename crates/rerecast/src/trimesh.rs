@@ -1,6 +1,7 @@
 //! Contains traits and methods for converting [`Collider`]s into trimeshes, expressed as [`TrimeshedCollider`]s.
 
 use glam::{UVec3, Vec3A};
+use thiserror::Error;
 
 use crate::{
     math::{Aabb3d, TriangleIndices as _},
@@ -24,17 +25,72 @@ pub struct TriMesh {
 }
 
 impl TriMesh {
+    /// Creates a new trimesh, checking that all indices point to existing vertices
+    /// and that there is exactly one area type per triangle.
+    pub fn try_new(
+        vertices: Vec<Vec3A>,
+        indices: Vec<UVec3>,
+        area_types: Vec<AreaType>,
+    ) -> Result<Self, TriMeshError> {
+        let trimesh = Self {
+            vertices,
+            indices,
+            area_types,
+        };
+        trimesh.validate()?;
+        Ok(trimesh)
+    }
+
+    /// Creates a new trimesh where all triangles are [`AreaType::NOT_WALKABLE`],
+    /// checking that all indices point to existing vertices.
+    ///
+    /// Use [`TriMesh::mark_walkable_triangles`] afterwards to assign walkable area types.
+    pub fn from_parts(vertices: Vec<Vec3A>, indices: Vec<UVec3>) -> Result<Self, TriMeshError> {
+        let area_types = vec![AreaType::NOT_WALKABLE; indices.len()];
+        Self::try_new(vertices, indices, area_types)
+    }
+
+    /// Checks that all indices point to existing vertices and that there is exactly one area type per triangle.
+    ///
+    /// Rasterizing a trimesh that does not uphold these invariants panics.
+    pub fn validate(&self) -> Result<(), TriMeshError> {
+        if self.vertices.len() > u32::MAX as usize {
+            return Err(TriMeshError::TooManyVertices(self.vertices.len()));
+        }
+        if self.area_types.len() != self.indices.len() {
+            return Err(TriMeshError::AreaTypeCountMismatch {
+                triangles: self.indices.len(),
+                area_types: self.area_types.len(),
+            });
+        }
+        let vertex_count = self.vertices.len();
+        for (triangle, indices) in self.indices.iter().enumerate() {
+            if let Some(&index) = indices
+                .to_array()
+                .iter()
+                .find(|i| **i as usize >= vertex_count)
+            {
+                return Err(TriMeshError::IndexOutOfBounds {
+                    triangle,
+                    index,
+                    vertex_count,
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Extends the trimesh with the vertices and indices of another trimesh.
     /// The indices of `other` will be offset by the number of vertices in `self`.
-    pub fn extend(&mut self, other: TriMesh) {
+    pub fn extend(&mut self, other: &TriMesh) {
         if self.vertices.len() > u32::MAX as usize {
             panic!("Cannot extend a trimesh with more than 2^32 vertices");
         }
         let next_vertex_index = self.vertices.len() as u32;
-        self.vertices.extend(other.vertices);
+        self.vertices.extend_from_slice(&other.vertices);
         self.indices
             .extend(other.indices.iter().map(|i| i + next_vertex_index));
-        self.area_types.extend(other.area_types);
+        self.area_types.extend_from_slice(&other.area_types);
     }
 
     /// Computes the AABB of the trimesh.
@@ -52,13 +108,140 @@ impl TriMesh {
     /// * `threshold_rad` - The threshold angle in radians.
     ///
     pub fn mark_walkable_triangles(&mut self, threshold_rad: f32) {
+        self.mark_walkable_triangles_as(threshold_rad, AreaType::DEFAULT_WALKABLE);
+    }
+
+    /// Same as [`TriMesh::mark_walkable_triangles`], but marks the walkable triangles as `area`
+    /// instead of [`AreaType::DEFAULT_WALKABLE`], e.g. to pre-mark roads.
+    pub fn mark_walkable_triangles_as(&mut self, threshold_rad: f32, area: AreaType) {
         let threshold_cos = threshold_rad.cos();
         for (i, indices) in self.indices.iter().enumerate() {
             let normal = indices.normal(&self.vertices);
 
             if normal.y > threshold_cos {
-                self.area_types[i] = AreaType::DEFAULT_WALKABLE;
+                self.area_types[i] = area;
             }
         }
     }
+
+    /// Assigns every triangle the area type returned by `area`, which is called with the index of the triangle,
+    /// its unit normal and its vertices. Degenerate triangles have a zero normal.
+    ///
+    /// Unlike [`TriMesh::mark_walkable_triangles`], this can take anything into account, e.g. height bands, material IDs or noise.
+    /// Every triangle is overwritten, so return [`AreaType::NOT_WALKABLE`] for the ones that should not be walkable.
+    ///
+    /// ```
+    /// # use glam::{UVec3, Vec3A};
+    /// # use rerecast::{AreaType, TriMesh};
+    /// let mut trimesh = TriMesh::from_parts(
+    ///     vec![
+    ///         // A triangle at the foot of the mountain...
+    ///         Vec3A::ZERO, Vec3A::Z, Vec3A::X,
+    ///         // ...and one at the top.
+    ///         Vec3A::new(0.0, 10.0, 0.0), Vec3A::new(0.0, 10.0, 1.0), Vec3A::new(1.0, 10.0, 0.0),
+    ///     ],
+    ///     vec![UVec3::new(0, 1, 2), UVec3::new(3, 4, 5)],
+    /// )
+    /// .unwrap();
+    /// let max_slope_cos = 45.0_f32.to_radians().cos();
+    /// trimesh.mark_walkable_with(|_triangle, normal, vertices| {
+    ///     // Only flat ground below the snow line is walkable.
+    ///     let below_snow_line = vertices.iter().all(|vertex| vertex.y < 5.0);
+    ///     if normal.y > max_slope_cos && below_snow_line {
+    ///         AreaType::DEFAULT_WALKABLE
+    ///     } else {
+    ///         AreaType::NOT_WALKABLE
+    ///     }
+    /// });
+    /// assert_eq!(trimesh.area_types, [AreaType::DEFAULT_WALKABLE, AreaType::NOT_WALKABLE]);
+    /// ```
+    pub fn mark_walkable_with(
+        &mut self,
+        mut area: impl FnMut(usize, Vec3A, [Vec3A; 3]) -> AreaType,
+    ) {
+        for (i, indices) in self.indices.iter().enumerate() {
+            let normal = indices.normal(&self.vertices);
+            let vertices = indices
+                .to_array()
+                .map(|index| self.vertices[index as usize]);
+            self.area_types[i] = area(i, normal, vertices);
+        }
+    }
+}
+
+/// Errors that can occur when creating a [`TriMesh`] with [`TriMesh::try_new`] or [`TriMesh::from_parts`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TriMeshError {
+    /// Happens when a triangle references a vertex that does not exist.
+    #[error(
+        "Triangle {triangle} references vertex {index}, but there are only {vertex_count} vertices"
+    )]
+    IndexOutOfBounds {
+        /// The index of the offending triangle in [`TriMesh::indices`]
+        triangle: usize,
+        /// The out-of-bounds vertex index
+        index: u32,
+        /// The number of vertices in the trimesh
+        vertex_count: usize,
+    },
+    /// Happens when the number of area types does not match the number of triangles.
+    #[error(
+        "Expected one area type per triangle, got {area_types} area types for {triangles} triangles"
+    )]
+    AreaTypeCountMismatch {
+        /// The number of triangles in [`TriMesh::indices`]
+        triangles: usize,
+        /// The number of area types in [`TriMesh::area_types`]
+        area_types: usize,
+    },
+    /// Happens when there are more vertices than can be indexed with a `u32`.
+    #[error("Trimesh has {0} vertices, but at most 2^32 are supported")]
+    TooManyVertices(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertices() -> Vec<Vec3A> {
+        vec![Vec3A::ZERO, Vec3A::X, Vec3A::Z]
+    }
+
+    #[test]
+    fn try_new_accepts_valid_trimesh() {
+        let trimesh = TriMesh::from_parts(vertices(), vec![UVec3::new(0, 1, 2)]).unwrap();
+        assert_eq!(trimesh.area_types, vec![AreaType::NOT_WALKABLE]);
+    }
+
+    #[test]
+    fn try_new_rejects_out_of_bounds_index() {
+        assert_eq!(
+            TriMesh::from_parts(vertices(), vec![UVec3::new(0, 1, 3)]),
+            Err(TriMeshError::IndexOutOfBounds {
+                triangle: 0,
+                index: 3,
+                vertex_count: 3
+            })
+        );
+    }
+
+    #[test]
+    fn try_new_rejects_area_type_mismatch() {
+        assert_eq!(
+            TriMesh::try_new(vertices(), vec![UVec3::new(0, 1, 2)], vec![]),
+            Err(TriMeshError::AreaTypeCountMismatch {
+                triangles: 1,
+                area_types: 0
+            })
+        );
+    }
+
+    #[test]
+    fn extend_offsets_indices() {
+        let mut trimesh = TriMesh::from_parts(vertices(), vec![UVec3::new(0, 1, 2)]).unwrap();
+        let other = trimesh.clone();
+        trimesh.extend(&other);
+        assert_eq!(trimesh.indices[1], UVec3::new(3, 4, 5));
+        assert_eq!(trimesh.validate(), Ok(()));
+    }
 }
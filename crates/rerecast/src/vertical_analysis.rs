@@ -0,0 +1,294 @@
+//! Suggestions for the vertical resolution of a navmesh, derived from the input geometry.
+//!
+//! Good values for [`NavmeshConfigBuilder::cell_height`] and [`NavmeshConfigBuilder::agent_max_climb`] depend on the level:
+//! a cell height that is too coarse blurs small steps into slopes and closes off low passages,
+//! while a max climb below the height of the stairs disconnects the floors they connect.
+//! [`NavmeshConfigBuilder::analyze_vertical`] measures the steps and gaps of the input and suggests values for both.
+
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::prelude::*;
+use thiserror::Error;
+
+use crate::{
+    AreaType, HeightfieldBuilder, HeightfieldBuilderError, NavmeshConfigBuilder,
+    RasterizationError, Span, TriMesh,
+};
+
+impl NavmeshConfigBuilder {
+    /// Analyzes the steps and gaps between the walkable surfaces of `trimesh` and suggests a
+    /// [`cell_height`](Self::cell_height) and [`agent_max_climb`](Self::agent_max_climb) for it.
+    ///
+    /// The geometry is rasterized with the [`cell_size`](Self::cell_size) and [`agent_max_slope`](Self::agent_max_slope) of the builder,
+    /// but several times finer along the y-axis than the current cell height, so that the result does not depend on the setting it tunes.
+    /// The area types of `trimesh` are ignored.
+    pub fn analyze_vertical(
+        &self,
+        trimesh: &TriMesh,
+    ) -> Result<VerticalAnalysis, VerticalAnalysisError> {
+        let resolution =
+            self.cell_height.min(self.cell_size / 2.0) / VerticalAnalysis::SUBDIVISIONS;
+        let Some(aabb) = trimesh.compute_aabb() else {
+            return Ok(VerticalAnalysis::new(
+                self,
+                resolution,
+                Vec::new(),
+                None,
+                None,
+            ));
+        };
+        // Keep tall levels within the height limit of the spans.
+        let resolution = resolution.max((aabb.max.y - aabb.min.y) / (Span::MAX_HEIGHT - 1) as f32);
+
+        let mut trimesh = trimesh.clone();
+        trimesh.area_types.fill(AreaType::NOT_WALKABLE);
+        trimesh.mark_walkable_triangles(self.agent_max_slope);
+        let mut heightfield = HeightfieldBuilder {
+            aabb,
+            cell_size: self.cell_size,
+            cell_height: resolution,
+        }
+        .build()?;
+        heightfield.rasterize_triangles(&trimesh, 1)?;
+
+        // The heights of the walkable floors of every column.
+        let (width, height) = (heightfield.width as i32, heightfield.height as i32);
+        let mut floors = vec![Vec::new(); width as usize * height as usize];
+        let mut min_gap = None::<u16>;
+        let mut min_passable_gap = None::<u16>;
+        let agent_height = (self.agent_height / resolution).ceil() as u16;
+        for z in 0..heightfield.height {
+            for x in 0..heightfield.width {
+                let mut span_key = heightfield.span_key_at(x, z);
+                while let Some(key) = span_key {
+                    let span = heightfield.span(key);
+                    span_key = span.next;
+                    if !span.area.is_walkable() {
+                        continue;
+                    }
+                    floors[x as usize + z as usize * width as usize].push(span.max);
+                    let Some(next) = span.next else {
+                        continue;
+                    };
+                    let gap = heightfield.span(next).min.saturating_sub(span.max);
+                    min_gap = Some(min_gap.map_or(gap, |min| min.min(gap)));
+                    if gap >= agent_height {
+                        min_passable_gap = Some(min_passable_gap.map_or(gap, |min| min.min(gap)));
+                    }
+                }
+            }
+        }
+
+        let floor_near = |x: i32, z: i32, target: u16| {
+            (0..width).contains(&x)
+                && (0..height).contains(&z)
+                && floors[(x + z * width) as usize]
+                    .iter()
+                    .any(|floor| floor.abs_diff(target) <= 1)
+        };
+        let max_step = (self.agent_height / 2.0 / resolution) as u16;
+        let mut step_histogram = vec![0; max_step as usize + 1];
+        for z in 0..height {
+            for x in 0..width {
+                for &floor in &floors[(x + z * width) as usize] {
+                    for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                        let (nx, nz) = (x + dx, z + dz);
+                        if !(0..width).contains(&nx) || !(0..height).contains(&nz) {
+                            continue;
+                        }
+                        // Steps are counted from their lower side, so that every step edge is only counted once.
+                        let Some(upper) = floors[(nx + nz * width) as usize]
+                            .iter()
+                            .copied()
+                            .filter(|upper| *upper > floor && upper - floor <= max_step)
+                            .min()
+                        else {
+                            continue;
+                        };
+                        // A step needs flat ground on both of its sides, otherwise it's part of a slope.
+                        if !floor_near(x - dx, z - dz, floor)
+                            || !floor_near(nx + dx, nz + dz, upper)
+                        {
+                            continue;
+                        }
+                        let step = upper - floor;
+                        if step >= 2 {
+                            step_histogram[step as usize] += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(VerticalAnalysis::new(
+            self,
+            resolution,
+            step_histogram,
+            min_gap.map(|gap| gap as f32 * resolution),
+            min_passable_gap.map(|gap| gap as f32 * resolution),
+        ))
+    }
+}
+
+/// The result of [`NavmeshConfigBuilder::analyze_vertical`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+#[cfg_attr(
+    all(feature = "serialize", feature = "bevy_reflect"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct VerticalAnalysis {
+    /// The vertical resolution of the analysis, which is also the height covered by each bucket of [`Self::step_histogram`]. `[Units: wu]`
+    pub resolution: f32,
+    /// How often each step height occurs, measured in columns along the edges of the steps.
+    /// Bucket `i` counts the steps that are `i * resolution` high.
+    ///
+    /// Only steps up to half the agent height are counted, as higher ones are walls rather than steps.
+    /// Steps below two buckets are indistinguishable from slopes and are not counted either.
+    pub step_histogram: Vec<u32>,
+    /// The smallest vertical gap above any walkable floor, if there is geometry above one. `[Units: wu]`
+    pub min_gap_height: Option<f32>,
+    /// The smallest vertical gap above a walkable floor that an agent still fits into, if any. `[Units: wu]`
+    pub min_passable_gap_height: Option<f32>,
+    /// The suggested [`NavmeshConfigBuilder::cell_height`]. `[Units: wu]`
+    ///
+    /// This is half the smallest common step height, so that steps are at least two cells high and don't blur into slopes,
+    /// but small enough that rounding at the floor and ceiling doesn't close off the smallest passable gap.
+    /// Without any steps, this is half the cell size, which is the usual starting point.
+    pub suggested_cell_height: f32,
+    /// The suggested [`NavmeshConfigBuilder::agent_max_climb`]. `[Units: wu]`
+    ///
+    /// This is the largest common step height rounded up to the suggested cell height, plus one cell to account for rounding.
+    /// Without any steps, this is the current max climb.
+    pub suggested_agent_max_climb: f32,
+}
+
+impl VerticalAnalysis {
+    /// How much finer than the cell height the geometry is rasterized.
+    const SUBDIVISIONS: f32 = 4.0;
+    /// The share of all steps a step height needs to reach to be considered common.
+    const COMMON_STEP_SHARE: f32 = 0.05;
+
+    fn new(
+        builder: &NavmeshConfigBuilder,
+        resolution: f32,
+        step_histogram: Vec<u32>,
+        min_gap_height: Option<f32>,
+        min_passable_gap_height: Option<f32>,
+    ) -> Self {
+        let mut analysis = Self {
+            resolution,
+            step_histogram,
+            min_gap_height,
+            min_passable_gap_height,
+            suggested_cell_height: builder.cell_height,
+            suggested_agent_max_climb: builder.agent_max_climb,
+        };
+        let common_steps = analysis.common_step_range();
+        let mut cell_height = common_steps.map_or(builder.cell_size / 2.0, |(min, _)| min / 2.0);
+        if let Some(gap) = min_passable_gap_height {
+            cell_height = cell_height.min((gap - builder.agent_height) / 2.0);
+        }
+        analysis.suggested_cell_height = cell_height.max(resolution);
+        if let Some((_, max)) = common_steps {
+            let cells = (max / analysis.suggested_cell_height).ceil() + 1.0;
+            analysis.suggested_agent_max_climb = cells * analysis.suggested_cell_height;
+        }
+        analysis
+    }
+
+    /// The heights of the lowest and highest step that make up a meaningful share of all steps, if there are any steps.
+    /// Rare step heights are ignored, as they are usually caused by props or rounding rather than by the level design.
+    pub fn common_step_range(&self) -> Option<(f32, f32)> {
+        let total: u32 = self.step_histogram.iter().sum();
+        let mut common = self
+            .step_histogram
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| {
+                total > 0 && **count as f32 >= total as f32 * Self::COMMON_STEP_SHARE
+            })
+            .map(|(bucket, _)| bucket as f32 * self.resolution);
+        let min = common.next()?;
+        Some((min, common.next_back().unwrap_or(min)))
+    }
+
+    /// Sets the [`NavmeshConfigBuilder::cell_height`] and [`NavmeshConfigBuilder::agent_max_climb`] to the suggested values.
+    pub fn apply(&self, builder: &mut NavmeshConfigBuilder) {
+        builder.cell_height = self.suggested_cell_height;
+        builder.agent_max_climb = self.suggested_agent_max_climb;
+    }
+}
+
+/// Errors that can occur when analyzing geometry with [`NavmeshConfigBuilder::analyze_vertical`].
+#[derive(Error, Debug)]
+pub enum VerticalAnalysisError {
+    /// Happens when the heightfield used for the analysis could not be built.
+    #[error("Failed to build heightfield: {0}")]
+    Heightfield(#[from] HeightfieldBuilderError),
+    /// Happens when the geometry could not be rasterized.
+    #[error("Failed to rasterize triangles: {0}")]
+    Rasterization(#[from] RasterizationError),
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3A};
+
+    use super::*;
+
+    /// A staircase along the x-axis with steps that are one unit deep.
+    fn stairs(steps: u32, step_height: f32) -> TriMesh {
+        let mut trimesh = TriMesh::default();
+        for step in 0..steps {
+            let (x, y) = (step as f32, step as f32 * step_height);
+            let base = trimesh.vertices.len() as u32;
+            trimesh.vertices.extend([
+                Vec3A::new(x, y, 0.0),
+                Vec3A::new(x, y, 4.0),
+                Vec3A::new(x + 1.0, y, 4.0),
+                Vec3A::new(x + 1.0, y, 0.0),
+            ]);
+            trimesh.indices.extend([
+                UVec3::new(base, base + 1, base + 2),
+                UVec3::new(base, base + 2, base + 3),
+            ]);
+            trimesh.area_types.extend([AreaType::NOT_WALKABLE; 2]);
+        }
+        trimesh
+    }
+
+    #[test]
+    fn stairs_suggest_fitting_climb() {
+        let builder = NavmeshConfigBuilder {
+            cell_size: 0.2,
+            cell_height: 0.2,
+            agent_height: 2.0,
+            agent_max_climb: 0.1,
+            ..Default::default()
+        };
+        let analysis = builder.analyze_vertical(&stairs(5, 0.3)).unwrap();
+
+        let (min, max) = analysis.common_step_range().unwrap();
+        assert!((min - 0.3).abs() <= analysis.resolution, "{min}");
+        assert!((max - 0.3).abs() <= analysis.resolution, "{max}");
+        assert_eq!(analysis.min_gap_height, None);
+        assert!(analysis.suggested_cell_height <= 0.15 + analysis.resolution);
+        assert!(analysis.suggested_agent_max_climb > 0.3);
+        assert!(analysis.suggested_agent_max_climb < 0.6);
+
+        let mut tuned = builder;
+        analysis.apply(&mut tuned);
+        let config = tuned.build();
+        let step_cells = (0.3 / tuned.cell_height).ceil() as u16;
+        assert!(config.walkable_climb > step_cells);
+    }
+
+    #[test]
+    fn empty_input_keeps_settings() {
+        let builder = NavmeshConfigBuilder::default();
+        let analysis = builder.analyze_vertical(&TriMesh::default()).unwrap();
+        assert_eq!(analysis.common_step_range(), None);
+        assert_eq!(analysis.suggested_agent_max_climb, builder.agent_max_climb);
+    }
+}
@@ -0,0 +1,225 @@
+//! Checks whether an agent can walk along a straight line on a navmesh, e.g. to skip waypoints of a path
+//! or to validate generated off-mesh links.
+
+use std::collections::HashSet;
+
+use glam::{Vec2, Vec3, Vec3Swizzles as _};
+
+use crate::{
+    EdgeConnection, EdgeId, PolyId, PolygonNavmesh, VertexId, math::distance_squared_to_segment,
+};
+
+impl PolygonNavmesh {
+    /// Whether an agent with the given `radius` can walk in a straight line from `start` to `end`, both in world space.
+    ///
+    /// This sweeps a circle along the segment on the xz-plane and checks that the whole corridor lies on the navmesh,
+    /// i.e. that neither the segment nor anything within `radius` of it crosses a border edge.
+    /// Portal edges count as border edges, since the polygons behind them are not part of this mesh.
+    ///
+    /// The walk starts on the polygon below or above `start` whose height is closest to it
+    /// and then follows the polygons connected along the segment, so overlapping floors are told apart.
+    /// Apart from picking that polygon, heights are ignored. Returns `false` if no polygon lies below or above `start`.
+    pub fn is_walkable_straight(&self, start: Vec3, end: Vec3, radius: f32) -> bool {
+        let Some(start_polygon) = self.polygon_at(start) else {
+            return false;
+        };
+        let (from, to) = (start.xz(), end.xz());
+        let direction = to - from;
+
+        // Follow the polygons the segment crosses.
+        let mut corridor = vec![start_polygon];
+        let mut polygon = start_polygon;
+        while corridor.len() <= self.polygon_count() {
+            let Some((exit, exit_t)) = self.exit_edge(polygon, from, direction) else {
+                break;
+            };
+            if exit_t >= 1.0 {
+                break;
+            }
+            match self.edge_connection(exit) {
+                EdgeConnection::Polygon(neighbor) => polygon = neighbor,
+                EdgeConnection::Portal(_) | EdgeConnection::Border => return false,
+            }
+            // Guards against cycling on degenerate geometry.
+            if corridor.contains(&polygon) {
+                return false;
+            }
+            corridor.push(polygon);
+        }
+        if radius <= 0.0 {
+            return true;
+        }
+
+        // Check the polygons the circle touches along the way for border edges within its radius.
+        let radius_squared = radius * radius;
+        let mut visited: HashSet<PolyId> = corridor.iter().copied().collect();
+        let mut stack = corridor;
+        while let Some(polygon) = stack.pop() {
+            for edge in self.edges(polygon) {
+                let [a, b] = self.edge_vertices(edge).map(|vertex| self.world_xz(vertex));
+                if segment_distance_squared((from, to), (a, b)) >= radius_squared {
+                    continue;
+                }
+                match self.edge_connection(edge) {
+                    EdgeConnection::Polygon(neighbor) => {
+                        if visited.insert(neighbor) {
+                            stack.push(neighbor);
+                        }
+                    }
+                    EdgeConnection::Portal(_) | EdgeConnection::Border => return false,
+                }
+            }
+        }
+        true
+    }
+
+    /// The polygon containing `point` on the xz-plane whose vertical extent is closest to it, if any.
+    fn polygon_at(&self, point: Vec3) -> Option<PolyId> {
+        self.polygon_ids()
+            .filter(|polygon| {
+                let vertices = self
+                    .polygon_vertices(*polygon)
+                    .map(|vertex| self.world_xz(vertex))
+                    .collect::<Vec<_>>();
+                contains_point(&vertices, point.xz())
+            })
+            .map(|polygon| {
+                let (min, max) = self
+                    .polygon_vertices(polygon)
+                    .map(|vertex| self.vertex_world(vertex).y)
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), y| {
+                        (min.min(y), max.max(y))
+                    });
+                let distance = (min - point.y).max(point.y - max).max(0.0);
+                (polygon, distance)
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(polygon, _)| polygon)
+    }
+
+    /// The edge through which the ray `from + t * direction` leaves the given polygon, along with `t` at that point.
+    /// Returns `None` if the ray never leaves it, i.e. if `direction` is zero.
+    fn exit_edge(&self, polygon: PolyId, from: Vec2, direction: Vec2) -> Option<(EdgeId, f32)> {
+        let vertices = self
+            .polygon_vertices(polygon)
+            .map(|vertex| self.world_xz(vertex))
+            .collect::<Vec<_>>();
+        let orientation = winding(&vertices);
+        self.edges(polygon)
+            .zip(&vertices)
+            .zip(vertices.iter().cycle().skip(1))
+            .filter_map(|((edge, a), b)| {
+                let along = b - a;
+                // How fast the ray moves towards the inside of the edge. Only edges it moves away from can be exits.
+                let speed = along.perp_dot(direction) * orientation;
+                (speed < 0.0).then(|| (edge, -along.perp_dot(from - a) * orientation / speed))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    fn world_xz(&self, vertex: VertexId) -> Vec2 {
+        self.vertex_world(vertex).xz()
+    }
+}
+
+/// `1` if the polygon winds counter-clockwise on the xz-plane, `-1` if it winds clockwise.
+pub(crate) fn winding(vertices: &[Vec2]) -> f32 {
+    let area: f32 = vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum();
+    area.signum()
+}
+
+/// Whether the convex polygon contains the point on the xz-plane, including its edges.
+pub(crate) fn contains_point(vertices: &[Vec2], point: Vec2) -> bool {
+    let orientation = winding(vertices);
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .all(|(a, b)| (b - a).perp_dot(point - a) * orientation >= 0.0)
+}
+
+/// The squared distance between two segments on the xz-plane.
+fn segment_distance_squared((a0, a1): (Vec2, Vec2), (b0, b1): (Vec2, Vec2)) -> f32 {
+    let side = |p: Vec2, (q0, q1): (Vec2, Vec2)| (q1 - q0).perp_dot(p - q0);
+    let crosses = side(a0, (b0, b1)) * side(a1, (b0, b1)) < 0.0
+        && side(b0, (a0, a1)) * side(b1, (a0, a1)) < 0.0;
+    if crosses {
+        return 0.0;
+    }
+    [
+        distance_squared_to_segment(a0, b0, b1),
+        distance_squared_to_segment(a1, b0, b1),
+        distance_squared_to_segment(b0, a0, a1),
+        distance_squared_to_segment(b1, a0, a1),
+    ]
+    .into_iter()
+    .fold(f32::INFINITY, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::U16Vec3;
+
+    use super::*;
+    use crate::{Aabb3d, AreaType, RegionId};
+
+    /// An L-shaped navmesh: two 4x4 quads next to each other along the x-axis and one along the z-axis.
+    fn l_shape() -> PolygonNavmesh {
+        const N: u16 = PolygonNavmesh::NO_INDEX;
+        PolygonNavmesh {
+            vertices: vec![
+                U16Vec3::new(0, 0, 0),
+                U16Vec3::new(0, 0, 4),
+                U16Vec3::new(4, 0, 4),
+                U16Vec3::new(4, 0, 0),
+                U16Vec3::new(8, 0, 4),
+                U16Vec3::new(8, 0, 0),
+                U16Vec3::new(0, 0, 8),
+                U16Vec3::new(4, 0, 8),
+            ],
+            polygons: vec![0, 1, 2, 3, 3, 2, 4, 5, 1, 6, 7, 2],
+            polygon_neighbors: vec![N, 2, 1, N, 0, N, N, N, N, N, N, 0],
+            flags: vec![0; 3],
+            regions: vec![RegionId::from(1); 3],
+            areas: vec![AreaType::DEFAULT_WALKABLE; 3],
+            max_vertices_per_polygon: 4,
+            aabb: Aabb3d {
+                min: Vec3::ZERO,
+                max: Vec3::new(8.0, 0.0, 8.0),
+            },
+            cell_size: 1.0,
+            cell_height: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn corridor_must_stay_on_navmesh() {
+        let navmesh = l_shape();
+        let walkable = |start: [f32; 2], end: [f32; 2], radius: f32| {
+            navmesh.is_walkable_straight(
+                Vec3::new(start[0], 0.0, start[1]),
+                Vec3::new(end[0], 0.0, end[1]),
+                radius,
+            )
+        };
+
+        // Along the first two quads.
+        assert!(walkable([1.0, 2.0], [7.0, 2.0], 0.0));
+        assert!(walkable([1.0, 2.0], [7.0, 2.0], 0.9));
+        assert!(!walkable([1.0, 2.0], [7.0, 2.0], 1.5));
+        // Around the inner corner of the L.
+        assert!(walkable([1.0, 6.0], [6.0, 1.0], 0.0));
+        assert!(walkable([1.0, 6.0], [6.0, 1.0], 0.5));
+        assert!(!walkable([1.0, 6.0], [6.0, 1.0], 1.0));
+        assert!(!walkable([2.0, 7.0], [7.0, 2.0], 0.0));
+        // Off the navmesh.
+        assert!(!walkable([1.0, 2.0], [9.0, 2.0], 0.0));
+        assert!(!walkable([-1.0, 2.0], [1.0, 2.0], 0.0));
+        // Standing still.
+        assert!(walkable([2.0, 2.0], [2.0, 2.0], 1.0));
+    }
+}
@@ -1,5 +1,9 @@
+use std::collections::HashSet;
+
+use glam::Vec3;
+
 use crate::{
-    AreaType, CompactHeightfield, RegionId,
+    AreaType, BuildScratch, CompactHeightfield, RegionId,
     math::{dir_offset_x, dir_offset_z},
 };
 
@@ -24,6 +28,22 @@ impl CompactHeightfield {
         border_size: u16,
         min_region_area: u16,
         merge_region_area: u16,
+    ) -> Result<(), BuildRegionsError> {
+        self.build_watershed_regions_with_scratch(
+            border_size,
+            min_region_area,
+            merge_region_area,
+            &mut BuildScratch::default(),
+        )
+    }
+
+    /// Same as [`CompactHeightfield::build_regions`], but takes the temporary buffers from `scratch`.
+    pub(crate) fn build_watershed_regions_with_scratch(
+        &mut self,
+        border_size: u16,
+        min_region_area: u16,
+        merge_region_area: u16,
+        scratch: &mut BuildScratch,
     ) -> Result<(), BuildRegionsError> {
         const LOG_NB_STACKS: usize = 3;
         const NB_STACKS: usize = 1 << LOG_NB_STACKS;
@@ -34,8 +54,8 @@ impl CompactHeightfield {
 
         let mut stack: Vec<LevelStackEntry> = Vec::with_capacity(256);
 
-        let mut src_reg = vec![RegionId::NONE; self.spans.len()];
-        let mut src_dist = vec![0_u16; self.spans.len()];
+        let mut src_reg = scratch.regions.take(self.spans.len(), RegionId::NONE);
+        let mut src_dist = scratch.distances.take(self.spans.len(), 0);
 
         let mut region_id = RegionId::from(1);
         let mut level = (self.max_distance + 1) & !1;
@@ -167,11 +187,195 @@ impl CompactHeightfield {
         for i in 0..self.spans.len() {
             self.spans[i].region = src_reg[i];
         }
+        scratch.regions.give(src_reg);
+        scratch.distances.give(src_dist);
 
         Ok(())
     }
 
-    fn merge_and_filter_regions(
+    /// Same as [`CompactHeightfield::build_regions`], but afterwards discards all regions that are not connected
+    /// to any of the `seeds` through walkable spans.
+    ///
+    /// Seeds are world-space positions an agent can be at, e.g. spawn points.
+    /// Each seed starts at the walkable span in its column whose floor is closest to the seed.
+    /// Unreachable surfaces like rooftops and ledges are thus dropped before the contours are built,
+    /// which saves work in all following stages. Discarded spans keep their area, but their region is reset to [`RegionId::NONE`].
+    ///
+    /// Seeds outside the heightfield are ignored. If no seed lies on a walkable span, all regions are discarded.
+    pub fn build_regions_with_seeds(
+        &mut self,
+        border_size: u16,
+        min_region_area: u16,
+        merge_region_area: u16,
+        seeds: &[Vec3],
+    ) -> Result<(), BuildRegionsError> {
+        self.build_regions(border_size, min_region_area, merge_region_area)?;
+        self.discard_unreachable_regions(seeds);
+        Ok(())
+    }
+
+    /// Discards all regions that are not connected to any of the `seeds` through walkable spans,
+    /// no matter which [`RegionPartitioning`](crate::RegionPartitioning) built them. See [`CompactHeightfield::build_regions_with_seeds`].
+    pub fn discard_unreachable_regions(&mut self, seeds: &[Vec3]) {
+        let mut visited = vec![false; self.spans.len()];
+        let mut stack = Vec::new();
+        for seed in seeds {
+            let cell = ((seed - self.aabb.min) / self.cell_size).floor();
+            if cell.x < 0.0
+                || cell.z < 0.0
+                || cell.x >= self.width as f32
+                || cell.z >= self.height as f32
+            {
+                continue;
+            }
+            let (x, z) = (cell.x as u16, cell.z as u16);
+            let closest = self.cell_at(x, z).index_range().min_by(|&a, &b| {
+                let distance = |i: usize| {
+                    (self.aabb.min.y + self.spans[i].y as f32 * self.cell_height - seed.y).abs()
+                };
+                distance(a).total_cmp(&distance(b))
+            });
+            if let Some(i) = closest.filter(|&i| self.areas[i].is_walkable())
+                && !visited[i]
+            {
+                visited[i] = true;
+                stack.push((x as i32, z as i32, i));
+            }
+        }
+
+        while let Some((x, z, i)) = stack.pop() {
+            for dir in 0..4 {
+                let Some(con) = self.spans[i].con(dir) else {
+                    continue;
+                };
+                let (nx, nz, ni) = self.con_indices(x, z, dir, con);
+                if visited[ni] || !self.areas[ni].is_walkable() {
+                    continue;
+                }
+                visited[ni] = true;
+                stack.push((nx, nz, ni));
+            }
+        }
+
+        let reachable = self
+            .spans
+            .iter()
+            .zip(&visited)
+            .filter_map(|(span, visited)| visited.then_some(span.region))
+            .collect::<HashSet<_>>();
+        for span in &mut self.spans {
+            if !span.region.contains(RegionId::BORDER_REGION) && !reachable.contains(&span.region) {
+                span.region = RegionId::NONE;
+            }
+        }
+    }
+
+    /// Splits every region that is more than `max_extent` cells wide or deep along a grid with cells of that size,
+    /// so that huge open areas don't end up as a single contour with thousands of vertices.
+    ///
+    /// This matches tiled builds, where no region can be larger than a tile. The grid starts at the inner edge of the
+    /// [`CompactHeightfield::border_size`]. Every connected piece of a region within a grid cell becomes its own region,
+    /// so that all regions stay connected. Regions that fit are left untouched.
+    ///
+    /// A `max_extent` of zero disables splitting.
+    ///
+    /// Warning: The regions must be built using [`CompactHeightfield::build_regions`] before attempting to split them.
+    pub fn split_large_regions(&mut self, max_extent: u16) -> Result<(), BuildRegionsError> {
+        if max_extent == 0 {
+            return Ok(());
+        }
+        let region_count = self.max_region.bits() as usize + 1;
+        let is_splittable = |region: RegionId| {
+            region != RegionId::NONE && !region.contains(RegionId::BORDER_REGION)
+        };
+
+        // Find the bounds of every region on the xz-plane.
+        let mut bounds = vec![(u16::MAX, u16::MAX, 0, 0); region_count];
+        for z in 0..self.height {
+            for x in 0..self.width {
+                for i in self.cell_at(x, z).index_range() {
+                    let region = self.spans[i].region;
+                    if !is_splittable(region) {
+                        continue;
+                    }
+                    let (min_x, min_z, max_x, max_z) = &mut bounds[region.bits() as usize];
+                    *min_x = (*min_x).min(x);
+                    *min_z = (*min_z).min(z);
+                    *max_x = (*max_x).max(x);
+                    *max_z = (*max_z).max(z);
+                }
+            }
+        }
+        let oversized = bounds
+            .iter()
+            .map(|&(min_x, min_z, max_x, max_z)| {
+                min_x <= max_x && (max_x - min_x >= max_extent || max_z - min_z >= max_extent)
+            })
+            .collect::<Vec<_>>();
+        if !oversized.contains(&true) {
+            return Ok(());
+        }
+
+        let border_size = self.border_size as i32;
+        let grid_cell = |x: i32, z: i32| {
+            (
+                (x - border_size).div_euclid(max_extent as i32),
+                (z - border_size).div_euclid(max_extent as i32),
+            )
+        };
+        // The first piece of every region keeps its id, the others get new ones.
+        let mut keeps_id = vec![true; region_count];
+        let mut visited = vec![false; self.spans.len()];
+        let mut next_id = self.max_region;
+        let mut stack = Vec::new();
+        for z in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                for i in self.cell_at(x as u16, z as u16).index_range() {
+                    let region = self.spans[i].region;
+                    if visited[i] || !is_splittable(region) || !oversized[region.bits() as usize] {
+                        continue;
+                    }
+                    let piece = if std::mem::take(&mut keeps_id[region.bits() as usize]) {
+                        region
+                    } else {
+                        // Don't spill into the border flag.
+                        if next_id.bits() + 1 >= RegionId::BORDER_REGION.bits() {
+                            return Err(BuildRegionsError::RegionIdOverflow);
+                        }
+                        next_id += 1;
+                        next_id
+                    };
+
+                    let cell = grid_cell(x, z);
+                    visited[i] = true;
+                    self.spans[i].region = piece;
+                    stack.push((x, z, i));
+                    while let Some((x, z, i)) = stack.pop() {
+                        for dir in 0..4 {
+                            let Some(con) = self.spans[i].con(dir) else {
+                                continue;
+                            };
+                            let (nx, nz, ni) = self.con_indices(x, z, dir, con);
+                            if visited[ni]
+                                || self.spans[ni].region != region
+                                || grid_cell(nx, nz) != cell
+                            {
+                                continue;
+                            }
+                            visited[ni] = true;
+                            self.spans[ni].region = piece;
+                            stack.push((nx, nz, ni));
+                        }
+                    }
+                }
+            }
+        }
+        self.max_region = next_id;
+
+        Ok(())
+    }
+
+    pub(crate) fn merge_and_filter_regions(
         &mut self,
         min_region_area: u16,
         merge_region_size: u16,
@@ -621,7 +825,7 @@ impl CompactHeightfield {
         count > 0
     }
 
-    fn paint_rect_region(
+    pub(crate) fn paint_rect_region(
         &self,
         min_x: u16,
         max_x: u16,
@@ -830,24 +1034,21 @@ struct DirtyEntry {
 }
 
 #[derive(Debug, Clone)]
-struct Region {
-    span_count: usize,
-    id: RegionId,
-    area: AreaType,
-    remap: bool,
-    visited: bool,
-    overlap: bool,
-    #[expect(dead_code, reason = "Used by the layer API")]
-    connects_to_border: bool,
-    #[expect(dead_code, reason = "Used by the layer API")]
-    y_min: u16,
-    #[expect(dead_code, reason = "Used by the layer API")]
-    y_max: u16,
-    connections: Vec<RegionId>,
-    floors: Vec<RegionId>,
+pub(crate) struct Region {
+    pub(crate) span_count: usize,
+    pub(crate) id: RegionId,
+    pub(crate) area: AreaType,
+    pub(crate) remap: bool,
+    pub(crate) visited: bool,
+    pub(crate) overlap: bool,
+    pub(crate) connects_to_border: bool,
+    pub(crate) y_min: u16,
+    pub(crate) y_max: u16,
+    pub(crate) connections: Vec<RegionId>,
+    pub(crate) floors: Vec<RegionId>,
 }
 impl Region {
-    fn new(id: RegionId) -> Self {
+    pub(crate) fn new(id: RegionId) -> Self {
         Self {
             id,
             span_count: 0,
@@ -863,7 +1064,7 @@ impl Region {
         }
     }
 
-    fn add_unique_floor_region(&mut self, floor_id: RegionId) {
+    pub(crate) fn add_unique_floor_region(&mut self, floor_id: RegionId) {
         if self.floors.contains(&floor_id) {
             return;
         }
@@ -989,3 +1190,99 @@ pub enum BuildRegionsError {
     #[error("Region ID overflow")]
     RegionIdOverflow,
 }
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec3, Vec3A};
+
+    use super::*;
+    use crate::{Aabb3d, HeightfieldBuilder, TriMesh};
+
+    #[test]
+    fn regions_unreachable_from_seeds_are_discarded() {
+        // Two floors at different heights that are not connected.
+        let mut trimesh = TriMesh::default();
+        for (x, y) in [(1.0, 0.0), (7.0, 3.0)] {
+            trimesh.extend(
+                &TriMesh::from_parts(
+                    vec![
+                        Vec3A::new(x, y, 1.0),
+                        Vec3A::new(x, y, 5.0),
+                        Vec3A::new(x + 4.0, y, 5.0),
+                        Vec3A::new(x + 4.0, y, 1.0),
+                    ],
+                    vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+                )
+                .unwrap(),
+            );
+        }
+        trimesh.mark_walkable_triangles(45.0_f32.to_radians());
+        let mut heightfield = HeightfieldBuilder {
+            aabb: Aabb3d::new(Vec3::new(6.0, 2.0, 3.0), [6.0, 2.0, 3.0]),
+            cell_size: 0.5,
+            cell_height: 0.2,
+        }
+        .build()
+        .unwrap();
+        heightfield.rasterize_triangles(&trimesh, 1).unwrap();
+        let mut compact = heightfield.into_compact(4, 1).unwrap();
+        compact.build_distance_field();
+        compact
+            .build_regions_with_seeds(0, 0, 0, &[Vec3::new(3.0, 0.0, 3.0)])
+            .unwrap();
+
+        let regions_at = |x: u16, z: u16| {
+            compact
+                .cell_at(x, z)
+                .index_range()
+                .map(|i| compact.spans[i].region)
+                .collect::<Vec<_>>()
+        };
+        assert!(regions_at(6, 6).iter().all(|r| *r != RegionId::NONE));
+        assert_eq!(regions_at(18, 6), vec![RegionId::NONE]);
+    }
+
+    #[test]
+    fn large_regions_are_split_along_grid() {
+        let mut trimesh = TriMesh::from_parts(
+            vec![
+                Vec3A::new(0.0, 0.0, 0.0),
+                Vec3A::new(0.0, 0.0, 10.0),
+                Vec3A::new(10.0, 0.0, 10.0),
+                Vec3A::new(10.0, 0.0, 0.0),
+            ],
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+        )
+        .unwrap();
+        trimesh.mark_walkable_triangles(45.0_f32.to_radians());
+        let mut heightfield = HeightfieldBuilder {
+            aabb: Aabb3d::new(Vec3::new(5.0, 1.0, 5.0), [5.0, 1.0, 5.0]),
+            cell_size: 0.5,
+            cell_height: 0.2,
+        }
+        .build()
+        .unwrap();
+        heightfield.rasterize_triangles(&trimesh, 1).unwrap();
+        let mut compact = heightfield.into_compact(4, 1).unwrap();
+        compact.build_distance_field();
+        compact.build_regions(0, 0, 400).unwrap();
+        let unsplit = compact.max_region;
+
+        compact.split_large_regions(5).unwrap();
+        assert!(compact.max_region > unsplit);
+        // Every region now fits into a single 5x5 grid cell. Merged regions leave unused ids behind.
+        for region in 1..=compact.max_region.bits() {
+            let cells = (0..compact.height)
+                .flat_map(|z| (0..compact.width).map(move |x| (x, z)))
+                .filter(|&(x, z)| {
+                    compact
+                        .cell_at(x, z)
+                        .index_range()
+                        .any(|i| compact.spans[i].region == RegionId::from(region))
+                })
+                .map(|(x, z)| (x / 5, z / 5))
+                .collect::<HashSet<_>>();
+            assert!(cells.len() <= 1, "region {region} spans {cells:?}");
+        }
+    }
+}
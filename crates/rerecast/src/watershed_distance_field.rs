@@ -9,24 +9,29 @@
 //!   * generally the best choice if you precompute the navmesh, use this if you have large open areas
 
 use crate::{
-    CompactHeightfield,
+    BuildScratch, CompactHeightfield,
     math::{dir_offset_x, dir_offset_z},
 };
 
 impl CompactHeightfield {
     /// Prepare for region partitioning, by calculating distance field along the walkable surface.
     pub fn build_distance_field(&mut self) {
-        let distance_field = self.calculate_distance_field();
-        // Safety: Unwrap is fine as long as `spans` is not empty, as `distance_field` has the same length
-        self.max_distance = *distance_field.iter().max().unwrap();
-        let distance_field = self.box_blur(1, &distance_field);
-        self.dist = distance_field;
+        self.build_distance_field_with_scratch(&mut BuildScratch::default());
     }
 
-    fn calculate_distance_field(&self) -> Vec<u16> {
-        // Init distance and points.
-        let mut distance_field = vec![u16::MAX; self.spans.len()];
+    /// Same as [`CompactHeightfield::build_distance_field`], but takes the buffers from `scratch`.
+    pub(crate) fn build_distance_field_with_scratch(&mut self, scratch: &mut BuildScratch) {
+        let distance_field =
+            self.calculate_distance_field(scratch.distances.take(self.spans.len(), u16::MAX));
+        // The original assumes that there is at least one span, but an empty heightfield is valid input.
+        self.max_distance = distance_field.iter().max().copied().unwrap_or_default();
+        scratch.dist.give(std::mem::take(&mut self.dist));
+        self.dist = self.box_blur(1, &distance_field, scratch.dist.take(self.spans.len(), 0));
+        scratch.distances.give(distance_field);
+    }
 
+    /// Calculates the distance field into `distance_field`, which must hold `u16::MAX` for every span.
+    fn calculate_distance_field(&self, mut distance_field: Vec<u16>) -> Vec<u16> {
         // Mark boundary cells.
         for z in 0..self.height {
             for x in 0..self.width {
@@ -149,9 +154,9 @@ impl CompactHeightfield {
         distance_field
     }
 
-    fn box_blur(&self, threshold: u16, distance_field: &[u16]) -> Vec<u16> {
+    /// Blurs `distance_field` into `result`, which must be as long as `distance_field`.
+    fn box_blur(&self, threshold: u16, distance_field: &[u16], mut result: Vec<u16>) -> Vec<u16> {
         let threshold = threshold.saturating_mul(2);
-        let mut result = vec![0; distance_field.len()];
 
         for z in 0..self.height {
             for x in 0..self.width {
@@ -5,8 +5,8 @@ use std::{env, fs, path::PathBuf};
 use glam::{U8Vec3, UVec3, Vec2, Vec3, Vec3A};
 use rerecast::{
     Aabb3d, AreaType, BuildContoursFlags, CompactHeightfield, ContourSet, ConvexVolume,
-    DetailNavmesh, Heightfield, HeightfieldBuilder, NavmeshConfig, PolygonNavmesh, RegionId,
-    TriMesh,
+    DetailNavmesh, DetailSampling, ErosionMethod, Heightfield, HeightfieldBuilder, LedgeFilter,
+    NavmeshConfig, PolygonNavmesh, RegionId, RegionPartitioning, TriMesh,
 };
 use serde::{Deserialize, de::DeserializeOwned};
 use serde_json::Value;
@@ -68,8 +68,11 @@ fn validate_navmesh_against_cpp_implementation() {
 
         assert_eq_compact_heightfield(&compact_heightfield, project, "compact_heightfield_initial");
 
+        let mut flat_eroded = compact_heightfield.clone();
         compact_heightfield.erode_walkable_area(config.walkable_radius);
         assert_eq_compact_heightfield(&compact_heightfield, project, "compact_heightfield_eroded");
+        flat_eroded.erode_walkable_area_with(config.walkable_radius, ErosionMethod::FlatChamfer);
+        assert_eq_compact_heightfield(&flat_eroded, project, "compact_heightfield_eroded");
 
         let volumes = load_json::<CppVolumes>(project, "convex_volumes");
         for volume in volumes.volumes {
@@ -152,10 +155,17 @@ fn load_config(project: &str) -> NavmeshConfig {
         max_simplification_error: config.max_simplification_error,
         min_region_area: config.min_region_area,
         merge_region_area: config.merge_region_area,
+        max_region_extent: None,
         max_vertices_per_polygon: config.max_verts_per_poly,
         detail_sample_dist: config.detail_sample_dist,
         detail_sample_max_error: config.detail_sample_max_error,
+        detail_sampling: DetailSampling::Grid,
         contour_flags: BuildContoursFlags::default(),
+        ledge_filter: LedgeFilter::Reference,
+        erosion_method: ErosionMethod::default(),
+        cache_polygon_geometry: false,
+        slope_cost: 0.0,
+        region_partitioning: RegionPartitioning::Watershed,
     }
 }
 
@@ -0,0 +1,79 @@
+//! Builds navmeshes for generated [`StressScene`]s and checks that their structure survives the whole pipeline.
+
+use std::collections::HashSet;
+
+use rerecast::{
+    EdgeConnection, NavmeshConfigBuilder, PolyId, PolygonNavmesh, StressScene, build_navmesh,
+};
+
+fn build(scene: &StressScene) -> PolygonNavmesh {
+    let config = NavmeshConfigBuilder {
+        aabb: scene.aabb(),
+        ..Default::default()
+    }
+    .build();
+    let (polygon_mesh, _detail_mesh) = build_navmesh(scene.build(), &config).unwrap();
+    polygon_mesh
+}
+
+/// The polygons connected to `start` through shared edges.
+fn connected(mesh: &PolygonNavmesh, start: PolyId) -> HashSet<PolyId> {
+    let mut visited = HashSet::from([start]);
+    let mut stack = vec![start];
+    while let Some(polygon) = stack.pop() {
+        for edge in mesh.edges(polygon) {
+            if let EdgeConnection::Polygon(neighbor) = mesh.edge_connection(edge)
+                && visited.insert(neighbor)
+            {
+                stack.push(neighbor);
+            }
+        }
+    }
+    visited
+}
+
+#[test]
+fn every_room_on_every_floor_is_reachable() {
+    let scene = StressScene::new(2).with_floors(3);
+    let mesh = build(&scene);
+
+    // Start on the ground floor of the first room, next to the foot of the ramp.
+    let start = mesh
+        .polygon_ids()
+        .find(|&polygon| {
+            let center = mesh.center(polygon);
+            center.y < 1.0 && center.x < scene.room_size && center.z < scene.room_size
+        })
+        .expect("The first room of the ground floor has polygons");
+    let reachable = connected(&mesh, start);
+
+    let rooms = reachable
+        .iter()
+        .map(|&polygon| {
+            let center = mesh.center(polygon);
+            (
+                (center.x / scene.room_size) as u32,
+                (center.y / scene.floor_height).round() as u32,
+                (center.z / scene.room_size) as u32,
+            )
+        })
+        .collect::<HashSet<_>>();
+    for floor in 0..scene.floors {
+        for z in 0..scene.rooms.y {
+            for x in 0..scene.rooms.x {
+                assert!(
+                    rooms.contains(&(x, floor, z)),
+                    "Room ({x}, {z}) on floor {floor} is not reachable"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn scenes_are_deterministic() {
+    let scene = StressScene::new(2);
+    assert_eq!(scene.build(), scene.build());
+    assert_ne!(scene.build(), scene.with_seed(1).build());
+    assert!(scene.with_props(0).build().indices.len() < scene.build().indices.len());
+}
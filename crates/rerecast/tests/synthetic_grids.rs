@@ -0,0 +1,396 @@
+//! Checks qualitative properties of the filtering, region, contour and polygon mesh stages on small heightfields
+//! that are constructed span by span, so that they do not depend on the rasterizer.
+
+use std::collections::{HashMap, HashSet};
+
+use glam::Vec3;
+use rerecast::{
+    Aabb3d, AreaType, BuildContoursFlags, CompactHeightfield, ContourSet, Heightfield,
+    HeightfieldBuilder, LedgeFilter, PolygonNavmesh, RegionId, Span,
+};
+
+const CELL_SIZE: f32 = 0.5;
+const CELL_HEIGHT: f32 = 0.2;
+const WALKABLE_HEIGHT: u16 = 10;
+const WALKABLE_CLIMB: u16 = 2;
+
+/// Builds a `width` by `depth` heightfield where `floors` returns the heights of the walkable floors in each column,
+/// from bottom to top. Every floor is the top of a one voxel thick solid span.
+fn heightfield(width: u16, depth: u16, floors: impl Fn(u16, u16) -> Vec<u16>) -> Heightfield {
+    let mut heightfield = HeightfieldBuilder {
+        aabb: Aabb3d {
+            min: Vec3::ZERO,
+            max: Vec3::new(
+                width as f32 * CELL_SIZE,
+                u8::MAX as f32 * CELL_HEIGHT,
+                depth as f32 * CELL_SIZE,
+            ),
+        },
+        cell_size: CELL_SIZE,
+        cell_height: CELL_HEIGHT,
+    }
+    .build()
+    .unwrap();
+    for z in 0..depth {
+        for x in 0..width {
+            let mut next = None;
+            for floor in floors(x, z).into_iter().rev() {
+                next = Some(heightfield.allocated_spans.insert(Span {
+                    min: floor.saturating_sub(1),
+                    max: floor,
+                    area: AreaType::DEFAULT_WALKABLE,
+                    next,
+                }));
+            }
+            heightfield.spans[x as usize + z as usize * width as usize] = next;
+        }
+    }
+    heightfield
+}
+
+/// Runs the stages from compaction up to the regions, keeping only the regions reachable from `seeds` if there are any.
+fn regions(heightfield: Heightfield, erosion_radius: u16, seeds: &[Vec3]) -> CompactHeightfield {
+    let mut compact = heightfield
+        .into_compact(WALKABLE_HEIGHT, WALKABLE_CLIMB)
+        .unwrap();
+    compact.erode_walkable_area(erosion_radius);
+    compact.build_distance_field();
+    if seeds.is_empty() {
+        compact.build_regions(0, 0, 0).unwrap();
+    } else {
+        compact.build_regions_with_seeds(0, 0, 0, seeds).unwrap();
+    }
+    compact
+}
+
+fn contours(compact: &CompactHeightfield) -> ContourSet {
+    compact.build_contours(1.3, 0, BuildContoursFlags::default())
+}
+
+/// The world-space position on the floor of the given column.
+fn seed(x: u16, z: u16, floor: u16) -> Vec3 {
+    Vec3::new(
+        (x as f32 + 0.5) * CELL_SIZE,
+        floor as f32 * CELL_HEIGHT,
+        (z as f32 + 0.5) * CELL_SIZE,
+    )
+}
+
+/// The regions of all spans in the given column, from bottom to top.
+fn regions_at(compact: &CompactHeightfield, x: u16, z: u16) -> Vec<RegionId> {
+    compact
+        .cell_at(x, z)
+        .index_range()
+        .map(|i| compact.spans[i].region)
+        .collect()
+}
+
+/// The floor heights of all spans in each region.
+fn region_floors(compact: &CompactHeightfield) -> HashMap<RegionId, HashSet<u16>> {
+    let mut floors: HashMap<RegionId, HashSet<u16>> = HashMap::new();
+    for span in &compact.spans {
+        if span.region != RegionId::NONE {
+            floors.entry(span.region).or_default().insert(span.y);
+        }
+    }
+    floors
+}
+
+/// The area of a polygon in square cells, projected onto the xz-plane.
+fn polygon_area(mesh: &PolygonNavmesh, polygon: rerecast::PolyId) -> f32 {
+    let vertices = mesh
+        .polygon_vertices(polygon)
+        .map(|vertex| mesh.vertex(vertex).as_vec3())
+        .collect::<Vec<_>>();
+    let doubled = (0..vertices.len())
+        .map(|i| {
+            let (a, b) = (vertices[i], vertices[(i + 1) % vertices.len()]);
+            a.x * b.z - b.x * a.z
+        })
+        .sum::<f32>();
+    doubled.abs() * 0.5
+}
+
+/// Steps of 8x12 cells, rising by `rise` voxels each.
+fn stairs(rise: u16) -> Heightfield {
+    heightfield(40, 12, |x, _z| vec![10 + x / 8 * rise])
+}
+
+#[test]
+fn stairs_within_climb_are_connected() {
+    let compact = regions(stairs(WALKABLE_CLIMB), 0, &[seed(1, 1, 10)]);
+    for z in 0..12 {
+        for x in 0..40 {
+            assert_ne!(
+                regions_at(&compact, x, z),
+                vec![RegionId::NONE],
+                "({x}, {z})"
+            );
+        }
+    }
+
+    let mesh = contours(&compact).into_polygon_mesh(6).unwrap();
+    let total_area = mesh
+        .polygon_ids()
+        .map(|polygon| polygon_area(&mesh, polygon))
+        .sum::<f32>();
+    assert_eq!(total_area, 40.0 * 12.0);
+}
+
+#[test]
+fn stairs_above_climb_are_split_into_regions() {
+    let compact = regions(stairs(WALKABLE_CLIMB + 3), 0, &[]);
+    let floors = region_floors(&compact);
+    assert!(floors.len() >= 5, "{floors:?}");
+    for (region, floors) in &floors {
+        assert_eq!(
+            floors.len(),
+            1,
+            "{region:?} spans several steps: {floors:?}"
+        );
+    }
+
+    // Only the bottom step is reachable from its floor.
+    let compact = regions(stairs(WALKABLE_CLIMB + 3), 0, &[seed(1, 1, 10)]);
+    assert_eq!(
+        region_floors(&compact)
+            .values()
+            .flatten()
+            .collect::<HashSet<_>>(),
+        HashSet::from([&10])
+    );
+}
+
+#[test]
+fn donut_room_keeps_its_hole() {
+    let is_hole = |x: u16, z: u16| (8..16).contains(&x) && (8..16).contains(&z);
+    let compact = regions(
+        heightfield(24, 24, |x, z| if is_hole(x, z) { vec![] } else { vec![10] }),
+        0,
+        &[],
+    );
+    let contours = contours(&compact);
+    // The hole is merged into the outline of the region surrounding it, leaving only outlines.
+    let outlines = contours
+        .contours
+        .iter()
+        .filter(|contour| !contour.vertices.is_empty())
+        .collect::<Vec<_>>();
+    let regions = outlines
+        .iter()
+        .map(|contour| contour.region)
+        .collect::<HashSet<_>>();
+    assert_eq!(outlines.len(), regions.len());
+    assert!(!regions.contains(&RegionId::NONE));
+    for corner in [(8, 8), (16, 8), (16, 16), (8, 16)] {
+        assert!(
+            outlines
+                .iter()
+                .flat_map(|contour| &contour.vertices)
+                .any(|(vertex, _)| (vertex.x, vertex.z) == corner),
+            "{corner:?}"
+        );
+    }
+
+    let mesh = contours.into_polygon_mesh(6).unwrap();
+    let mut total_area = 0.0;
+    for polygon in mesh.polygon_ids() {
+        total_area += polygon_area(&mesh, polygon);
+        let vertices = mesh
+            .polygon_vertices(polygon)
+            .map(|vertex| mesh.vertex(vertex).as_vec3())
+            .collect::<Vec<_>>();
+        let center = vertices.iter().sum::<Vec3>() / vertices.len() as f32;
+        assert!(
+            !is_hole(center.x as u16, center.z as u16),
+            "{polygon:?} covers the hole: {vertices:?}"
+        );
+    }
+    assert_eq!(total_area, (24.0 * 24.0) - (8.0 * 8.0));
+}
+
+/// Two 10x10 platforms connected by a bridge of the given width along the z-axis.
+fn platforms_with_bridge(bridge_width: u16) -> Heightfield {
+    let start = 12 - bridge_width / 2;
+    let bridge = start..start + bridge_width;
+    heightfield(30, 24, move |x, z| {
+        let on_platform = (7..17).contains(&z) && !(10..20).contains(&x);
+        let on_bridge = bridge.contains(&z) && (10..20).contains(&x);
+        if on_platform || on_bridge {
+            vec![10]
+        } else {
+            vec![]
+        }
+    })
+}
+
+#[test]
+fn thin_bridges_are_eroded_away() {
+    let seeds = [seed(2, 12, 10)];
+    let reaches_other_platform =
+        |compact: &CompactHeightfield| regions_at(compact, 27, 12) != vec![RegionId::NONE];
+
+    let compact = regions(platforms_with_bridge(2), 2, &seeds);
+    assert!(!reaches_other_platform(&compact));
+    assert_eq!(regions_at(&compact, 15, 12), vec![RegionId::NONE]);
+
+    let compact = regions(platforms_with_bridge(8), 2, &seeds);
+    assert!(reaches_other_platform(&compact));
+}
+
+#[test]
+fn spiral_ramp_overlapping_itself_stays_connected() {
+    // A ramp winding twice around a pillar in the middle, rising by one voxel per step along the ring.
+    const STEPS: u16 = 24;
+    let center = Vec3::new(10.0, 0.0, 10.0);
+    let ring_step = |x: u16, z: u16| {
+        let offset = Vec3::new(x as f32 + 0.5, 0.0, z as f32 + 0.5) - center;
+        let on_ring = (3.0..9.0).contains(&offset.x.abs().max(offset.z.abs()));
+        let angle = offset.z.atan2(offset.x) + std::f32::consts::PI;
+        on_ring.then(|| (angle / std::f32::consts::TAU * STEPS as f32) as u16 % STEPS)
+    };
+    let compact = regions(
+        heightfield(20, 20, |x, z| {
+            ring_step(x, z)
+                .map(|step| vec![10 + step, 10 + STEPS + step])
+                .unwrap_or_default()
+        }),
+        0,
+        &[seed(1, 9, 10)],
+    );
+
+    assert_eq!(ring_step(1, 9), Some(0));
+    for z in 0..20 {
+        for x in 0..20 {
+            let regions = regions_at(&compact, x, z);
+            match ring_step(x, z) {
+                Some(_) => {
+                    assert_eq!(regions.len(), 2, "({x}, {z})");
+                    assert!(regions.iter().all(|r| *r != RegionId::NONE), "({x}, {z})");
+                }
+                None => assert!(regions.is_empty(), "({x}, {z})"),
+            }
+        }
+    }
+
+    // Cutting a few steps out of the end of the first lap makes the second lap unreachable.
+    // Watershed partitioning can put both laps into the same region, which is a known limitation
+    // of the original as well, so this only checks reachability and not the regions themselves.
+    let compact = regions(
+        heightfield(20, 20, |x, z| match ring_step(x, z) {
+            Some(step) if step >= STEPS - 3 => vec![10 + STEPS + step],
+            Some(step) => vec![10 + step, 10 + STEPS + step],
+            None => vec![],
+        }),
+        0,
+        &[seed(1, 9, 10)],
+    );
+    let [bottom, top] = regions_at(&compact, 1, 9)[..] else {
+        panic!("Expected two floors");
+    };
+    assert_ne!(bottom, RegionId::NONE);
+    assert_eq!(top, RegionId::NONE);
+}
+
+/// A ramp winding once around a pillar in the middle, rising by `lap_rise` voxels over the lap.
+/// Returns the floor of each column on the ring.
+fn spiral_ramp_floor(lap_rise: f32) -> impl Fn(u16, u16) -> Option<u16> {
+    let center = Vec3::new(10.0, 0.0, 10.0);
+    move |x, z| {
+        let offset = Vec3::new(x as f32 + 0.5, 0.0, z as f32 + 0.5) - center;
+        let on_ring = (4.0..9.0).contains(&offset.length());
+        let angle = offset.z.atan2(offset.x) + std::f32::consts::PI;
+        on_ring.then(|| 10 + (angle / std::f32::consts::TAU * lap_rise) as u16)
+    }
+}
+
+/// The floors of the walkable spans in the given column, from bottom to top.
+fn walkable_floors(heightfield: &Heightfield, x: u16, z: u16) -> Vec<u16> {
+    let mut floors = Vec::new();
+    let mut span = heightfield.span_at(x, z);
+    while let Some(current) = span {
+        if current.area.is_walkable() {
+            floors.push(current.max);
+        }
+        span = current.next.map(|key| heightfield.span(key));
+    }
+    floors
+}
+
+/// Filters the ledges of the [`spiral_ramp_floor`] with the given method and returns the columns
+/// whose neighbors are all on the ramp, split into those that are still walkable and those that are not.
+/// Columns next to the seam where the lap ends above its start are skipped, as that is a real ledge.
+fn filter_spiral_ramp(lap_rise: f32, method: LedgeFilter) -> [Vec<(u16, u16)>; 2] {
+    let floor = spiral_ramp_floor(lap_rise);
+    let mut heightfield = heightfield(20, 20, |x, z| floor(x, z).into_iter().collect());
+    heightfield.filter_ledge_spans_with(WALKABLE_HEIGHT, WALKABLE_CLIMB, method);
+
+    let mut walkable = Vec::new();
+    let mut filtered = Vec::new();
+    for z in 1..19 {
+        for x in 1..19 {
+            let Some(center) = floor(x, z) else {
+                continue;
+            };
+            let inside = [(x - 1, z), (x + 1, z), (x, z - 1), (x, z + 1)]
+                .into_iter()
+                .all(|(x, z)| floor(x, z).is_some_and(|floor| floor.abs_diff(center) < 10));
+            if !inside {
+                continue;
+            }
+            if walkable_floors(&heightfield, x, z).is_empty() {
+                filtered.push((x, z));
+            } else {
+                walkable.push((x, z));
+            }
+        }
+    }
+    [walkable, filtered]
+}
+
+#[test]
+fn ramp_aware_ledge_filter_keeps_spiral_ramp_walkable() {
+    // Two cells along the ramp rise by a bit more than the climb, so the neighbors of some columns
+    // differ by one voxel more than the climb and others do not, depending on how the floors are rounded.
+    const LAP_RISE: f32 = 52.0;
+    let [_, holes] = filter_spiral_ramp(LAP_RISE, LedgeFilter::Reference);
+    assert!(holes.len() > 10, "{holes:?}");
+
+    let [walkable, holes] = filter_spiral_ramp(LAP_RISE, LedgeFilter::RampAware { window: 4 });
+    assert!(holes.is_empty(), "{holes:?}");
+    assert!(walkable.len() > 100);
+
+    // The seam and the sides of the ramp are still ledges.
+    let floor = spiral_ramp_floor(LAP_RISE);
+    let mut heightfield = heightfield(20, 20, |x, z| floor(x, z).into_iter().collect());
+    heightfield.filter_ledge_spans_with(
+        WALKABLE_HEIGHT,
+        WALKABLE_CLIMB,
+        LedgeFilter::RampAware { window: 4 },
+    );
+    assert_eq!(floor(1, 9), Some(10));
+    assert_eq!(floor(1, 10), Some(10 + LAP_RISE as u16 - 1));
+    assert!(walkable_floors(&heightfield, 1, 9).is_empty());
+    assert!(walkable_floors(&heightfield, 1, 10).is_empty());
+    assert!(walkable_floors(&heightfield, 10, 1).is_empty());
+}
+
+#[test]
+fn ramp_aware_ledge_filter_still_filters_steep_spiral_ramp() {
+    const LAP_RISE: f32 = 120.0;
+    let reference = filter_spiral_ramp(LAP_RISE, LedgeFilter::Reference);
+    let ramp_aware = filter_spiral_ramp(LAP_RISE, LedgeFilter::RampAware { window: 4 });
+    assert!(reference[1].len() > 100);
+    assert_eq!(ramp_aware, reference);
+}
+
+#[test]
+fn ramp_aware_ledge_filter_with_empty_window_still_filters() {
+    const LAP_RISE: f32 = 120.0;
+    let empty_window = filter_spiral_ramp(LAP_RISE, LedgeFilter::RampAware { window: 0 });
+    assert!(empty_window[1].len() > 100);
+    assert_eq!(
+        empty_window,
+        filter_spiral_ramp(LAP_RISE, LedgeFilter::RampAware { window: 1 })
+    );
+}
@@ -0,0 +1,33 @@
+//! Compiles the original C++ Recast when the `link` feature is enabled.
+
+fn main() {
+    #[cfg(feature = "link")]
+    link::compile();
+}
+
+#[cfg(feature = "link")]
+mod link {
+    use std::{env, fs, path::PathBuf};
+
+    pub(super) fn compile() {
+        println!("cargo::rerun-if-env-changed=RECAST_DIR");
+        println!("cargo::rerun-if-changed=src/shim.cpp");
+        let Some(dir) = env::var_os("RECAST_DIR").map(PathBuf::from) else {
+            panic!(
+                "The `link` feature requires the `RECAST_DIR` environment variable to point to the `Recast` directory \
+                 of a recastnavigation checkout, i.e. the one containing `Include` and `Source`."
+            );
+        };
+        let sources = fs::read_dir(dir.join("Source"))
+            .expect("Failed to read `Source` in `RECAST_DIR`")
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "cpp"));
+        cc::Build::new()
+            .cpp(true)
+            .include(dir.join("Include"))
+            .files(sources)
+            .file("src/shim.cpp")
+            .warnings(false)
+            .compile("recast");
+    }
+}
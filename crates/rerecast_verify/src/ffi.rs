@@ -0,0 +1,167 @@
+//! Bindings to the C interface in `shim.cpp`.
+
+use std::{ffi::c_int, slice};
+
+use glam::U16Vec3;
+use rerecast::{AreaType, BuildContoursFlags, NavmeshConfig, PolygonNavmesh, RegionId, TriMesh};
+
+use crate::{ComparisonReport, VerifyError};
+
+#[repr(C)]
+struct RerecastConfig {
+    width: c_int,
+    height: c_int,
+    border_size: c_int,
+    cell_size: f32,
+    cell_height: f32,
+    bmin: [f32; 3],
+    bmax: [f32; 3],
+    walkable_slope_angle: f32,
+    walkable_height: c_int,
+    walkable_climb: c_int,
+    walkable_radius: c_int,
+    max_edge_len: c_int,
+    max_simplification_error: f32,
+    min_region_area: c_int,
+    merge_region_area: c_int,
+    max_verts_per_poly: c_int,
+    contour_flags: c_int,
+}
+
+#[repr(C)]
+struct RerecastPolyMesh {
+    mesh: *mut std::ffi::c_void,
+    verts: *const u16,
+    nverts: c_int,
+    polys: *const u16,
+    regs: *const u16,
+    areas: *const u8,
+    npolys: c_int,
+    nvp: c_int,
+}
+
+unsafe extern "C" {
+    fn rerecast_build_poly_mesh(
+        verts: *const f32,
+        nverts: c_int,
+        tris: *const c_int,
+        ntris: c_int,
+        cfg: *const RerecastConfig,
+        out: *mut RerecastPolyMesh,
+    ) -> c_int;
+    fn rerecast_free_poly_mesh(out: *mut RerecastPolyMesh);
+}
+
+/// Builds the polygon mesh of `trimesh` with the C++ implementation, running the same stages as [`rerecast::build_navmesh`].
+///
+/// The area types of `trimesh` are ignored, as the C++ implementation marks the walkable triangles itself.
+/// The polygon flags are left at 0.
+pub fn build_cpp_poly_mesh(
+    trimesh: &TriMesh,
+    config: &NavmeshConfig,
+) -> Result<PolygonNavmesh, VerifyError> {
+    let verts = trimesh
+        .vertices
+        .iter()
+        .flat_map(|v| v.to_array())
+        .collect::<Vec<_>>();
+    let tris = trimesh
+        .indices
+        .iter()
+        .flat_map(|i| i.to_array().map(|i| i as c_int))
+        .collect::<Vec<_>>();
+    let cfg = RerecastConfig {
+        width: config.width.into(),
+        height: config.height.into(),
+        border_size: config.border_size.into(),
+        cell_size: config.cell_size,
+        cell_height: config.cell_height,
+        bmin: config.aabb.min.to_array(),
+        bmax: config.aabb.max.to_array(),
+        walkable_slope_angle: config.walkable_slope_angle.to_degrees(),
+        walkable_height: config.walkable_height.into(),
+        walkable_climb: config.walkable_climb.into(),
+        walkable_radius: config.walkable_radius.into(),
+        max_edge_len: config.max_edge_len.into(),
+        max_simplification_error: config.max_simplification_error,
+        min_region_area: config.min_region_area.into(),
+        merge_region_area: config.merge_region_area.into(),
+        max_verts_per_poly: config.max_vertices_per_polygon.into(),
+        // Recast only knows the tessellation flags.
+        contour_flags: (config.contour_flags
+            & (BuildContoursFlags::TESSELLATE_SOLID_WALL_EDGES
+                | BuildContoursFlags::TESSELLATE_AREA_EDGES))
+            .bits()
+            .into(),
+    };
+    let mut out = RerecastPolyMesh {
+        mesh: std::ptr::null_mut(),
+        verts: std::ptr::null(),
+        nverts: 0,
+        polys: std::ptr::null(),
+        regs: std::ptr::null(),
+        areas: std::ptr::null(),
+        npolys: 0,
+        nvp: 0,
+    };
+    // SAFETY: The buffers outlive the call and their lengths are passed along.
+    let stage = unsafe {
+        rerecast_build_poly_mesh(
+            verts.as_ptr(),
+            trimesh.vertices.len() as c_int,
+            tris.as_ptr(),
+            trimesh.indices.len() as c_int,
+            &cfg,
+            &mut out,
+        )
+    };
+    if stage != 0 {
+        return Err(VerifyError::Cpp(stage));
+    }
+
+    let nvp = out.nvp as usize;
+    let npolys = out.npolys as usize;
+    // SAFETY: On success, the pointers refer to the arrays of the `rcPolyMesh`, which stays alive until it is freed below.
+    let (verts, polys, regs, areas) = unsafe {
+        (
+            slice::from_raw_parts(out.verts, out.nverts as usize * 3),
+            slice::from_raw_parts(out.polys, npolys * nvp * 2),
+            slice::from_raw_parts(out.regs, npolys),
+            slice::from_raw_parts(out.areas, npolys),
+        )
+    };
+    let mesh = PolygonNavmesh {
+        vertices: verts
+            .chunks_exact(3)
+            .map(|v| U16Vec3::new(v[0], v[1], v[2]))
+            .collect(),
+        polygons: polys
+            .chunks_exact(nvp * 2)
+            .flat_map(|p| p[..nvp].iter().copied())
+            .collect(),
+        polygon_neighbors: polys
+            .chunks_exact(nvp * 2)
+            .flat_map(|p| p[nvp..].iter().copied())
+            .collect(),
+        flags: vec![0; npolys],
+        regions: regs.iter().map(|r| RegionId::from(*r)).collect(),
+        areas: areas.iter().map(|a| AreaType(*a)).collect(),
+        max_vertices_per_polygon: nvp as u16,
+        aabb: config.aabb,
+        cell_size: config.cell_size,
+        cell_height: config.cell_height,
+        border_size: config.border_size,
+        max_edge_error: config.max_simplification_error,
+        ..Default::default()
+    };
+    // SAFETY: `out` was filled by a successful build and is not used afterwards.
+    unsafe { rerecast_free_poly_mesh(&mut out) };
+    Ok(mesh)
+}
+
+/// Builds the polygon mesh of `trimesh` with both rerecast and the C++ implementation and compares them.
+pub fn verify(trimesh: TriMesh, config: &NavmeshConfig) -> Result<ComparisonReport, VerifyError> {
+    let cpp = build_cpp_poly_mesh(&trimesh, config)?;
+    let (rust, _detail) = rerecast::build_navmesh(trimesh, config)?;
+    Ok(ComparisonReport::new(&rust, &cpp))
+}
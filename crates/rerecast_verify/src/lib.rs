@@ -0,0 +1,301 @@
+//! Verifies rerecast against the original C++ implementation of Recast on arbitrary geometry.
+//!
+//! The reference data in `rerecast/tests/reference_data` only covers a handful of scenes.
+//! This crate instead runs both implementations on the same input and compares the resulting polygon meshes,
+//! so that any mesh can be checked, e.g. one that produced a suspicious navmesh in a game.
+//!
+//! Running the C++ implementation requires the `link` feature, which compiles the Recast sources found in the
+//! directory pointed to by the `RECAST_DIR` environment variable. With it enabled, the `verify_meshes` test
+//! compares every `.obj` file in the directory pointed to by `RERECAST_VERIFY_MESHES` and writes a report for each:
+//!
+//! ```sh
+//! RECAST_DIR=path/to/recastnavigation/Recast RERECAST_VERIFY_MESHES=path/to/meshes \
+//!     cargo test -p rerecast_verify --features link
+//! ```
+
+use std::{fmt, fs, path::Path};
+
+use glam::{U16Vec3, UVec3, Vec3A};
+use rerecast::{AreaType, PolygonNavmesh, RegionId, TriMesh, VertexId};
+use thiserror::Error;
+
+#[cfg(feature = "link")]
+mod ffi;
+#[cfg(feature = "link")]
+pub use ffi::{build_cpp_poly_mesh, verify};
+
+/// The differences between the polygon mesh built by rerecast and the one built by the C++ implementation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonReport {
+    /// The number of vertices of the Rust and the C++ mesh.
+    pub vertex_count: (usize, usize),
+    /// The number of polygons of the Rust and the C++ mesh.
+    pub polygon_count: (usize, usize),
+    /// All differences, in the order of the vertices and polygons they occur in.
+    pub mismatches: Vec<Mismatch>,
+}
+
+/// A single difference found by [`ComparisonReport::new`].
+/// Values that only exist in one of the meshes are `None` in the other.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    /// The vertex at the given index differs.
+    Vertex {
+        /// The index of the vertex.
+        index: usize,
+        /// The vertex of the Rust mesh.
+        rust: Option<U16Vec3>,
+        /// The vertex of the C++ mesh.
+        cpp: Option<U16Vec3>,
+    },
+    /// The vertex indices of the polygon at the given index differ.
+    Polygon {
+        /// The index of the polygon.
+        index: usize,
+        /// The vertex indices of the Rust polygon.
+        rust: Option<Vec<VertexId>>,
+        /// The vertex indices of the C++ polygon.
+        cpp: Option<Vec<VertexId>>,
+    },
+    /// The region of the polygon at the given index differs.
+    Region {
+        /// The index of the polygon.
+        index: usize,
+        /// The region of the Rust polygon.
+        rust: Option<RegionId>,
+        /// The region of the C++ polygon.
+        cpp: Option<RegionId>,
+    },
+    /// The area type of the polygon at the given index differs.
+    Area {
+        /// The index of the polygon.
+        index: usize,
+        /// The area type of the Rust polygon.
+        rust: Option<AreaType>,
+        /// The area type of the C++ polygon.
+        cpp: Option<AreaType>,
+    },
+}
+
+impl ComparisonReport {
+    /// The number of mismatches shown when the report is displayed.
+    pub const DISPLAYED_MISMATCHES: usize = 20;
+
+    /// Compares the polygon mesh built by rerecast with the one built by the C++ implementation.
+    pub fn new(rust: &PolygonNavmesh, cpp: &PolygonNavmesh) -> Self {
+        let mut mismatches = Vec::new();
+        for index in 0..rust.vertices.len().max(cpp.vertices.len()) {
+            let (r, c) = (rust.vertices.get(index), cpp.vertices.get(index));
+            if r != c {
+                mismatches.push(Mismatch::Vertex {
+                    index,
+                    rust: r.copied(),
+                    cpp: c.copied(),
+                });
+            }
+        }
+        let rust_polygons = rust.polygons().map(Iterator::collect).collect::<Vec<_>>();
+        let cpp_polygons = cpp.polygons().map(Iterator::collect).collect::<Vec<_>>();
+        for index in 0..rust_polygons.len().max(cpp_polygons.len()) {
+            let (r, c) = (rust_polygons.get(index), cpp_polygons.get(index));
+            if r != c {
+                mismatches.push(Mismatch::Polygon {
+                    index,
+                    rust: r.cloned(),
+                    cpp: c.cloned(),
+                });
+            }
+            let (r, c) = (rust.regions.get(index), cpp.regions.get(index));
+            if r != c {
+                mismatches.push(Mismatch::Region {
+                    index,
+                    rust: r.copied(),
+                    cpp: c.copied(),
+                });
+            }
+            let (r, c) = (rust.areas.get(index), cpp.areas.get(index));
+            if r != c {
+                mismatches.push(Mismatch::Area {
+                    index,
+                    rust: r.copied(),
+                    cpp: c.copied(),
+                });
+            }
+        }
+        Self {
+            vertex_count: (rust.vertices.len(), cpp.vertices.len()),
+            polygon_count: (rust_polygons.len(), cpp_polygons.len()),
+            mismatches,
+        }
+    }
+
+    /// Whether both implementations produced the same polygon mesh.
+    pub fn is_match(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Vertices: {} (Rust) vs {} (C++)",
+            self.vertex_count.0, self.vertex_count.1
+        )?;
+        writeln!(
+            f,
+            "Polygons: {} (Rust) vs {} (C++)",
+            self.polygon_count.0, self.polygon_count.1
+        )?;
+        if self.is_match() {
+            return writeln!(f, "The polygon meshes are identical.");
+        }
+        writeln!(f, "{} mismatches:", self.mismatches.len())?;
+        for mismatch in self.mismatches.iter().take(Self::DISPLAYED_MISMATCHES) {
+            match mismatch {
+                Mismatch::Vertex { index, rust, cpp } => {
+                    writeln!(f, "- Vertex {index}: {rust:?} (Rust) vs {cpp:?} (C++)")?
+                }
+                Mismatch::Polygon { index, rust, cpp } => {
+                    writeln!(f, "- Polygon {index}: {rust:?} (Rust) vs {cpp:?} (C++)")?
+                }
+                Mismatch::Region { index, rust, cpp } => writeln!(
+                    f,
+                    "- Region of polygon {index}: {:?} (Rust) vs {:?} (C++)",
+                    rust.map(|r| r.bits()),
+                    cpp.map(|r| r.bits())
+                )?,
+                Mismatch::Area { index, rust, cpp } => writeln!(
+                    f,
+                    "- Area of polygon {index}: {:?} (Rust) vs {:?} (C++)",
+                    rust.map(|a| a.0),
+                    cpp.map(|a| a.0)
+                )?,
+            }
+        }
+        if self.mismatches.len() > Self::DISPLAYED_MISMATCHES {
+            writeln!(
+                f,
+                "... and {} more",
+                self.mismatches.len() - Self::DISPLAYED_MISMATCHES
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Loads the vertices and faces of a Wavefront OBJ file. Faces with more than three vertices are triangulated as fans.
+/// All other data, like normals, texture coordinates and groups, is ignored.
+pub fn load_obj(path: impl AsRef<Path>) -> Result<TriMesh, VerifyError> {
+    parse_obj(&fs::read_to_string(path)?)
+}
+
+fn parse_obj(source: &str) -> Result<TriMesh, VerifyError> {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let coords = tokens
+                    .take(3)
+                    .map(str::parse::<f32>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| VerifyError::Obj(line_number))?;
+                let [x, y, z] = coords[..] else {
+                    return Err(VerifyError::Obj(line_number));
+                };
+                vertices.push(Vec3A::new(x, y, z));
+            }
+            Some("f") => {
+                let face = tokens
+                    .map(|token| {
+                        // Faces can be written as `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+                        let index = token.split('/').next()?.parse::<i64>().ok()?;
+                        // Negative indices are relative to the end of the vertices read so far.
+                        let index = if index < 0 {
+                            vertices.len() as i64 + index
+                        } else {
+                            index - 1
+                        };
+                        u32::try_from(index).ok()
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .filter(|face| face.len() >= 3)
+                    .ok_or(VerifyError::Obj(line_number))?;
+                for i in 1..face.len() - 1 {
+                    indices.push(UVec3::new(face[0], face[i], face[i + 1]));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(TriMesh::from_parts(vertices, indices)?)
+}
+
+/// Errors that can occur when loading meshes or verifying them.
+#[derive(Error, Debug)]
+pub enum VerifyError {
+    /// Happens when a mesh file cannot be read.
+    #[error("Failed to read mesh: {0}")]
+    Io(#[from] std::io::Error),
+    /// Happens when an OBJ file contains an invalid vertex or face in the given line.
+    #[error("Invalid OBJ data in line {0}")]
+    Obj(usize),
+    /// Happens when a face of an OBJ file refers to a vertex that does not exist.
+    #[error("Invalid OBJ faces: {0}")]
+    TriMesh(#[from] rerecast::TriMeshError),
+    /// Happens when rerecast fails to build the navmesh.
+    #[error("Failed to build navmesh with rerecast: {0}")]
+    Rust(#[from] rerecast::BuildNavmeshError),
+    /// Happens when the C++ implementation fails to build the navmesh.
+    #[error("Failed to build navmesh with the C++ implementation in stage {0}")]
+    Cpp(i32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_obj_quads_as_triangle_fans() {
+        let trimesh = parse_obj(
+            "# A quad\nv 0 0 0\nv 0 0 1\nv 1 0 1\nv 1 0 0\nvn 0 1 0\nf 1//1 2//1 3//1 -1//1\n",
+        )
+        .unwrap();
+        assert_eq!(trimesh.vertices.len(), 4);
+        assert_eq!(
+            trimesh.indices,
+            vec![UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)]
+        );
+        assert!(matches!(parse_obj("f 1 2 3"), Err(VerifyError::TriMesh(_))));
+    }
+
+    #[test]
+    fn reports_differing_polygons() {
+        let rust = PolygonNavmesh {
+            vertices: vec![U16Vec3::ZERO, U16Vec3::X, U16Vec3::Z],
+            polygons: vec![0, 1, 2],
+            polygon_neighbors: vec![PolygonNavmesh::NO_CONNECTION; 3],
+            flags: vec![0],
+            regions: vec![RegionId::from(1)],
+            areas: vec![AreaType::DEFAULT_WALKABLE],
+            max_vertices_per_polygon: 3,
+            ..Default::default()
+        };
+        assert!(ComparisonReport::new(&rust, &rust).is_match());
+
+        let mut cpp = rust.clone();
+        cpp.polygons = vec![0, 2, 1];
+        let report = ComparisonReport::new(&rust, &cpp);
+        assert_eq!(
+            report.mismatches,
+            vec![Mismatch::Polygon {
+                index: 0,
+                rust: Some([0, 1, 2].map(VertexId).to_vec()),
+                cpp: Some([0, 2, 1].map(VertexId).to_vec()),
+            }]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+// A C interface to the polygon mesh pipeline of Recast, as run by `Sample_SoloMesh`.
+
+#include <cstring>
+
+#include "Recast.h"
+
+extern "C" {
+
+struct RerecastConfig {
+    int width;
+    int height;
+    int border_size;
+    float cell_size;
+    float cell_height;
+    float bmin[3];
+    float bmax[3];
+    // In degrees.
+    float walkable_slope_angle;
+    int walkable_height;
+    int walkable_climb;
+    int walkable_radius;
+    int max_edge_len;
+    float max_simplification_error;
+    int min_region_area;
+    int merge_region_area;
+    int max_verts_per_poly;
+    int contour_flags;
+};
+
+struct RerecastPolyMesh {
+    rcPolyMesh* mesh;
+    const unsigned short* verts;
+    int nverts;
+    // `npolys * nvp * 2` entries: the vertex indices of each polygon, followed by its neighbors.
+    const unsigned short* polys;
+    const unsigned short* regs;
+    const unsigned char* areas;
+    int npolys;
+    int nvp;
+};
+
+// Returns 0 on success, or the 1-based index of the stage that failed.
+int rerecast_build_poly_mesh(
+    const float* verts,
+    int nverts,
+    const int* tris,
+    int ntris,
+    const RerecastConfig* cfg,
+    RerecastPolyMesh* out
+) {
+    rcContext ctx(false);
+    rcHeightfield* solid = rcAllocHeightfield();
+    rcCompactHeightfield* chf = rcAllocCompactHeightfield();
+    rcContourSet* cset = rcAllocContourSet();
+    rcPolyMesh* pmesh = rcAllocPolyMesh();
+    unsigned char* tri_areas = new unsigned char[ntris];
+    memset(tri_areas, 0, ntris * sizeof(unsigned char));
+
+    int stage = 0;
+    auto run = [&]() -> bool {
+        stage = 1;
+        if (!rcCreateHeightfield(&ctx, *solid, cfg->width, cfg->height, cfg->bmin, cfg->bmax, cfg->cell_size, cfg->cell_height)) {
+            return false;
+        }
+        rcMarkWalkableTriangles(&ctx, cfg->walkable_slope_angle, verts, nverts, tris, ntris, tri_areas);
+        stage = 2;
+        if (!rcRasterizeTriangles(&ctx, verts, nverts, tris, tri_areas, ntris, *solid, cfg->walkable_climb)) {
+            return false;
+        }
+        rcFilterLowHangingWalkableObstacles(&ctx, cfg->walkable_climb, *solid);
+        rcFilterLedgeSpans(&ctx, cfg->walkable_height, cfg->walkable_climb, *solid);
+        rcFilterWalkableLowHeightSpans(&ctx, cfg->walkable_height, *solid);
+        stage = 3;
+        if (!rcBuildCompactHeightfield(&ctx, cfg->walkable_height, cfg->walkable_climb, *solid, *chf)) {
+            return false;
+        }
+        stage = 4;
+        if (!rcErodeWalkableArea(&ctx, cfg->walkable_radius, *chf)) {
+            return false;
+        }
+        stage = 5;
+        if (!rcBuildDistanceField(&ctx, *chf)) {
+            return false;
+        }
+        stage = 6;
+        if (!rcBuildRegions(&ctx, *chf, cfg->border_size, cfg->min_region_area, cfg->merge_region_area)) {
+            return false;
+        }
+        stage = 7;
+        if (!rcBuildContours(&ctx, *chf, cfg->max_simplification_error, cfg->max_edge_len, *cset, cfg->contour_flags)) {
+            return false;
+        }
+        stage = 8;
+        if (!rcBuildPolyMesh(&ctx, *cset, cfg->max_verts_per_poly, *pmesh)) {
+            return false;
+        }
+        stage = 0;
+        return true;
+    };
+    bool ok = run();
+
+    delete[] tri_areas;
+    rcFreeHeightField(solid);
+    rcFreeCompactHeightfield(chf);
+    rcFreeContourSet(cset);
+    if (!ok) {
+        rcFreePolyMesh(pmesh);
+        return stage;
+    }
+
+    out->mesh = pmesh;
+    out->verts = pmesh->verts;
+    out->nverts = pmesh->nverts;
+    out->polys = pmesh->polys;
+    out->regs = pmesh->regs;
+    out->areas = pmesh->areas;
+    out->npolys = pmesh->npolys;
+    out->nvp = pmesh->nvp;
+    return 0;
+}
+
+void rerecast_free_poly_mesh(RerecastPolyMesh* out) {
+    rcFreePolyMesh(out->mesh);
+    out->mesh = nullptr;
+}
+
+}
@@ -0,0 +1,48 @@
+//! Compares rerecast with the C++ implementation on every mesh in `RERECAST_VERIFY_MESHES`.
+#![cfg(feature = "link")]
+
+use std::{env, fs, path::Path};
+
+use rerecast::NavmeshConfigBuilder;
+use rerecast_verify::{load_obj, verify};
+
+#[test]
+fn verify_meshes() {
+    let Some(dir) = env::var_os("RERECAST_VERIFY_MESHES") else {
+        println!("Skipping because RERECAST_VERIFY_MESHES is not set");
+        return;
+    };
+    let report_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cpp_verification");
+    fs::create_dir_all(&report_dir).unwrap();
+
+    let mut failures = Vec::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "obj") {
+            continue;
+        }
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let trimesh = load_obj(&path).unwrap();
+        let Some(aabb) = trimesh.compute_aabb() else {
+            println!("Skipping {name} because it is empty");
+            continue;
+        };
+        let config = NavmeshConfigBuilder {
+            aabb,
+            ..Default::default()
+        }
+        .build();
+        let report = verify(trimesh, &config).unwrap();
+        let report_path = report_dir.join(format!("{name}.txt"));
+        fs::write(&report_path, report.to_string()).unwrap();
+        println!("{name}: {}", report_path.display());
+        if !report.is_match() {
+            failures.push(name);
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "Mismatches found for {failures:?}. See the reports in {}",
+        report_dir.display()
+    );
+}